@@ -0,0 +1,9 @@
+-- Nomor struk INV-YYYYMMDD-NNNN; counter per hari dinaikkan dalam transaksi yang sama dengan INSERT transaksi
+CREATE TABLE transaksi_counters (
+    tanggal VARCHAR(8) PRIMARY KEY,
+    last_seq INTEGER NOT NULL
+);
+
+ALTER TABLE transaksi ADD COLUMN nomor_transaksi VARCHAR(32);
+
+CREATE UNIQUE INDEX idx_transaksi_nomor_transaksi ON transaksi (nomor_transaksi);
@@ -0,0 +1,2 @@
+-- Ambang stok minimum per produk; produk dengan stok di bawah nilai ini masuk daftar stok rendah
+ALTER TABLE produk ADD COLUMN stok_minimum INTEGER NOT NULL DEFAULT 0;
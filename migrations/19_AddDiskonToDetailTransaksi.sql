@@ -0,0 +1,2 @@
+-- Potongan nominal per baris detail; NULL berarti baris tanpa diskon
+ALTER TABLE detail_transaksi ADD COLUMN diskon DECIMAL(15,2);
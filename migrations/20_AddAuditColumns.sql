@@ -0,0 +1,9 @@
+-- Username pembuat dan pengubah terakhir; NULL untuk data sebelum audit dicatat
+ALTER TABLE payments ADD COLUMN created_by VARCHAR(100);
+ALTER TABLE payments ADD COLUMN updated_by VARCHAR(100);
+ALTER TABLE transaksi ADD COLUMN created_by VARCHAR(100);
+ALTER TABLE transaksi ADD COLUMN updated_by VARCHAR(100);
+ALTER TABLE produk ADD COLUMN created_by VARCHAR(100);
+ALTER TABLE produk ADD COLUMN updated_by VARCHAR(100);
+ALTER TABLE suppliers ADD COLUMN created_by VARCHAR(100);
+ALTER TABLE suppliers ADD COLUMN updated_by VARCHAR(100);
@@ -0,0 +1,12 @@
+-- Satu baris per perubahan status transaksi (selesai, batal, atau PATCH status) untuk jejak audit
+CREATE TABLE transaksi_status_history (
+    id SERIAL PRIMARY KEY,
+    id_transaksi INTEGER NOT NULL,
+    status_lama VARCHAR(50) NOT NULL,
+    status_baru VARCHAR(50) NOT NULL,
+    alasan TEXT,
+    changed_by VARCHAR(255),
+    changed_at VARCHAR(100) NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_transaksi_status_history_id_transaksi ON transaksi_status_history(id_transaksi);
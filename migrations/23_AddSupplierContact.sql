@@ -0,0 +1,3 @@
+-- Kontak supplier opsional; format divalidasi di service
+ALTER TABLE suppliers ADD COLUMN phone VARCHAR(32);
+ALTER TABLE suppliers ADD COLUMN email VARCHAR(255);
@@ -0,0 +1,2 @@
+-- Harga modal (harga beli) per produk untuk laporan margin; NULL jika belum diisi
+ALTER TABLE produk ADD COLUMN harga_modal DOUBLE PRECISION;
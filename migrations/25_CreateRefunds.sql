@@ -0,0 +1,13 @@
+-- Buku besar refund per payment; beberapa refund parsial boleh dicatat selama
+-- totalnya tidak melebihi jumlah yang sudah diterima
+CREATE TABLE IF NOT EXISTS refunds (
+    id TEXT PRIMARY KEY,
+    payment_id TEXT NOT NULL,
+    amount REAL NOT NULL,
+    reason TEXT NOT NULL,
+    refund_date TEXT NOT NULL,
+    created_by VARCHAR(100),
+    FOREIGN KEY (payment_id) REFERENCES payments(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_refunds_payment_id ON refunds(payment_id);
@@ -0,0 +1,9 @@
+-- Tag bebas per produk (mis. "promo", "new-arrival"), di samping kategori yang tetap tunggal
+CREATE TABLE IF NOT EXISTS produk_tags (
+    produk_id BIGINT NOT NULL,
+    tag VARCHAR(50) NOT NULL,
+    PRIMARY KEY (produk_id, tag),
+    FOREIGN KEY (produk_id) REFERENCES produk(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_produk_tags_tag ON produk_tags(tag);
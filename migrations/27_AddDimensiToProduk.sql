@@ -0,0 +1,5 @@
+-- Berat (gram) dan dimensi (cm) produk untuk perhitungan ongkos kirim; NULL jika belum diisi
+ALTER TABLE produk ADD COLUMN berat_gram INTEGER;
+ALTER TABLE produk ADD COLUMN panjang_cm INTEGER;
+ALTER TABLE produk ADD COLUMN lebar_cm INTEGER;
+ALTER TABLE produk ADD COLUMN tinggi_cm INTEGER;
@@ -0,0 +1,4 @@
+-- Harga grosir per unit yang berlaku bila jumlah satu baris transaksi mencapai min_qty_grosir;
+-- NULL berarti produk hanya dijual dengan harga eceran
+ALTER TABLE produk ADD COLUMN harga_grosir DOUBLE PRECISION;
+ALTER TABLE produk ADD COLUMN min_qty_grosir INTEGER;
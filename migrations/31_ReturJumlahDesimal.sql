@@ -0,0 +1,3 @@
+-- Retur barang timbangan boleh desimal (mis. 0.5 kg dari penjualan 2.5 kg), sama seperti
+-- detail_transaksi.jumlah
+ALTER TABLE retur_transaksi_item ALTER COLUMN jumlah TYPE DOUBLE PRECISION;
@@ -0,0 +1,2 @@
+-- 0 = aktif, 1 = voided (INTEGER agar bisa dibaca lewat driver Any di SQLite)
+ALTER TABLE supplier_transactions ADD COLUMN voided INTEGER NOT NULL DEFAULT 0;
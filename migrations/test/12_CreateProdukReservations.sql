@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS produk_reservations (
+    id VARCHAR(255) PRIMARY KEY NOT NULL,
+    produk_id BIGINT NOT NULL,
+    jumlah INTEGER NOT NULL,
+    expires_at TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (produk_id) REFERENCES produk(id) ON DELETE CASCADE
+);
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS installment_schedules (
+    id TEXT PRIMARY KEY,
+    payment_id TEXT NOT NULL,
+    sequence INTEGER NOT NULL,
+    amount REAL NOT NULL,
+    due_date TEXT NOT NULL,
+    FOREIGN KEY (payment_id) REFERENCES payments(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_installment_schedules_payment_id ON installment_schedules(payment_id);
@@ -0,0 +1,4 @@
+-- SKU opsional untuk import; NULL boleh berulang, nilai terisi harus unik (target ON CONFLICT upsert)
+ALTER TABLE produk ADD COLUMN sku VARCHAR(64);
+
+CREATE UNIQUE INDEX idx_produk_sku ON produk (sku);
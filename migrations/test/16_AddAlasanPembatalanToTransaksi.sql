@@ -0,0 +1,2 @@
+-- Alasan yang dicatat staf saat transaksi dibatalkan; NULL untuk transaksi lain atau pembatalan tanpa alasan
+ALTER TABLE transaksi ADD COLUMN alasan_pembatalan TEXT;
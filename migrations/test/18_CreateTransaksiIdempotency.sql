@@ -0,0 +1,6 @@
+-- Idempotency-Key dari client -> transaksi yang dibuat pada percobaan pertama
+CREATE TABLE transaksi_idempotency (
+    idempotency_key VARCHAR(128) PRIMARY KEY,
+    id_transaksi INTEGER NOT NULL,
+    created_at VARCHAR(100) NOT NULL
+);
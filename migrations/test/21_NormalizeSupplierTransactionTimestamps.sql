@@ -0,0 +1,7 @@
+-- tanggal_transaksi kini ditulis aplikasi sebagai RFC3339 UTC dengan presisi
+-- nanodetik dan akhiran Z (mis. 2024-01-05T08:00:00.000000000Z) supaya urutan
+-- teks sama dengan urutan waktu. Baris lama hasil to_rfc3339() memakai +00:00;
+-- samakan akhirannya. Pembacaan tetap menerima format lama.
+UPDATE supplier_transactions
+SET tanggal_transaksi = REPLACE(tanggal_transaksi, '+00:00', 'Z')
+WHERE tanggal_transaksi LIKE '%+00:00';
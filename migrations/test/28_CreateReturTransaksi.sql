@@ -0,0 +1,26 @@
+-- Retur sebagian barang dari transaksi SELESAI. Transaksi tetap SELESAI; stok barang yang
+-- dikembalikan ditambahkan lagi dan nilainya di-refund lewat tabel refunds
+CREATE TABLE IF NOT EXISTS retur_transaksi (
+    id VARCHAR(64) PRIMARY KEY,
+    id_transaksi INTEGER NOT NULL,
+    nilai_retur REAL NOT NULL,
+    total_refund REAL NOT NULL,
+    alasan TEXT,
+    created_by VARCHAR(255),
+    created_at VARCHAR(100) NOT NULL,
+    FOREIGN KEY (id_transaksi) REFERENCES transaksi(id) ON DELETE CASCADE
+);
+
+-- Jumlah per baris detail yang dikembalikan; dijumlahkan lintas retur agar total retur
+-- satu baris tidak melebihi jumlah yang dibeli
+CREATE TABLE IF NOT EXISTS retur_transaksi_item (
+    id_retur VARCHAR(64) NOT NULL,
+    id_detail INTEGER NOT NULL,
+    id_produk INTEGER NOT NULL,
+    jumlah INTEGER NOT NULL,
+    nilai REAL NOT NULL,
+    PRIMARY KEY (id_retur, id_detail),
+    FOREIGN KEY (id_retur) REFERENCES retur_transaksi(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_retur_transaksi_id_transaksi ON retur_transaksi(id_transaksi);
@@ -0,0 +1,7 @@
+-- Satuan jual dan presisi desimal stok per produk. stok/stok_minimum tetap INTEGER dalam unit
+-- terkecil (10^-presisi_stok satuan); presisi 0 berarti barang hitungan seperti sebelumnya
+ALTER TABLE produk ADD COLUMN satuan VARCHAR(20) NOT NULL DEFAULT 'pcs';
+ALTER TABLE produk ADD COLUMN presisi_stok INTEGER NOT NULL DEFAULT 0;
+
+-- SQLite tidak bisa mengubah tipe kolom; detail_transaksi.jumlah menyimpan nilai desimal apa
+-- adanya dan nilai bulat sebagai INTEGER, keduanya dibaca sebagai f64 oleh repository
@@ -0,0 +1,2 @@
+-- SQLite tidak bisa mengubah tipe kolom; retur_transaksi_item.jumlah menyimpan nilai desimal
+-- apa adanya dan SUM-nya dibaca sebagai DOUBLE PRECISION oleh repository
@@ -2,6 +2,7 @@ use rocket::serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use crate::auth::model::user::User;
+use crate::common::clock::{Clock, SystemClock};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(crate = "rocket::serde")]
@@ -21,7 +22,12 @@ impl Session {
     }
 
     pub fn is_valid(&self) -> bool {
-        Utc::now() < self.expires_at
+        self.is_valid_at(&SystemClock)
+    }
+
+    /// Sesi berakhir tepat pada `expires_at`.
+    pub fn is_valid_at(&self, clock: &dyn Clock) -> bool {
+        clock.now() < self.expires_at
     }
 
     pub fn generate_session_key() -> String {
@@ -61,4 +67,15 @@ mod test {
         let session_key = Session::generate_session_key();
         assert_eq!(session_key.len(), 36);
     }
+
+    #[test]
+    fn test_session_expires_exactly_at_expires_at() {
+        use crate::common::clock::FixedClock;
+
+        let user = User::new("test_user".to_string(), "password".to_string(), false);
+        let session = Session::new(user);
+        let expires_at = session.expires_at;
+        assert!(session.is_valid_at(&FixedClock(expires_at - chrono::Duration::seconds(1))));
+        assert!(!session.is_valid_at(&FixedClock(expires_at)));
+    }
 }
\ No newline at end of file
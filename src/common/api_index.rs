@@ -0,0 +1,100 @@
+// Indeks API di `GET /`: daftar grup endpoint beserta base path-nya, dan route yang
+// benar-benar ter-mount di bawah base path tersebut.
+
+use rocket::serde::json::Json;
+use rocket::serde::{Deserialize, Serialize};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::{get, Route};
+
+/// Grup yang ditampilkan di indeks; tambahkan di sini saat modul baru di-mount.
+pub const ENDPOINT_GROUPS: &[(&str, &str)] = &[
+    ("payments", "/api/payments"),
+    ("transaksi", "/api/transaksi"),
+    ("produk", "/api/produk"),
+    ("suppliers", "/api/suppliers"),
+];
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct EndpointGroup {
+    pub name: String,
+    pub base_path: String,
+    /// Format `METHOD /path`, mis. `GET /api/produk/<id>`.
+    pub routes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ApiIndex {
+    pub groups: Vec<EndpointGroup>,
+}
+
+impl ApiIndex {
+    pub fn from_routes<'a>(routes: impl IntoIterator<Item = &'a Route>) -> Self {
+        let routes: Vec<&Route> = routes.into_iter().collect();
+        let groups = ENDPOINT_GROUPS
+            .iter()
+            .map(|(name, base_path)| {
+                let mut group_routes: Vec<String> = routes
+                    .iter()
+                    .filter(|route| {
+                        let path = route.uri.path();
+                        path == *base_path || path.starts_with(&format!("{}/", base_path))
+                    })
+                    .map(|route| format!("{} {}", route.method, route.uri.path()))
+                    .collect();
+                group_routes.sort();
+                group_routes.dedup();
+                EndpointGroup {
+                    name: name.to_string(),
+                    base_path: base_path.to_string(),
+                    routes: group_routes,
+                }
+            })
+            .collect();
+        ApiIndex { groups }
+    }
+}
+
+/// Dibangun dari route yang ter-mount di instance Rocket yang sedang berjalan.
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ApiIndex {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(ApiIndex::from_routes(req.rocket().routes()))
+    }
+}
+
+#[get("/")]
+pub fn index(api_index: ApiIndex) -> Json<ApiIndex> {
+    Json(api_index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::local::asynchronous::Client;
+    use rocket::routes;
+
+    #[rocket::async_test]
+    async fn test_index_lists_all_endpoint_groups_with_mounted_routes() {
+        let client = Client::untracked(rocket::build().mount("/", routes![index])).await.expect("valid rocket instance");
+        let response = client.get("/").dispatch().await;
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let api_index: ApiIndex = response.into_json().await.expect("valid JSON index");
+        let names: Vec<&str> = api_index.groups.iter().map(|group| group.name.as_str()).collect();
+        assert_eq!(names, vec!["payments", "transaksi", "produk", "suppliers"]);
+        assert!(api_index.groups.iter().all(|group| group.routes.is_empty()));
+
+        let mounted = rocket::build()
+            .mount("/api", crate::manajemen_produk::controller::routes())
+            .mount("/api", crate::manajemen_supplier::controller::supplier_controller::supplier_routes());
+        let api_index = ApiIndex::from_routes(mounted.routes());
+        let group = |name: &str| api_index.groups.iter().find(|group| group.name == name).unwrap();
+        assert_eq!(group("produk").base_path, "/api/produk");
+        assert!(group("produk").routes.contains(&"GET /api/produk/<id>".to_string()));
+        assert!(group("suppliers").routes.contains(&"PATCH /api/suppliers/<id>".to_string()));
+        assert!(group("payments").routes.is_empty());
+    }
+}
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+
+use crate::auth::guards::auth::AuthenticatedUser;
+
+/// Siapa yang membuat dan terakhir mengubah sebuah record, diisi dari username
+/// pengguna yang terautentikasi. Kosong untuk data lama atau request tanpa sesi.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AuditTrail {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_by: Option<String>,
+}
+
+impl AuditTrail {
+    pub fn created_by(actor: Option<&str>) -> Self {
+        Self {
+            created_by: actor.map(str::to_string),
+            updated_by: actor.map(str::to_string),
+        }
+    }
+
+    pub fn touch(&mut self, actor: Option<&str>) {
+        if let Some(actor) = actor {
+            self.updated_by = Some(actor.to_string());
+        }
+    }
+
+    /// Field audit hanya ditampilkan ke admin; selain itu dikosongkan sebelum respons dikirim.
+    pub fn redact_unless_admin(&mut self, user: Option<&AuthenticatedUser>) {
+        if !user.is_some_and(|user| user.is_admin) {
+            *self = AuditTrail::default();
+        }
+    }
+}
+
+pub fn actor(user: Option<&AuthenticatedUser>) -> Option<&str> {
+    user.map(|user| user.username.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(is_admin: bool) -> AuthenticatedUser {
+        AuthenticatedUser { user_id: 1, username: "kasir".to_string(), is_admin }
+    }
+
+    #[test]
+    fn test_audit_trail_touch_and_redaction() {
+        let mut audit = AuditTrail::created_by(Some("admin"));
+        audit.touch(Some("kasir"));
+        audit.touch(None);
+        assert_eq!(audit.created_by.as_deref(), Some("admin"));
+        assert_eq!(audit.updated_by.as_deref(), Some("kasir"));
+
+        let mut visible = audit.clone();
+        visible.redact_unless_admin(Some(&user(true)));
+        assert_eq!(visible, audit);
+
+        let mut hidden = audit.clone();
+        hidden.redact_unless_admin(Some(&user(false)));
+        assert_eq!(hidden, AuditTrail::default());
+        assert_eq!(serde_json::to_string(&hidden).unwrap(), "{}");
+    }
+}
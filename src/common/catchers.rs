@@ -0,0 +1,66 @@
+use rocket::serde::json::{json, Json, Value};
+use rocket::{catch, Request};
+
+// Dipicu oleh guard `Json<T>` saat body melebihi limit `json` (Rocket.toml / ROCKET_LIMITS).
+// Bentuknya sama dengan envelope ApiResponse di tiap modul: success, message, data.
+#[catch(413)]
+pub fn payload_too_large(req: &Request<'_>) -> Json<Value> {
+    let message = match req.limits().get("json") {
+        Some(limit) => format!("Payload too large: JSON body exceeds the {} limit", limit),
+        None => "Payload too large".to_string(),
+    };
+
+    Json(json!({
+        "success": false,
+        "message": message,
+        "data": null,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::data::{ByteUnit, Limits};
+    use rocket::http::{ContentType, Status};
+    use rocket::local::asynchronous::Client;
+    use rocket::{catchers, routes};
+    use sqlx::any::{install_default_drivers, AnyPoolOptions};
+
+    use crate::manajemen_pembayaran::controller::payment_controller::{create_payment, ApiResponse};
+    use crate::manajemen_pembayaran::service::payment_service::PaymentService;
+
+    #[rocket::async_test]
+    async fn test_over_limit_json_body_returns_413_envelope() {
+        install_default_drivers();
+        let db_pool = AnyPoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        let figment = rocket::Config::figment().merge(("limits", Limits::default().limit("json", ByteUnit::Byte(64))));
+        let rocket = rocket::custom(figment)
+            .manage(db_pool)
+            .manage(PaymentService::new())
+            .mount("/api", routes![create_payment])
+            .register("/", catchers![payload_too_large]);
+        let client = Client::tracked(rocket).await.expect("valid rocket instance");
+
+        let body = format!(
+            r#"{{"transaction_id":"{}","amount":1000.0,"method":"CASH","status":"LUNAS","due_date":null}}"#,
+            "X".repeat(256)
+        );
+        let response = client.post("/api/payments")
+            .header(ContentType::JSON)
+            .body(body)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::PayloadTooLarge);
+        let envelope: ApiResponse<()> = response.into_json().await.expect("413 body should be an ApiResponse");
+        assert!(!envelope.success);
+        assert!(envelope.message.contains("Payload too large"));
+        assert!(envelope.message.contains("64"));
+        assert!(envelope.data.is_none());
+    }
+}
@@ -0,0 +1,44 @@
+// Sumber waktu untuk logika yang bergantung pada "sekarang" (kedaluwarsa, jatuh tempo),
+// supaya test bisa memakai waktu tetap alih-alih `Utc::now()`.
+
+use chrono::{DateTime, Utc};
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Selalu mengembalikan waktu yang sama; dipakai test untuk menguji batas waktu secara tepat.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_fixed_clock_is_stable_and_system_clock_advances() {
+        let instant = Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap();
+        let clock = FixedClock(instant);
+        assert_eq!(clock.now(), instant);
+        assert_eq!(clock.now(), clock.now());
+
+        let before = Utc::now();
+        assert!(SystemClock.now() >= before);
+    }
+}
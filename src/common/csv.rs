@@ -0,0 +1,67 @@
+// Utilitas CSV bersama: escaping dan parsing field, serta responder unduhan dengan
+// `Content-Disposition: attachment`.
+
+use rocket::http::Header;
+
+/// Field CSV dikutip bila mengandung pemisah, kutip, atau baris baru.
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Memecah satu baris CSV menjadi field; kebalikan `csv_field`. Field berkutip boleh berisi
+/// koma dan `""` sebagai kutip literal, tetapi tidak baris baru (satu record per baris).
+pub fn parse_csv_record(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => {
+                    quoted = false;
+                    if !matches!(chars.peek(), None | Some(',')) {
+                        return Err("Karakter setelah kutip penutup harus koma".to_string());
+                    }
+                }
+                _ => field.push(c),
+            }
+        } else {
+            match c {
+                ',' => fields.push(std::mem::take(&mut field)),
+                '"' if field.is_empty() => quoted = true,
+                _ => field.push(c),
+            }
+        }
+    }
+    if quoted {
+        return Err("Kutip tidak ditutup".to_string());
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+#[derive(rocket::Responder)]
+#[response(content_type = "text/csv")]
+pub struct CsvExport {
+    body: String,
+    disposition: Header<'static>,
+}
+
+impl CsvExport {
+    pub fn attachment(body: String, filename: &str) -> Self {
+        CsvExport {
+            body,
+            disposition: Header::new("Content-Disposition", format!("attachment; filename=\"{}\"", filename)),
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::time::Duration;
+use sqlx::any::AnyPoolOptions;
+
+pub const DEFAULT_DB_ACQUIRE_TIMEOUT_SECS: u64 = 30;
+
+/// Pemeriksaan koneksi sebelum dipinjamkan dari pool, supaya koneksi basi (mis. diputus
+/// server setelah idle) dibuang di sini alih-alih gagal di tengah request. Diatur lewat env
+/// `DB_HEALTH_CHECK`: `ping` (default, ping bawaan driver), `query` (`SELECT 1`) atau `off`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PoolHealthCheck {
+    Ping,
+    Query,
+    Off,
+}
+
+impl PoolHealthCheck {
+    pub fn from_env() -> Self {
+        Self::parse(std::env::var("DB_HEALTH_CHECK").ok().as_deref())
+    }
+
+    pub fn parse(value: Option<&str>) -> Self {
+        match value.map(|value| value.trim().to_lowercase()).as_deref() {
+            Some("query") | Some("select") => PoolHealthCheck::Query,
+            Some("off") | Some("0") | Some("false") | Some("none") => PoolHealthCheck::Off,
+            _ => PoolHealthCheck::Ping,
+        }
+    }
+
+    /// Opsi pool dengan pemeriksaan ini terpasang. Koneksi yang gagal diperiksa ditutup
+    /// oleh sqlx dan diganti koneksi lain sebelum dikembalikan ke pemanggil.
+    pub fn apply(self, options: AnyPoolOptions) -> AnyPoolOptions {
+        match self {
+            PoolHealthCheck::Ping => options.test_before_acquire(true),
+            PoolHealthCheck::Off => options.test_before_acquire(false),
+            PoolHealthCheck::Query => options
+                .test_before_acquire(false)
+                .before_acquire(|conn, _meta| Box::pin(async move {
+                    sqlx::query("SELECT 1").execute(&mut *conn).await?;
+                    Ok(true)
+                })),
+        }
+    }
+}
+
+/// Batas tunggu koneksi saat pool habis, lewat env `DB_ACQUIRE_TIMEOUT_SECS` (default 30 detik).
+pub fn acquire_timeout() -> Duration {
+    let secs = std::env::var("DB_ACQUIRE_TIMEOUT_SECS")
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(DEFAULT_DB_ACQUIRE_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
+
+/// Opsi pool utama aplikasi sesuai konfigurasi env di atas.
+pub fn pool_options_from_env() -> AnyPoolOptions {
+    PoolHealthCheck::from_env().apply(AnyPoolOptions::new().acquire_timeout(acquire_timeout()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::Row;
+
+    #[test]
+    fn test_parse_health_check_mode() {
+        assert_eq!(PoolHealthCheck::parse(None), PoolHealthCheck::Ping);
+        assert_eq!(PoolHealthCheck::parse(Some("ping")), PoolHealthCheck::Ping);
+        assert_eq!(PoolHealthCheck::parse(Some(" Query ")), PoolHealthCheck::Query);
+        assert_eq!(PoolHealthCheck::parse(Some("SELECT")), PoolHealthCheck::Query);
+        assert_eq!(PoolHealthCheck::parse(Some("off")), PoolHealthCheck::Off);
+        assert_eq!(PoolHealthCheck::parse(Some("false")), PoolHealthCheck::Off);
+        assert_eq!(PoolHealthCheck::parse(Some("unknown")), PoolHealthCheck::Ping);
+    }
+
+    #[tokio::test]
+    async fn test_pool_serves_queries_under_every_health_check() {
+        sqlx::any::install_default_drivers();
+        for mode in [PoolHealthCheck::Ping, PoolHealthCheck::Query, PoolHealthCheck::Off] {
+            let pool = mode
+                .apply(AnyPoolOptions::new().max_connections(1).acquire_timeout(Duration::from_secs(5)))
+                .connect("sqlite::memory:")
+                .await
+                .unwrap();
+
+            // Satu koneksi dipinjam berulang kali, jadi pemeriksaan berjalan tiap acquire
+            for expected in 1..=3i64 {
+                let row = sqlx::query("SELECT CAST($1 AS BIGINT) AS value").bind(expected).fetch_one(&pool).await.unwrap();
+                assert_eq!(row.get::<i64, _>("value"), expected, "{mode:?}");
+            }
+            pool.close().await;
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use rocket::request::{FromRequest, Outcome};
+use rocket::Request;
+use std::fmt::Display;
+
+// Bahasa pesan respons, dipilih dari header Accept-Language (default: Indonesia).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    Id,
+    En,
+}
+
+impl Lang {
+    fn from_tag(tag: &str) -> Option<Self> {
+        let primary = tag.split(['-', '_']).next().unwrap_or("").trim();
+        if primary.eq_ignore_ascii_case("id") || primary.eq_ignore_ascii_case("in") {
+            Some(Lang::Id)
+        } else if primary.eq_ignore_ascii_case("en") {
+            Some(Lang::En)
+        } else {
+            None
+        }
+    }
+
+    /// Memilih bahasa yang didukung dengan bobot `q` tertinggi; urutan di header
+    /// menjadi pemecah seri. Header kosong/tidak dikenal jatuh ke Indonesia.
+    pub fn from_accept_language(header: Option<&str>) -> Self {
+        let Some(header) = header else {
+            return Lang::Id;
+        };
+
+        let mut best: Option<(Lang, f32)> = None;
+        for entry in header.split(',') {
+            let mut parts = entry.split(';');
+            let Some(lang) = Lang::from_tag(parts.next().unwrap_or("")) else {
+                continue;
+            };
+            let weight = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .and_then(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if weight > 0.0 && best.is_none_or(|(_, best_weight)| weight > best_weight) {
+                best = Some((lang, weight));
+            }
+        }
+
+        best.map(|(lang, _)| lang).unwrap_or(Lang::Id)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Lang {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(Lang::from_accept_language(req.headers().get_one("Accept-Language")))
+    }
+}
+
+// (kode, Indonesia, English). Placeholder `{}` diisi berurutan oleh `Messages::format`.
+const CATALOG: &[(&str, &str, &str)] = &[
+    ("produk.list_ok", "Berhasil mengambil daftar produk", "Product list retrieved successfully"),
+    ("produk.list_failed", "Gagal mengambil daftar produk: {}", "Failed to retrieve product list: {}"),
+    ("produk.stok_filter_conflict", "low_stock_only dan out_of_stock_only tidak bisa dipakai bersamaan", "low_stock_only and out_of_stock_only cannot be combined"),
+    ("produk.valuation_ok", "Berhasil menghitung nilai persediaan", "Inventory valuation computed successfully"),
+    ("produk.margin_ok", "Berhasil mengambil laporan margin produk", "Product margin report retrieved successfully"),
+    ("produk.detail_ok", "Berhasil mengambil detail produk", "Product detail retrieved successfully"),
+    ("produk.detail_failed", "Gagal mengambil detail produk: {}", "Failed to retrieve product detail: {}"),
+    ("produk.not_found", "Produk dengan ID {} tidak ditemukan", "Product with ID {} not found"),
+    ("produk.sku_not_found", "Produk dengan SKU {} tidak ditemukan", "Product with SKU {} not found"),
+    ("produk.deleted", "Produk dengan ID {} berhasil dihapus", "Product with ID {} deleted successfully"),
+    ("produk.delete_failed", "Gagal menghapus produk: {}", "Failed to delete product: {}"),
+    // Entri `*_failed` payment tanpa placeholder dipakai sebagai awalan `ServiceResult::context`.
+    ("payment.invalid_due_date", "Format due date tidak valid. Gunakan format RFC3339", "Invalid due date format. Use RFC3339 format"),
+    ("payment.invalid_fields", "fields '{}' tidak valid, gunakan 'basic' atau 'full'", "Invalid fields '{}', expected 'basic' or 'full'"),
+    ("payment.created", "Payment berhasil dibuat", "Payment created successfully"),
+    ("payment.created_overpaid", "Payment berhasil dibuat; transaksi kelebihan bayar {}", "Payment created successfully; transaction overpaid by {}"),
+    ("payment.create_failed", "Gagal membuat payment", "Failed to create payment"),
+    ("payment.detail_ok", "Berhasil mengambil payment", "Payment retrieved successfully"),
+    ("payment.detail_failed", "Gagal mengambil payment", "Failed to retrieve payment"),
+    ("payment.updated", "Payment berhasil diperbarui", "Payment updated successfully"),
+    ("payment.update_failed", "Gagal memperbarui payment", "Failed to update payment"),
+    ("payment.created_by_forbidden", "Hanya admin atau user yang sama yang boleh memfilter payment berdasarkan created_by", "Only admins or the same user can filter payments by created_by"),
+    ("payment.list_ok", "Berhasil mengambil {} payment", "Successfully retrieved {} payments"),
+    ("payment.list_failed", "Gagal mengambil daftar payment", "Failed to retrieve payments"),
+    ("payment.count_ok", "Berhasil menghitung payment", "Payments counted successfully"),
+    ("payment.count_failed", "Gagal menghitung payment", "Failed to count payments"),
+    ("payment.match_ok", "Berhasil mengambil payment yang cocok", "Matching payments retrieved successfully"),
+    ("payment.match_failed", "Gagal mencocokkan payment", "Failed to match payments"),
+    ("payment.status_updated", "Status payment berhasil diperbarui", "Payment status updated successfully"),
+    ("payment.status_update_failed", "Gagal memperbarui status payment", "Failed to update payment status"),
+    ("payment.method_updated", "Metode payment berhasil diperbarui", "Payment method updated successfully"),
+    ("payment.method_update_failed", "Gagal memperbarui metode payment", "Failed to update payment method"),
+    ("payment.installment_added", "Cicilan berhasil ditambahkan", "Installment added successfully"),
+    ("payment.installment_add_failed", "Gagal menambahkan cicilan", "Failed to add installment"),
+    ("payment.next_installment_ok", "Berhasil mengambil cicilan berikutnya", "Next installment retrieved successfully"),
+    ("payment.next_installment_failed", "Gagal mengambil cicilan berikutnya", "Failed to retrieve next installment"),
+    ("payment.refund_recorded", "Refund berhasil dicatat", "Refund recorded successfully"),
+    ("payment.refund_failed", "Gagal mencatat refund", "Failed to record refund"),
+    ("payment.refunds_ok", "Berhasil mengambil daftar refund", "Refunds retrieved successfully"),
+    ("payment.refunds_failed", "Gagal mengambil daftar refund", "Failed to retrieve refunds"),
+    ("payment.force_delete_forbidden", "Hanya admin yang boleh menghapus paksa payment", "Only admins can force-delete a payment"),
+    ("payment.deleted", "Payment berhasil dihapus", "Payment deleted successfully"),
+    ("payment.delete_failed", "Gagal menghapus payment", "Failed to delete payment"),
+    ("payment.by_transaction_ok", "Berhasil mengambil {} payment untuk transaksi {}", "Successfully retrieved {} payments for transaction {}"),
+    ("payment.expire_forbidden", "Hanya admin yang boleh membatalkan payment pending yang kedaluwarsa", "Only admins can expire stale pending payments"),
+    ("payment.expired", "{} payment pending yang lebih lama dari {} menit dibatalkan", "Expired {} pending payments older than {} minutes"),
+    ("payment.expire_failed", "Gagal membatalkan payment pending", "Failed to expire pending payments"),
+    ("payment.aging_ok", "Laporan umur payment berhasil dibuat", "Payment aging report generated successfully"),
+    ("payment.aging_failed", "Gagal membuat laporan umur payment", "Failed to generate payment aging report"),
+    ("payment.monthly_ok", "Laporan pendapatan bulanan berhasil dibuat", "Monthly revenue report generated successfully"),
+    ("payment.monthly_failed", "Gagal membuat laporan pendapatan bulanan", "Failed to generate monthly revenue report"),
+    ("payment.methods_ok", "Berhasil mengambil daftar metode payment", "Payment methods retrieved successfully"),
+    ("payment.statuses_ok", "Berhasil mengambil daftar status payment", "Payment statuses retrieved successfully"),
+    ("transaksi.unknown_filter", "Filter '{}' tidak dikenal; yang diizinkan: {}", "Unknown filter '{}'; allowed: {}"),
+    ("transaksi.details_failed", "Gagal mengambil detail transaksi", "Failed to fetch detail transaksi"),
+    ("transaksi.invalid_search", "Parameter sort, filter atau tanggal tidak valid", "Invalid sort, filter or date parameter"),
+    ("transaksi.fetch_failed", "Gagal mengambil transaksi", "Failed to fetch transaksi"),
+    ("transaksi.invalid_date", "Tanggal '{}' tidak valid; gunakan YYYY-MM-DD atau YYYY-MM-DD HH:MM:SS", "Invalid date '{}'; use YYYY-MM-DD or YYYY-MM-DD HH:MM:SS"),
+    ("transaksi.export_failed", "Gagal mengekspor transaksi", "Failed to export transaksi"),
+    ("transaksi.invalid_idempotency_key", "Validasi gagal: Idempotency-Key harus 1-{} karakter", "Validation error: Idempotency-Key must be 1-{} characters"),
+    ("transaksi.validation_error", "Validasi gagal: {}", "Validation error: {}"),
+    ("transaksi.created", "Transaksi berhasil dibuat", "Transaksi created successfully"),
+    ("transaksi.insufficient_stock", "Validasi gagal atau stok tidak mencukupi", "Validation error or insufficient stock"),
+    ("transaksi.database_error", "Terjadi kesalahan database", "Database error occurred"),
+    ("transaksi.create_failed", "Gagal membuat transaksi", "Failed to create transaction"),
+    ("transaksi.invalid_data", "Data tidak valid", "Invalid data"),
+    ("transaksi.updated", "Transaksi berhasil diperbarui", "Transaksi updated successfully"),
+    ("transaksi.not_modifiable", "Transaksi tidak dapat diubah", "Transaksi cannot be modified"),
+    ("transaksi.try_again_later", "Coba lagi nanti", "Try again later"),
+    ("transaksi.deleted", "Transaksi berhasil dihapus", "Transaksi deleted successfully"),
+    ("transaksi.not_deletable", "Transaksi tidak dapat dihapus", "Transaksi cannot be deleted"),
+    ("transaksi.delete_failed", "Gagal menghapus transaksi", "Failed to delete transaksi"),
+    ("transaksi.completed", "Transaksi berhasil diselesaikan", "Transaksi completed successfully"),
+    ("transaksi.not_completable", "Transaksi tidak dapat diselesaikan", "Transaksi cannot be completed"),
+    ("transaksi.complete_failed", "Gagal menyelesaikan transaksi", "Failed to complete transaksi"),
+    ("transaksi.cancelled", "Transaksi berhasil dibatalkan", "Transaksi cancelled successfully"),
+    ("transaksi.not_cancellable", "Transaksi tidak dapat dibatalkan", "Transaksi cannot be cancelled"),
+    ("transaksi.cancel_failed", "Gagal membatalkan transaksi", "Failed to cancel transaksi"),
+    ("transaksi.alasan_pembatalan_too_long", "alasan_pembatalan maksimal {} karakter", "alasan_pembatalan must be at most {} characters"),
+    ("transaksi.alasan_too_long", "alasan maksimal {} karakter", "alasan must be at most {} characters"),
+    ("transaksi.voided", "Transaksi berhasil di-void", "Transaksi voided successfully"),
+    ("transaksi.items_returned", "Barang berhasil diretur", "Items returned successfully"),
+    ("transaksi.invalid_transaction_id", "ID transaksi tidak valid", "Invalid transaction ID"),
+    ("transaksi.price_check_failed", "Gagal memeriksa harga produk", "Failed to check produk price"),
+    ("transaksi.price_mismatch", "Harga tidak sesuai: {}", "Price mismatch: {}"),
+    ("transaksi.detail_added", "Detail transaksi berhasil ditambahkan", "Detail transaksi added successfully"),
+    ("transaksi.detail_added_with_warning", "Detail transaksi berhasil ditambahkan; peringatan: {}", "Detail transaksi added successfully; warning: {}"),
+    ("transaksi.detail_not_modifiable", "Transaksi tidak dapat diubah", "Transaction cannot be modified"),
+    ("transaksi.detail_add_failed", "Gagal menambahkan detail transaksi", "Failed to add detail transaksi"),
+    ("transaksi.detail_updated", "Detail transaksi berhasil diperbarui", "Detail transaksi updated successfully"),
+    ("transaksi.detail_update_failed", "Gagal memperbarui detail transaksi", "Failed to update detail transaksi"),
+    ("transaksi.detail_deleted", "Detail transaksi berhasil dihapus", "Detail transaksi deleted successfully"),
+    ("transaksi.detail_delete_failed", "Gagal menghapus detail transaksi", "Failed to delete detail transaksi"),
+    ("transaksi.not_found", "Transaksi {} tidak ditemukan", "Transaksi {} not found"),
+    ("transaksi.payment_plan_not_allowed", "Payment plan tidak dapat dibuat untuk transaksi yang dibatalkan atau di-void", "Cannot create a payment plan for a cancelled or voided transaksi"),
+    ("transaksi.payment_plan_created", "Payment plan dengan {} cicilan berhasil dibuat", "Payment plan with {} installment(s) created"),
+    ("transaksi.checkout_ok", "Checkout berhasil", "Checkout completed successfully"),
+    ("transaksi.payment_summary_ok", "Ringkasan pembayaran berhasil diambil", "Payment summary retrieved successfully"),
+    ("transaksi.payment_breakdown_ok", "Rincian pembayaran per metode berhasil diambil", "Payment breakdown retrieved successfully"),
+    ("transaksi.recompute_forbidden", "Hanya admin yang dapat menghitung ulang total transaksi", "Only admins can recompute transaksi totals"),
+    ("transaksi.recomputed", "Total transaksi berhasil dihitung ulang", "Transaksi total recomputed successfully"),
+    ("transaksi.recompute_failed", "Gagal menghitung ulang total transaksi", "Failed to recompute transaksi total"),
+    ("transaksi.stock_available", "Semua produk tersedia", "All products available"),
+    ("transaksi.invalid_report_date", "Parameter tanggal tidak valid", "Invalid date parameter"),
+    ("transaksi.top_products_failed", "Gagal mengambil produk terlaris", "Failed to fetch top products"),
+    ("transaksi.sales_by_category_failed", "Gagal mengambil penjualan per kategori", "Failed to fetch sales by category"),
+    ("supplier.created", "Supplier berhasil dibuat", "Supplier created successfully"),
+    ("supplier.found", "Supplier ditemukan.", "Supplier found successfully."),
+    ("supplier.not_found", "Supplier dengan ID '{}' tidak ditemukan.", "Supplier with ID '{}' not found."),
+    ("supplier.not_found_after_update", "Supplier dengan ID '{}' tidak ditemukan setelah diperbarui.", "Supplier with ID '{}' not found after update."),
+    ("supplier.fetch_after_update_failed", "Gagal mengambil supplier setelah diperbarui: {}", "Error fetching supplier after update: {}"),
+    ("supplier.updated", "Supplier berhasil diperbarui.", "Supplier updated successfully."),
+    ("supplier.deleted", "Supplier dengan ID '{}' berhasil dihapus.", "Supplier with ID '{}' deleted successfully."),
+    ("supplier.list_ok", "Daftar supplier berhasil diambil.", "Suppliers retrieved successfully."),
+    ("supplier.transactions_ok", "Daftar transaksi supplier berhasil diambil.", "Supplier transactions retrieved successfully."),
+    ("supplier.merged", "Supplier '{}' digabung ke '{}'; {} transaksi dipindahkan.", "Supplier '{}' merged into '{}'; {} transaction(s) moved."),
+    ("supplier.transaction_voided", "Transaksi supplier '{}' berhasil di-void.", "Supplier transaction '{}' voided successfully."),
+    ("supplier.performance_ok", "Performa supplier berhasil diambil.", "Supplier performance retrieved successfully."),
+    ("supplier.stats_ok", "Statistik supplier berhasil diambil.", "Supplier stats retrieved successfully."),
+];
+
+pub struct Messages;
+
+impl Messages {
+    /// Teks pesan untuk `code` dalam `lang`; kode yang tidak ada di katalog dikembalikan apa adanya.
+    pub fn get(code: &str, lang: Lang) -> &str {
+        CATALOG
+            .iter()
+            .find(|(key, _, _)| *key == code)
+            .map(|(_, id, en)| match lang {
+                Lang::Id => *id,
+                Lang::En => *en,
+            })
+            .unwrap_or(code)
+    }
+
+    /// Mengisi placeholder `{}` secara berurutan. Template dipecah sekali, jadi `{}` di dalam
+    /// argumen tidak ikut diganti; placeholder tanpa argumen dibiarkan apa adanya.
+    pub fn format(code: &str, lang: Lang, args: &[&dyn Display]) -> String {
+        let mut parts = Messages::get(code, lang).split("{}");
+        let mut message = parts.next().unwrap_or_default().to_string();
+        let mut args = args.iter();
+        for part in parts {
+            match args.next() {
+                Some(arg) => message.push_str(&arg.to_string()),
+                None => message.push_str("{}"),
+            }
+            message.push_str(part);
+        }
+        message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_code_is_localized_per_language() {
+        assert_eq!(Messages::get("produk.list_ok", Lang::Id), "Berhasil mengambil daftar produk");
+        assert_eq!(Messages::get("produk.list_ok", Lang::En), "Product list retrieved successfully");
+        assert_eq!(Messages::format("produk.not_found", Lang::Id, &[&42]), "Produk dengan ID 42 tidak ditemukan");
+        assert_eq!(Messages::format("produk.not_found", Lang::En, &[&42]), "Product with ID 42 not found");
+        assert_eq!(Messages::get("kode.tidak_ada", Lang::En), "kode.tidak_ada");
+    }
+
+    #[test]
+    fn test_format_does_not_substitute_placeholders_inside_arguments() {
+        assert_eq!(Messages::format("produk.not_found", Lang::En, &[&"{}"]), "Product with ID {} not found");
+        assert_eq!(
+            Messages::format("supplier.merged", Lang::En, &[&"SUP-{}", &"SUP-1", &2]),
+            "Supplier 'SUP-{}' merged into 'SUP-1'; 2 transaction(s) moved."
+        );
+        assert_eq!(Messages::format("transaksi.unknown_filter", Lang::En, &[&"x"]), "Unknown filter 'x'; allowed: {}");
+    }
+
+    #[test]
+    fn test_every_catalog_entry_has_matching_placeholders() {
+        for (code, id, en) in CATALOG {
+            assert_eq!(id.matches("{}").count(), en.matches("{}").count(), "placeholder mismatch for {code}");
+        }
+    }
+
+    #[test]
+    fn test_lang_from_accept_language() {
+        assert_eq!(Lang::from_accept_language(None), Lang::Id);
+        assert_eq!(Lang::from_accept_language(Some("en-US,en;q=0.9")), Lang::En);
+        assert_eq!(Lang::from_accept_language(Some("id-ID")), Lang::Id);
+        assert_eq!(Lang::from_accept_language(Some("fr-FR, en;q=0.5, id;q=0.8")), Lang::Id);
+        assert_eq!(Lang::from_accept_language(Some("fr-FR, de")), Lang::Id);
+        assert_eq!(Lang::from_accept_language(Some("*")), Lang::Id);
+    }
+}
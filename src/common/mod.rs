@@ -0,0 +1,14 @@
+pub mod api_index;
+pub mod audit;
+pub mod catchers;
+pub mod clock;
+pub mod csv;
+pub mod db_pool;
+pub mod messages;
+pub mod money;
+pub mod pagination;
+pub mod request_id;
+pub mod service_result;
+pub mod slow_query;
+pub mod sql_dialect;
+pub mod timestamp;
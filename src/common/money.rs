@@ -0,0 +1,123 @@
+// Nominal uang dihitung dalam satuan sen (i64) supaya penjumlahan banyak baris
+// tidak menumpuk galat float; `f64` hanya dipakai di batas API/DB.
+
+/// Rupiah -> sen, dibulatkan ke sen terdekat (setengah sen menjauhi nol).
+pub fn to_sen(amount: f64) -> i64 {
+    (amount * 100.0).round() as i64
+}
+
+pub fn from_sen(sen: i64) -> f64 {
+    sen as f64 / 100.0
+}
+
+/// Subtotal satu baris: harga satuan dibulatkan ke sen dulu, baru dikali jumlah. Jumlah desimal
+/// (barang timbangan) dibulatkan ke sen terdekat; jumlah bulat memberi hasil yang eksak.
+pub fn line_subtotal_sen(harga_satuan: f64, jumlah: f64) -> i64 {
+    (to_sen(harga_satuan) as f64 * jumlah).round() as i64
+}
+
+/// Subtotal baris setelah diskon baris; tidak pernah di bawah nol.
+pub fn discounted_line_subtotal_sen(harga_satuan: f64, jumlah: f64, diskon: Option<f64>) -> i64 {
+    (line_subtotal_sen(harga_satuan, jumlah) - diskon.map(to_sen).unwrap_or(0)).max(0)
+}
+
+/// Jumlah nominal dalam sen lalu dikembalikan sebagai nilai kanonik dua desimal.
+pub fn sum_amounts<I: IntoIterator<Item = f64>>(amounts: I) -> f64 {
+    from_sen(amounts.into_iter().map(to_sen).sum())
+}
+
+pub const DEFAULT_REPORT_PRECISION: u32 = 2;
+const MAX_REPORT_PRECISION: u32 = 6;
+
+/// Cara pembulatan nominal pada keluaran laporan/ringkasan, diatur lewat env
+/// `MONEY_ROUNDING_MODE` (`half_even` default, `half_up` untuk setengah menjauhi nol).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RoundingMode {
+    HalfEven,
+    HalfUp,
+}
+
+impl RoundingMode {
+    pub fn from_env() -> Self {
+        match std::env::var("MONEY_ROUNDING_MODE") {
+            Ok(value) if value.eq_ignore_ascii_case("half_up") => RoundingMode::HalfUp,
+            _ => RoundingMode::HalfEven,
+        }
+    }
+
+    pub fn round(self, amount: f64, precision: u32) -> f64 {
+        let factor = 10f64.powi(precision as i32);
+        let scaled = amount * factor;
+        let floor = scaled.floor();
+        // Angka seperti 0.125 tersimpan sedikit di bawah/atas setengah; anggap tetap seri
+        let is_tie = ((scaled - floor) - 0.5).abs() < 1e-9;
+        let rounded = match self {
+            RoundingMode::HalfEven if is_tie => {
+                if floor % 2.0 == 0.0 { floor } else { floor + 1.0 }
+            }
+            RoundingMode::HalfUp if is_tie => {
+                if scaled < 0.0 { floor } else { floor + 1.0 }
+            }
+            _ => scaled.round(),
+        };
+        rounded / factor
+    }
+}
+
+/// Jumlah desimal nominal di laporan, lewat env `MONEY_REPORT_PRECISION` (default 2, maks 6).
+pub fn report_precision() -> u32 {
+    std::env::var("MONEY_REPORT_PRECISION")
+        .ok()
+        .and_then(|value| value.parse::<u32>().ok())
+        .filter(|value| *value <= MAX_REPORT_PRECISION)
+        .unwrap_or(DEFAULT_REPORT_PRECISION)
+}
+
+/// Membulatkan nominal untuk ditampilkan di laporan/ringkasan; nilai tersimpan tidak diubah.
+pub fn round_report_amount(amount: f64) -> f64 {
+    RoundingMode::from_env().round(amount, report_precision())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sen_round_trip_and_rounding() {
+        assert_eq!(to_sen(0.1), 10);
+        assert_eq!(to_sen(19.999), 2000);
+        assert_eq!(to_sen(-0.005), -1);
+        assert_eq!(from_sen(12345), 123.45);
+        assert_eq!(line_subtotal_sen(0.07, 3.0), 21);
+        assert_eq!(discounted_line_subtotal_sen(0.07, 3.0, Some(0.05)), 16);
+        assert_eq!(discounted_line_subtotal_sen(0.07, 3.0, None), 21);
+    }
+
+    #[test]
+    fn test_sum_amounts_is_exact_where_float_sum_drifts() {
+        let amounts = vec![0.1; 1000];
+        assert_ne!(amounts.iter().sum::<f64>(), 100.0);
+        assert_eq!(sum_amounts(amounts), 100.0);
+    }
+
+    #[test]
+    fn test_half_even_rounding_edge_cases() {
+        let mode = RoundingMode::HalfEven;
+        assert_eq!(mode.round(0.125, 2), 0.12);
+        assert_eq!(mode.round(0.135, 2), 0.14);
+        assert_eq!(mode.round(-0.125, 2), -0.12);
+        assert_eq!(mode.round(2.5, 0), 2.0);
+        assert_eq!(mode.round(3.5, 0), 4.0);
+        assert_eq!(mode.round(0.1 + 0.2, 2), 0.3);
+        assert_eq!(mode.round(1234.5678, 3), 1234.568);
+        assert_eq!(mode.round(0.126, 2), 0.13);
+    }
+
+    #[test]
+    fn test_half_up_rounding_moves_ties_away_from_zero() {
+        let mode = RoundingMode::HalfUp;
+        assert_eq!(mode.round(0.125, 2), 0.13);
+        assert_eq!(mode.round(-0.125, 2), -0.13);
+        assert_eq!(mode.round(2.5, 0), 3.0);
+    }
+}
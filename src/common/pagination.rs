@@ -0,0 +1,167 @@
+use rocket::FromForm;
+use sqlx::{Any, QueryBuilder};
+
+pub const DEFAULT_PAGE: usize = 1;
+pub const DEFAULT_LIMIT: usize = 10;
+pub const MAX_LIMIT: usize = 100;
+
+/// Nilai `sort` dan ekspresi kolom SQL-nya; sufiks `_desc` membalik urutannya. Ekspresinya
+/// hanya berasal dari konstanta, tidak pernah dari input.
+pub type SortColumn = (&'static str, &'static str);
+
+/// Parameter query `page`, `limit`, `sort` dan `keyword` yang dipakai bersama oleh
+/// semua endpoint listing, diambil lewat `<list..>` di route.
+#[derive(Debug, Clone, Default, PartialEq, FromForm)]
+pub struct ListQuery {
+    pub page: Option<usize>,
+    pub limit: Option<usize>,
+    pub sort: Option<String>,
+    pub keyword: Option<String>,
+}
+
+impl ListQuery {
+    pub fn page(&self) -> usize {
+        self.page.filter(|p| *p > 0).unwrap_or(DEFAULT_PAGE)
+    }
+
+    /// Limit 0/kosong memakai default, limit di atas `MAX_LIMIT` dipotong.
+    pub fn limit(&self) -> usize {
+        self.limit.filter(|l| *l > 0).unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT)
+    }
+
+    pub fn offset(&self) -> usize {
+        (self.page() - 1) * self.limit()
+    }
+
+    /// Listing yang dulu selalu mengembalikan semua data hanya dipaginasi bila diminta.
+    pub fn is_paginated(&self) -> bool {
+        self.page.is_some() || self.limit.is_some()
+    }
+
+    pub fn sort(&self) -> Option<&str> {
+        self.sort.as_deref().map(str::trim).filter(|s| !s.is_empty())
+    }
+
+    /// Keyword yang sudah di-trim dan di-lowercase; kosong dianggap tidak ada.
+    pub fn keyword(&self) -> Option<String> {
+        self.keyword
+            .as_deref()
+            .map(|k| k.trim().to_lowercase())
+            .filter(|k| !k.is_empty())
+    }
+
+    /// Ekspresi `ORDER BY` untuk `sort`, diakhiri `tiebreak` supaya urutan antarhalaman stabil.
+    /// Tanpa `sort` hasilnya `None` dan pemanggil memakai urutan bawaannya; `sort` yang tidak
+    /// ada di `sort_columns` ditolak.
+    pub fn order_by(&self, sort_columns: &[SortColumn], tiebreak: &str) -> Result<Option<String>, String> {
+        let Some(sort) = self.sort() else {
+            return Ok(None);
+        };
+        let (field, direction) = match sort.strip_suffix("_desc") {
+            Some(field) => (field, "DESC"),
+            None => (sort, "ASC"),
+        };
+        let column = sort_columns
+            .iter()
+            .find(|(name, _)| *name == field)
+            .map(|(_, column)| *column)
+            .ok_or_else(|| format!("Invalid sort parameter: {}", sort))?;
+        Ok(Some(format!("{} {}, {}", column, direction, tiebreak)))
+    }
+
+    /// ` LIMIT n OFFSET m` bila listing dipaginasi (lihat `apply_pagination`), kosong bila tidak.
+    pub fn limit_clause(&self) -> String {
+        if !self.is_paginated() {
+            return String::new();
+        }
+        let (limit, offset) = limit_offset(self.page, self.limit);
+        format!(" LIMIT {} OFFSET {}", limit, offset)
+    }
+}
+
+/// Menambahkan `LIMIT`/`OFFSET` ke query. Nilainya ditulis sebagai literal integer,
+/// bukan placeholder, supaya tidak bergantung pada gaya placeholder driver Any
+/// (`push_bind` menghasilkan `?` yang ditolak Postgres) dan tidak menggeser nomor `$n`.
+pub fn apply_pagination(qb: &mut QueryBuilder<'_, Any>, page: Option<usize>, limit: Option<usize>) {
+    let (limit, offset) = limit_offset(page, limit);
+    qb.push(format!(" LIMIT {} OFFSET {}", limit, offset));
+}
+
+/// Escape `%`, `_` dan `\` supaya keyword dicocokkan sebagai karakter biasa;
+/// pasangkan dengan `ESCAPE '\'` di klausa `LIKE`.
+pub fn escape_like(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// Page dimulai dari 1; page/limit 0 diperlakukan sebagai nilai default dan limit di atas
+/// `MAX_LIMIT` dipotong, sama seperti `ListQuery::limit`.
+pub fn limit_offset(page: Option<usize>, limit: Option<usize>) -> (usize, usize) {
+    let page = page.filter(|p| *p > 0).unwrap_or(DEFAULT_PAGE);
+    let limit = limit.filter(|l| *l > 0).unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    (limit, (page - 1) * limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_pagination_sql_fragment() {
+        let mut qb: QueryBuilder<Any> = QueryBuilder::new("SELECT id FROM transaksi ORDER BY id");
+        apply_pagination(&mut qb, Some(3), Some(20));
+        assert_eq!(qb.sql(), "SELECT id FROM transaksi ORDER BY id LIMIT 20 OFFSET 40");
+    }
+
+    #[test]
+    fn test_apply_pagination_defaults() {
+        let mut qb: QueryBuilder<Any> = QueryBuilder::new("SELECT id FROM payments");
+        apply_pagination(&mut qb, None, None);
+        assert_eq!(qb.sql(), "SELECT id FROM payments LIMIT 10 OFFSET 0");
+
+        assert_eq!(limit_offset(Some(0), Some(0)), (10, 0));
+        assert_eq!(limit_offset(Some(2), Some(1000)), (MAX_LIMIT, MAX_LIMIT));
+    }
+
+    fn parse_list_query(query: &str) -> ListQuery {
+        rocket::form::Form::parse_encoded(rocket::http::RawStr::new(query)).expect("valid list query")
+    }
+
+    #[test]
+    fn test_list_query_defaults_and_clamping() {
+        let empty = parse_list_query("");
+        assert_eq!(empty, ListQuery::default());
+        assert_eq!((empty.page(), empty.limit(), empty.offset()), (1, DEFAULT_LIMIT, 0));
+        assert!(!empty.is_paginated());
+        assert_eq!(empty.keyword(), None);
+
+        let clamped = parse_list_query("page=0&limit=1000&keyword=%20%20&sort=%20");
+        assert_eq!((clamped.page(), clamped.limit()), (1, MAX_LIMIT));
+        assert!(clamped.is_paginated());
+        assert_eq!(clamped.keyword(), None);
+        assert_eq!(clamped.sort(), None);
+
+        let query = parse_list_query("page=3&limit=20&keyword=%20Semen%20&sort=nama_desc");
+        assert_eq!((query.page(), query.limit(), query.offset()), (3, 20, 40));
+        assert_eq!(query.keyword().as_deref(), Some("semen"));
+        assert_eq!(query.sort(), Some("nama_desc"));
+    }
+
+    #[test]
+    fn test_list_query_order_by_and_limit_clause() {
+        let sort_columns: &[SortColumn] = &[("nama", "LOWER(nama)"), ("stok", "stok")];
+
+        assert_eq!(ListQuery::default().order_by(sort_columns, "id").unwrap(), None);
+        assert_eq!(ListQuery::default().limit_clause(), "");
+
+        let query = ListQuery { sort: Some("nama_desc".to_string()), page: Some(2), limit: Some(2), ..Default::default() };
+        assert_eq!(query.order_by(sort_columns, "id").unwrap().as_deref(), Some("LOWER(nama) DESC, id"));
+        assert_eq!(query.limit_clause(), " LIMIT 2 OFFSET 2");
+
+        let query = ListQuery { sort: Some("stok".to_string()), limit: Some(1000), ..Default::default() };
+        assert_eq!(query.order_by(sort_columns, "id").unwrap().as_deref(), Some("stok ASC, id"));
+        assert_eq!(query.limit_clause(), format!(" LIMIT {} OFFSET 0", MAX_LIMIT));
+
+        let query = ListQuery { sort: Some("harga; DROP TABLE produk".to_string()), ..Default::default() };
+        assert!(query.order_by(sort_columns, "id").is_err());
+    }
+}
@@ -0,0 +1,135 @@
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::Header;
+use rocket::request::{FromRequest, Outcome};
+use rocket::{Data, Request, Response};
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+pub const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Id korelasi satu request. Dipakai dari header `X-Request-Id` bila valid, selain itu
+/// dibuat baru (UUID v4). Bisa diambil sebagai guard supaya log di handler ikut mencantumkannya.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    /// Id dari klien hanya dipakai bila aman ditulis ke log (tanpa spasi/kontrol, panjang terbatas).
+    fn from_header(value: Option<&str>) -> Self {
+        match value.map(str::trim) {
+            Some(id) if !id.is_empty()
+                && id.len() <= MAX_REQUEST_ID_LEN
+                && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':')) => {
+                RequestId(id.to_string())
+            }
+            _ => RequestId(Uuid::new_v4().to_string()),
+        }
+    }
+
+    pub fn of<'r>(req: &'r Request<'_>) -> &'r RequestId {
+        req.local_cache(|| RequestId::from_header(req.headers().get_one(REQUEST_ID_HEADER)))
+    }
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RequestId {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(RequestId::of(req).clone())
+    }
+}
+
+/// Menetapkan `RequestId` di awal request, mengirimkannya balik di header respons, dan
+/// mencatat setiap respons dengan id tersebut (5xx sebagai error, 4xx sebagai warning).
+pub struct RequestIdFairing;
+
+#[rocket::async_trait]
+impl Fairing for RequestIdFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request ID",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _data: &mut Data<'_>) {
+        let request_id = RequestId::of(req);
+        log::debug!("[{}] {} {}", request_id, req.method(), req.uri());
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let request_id = RequestId::of(req);
+        res.set_header(Header::new(REQUEST_ID_HEADER, request_id.0.clone()));
+
+        let status = res.status();
+        if status.code >= 500 {
+            log::error!("[{}] {} {} -> {}", request_id, req.method(), req.uri(), status);
+        } else if status.code >= 400 {
+            log::warn!("[{}] {} {} -> {}", request_id, req.method(), req.uri(), status);
+        } else {
+            log::info!("[{}] {} {} -> {}", request_id, req.method(), req.uri(), status);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::get;
+    use rocket::http::Status;
+    use rocket::local::asynchronous::Client;
+
+    #[get("/ping")]
+    fn ping(request_id: RequestId) -> String {
+        request_id.0
+    }
+
+    async fn client() -> Client {
+        let rocket = rocket::build()
+            .attach(RequestIdFairing)
+            .mount("/", rocket::routes![ping]);
+        Client::tracked(rocket).await.expect("valid rocket instance")
+    }
+
+    #[rocket::async_test]
+    async fn test_response_carries_generated_request_id() {
+        let client = client().await;
+
+        let response = client.get("/ping").dispatch().await;
+        let header = response.headers().get_one(REQUEST_ID_HEADER).unwrap().to_string();
+        assert!(Uuid::parse_str(&header).is_ok());
+        assert_eq!(response.into_string().await.unwrap(), header);
+
+        let other = client.get("/ping").dispatch().await;
+        assert_ne!(other.headers().get_one(REQUEST_ID_HEADER).unwrap(), header);
+
+        let missing = client.get("/missing").dispatch().await;
+        assert_eq!(missing.status(), Status::NotFound);
+        assert!(missing.headers().get_one(REQUEST_ID_HEADER).is_some());
+    }
+
+    #[rocket::async_test]
+    async fn test_client_supplied_request_id_is_preserved() {
+        let client = client().await;
+
+        let response = client.get("/ping")
+            .header(Header::new(REQUEST_ID_HEADER, "trace-abc.123"))
+            .dispatch()
+            .await;
+        assert_eq!(response.headers().get_one(REQUEST_ID_HEADER), Some("trace-abc.123"));
+        assert_eq!(response.into_string().await.unwrap(), "trace-abc.123");
+
+        let unsafe_id = client.get("/ping")
+            .header(Header::new(REQUEST_ID_HEADER, "bad id\nINJECTED"))
+            .dispatch()
+            .await;
+        let replaced = unsafe_id.headers().get_one(REQUEST_ID_HEADER).unwrap();
+        assert!(Uuid::parse_str(replaced).is_ok());
+    }
+}
@@ -0,0 +1,119 @@
+// Responder bersama untuk handler yang hanya meneruskan hasil service: status HTTP diambil
+// dari varian error, envelope `{success, message, data}` diisi di sini. Error 5xx dicatat
+// bersama `RequestId`-nya.
+
+use std::fmt;
+
+use rocket::http::Status;
+use rocket::response::{self, Responder};
+use rocket::serde::json::Json;
+use rocket::serde::Serialize;
+use rocket::Request;
+
+use crate::common::request_id::RequestId;
+
+/// Error service yang tahu status HTTP-nya sendiri.
+pub trait ServiceError: fmt::Display {
+    fn status(&self) -> Status;
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+struct Envelope<T> {
+    success: bool,
+    message: String,
+    data: Option<T>,
+}
+
+pub struct ServiceResult<T, E> {
+    result: Result<T, E>,
+    status: Status,
+    message: String,
+    context: Option<&'static str>,
+}
+
+impl<T, E> ServiceResult<T, E> {
+    /// `message` dipakai saat sukses; status sukses default 200.
+    pub fn new(result: Result<T, E>, message: impl Into<String>) -> Self {
+        Self { result, status: Status::Ok, message: message.into(), context: None }
+    }
+
+    pub fn with_status(mut self, status: Status) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Awalan pesan untuk error 5xx (mis. `"Failed to add installment"`);
+    /// error 4xx dikirim apa adanya karena pesannya sudah ditujukan ke klien.
+    pub fn context(mut self, context: &'static str) -> Self {
+        self.context = Some(context);
+        self
+    }
+}
+
+impl<'r, T: Serialize, E: ServiceError> Responder<'r, 'static> for ServiceResult<T, E> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let (status, envelope) = match self.result {
+            Ok(data) => (self.status, Envelope { success: true, message: self.message, data: Some(data) }),
+            Err(error) => {
+                let status = error.status();
+                let message = match self.context {
+                    Some(context) if status.code >= 500 => format!("{context}: {error}"),
+                    _ => error.to_string(),
+                };
+                if status.code >= 500 {
+                    log::error!("[{}] {}", RequestId::of(req), message);
+                }
+                (status, Envelope { success: false, message, data: None })
+            }
+        };
+        (status, Json(envelope)).respond_to(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::local::asynchronous::Client;
+    use rocket::{get, routes};
+
+    #[derive(Debug)]
+    struct Gone;
+
+    impl fmt::Display for Gone {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "storage offline")
+        }
+    }
+
+    impl ServiceError for Gone {
+        fn status(&self) -> Status {
+            Status::ServiceUnavailable
+        }
+    }
+
+    #[get("/ok")]
+    fn ok_route() -> ServiceResult<u32, Gone> {
+        ServiceResult::new(Ok(7), "created").with_status(Status::Created)
+    }
+
+    #[get("/err")]
+    fn err_route() -> ServiceResult<u32, Gone> {
+        ServiceResult::new(Err(Gone), "unused").context("Failed to load")
+    }
+
+    #[rocket::async_test]
+    async fn test_service_result_uses_error_status_and_envelope() {
+        let client = Client::untracked(rocket::build().mount("/", routes![ok_route, err_route])).await.unwrap();
+
+        let response = client.get("/ok").dispatch().await;
+        assert_eq!(response.status(), Status::Created);
+        let body: serde_json::Value = response.into_json().await.unwrap();
+        assert_eq!(body, serde_json::json!({ "success": true, "message": "created", "data": 7 }));
+
+        let response = client.get("/err").dispatch().await;
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+        let body: serde_json::Value = response.into_json().await.unwrap();
+        assert_eq!(body, serde_json::json!({ "success": false, "message": "Failed to load: storage offline", "data": null }));
+    }
+}
@@ -0,0 +1,103 @@
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_SLOW_QUERY_THRESHOLD_MS: u64 = 100;
+
+/// Ambang query lambat, bisa diatur lewat env `SLOW_QUERY_THRESHOLD_MS` (default 100ms).
+pub fn slow_query_threshold() -> Duration {
+    let millis = std::env::var("SLOW_QUERY_THRESHOLD_MS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_SLOW_QUERY_THRESHOLD_MS);
+    Duration::from_millis(millis)
+}
+
+/// Menjalankan future query dan mencatat `log::warn!` jika durasinya melewati ambang.
+pub async fn timed_query<F, T>(label: &str, fut: F) -> T
+where
+    F: Future<Output = T>,
+{
+    let (output, _elapsed, _slow) = timed_query_with_threshold(label, slow_query_threshold(), fut).await;
+    output
+}
+
+pub async fn timed_query_with_threshold<F, T>(label: &str, threshold: Duration, fut: F) -> (T, Duration, bool)
+where
+    F: Future<Output = T>,
+{
+    #[cfg(test)]
+    query_counter::record(label);
+
+    let started = Instant::now();
+    let output = fut.await;
+    let elapsed = started.elapsed();
+
+    let slow = is_slow(elapsed, threshold);
+    if slow {
+        log::warn!(
+            "Slow query [{}] took {}ms (threshold {}ms)",
+            label,
+            elapsed.as_millis(),
+            threshold.as_millis()
+        );
+    }
+
+    (output, elapsed, slow)
+}
+
+pub fn is_slow(elapsed: Duration, threshold: Duration) -> bool {
+    elapsed > threshold
+}
+
+/// Penghitung eksekusi `timed_query` per label untuk test (mis. memastikan tidak ada N+1).
+/// Disimpan per thread; runtime `async_test` berjalan di satu thread sehingga test lain tidak ikut terhitung.
+#[cfg(test)]
+pub mod query_counter {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    thread_local! {
+        static COUNTS: RefCell<HashMap<String, usize>> = RefCell::new(HashMap::new());
+    }
+
+    pub fn record(label: &str) {
+        COUNTS.with(|counts| *counts.borrow_mut().entry(label.to_string()).or_insert(0) += 1);
+    }
+
+    pub fn count(label: &str) -> usize {
+        COUNTS.with(|counts| counts.borrow().get(label).copied().unwrap_or(0))
+    }
+
+    pub fn reset() {
+        COUNTS.with(|counts| counts.borrow_mut().clear());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_slow_threshold() {
+        let threshold = Duration::from_millis(100);
+        assert!(!is_slow(Duration::from_millis(99), threshold));
+        assert!(!is_slow(Duration::from_millis(100), threshold));
+        assert!(is_slow(Duration::from_millis(101), threshold));
+    }
+
+    #[tokio::test]
+    async fn test_timed_query_fast_and_slow_future() {
+        let (value, _, slow) = timed_query_with_threshold("fast", Duration::from_millis(200), async { 42 }).await;
+        assert_eq!(value, 42);
+        assert!(!slow);
+
+        let (value, elapsed, slow) = timed_query_with_threshold("slow", Duration::from_millis(5), async {
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            "done"
+        })
+        .await;
+        assert_eq!(value, "done");
+        assert!(elapsed >= Duration::from_millis(30));
+        assert!(slow);
+    }
+}
@@ -0,0 +1,90 @@
+use sqlx::AnyConnection;
+
+/// Jenis database di balik koneksi `Any`, untuk potongan SQL yang berbeda per driver.
+/// Driver selain PostgreSQL diperlakukan seperti SQLite (dipakai untuk test).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DbKind {
+    Postgres,
+    Sqlite,
+}
+
+impl DbKind {
+    pub fn from_backend_name(name: &str) -> Self {
+        if name.eq_ignore_ascii_case("PostgreSQL") {
+            DbKind::Postgres
+        } else {
+            DbKind::Sqlite
+        }
+    }
+
+    pub fn of(conn: &AnyConnection) -> Self {
+        Self::from_backend_name(conn.backend_name())
+    }
+}
+
+/// Satuan pengelompokan tanggal untuk laporan harian/bulanan.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateUnit {
+    Day,
+    Month,
+}
+
+/// Ekspresi SQL yang memotong kolom tanggal teks (RFC3339 atau `%Y-%m-%d %H:%M:%S`) ke
+/// awal hari/bulan dan mengembalikannya sebagai teks `YYYY-MM-DD` / `YYYY-MM`, sehingga
+/// hasilnya sama persis di kedua driver dan bisa dibaca sebagai `String`.
+/// `column` disisipkan apa adanya: hanya untuk nama kolom dari kode, bukan input pengguna.
+pub fn date_trunc_expr(kind: DbKind, unit: DateUnit, column: &str) -> String {
+    match (kind, unit) {
+        (DbKind::Postgres, DateUnit::Day) => format!("to_char(date_trunc('day', CAST({column} AS TIMESTAMP)), 'YYYY-MM-DD')"),
+        (DbKind::Postgres, DateUnit::Month) => format!("to_char(date_trunc('month', CAST({column} AS TIMESTAMP)), 'YYYY-MM')"),
+        (DbKind::Sqlite, DateUnit::Day) => format!("strftime('%Y-%m-%d', {column})"),
+        (DbKind::Sqlite, DateUnit::Month) => format!("strftime('%Y-%m', {column})"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::{Connection, Row};
+
+    #[test]
+    fn test_db_kind_from_backend_name() {
+        assert_eq!(DbKind::from_backend_name("PostgreSQL"), DbKind::Postgres);
+        assert_eq!(DbKind::from_backend_name("SQLite"), DbKind::Sqlite);
+    }
+
+    #[test]
+    fn test_date_trunc_expr_per_driver() {
+        assert_eq!(
+            date_trunc_expr(DbKind::Postgres, DateUnit::Day, "p.payment_date"),
+            "to_char(date_trunc('day', CAST(p.payment_date AS TIMESTAMP)), 'YYYY-MM-DD')"
+        );
+        assert_eq!(
+            date_trunc_expr(DbKind::Postgres, DateUnit::Month, "tanggal_transaksi"),
+            "to_char(date_trunc('month', CAST(tanggal_transaksi AS TIMESTAMP)), 'YYYY-MM')"
+        );
+        assert_eq!(date_trunc_expr(DbKind::Sqlite, DateUnit::Day, "p.payment_date"), "strftime('%Y-%m-%d', p.payment_date)");
+        assert_eq!(date_trunc_expr(DbKind::Sqlite, DateUnit::Month, "tanggal_transaksi"), "strftime('%Y-%m', tanggal_transaksi)");
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_date_trunc_handles_both_stored_formats() {
+        sqlx::any::install_default_drivers();
+        let mut conn = AnyConnection::connect("sqlite::memory:").await.unwrap();
+        assert_eq!(DbKind::of(&conn), DbKind::Sqlite);
+
+        for (value, day, month) in [
+            ("2024-03-31T23:15:00+00:00", "2024-03-31", "2024-03"),
+            ("2024-04-01 08:00:00", "2024-04-01", "2024-04"),
+        ] {
+            let sql = format!(
+                "SELECT {} AS day, {} AS month FROM (SELECT $1 AS tanggal) t",
+                date_trunc_expr(DbKind::Sqlite, DateUnit::Day, "tanggal"),
+                date_trunc_expr(DbKind::Sqlite, DateUnit::Month, "tanggal"),
+            );
+            let row = sqlx::query(&sql).bind(value).fetch_one(&mut conn).await.unwrap();
+            assert_eq!(row.get::<String, _>("day"), day);
+            assert_eq!(row.get::<String, _>("month"), month);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+// Format waktu di wire untuk semua modul: RFC3339 UTC dengan akhiran `Z`
+// (mis. `2024-01-01T10:00:00Z`). Dipakai lewat `#[serde(with = "...")]`.
+
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Deserializer, Serializer};
+
+/// Format kolom tanggal transaksi penjualan di database (UTC tanpa zona).
+pub const NAIVE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+pub fn format(value: &DateTime<Utc>) -> String {
+    value.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// Untuk kolom TEXT: presisi nanodetik tetap supaya urutan teks sama dengan urutan waktu.
+pub fn format_for_storage(value: &DateTime<Utc>) -> String {
+    value.to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+/// Menerima RFC3339 dengan offset apa pun, atau format naive `NAIVE_FORMAT`
+/// (pecahan detik opsional) yang dianggap UTC.
+pub fn parse(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    DateTime::parse_from_rfc3339(value)
+        .map(|parsed| parsed.with_timezone(&Utc))
+        .ok()
+        .or_else(|| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f").ok().map(|naive| naive.and_utc()))
+}
+
+fn parse_or_error<E: serde::de::Error>(value: &str) -> Result<DateTime<Utc>, E> {
+    parse(value).ok_or_else(|| E::custom(format!("invalid timestamp '{value}', expected RFC3339")))
+}
+
+pub mod rfc3339 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format(value))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+        parse_or_error(&String::deserialize(deserializer)?)
+    }
+}
+
+/// Pasangkan dengan `#[serde(default)]` supaya field yang tidak dikirim tetap `None`.
+pub mod option_rfc3339 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.serialize_some(&format(value)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|value| parse_or_error(&value))
+            .transpose()
+    }
+}
+
+/// Untuk field `String` berisi waktu yang disimpan apa adanya dari database
+/// (mis. `to_rfc3339()` dengan `+00:00`): ditulis ulang ke format wire saat serialisasi.
+/// Nilai yang tidak bisa di-parse dikirim apa adanya.
+pub mod rfc3339_string {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &str, serializer: S) -> Result<S::Ok, S::Error> {
+        match parse(value) {
+            Some(parsed) => serializer.serialize_str(&format(&parsed)),
+            None => serializer.serialize_str(value),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+        String::deserialize(deserializer)
+    }
+}
+
+/// Untuk field `String` yang di database memakai `NAIVE_FORMAT`: wire tetap RFC3339 `Z`,
+/// dan input RFC3339 dikembalikan ke `NAIVE_FORMAT` supaya filter tanggal tetap konsisten.
+pub mod naive_string {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &str, serializer: S) -> Result<S::Ok, S::Error> {
+        rfc3339_string::serialize(value, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Ok(parse(&value)
+            .map(|parsed| parsed.format(NAIVE_FORMAT).to_string())
+            .unwrap_or(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_accepts_offsets_and_naive_utc() {
+        let expected = Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap();
+        assert_eq!(parse("2024-01-01T10:00:00Z"), Some(expected));
+        assert_eq!(parse("2024-01-01T17:00:00+07:00"), Some(expected));
+        assert_eq!(parse("2024-01-01 10:00:00"), Some(expected));
+        assert_eq!(parse("2024-01-01 10:00:00.250"), Some(expected + chrono::Duration::milliseconds(250)));
+        assert_eq!(format_for_storage(&expected), "2024-01-01T10:00:00.000000000Z");
+        assert_eq!(parse("kemarin"), None);
+        assert_eq!(format(&expected), "2024-01-01T10:00:00Z");
+    }
+}
@@ -3,21 +3,20 @@ use rocket_cors::{AllowedOrigins, CorsOptions};
 use rocket_db_pools::Database;
 use buildingstore_be::{BuildingStoreDB};
 use dotenvy::dotenv;
+use env_logger::Env;
 use sqlx::any::install_default_drivers;
 use autometrics::prometheus_exporter;
+use manajemen_pembayaran::repository::payment_repository_trait::{payment_repository_for, use_optimized_payment_repo};
+use manajemen_pembayaran::service::payment_service::PaymentService;
 
 pub mod auth;
+pub mod common;
 pub mod manajemen_produk;
 pub mod manajemen_pelanggan;
 pub mod manajemen_pembayaran;
 pub mod transaksi_penjualan;
 pub mod manajemen_supplier;
 
-#[get("/")]
-fn index() -> &'static str {
-    "Hello, world!"
-}
-
 #[get("/metrics")]
 pub fn metrics() -> String {
     prometheus_exporter::encode_to_string().unwrap()
@@ -26,6 +25,9 @@ pub fn metrics() -> String {
 #[launch]
 async fn rocket() -> _ {
     dotenv().ok();
+    // env_logger menggantikan logger bawaan Rocket (Rocket hanya memasang logger-nya bila belum
+    // ada), supaya log sebelum ignite ikut tercatat. Default `info`; atur lewat `RUST_LOG`.
+    env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
     let production = std::env::var("PRODUCTION").unwrap_or_else(|_| "false".to_string()) == "true";
 
     // CORS Configuration
@@ -45,17 +47,26 @@ async fn rocket() -> _ {
 
     install_default_drivers();
     let database_url = dotenvy::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let db_pool = sqlx::AnyPool::connect(&database_url).await.unwrap();
+    let db_pool = common::db_pool::pool_options_from_env()
+        .connect(&database_url)
+        .await
+        .unwrap();
+    log::info!("Database health check: {:?}", common::db_pool::PoolHealthCheck::from_env());
     sqlx::migrate!()
         .run(&db_pool)
         .await
         .expect("Failed to run migrations");    
 
+    let payment_service = PaymentService::with_repository(payment_repository_for(use_optimized_payment_repo()));
+    log::info!("Payment repository: {}", payment_service.repository_name());
+
     rocket::build()
         .manage(reqwest::Client::builder().build().unwrap())
         .manage(db_pool)
         .manage(production)
+        .manage(payment_service)
         .attach(cors)
+        .attach(common::request_id::RequestIdFairing)
         .attach(BuildingStoreDB::init())
         .attach(auth::controller::route_stage())
         .attach(manajemen_pelanggan::controller::route_stage())
@@ -63,5 +74,6 @@ async fn rocket() -> _ {
         .attach(transaksi_penjualan::controller::route_stage())
         .attach(manajemen_supplier::controller::route_stage())
         .attach(manajemen_produk::controller::route_stage())
-        .mount("/", routes![index, metrics])
+        .mount("/", routes![common::api_index::index, metrics])
+        .register("/", catchers![common::catchers::payload_too_large])
 }
\ No newline at end of file
@@ -6,11 +6,19 @@ use rocket::serde::json::Json;
 use rocket::http::Status;
 use autometrics::autometrics;
 
-use crate::manajemen_pembayaran::model::payment::Payment;
-use crate::manajemen_pembayaran::service::payment_service::{PaymentService, PaymentError};
+use crate::manajemen_pembayaran::model::payment::{MonthlyRevenueReport, NextInstallment, Payment, PaymentAgingReport, PaymentMethod, RefundLedger};
+use crate::manajemen_pembayaran::enums::payment_status::PaymentStatus;
+use crate::manajemen_pembayaran::service::payment_service::{PaymentService, PaymentError, OverpaymentPolicy, pending_expiry_minutes};
+use crate::common::pagination::ListQuery;
+use crate::auth::guards::auth::AuthenticatedUser;
+use crate::common::audit::actor;
+use crate::common::service_result::ServiceResult;
+use crate::common::messages::{Lang, Messages};
+use crate::common::request_id::RequestId;
 use sqlx::{Any, Pool};
 
 #[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub struct CreatePaymentRequest {
     pub transaction_id: String,
     pub amount: f64,
@@ -19,6 +27,11 @@ pub struct CreatePaymentRequest {
     pub due_date: Option<String>,
 }
 
+#[derive(Deserialize)]
+pub struct UpdatePaymentMethodRequest {
+    pub method: String,
+}
+
 #[derive(Deserialize)]
 pub struct UpdatePaymentStatusRequest {
     pub new_status: String,
@@ -39,6 +52,23 @@ pub struct AddInstallmentRequest {
     pub amount: f64,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct RefundRequest {
+    pub amount: f64,
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PaymentCount {
+    pub count: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PaymentOption {
+    pub code: String,
+    pub label: String,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct ApiResponse<T> {
     pub success: bool,
@@ -46,10 +76,14 @@ pub struct ApiResponse<T> {
     pub data: Option<T>,
 }
 
+/// Pesan error 5xx: awalan terlokalisasi `code` lalu error aslinya, seperti `ServiceResult::context`.
+fn failed(code: &str, lang: Lang, error: &PaymentError) -> String {
+    format!("{}: {error}", Messages::get(code, lang))
+}
+
 #[autometrics]
 #[post("/payments", format = "json", data = "<payment_request>")]
-pub async fn create_payment(payment_request: Json<CreatePaymentRequest>, db: &State<Pool<Any>>) -> (Status, Json<ApiResponse<Payment>>) {
-    let payment_service = PaymentService::new();
+pub async fn create_payment(payment_request: Json<CreatePaymentRequest>, user: Option<AuthenticatedUser>, lang: Lang, request_id: RequestId, db: &State<Pool<Any>>, payment_service: &State<PaymentService>) -> (Status, Json<ApiResponse<Payment>>) {
     
     let method: crate::manajemen_pembayaran::model::payment::PaymentMethod = match payment_service.parse_payment_method(&payment_request.method) {
         Ok(m) => m,        Err(e) => {
@@ -57,7 +91,7 @@ pub async fn create_payment(payment_request: Json<CreatePaymentRequest>, db: &St
                 Status::BadRequest,
                 Json(ApiResponse {
                     success: false,
-                    message: format!("Invalid payment method: {e:?}"),
+                    message: e.to_string(),
                     data: None,
                 }),
             );
@@ -70,7 +104,7 @@ pub async fn create_payment(payment_request: Json<CreatePaymentRequest>, db: &St
                 Status::BadRequest,
                 Json(ApiResponse {
                     success: false,
-                    message: format!("Invalid payment status: {e:?}"),
+                    message: e.to_string(),
                     data: None,
                 }),
             );
@@ -84,7 +118,7 @@ pub async fn create_payment(payment_request: Json<CreatePaymentRequest>, db: &St
                     Status::BadRequest,
                     Json(ApiResponse {
                         success: false,
-                        message: "Invalid due date format. Use RFC3339 format".to_string(),
+                        message: Messages::get("payment.invalid_due_date", lang).to_string(),
                         data: None,
                     }),
                 );
@@ -102,66 +136,83 @@ pub async fn create_payment(payment_request: Json<CreatePaymentRequest>, db: &St
         payment_date: Utc::now(),
         installments: Vec::new(),
         due_date,
+        audit: Default::default(),
     };
     
-    match payment_service.create_payment(db, payment).await {
-        Ok(created_payment) => (
-            Status::Created,
-            Json(ApiResponse {
-                success: true,
-                message: "Payment created successfully".to_string(),
-                data: Some(created_payment),
-            }),
-        ),        Err(e) => (
-            Status::InternalServerError,
+    match payment_service.create_payment_by(db, payment, OverpaymentPolicy::from_env(), actor(user.as_ref())).await {
+        Ok((mut created_payment, overage)) => {
+            if let Some(overage) = overage {
+                log::warn!("[{}] Payment {} overpays transaction {} by {:.2}", request_id, created_payment.id, created_payment.transaction_id, overage);
+            }
+            created_payment.audit.redact_unless_admin(user.as_ref());
+            (
+                Status::Created,
+                Json(ApiResponse {
+                    success: true,
+                    message: match overage {
+                        Some(overage) => Messages::format("payment.created_overpaid", lang, &[&format!("{overage:.2}")]),
+                        None => Messages::get("payment.created", lang).to_string(),
+                    },
+                    data: Some(created_payment),
+                }),
+            )
+        },
+        Err(e @ PaymentError::Overpayment(_)) => (
+            Status::Conflict,
             Json(ApiResponse {
                 success: false,
-                message: format!("Failed to create payment: {e:?}"),
+                message: failed("payment.create_failed", lang, &e),
                 data: None,
             }),
         ),
-    }
-}
-
-#[autometrics]
-#[get("/payments/<id>")]
-pub async fn get_payment_by_id(id: String, db: &State<Pool<Any>>) -> (Status, Json<ApiResponse<Payment>>) {
-    let payment_service = PaymentService::new();
-    
-    match payment_service.get_payment_by_id(db, &id).await {
-        Ok(payment) => (
-            Status::Ok,
-            Json(ApiResponse {
-                success: true,
-                message: "Payment retrieved successfully".to_string(),
-                data: Some(payment),
-            }),
-        ),
-        Err(PaymentError::NotFound(msg)) => (
-            Status::NotFound,
+        Err(PaymentError::InvalidInput(msg)) => (
+            Status::BadRequest,
             Json(ApiResponse {
                 success: false,
                 message: msg,
                 data: None,
             }),
-        ),        Err(e) => (
-            Status::InternalServerError,            Json(ApiResponse {
-                success: false,
-                message: format!("Failed to retrieve payment: {e:?}"),
-                data: None,
-            }),
         ),
+        Err(e) => {
+            log::error!("[{}] Create payment failed: {}", request_id, e);
+            (
+                Status::InternalServerError,
+                Json(ApiResponse {
+                    success: false,
+                    message: failed("payment.create_failed", lang, &e),
+                    data: None,
+                }),
+            )
+        },
     }
 }
 
+#[autometrics]
+#[get("/payments/<id>?<fields>")]
+pub async fn get_payment_by_id(id: String, fields: Option<String>, user: Option<AuthenticatedUser>, lang: Lang, db: &State<Pool<Any>>, payment_service: &State<PaymentService>) -> ServiceResult<Payment, PaymentError> {
+    // `basic` melewati join cicilan; `full` (default) memuat cicilan
+    let result = match fields.as_deref().map(str::trim) {
+        None | Some("full") => payment_service.get_payment_by_id_with_installments(db, &id, true).await,
+        Some("basic") => payment_service.get_payment_by_id_with_installments(db, &id, false).await,
+        Some(other) => Err(PaymentError::InvalidInput(Messages::format("payment.invalid_fields", lang, &[&other]))),
+    };
+    let result = result.map(|mut payment| {
+        payment.audit.redact_unless_admin(user.as_ref());
+        payment
+    });
+    ServiceResult::new(result, Messages::get("payment.detail_ok", lang)).context(Messages::get("payment.detail_failed", lang))
+}
+
 #[autometrics]
 #[put("/payments/<id>", format = "json", data = "<update_request>")]
 pub async fn update_payment(
     id: String,
     update_request: Json<UpdatePaymentRequest>,
-    db: &State<Pool<Any>>
+    user: Option<AuthenticatedUser>,
+    lang: Lang,
+    db: &State<Pool<Any>>,
+    payment_service: &State<PaymentService>
 ) -> (Status, Json<ApiResponse<Payment>>) {
-    let payment_service = PaymentService::new();
     
     let method = match payment_service.parse_payment_method(&update_request.method) {
         Ok(m) => m,
@@ -170,7 +221,7 @@ pub async fn update_payment(
                 Status::BadRequest,
                 Json(ApiResponse {
                     success: false,
-                    message: format!("Invalid payment method: {e:?}"),
+                    message: e.to_string(),
                     data: None,
                 }),
             );
@@ -184,7 +235,7 @@ pub async fn update_payment(
                 Status::BadRequest,
                 Json(ApiResponse {
                     success: false,
-                    message: format!("Invalid payment status: {e:?}"),
+                    message: e.to_string(),
                     data: None,
                 }),
             );
@@ -199,7 +250,7 @@ pub async fn update_payment(
                     Status::BadRequest,
                     Json(ApiResponse {
                         success: false,
-                        message: "Invalid due date format. Use RFC3339 format.".to_string(),
+                        message: Messages::get("payment.invalid_due_date", lang).to_string(),
                         data: None,
                     }),
                 );
@@ -226,7 +277,7 @@ pub async fn update_payment(
                 Status::InternalServerError,
                 Json(ApiResponse {
                     success: false,
-                    message: format!("Failed to retrieve payment: {e:?}"),
+                    message: failed("payment.detail_failed", lang, &e),
                     data: None,
                 }),
             );
@@ -242,19 +293,31 @@ pub async fn update_payment(
         payment_date: current_payment.payment_date,
         installments: current_payment.installments,
         due_date,
+        audit: current_payment.audit,
     };
     
-    match payment_service.update_payment(db, updated_payment).await {
-        Ok(updated_payment) => (
-            Status::Ok,
+    match payment_service.update_payment_by(db, updated_payment, actor(user.as_ref())).await {
+        Ok(mut updated_payment) => {
+            updated_payment.audit.redact_unless_admin(user.as_ref());
+            (
+                Status::Ok,
+                Json(ApiResponse {
+                    success: true,
+                    message: Messages::get("payment.updated", lang).to_string(),
+                    data: Some(updated_payment),
+                }),
+            )
+        },
+        Err(PaymentError::NotFound(msg)) => (
+            Status::NotFound,
             Json(ApiResponse {
-                success: true,
-                message: "Payment updated successfully".to_string(),
-                data: Some(updated_payment),
+                success: false,
+                message: msg,
+                data: None,
             }),
         ),
-        Err(PaymentError::NotFound(msg)) => (
-            Status::NotFound,
+        Err(PaymentError::InvalidInput(msg)) => (
+            Status::BadRequest,
             Json(ApiResponse {
                 success: false,
                 message: msg,
@@ -265,122 +328,279 @@ pub async fn update_payment(
             Status::InternalServerError,
             Json(ApiResponse {
                 success: false,
-                message: format!("Failed to update payment: {e:?}"),
+                message: failed("payment.update_failed", lang, &e),
                 data: None,
             }),
         ),
     }
 }
 
+/// Filter query listing/count menjadi map untuk `build_filter_clause`; `None` bila tanpa filter.
+fn payment_filters(status: Option<String>, method: Option<String>, transaction_id: Option<String>, created_by: Option<String>) -> Option<HashMap<String, String>> {
+    let filters: HashMap<String, String> = [("status", status), ("method", method), ("transaction_id", transaction_id), ("created_by", created_by)]
+        .into_iter()
+        .filter_map(|(key, value)| value.map(|value| (key.to_string(), value)))
+        .collect();
+    if filters.is_empty() { None } else { Some(filters) }
+}
+
 #[autometrics]
-#[get("/payments?<status>&<method>&<transaction_id>")]
+#[get("/payments?<status>&<method>&<transaction_id>&<created_by>&<with_installments>&<list..>")]
 pub async fn get_all_payments(
     status: Option<String>,
     method: Option<String>,
     transaction_id: Option<String>,
-    db: &State<Pool<Any>>
+    created_by: Option<String>,
+    with_installments: Option<bool>,
+    list: ListQuery,
+    user: Option<AuthenticatedUser>,
+    lang: Lang,
+    db: &State<Pool<Any>>,
+    payment_service: &State<PaymentService>
 ) -> (Status, Json<ApiResponse<Vec<Payment>>>) {
-    let payment_service = PaymentService::new();
-    
-    let mut filters = HashMap::new();
-    if let Some(status_str) = status {
-        filters.insert("status".to_string(), status_str);
-    }
-    if let Some(method_str) = method {
-        filters.insert("method".to_string(), method_str);
+    // ?created_by= untuk rekonsiliasi shift kasir: hanya admin atau kasir itu sendiri
+    let created_by = created_by.map(|username| username.trim().to_string()).filter(|username| !username.is_empty());
+    let allowed = |username: &String| user.as_ref().is_some_and(|user| user.is_admin || &user.username == username);
+    if created_by.as_ref().is_some_and(|username| !allowed(username)) {
+        return (
+            Status::Forbidden,
+            Json(ApiResponse {
+                success: false,
+                message: Messages::get("payment.created_by_forbidden", lang).to_string(),
+                data: None,
+            }),
+        );
     }
-    if let Some(tx_id) = transaction_id {
-        filters.insert("transaction_id".to_string(), tx_id);
+    let mut filters_option = payment_filters(status, method, transaction_id, created_by);
+    if let Some(keyword) = list.keyword() {
+        filters_option.get_or_insert_with(HashMap::new).insert("keyword".to_string(), keyword);
     }
     
-    let filters_option = if filters.is_empty() { None } else { Some(filters) };
-    
-    match payment_service.get_all_payments(db, filters_option).await {
-        Ok(payments) => (
-            Status::Ok,
+    // Default memuat cicilan; ?with_installments=false untuk listing ringan
+    match payment_service.get_all_payments(db, filters_option, with_installments.unwrap_or(true), &list).await {
+        Err(PaymentError::InvalidInput(message)) => (
+            Status::BadRequest,
             Json(ApiResponse {
-                success: true,
-                message: format!("Successfully retrieved {} payments", payments.len()),
-                data: Some(payments),
+                success: false,
+                message,
+                data: None,
             }),
-        ),        Err(e) => (
+        ),
+        Ok(mut payments) => {
+            payments.iter_mut().for_each(|payment| payment.audit.redact_unless_admin(user.as_ref()));
+            (
+                Status::Ok,
+                Json(ApiResponse {
+                    success: true,
+                    message: Messages::format("payment.list_ok", lang, &[&payments.len()]),
+                    data: Some(payments),
+                }),
+            )
+        },
+        Err(e) => (
             Status::InternalServerError,
             Json(ApiResponse {
                 success: false,
-                message: format!("Failed to retrieve payments: {e:?}"),
+                message: failed("payment.list_failed", lang, &e),
                 data: None,
             }),
         ),
     }
 }
 
+/// Hanya jumlah payment untuk dashboard; filter sama dengan `GET /payments`.
+#[autometrics]
+#[get("/payments/count?<status>&<method>&<transaction_id>")]
+pub async fn count_payments(
+    status: Option<String>,
+    method: Option<String>,
+    transaction_id: Option<String>,
+    lang: Lang,
+    db: &State<Pool<Any>>,
+    payment_service: &State<PaymentService>
+) -> ServiceResult<PaymentCount, PaymentError> {
+    let result = payment_service.count_payments(db, payment_filters(status, method, transaction_id, None)).await
+        .map(|count| PaymentCount { count });
+    ServiceResult::new(result, Messages::get("payment.count_ok", lang)).context(Messages::get("payment.count_failed", lang))
+}
+
+/// Kandidat payment untuk mencocokkan satu baris mutasi bank; nominal terdekat lebih dulu.
+#[autometrics]
+#[get("/payments/match?<method>&<date_from>&<date_to>&<amount>")]
+pub async fn match_payments(
+    method: Option<String>,
+    date_from: Option<String>,
+    date_to: Option<String>,
+    amount: Option<f64>,
+    user: Option<AuthenticatedUser>,
+    lang: Lang,
+    db: &State<Pool<Any>>,
+    payment_service: &State<PaymentService>
+) -> ServiceResult<Vec<Payment>, PaymentError> {
+    let result = payment_service
+        .find_statement_matches(db, method.as_deref(), date_from.as_deref(), date_to.as_deref(), amount)
+        .await
+        .map(|mut payments| {
+            payments.iter_mut().for_each(|payment| payment.audit.redact_unless_admin(user.as_ref()));
+            payments
+        });
+    ServiceResult::new(result, Messages::get("payment.match_ok", lang)).context(Messages::get("payment.match_failed", lang))
+}
 
 #[autometrics]
 #[put("/payments/<id>/status", format = "json", data = "<status_request>")]
 pub async fn update_payment_status(
     id: String,
     status_request: Json<UpdatePaymentStatusRequest>,
-    db: &State<Pool<Any>>
-) -> (Status, Json<ApiResponse<Payment>>) {
-    let payment_service = PaymentService::new();
-      let new_status = match payment_service.parse_payment_status(&status_request.new_status) {
-        Ok(s) => s,
-        Err(e) => {
-            return (
-                Status::BadRequest,
-                Json(ApiResponse {
-                    success: false,
-                    message: format!("Invalid payment status: {e:?}"),
-                    data: None,
-                }),
-            );
-        }
+    user: Option<AuthenticatedUser>,
+    lang: Lang,
+    db: &State<Pool<Any>>,
+    payment_service: &State<PaymentService>
+) -> ServiceResult<Payment, PaymentError> {
+    let result = match payment_service.parse_payment_status(&status_request.new_status) {
+        Ok(new_status) => payment_service
+            .update_payment_status_by(db, id, new_status, status_request.additional_amount, actor(user.as_ref()))
+            .await,
+        Err(e) => Err(e),
     };
-    
-    match payment_service.update_payment_status(db, id, new_status, status_request.additional_amount).await {
-        Ok(updated_payment) => (
-            Status::Ok,
-            Json(ApiResponse {
-                success: true,
-                message: "Payment status updated successfully".to_string(),
-                data: Some(updated_payment),
-            }),
-        ),
-        Err(PaymentError::NotFound(msg)) => (
-            Status::NotFound,
-            Json(ApiResponse {
-                success: false,
-                message: msg,
-                data: None,
-            }),
-        ),        Err(e) => (
-            Status::InternalServerError,
-            Json(ApiResponse {
-                success: false,
-                message: format!("Failed to update payment status: {e:?}"),
-                data: None,
-            }),
-        ),
-    }
+    let result = result.map(|mut updated_payment| {
+        updated_payment.audit.redact_unless_admin(user.as_ref());
+        updated_payment
+    });
+    ServiceResult::new(result, Messages::get("payment.status_updated", lang)).context(Messages::get("payment.status_update_failed", lang))
 }
 
 
+#[autometrics]
+#[put("/payments/<id>/method", format = "json", data = "<method_request>")]
+pub async fn update_payment_method(
+    id: String,
+    method_request: Json<UpdatePaymentMethodRequest>,
+    user: Option<AuthenticatedUser>,
+    lang: Lang,
+    db: &State<Pool<Any>>,
+    payment_service: &State<PaymentService>
+) -> ServiceResult<Payment, PaymentError> {
+    let result = match payment_service.parse_payment_method(&method_request.method) {
+        Ok(method) => payment_service.update_payment_method(db, &id, method, actor(user.as_ref())).await,
+        Err(e) => Err(e),
+    };
+    let result = result.map(|mut updated_payment| {
+        updated_payment.audit.redact_unless_admin(user.as_ref());
+        updated_payment
+    });
+    ServiceResult::new(result, Messages::get("payment.method_updated", lang)).context(Messages::get("payment.method_update_failed", lang))
+}
+
 #[autometrics]
 #[post("/payments/<id>/installments", format = "json", data = "<installment_request>")]
 pub async fn add_installment(
     id: String,
     installment_request: Json<AddInstallmentRequest>,
-    db: &State<Pool<Any>>
-) -> (Status, Json<ApiResponse<Payment>>) {
-    let payment_service = PaymentService::new();
-    
-    match payment_service.add_installment(db, &id, installment_request.amount).await {
-        Ok(updated_payment) => (
+    user: Option<AuthenticatedUser>,
+    lang: Lang,
+    db: &State<Pool<Any>>,
+    payment_service: &State<PaymentService>
+) -> ServiceResult<Payment, PaymentError> {
+    let result = payment_service
+        .add_installment_by(db, &id, installment_request.amount, actor(user.as_ref()))
+        .await
+        .map(|mut updated_payment| {
+            updated_payment.audit.redact_unless_admin(user.as_ref());
+            updated_payment
+        });
+    ServiceResult::new(result, Messages::get("payment.installment_added", lang)).context(Messages::get("payment.installment_add_failed", lang))
+}
+
+/// Cicilan berikutnya yang belum dibayar; `data` bernilai null bila payment sudah lunas.
+#[autometrics]
+#[get("/payments/<id>/next-installment", rank = 2)]
+pub async fn get_next_installment(
+    id: String,
+    lang: Lang,
+    db: &State<Pool<Any>>,
+    payment_service: &State<PaymentService>
+) -> ServiceResult<Option<NextInstallment>, PaymentError> {
+    let result = payment_service.get_next_installment(db, &id).await;
+    ServiceResult::new(result, Messages::get("payment.next_installment_ok", lang)).context(Messages::get("payment.next_installment_failed", lang))
+}
+
+// Sama seperti field audit payment, pencatat refund hanya ditampilkan ke admin
+fn redact_refund_ledger(mut ledger: RefundLedger, user: Option<&AuthenticatedUser>) -> RefundLedger {
+    if !user.is_some_and(|user| user.is_admin) {
+        for refund in &mut ledger.refunds {
+            refund.created_by = None;
+        }
+    }
+    ledger
+}
+
+#[autometrics]
+#[post("/payments/<id>/refunds", format = "json", data = "<refund_request>")]
+pub async fn refund_payment(
+    id: String,
+    refund_request: Json<RefundRequest>,
+    user: Option<AuthenticatedUser>,
+    lang: Lang,
+    db: &State<Pool<Any>>,
+    payment_service: &State<PaymentService>
+) -> ServiceResult<RefundLedger, PaymentError> {
+    let result = payment_service
+        .refund_payment(db, &id, refund_request.amount, &refund_request.reason, actor(user.as_ref()))
+        .await
+        .map(|ledger| redact_refund_ledger(ledger, user.as_ref()));
+    ServiceResult::new(result, Messages::get("payment.refund_recorded", lang))
+        .with_status(Status::Created)
+        .context(Messages::get("payment.refund_failed", lang))
+}
+
+// rank 2: `/payments/by-transaction/<transaction_id>` juga cocok dengan path ini
+#[autometrics]
+#[get("/payments/<id>/refunds", rank = 2)]
+pub async fn get_payment_refunds(
+    id: String,
+    user: Option<AuthenticatedUser>,
+    lang: Lang,
+    db: &State<Pool<Any>>,
+    payment_service: &State<PaymentService>
+) -> ServiceResult<RefundLedger, PaymentError> {
+    let result = payment_service
+        .get_refund_ledger(db, &id)
+        .await
+        .map(|ledger| redact_refund_ledger(ledger, user.as_ref()));
+    ServiceResult::new(result, Messages::get("payment.refunds_ok", lang)).context(Messages::get("payment.refunds_failed", lang))
+}
+
+
+#[autometrics]
+#[delete("/payments/<id>?<force>")]
+pub async fn delete_payment(
+    id: String,
+    force: Option<bool>,
+    user: Option<AuthenticatedUser>,
+    lang: Lang,
+    db: &State<Pool<Any>>,
+    payment_service: &State<PaymentService>
+) -> (Status, Json<ApiResponse<()>>) {
+    let force = force.unwrap_or(false);
+    if force && !user.is_some_and(|user| user.is_admin) {
+        return (
+            Status::Forbidden,
+            Json(ApiResponse {
+                success: false,
+                message: Messages::get("payment.force_delete_forbidden", lang).to_string(),
+                data: None,
+            }),
+        );
+    }
+
+    match payment_service.delete_payment_with_force(db, &id, force).await {
+        Ok(_) => (
             Status::Ok,
             Json(ApiResponse {
                 success: true,
-                message: "Installment added successfully".to_string(),
-                data: Some(updated_payment),
+                message: Messages::get("payment.deleted", lang).to_string(),
+                data: None,
             }),
         ),
         Err(PaymentError::NotFound(msg)) => (
@@ -391,47 +611,119 @@ pub async fn add_installment(
                 data: None,
             }),
         ),
-        Err(PaymentError::InvalidInput(msg)) => (
-            Status::BadRequest,
+        Err(PaymentError::Conflict(msg)) => (
+            Status::Conflict,
             Json(ApiResponse {
                 success: false,
                 message: msg,
                 data: None,
             }),
-        ),        Err(e) => (
+        ),
+        Err(e) => (
             Status::InternalServerError,
             Json(ApiResponse {
                 success: false,
-                message: format!("Failed to add installment: {e:?}"),
+                message: failed("payment.delete_failed", lang, &e),
                 data: None,
             }),
         ),
     }
 }
 
+#[autometrics]
+#[get("/payments/by-transaction/<transaction_id>")]
+pub async fn get_payments_by_transaction(
+    transaction_id: String,
+    user: Option<AuthenticatedUser>,
+    lang: Lang,
+    db: &State<Pool<Any>>,
+    payment_service: &State<PaymentService>
+) -> ServiceResult<Vec<Payment>, PaymentError> {
+    let result = payment_service.get_payments_by_transaction(db, &transaction_id).await
+        .map(|mut payments| {
+            payments.iter_mut().for_each(|payment| payment.audit.redact_unless_admin(user.as_ref()));
+            payments
+        });
+    let count = result.as_ref().map_or(0, Vec::len);
+    ServiceResult::new(result, Messages::format("payment.by_transaction_ok", lang, &[&count, &transaction_id]))
+        .context(Messages::get("payment.list_failed", lang))
+}
 
 #[autometrics]
-#[delete("/payments/<id>")]
-pub async fn delete_payment(id: String, db: &State<Pool<Any>>) -> (Status, Json<ApiResponse<()>>) {
-    let payment_service = PaymentService::new();
-    
-    match payment_service.delete_payment(db, &id).await {
-        Ok(_) => (
-            Status::Ok,
-            Json(ApiResponse {
-                success: true,
-                message: "Payment deleted successfully".to_string(),
-                data: None,
-            }),
-        ),        Err(e) => (
-            Status::InternalServerError,
-            Json(ApiResponse {
-                success: false,
-                message: format!("Failed to delete payment: {e:?}"),
-                data: None,
-            }),
-        ),
-    }
+#[post("/payments/expire-stale?<minutes>")]
+pub async fn expire_stale_payments(
+    minutes: Option<i64>,
+    user: AuthenticatedUser,
+    lang: Lang,
+    db: &State<Pool<Any>>,
+    payment_service: &State<PaymentService>
+) -> ServiceResult<u64, PaymentError> {
+    let minutes = minutes.unwrap_or_else(pending_expiry_minutes);
+    let result = if !user.is_admin {
+        Err(PaymentError::Forbidden(Messages::get("payment.expire_forbidden", lang).to_string()))
+    } else {
+        match chrono::Duration::try_minutes(minutes) {
+            Some(older_than) => payment_service.expire_stale_pending(db, older_than).await,
+            None => Err(PaymentError::InvalidInput(format!("Expiry age of {minutes} minutes is out of range"))),
+        }
+    };
+    let expired = result.as_ref().copied().unwrap_or(0);
+    ServiceResult::new(result, Messages::format("payment.expired", lang, &[&expired, &minutes]))
+        .context(Messages::get("payment.expire_failed", lang))
+}
+
+#[autometrics]
+#[get("/reports/payment-aging")]
+pub async fn get_payment_aging_report(
+    lang: Lang,
+    db: &State<Pool<Any>>,
+    payment_service: &State<PaymentService>
+) -> ServiceResult<PaymentAgingReport, PaymentError> {
+    let result = payment_service.payment_aging_report(db).await;
+    ServiceResult::new(result, Messages::get("payment.aging_ok", lang)).context(Messages::get("payment.aging_failed", lang))
+}
+
+/// Jumlah dan total payment per bulan kalender untuk `year` (default tahun berjalan), 12 bulan penuh.
+#[autometrics]
+#[get("/payments/report/monthly?<year>")]
+pub async fn get_monthly_revenue_report(
+    year: Option<i32>,
+    lang: Lang,
+    db: &State<Pool<Any>>,
+    payment_service: &State<PaymentService>
+) -> ServiceResult<MonthlyRevenueReport, PaymentError> {
+    let result = payment_service.monthly_revenue_report(db, year).await;
+    ServiceResult::new(result, Messages::get("payment.monthly_ok", lang)).context(Messages::get("payment.monthly_failed", lang))
+}
+
+#[autometrics]
+#[get("/payments/methods")]
+pub async fn get_payment_methods(lang: Lang) -> Json<ApiResponse<Vec<PaymentOption>>> {
+    let methods = PaymentMethod::ALL
+        .iter()
+        .map(|method| PaymentOption { code: method.to_string(), label: method.label().to_string() })
+        .collect();
+
+    Json(ApiResponse {
+        success: true,
+        message: Messages::get("payment.methods_ok", lang).to_string(),
+        data: Some(methods),
+    })
+}
+
+#[autometrics]
+#[get("/payments/statuses")]
+pub async fn get_payment_statuses(lang: Lang) -> Json<ApiResponse<Vec<PaymentOption>>> {
+    let statuses = PaymentStatus::ALL
+        .iter()
+        .map(|status| PaymentOption { code: status.to_string(), label: status.label().to_string() })
+        .collect();
+
+    Json(ApiResponse {
+        success: true,
+        message: Messages::get("payment.statuses_ok", lang).to_string(),
+        data: Some(statuses),
+    })
 }
 
 #[derive(Deserialize)]
@@ -447,9 +739,21 @@ pub fn routes() -> Vec<Route> {
         get_payment_by_id,
         update_payment,
         get_all_payments,
+        count_payments,
+        match_payments,
         update_payment_status,
+        update_payment_method,
         add_installment,
-        delete_payment
+        get_next_installment,
+        refund_payment,
+        get_payment_refunds,
+        delete_payment,
+        get_payment_methods,
+        get_payment_statuses,
+        get_payments_by_transaction,
+        expire_stale_payments,
+        get_payment_aging_report,
+        get_monthly_revenue_report
     ]
 }
 
@@ -475,6 +779,32 @@ pub fn bad_request_catcher() -> Json<ApiResponse<()>> {
 mod tests {
     use super::*;
     use chrono::{Utc};
+    use rocket::local::asynchronous::Client;
+    use crate::auth::controller::auth::login;
+
+    // Semua route payment plus login di atas sqlite in-memory yang sudah dimigrasi
+    async fn test_client() -> (Pool<Any>, Client) {
+        sqlx::any::install_default_drivers();
+        let db_pool = sqlx::any::AnyPoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!("migrations/test").run(&db_pool).await.unwrap();
+        let client = client_for(&db_pool).await;
+        (db_pool, client)
+    }
+
+    // Klien kedua dengan sesi sendiri di atas pool yang sama
+    async fn client_for(db_pool: &Pool<Any>) -> Client {
+        let rocket = rocket::build()
+            .manage(db_pool.clone())
+            .manage(false)
+            .manage(PaymentService::new())
+            .mount("/", routes())
+            .mount("/", rocket::routes![login]);
+        Client::tracked(rocket).await.expect("valid rocket instance")
+    }
     
     #[test]
     fn test_api_response_serialization() {
@@ -525,6 +855,624 @@ mod tests {
         assert!(request.due_date.is_some());
     }
 
+    #[rocket::async_test]
+    async fn test_payment_option_endpoints_list_every_variant() {
+        let service = PaymentService::new();
+
+        let Json(methods) = get_payment_methods(Lang::En).await;
+        let methods = methods.data.unwrap();
+        assert_eq!(methods.len(), PaymentMethod::ALL.len());
+        let codes: Vec<&str> = methods.iter().map(|m| m.code.as_str()).collect();
+        assert_eq!(codes, vec!["CASH", "CREDIT_CARD", "BANK_TRANSFER", "E_WALLET"]);
+        for option in &methods {
+            assert!(service.parse_payment_method(&option.code).is_ok(), "unparseable method {}", option.code);
+            assert!(!option.label.is_empty());
+        }
+
+        let Json(statuses) = get_payment_statuses(Lang::En).await;
+        let statuses = statuses.data.unwrap();
+        let codes: Vec<&str> = statuses.iter().map(|s| s.code.as_str()).collect();
+        assert_eq!(codes, vec!["LUNAS", "CICILAN", "MENUNGGU", "GAGAL"]);
+        assert!(statuses.iter().all(|s| PaymentStatus::from_string(&s.code).is_some()));
+    }
+
+    #[rocket::async_test]
+    async fn test_delete_payment_of_completed_transaction_requires_admin_force() {
+        use crate::auth::controller::auth::AuthForm;
+        use crate::auth::model::user::User;
+        use crate::auth::service::auth::AuthService;
+
+        let (db_pool, client) = test_client().await;
+        AuthService::register_user(db_pool.clone(), User::new("admin".to_string(), "admin123".to_string(), true))
+            .await
+            .unwrap();
+
+        let row = sqlx::query("
+            INSERT INTO transaksi (id_pelanggan, nama_pelanggan, tanggal_transaksi, total_harga, status, catatan, created_at, updated_at)
+            VALUES (1, 'Alice', '2024-01-01 10:00:00', 50000, 'SELESAI', '', '2024-01-01 10:00:00', '2024-01-01 10:00:00')
+            RETURNING id
+        ")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        let transaction_id: i32 = sqlx::Row::get(&row, "id");
+
+        let service = PaymentService::new();
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let settled = service.create_payment(db, Payment {
+            id: service.generate_payment_id(),
+            transaction_id: transaction_id.to_string(),
+            amount: 50000.0,
+            method: PaymentMethod::Cash,
+            status: PaymentStatus::Paid,
+            payment_date: Utc::now(),
+            installments: Vec::new(),
+            due_date: None,
+            audit: Default::default(),
+        }).await.unwrap();
+
+        let response = client.delete(format!("/payments/{}", settled.id)).dispatch().await;
+        assert_eq!(response.status(), Status::Conflict);
+
+        let response = client.delete(format!("/payments/{}?force=true", settled.id)).dispatch().await;
+        assert_eq!(response.status(), Status::Forbidden);
+        assert!(service.get_payment_by_id(db, &settled.id).await.is_ok());
+
+        client.post("/login")
+            .json(&AuthForm { username: "admin".to_string(), password: "admin123".to_string() })
+            .dispatch()
+            .await;
+        let response = client.delete(format!("/payments/{}?force=true", settled.id)).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert!(matches!(service.get_payment_by_id(db, &settled.id).await, Err(PaymentError::NotFound(_))));
+    }
+
+    #[rocket::async_test]
+    async fn test_delete_unknown_payment_returns_not_found() {
+
+        let (_, client) = test_client().await;
+
+        let response = client.delete("/payments/PMT-missing").dispatch().await;
+        assert_eq!(response.status(), Status::NotFound);
+        let body = response.into_json::<ApiResponse<()>>().await.unwrap();
+        assert!(!body.success);
+        assert_eq!(body.message, "Payment with id PMT-missing not found");
+
+        let response = client.post("/payments/PMT-missing/installments")
+            .header(rocket::http::ContentType::JSON)
+            .body(r#"{"amount": 1000}"#)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[rocket::async_test]
+    async fn test_payment_messages_follow_accept_language() {
+        use rocket::http::Header;
+
+        let (_, client) = test_client().await;
+
+        let response = client.get("/payments").dispatch().await;
+        assert_eq!(response.into_json::<ApiResponse<Vec<Payment>>>().await.unwrap().message, "Berhasil mengambil 0 payment");
+
+        let response = client.get("/payments").header(Header::new("Accept-Language", "en-US,en;q=0.9")).dispatch().await;
+        assert_eq!(response.into_json::<ApiResponse<Vec<Payment>>>().await.unwrap().message, "Successfully retrieved 0 payments");
+
+        let response = client.get("/payments/PMT-1?fields=raw").header(Header::new("Accept-Language", "en")).dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest);
+        let body = response.into_json::<ApiResponse<Payment>>().await.unwrap();
+        assert_eq!(body.message, "Invalid fields 'raw', expected 'basic' or 'full'");
+
+        let response = client.get("/payments/PMT-1?fields=raw").dispatch().await;
+        let body = response.into_json::<ApiResponse<Payment>>().await.unwrap();
+        assert_eq!(body.message, "fields 'raw' tidak valid, gunakan 'basic' atau 'full'");
+    }
+
+    #[rocket::async_test]
+    async fn test_create_payment_records_created_by_from_session() {
+        use crate::auth::controller::auth::AuthForm;
+        use crate::auth::model::user::User;
+        use crate::auth::service::auth::AuthService;
+
+        let (db_pool, client) = test_client().await;
+        AuthService::register_user(db_pool.clone(), User::new("admin".to_string(), "admin123".to_string(), true))
+            .await
+            .unwrap();
+
+        client.post("/login")
+            .json(&AuthForm { username: "admin".to_string(), password: "admin123".to_string() })
+            .dispatch()
+            .await;
+
+        let response = client.post("/payments")
+            .json(&serde_json::json!({
+                "transaction_id": "TRX-AUDIT",
+                "amount": 25000.0,
+                "method": "CASH",
+                "status": "LUNAS",
+                "due_date": null
+            }))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Created);
+        let body: serde_json::Value = response.into_json().await.unwrap();
+        assert_eq!(body["data"]["created_by"], "admin");
+        assert_eq!(body["data"]["updated_by"], "admin");
+
+        let payment_id = body["data"]["id"].as_str().unwrap().to_string();
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let stored = PaymentService::new().get_payment_by_id(db, &payment_id).await.unwrap();
+        assert_eq!(stored.audit.created_by.as_deref(), Some("admin"));
+
+        let anonymous = client_for(&db_pool).await;
+        let response = anonymous.get(format!("/payments/{}", payment_id)).dispatch().await;
+        let body: serde_json::Value = response.into_json().await.unwrap();
+        assert!(body["data"].get("created_by").is_none());
+
+        // Perubahan status dan cicilan mencatat pelakunya; respons ke non-admin tetap disensor
+        AuthService::register_user(db_pool.clone(), User::new("kasir1".to_string(), "kasir123".to_string(), false))
+            .await
+            .unwrap();
+        let kasir = client_for(&db_pool).await;
+        kasir.post("/login")
+            .json(&AuthForm { username: "kasir1".to_string(), password: "kasir123".to_string() })
+            .dispatch()
+            .await;
+
+        let response = client.post("/payments")
+            .json(&serde_json::json!({
+                "transaction_id": "TRX-AUDIT",
+                "amount": 40000.0,
+                "method": "CASH",
+                "status": "CICILAN",
+                "due_date": null
+            }))
+            .dispatch()
+            .await;
+        let body: serde_json::Value = response.into_json().await.unwrap();
+        let installment_id = body["data"]["id"].as_str().unwrap().to_string();
+
+        let response = kasir.put(format!("/payments/{}/status", payment_id))
+            .json(&serde_json::json!({ "new_status": "MENUNGGU", "additional_amount": null }))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let body: serde_json::Value = response.into_json().await.unwrap();
+        assert!(body["data"].get("created_by").is_none());
+        assert!(body["data"].get("updated_by").is_none());
+
+        let response = kasir.post(format!("/payments/{}/installments", installment_id))
+            .json(&serde_json::json!({ "amount": 10000.0 }))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let body: serde_json::Value = response.into_json().await.unwrap();
+        assert!(body["data"].get("updated_by").is_none());
+
+        for uri in ["/payments/by-transaction/TRX-AUDIT", "/payments/match?method=CASH"] {
+            let body: serde_json::Value = kasir.get(uri).dispatch().await.into_json().await.unwrap();
+            let payments = body["data"].as_array().unwrap();
+            assert_eq!(payments.len(), 2, "{}", uri);
+            assert!(payments.iter().all(|payment| payment.get("created_by").is_none()), "{}", uri);
+        }
+
+        let body: serde_json::Value = client.get("/payments/by-transaction/TRX-AUDIT").dispatch().await.into_json().await.unwrap();
+        let payments = body["data"].as_array().unwrap();
+        assert!(payments.iter().all(|payment| payment["created_by"] == "admin" && payment["updated_by"] == "kasir1"));
+        let body: serde_json::Value = client.get("/payments/match?method=CASH").dispatch().await.into_json().await.unwrap();
+        assert!(body["data"].as_array().unwrap().iter().all(|payment| payment["created_by"] == "admin"));
+    }
+
+    #[rocket::async_test]
+    async fn test_get_all_payments_filters_by_created_by_for_admin_or_same_user() {
+        use crate::auth::controller::auth::AuthForm;
+        use crate::auth::model::user::User;
+        use crate::auth::service::auth::AuthService;
+
+        let (db_pool, admin) = test_client().await;
+        let kasir = client_for(&db_pool).await;
+        for (client, username, password, is_admin) in [(&admin, "admin", "admin123", true), (&kasir, "kasir1", "kasir123", false)] {
+            AuthService::register_user(db_pool.clone(), User::new(username.to_string(), password.to_string(), is_admin))
+                .await
+                .unwrap();
+            client.post("/login")
+                .json(&AuthForm { username: username.to_string(), password: password.to_string() })
+                .dispatch()
+                .await;
+        }
+
+        for (client, transaction_id) in [(&admin, "TRX-SHIFT-1"), (&kasir, "TRX-SHIFT-2"), (&kasir, "TRX-SHIFT-3")] {
+            let response = client.post("/payments")
+                .json(&serde_json::json!({
+                    "transaction_id": transaction_id,
+                    "amount": 25000.0,
+                    "method": "CASH",
+                    "status": "LUNAS",
+                    "due_date": null
+                }))
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Created);
+        }
+
+        async fn list(client: &Client, uri: &str) -> Vec<String> {
+            let response = client.get(uri).dispatch().await;
+            assert_eq!(response.status(), Status::Ok);
+            let body: ApiResponse<Vec<Payment>> = response.into_json().await.unwrap();
+            let mut ids: Vec<String> = body.data.unwrap().into_iter().map(|payment| payment.transaction_id).collect();
+            ids.sort();
+            ids
+        }
+        assert_eq!(list(&kasir, "/payments?created_by=kasir1").await, vec!["TRX-SHIFT-2", "TRX-SHIFT-3"]);
+        assert_eq!(list(&admin, "/payments?created_by=kasir1").await, vec!["TRX-SHIFT-2", "TRX-SHIFT-3"]);
+        assert_eq!(list(&admin, "/payments?created_by=admin").await, vec!["TRX-SHIFT-1"]);
+
+        // Kasir lain dan pengguna anonim tidak boleh melihat pembayaran kasir lain
+        assert_eq!(kasir.get("/payments?created_by=admin").dispatch().await.status(), Status::Forbidden);
+        let anonymous = client_for(&db_pool).await;
+        assert_eq!(anonymous.get("/payments?created_by=kasir1").dispatch().await.status(), Status::Forbidden);
+    }
+
+    #[rocket::async_test]
+    async fn test_get_payments_by_transaction_returns_all_linked_payments() {
+
+        let (db_pool, client) = test_client().await;
+
+        let service = PaymentService::new();
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let payment = |transaction_id: &str, amount: f64, status: PaymentStatus| Payment {
+            id: service.generate_payment_id(),
+            transaction_id: transaction_id.to_string(),
+            amount,
+            method: PaymentMethod::Cash,
+            status,
+            payment_date: Utc::now(),
+            installments: Vec::new(),
+            due_date: None,
+            audit: Default::default(),
+        };
+        let down_payment = service.create_payment(db, payment("TRX-77", 50000.0, PaymentStatus::Paid)).await.unwrap();
+        let balance = service.create_payment(db, payment("TRX-77", 150000.0, PaymentStatus::Installment)).await.unwrap();
+        service.create_payment(db, payment("TRX-78", 10000.0, PaymentStatus::Paid)).await.unwrap();
+
+        let response = client.get("/payments/by-transaction/TRX-77").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let body: ApiResponse<Vec<Payment>> = response.into_json().await.unwrap();
+        let mut payments: Vec<(String, PaymentStatus)> = body.data.unwrap()
+            .into_iter()
+            .map(|p| (p.id, p.status))
+            .collect();
+        payments.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut expected = vec![(down_payment.id, PaymentStatus::Paid), (balance.id, PaymentStatus::Installment)];
+        expected.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(payments, expected);
+
+        let response = client.get("/payments/by-transaction/TRX-NONE").dispatch().await;
+        let body: ApiResponse<Vec<Payment>> = response.into_json().await.unwrap();
+        assert!(body.data.unwrap().is_empty());
+    }
+
+    #[rocket::async_test]
+    async fn test_count_payments_matches_seeded_data_under_filters() {
+
+        let (db_pool, client) = test_client().await;
+
+        let service = PaymentService::new();
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let payment = |amount: f64, method: PaymentMethod, status: PaymentStatus| Payment {
+            id: service.generate_payment_id(),
+            transaction_id: format!("TRX-{amount}"),
+            amount,
+            method,
+            status,
+            payment_date: Utc::now(),
+            installments: Vec::new(),
+            due_date: None,
+            audit: Default::default(),
+        };
+        service.create_payment(db, payment(10000.0, PaymentMethod::Cash, PaymentStatus::Paid)).await.unwrap();
+        service.create_payment(db, payment(20000.0, PaymentMethod::BankTransfer, PaymentStatus::Paid)).await.unwrap();
+        service.create_payment(db, payment(30000.0, PaymentMethod::Cash, PaymentStatus::Pending)).await.unwrap();
+
+        let count = |uri: &'static str| {
+            let client = &client;
+            async move {
+                let response = client.get(uri).dispatch().await;
+                assert_eq!(response.status(), Status::Ok);
+                response.into_json::<ApiResponse<PaymentCount>>().await.unwrap().data.unwrap().count
+            }
+        };
+        assert_eq!(count("/payments/count").await, 3);
+        assert_eq!(count("/payments/count?status=LUNAS").await, 2);
+        assert_eq!(count("/payments/count?status=LUNAS&method=CASH").await, 1);
+        assert_eq!(count("/payments/count?status=GAGAL").await, 0);
+    }
+
+    #[rocket::async_test]
+    async fn test_match_payments_finds_statement_line_among_seeded_payments() {
+
+        let (db_pool, client) = test_client().await;
+
+        let service = PaymentService::new();
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let payment = |transaction_id: &str, amount: f64, method: PaymentMethod, paid_at: &str| Payment {
+            id: service.generate_payment_id(),
+            transaction_id: transaction_id.to_string(),
+            amount,
+            method,
+            status: PaymentStatus::Paid,
+            payment_date: crate::common::timestamp::parse(paid_at).unwrap(),
+            installments: Vec::new(),
+            due_date: None,
+            audit: Default::default(),
+        };
+        let seeded = [
+            payment("TRX-MATCH", 150000.0, PaymentMethod::BankTransfer, "2024-03-10T09:00:00Z"),
+            payment("TRX-EARLY", 150000.0, PaymentMethod::BankTransfer, "2024-02-01T09:00:00Z"),
+            payment("TRX-CASH", 150000.0, PaymentMethod::Cash, "2024-03-10T10:00:00Z"),
+            payment("TRX-OTHER", 250000.0, PaymentMethod::BankTransfer, "2024-03-11T09:00:00Z"),
+            payment("TRX-CENT", 150000.01, PaymentMethod::BankTransfer, "2024-03-12T23:59:00Z"),
+        ];
+        for seed in seeded {
+            service.create_payment(db, seed).await.unwrap();
+        }
+
+        let matches = |uri: &'static str| {
+            let client = &client;
+            async move {
+                let response = client.get(uri).dispatch().await;
+                assert_eq!(response.status(), Status::Ok);
+                response.into_json::<ApiResponse<Vec<Payment>>>().await.unwrap().data.unwrap()
+                    .into_iter()
+                    .map(|payment| payment.transaction_id)
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(
+            matches("/payments/match?method=bank_transfer&date_from=2024-03-01&date_to=2024-03-12&amount=150000").await,
+            vec!["TRX-MATCH"]
+        );
+        assert_eq!(
+            matches("/payments/match?method=BANK_TRANSFER&date_from=2024-03-01&date_to=2024-03-31").await,
+            vec!["TRX-CENT", "TRX-OTHER", "TRX-MATCH"]
+        );
+
+        let invalid = client.get("/payments/match?date_from=10-03-2024").dispatch().await;
+        assert_eq!(invalid.status(), Status::BadRequest);
+        let invalid = client.get("/payments/match?method=CHEQUE").dispatch().await;
+        assert_eq!(invalid.status(), Status::BadRequest);
+    }
+
+    #[rocket::async_test]
+    async fn test_get_payment_by_id_fields_basic_and_full() {
+
+        let (db_pool, client) = test_client().await;
+
+        let service = PaymentService::new();
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let payment_id = service.generate_payment_id();
+        let payment = Payment {
+            id: payment_id.clone(),
+            transaction_id: "TRX-90".to_string(),
+            amount: 300000.0,
+            method: PaymentMethod::Cash,
+            status: PaymentStatus::Installment,
+            payment_date: Utc::now(),
+            installments: vec![crate::manajemen_pembayaran::model::payment::Installment {
+                id: "INST-90-1".to_string(),
+                payment_id: payment_id.clone(),
+                amount: 100000.0,
+                payment_date: Utc::now(),
+            }],
+            due_date: None,
+            audit: Default::default(),
+        };
+        service.create_payment(db, payment).await.unwrap();
+
+        for uri in [format!("/payments/{payment_id}"), format!("/payments/{payment_id}?fields=full")] {
+            let response = client.get(uri).dispatch().await;
+            assert_eq!(response.status(), Status::Ok);
+            let body: ApiResponse<Payment> = response.into_json().await.unwrap();
+            assert_eq!(body.data.unwrap().installments.len(), 1);
+        }
+
+        let response = client.get(format!("/payments/{payment_id}?fields=basic")).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let basic = response.into_json::<ApiResponse<Payment>>().await.unwrap().data.unwrap();
+        assert_eq!(basic.id, payment_id);
+        assert_eq!(basic.amount, 300000.0);
+        assert!(basic.installments.is_empty());
+
+        let response = client.get(format!("/payments/{payment_id}?fields=everything")).dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest);
+        let response = client.get("/payments/PMT-TIDAK-ADA?fields=basic").dispatch().await;
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[rocket::async_test]
+    async fn test_update_payment_method_changes_only_method_and_blocks_failed_and_refunded_payments() {
+
+        let (db_pool, client) = test_client().await;
+
+        let service = PaymentService::new();
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let payment = |status: PaymentStatus| Payment {
+            id: service.generate_payment_id(),
+            transaction_id: "TRX-METHOD".to_string(),
+            amount: 40000.0,
+            method: PaymentMethod::Cash,
+            status,
+            payment_date: Utc::now(),
+            installments: Vec::new(),
+            due_date: None,
+            audit: Default::default(),
+        };
+        let pending = service.create_payment(db, payment(PaymentStatus::Pending)).await.unwrap();
+        let failed = service.create_payment(db, payment(PaymentStatus::Failed)).await.unwrap();
+        let refunded = service.create_payment(db, payment(PaymentStatus::Paid)).await.unwrap();
+        service.refund_payment(db, &refunded.id, 5000.0, "Barang retur", None).await.unwrap();
+
+        let response = client.put(format!("/payments/{}/method", pending.id))
+            .json(&serde_json::json!({ "method": "e_wallet" }))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let body: ApiResponse<Payment> = response.into_json().await.unwrap();
+        let updated = body.data.unwrap();
+        assert_eq!(updated.method, PaymentMethod::EWallet);
+        assert_eq!(updated.status, PaymentStatus::Pending);
+        assert_eq!(updated.amount, 40000.0);
+
+        let response = client.put(format!("/payments/{}/method", failed.id))
+            .json(&serde_json::json!({ "method": "BANK_TRANSFER" }))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Conflict);
+        assert_eq!(service.get_payment_by_id(db, &failed.id).await.unwrap().method, PaymentMethod::Cash);
+
+        let response = client.put(format!("/payments/{}/method", refunded.id))
+            .json(&serde_json::json!({ "method": "BANK_TRANSFER" }))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Conflict);
+        let body: ApiResponse<Payment> = response.into_json().await.unwrap();
+        assert!(body.message.contains("has been refunded"));
+        assert_eq!(service.get_payment_by_id(db, &refunded.id).await.unwrap().method, PaymentMethod::Cash);
+
+        let response = client.put(format!("/payments/{}/method", pending.id))
+            .json(&serde_json::json!({ "method": "CHEQUE" }))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::BadRequest);
+
+        let response = client.put("/payments/PMT-MISSING/method")
+            .json(&serde_json::json!({ "method": "CASH" }))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[rocket::async_test]
+    async fn test_expire_stale_pending_only_expires_old_pending_payments() {
+        use crate::auth::controller::auth::AuthForm;
+        use crate::auth::model::user::User;
+        use crate::auth::service::auth::AuthService;
+
+        let (db_pool, client) = test_client().await;
+        AuthService::register_user(db_pool.clone(), User::new("admin".to_string(), "admin123".to_string(), true))
+            .await
+            .unwrap();
+
+        let service = PaymentService::new();
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let payment = |status: PaymentStatus, age_minutes: i64| Payment {
+            id: service.generate_payment_id(),
+            transaction_id: "TRX-CHECKOUT".to_string(),
+            amount: 75000.0,
+            method: PaymentMethod::EWallet,
+            status,
+            payment_date: Utc::now() - chrono::Duration::minutes(age_minutes),
+            installments: Vec::new(),
+            due_date: None,
+            audit: Default::default(),
+        };
+        let abandoned = service.create_payment(db, payment(PaymentStatus::Pending, 120)).await.unwrap();
+        let fresh = service.create_payment(db, payment(PaymentStatus::Pending, 5)).await.unwrap();
+        let old_paid = service.create_payment(db, payment(PaymentStatus::Paid, 120)).await.unwrap();
+
+        let response = client.post("/payments/expire-stale?minutes=60").dispatch().await;
+        assert_eq!(response.status(), Status::Unauthorized);
+        assert_eq!(service.get_payment_by_id(db, &abandoned.id).await.unwrap().status, PaymentStatus::Pending);
+
+        client.post("/login")
+            .json(&AuthForm { username: "admin".to_string(), password: "admin123".to_string() })
+            .dispatch()
+            .await;
+        let response = client.post("/payments/expire-stale?minutes=60").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let body: ApiResponse<u64> = response.into_json().await.unwrap();
+        assert_eq!(body.data, Some(1));
+
+        assert_eq!(service.get_payment_by_id(db, &abandoned.id).await.unwrap().status, PaymentStatus::Failed);
+        assert_eq!(service.get_payment_by_id(db, &fresh.id).await.unwrap().status, PaymentStatus::Pending);
+        assert_eq!(service.get_payment_by_id(db, &old_paid.id).await.unwrap().status, PaymentStatus::Paid);
+
+        let response = client.post("/payments/expire-stale?minutes=60").dispatch().await;
+        let body: ApiResponse<u64> = response.into_json().await.unwrap();
+        assert_eq!(body.data, Some(0));
+
+        let response = client.post("/payments/expire-stale?minutes=0").dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest);
+
+        // Di luar jangkauan `TimeDelta`, lalu masih valid tetapi melewati batas `DateTime`
+        for minutes in [i64::MAX, 1_000_000_000_000] {
+            let response = client.post(format!("/payments/expire-stale?minutes={minutes}")).dispatch().await;
+            assert_eq!(response.status(), Status::BadRequest);
+            let body: ApiResponse<u64> = response.into_json().await.unwrap();
+            assert!(body.message.contains("out of range"));
+        }
+        assert_eq!(service.get_payment_by_id(db, &fresh.id).await.unwrap().status, PaymentStatus::Pending);
+    }
+
+    #[rocket::async_test]
+    async fn test_expire_stale_payments_is_forbidden_for_non_admins() {
+        use crate::auth::controller::auth::AuthForm;
+        use crate::auth::model::user::User;
+        use crate::auth::service::auth::AuthService;
+
+        let (db_pool, client) = test_client().await;
+        AuthService::register_user(db_pool.clone(), User::new("kasir".to_string(), "kasir123".to_string(), false))
+            .await
+            .unwrap();
+
+        let service = PaymentService::new();
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let abandoned = service.create_payment(db, Payment {
+            id: service.generate_payment_id(),
+            transaction_id: "TRX-CHECKOUT".to_string(),
+            amount: 75000.0,
+            method: PaymentMethod::EWallet,
+            status: PaymentStatus::Pending,
+            payment_date: Utc::now() - chrono::Duration::minutes(120),
+            installments: Vec::new(),
+            due_date: None,
+            audit: Default::default(),
+        }).await.unwrap();
+
+        client.post("/login")
+            .json(&AuthForm { username: "kasir".to_string(), password: "kasir123".to_string() })
+            .dispatch()
+            .await;
+        let response = client.post("/payments/expire-stale?minutes=60")
+            .header(rocket::http::Header::new("Accept-Language", "en"))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Forbidden);
+        let body: ApiResponse<u64> = response.into_json().await.unwrap();
+        assert_eq!(body.message, "Only admins can expire stale pending payments");
+        assert_eq!(service.get_payment_by_id(db, &abandoned.id).await.unwrap().status, PaymentStatus::Pending);
+    }
+
+    #[test]
+    fn test_create_payment_request_json_keys() {
+        let request = CreatePaymentRequest {
+            transaction_id: "TXN-123".to_string(),
+            amount: 1000.0,
+            method: "CASH".to_string(),
+            status: "PENDING".to_string(),
+            due_date: None,
+        };
+
+        let value = serde_json::to_value(&request).unwrap();
+        let mut keys: Vec<&str> = value.as_object().unwrap().keys().map(|k| k.as_str()).collect();
+        keys.sort();
+        assert_eq!(keys, vec!["amount", "due_date", "method", "status", "transaction_id"]);
+
+        let round_trip: CreatePaymentRequest = serde_json::from_value(value).unwrap();
+        assert_eq!(round_trip.transaction_id, "TXN-123");
+        assert_eq!(round_trip.amount, 1000.0);
+    }
+
     #[test]
     fn test_create_payment_request_without_due_date() {
         let json_str = r#"{
@@ -614,6 +1562,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: None,
+            audit: Default::default(),
         };
 
         let response = ApiResponse {
@@ -644,6 +1593,7 @@ mod tests {
                 payment_date: Utc::now(),
                 installments: Vec::new(),
                 due_date: None,
+                audit: Default::default(),
             },
             Payment {
                 id: "PMT-2".to_string(),
@@ -653,6 +1603,7 @@ mod tests {
                 payment_date: Utc::now(),
                 installments: Vec::new(),
                 due_date: Some(Utc::now()),
+                audit: Default::default(),
             },
         ];
 
@@ -769,6 +1720,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: None,
+            audit: Default::default(),
         };
 
         let response = ApiResponse {
@@ -952,4 +1904,216 @@ mod tests {
         let delete_error = format!("Failed to delete payment: {:?}", "TestError");
         assert!(delete_error.contains("Failed to delete payment"));
     }
-}
\ No newline at end of file
+
+    #[rocket::async_test]
+    async fn test_payment_aging_report_buckets_overdue_installments() {
+        use crate::manajemen_pembayaran::model::payment::Installment;
+
+        let (db_pool, client) = test_client().await;
+
+        let service = PaymentService::new();
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let now = Utc::now();
+        let seed = |days_overdue: i64, status: PaymentStatus, amount: f64, paid: f64| {
+            let id = service.generate_payment_id();
+            Payment {
+                id: id.clone(),
+                transaction_id: format!("TRX-AGING-{days_overdue}"),
+                amount,
+                method: PaymentMethod::BankTransfer,
+                status,
+                payment_date: now - chrono::Duration::days(200),
+                installments: if paid > 0.0 {
+                    vec![Installment { id: format!("INST-{id}"), payment_id: id, amount: paid, payment_date: now - chrono::Duration::days(150) }]
+                } else {
+                    Vec::new()
+                },
+                due_date: Some(now - chrono::Duration::days(days_overdue) - chrono::Duration::hours(1)),
+                audit: Default::default(),
+            }
+        };
+        for payment in [
+            seed(5, PaymentStatus::Installment, 100000.0, 40000.0),
+            seed(30, PaymentStatus::Installment, 50000.0, 0.0),
+            seed(45, PaymentStatus::Installment, 75000.5, 25000.0),
+            seed(90, PaymentStatus::Installment, 20000.0, 0.0),
+            seed(120, PaymentStatus::Installment, 300000.0, 100000.0),
+            seed(-10, PaymentStatus::Installment, 80000.0, 0.0),
+            seed(60, PaymentStatus::Pending, 99999.0, 0.0),
+        ] {
+            service.create_payment(db, payment).await.unwrap();
+        }
+
+        let response = client.get("/reports/payment-aging").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let report = response.into_json::<ApiResponse<PaymentAgingReport>>().await.unwrap().data.unwrap();
+
+        let summary: Vec<(String, i64, f64)> = report.buckets.iter()
+            .map(|bucket| (bucket.bucket.clone(), bucket.payment_count, bucket.outstanding_total))
+            .collect();
+        assert_eq!(summary, vec![
+            ("0-30".to_string(), 2, 110000.0),
+            ("31-60".to_string(), 1, 50000.5),
+            ("61-90".to_string(), 1, 20000.0),
+            ("90+".to_string(), 1, 200000.0),
+        ]);
+        assert_eq!(report.outstanding_total, 380000.5);
+
+        let first_bucket: Vec<(&str, i64)> = report.buckets[0].payments.iter()
+            .map(|payment| (payment.transaction_id.as_str(), payment.days_overdue))
+            .collect();
+        assert_eq!(first_bucket, vec![("TRX-AGING-30", 30), ("TRX-AGING-5", 5)]);
+        assert_eq!(report.buckets[3].payments[0].outstanding, 200000.0);
+    }
+
+    #[rocket::async_test]
+    async fn test_refund_endpoints_record_partial_refunds_and_reject_over_limit() {
+
+        let (db_pool, client) = test_client().await;
+
+        let service = PaymentService::new();
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let payment = service.create_payment(db, Payment {
+            id: service.generate_payment_id(),
+            transaction_id: "TRX-REFUND".to_string(),
+            amount: 120000.0,
+            method: PaymentMethod::Cash,
+            status: PaymentStatus::Paid,
+            payment_date: Utc::now(),
+            installments: Vec::new(),
+            due_date: None,
+            audit: Default::default(),
+        }).await.unwrap();
+
+        let url = format!("/payments/{}/refunds", payment.id);
+
+        for amount in [20000.0, 70000.0] {
+            let response = client.post(&url)
+                .json(&RefundRequest { amount, reason: "Retur keramik pecah".to_string() })
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Created);
+        }
+
+        let response = client.post(&url)
+            .json(&RefundRequest { amount: 30000.01, reason: "Kelebihan".to_string() })
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::BadRequest);
+
+        let response = client.get(&url).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let ledger = response.into_json::<ApiResponse<RefundLedger>>().await.unwrap().data.unwrap();
+        let amounts: Vec<f64> = ledger.refunds.iter().map(|refund| refund.amount).collect();
+        assert_eq!(amounts, vec![20000.0, 70000.0]);
+        assert_eq!((ledger.refundable_total, ledger.refunded_total, ledger.remaining), (120000.0, 90000.0, 30000.0));
+
+        let response = client.get("/payments/PMT-missing/refunds").dispatch().await;
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[rocket::async_test]
+    async fn test_next_installment_follows_schedule_of_partially_paid_plan() {
+
+        let (db_pool, client) = test_client().await;
+
+        let service = PaymentService::new();
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let (payment, schedule) = service
+            .create_payment_plan(db, "TRX-NEXT", 300000.0, PaymentMethod::BankTransfer, 3)
+            .await
+            .unwrap();
+
+        let url = format!("/payments/{}/next-installment", payment.id);
+        let installments_url = format!("/payments/{}/installments", payment.id);
+
+        // Cicilan pertama lunas, cicilan kedua baru dibayar 30.000 dari 100.000
+        for amount in [100000.0, 30000.0] {
+            let response = client.post(&installments_url)
+                .header(rocket::http::ContentType::JSON)
+                .body(format!(r#"{{"amount": {amount}}}"#))
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+        }
+
+        let response = client.get(&url).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let next = response.into_json::<ApiResponse<NextInstallment>>().await.unwrap().data.unwrap();
+        assert_eq!((next.sequence, next.scheduled_amount, next.amount), (2, 100000.0, 70000.0));
+        assert_eq!(next.due_date, schedule[1].due_date);
+
+        for amount in [70000.0, 100000.0] {
+            let response = client.post(&installments_url)
+                .header(rocket::http::ContentType::JSON)
+                .body(format!(r#"{{"amount": {amount}}}"#))
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+        }
+
+        let response = client.get(&url).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert!(response.into_json::<ApiResponse<NextInstallment>>().await.unwrap().data.is_none());
+
+        let response = client.get("/payments/PMT-missing/next-installment").dispatch().await;
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    #[rocket::async_test]
+    async fn test_monthly_revenue_report_returns_full_year_series() {
+        use chrono::Datelike;
+
+        let (db_pool, client) = test_client().await;
+
+        let service = PaymentService::new();
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let payment = |amount: f64, paid_at: &str| Payment {
+            id: service.generate_payment_id(),
+            transaction_id: format!("TRX-MONTHLY-{paid_at}"),
+            amount,
+            method: PaymentMethod::Cash,
+            status: PaymentStatus::Paid,
+            payment_date: crate::common::timestamp::parse(paid_at).unwrap(),
+            installments: Vec::new(),
+            due_date: None,
+            audit: Default::default(),
+        };
+        let seeded = [
+            payment(100000.0, "2024-02-01T00:00:00Z"),
+            payment(50000.5, "2024-02-29T23:59:59Z"),
+            payment(75000.0, "2024-07-15T12:00:00Z"),
+            payment(999999.0, "2023-12-31T23:59:59Z"),
+        ];
+        for seed in seeded {
+            service.create_payment(db, seed).await.unwrap();
+        }
+
+        let response = client.get("/payments/report/monthly?year=2024").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let report = response.into_json::<ApiResponse<MonthlyRevenueReport>>().await.unwrap().data.unwrap();
+        assert_eq!(report.year, 2024);
+        assert_eq!(report.months.len(), 12);
+        let months: Vec<String> = report.months.iter().map(|month| month.month.clone()).collect();
+        assert_eq!(months, (1..=12).map(|month| format!("2024-{month:02}")).collect::<Vec<_>>());
+        for month in &report.months {
+            let expected = match month.month.as_str() {
+                "2024-02" => (2, 150000.5),
+                "2024-07" => (1, 75000.0),
+                _ => (0, 0.0),
+            };
+            assert_eq!((month.payment_count, month.total), expected, "{}", month.month);
+        }
+        assert_eq!(report.total, 225000.5);
+
+        // Tanpa `year` memakai tahun berjalan, tetap 12 bulan
+        let response = client.get("/payments/report/monthly").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let report = response.into_json::<ApiResponse<MonthlyRevenueReport>>().await.unwrap().data.unwrap();
+        assert_eq!(report.year, Utc::now().year());
+        assert_eq!(report.months.len(), 12);
+
+        let response = client.get("/payments/report/monthly?year=0").dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+}
@@ -4,13 +4,34 @@ use serde::{Serialize, Deserialize};
 pub enum PaymentStatus {
     Paid,    // LUNAS
     Installment,  // CICILAN
+    Pending,  // MENUNGGU
+    Failed,  // GAGAL
 }
 
 impl PaymentStatus {
+    /// Semua varian; tambahkan varian baru di sini dan di `label`.
+    pub const ALL: [PaymentStatus; 4] = [
+        PaymentStatus::Paid,
+        PaymentStatus::Installment,
+        PaymentStatus::Pending,
+        PaymentStatus::Failed,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PaymentStatus::Paid => "Paid",
+            PaymentStatus::Installment => "Installment",
+            PaymentStatus::Pending => "Pending",
+            PaymentStatus::Failed => "Failed",
+        }
+    }
+
     pub fn from_string(status: &str) -> Option<Self> {
         match status.to_uppercase().as_str() {
             "LUNAS" => Some(PaymentStatus::Paid),
             "CICILAN" => Some(PaymentStatus::Installment),
+            "MENUNGGU" => Some(PaymentStatus::Pending),
+            "GAGAL" => Some(PaymentStatus::Failed),
             _ => None,
         }
     }
@@ -21,6 +42,8 @@ impl std::fmt::Display for PaymentStatus {
         let s = match self {
             PaymentStatus::Paid => "LUNAS",
             PaymentStatus::Installment => "CICILAN",
+            PaymentStatus::Pending => "MENUNGGU",
+            PaymentStatus::Failed => "GAGAL",
         };
         write!(f, "{s}")
     }
@@ -52,6 +75,10 @@ mod tests {
     fn test_payment_status_to_string() {
         assert_eq!(PaymentStatus::Paid.to_string(), "LUNAS");
         assert_eq!(PaymentStatus::Installment.to_string(), "CICILAN");
+        assert_eq!(PaymentStatus::Pending.to_string(), "MENUNGGU");
+        assert_eq!(PaymentStatus::Failed.to_string(), "GAGAL");
+        assert_eq!(PaymentStatus::from_string("menunggu"), Some(PaymentStatus::Pending));
+        assert_eq!(PaymentStatus::from_string("GAGAL"), Some(PaymentStatus::Failed));
     }
 
     #[test]
@@ -166,11 +193,13 @@ mod tests {
         match paid {
             PaymentStatus::Paid => assert!(true),
             PaymentStatus::Installment => panic!("Should not match Installment"),
+            PaymentStatus::Pending | PaymentStatus::Failed => panic!("Should not match Pending/Failed"),
         }
 
         match installment {
             PaymentStatus::Paid => panic!("Should not match Paid"),
             PaymentStatus::Installment => assert!(true),
+            PaymentStatus::Pending | PaymentStatus::Failed => panic!("Should not match Pending/Failed"),
         }
     }    #[test]
     fn test_payment_status_match_coverage_paid() {
@@ -179,6 +208,7 @@ mod tests {
         let result = match paid {
             PaymentStatus::Paid => "correctly_matched_paid",
             PaymentStatus::Installment => "incorrectly_matched_installment",
+            PaymentStatus::Pending | PaymentStatus::Failed => "incorrectly_matched_unpaid",
         };
         
         assert_eq!(result, "correctly_matched_paid");
@@ -194,6 +224,7 @@ mod tests {
         let result = match installment {
             PaymentStatus::Paid => "incorrectly_matched_paid", 
             PaymentStatus::Installment => "correctly_matched_installment",
+            PaymentStatus::Pending | PaymentStatus::Failed => "incorrectly_matched_unpaid",
         };
         
         assert_eq!(result, "correctly_matched_installment");
@@ -207,17 +238,23 @@ mod tests {
         let test_cases = vec![
             (PaymentStatus::Paid, "Paid variant"),
             (PaymentStatus::Installment, "Installment variant"),
+            (PaymentStatus::Pending, "Pending variant"),
+            (PaymentStatus::Failed, "Failed variant"),
         ];
 
         for (status, description) in test_cases {
             let result = match status {
                 PaymentStatus::Paid => "matched_paid",
                 PaymentStatus::Installment => "matched_installment",
+                PaymentStatus::Pending => "matched_pending",
+                PaymentStatus::Failed => "matched_failed",
             };
 
             match status {
                 PaymentStatus::Paid => assert_eq!(result, "matched_paid", "{}", description),
                 PaymentStatus::Installment => assert_eq!(result, "matched_installment", "{}", description),
+                PaymentStatus::Pending => assert_eq!(result, "matched_pending", "{}", description),
+                PaymentStatus::Failed => assert_eq!(result, "matched_failed", "{}", description),
             }
         }
     }
@@ -241,6 +278,7 @@ mod tests {
                     assert!(!is_paid);
                     assert!(is_installment);
                 },
+                PaymentStatus::Pending | PaymentStatus::Failed => unreachable!(),
             }
         }
     }
@@ -268,6 +306,7 @@ mod tests {
                     assert!(!paid_match, "Installment variant should not match Paid pattern");
                     
                 },
+                PaymentStatus::Pending | PaymentStatus::Failed => unreachable!(),
             }
         }
     }
@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use crate::manajemen_pembayaran::enums::payment_status::PaymentStatus;
+use crate::common::audit::AuditTrail;
 use serde::{Serialize, Deserialize};
 use std::fmt;
 
@@ -11,6 +12,26 @@ pub enum PaymentMethod {
     EWallet,
 }
 
+impl PaymentMethod {
+    /// Semua varian, urut seperti deklarasi enum. Varian baru wajib ditambahkan di sini
+    /// dan di `label` supaya ikut muncul di `GET /payments/methods`.
+    pub const ALL: [PaymentMethod; 4] = [
+        PaymentMethod::Cash,
+        PaymentMethod::CreditCard,
+        PaymentMethod::BankTransfer,
+        PaymentMethod::EWallet,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PaymentMethod::Cash => "Cash",
+            PaymentMethod::CreditCard => "Credit Card",
+            PaymentMethod::BankTransfer => "Bank Transfer",
+            PaymentMethod::EWallet => "E-Wallet",
+        }
+    }
+}
+
 impl fmt::Display for PaymentMethod {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -30,9 +51,43 @@ pub struct Payment {
     pub amount: f64,
     pub method: PaymentMethod,
     pub status: PaymentStatus,
+    #[serde(with = "crate::common::timestamp::rfc3339")]
     pub payment_date: DateTime<Utc>,
     pub installments: Vec<Installment>,
+    #[serde(default, with = "crate::common::timestamp::option_rfc3339")]
     pub due_date: Option<DateTime<Utc>>,
+    #[serde(flatten)]
+    pub audit: AuditTrail,
+}
+
+impl Payment {
+    pub fn installments_total(&self) -> f64 {
+        self.installments.iter().map(|installment| installment.amount).sum()
+    }
+
+    /// Satu-satunya tempat status diturunkan dari cicilan: payment bercicilan menjadi
+    /// LUNAS begitu jumlah cicilan mencapai `amount` (toleransi setengah sen), selain itu
+    /// CICILAN. Payment tanpa cicilan tidak diubah statusnya.
+    pub fn recompute_status(&mut self) -> PaymentStatus {
+        if !self.installments.is_empty() {
+            self.status = if self.installments_total() + 0.005 >= self.amount {
+                PaymentStatus::Paid
+            } else {
+                PaymentStatus::Installment
+            };
+        }
+        self.status.clone()
+    }
+
+    /// Batas total refund: jumlah yang sudah benar-benar diterima. LUNAS dihitung penuh,
+    /// CICILAN sebesar cicilan yang masuk, MENUNGGU/GAGAL belum menerima apa pun.
+    pub fn refundable_total(&self) -> f64 {
+        match self.status {
+            PaymentStatus::Paid => self.amount,
+            PaymentStatus::Installment => self.installments_total(),
+            PaymentStatus::Pending | PaymentStatus::Failed => 0.0,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,9 +96,146 @@ pub struct Installment {
     pub id: String,
     pub payment_id: String,
     pub amount: f64,
+    #[serde(with = "crate::common::timestamp::rfc3339")]
     pub payment_date: DateTime<Utc>,
 }
 
+/// Agregat pembayaran untuk satu transaksi. Payment CICILAN dihitung dari
+/// cicilan yang sudah masuk, bukan dari `amount` yang ditagihkan.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PaymentTotals {
+    pub paid_total: f64,
+    pub payment_count: i64,
+    #[serde(default, with = "crate::common::timestamp::option_rfc3339")]
+    pub last_payment_date: Option<DateTime<Utc>>,
+}
+
+/// Bagian pembayaran satu transaksi yang masuk lewat satu metode; dihitung sama seperti
+/// `PaymentTotals` (CICILAN sebesar cicilan yang sudah masuk).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PaymentMethodTotal {
+    pub method: PaymentMethod,
+    pub paid_total: f64,
+    pub payment_count: i64,
+}
+
+/// Satu baris jadwal cicilan: berapa yang harus dibayar dan kapan jatuh tempo.
+/// Berbeda dengan `Installment` yang mencatat cicilan yang sudah dibayar.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ScheduledInstallment {
+    pub id: String,
+    pub payment_id: String,
+    pub sequence: i32,
+    pub amount: f64,
+    #[serde(with = "crate::common::timestamp::rfc3339")]
+    pub due_date: DateTime<Utc>,
+}
+
+/// Baris jadwal cicilan berikutnya yang belum terbayar penuh. `amount` adalah sisa
+/// tagihan baris tersebut, lebih kecil dari `scheduled_amount` bila sudah dicicil sebagian.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct NextInstallment {
+    pub payment_id: String,
+    pub sequence: i32,
+    pub scheduled_amount: f64,
+    pub amount: f64,
+    #[serde(with = "crate::common::timestamp::rfc3339")]
+    pub due_date: DateTime<Utc>,
+}
+
+/// Satu payment CICILAN yang lewat jatuh tempo dan masih punya sisa tagihan.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct OverduePayment {
+    pub payment_id: String,
+    pub transaction_id: String,
+    pub amount: f64,
+    pub outstanding: f64,
+    #[serde(with = "crate::common::timestamp::rfc3339")]
+    pub due_date: DateTime<Utc>,
+    pub days_overdue: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PaymentAgingBucket {
+    pub bucket: String,
+    pub payment_count: i64,
+    pub outstanding_total: f64,
+    pub payments: Vec<OverduePayment>,
+}
+
+/// Laporan umur piutang: selalu berisi semua bucket, termasuk yang kosong.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PaymentAgingReport {
+    #[serde(with = "crate::common::timestamp::rfc3339")]
+    pub as_of: DateTime<Utc>,
+    pub buckets: Vec<PaymentAgingBucket>,
+    pub outstanding_total: f64,
+}
+
+/// Satu bulan kalender dalam laporan pendapatan; `month` berformat `YYYY-MM`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct MonthlyRevenue {
+    pub month: String,
+    pub payment_count: i64,
+    pub total: f64,
+}
+
+/// Laporan pendapatan per bulan `payment_date` dalam satu tahun: selalu 12 bulan berurutan,
+/// bulan tanpa payment tetap muncul dengan nilai nol.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct MonthlyRevenueReport {
+    pub year: i32,
+    pub months: Vec<MonthlyRevenue>,
+    pub total: f64,
+}
+
+/// Satu pengembalian dana atas payment, dicatat di tabel `refunds` terpisah dari cicilan.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Refund {
+    pub id: String,
+    pub payment_id: String,
+    pub amount: f64,
+    pub reason: String,
+    #[serde(with = "crate::common::timestamp::rfc3339")]
+    pub refund_date: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+}
+
+/// Riwayat refund satu payment beserta sisa yang masih bisa dikembalikan.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct RefundLedger {
+    pub payment_id: String,
+    pub refundable_total: f64,
+    pub refunded_total: f64,
+    pub remaining: f64,
+    pub refunds: Vec<Refund>,
+}
+
+impl RefundLedger {
+    pub fn new(payment_id: &str, refundable_total: f64, refunds: Vec<Refund>) -> Self {
+        let refunded_total = (refunds.iter().map(|refund| refund.amount).sum::<f64>() * 100.0).round() / 100.0;
+        RefundLedger {
+            payment_id: payment_id.to_string(),
+            refundable_total,
+            refunded_total,
+            remaining: (((refundable_total - refunded_total) * 100.0).round() / 100.0).max(0.0),
+            refunds,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -106,6 +298,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: None,
+            audit: Default::default(),
         };
         
         assert_eq!(payment.id, payment_id);
@@ -131,6 +324,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: Some(due_date),
+            audit: Default::default(),
         };
         
         assert_eq!(payment.id, payment_id);
@@ -154,6 +348,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: None,
+            audit: Default::default(),
         };
         
         let installment = Installment {
@@ -183,6 +378,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: None,
+            audit: Default::default(),
         };
         
         let installment1 = Installment {
@@ -238,6 +434,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: None,
+            audit: Default::default(),
         };
         
         let serialized = serde_json::to_string(&payment);
@@ -266,4 +463,81 @@ mod tests {
         assert!(json_str.contains("PMT-123"));
         assert!(json_str.contains("500"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_recompute_status_from_installments() {
+        let installment = |amount: f64| Installment {
+            id: format!("INST-{}", Uuid::new_v4()),
+            payment_id: "PMT-1".to_string(),
+            amount,
+            payment_date: Utc::now(),
+        };
+        let mut payment = Payment {
+            id: "PMT-1".to_string(),
+            transaction_id: "TRX-1".to_string(),
+            amount: 300000.0,
+            method: PaymentMethod::Cash,
+            status: PaymentStatus::Installment,
+            payment_date: Utc::now(),
+            installments: vec![installment(100000.0)],
+            due_date: None,
+            audit: Default::default(),
+        };
+
+        assert_eq!(payment.recompute_status(), PaymentStatus::Installment);
+
+        payment.installments.push(installment(100000.0));
+        payment.installments.push(installment(99999.999));
+        assert_eq!(payment.recompute_status(), PaymentStatus::Paid);
+        assert_eq!(payment.status, PaymentStatus::Paid);
+
+        // Status manual LUNAS untuk cicilan yang belum lunas dikembalikan ke CICILAN
+        payment.installments.pop();
+        payment.status = PaymentStatus::Paid;
+        assert_eq!(payment.recompute_status(), PaymentStatus::Installment);
+
+        // Pembayaran langsung tanpa cicilan dibiarkan apa adanya
+        payment.installments.clear();
+        payment.status = PaymentStatus::Paid;
+        assert_eq!(payment.recompute_status(), PaymentStatus::Paid);
+    }
+
+    #[test]
+    fn test_payment_timestamps_serialize_as_rfc3339_utc() {
+        use chrono::TimeZone;
+
+        let paid_at = Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap();
+        let payment = Payment {
+            id: "PMT-TS".to_string(),
+            transaction_id: "TRX-TS".to_string(),
+            amount: 100000.0,
+            method: PaymentMethod::Cash,
+            status: PaymentStatus::Installment,
+            payment_date: paid_at,
+            installments: vec![Installment {
+                id: "INST-TS".to_string(),
+                payment_id: "PMT-TS".to_string(),
+                amount: 50000.0,
+                payment_date: paid_at + chrono::Duration::milliseconds(1500),
+            }],
+            due_date: Some(paid_at + chrono::Duration::days(30)),
+            audit: Default::default(),
+        };
+
+        let value = serde_json::to_value(&payment).unwrap();
+        assert_eq!(value["payment_date"], "2024-01-01T10:00:00Z");
+        assert_eq!(value["due_date"], "2024-01-31T10:00:00Z");
+        assert_eq!(value["installments"][0]["payment_date"], "2024-01-01T10:00:01.500Z");
+
+        let round_trip: Payment = serde_json::from_value(value).unwrap();
+        assert_eq!(round_trip.payment_date, payment.payment_date);
+        assert_eq!(round_trip.due_date, payment.due_date);
+        assert_eq!(round_trip.installments[0].payment_date, payment.installments[0].payment_date);
+
+        let without_due_date: Payment = serde_json::from_str(
+            r#"{"id":"P","transaction_id":"T","amount":1.0,"method":"Cash","status":"Paid","payment_date":"2024-01-01T17:00:00+07:00","installments":[]}"#
+        ).unwrap();
+        assert_eq!(without_due_date.payment_date, paid_at);
+        assert_eq!(without_due_date.due_date, None);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::manajemen_pembayaran::model::payment::PaymentMethod;
 use crate::manajemen_pembayaran::enums::payment_status::PaymentStatus;
-use crate::manajemen_pembayaran::patterns::state::{PaymentState, PaidState, InstallmentState};
+use crate::manajemen_pembayaran::patterns::state::{PaymentState, PaidState, InstallmentState, PendingState, FailedState};
 
 pub struct PaymentStateFactory;
 impl PaymentStateFactory {
@@ -8,6 +8,8 @@ impl PaymentStateFactory {
         match status {
             PaymentStatus::Paid => Box::new(PaidState),
             PaymentStatus::Installment => Box::new(InstallmentState),
+            PaymentStatus::Pending => Box::new(PendingState),
+            PaymentStatus::Failed => Box::new(FailedState),
         }
     }
 }
@@ -57,6 +57,42 @@ impl PaymentState for InstallmentState {
     }
 }
 
+// Belum ada uang masuk; pembayaran pertama menjadikannya CICILAN atau langsung LUNAS
+pub struct PendingState;
+impl PaymentState for PendingState {
+    fn process_payment(&self, payment: &mut Payment, amount: f64) -> Result<(), String> {
+        InstallmentState.process_payment(payment, amount)?;
+        if payment.status == PaymentStatus::Pending {
+            payment.status = PaymentStatus::Installment;
+        }
+        Ok(())
+    }
+
+    fn can_delete(&self) -> bool {
+        true
+    }
+
+    fn get_name(&self) -> String {
+        "MENUNGGU".to_string()
+    }
+}
+
+// Pembayaran yang kedaluwarsa/gagal tidak bisa menerima uang lagi
+pub struct FailedState;
+impl PaymentState for FailedState {
+    fn process_payment(&self, _payment: &mut Payment, _amount: f64) -> Result<(), String> {
+        Err("Pembayaran sudah gagal, buat pembayaran baru".to_string())
+    }
+
+    fn can_delete(&self) -> bool {
+        true
+    }
+
+    fn get_name(&self) -> String {
+        "GAGAL".to_string()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -76,6 +112,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: None,
+            audit: Default::default(),
         };
         
         let result = state.process_payment(&mut payment, 500.0);
@@ -96,6 +133,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: None,
+            audit: Default::default(),
         };
         
         let result = state.process_payment(&mut payment, 400.0);
@@ -124,6 +162,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: None,
+            audit: Default::default(),
         };
         
         let result = state.process_payment(&mut payment, -100.0);
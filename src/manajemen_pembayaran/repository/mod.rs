@@ -1 +1,3 @@
-pub mod payment_repository;
\ No newline at end of file
+pub mod payment_repository;
+pub mod payment_repository_trait;
+pub mod optimized_payment_repository;
\ No newline at end of file
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{Any, Connection, pool::PoolConnection};
+
+use crate::manajemen_pembayaran::enums::payment_status::PaymentStatus;
+use crate::manajemen_pembayaran::model::payment::{Installment, MonthlyRevenue, Payment, PaymentMethod, PaymentMethodTotal, PaymentTotals, Refund, ScheduledInstallment};
+use crate::manajemen_pembayaran::repository::payment_repository::PembayaranRepository;
+use crate::manajemen_pembayaran::repository::payment_repository_trait::PaymentRepository;
+use crate::common::pagination::ListQuery;
+use crate::common::slow_query::timed_query;
+
+/// Varian repository dengan round trip lebih sedikit: `create` menulis payment dan
+/// cicilannya dalam satu transaksi tanpa SELECT ulang, `find_by_id` memakai satu LEFT JOIN.
+/// Operasi lain sama dengan `PembayaranRepository`.
+pub struct OptimizedPembayaranRepository;
+
+#[async_trait]
+impl PaymentRepository for OptimizedPembayaranRepository {
+    fn name(&self) -> &'static str {
+        "optimized"
+    }
+
+    async fn create(&self, payment: &Payment, mut db: PoolConnection<Any>) -> Result<Payment, sqlx::Error> {
+        let mut tx = db.begin().await?;
+
+        timed_query("payment::optimized_create", sqlx::query("
+            INSERT INTO payments (id, transaction_id, amount, method, status, payment_date, due_date, created_by, updated_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        ")
+            .bind(&payment.id)
+            .bind(&payment.transaction_id)
+            .bind(payment.amount)
+            .bind(payment.method.to_string())
+            .bind(payment.status.to_string())
+            .bind(payment.payment_date.to_rfc3339())
+            .bind(payment.due_date.map(|d| d.to_rfc3339()))
+            .bind(&payment.audit.created_by)
+            .bind(&payment.audit.updated_by)
+            .execute(&mut *tx))
+            .await?;
+
+        for installment in &payment.installments {
+            timed_query("payment::optimized_create", sqlx::query("
+                INSERT INTO installments (id, payment_id, amount, payment_date)
+                VALUES ($1, $2, $3, $4)
+            ")
+                .bind(&installment.id)
+                .bind(&installment.payment_id)
+                .bind(installment.amount)
+                .bind(installment.payment_date.to_rfc3339())
+                .execute(&mut *tx))
+                .await?;
+        }
+
+        tx.commit().await?;
+
+        // Nilai yang ditulis sudah diketahui; cukup samakan urutan cicilan dengan hasil baca
+        let mut created = payment.clone();
+        created.installments.sort_by(|a, b| a.payment_date.cmp(&b.payment_date));
+        Ok(created)
+    }
+
+    async fn find_by_id(&self, id: &str, mut db: PoolConnection<Any>) -> Result<Payment, sqlx::Error> {
+        let rows = timed_query("payment::optimized_find_by_id", sqlx::query("
+            SELECT p.id, p.transaction_id, p.amount, p.method, p.status, p.payment_date, p.due_date, p.created_by, p.updated_by,
+                   i.id AS installment_id, i.amount AS installment_amount, i.payment_date AS installment_payment_date
+            FROM payments p
+            LEFT JOIN installments i ON i.payment_id = p.id
+            WHERE p.id = $1
+            ORDER BY i.payment_date ASC
+        ")
+            .bind(id)
+            .fetch_all(&mut *db))
+            .await?;
+
+        let mut rows = rows.into_iter();
+        let first = rows.next().ok_or(sqlx::Error::RowNotFound)?;
+        let first_installment = PembayaranRepository::parse_joined_installment(&first, id)?;
+        let mut payment = PembayaranRepository::parse_row_to_payment(first)?;
+        payment.installments.extend(first_installment);
+        for row in rows {
+            payment.installments.extend(PembayaranRepository::parse_joined_installment(&row, id)?);
+        }
+
+        Ok(payment)
+    }
+
+    async fn find_by_id_without_installments(&self, id: &str, db: PoolConnection<Any>) -> Result<Payment, sqlx::Error> {
+        PembayaranRepository::find_by_id_without_installments(db, id).await
+    }
+
+    async fn find_all(&self, filters: Option<HashMap<String, String>>, with_installments: bool, order_by: &str, list: &ListQuery, db: PoolConnection<Any>) -> Result<Vec<Payment>, sqlx::Error> {
+        if with_installments {
+            PembayaranRepository::find_all_with_installments(db, filters, order_by, list).await
+        } else {
+            PembayaranRepository::find_all_without_installments(db, filters, order_by, list).await
+        }
+    }
+
+    async fn create_with_schedule(&self, payment: &Payment, schedule: &[ScheduledInstallment], max_already_paid: f64, db: PoolConnection<Any>) -> Result<Payment, sqlx::Error> {
+        PembayaranRepository::create_with_schedule(db, payment, schedule, max_already_paid).await
+    }
+
+    async fn count(&self, filters: Option<HashMap<String, String>>, db: PoolConnection<Any>) -> Result<i64, sqlx::Error> {
+        PembayaranRepository::count_all(db, filters).await
+    }
+
+    async fn find_match_candidates(&self, filters: Option<HashMap<String, String>>, amount: Option<f64>, tolerance: f64, db: PoolConnection<Any>) -> Result<Vec<Payment>, sqlx::Error> {
+        PembayaranRepository::find_match_candidates(db, filters, amount, tolerance).await
+    }
+
+    async fn update(&self, payment: &Payment, db: PoolConnection<Any>) -> Result<Payment, sqlx::Error> {
+        PembayaranRepository::update(db, payment).await
+    }
+
+    async fn update_status(&self, payment_id: String, new_status: PaymentStatus, additional_amount: Option<f64>, updated_by: Option<String>, db: PoolConnection<Any>) -> Result<Payment, sqlx::Error> {
+        PembayaranRepository::update_payment_status(db, payment_id, new_status, additional_amount, updated_by).await
+    }
+
+    async fn record_installment(&self, installment: &Installment, recompute_status: bool, updated_by: Option<String>, db: PoolConnection<Any>) -> Result<Payment, sqlx::Error> {
+        PembayaranRepository::record_installment(db, installment, recompute_status, updated_by).await
+    }
+
+    async fn update_method(&self, payment_id: &str, method: &PaymentMethod, updated_by: Option<String>, db: PoolConnection<Any>) -> Result<Payment, sqlx::Error> {
+        PembayaranRepository::update_method(db, payment_id, method, updated_by).await
+    }
+
+    async fn record_refund(&self, refund: &Refund, refundable_total: f64, db: PoolConnection<Any>) -> Result<(), sqlx::Error> {
+        PembayaranRepository::record_refund(db, refund, refundable_total).await
+    }
+
+    async fn find_refunds(&self, payment_id: &str, db: PoolConnection<Any>) -> Result<Vec<Refund>, sqlx::Error> {
+        PembayaranRepository::find_refunds(db, payment_id).await
+    }
+
+    async fn find_schedule(&self, payment_id: &str, db: PoolConnection<Any>) -> Result<Vec<ScheduledInstallment>, sqlx::Error> {
+        PembayaranRepository::find_schedule(db, payment_id).await
+    }
+
+    async fn delete(&self, id: &str, db: PoolConnection<Any>) -> Result<(), sqlx::Error> {
+        PembayaranRepository::delete(db, id).await
+    }
+
+    async fn expire_pending_before(&self, cutoff: DateTime<Utc>, db: PoolConnection<Any>) -> Result<u64, sqlx::Error> {
+        PembayaranRepository::expire_pending_before(db, cutoff).await
+    }
+
+    async fn find_overdue_installments(&self, now: DateTime<Utc>, db: PoolConnection<Any>) -> Result<Vec<Payment>, sqlx::Error> {
+        PembayaranRepository::find_overdue_installments(db, now).await
+    }
+
+    async fn find_transaction_total(&self, transaction_id: &str, db: PoolConnection<Any>) -> Result<Option<f64>, sqlx::Error> {
+        PembayaranRepository::find_transaction_total(db, transaction_id).await
+    }
+
+    async fn find_transaction_status(&self, transaction_id: &str, db: PoolConnection<Any>) -> Result<Option<String>, sqlx::Error> {
+        PembayaranRepository::find_transaction_status(db, transaction_id).await
+    }
+
+    async fn sum_amount_by_transaction(&self, transaction_id: &str, db: PoolConnection<Any>) -> Result<f64, sqlx::Error> {
+        PembayaranRepository::sum_amount_by_transaction(db, transaction_id).await
+    }
+
+    async fn totals_by_transaction(&self, transaction_id: &str, db: PoolConnection<Any>) -> Result<PaymentTotals, sqlx::Error> {
+        PembayaranRepository::totals_by_transaction(db, transaction_id).await
+    }
+
+    async fn totals_by_method_for_transaction(&self, transaction_id: &str, db: PoolConnection<Any>) -> Result<Vec<PaymentMethodTotal>, sqlx::Error> {
+        PembayaranRepository::totals_by_method_for_transaction(db, transaction_id).await
+    }
+
+    async fn monthly_totals(&self, year: i32, db: PoolConnection<Any>) -> Result<Vec<MonthlyRevenue>, sqlx::Error> {
+        PembayaranRepository::monthly_totals(db, year).await
+    }
+}
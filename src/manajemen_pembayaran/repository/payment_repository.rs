@@ -1,43 +1,51 @@
 use sqlx::any::AnyRow;
-use sqlx::{Any, pool::PoolConnection};
+use sqlx::{Any, AnyConnection, Connection, pool::PoolConnection};
 use sqlx::Row;
 use chrono::{DateTime, Utc, NaiveDateTime};
 use std::collections::HashMap;
 use uuid::Uuid;
 
 use crate::manajemen_pembayaran::enums::payment_status::PaymentStatus;
-use crate::manajemen_pembayaran::model::payment::{Payment, PaymentMethod, Installment};
+use crate::manajemen_pembayaran::model::payment::{MonthlyRevenue, Payment, PaymentMethod, PaymentMethodTotal, Installment, Refund, ScheduledInstallment, PaymentTotals};
+use crate::common::slow_query::timed_query;
+use crate::common::sql_dialect::{date_trunc_expr, DateUnit, DbKind};
+use crate::common::audit::AuditTrail;
+use crate::common::pagination::{escape_like, ListQuery, SortColumn};
 
 pub struct PembayaranRepository;
 
 impl PembayaranRepository {    
+    /// Urutan default listing agar paginasi deterministik: terbaru dulu, `id` sebagai pemecah seri.
+    pub const DEFAULT_ORDER_BY: &'static str = "payment_date DESC, id ASC";
+
+    /// Nilai `sort` listing payment; dipakai bersama `ListQuery::order_by` dengan `id ASC` sebagai pemecah seri.
+    pub const SORT_COLUMNS: &'static [SortColumn] = &[
+        ("payment_date", "payment_date"),
+        ("amount", "amount"),
+        ("transaction_id", "transaction_id"),
+    ];
+
+    /// Payment `p` yang uangnya benar-benar diterima: MENUNGGU belum dibayar, GAGAL tidak pernah masuk.
+    const COLLECTED_STATUS_FILTER: &'static str = "p.status NOT IN ('MENUNGGU', 'GAGAL')";
+
+    /// Total refund payment `p`; dikurangkan dari setiap agregat uang yang diterima.
+    const REFUNDED_AMOUNT: &'static str = "COALESCE((SELECT SUM(r.amount) FROM refunds r WHERE r.payment_id = p.id), 0)";
+
     pub async fn create(mut db: PoolConnection<Any>, payment: &Payment) -> Result<Payment, sqlx::Error>{        
         eprintln!("DEBUG: Creating payment with ID: {}, Transaction ID: {}", payment.id, payment.transaction_id);
-        sqlx::query("
-            INSERT INTO payments (id, transaction_id, amount, method, status, payment_date, due_date)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
-        ")
-            .bind(&payment.id)
-            .bind(&payment.transaction_id)
-            .bind(payment.amount)
-            .bind(payment.method.to_string())
-            .bind(payment.status.to_string())
-            .bind(payment.payment_date.to_rfc3339())
-            .bind(payment.due_date.map(|d| d.to_rfc3339()))            
-            .execute(&mut *db)
-            .await
+        Self::insert_payment(&mut db, payment).await
             .map_err(|e| {
                 eprintln!("DEBUG: Failed to insert payment: {e}");
                 e
             })?;
 
-        let result = sqlx::query("
-            SELECT id, transaction_id, amount, method, status, payment_date, due_date
+        let result = timed_query("payment::create", sqlx::query("
+            SELECT id, transaction_id, amount, method, status, payment_date, due_date, created_by, updated_by
             FROM payments
             WHERE id = $1
         ")
             .bind(&payment.id)
-            .fetch_one(&mut *db)
+            .fetch_one(&mut *db))
             .await?;
 
         let mut created_payment = Self::parse_row_to_payment(result)?;
@@ -53,44 +61,56 @@ impl PembayaranRepository {
         Ok(created_payment)
     }    
     
+    /// Hanya baris `payments` (tanpa cicilan) pada koneksi/transaksi milik pemanggil,
+    /// supaya bisa digabung dengan penulisan lain dalam satu DB transaction.
+    pub async fn insert_payment(conn: &mut AnyConnection, payment: &Payment) -> Result<(), sqlx::Error> {
+        timed_query("payment::create", sqlx::query("
+            INSERT INTO payments (id, transaction_id, amount, method, status, payment_date, due_date, created_by, updated_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        ")
+            .bind(&payment.id)
+            .bind(&payment.transaction_id)
+            .bind(payment.amount)
+            .bind(payment.method.to_string())
+            .bind(payment.status.to_string())
+            .bind(payment.payment_date.to_rfc3339())
+            .bind(payment.due_date.map(|d| d.to_rfc3339()))
+            .bind(&payment.audit.created_by)
+            .bind(&payment.audit.updated_by)
+            .execute(&mut *conn))
+            .await?;
+
+        Ok(())
+    }
+
     pub async fn find_by_id(mut db: PoolConnection<Any>, id: &str) -> Result<Payment, sqlx::Error>{
         let payment_with_installments = Self::load_payment_with_installments(&mut db, id).await?;
         Ok(payment_with_installments)
     }    
+
+    /// Satu baris `payments` saja tanpa join cicilan; `installments` selalu kosong.
+    pub async fn find_by_id_without_installments(mut db: PoolConnection<Any>, id: &str) -> Result<Payment, sqlx::Error> {
+        let row = timed_query("payment::find_by_id_without_installments", sqlx::query("
+            SELECT id, transaction_id, amount, method, status, payment_date, due_date, created_by, updated_by
+            FROM payments
+            WHERE id = $1
+        ")
+        .bind(id)
+        .fetch_one(&mut *db))
+        .await?;
+
+        Self::parse_row_to_payment(row)
+    }
     
     pub async fn find_all(mut db: PoolConnection<Any>, filters: Option<HashMap<String, String>>) -> Result<Vec<Payment>, sqlx::Error> {
-        let mut base_query = "SELECT id, transaction_id, amount, method, status, payment_date, due_date FROM payments".to_string();
-        let mut where_clauses = Vec::new();
-        let mut bind_values: Vec<&str> = Vec::new();
-        
-        if let Some(filter_map) = &filters {
-            if let Some(status_str) = filter_map.get("status") {
-                where_clauses.push("status = $1".to_string());
-                bind_values.push(status_str);
-            }
-            if let Some(method) = filter_map.get("method") {
-                let param_num = bind_values.len() + 1;
-                where_clauses.push(format!("method = ${param_num}"));
-                bind_values.push(method);
-            }
-            if let Some(transaction_id) = filter_map.get("transaction_id") {
-                let param_num = bind_values.len() + 1;
-                where_clauses.push(format!("transaction_id = ${param_num}"));
-                bind_values.push(transaction_id);
-            }
+        let (where_sql, bind_values) = Self::build_filter_clause(filters.as_ref(), "");
+        let base_query = format!("SELECT id, transaction_id, amount, method, status, payment_date, due_date, created_by, updated_by FROM payments{where_sql} ORDER BY {}", Self::DEFAULT_ORDER_BY);
+
+        let mut query = sqlx::query(&base_query);
+        for value in &bind_values {
+            query = query.bind(value.as_str());
         }
-        
-        if !where_clauses.is_empty() {
-            base_query.push_str(" WHERE ");
-            base_query.push_str(&where_clauses.join(" AND "));
-        }        
-        let rows = match bind_values.len() {
-            0 => sqlx::query(&base_query).fetch_all(&mut *db).await?,
-            1 => sqlx::query(&base_query).bind(bind_values[0]).fetch_all(&mut *db).await?,
-            2 => sqlx::query(&base_query).bind(bind_values[0]).bind(bind_values[1]).fetch_all(&mut *db).await?,
-            3 => sqlx::query(&base_query).bind(bind_values[0]).bind(bind_values[1]).bind(bind_values[2]).fetch_all(&mut *db).await?,
-            _ => return Err(sqlx::Error::ColumnNotFound("Too many filters".to_string())),
-        };
+        let rows = timed_query("payment::find_all", query.fetch_all(&mut *db)).await?;
         
         let mut payments = Vec::with_capacity(rows.len());
         for row in rows {
@@ -101,14 +121,419 @@ impl PembayaranRepository {
         
         Ok(payments)
     }
+
+    /// Listing ringan: satu query ke `payments`, `installments` selalu kosong. `order_by` berasal
+    /// dari `DEFAULT_ORDER_BY` atau `ListQuery::order_by`; `LIMIT`/`OFFSET` hanya bila `list` dipaginasi.
+    pub async fn find_all_without_installments(mut db: PoolConnection<Any>, filters: Option<HashMap<String, String>>, order_by: &str, list: &ListQuery) -> Result<Vec<Payment>, sqlx::Error> {
+        let (where_sql, bind_values) = Self::build_filter_clause(filters.as_ref(), "");
+        let base_query = format!("SELECT id, transaction_id, amount, method, status, payment_date, due_date, created_by, updated_by FROM payments{where_sql} ORDER BY {order_by}{}", list.limit_clause());
+
+        let mut query = sqlx::query(&base_query);
+        for value in &bind_values {
+            query = query.bind(value.as_str());
+        }
+        let rows = timed_query("payment::find_all_without_installments", query.fetch_all(&mut *db)).await?;
+
+        rows.into_iter().map(Self::parse_row_to_payment).collect()
+    }
+
+    /// Jumlah payment yang cocok dengan filter listing, tanpa memuat barisnya.
+    pub async fn count_all(mut db: PoolConnection<Any>, filters: Option<HashMap<String, String>>) -> Result<i64, sqlx::Error> {
+        let (where_sql, bind_values) = Self::build_filter_clause(filters.as_ref(), "");
+        let base_query = format!("SELECT CAST(COUNT(*) AS BIGINT) AS count FROM payments{where_sql}");
+
+        let mut query = sqlx::query(&base_query);
+        for value in &bind_values {
+            query = query.bind(value.as_str());
+        }
+        let row = timed_query("payment::count_all", query.fetch_one(&mut *db)).await?;
+        row.try_get("count")
+    }
+
+    /// Kandidat pencocokan mutasi bank: filter listing ditambah nominal dalam `tolerance`,
+    /// yang nominalnya paling dekat lebih dulu. Tanpa `amount` urutannya sama dengan listing.
+    pub async fn find_match_candidates(mut db: PoolConnection<Any>, filters: Option<HashMap<String, String>>, amount: Option<f64>, tolerance: f64) -> Result<Vec<Payment>, sqlx::Error> {
+        let (mut where_sql, bind_values) = Self::build_filter_clause(filters.as_ref(), "");
+        let mut order_by = Self::DEFAULT_ORDER_BY.to_string();
+        if amount.is_some() {
+            let param_num = bind_values.len() + 1;
+            where_sql.push_str(if where_sql.is_empty() { " WHERE " } else { " AND " });
+            where_sql.push_str(&format!("CAST(amount AS DOUBLE PRECISION) BETWEEN ${} AND ${}", param_num, param_num + 1));
+            order_by = format!("ABS(CAST(amount AS DOUBLE PRECISION) - ${}), {}", param_num + 2, Self::DEFAULT_ORDER_BY);
+        }
+        let base_query = format!("SELECT id, transaction_id, amount, method, status, payment_date, due_date, created_by, updated_by FROM payments{where_sql} ORDER BY {order_by}");
+
+        let mut query = sqlx::query(&base_query);
+        for value in &bind_values {
+            query = query.bind(value.as_str());
+        }
+        if let Some(amount) = amount {
+            // Setengah sen ekstra supaya nominal yang sama persis tidak lolos karena galat float
+            let slack = tolerance + 0.005;
+            query = query.bind(amount - slack).bind(amount + slack).bind(amount);
+        }
+        let rows = timed_query("payment::find_match_candidates", query.fetch_all(&mut *db)).await?;
+
+        rows.into_iter().map(Self::parse_row_to_payment).collect()
+    }
+
+    /// Memuat payment beserta cicilannya dengan satu LEFT JOIN, bukan query per baris. Urutan dan
+    /// paginasi diterapkan ke `payments` di subquery supaya `LIMIT` menghitung payment, bukan baris
+    /// hasil join; `list_position` membawa urutan itu ke query luar.
+    pub async fn find_all_with_installments(mut db: PoolConnection<Any>, filters: Option<HashMap<String, String>>, order_by: &str, list: &ListQuery) -> Result<Vec<Payment>, sqlx::Error> {
+        let (where_sql, bind_values) = Self::build_filter_clause(filters.as_ref(), "");
+        let base_query = format!("
+            SELECT p.id, p.transaction_id, p.amount, p.method, p.status, p.payment_date, p.due_date, p.created_by, p.updated_by,
+                   i.id AS installment_id, i.amount AS installment_amount, i.payment_date AS installment_payment_date
+            FROM (
+                SELECT id, transaction_id, amount, method, status, payment_date, due_date, created_by, updated_by,
+                       ROW_NUMBER() OVER (ORDER BY {order_by}) AS list_position
+                FROM payments{where_sql}
+                ORDER BY {order_by}{}
+            ) p
+            LEFT JOIN installments i ON i.payment_id = p.id
+            ORDER BY p.list_position, i.payment_date ASC
+        ", list.limit_clause());
+
+        let mut query = sqlx::query(&base_query);
+        for value in &bind_values {
+            query = query.bind(value.as_str());
+        }
+        let rows = timed_query("payment::find_all_with_installments", query.fetch_all(&mut *db)).await?;
+
+        Self::group_joined_rows(rows)
+    }
+
+    /// Semua payment untuk satu transaksi penjualan beserta cicilannya, dari yang paling lama;
+    /// memakai koneksi pemanggil agar bisa dibaca di dalam DB transaction yang sama.
+    pub async fn find_by_transaction_with_installments(conn: &mut AnyConnection, transaction_id: &str) -> Result<Vec<Payment>, sqlx::Error> {
+        let rows = timed_query("payment::find_by_transaction_with_installments", sqlx::query("
+            SELECT p.id, p.transaction_id, p.amount, p.method, p.status, p.payment_date, p.due_date, p.created_by, p.updated_by,
+                   i.id AS installment_id, i.amount AS installment_amount, i.payment_date AS installment_payment_date
+            FROM payments p
+            LEFT JOIN installments i ON i.payment_id = p.id
+            WHERE p.transaction_id = $1
+            ORDER BY p.payment_date ASC, p.id ASC, i.payment_date ASC
+        ")
+            .bind(transaction_id)
+            .fetch_all(&mut *conn))
+            .await?;
+
+        Self::group_joined_rows(rows)
+    }
+
+    /// Payment CICILAN yang `due_date`-nya sebelum `now`, beserta cicilannya. Seperti
+    /// `expire_pending_before`, perbandingan teks RFC3339 UTC mengikuti urutan waktu.
+    pub async fn find_overdue_installments(mut db: PoolConnection<Any>, now: DateTime<Utc>) -> Result<Vec<Payment>, sqlx::Error> {
+        let rows = timed_query("payment::find_overdue_installments", sqlx::query("
+            SELECT p.id, p.transaction_id, p.amount, p.method, p.status, p.payment_date, p.due_date, p.created_by, p.updated_by,
+                   i.id AS installment_id, i.amount AS installment_amount, i.payment_date AS installment_payment_date
+            FROM payments p
+            LEFT JOIN installments i ON i.payment_id = p.id
+            WHERE p.status = $1 AND p.due_date IS NOT NULL AND p.due_date < $2
+            ORDER BY p.due_date ASC, p.id ASC, i.payment_date ASC
+        ")
+            .bind(PaymentStatus::Installment.to_string())
+            .bind(now.to_rfc3339())
+            .fetch_all(&mut *db))
+            .await?;
+
+        Self::group_joined_rows(rows)
+    }
+
+    /// Menggabungkan baris hasil `LEFT JOIN installments` menjadi satu `Payment` per id,
+    /// dengan urutan payment mengikuti urutan baris.
+    fn group_joined_rows(rows: Vec<AnyRow>) -> Result<Vec<Payment>, sqlx::Error> {
+        let mut payments: Vec<Payment> = Vec::new();
+        let mut index_by_id: HashMap<String, usize> = HashMap::new();
+        for row in rows {
+            let payment_id: String = row.get("id");
+            let installment = Self::parse_joined_installment(&row, &payment_id)?;
+
+            let index = match index_by_id.get(&payment_id) {
+                Some(index) => *index,
+                None => {
+                    payments.push(Self::parse_row_to_payment(row)?);
+                    index_by_id.insert(payment_id, payments.len() - 1);
+                    payments.len() - 1
+                }
+            };
+
+            if let Some(installment) = installment {
+                payments[index].installments.push(installment);
+            }
+        }
+
+        for payment in &mut payments {
+            payment.installments.sort_by(|a, b| a.payment_date.cmp(&b.payment_date));
+        }
+
+        Ok(payments)
+    }
+
+    fn build_filter_clause(filters: Option<&HashMap<String, String>>, column_prefix: &str) -> (String, Vec<String>) {
+        let mut where_clauses = Vec::new();
+        let mut bind_values: Vec<String> = Vec::new();
+
+        // `date_from`/`date_to` dibandingkan sebagai teks RFC3339; `date_to` eksklusif
+        let conditions = [
+            ("status", "status ="),
+            ("method", "method ="),
+            ("transaction_id", "transaction_id ="),
+            ("created_by", "created_by ="),
+            ("date_from", "payment_date >="),
+            ("date_to", "payment_date <"),
+        ];
+        if let Some(filter_map) = filters {
+            for (key, condition) in conditions {
+                if let Some(value) = filter_map.get(key) {
+                    let param_num = bind_values.len() + 1;
+                    where_clauses.push(format!("{column_prefix}{condition} ${param_num}"));
+                    bind_values.push(value.clone());
+                }
+            }
+            // `keyword` (sudah lowercase) dicocokkan ke id dan transaction_id dengan satu placeholder
+            if let Some(keyword) = filter_map.get("keyword") {
+                let param_num = bind_values.len() + 1;
+                where_clauses.push(format!("(LOWER({column_prefix}id) LIKE ${param_num} ESCAPE '\\' OR LOWER({column_prefix}transaction_id) LIKE ${param_num} ESCAPE '\\')"));
+                bind_values.push(format!("%{}%", escape_like(keyword)));
+            }
+        }
+
+        if where_clauses.is_empty() {
+            (String::new(), bind_values)
+        } else {
+            (format!(" WHERE {}", where_clauses.join(" AND ")), bind_values)
+        }
+    }
     
+    /// Total transaksi penjualan yang dirujuk `transaction_id`, atau `None` jika
+    /// id bukan angka atau transaksinya tidak ada.
+    pub async fn find_transaction_total(mut db: PoolConnection<Any>, transaction_id: &str) -> Result<Option<f64>, sqlx::Error> {
+        let Ok(id) = transaction_id.trim().parse::<i32>() else {
+            return Ok(None);
+        };
+
+        let row = timed_query("payment::find_transaction_total", sqlx::query("SELECT CAST(total_harga AS DOUBLE PRECISION) AS total_harga FROM transaksi WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&mut *db))
+            .await?;
+
+        row.map(|row| row.try_get::<f64, _>("total_harga")).transpose()
+    }
+
+    /// Status transaksi penjualan yang dibayar payment ini; `None` bila id bukan transaksi yang dikenal.
+    pub async fn find_transaction_status(mut db: PoolConnection<Any>, transaction_id: &str) -> Result<Option<String>, sqlx::Error> {
+        let Ok(id) = transaction_id.trim().parse::<i32>() else {
+            return Ok(None);
+        };
+
+        let row = timed_query("payment::find_transaction_status", sqlx::query("SELECT status FROM transaksi WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&mut *db))
+            .await?;
+
+        row.map(|row| row.try_get::<String, _>("status")).transpose()
+    }
+
+    /// Total `amount` payment transaksi yang uangnya diterima, dikurangi refund.
+    pub async fn sum_amount_by_transaction(mut db: PoolConnection<Any>, transaction_id: &str) -> Result<f64, sqlx::Error> {
+        let sql = format!(
+            "SELECT CAST(COALESCE(SUM(p.amount - {}), 0) AS DOUBLE PRECISION) AS total_paid FROM payments p WHERE p.transaction_id = $1 AND {}",
+            Self::REFUNDED_AMOUNT,
+            Self::COLLECTED_STATUS_FILTER
+        );
+        let row = timed_query("payment::sum_amount_by_transaction", sqlx::query(&sql)
+            .bind(transaction_id)
+            .fetch_one(&mut *db))
+            .await?;
+
+        row.try_get("total_paid")
+    }
+
+    pub async fn totals_by_transaction(mut db: PoolConnection<Any>, transaction_id: &str) -> Result<PaymentTotals, sqlx::Error> {
+        let sql = format!("
+            SELECT CAST(COALESCE(SUM(
+                       CASE WHEN p.status = 'CICILAN'
+                            THEN COALESCE((SELECT SUM(i.amount) FROM installments i WHERE i.payment_id = p.id), 0)
+                            ELSE p.amount
+                       END - {refunded}), 0) AS DOUBLE PRECISION) AS paid_total,
+                   COUNT(*) AS payment_count,
+                   MAX(p.payment_date) AS last_payment_date
+            FROM payments p
+            WHERE p.transaction_id = $1 AND {collected}
+        ", refunded = Self::REFUNDED_AMOUNT, collected = Self::COLLECTED_STATUS_FILTER);
+        let row = timed_query("payment::totals_by_transaction", sqlx::query(&sql)
+            .bind(transaction_id)
+            .fetch_one(&mut *db))
+            .await?;
+
+        let last_payment_date = row.try_get::<String, _>("last_payment_date").ok()
+            .and_then(|value| DateTime::parse_from_rfc3339(&value).ok())
+            .map(|dt| dt.with_timezone(&Utc));
+
+        Ok(PaymentTotals {
+            paid_total: row.try_get("paid_total")?,
+            payment_count: row.try_get("payment_count")?,
+            last_payment_date,
+        })
+    }
+
+    /// Seperti `totals_by_transaction`, tetapi dipecah per metode pembayaran (urut nama metode).
+    pub async fn totals_by_method_for_transaction(mut db: PoolConnection<Any>, transaction_id: &str) -> Result<Vec<PaymentMethodTotal>, sqlx::Error> {
+        let sql = format!("
+            SELECT p.method,
+                   CAST(COALESCE(SUM(
+                       CASE WHEN p.status = 'CICILAN'
+                            THEN COALESCE((SELECT SUM(i.amount) FROM installments i WHERE i.payment_id = p.id), 0)
+                            ELSE p.amount
+                       END - {refunded}), 0) AS DOUBLE PRECISION) AS paid_total,
+                   COUNT(*) AS payment_count
+            FROM payments p
+            WHERE p.transaction_id = $1 AND {collected}
+            GROUP BY p.method
+            ORDER BY p.method
+        ", refunded = Self::REFUNDED_AMOUNT, collected = Self::COLLECTED_STATUS_FILTER);
+        let rows = timed_query("payment::totals_by_method_for_transaction", sqlx::query(&sql)
+            .bind(transaction_id)
+            .fetch_all(&mut *db))
+            .await?;
+
+        rows.iter().map(|row| {
+            let method: String = row.try_get("method")?;
+            Ok(PaymentMethodTotal {
+                method: Self::decode_method(&method)?,
+                paid_total: row.try_get("paid_total")?,
+                payment_count: row.try_get("payment_count")?,
+            })
+        }).collect()
+    }
+
+    /// Jumlah payment dan total `amount` (dikurangi refund) per bulan kalender `payment_date` pada
+    /// `year`, hanya untuk payment yang uangnya diterima (lihat `COLLECTED_STATUS_FILTER`).
+    /// Hanya bulan yang punya payment yang dikembalikan, urut bulan.
+    pub async fn monthly_totals(mut db: PoolConnection<Any>, year: i32) -> Result<Vec<MonthlyRevenue>, sqlx::Error> {
+        let month = date_trunc_expr(DbKind::of(&db), DateUnit::Month, "p.payment_date");
+        let sql = format!("
+            SELECT {month} AS month,
+                   COUNT(*) AS payment_count,
+                   CAST(COALESCE(SUM(p.amount - {refunded}), 0) AS DOUBLE PRECISION) AS total
+            FROM payments p
+            WHERE {month} BETWEEN $1 AND $2 AND {collected}
+            GROUP BY {month}
+            ORDER BY month
+        ", refunded = Self::REFUNDED_AMOUNT, collected = Self::COLLECTED_STATUS_FILTER);
+        let rows = timed_query("payment::monthly_totals", sqlx::query(&sql)
+            .bind(format!("{year:04}-01"))
+            .bind(format!("{year:04}-12"))
+            .fetch_all(&mut *db))
+            .await?;
+
+        rows.iter().map(|row| {
+            Ok(MonthlyRevenue {
+                month: row.try_get("month")?,
+                payment_count: row.try_get("payment_count")?,
+                total: row.try_get("total")?,
+            })
+        }).collect()
+    }
+
+    /// Menyimpan payment CICILAN beserta jadwal cicilannya dalam satu DB transaction.
+    /// Insert payment beserta jadwal cicilannya hanya bila total yang sudah dibayar untuk
+    /// transaksinya (lihat `sum_amount_by_transaction`) tidak melebihi `max_already_paid`.
+    /// Pengecekan dan insert ada dalam satu DB transaction; di PostgreSQL baris transaksi
+    /// dikunci dulu supaya dua rencana bersamaan tidak sama-sama lolos. `RowNotFound` bila
+    /// transaksi sudah dibayar.
+    pub async fn create_with_schedule(mut db: PoolConnection<Any>, payment: &Payment, schedule: &[ScheduledInstallment], max_already_paid: f64) -> Result<Payment, sqlx::Error> {
+        let mut tx = db.begin().await?;
+
+        if DbKind::of(&tx) == DbKind::Postgres {
+            timed_query("payment::create_with_schedule", sqlx::query("SELECT id FROM transaksi WHERE CAST(id AS TEXT) = $1 FOR UPDATE")
+                .bind(&payment.transaction_id)
+                .fetch_optional(&mut *tx))
+                .await?;
+        }
+        let sql = format!(
+            "SELECT CAST(COALESCE(SUM(p.amount - {}), 0) AS DOUBLE PRECISION) AS total_paid FROM payments p WHERE p.transaction_id = $1 AND {}",
+            Self::REFUNDED_AMOUNT,
+            Self::COLLECTED_STATUS_FILTER
+        );
+        let already_paid: f64 = timed_query("payment::create_with_schedule", sqlx::query(&sql)
+            .bind(&payment.transaction_id)
+            .fetch_one(&mut *tx))
+            .await?
+            .try_get("total_paid")?;
+        if already_paid > max_already_paid {
+            return Err(sqlx::Error::RowNotFound);
+        }
+
+        timed_query("payment::create_with_schedule", sqlx::query("
+            INSERT INTO payments (id, transaction_id, amount, method, status, payment_date, due_date, created_by, updated_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        ")
+            .bind(&payment.id)
+            .bind(&payment.transaction_id)
+            .bind(payment.amount)
+            .bind(payment.method.to_string())
+            .bind(payment.status.to_string())
+            .bind(payment.payment_date.to_rfc3339())
+            .bind(payment.due_date.map(|d| d.to_rfc3339()))
+            .bind(&payment.audit.created_by)
+            .bind(&payment.audit.updated_by)
+            .execute(&mut *tx))
+            .await?;
+
+        for item in schedule {
+            timed_query("payment::create_with_schedule", sqlx::query("
+                INSERT INTO installment_schedules (id, payment_id, sequence, amount, due_date)
+                VALUES ($1, $2, $3, $4, $5)
+            ")
+                .bind(&item.id)
+                .bind(&item.payment_id)
+                .bind(item.sequence)
+                .bind(item.amount)
+                .bind(item.due_date.to_rfc3339())
+                .execute(&mut *tx))
+                .await?;
+        }
+
+        tx.commit().await?;
+        Self::load_payment_with_installments(&mut db, &payment.id).await
+    }
+
+    pub async fn find_schedule(mut db: PoolConnection<Any>, payment_id: &str) -> Result<Vec<ScheduledInstallment>, sqlx::Error> {
+        let rows = timed_query("payment::find_schedule", sqlx::query("
+            SELECT id, payment_id, sequence, amount, due_date
+            FROM installment_schedules
+            WHERE payment_id = $1
+            ORDER BY sequence ASC
+        ")
+            .bind(payment_id)
+            .fetch_all(&mut *db))
+            .await?;
+
+        rows.into_iter().map(|row| {
+            let due_date_str: String = row.get("due_date");
+            let due_date = DateTime::parse_from_rfc3339(&due_date_str)
+                .map(|dt| dt.with_timezone(&Utc))
+                .map_err(|_| sqlx::Error::RowNotFound)?;
+            Ok(ScheduledInstallment {
+                id: row.get("id"),
+                payment_id: row.get("payment_id"),
+                sequence: row.get("sequence"),
+                amount: row.try_get("amount")?,
+                due_date,
+            })
+        }).collect()
+    }
+
     pub async fn update(mut db: PoolConnection<Any>, payment: &Payment) -> Result<Payment, sqlx::Error>{
         let payment_method_str = payment.method.to_string();
         let status_str = payment.status.to_string();
-        sqlx::query("
+        timed_query("payment::update", sqlx::query("
             UPDATE payments
-            SET transaction_id = $1, amount = $2, method = $3, status = $4, payment_date = $5, due_date = $6
-            WHERE id = $7
+            SET transaction_id = $1, amount = $2, method = $3, status = $4, payment_date = $5, due_date = $6, updated_by = COALESCE($7, updated_by)
+            WHERE id = $8
         ")
         .bind(&payment.transaction_id)
         .bind(payment.amount)
@@ -116,17 +541,18 @@ impl PembayaranRepository {
         .bind(&status_str)
         .bind(payment.payment_date.to_rfc3339())
         .bind(payment.due_date.map(|d| d.to_rfc3339()))
+        .bind(&payment.audit.updated_by)
         .bind(&payment.id)
-        .execute(&mut *db)
+        .execute(&mut *db))
         .await?;
 
-        let result = sqlx::query("
-            SELECT id, transaction_id, amount, method, status, payment_date, due_date
+        let result = timed_query("payment::update", sqlx::query("
+            SELECT id, transaction_id, amount, method, status, payment_date, due_date, created_by, updated_by
             FROM payments
             WHERE id = $1
         ")
         .bind(&payment.id)
-        .fetch_one(&mut *db)
+        .fetch_one(&mut *db))
         .await?;
         
         let updated_payment = Self::parse_row_to_payment(result)?;
@@ -135,17 +561,18 @@ impl PembayaranRepository {
         Ok(payment_with_installments)
     }
 
-    pub async fn update_payment_status(mut db: PoolConnection<Any>, payment_id: String, new_status: PaymentStatus, additional_amount: Option<f64>) -> Result<Payment, sqlx::Error> {        let payment_result = sqlx::query("
-            SELECT id, transaction_id, amount, method, status, payment_date, due_date
+    pub async fn update_payment_status(mut db: PoolConnection<Any>, payment_id: String, new_status: PaymentStatus, additional_amount: Option<f64>, updated_by: Option<String>) -> Result<Payment, sqlx::Error> {        let payment_result = timed_query("payment::update_payment_status", sqlx::query("
+            SELECT id, transaction_id, amount, method, status, payment_date, due_date, created_by, updated_by
             FROM payments
             WHERE id = $1
         ")
-        .bind(&payment_id)        .fetch_one(&mut *db)
+        .bind(&payment_id)        .fetch_one(&mut *db))
         .await?;
         
         let mut payment = Self::parse_row_to_payment(payment_result)?;
         
         payment.status = new_status;
+        payment.audit.touch(updated_by.as_deref());
         
         if let Some(amount) = additional_amount {
             let installment = Installment {
@@ -161,22 +588,58 @@ impl PembayaranRepository {
         Self::update(db, &payment).await
     }
 
+    /// Hanya kolom `method` (dan `updated_by`) yang diubah; field lain dibiarkan apa adanya.
+    /// Payment yang sudah punya refund tidak diubah dan menghasilkan `RowNotFound`.
+    pub async fn update_method(mut db: PoolConnection<Any>, payment_id: &str, method: &PaymentMethod, updated_by: Option<String>) -> Result<Payment, sqlx::Error> {
+        let result = timed_query("payment::update_method", sqlx::query("
+            UPDATE payments SET method = $1, updated_by = COALESCE($2, updated_by)
+            WHERE id = $3
+              AND NOT EXISTS (SELECT 1 FROM refunds WHERE refunds.payment_id = payments.id)
+        ")
+            .bind(method.to_string())
+            .bind(updated_by)
+            .bind(payment_id)
+            .execute(&mut *db))
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        Self::load_payment_with_installments(&mut db, payment_id).await
+    }
+
     pub async fn delete(mut db: PoolConnection<Any>, id: &str) -> Result<(), sqlx::Error>{
-        sqlx::query("DELETE FROM installments WHERE payment_id = $1")
+        timed_query("payment::delete", sqlx::query("DELETE FROM installments WHERE payment_id = $1")
             .bind(id)
-            .execute(&mut *db)
+            .execute(&mut *db))
             .await?;
         
-        sqlx::query("DELETE FROM payments WHERE id = $1")
+        timed_query("payment::delete", sqlx::query("DELETE FROM payments WHERE id = $1")
             .bind(id)
-            .execute(&mut *db)
+            .execute(&mut *db))
             .await?;
         
         Ok(())
     }
     
-    pub async fn add_installment(db: &mut PoolConnection<Any>, installment: &Installment) -> Result<(), sqlx::Error> {
-        sqlx::query("
+    /// MENUNGGU yang `payment_date`-nya sebelum `cutoff` diubah menjadi GAGAL; mengembalikan jumlah baris.
+    /// `payment_date` disimpan sebagai RFC3339 UTC sehingga perbandingan teks sama dengan urutan waktu.
+    pub async fn expire_pending_before(mut db: PoolConnection<Any>, cutoff: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+        let result = timed_query("payment::expire_pending_before", sqlx::query("
+            UPDATE payments SET status = $1
+            WHERE status = $2 AND payment_date < $3
+        ")
+            .bind(PaymentStatus::Failed.to_string())
+            .bind(PaymentStatus::Pending.to_string())
+            .bind(cutoff.to_rfc3339())
+            .execute(&mut *db))
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn add_installment(conn: &mut AnyConnection, installment: &Installment) -> Result<(), sqlx::Error> {
+        timed_query("payment::add_installment", sqlx::query("
             INSERT INTO installments (id, payment_id, amount, payment_date)
             VALUES ($1, $2, $3, $4)
         ")
@@ -184,31 +647,147 @@ impl PembayaranRepository {
         .bind(&installment.payment_id)
         .bind(installment.amount)
         .bind(installment.payment_date.to_rfc3339())
-        .execute(&mut **db)
+        .execute(&mut *conn))
         .await?;
         
         Ok(())
     }    
+
+    /// Mencatat cicilan dalam satu DB transaction tanpa menimpa baris payment dari salinan
+    /// di memori: status dikunci dan dicek masih CICILAN, baris cicilan di-insert, lalu
+    /// (bila `recompute_status`) status dihitung ulang dari jumlah cicilan di database.
+    /// `RowNotFound` bila payment tidak ada atau sudah tidak berstatus CICILAN.
+    pub async fn record_installment(mut db: PoolConnection<Any>, installment: &Installment, recompute_status: bool, updated_by: Option<String>) -> Result<Payment, sqlx::Error> {
+        let mut tx = db.begin().await?;
+
+        let locked = timed_query("payment::record_installment", sqlx::query("
+            UPDATE payments SET status = $1, updated_by = COALESCE($3, updated_by) WHERE id = $2 AND status = $1
+        ")
+            .bind(PaymentStatus::Installment.to_string())
+            .bind(&installment.payment_id)
+            .bind(updated_by)
+            .execute(&mut *tx))
+            .await?;
+        if locked.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+
+        Self::add_installment(&mut tx, installment).await?;
+
+        if recompute_status {
+            // Toleransi setengah sen sama dengan `Payment::recompute_status`
+            timed_query("payment::record_installment", sqlx::query("
+                UPDATE payments SET status = CASE
+                    WHEN (SELECT COALESCE(SUM(amount), 0) FROM installments WHERE payment_id = $1) + 0.005 >= amount THEN $2
+                    ELSE $3
+                END
+                WHERE id = $1
+            ")
+                .bind(&installment.payment_id)
+                .bind(PaymentStatus::Paid.to_string())
+                .bind(PaymentStatus::Installment.to_string())
+                .execute(&mut *tx))
+                .await?;
+        }
+
+        tx.commit().await?;
+        Self::load_payment_with_installments(&mut db, &installment.payment_id).await
+    }
+
+    /// Mencatat refund hanya bila total refund payment ini (termasuk yang baru) tidak melebihi
+    /// `refundable_total`. Baris payment dikunci dulu supaya dua refund bersamaan tidak sama-sama
+    /// lolos pengecekan. `RowNotFound` bila payment tidak ada atau batasnya terlampaui.
+    pub async fn record_refund(mut db: PoolConnection<Any>, refund: &Refund, refundable_total: f64) -> Result<(), sqlx::Error> {
+        let mut tx = db.begin().await?;
+        Self::insert_refund_within_limit(&mut tx, refund, refundable_total).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Inti `record_refund` tanpa membuka DB transaction sendiri, untuk dipakai di dalam
+    /// transaction pemanggil (mis. void transaksi penjualan).
+    pub async fn insert_refund_within_limit(conn: &mut AnyConnection, refund: &Refund, refundable_total: f64) -> Result<(), sqlx::Error> {
+        let locked = timed_query("payment::record_refund", sqlx::query("
+            UPDATE payments SET status = status WHERE id = $1
+        ")
+            .bind(&refund.payment_id)
+            .execute(&mut *conn))
+            .await?;
+        if locked.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+
+        // Toleransi setengah sen sama dengan pengecekan kelebihan bayar
+        let inserted = timed_query("payment::record_refund", sqlx::query("
+            INSERT INTO refunds (id, payment_id, amount, reason, refund_date, created_by)
+            SELECT $1, $2, $3, $4, $5, $6
+            WHERE (SELECT COALESCE(SUM(amount), 0) FROM refunds WHERE payment_id = $2) + $3 <= $7 + 0.005
+        ")
+            .bind(&refund.id)
+            .bind(&refund.payment_id)
+            .bind(refund.amount)
+            .bind(&refund.reason)
+            .bind(refund.refund_date.to_rfc3339())
+            .bind(&refund.created_by)
+            .bind(refundable_total)
+            .execute(&mut *conn))
+            .await?;
+        if inserted.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        Ok(())
+    }
+
+    pub async fn find_refunds(mut db: PoolConnection<Any>, payment_id: &str) -> Result<Vec<Refund>, sqlx::Error> {
+        Self::fetch_refunds(&mut db, payment_id).await
+    }
+
+    pub async fn fetch_refunds(conn: &mut AnyConnection, payment_id: &str) -> Result<Vec<Refund>, sqlx::Error> {
+        let rows = timed_query("payment::find_refunds", sqlx::query("
+            SELECT id, payment_id, amount, reason, refund_date, created_by
+            FROM refunds
+            WHERE payment_id = $1
+            ORDER BY refund_date ASC, id ASC
+        ")
+            .bind(payment_id)
+            .fetch_all(&mut *conn))
+            .await?;
+
+        rows.into_iter().map(|row| {
+            let refund_date_str: String = row.get("refund_date");
+            let refund_date = DateTime::parse_from_rfc3339(&refund_date_str)
+                .map(|dt| dt.with_timezone(&Utc))
+                .map_err(|e| sqlx::Error::ColumnDecode { index: "refund_date".to_string(), source: Box::new(e) })?;
+            Ok(Refund {
+                id: row.get("id"),
+                payment_id: row.get("payment_id"),
+                amount: row.try_get("amount")?,
+                reason: row.get("reason"),
+                refund_date,
+                created_by: row.try_get("created_by").ok(),
+            })
+        }).collect()
+    }
     
     pub async fn load_payment_with_installments(db: &mut PoolConnection<Any>, payment_id: &str) -> Result<Payment, sqlx::Error> {        
-        let payment_row = sqlx::query("
-            SELECT id, transaction_id, amount, method, status, payment_date, due_date
+        let payment_row = timed_query("payment::load_payment_with_installments", sqlx::query("
+            SELECT id, transaction_id, amount, method, status, payment_date, due_date, created_by, updated_by
             FROM payments
             WHERE id = $1
         ")        .bind(payment_id)
-        .fetch_one(&mut **db)
+        .fetch_one(&mut **db))
         .await?;
         
         let mut payment = Self::parse_row_to_payment(payment_row)?;
         
-        let installment_rows = sqlx::query("
+        let installment_rows = timed_query("payment::load_payment_with_installments", sqlx::query("
             SELECT id, payment_id, amount, payment_date
             FROM installments
             WHERE payment_id = $1
             ORDER BY payment_date ASC
         ")
         .bind(payment_id)
-        .fetch_all(&mut **db)
+        .fetch_all(&mut **db))
         .await?;
         
         let mut installments = Vec::with_capacity(installment_rows.len());
@@ -222,51 +801,22 @@ impl PembayaranRepository {
         Ok(payment)
     }
     
-    fn parse_row_to_payment(row: AnyRow) -> Result<Payment, sqlx::Error> {
+    pub(crate) fn parse_row_to_payment(row: AnyRow) -> Result<Payment, sqlx::Error> {
         let id: String = row.get("id");
         let transaction_id: String = row.get("transaction_id");
-        let amount: f64 = row.try_get("amount").unwrap_or_else(|_| {
-            row.try_get::<f32, _>("amount").map(|v| v as f64).unwrap_or(0.0)
-        });        let payment_method_str: String = row.get("method");
+        let amount = Self::decode_amount(&row, "amount")?;
+        let payment_method_str: String = row.get("method");
         let status_str: String = row.get("status");
         let payment_date_str: String = row.get("payment_date");
         let due_date_str: Option<String> = row.try_get("due_date").ok();
         
-        let payment_method = match payment_method_str.as_str() {
-            "CASH" => PaymentMethod::Cash,
-            "CREDIT_CARD" => PaymentMethod::CreditCard,
-            "BANK_TRANSFER" => PaymentMethod::BankTransfer,
-            "E_WALLET" => PaymentMethod::EWallet,
-            _ => return Err(sqlx::Error::RowNotFound),
-        };
+        let payment_method = Self::decode_method(&payment_method_str)?;
         let payment_status = PaymentStatus::from_string(&status_str)
-            .ok_or(sqlx::Error::RowNotFound)?;        let payment_date = DateTime::parse_from_rfc3339(&payment_date_str)
-            .map(|dt| dt.with_timezone(&Utc))
-            .or_else(|_| {
-                NaiveDateTime::parse_from_str(&payment_date_str, "%Y-%m-%d %H:%M:%S%.f")
-                    .or_else(|_| {
-                        NaiveDateTime::parse_from_str(&payment_date_str, "%Y-%m-%d %H:%M:%S")
-                    })
-                    .map(|naive_dt| naive_dt.and_utc())
-            })            .map_err(|e| {
-                eprintln!("Failed to parse payment_date '{payment_date_str}': {e}");
-                sqlx::Error::RowNotFound
-            })?;let due_date = due_date_str
-            .map(|d| {
-                DateTime::parse_from_rfc3339(&d)
-                    .map(|dt| dt.with_timezone(&Utc))
-                    .or_else(|_| {
-                        NaiveDateTime::parse_from_str(&d, "%Y-%m-%d %H:%M:%S%.f")
-                            .or_else(|_| {
-                                NaiveDateTime::parse_from_str(&d, "%Y-%m-%d %H:%M:%S")
-                            })
-                            .map(|naive_dt| naive_dt.and_utc())
-                    })
-            })
-            .transpose()            .map_err(|e| {
-                eprintln!("Failed to parse due_date: {e}");
-                sqlx::Error::RowNotFound
-            })?;
+            .ok_or(sqlx::Error::RowNotFound)?;
+        let payment_date = Self::parse_payment_date(&payment_date_str, "payment_date")?;
+        let due_date = due_date_str
+            .map(|d| Self::parse_payment_date(&d, "due_date"))
+            .transpose()?;
         
         Ok(Payment {
             id,
@@ -277,27 +827,59 @@ impl PembayaranRepository {
             payment_date,
             installments: Vec::new(), 
             due_date,
+            audit: AuditTrail {
+                created_by: row.try_get("created_by").ok(),
+                updated_by: row.try_get("updated_by").ok(),
+            },
         })
     }
     
-    fn parse_row_to_installment(row: AnyRow) -> Result<Installment, sqlx::Error> {
-        let id: String = row.get("id");
-        let payment_id: String = row.get("payment_id");
-        let amount: f64 = row.try_get("amount").unwrap_or_else(|_| {
-            row.try_get::<f32, _>("amount").map(|v| v as f64).unwrap_or(0.0)
-        });
-        let payment_date_str: String = row.get("payment_date");        let payment_date = DateTime::parse_from_rfc3339(&payment_date_str)
+    /// Nominal uang dari kolom `column`. Nilai yang tidak bisa dibaca sebagai angka
+    /// menjadi error, bukan 0.0 diam-diam yang merusak data keuangan.
+    fn decode_method(method: &str) -> Result<PaymentMethod, sqlx::Error> {
+        match method {
+            "CASH" => Ok(PaymentMethod::Cash),
+            "CREDIT_CARD" => Ok(PaymentMethod::CreditCard),
+            "BANK_TRANSFER" => Ok(PaymentMethod::BankTransfer),
+            "E_WALLET" => Ok(PaymentMethod::EWallet),
+            _ => Err(sqlx::Error::RowNotFound),
+        }
+    }
+
+    fn decode_amount(row: &AnyRow, column: &str) -> Result<f64, sqlx::Error> {
+        match row.try_get::<f64, _>(column) {
+            Ok(amount) => Ok(amount),
+            Err(f64_error) => row.try_get::<f32, _>(column).map(|v| v as f64)
+                .or_else(|_| row.try_get::<i64, _>(column).map(|v| v as f64))
+                .map_err(|_| {
+                    log::error!("Unreadable amount in column '{column}': {f64_error}");
+                    sqlx::Error::ColumnDecode { index: column.to_string(), source: Box::new(f64_error) }
+                }),
+        }
+    }
+    
+    /// Tanggal payment/cicilan dalam RFC 3339 (Postgres) atau `YYYY-MM-DD HH:MM:SS[.f]` (SQLite).
+    /// Nilai yang tidak bisa dibaca menjadi error decode, bukan "baris tidak ditemukan".
+    fn parse_payment_date(value: &str, column: &str) -> Result<DateTime<Utc>, sqlx::Error> {
+        DateTime::parse_from_rfc3339(value)
             .map(|dt| dt.with_timezone(&Utc))
             .or_else(|_| {
-                NaiveDateTime::parse_from_str(&payment_date_str, "%Y-%m-%d %H:%M:%S%.f")
-                    .or_else(|_| {
-                        NaiveDateTime::parse_from_str(&payment_date_str, "%Y-%m-%d %H:%M:%S")
-                    })
+                NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f")
+                    .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S"))
                     .map(|naive_dt| naive_dt.and_utc())
-            })            .map_err(|e| {
-                eprintln!("Failed to parse installment payment_date '{payment_date_str}': {e}");
-                sqlx::Error::RowNotFound
-            })?;
+            })
+            .map_err(|e| {
+                log::error!("Failed to parse {column} '{value}': {e}");
+                sqlx::Error::Decode(Box::new(e))
+            })
+    }
+
+    fn parse_row_to_installment(row: AnyRow) -> Result<Installment, sqlx::Error> {
+        let id: String = row.get("id");
+        let payment_id: String = row.get("payment_id");
+        let amount = Self::decode_amount(&row, "amount")?;
+        let payment_date_str: String = row.get("payment_date");
+        let payment_date = Self::parse_payment_date(&payment_date_str, "installment payment_date")?;
         
         Ok(Installment {
             id,
@@ -306,6 +888,25 @@ impl PembayaranRepository {
             payment_date,
         })
     }
+
+    pub(crate) fn parse_joined_installment(row: &AnyRow, payment_id: &str) -> Result<Option<Installment>, sqlx::Error> {
+        // LEFT JOIN tanpa cicilan menghasilkan NULL; driver Any gagal decode NULL ke Option
+        let installment_id: Option<String> = row.try_get("installment_id").ok();
+        let Some(id) = installment_id else {
+            return Ok(None);
+        };
+
+        let amount = Self::decode_amount(row, "installment_amount")?;
+        let payment_date_str: String = row.try_get("installment_payment_date")?;
+        let payment_date = Self::parse_payment_date(&payment_date_str, "installment payment_date")?;
+
+        Ok(Some(Installment {
+            id,
+            payment_id: payment_id.to_string(),
+            amount,
+            payment_date,
+        }))
+    }
 }
 
 #[cfg(test)]
@@ -339,7 +940,9 @@ mod tests {
                 method TEXT NOT NULL,
                 status TEXT NOT NULL,
                 payment_date TEXT NOT NULL,
-                due_date TEXT
+                due_date TEXT,
+                created_by TEXT,
+                updated_by TEXT
             )
             "#
         )
@@ -362,6 +965,23 @@ mod tests {
         .await
         .expect("Failed to create installments table");
 
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS refunds (
+                id TEXT PRIMARY KEY,
+                payment_id TEXT NOT NULL,
+                amount REAL NOT NULL,
+                reason TEXT NOT NULL,
+                refund_date TEXT NOT NULL,
+                created_by VARCHAR(100),
+                FOREIGN KEY (payment_id) REFERENCES payments(id) ON DELETE CASCADE
+            )
+            "#
+        )
+        .execute(&db_pool)
+        .await
+        .expect("Failed to create refunds table");
+
         db_pool
     }
 
@@ -375,6 +995,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: Some(Utc::now()),
+            audit: Default::default(),
         }
     }
 
@@ -402,7 +1023,85 @@ mod tests {
                 }
             ],
             due_date: Some(Utc::now()),
+            audit: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_method_skips_refunded_payments() {
+        let db_pool = setup_test_db().await;
+        let payment = create_test_payment();
+        PembayaranRepository::create(db_pool.acquire().await.unwrap(), &payment).await.unwrap();
+
+        let updated = PembayaranRepository::update_method(db_pool.acquire().await.unwrap(), &payment.id, &PaymentMethod::EWallet, None).await.unwrap();
+        assert_eq!(updated.method, PaymentMethod::EWallet);
+
+        let refund = Refund {
+            id: format!("RFD-{}", Uuid::new_v4()),
+            payment_id: payment.id.clone(),
+            amount: 1000.0,
+            reason: "Barang retur".to_string(),
+            refund_date: Utc::now(),
+            created_by: None,
+        };
+        PembayaranRepository::record_refund(db_pool.acquire().await.unwrap(), &refund, payment.amount).await.unwrap();
+
+        let result = PembayaranRepository::update_method(db_pool.acquire().await.unwrap(), &payment.id, &PaymentMethod::Cash, None).await;
+        assert!(matches!(result, Err(sqlx::Error::RowNotFound)));
+        let stored = PembayaranRepository::find_by_id(db_pool.acquire().await.unwrap(), &payment.id).await.unwrap();
+        assert_eq!(stored.method, PaymentMethod::EWallet);
+    }
+
+    #[tokio::test]
+    async fn test_aggregates_skip_pending_and_failed_payments_and_subtract_refunds() {
+        use chrono::Datelike;
+
+        let db_pool = setup_test_db().await;
+        let transaction_id = format!("TXN-AGG-{}", Uuid::new_v4());
+        let seed = |amount: f64, method: PaymentMethod, status: PaymentStatus| Payment {
+            transaction_id: transaction_id.clone(),
+            amount,
+            method,
+            status,
+            due_date: None,
+            ..create_test_payment()
+        };
+        let lunas = seed(100000.0, PaymentMethod::Cash, PaymentStatus::Paid);
+        let mut cicilan = seed(90000.0, PaymentMethod::CreditCard, PaymentStatus::Installment);
+        cicilan.installments = vec![Installment {
+            id: format!("INST-{}", Uuid::new_v4()),
+            payment_id: cicilan.id.clone(),
+            amount: 30000.0,
+            payment_date: Utc::now(),
+        }];
+        let menunggu = seed(50000.0, PaymentMethod::BankTransfer, PaymentStatus::Pending);
+        let gagal = seed(70000.0, PaymentMethod::Cash, PaymentStatus::Failed);
+        for payment in [&lunas, &cicilan, &menunggu, &gagal] {
+            PembayaranRepository::create(db_pool.acquire().await.unwrap(), payment).await.unwrap();
         }
+        let refund = Refund {
+            id: format!("RFD-{}", Uuid::new_v4()),
+            payment_id: lunas.id.clone(),
+            amount: 20000.0,
+            reason: "Barang rusak".to_string(),
+            refund_date: Utc::now(),
+            created_by: None,
+        };
+        PembayaranRepository::record_refund(db_pool.acquire().await.unwrap(), &refund, 100000.0).await.unwrap();
+
+        let already_paid = PembayaranRepository::sum_amount_by_transaction(db_pool.acquire().await.unwrap(), &transaction_id).await.unwrap();
+        assert_eq!(already_paid, 170000.0);
+
+        let totals = PembayaranRepository::totals_by_transaction(db_pool.acquire().await.unwrap(), &transaction_id).await.unwrap();
+        assert_eq!((totals.paid_total, totals.payment_count), (110000.0, 2));
+
+        let by_method = PembayaranRepository::totals_by_method_for_transaction(db_pool.acquire().await.unwrap(), &transaction_id).await.unwrap();
+        let by_method: Vec<(PaymentMethod, f64, i64)> = by_method.into_iter().map(|total| (total.method, total.paid_total, total.payment_count)).collect();
+        assert_eq!(by_method, vec![(PaymentMethod::Cash, 80000.0, 1), (PaymentMethod::CreditCard, 30000.0, 1)]);
+
+        let monthly = PembayaranRepository::monthly_totals(db_pool.acquire().await.unwrap(), Utc::now().year()).await.unwrap();
+        assert_eq!(monthly.len(), 1);
+        assert_eq!((monthly[0].payment_count, monthly[0].total), (2, 170000.0));
     }
 
     #[tokio::test]
@@ -521,6 +1220,142 @@ mod tests {
         assert_eq!(payments[0].status, PaymentStatus::Paid);
     }
 
+    #[tokio::test]
+    async fn test_find_all_without_installments_omits_installments() {
+        let db_pool = setup_test_db().await;
+        let payment = create_test_payment_with_installments();
+
+        let db_conn = db_pool.acquire().await.unwrap();
+        PembayaranRepository::create(db_conn, &payment).await.unwrap();
+
+        let db_conn = db_pool.acquire().await.unwrap();
+        let payments = PembayaranRepository::find_all_without_installments(db_conn, None, PembayaranRepository::DEFAULT_ORDER_BY, &ListQuery::default()).await.unwrap();
+
+        assert_eq!(payments.len(), 1);
+        assert_eq!(payments[0].id, payment.id);
+        assert!(payments[0].installments.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_find_all_default_order_is_deterministic() {
+        let db_pool = setup_test_db().await;
+        let same_date = Utc::now();
+        let mut expected_ids = Vec::new();
+        for _ in 0..4 {
+            let mut payment = create_test_payment_with_installments();
+            payment.payment_date = same_date;
+            expected_ids.push(payment.id.clone());
+            let db_conn = db_pool.acquire().await.unwrap();
+            PembayaranRepository::create(db_conn, &payment).await.unwrap();
+        }
+        expected_ids.sort();
+
+        let db_conn = db_pool.acquire().await.unwrap();
+        let first = PembayaranRepository::find_all_without_installments(db_conn, None, PembayaranRepository::DEFAULT_ORDER_BY, &ListQuery::default()).await.unwrap();
+        let db_conn = db_pool.acquire().await.unwrap();
+        let second = PembayaranRepository::find_all_without_installments(db_conn, None, PembayaranRepository::DEFAULT_ORDER_BY, &ListQuery::default()).await.unwrap();
+        let db_conn = db_pool.acquire().await.unwrap();
+        let joined = PembayaranRepository::find_all_with_installments(db_conn, None, PembayaranRepository::DEFAULT_ORDER_BY, &ListQuery::default()).await.unwrap();
+
+        let ids = |payments: &[Payment]| payments.iter().map(|p| p.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&first), expected_ids);
+        assert_eq!(ids(&first), ids(&second));
+        assert_eq!(ids(&first), ids(&joined));
+    }
+
+    #[tokio::test]
+    async fn test_find_all_sorts_filters_and_paginates_in_sql() {
+        let db_pool = setup_test_db().await;
+        let mut ids_by_amount = Vec::new();
+        for (index, amount) in [300.0, 100.0, 400.0, 200.0].into_iter().enumerate() {
+            let mut payment = create_test_payment_with_installments();
+            payment.amount = amount;
+            payment.transaction_id = format!("TXN-LIST-{}", index);
+            ids_by_amount.push((amount as i64, payment.id.clone()));
+            let db_conn = db_pool.acquire().await.unwrap();
+            PembayaranRepository::create(db_conn, &payment).await.unwrap();
+        }
+        let db_conn = db_pool.acquire().await.unwrap();
+        PembayaranRepository::create(db_conn, &create_test_payment()).await.unwrap();
+        ids_by_amount.sort();
+        let expected: Vec<String> = ids_by_amount.into_iter().map(|(_, id)| id).collect();
+
+        // `keyword` mencocokkan transaction_id, `LIMIT` menghitung payment, bukan baris join cicilan
+        let filters = HashMap::from([("keyword".to_string(), "txn-list".to_string())]);
+        let list = ListQuery { page: Some(2), limit: Some(2), ..Default::default() };
+        let ids = |payments: &[Payment]| payments.iter().map(|p| p.id.clone()).collect::<Vec<_>>();
+
+        let db_conn = db_pool.acquire().await.unwrap();
+        let joined = PembayaranRepository::find_all_with_installments(db_conn, Some(filters.clone()), "amount ASC, id ASC", &list).await.unwrap();
+        assert_eq!(ids(&joined), expected[2..].to_vec());
+        assert!(joined.iter().all(|p| p.installments.len() == 2));
+
+        let db_conn = db_pool.acquire().await.unwrap();
+        let light = PembayaranRepository::find_all_without_installments(db_conn, Some(filters), "amount DESC, id ASC", &list).await.unwrap();
+        assert_eq!(ids(&light), vec![expected[1].clone(), expected[0].clone()]);
+    }
+
+    #[tokio::test]
+    async fn test_find_all_with_installments_matches_per_row_loader() {
+        let db_pool = setup_test_db().await;
+        let with_installments = create_test_payment_with_installments();
+        let without_installments = create_test_payment();
+
+        let db_conn = db_pool.acquire().await.unwrap();
+        PembayaranRepository::create(db_conn, &with_installments).await.unwrap();
+        let db_conn = db_pool.acquire().await.unwrap();
+        PembayaranRepository::create(db_conn, &without_installments).await.unwrap();
+
+        let mut filters = HashMap::new();
+        filters.insert("method".to_string(), "CREDIT_CARD".to_string());
+        let db_conn = db_pool.acquire().await.unwrap();
+        let filtered = PembayaranRepository::find_all_with_installments(db_conn, Some(filters), PembayaranRepository::DEFAULT_ORDER_BY, &ListQuery::default()).await.unwrap();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].installments.len(), with_installments.installments.len());
+
+        let db_conn = db_pool.acquire().await.unwrap();
+        let joined = PembayaranRepository::find_all_with_installments(db_conn, None, PembayaranRepository::DEFAULT_ORDER_BY, &ListQuery::default()).await.unwrap();
+        let db_conn = db_pool.acquire().await.unwrap();
+        let per_row = PembayaranRepository::find_all(db_conn, None).await.unwrap();
+
+        assert_eq!(joined.len(), 2);
+        for payment in &per_row {
+            let joined_payment = joined.iter().find(|p| p.id == payment.id).unwrap();
+            let joined_ids: Vec<&String> = joined_payment.installments.iter().map(|i| &i.id).collect();
+            let per_row_ids: Vec<&String> = payment.installments.iter().map(|i| &i.id).collect();
+            assert_eq!(joined_ids, per_row_ids);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_listing_modes_issue_fewer_queries_than_per_row_loader() {
+        use crate::common::slow_query::query_counter;
+
+        let db_pool = setup_test_db().await;
+        for _ in 0..3 {
+            let db_conn = db_pool.acquire().await.unwrap();
+            PembayaranRepository::create(db_conn, &create_test_payment_with_installments()).await.unwrap();
+        }
+
+        query_counter::reset();
+        let db_conn = db_pool.acquire().await.unwrap();
+        PembayaranRepository::find_all(db_conn, None).await.unwrap();
+        assert_eq!(query_counter::count("payment::find_all"), 1);
+        assert_eq!(query_counter::count("payment::load_payment_with_installments"), 3 * 2);
+
+        query_counter::reset();
+        let db_conn = db_pool.acquire().await.unwrap();
+        PembayaranRepository::find_all_with_installments(db_conn, None, PembayaranRepository::DEFAULT_ORDER_BY, &ListQuery::default()).await.unwrap();
+        assert_eq!(query_counter::count("payment::find_all_with_installments"), 1);
+        assert_eq!(query_counter::count("payment::load_payment_with_installments"), 0);
+
+        query_counter::reset();
+        let db_conn = db_pool.acquire().await.unwrap();
+        PembayaranRepository::find_all_without_installments(db_conn, None, PembayaranRepository::DEFAULT_ORDER_BY, &ListQuery::default()).await.unwrap();
+        assert_eq!(query_counter::count("payment::find_all_without_installments"), 1);
+        assert_eq!(query_counter::count("payment::load_payment_with_installments"), 0);
+    }
+
     #[tokio::test]
     async fn test_update_payment_integration() {
         let db_pool = setup_test_db().await;
@@ -554,7 +1389,8 @@ mod tests {
             db_conn, 
             payment.id.clone(), 
             PaymentStatus::Installment, 
-            Some(500.0)
+            Some(500.0),
+            None
         ).await;
         
         assert!(result.is_ok(), "Update payment status failed: {:?}", result.err());
@@ -859,6 +1695,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: None,
+            audit: Default::default(),
         };
 
         test_payment.status = new_status;
@@ -902,6 +1739,7 @@ mod tests {
                 },
             ],
             due_date: Some(Utc::now()),
+            audit: Default::default(),
         };
 
         assert!(!payment.installments.is_empty());
@@ -1014,6 +1852,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: None,
+            audit: Default::default(),
         };
 
         let payment_method_str = payment.method.to_string();
@@ -1112,6 +1951,7 @@ mod tests {
             status: PaymentStatus::Installment,
             payment_date: Utc::now(),
             due_date: Some(Utc::now()),
+            audit: Default::default(),
             installments: vec![
                 Installment {
                     id: "INST-001".to_string(),
@@ -1142,6 +1982,7 @@ mod tests {
             status: PaymentStatus::Paid,
             payment_date: Utc::now(),
             due_date: None,
+            audit: Default::default(),
             installments: vec![],
         };
 
@@ -1327,6 +2168,7 @@ mod tests {
                 status: PaymentStatus::Paid,
                 payment_date: Utc::now(),
                 due_date: None,
+                audit: Default::default(),
                 installments: vec![],
             };
             payments.push(payment);
@@ -1363,6 +2205,7 @@ mod tests {
             status: PaymentStatus::Paid,
             payment_date: Utc::now(),
             due_date: None,
+            audit: Default::default(),
             installments: vec![],
         };
         
@@ -1620,6 +2463,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: None,
+            audit: Default::default(),
         };
         
         assert_eq!(payment_data.id, "PMT-STRUCT-001");
@@ -1644,6 +2488,49 @@ mod tests {
         assert!(!payment_date_str.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_unreadable_amount_is_an_error_not_zero() {
+        let db_pool = setup_test_db().await;
+        sqlx::query("INSERT INTO payments (id, transaction_id, amount, method, status, payment_date) VALUES ($1, $2, $3, $4, $5, $6)")
+            .bind("PMT-CORRUPT")
+            .bind("TXN-1")
+            .bind("not-a-number")
+            .bind("CASH")
+            .bind("LUNAS")
+            .bind(Utc::now().to_rfc3339())
+            .execute(&db_pool)
+            .await
+            .unwrap();
+
+        let result = PembayaranRepository::find_by_id(db_pool.acquire().await.unwrap(), "PMT-CORRUPT").await;
+        assert!(matches!(result, Err(sqlx::Error::ColumnDecode { ref index, .. }) if index == "amount"), "got {result:?}");
+
+        let listed = PembayaranRepository::find_all_without_installments(db_pool.acquire().await.unwrap(), None, PembayaranRepository::DEFAULT_ORDER_BY, &ListQuery::default()).await;
+        assert!(listed.is_err());
+
+        sqlx::query("INSERT INTO payments (id, transaction_id, amount, method, status, payment_date) VALUES ($1, $2, $3, $4, $5, $6)")
+            .bind("PMT-CICIL")
+            .bind("TXN-2")
+            .bind(1000.0)
+            .bind("CASH")
+            .bind("CICILAN")
+            .bind(Utc::now().to_rfc3339())
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO installments (id, payment_id, amount, payment_date) VALUES ($1, $2, $3, $4)")
+            .bind("INST-CORRUPT")
+            .bind("PMT-CICIL")
+            .bind("lima ratus")
+            .bind(Utc::now().to_rfc3339())
+            .execute(&db_pool)
+            .await
+            .unwrap();
+
+        let result = PembayaranRepository::find_by_id(db_pool.acquire().await.unwrap(), "PMT-CICIL").await;
+        assert!(matches!(result, Err(sqlx::Error::ColumnDecode { .. })), "got {result:?}");
+    }
+
     #[test]
     fn test_parse_row_to_installment_amount_conversion() {
         let amount_f64 = 750.0f64;
@@ -1658,21 +2545,15 @@ mod tests {
 
     #[test]
     fn test_parse_row_to_installment_date_formats() {
-        let rfc3339_date = "2023-10-15T10:30:00Z";
-        let standard_date = "2023-10-15 10:30:00.123";
-        let simple_date = "2023-10-15 10:30:00";
-        
-        let parsed_rfc3339 = DateTime::parse_from_rfc3339(rfc3339_date)
-            .map(|dt| dt.with_timezone(&Utc));
-        assert!(parsed_rfc3339.is_ok());
-        
-        let parsed_standard = NaiveDateTime::parse_from_str(standard_date, "%Y-%m-%d %H:%M:%S%.f")
-            .map(|naive_dt| naive_dt.and_utc());
-        assert!(parsed_standard.is_ok());
-        
-        let parsed_simple = NaiveDateTime::parse_from_str(simple_date, "%Y-%m-%d %H:%M:%S")
-            .map(|naive_dt| naive_dt.and_utc());
-        assert!(parsed_simple.is_ok());
+        use chrono::TimeZone;
+
+        let expected = Utc.with_ymd_and_hms(2023, 10, 15, 10, 30, 0).unwrap();
+        for value in ["2023-10-15T10:30:00Z", "2023-10-15 10:30:00.000", "2023-10-15 10:30:00"] {
+            assert_eq!(PembayaranRepository::parse_payment_date(value, "payment_date").unwrap(), expected, "{value}");
+        }
+
+        let invalid = PembayaranRepository::parse_payment_date("15/10/2023", "payment_date");
+        assert!(matches!(invalid, Err(sqlx::Error::Decode(_))), "got {invalid:?}");
     }
 
     #[test]
@@ -1763,6 +2644,7 @@ mod tests {
             status: PaymentStatus::Paid,
             payment_date: Utc::now(),
             due_date: None,
+            audit: Default::default(),
             installments: vec![],
         };
         
@@ -1836,6 +2718,7 @@ mod tests {
                 status: PaymentStatus::Paid,
                 payment_date: Utc::now(),
                 due_date: None,
+                audit: Default::default(),
                 installments: vec![],
             };
             payments.push(payment);
@@ -1857,6 +2740,7 @@ mod tests {
             status: PaymentStatus::Installment,
             payment_date: Utc::now(),
             due_date: Some(Utc::now()),
+            audit: Default::default(),
             installments: vec![],
         };
         
@@ -1957,6 +2841,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: None,
+            audit: Default::default(),
         };
         
         assert!(payment.installments.is_empty());
@@ -2079,6 +2964,7 @@ mod tests {
             payment_date,
             installments: Vec::new(),
             due_date,
+            audit: Default::default(),
         };
 
         assert_eq!(payment.id, id);
@@ -2174,6 +3060,7 @@ mod tests {
                 },
             ],
             due_date: None,
+            audit: Default::default(),
         };
 
         let total_installments: f64 = main_payment.installments.iter().map(|i| i.amount).sum();
@@ -2194,6 +3081,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: Some(Utc::now()),
+            audit: Default::default(),
         };
 
         assert!(payment.installments.is_empty());
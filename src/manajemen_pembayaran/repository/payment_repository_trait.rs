@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use async_trait::async_trait;
+use mockall::automock;
+use chrono::{DateTime, Utc};
+use sqlx::{Any, pool::PoolConnection};
+
+use crate::manajemen_pembayaran::enums::payment_status::PaymentStatus;
+use crate::manajemen_pembayaran::model::payment::{Installment, MonthlyRevenue, Payment, PaymentMethod, PaymentMethodTotal, PaymentTotals, Refund, ScheduledInstallment};
+use crate::manajemen_pembayaran::repository::payment_repository::PembayaranRepository;
+use crate::manajemen_pembayaran::repository::optimized_payment_repository::OptimizedPembayaranRepository;
+use crate::common::pagination::ListQuery;
+
+/// Semua akses data yang dipakai `PaymentService`, supaya implementasi standar,
+/// optimized, maupun mock bisa dipertukarkan lewat trait object.
+#[async_trait]
+#[automock]
+pub trait PaymentRepository: Send + Sync {
+    /// Nama implementasi untuk log saat startup.
+    fn name(&self) -> &'static str;
+    async fn create(&self, payment: &Payment, db: PoolConnection<Any>) -> Result<Payment, sqlx::Error>;
+    /// Cek "belum dibayar" dan insert secara atomik; lihat `PembayaranRepository::create_with_schedule`.
+    async fn create_with_schedule(&self, payment: &Payment, schedule: &[ScheduledInstallment], max_already_paid: f64, db: PoolConnection<Any>) -> Result<Payment, sqlx::Error>;
+    async fn find_by_id(&self, id: &str, db: PoolConnection<Any>) -> Result<Payment, sqlx::Error>;
+    async fn find_by_id_without_installments(&self, id: &str, db: PoolConnection<Any>) -> Result<Payment, sqlx::Error>;
+    async fn find_all(&self, filters: Option<HashMap<String, String>>, with_installments: bool, order_by: &str, list: &ListQuery, db: PoolConnection<Any>) -> Result<Vec<Payment>, sqlx::Error>;
+    async fn count(&self, filters: Option<HashMap<String, String>>, db: PoolConnection<Any>) -> Result<i64, sqlx::Error>;
+    /// Lihat `PembayaranRepository::find_match_candidates`.
+    async fn find_match_candidates(&self, filters: Option<HashMap<String, String>>, amount: Option<f64>, tolerance: f64, db: PoolConnection<Any>) -> Result<Vec<Payment>, sqlx::Error>;
+    async fn update(&self, payment: &Payment, db: PoolConnection<Any>) -> Result<Payment, sqlx::Error>;
+    async fn update_status(&self, payment_id: String, new_status: PaymentStatus, additional_amount: Option<f64>, updated_by: Option<String>, db: PoolConnection<Any>) -> Result<Payment, sqlx::Error>;
+    /// Insert cicilan dan hitung ulang status secara atomik; lihat `PembayaranRepository::record_installment`.
+    async fn record_installment(&self, installment: &Installment, recompute_status: bool, updated_by: Option<String>, db: PoolConnection<Any>) -> Result<Payment, sqlx::Error>;
+    async fn update_method(&self, payment_id: &str, method: &PaymentMethod, updated_by: Option<String>, db: PoolConnection<Any>) -> Result<Payment, sqlx::Error>;
+    /// Insert refund bila masih dalam batas `refundable_total`; lihat `PembayaranRepository::record_refund`.
+    async fn record_refund(&self, refund: &Refund, refundable_total: f64, db: PoolConnection<Any>) -> Result<(), sqlx::Error>;
+    async fn find_refunds(&self, payment_id: &str, db: PoolConnection<Any>) -> Result<Vec<Refund>, sqlx::Error>;
+    /// Jadwal cicilan payment, urut `sequence`; kosong bila payment tidak dibuat lewat rencana cicilan.
+    async fn find_schedule(&self, payment_id: &str, db: PoolConnection<Any>) -> Result<Vec<ScheduledInstallment>, sqlx::Error>;
+    async fn delete(&self, id: &str, db: PoolConnection<Any>) -> Result<(), sqlx::Error>;
+    async fn expire_pending_before(&self, cutoff: DateTime<Utc>, db: PoolConnection<Any>) -> Result<u64, sqlx::Error>;
+    async fn find_overdue_installments(&self, now: DateTime<Utc>, db: PoolConnection<Any>) -> Result<Vec<Payment>, sqlx::Error>;
+    async fn find_transaction_total(&self, transaction_id: &str, db: PoolConnection<Any>) -> Result<Option<f64>, sqlx::Error>;
+    async fn find_transaction_status(&self, transaction_id: &str, db: PoolConnection<Any>) -> Result<Option<String>, sqlx::Error>;
+    async fn sum_amount_by_transaction(&self, transaction_id: &str, db: PoolConnection<Any>) -> Result<f64, sqlx::Error>;
+    async fn totals_by_transaction(&self, transaction_id: &str, db: PoolConnection<Any>) -> Result<PaymentTotals, sqlx::Error>;
+    async fn totals_by_method_for_transaction(&self, transaction_id: &str, db: PoolConnection<Any>) -> Result<Vec<PaymentMethodTotal>, sqlx::Error>;
+    /// Lihat `PembayaranRepository::monthly_totals`.
+    async fn monthly_totals(&self, year: i32, db: PoolConnection<Any>) -> Result<Vec<MonthlyRevenue>, sqlx::Error>;
+}
+
+#[async_trait]
+impl PaymentRepository for PembayaranRepository {
+    fn name(&self) -> &'static str {
+        "standard"
+    }
+
+    async fn create(&self, payment: &Payment, db: PoolConnection<Any>) -> Result<Payment, sqlx::Error> {
+        PembayaranRepository::create(db, payment).await
+    }
+
+    async fn create_with_schedule(&self, payment: &Payment, schedule: &[ScheduledInstallment], max_already_paid: f64, db: PoolConnection<Any>) -> Result<Payment, sqlx::Error> {
+        PembayaranRepository::create_with_schedule(db, payment, schedule, max_already_paid).await
+    }
+
+    async fn find_by_id(&self, id: &str, db: PoolConnection<Any>) -> Result<Payment, sqlx::Error> {
+        PembayaranRepository::find_by_id(db, id).await
+    }
+
+    async fn find_by_id_without_installments(&self, id: &str, db: PoolConnection<Any>) -> Result<Payment, sqlx::Error> {
+        PembayaranRepository::find_by_id_without_installments(db, id).await
+    }
+
+    async fn find_all(&self, filters: Option<HashMap<String, String>>, with_installments: bool, order_by: &str, list: &ListQuery, db: PoolConnection<Any>) -> Result<Vec<Payment>, sqlx::Error> {
+        if with_installments {
+            PembayaranRepository::find_all_with_installments(db, filters, order_by, list).await
+        } else {
+            PembayaranRepository::find_all_without_installments(db, filters, order_by, list).await
+        }
+    }
+
+    async fn count(&self, filters: Option<HashMap<String, String>>, db: PoolConnection<Any>) -> Result<i64, sqlx::Error> {
+        PembayaranRepository::count_all(db, filters).await
+    }
+
+    async fn find_match_candidates(&self, filters: Option<HashMap<String, String>>, amount: Option<f64>, tolerance: f64, db: PoolConnection<Any>) -> Result<Vec<Payment>, sqlx::Error> {
+        PembayaranRepository::find_match_candidates(db, filters, amount, tolerance).await
+    }
+
+    async fn update(&self, payment: &Payment, db: PoolConnection<Any>) -> Result<Payment, sqlx::Error> {
+        PembayaranRepository::update(db, payment).await
+    }
+
+    async fn update_status(&self, payment_id: String, new_status: PaymentStatus, additional_amount: Option<f64>, updated_by: Option<String>, db: PoolConnection<Any>) -> Result<Payment, sqlx::Error> {
+        PembayaranRepository::update_payment_status(db, payment_id, new_status, additional_amount, updated_by).await
+    }
+
+    async fn record_installment(&self, installment: &Installment, recompute_status: bool, updated_by: Option<String>, db: PoolConnection<Any>) -> Result<Payment, sqlx::Error> {
+        PembayaranRepository::record_installment(db, installment, recompute_status, updated_by).await
+    }
+
+    async fn update_method(&self, payment_id: &str, method: &PaymentMethod, updated_by: Option<String>, db: PoolConnection<Any>) -> Result<Payment, sqlx::Error> {
+        PembayaranRepository::update_method(db, payment_id, method, updated_by).await
+    }
+
+    async fn record_refund(&self, refund: &Refund, refundable_total: f64, db: PoolConnection<Any>) -> Result<(), sqlx::Error> {
+        PembayaranRepository::record_refund(db, refund, refundable_total).await
+    }
+
+    async fn find_refunds(&self, payment_id: &str, db: PoolConnection<Any>) -> Result<Vec<Refund>, sqlx::Error> {
+        PembayaranRepository::find_refunds(db, payment_id).await
+    }
+
+    async fn find_schedule(&self, payment_id: &str, db: PoolConnection<Any>) -> Result<Vec<ScheduledInstallment>, sqlx::Error> {
+        PembayaranRepository::find_schedule(db, payment_id).await
+    }
+
+    async fn delete(&self, id: &str, db: PoolConnection<Any>) -> Result<(), sqlx::Error> {
+        PembayaranRepository::delete(db, id).await
+    }
+
+    async fn expire_pending_before(&self, cutoff: DateTime<Utc>, db: PoolConnection<Any>) -> Result<u64, sqlx::Error> {
+        PembayaranRepository::expire_pending_before(db, cutoff).await
+    }
+
+    async fn find_overdue_installments(&self, now: DateTime<Utc>, db: PoolConnection<Any>) -> Result<Vec<Payment>, sqlx::Error> {
+        PembayaranRepository::find_overdue_installments(db, now).await
+    }
+
+    async fn find_transaction_total(&self, transaction_id: &str, db: PoolConnection<Any>) -> Result<Option<f64>, sqlx::Error> {
+        PembayaranRepository::find_transaction_total(db, transaction_id).await
+    }
+
+    async fn find_transaction_status(&self, transaction_id: &str, db: PoolConnection<Any>) -> Result<Option<String>, sqlx::Error> {
+        PembayaranRepository::find_transaction_status(db, transaction_id).await
+    }
+
+    async fn sum_amount_by_transaction(&self, transaction_id: &str, db: PoolConnection<Any>) -> Result<f64, sqlx::Error> {
+        PembayaranRepository::sum_amount_by_transaction(db, transaction_id).await
+    }
+
+    async fn totals_by_transaction(&self, transaction_id: &str, db: PoolConnection<Any>) -> Result<PaymentTotals, sqlx::Error> {
+        PembayaranRepository::totals_by_transaction(db, transaction_id).await
+    }
+
+    async fn totals_by_method_for_transaction(&self, transaction_id: &str, db: PoolConnection<Any>) -> Result<Vec<PaymentMethodTotal>, sqlx::Error> {
+        PembayaranRepository::totals_by_method_for_transaction(db, transaction_id).await
+    }
+
+    async fn monthly_totals(&self, year: i32, db: PoolConnection<Any>) -> Result<Vec<MonthlyRevenue>, sqlx::Error> {
+        PembayaranRepository::monthly_totals(db, year).await
+    }
+}
+
+/// Flag env `USE_OPTIMIZED_PAYMENT_REPO` (`true`/`1`) untuk rollout bertahap repository optimized.
+pub fn use_optimized_payment_repo() -> bool {
+    std::env::var("USE_OPTIMIZED_PAYMENT_REPO")
+        .map(|value| matches!(value.trim().to_lowercase().as_str(), "1" | "true"))
+        .unwrap_or(false)
+}
+
+pub fn payment_repository_for(use_optimized: bool) -> Arc<dyn PaymentRepository> {
+    if use_optimized {
+        Arc::new(OptimizedPembayaranRepository)
+    } else {
+        Arc::new(PembayaranRepository)
+    }
+}
@@ -0,0 +1,173 @@
+use chrono::{DateTime, Months, Utc};
+use uuid::Uuid;
+
+use crate::manajemen_pembayaran::model::payment::{NextInstallment, ScheduledInstallment};
+use crate::manajemen_pembayaran::service::payment_service::PaymentError;
+
+pub const DEFAULT_MAX_PLAN_MONTHS: u32 = 24;
+pub const DEFAULT_MAX_PLAN_INSTALLMENTS: u32 = 24;
+
+/// Batas jadwal cicilan: rentang dari `start` sampai jatuh tempo terakhir (bulan) dan
+/// jumlah cicilan. Diatur lewat env `PAYMENT_PLAN_MAX_MONTHS` dan
+/// `PAYMENT_PLAN_MAX_INSTALLMENTS` (default masing-masing 24).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InstallmentPlanLimits {
+    pub max_months: u32,
+    pub max_installments: u32,
+}
+
+impl Default for InstallmentPlanLimits {
+    fn default() -> Self {
+        InstallmentPlanLimits {
+            max_months: DEFAULT_MAX_PLAN_MONTHS,
+            max_installments: DEFAULT_MAX_PLAN_INSTALLMENTS,
+        }
+    }
+}
+
+impl InstallmentPlanLimits {
+    pub fn from_env() -> Self {
+        let read = |name: &str, default: u32| {
+            std::env::var(name)
+                .ok()
+                .and_then(|value| value.trim().parse::<u32>().ok())
+                .filter(|value| *value > 0)
+                .unwrap_or(default)
+        };
+        InstallmentPlanLimits {
+            max_months: read("PAYMENT_PLAN_MAX_MONTHS", DEFAULT_MAX_PLAN_MONTHS),
+            max_installments: read("PAYMENT_PLAN_MAX_INSTALLMENTS", DEFAULT_MAX_PLAN_INSTALLMENTS),
+        }
+    }
+}
+
+fn round_cents(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// Membagi `total` menjadi `count` cicilan bulanan, jatuh tempo pertama satu bulan
+/// setelah `start`. Sisa pembulatan sen ditaruh di cicilan terakhir sehingga jumlah
+/// jadwal selalu sama persis dengan `total`. Jadwal yang melewati `limits` ditolak.
+pub fn generate_installment_plan(
+    payment_id: &str,
+    total: f64,
+    count: u32,
+    start: DateTime<Utc>,
+    limits: InstallmentPlanLimits,
+) -> Result<Vec<ScheduledInstallment>, PaymentError> {
+    if !total.is_finite() || total <= 0.0 {
+        return Err(PaymentError::InvalidInput("Installment plan total must be greater than 0".to_string()));
+    }
+    if count == 0 {
+        return Err(PaymentError::InvalidInput("Installment plan must have at least one installment".to_string()));
+    }
+    if count > limits.max_installments {
+        return Err(PaymentError::InvalidInput(format!(
+            "Installment plan cannot have more than {} installments (got {count})",
+            limits.max_installments
+        )));
+    }
+    // Satu cicilan per bulan, jadi jatuh tempo terakhir jatuh `count` bulan setelah `start`
+    if count > limits.max_months {
+        return Err(PaymentError::InvalidInput(format!(
+            "Installment plan cannot span more than {} months (got {count})",
+            limits.max_months
+        )));
+    }
+
+    let per_installment = ((total / count as f64) * 100.0).floor() / 100.0;
+    let mut schedule = Vec::with_capacity(count as usize);
+    for sequence in 1..=count {
+        let due_date = start
+            .checked_add_months(Months::new(sequence))
+            .ok_or_else(|| PaymentError::InvalidInput("Installment plan due date is out of range".to_string()))?;
+        let amount = if sequence == count {
+            round_cents(total - per_installment * (count - 1) as f64)
+        } else {
+            per_installment
+        };
+
+        schedule.push(ScheduledInstallment {
+            id: format!("SCHED-{}", Uuid::new_v4()),
+            payment_id: payment_id.to_string(),
+            sequence: sequence as i32,
+            amount,
+            due_date,
+        });
+    }
+
+    Ok(schedule)
+}
+
+/// Mencocokkan total cicilan yang sudah masuk dengan jadwal (urut `sequence`): pembayaran
+/// menutup baris jadwal dari yang paling awal. Mengembalikan baris pertama yang belum
+/// tertutup penuh, atau `None` bila seluruh jadwal sudah terbayar (toleransi setengah sen).
+pub fn next_unpaid_installment(schedule: &[ScheduledInstallment], paid_total: f64) -> Option<NextInstallment> {
+    let mut cumulative = 0.0;
+    for item in schedule {
+        cumulative += item.amount;
+        if cumulative > paid_total + 0.005 {
+            return Some(NextInstallment {
+                payment_id: item.payment_id.clone(),
+                sequence: item.sequence,
+                scheduled_amount: item.amount,
+                amount: round_cents(cumulative - paid_total).min(item.amount),
+                due_date: item.due_date,
+            });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_generate_installment_plan_splits_total_monthly() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 31, 9, 0, 0).unwrap();
+        let schedule = generate_installment_plan("PMT-1", 1000.0, 3, start, InstallmentPlanLimits::default()).unwrap();
+
+        assert_eq!(schedule.len(), 3);
+        let amounts: Vec<f64> = schedule.iter().map(|s| s.amount).collect();
+        assert_eq!(amounts, vec![333.33, 333.33, 333.34]);
+        assert!((amounts.iter().sum::<f64>() - 1000.0).abs() < 1e-9);
+
+        let sequences: Vec<i32> = schedule.iter().map(|s| s.sequence).collect();
+        assert_eq!(sequences, vec![1, 2, 3]);
+        assert_eq!(schedule[0].due_date, Utc.with_ymd_and_hms(2024, 2, 29, 9, 0, 0).unwrap());
+        assert_eq!(schedule[2].due_date, Utc.with_ymd_and_hms(2024, 4, 30, 9, 0, 0).unwrap());
+        assert!(schedule.iter().all(|s| s.payment_id == "PMT-1"));
+    }
+
+    #[test]
+    fn test_generate_installment_plan_rejects_invalid_input() {
+        let start = Utc::now();
+        let limits = InstallmentPlanLimits::default();
+        assert!(matches!(generate_installment_plan("PMT-1", 1000.0, 0, start, limits), Err(PaymentError::InvalidInput(_))));
+        assert!(matches!(generate_installment_plan("PMT-1", 0.0, 3, start, limits), Err(PaymentError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_generate_installment_plan_enforces_limits_at_boundary() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 15, 9, 0, 0).unwrap();
+        let limits = InstallmentPlanLimits::default();
+
+        let schedule = generate_installment_plan("PMT-1", 2400.0, DEFAULT_MAX_PLAN_INSTALLMENTS, start, limits).unwrap();
+        assert_eq!(schedule.len(), 24);
+        assert_eq!(schedule.last().unwrap().due_date, Utc.with_ymd_and_hms(2026, 1, 15, 9, 0, 0).unwrap());
+        match generate_installment_plan("PMT-1", 2400.0, 25, start, limits) {
+            Err(PaymentError::InvalidInput(msg)) => assert!(msg.contains("more than 24 installments"), "{msg}"),
+            other => panic!("expected InvalidInput, got {other:?}"),
+        }
+
+        // Rentang bulan dicek terpisah dari jumlah cicilan
+        let limits = InstallmentPlanLimits { max_months: 12, max_installments: 36 };
+        assert_eq!(generate_installment_plan("PMT-1", 1200.0, 12, start, limits).unwrap().len(), 12);
+        match generate_installment_plan("PMT-1", 1200.0, 13, start, limits) {
+            Err(PaymentError::InvalidInput(msg)) => assert!(msg.contains("more than 12 months"), "{msg}"),
+            other => panic!("expected InvalidInput, got {other:?}"),
+        }
+    }
+}
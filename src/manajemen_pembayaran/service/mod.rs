@@ -1 +1,3 @@
-pub mod payment_service;
\ No newline at end of file
+pub mod payment_service;
+pub mod installment_plan;
+pub mod payment_aging;
\ No newline at end of file
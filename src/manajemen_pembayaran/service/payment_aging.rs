@@ -0,0 +1,83 @@
+use chrono::{DateTime, Utc};
+
+use crate::common::money::{from_sen, round_report_amount, to_sen};
+use crate::manajemen_pembayaran::model::payment::{OverduePayment, Payment, PaymentAgingBucket, PaymentAgingReport};
+
+/// Label bucket umur piutang, urut dari yang paling baru lewat jatuh tempo.
+pub const AGING_BUCKETS: [&str; 4] = ["0-30", "31-60", "61-90", "90+"];
+
+/// Bucket untuk keterlambatan `days_overdue` hari penuh; kurang dari sehari dihitung 0.
+pub fn aging_bucket(days_overdue: i64) -> &'static str {
+    match days_overdue {
+        ..=30 => AGING_BUCKETS[0],
+        31..=60 => AGING_BUCKETS[1],
+        61..=90 => AGING_BUCKETS[2],
+        _ => AGING_BUCKETS[3],
+    }
+}
+
+/// Mengelompokkan payment CICILAN menurut umur keterlambatan per `now`. Payment yang belum
+/// jatuh tempo, tanpa `due_date`, atau sudah tidak bersisa diabaikan. Sisa tagihan dan total
+/// dihitung dalam sen.
+pub fn build_aging_report(payments: &[Payment], now: DateTime<Utc>) -> PaymentAgingReport {
+    let mut buckets: Vec<PaymentAgingBucket> = AGING_BUCKETS
+        .iter()
+        .map(|bucket| PaymentAgingBucket {
+            bucket: bucket.to_string(),
+            payment_count: 0,
+            outstanding_total: 0.0,
+            payments: Vec::new(),
+        })
+        .collect();
+    let mut bucket_sen = [0i64; AGING_BUCKETS.len()];
+
+    for payment in payments {
+        let Some(due_date) = payment.due_date.filter(|due_date| *due_date < now) else {
+            continue;
+        };
+        let outstanding_sen = to_sen(payment.amount) - payment.installments.iter().map(|i| to_sen(i.amount)).sum::<i64>();
+        if outstanding_sen <= 0 {
+            continue;
+        }
+
+        let days_overdue = (now - due_date).num_days();
+        let index = AGING_BUCKETS.iter().position(|bucket| *bucket == aging_bucket(days_overdue)).unwrap_or(0);
+        bucket_sen[index] += outstanding_sen;
+        buckets[index].payments.push(OverduePayment {
+            payment_id: payment.id.clone(),
+            transaction_id: payment.transaction_id.clone(),
+            amount: payment.amount,
+            outstanding: round_report_amount(from_sen(outstanding_sen)),
+            due_date,
+            days_overdue,
+        });
+    }
+
+    for (bucket, sen) in buckets.iter_mut().zip(bucket_sen) {
+        bucket.payments.sort_by(|a, b| b.days_overdue.cmp(&a.days_overdue).then_with(|| a.payment_id.cmp(&b.payment_id)));
+        bucket.payment_count = bucket.payments.len() as i64;
+        bucket.outstanding_total = round_report_amount(from_sen(sen));
+    }
+
+    PaymentAgingReport {
+        as_of: now,
+        buckets,
+        outstanding_total: round_report_amount(from_sen(bucket_sen.iter().sum())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aging_bucket_boundaries() {
+        assert_eq!(aging_bucket(0), "0-30");
+        assert_eq!(aging_bucket(30), "0-30");
+        assert_eq!(aging_bucket(31), "31-60");
+        assert_eq!(aging_bucket(60), "31-60");
+        assert_eq!(aging_bucket(61), "61-90");
+        assert_eq!(aging_bucket(90), "61-90");
+        assert_eq!(aging_bucket(91), "90+");
+    }
+}
@@ -1,70 +1,366 @@
 use std::collections::HashMap;
+use std::fmt;
 use rocket::State;
-use chrono::{Utc};
+use chrono::{Datelike, Duration, NaiveDate, Utc};
 use uuid::Uuid;
 
-use crate::manajemen_pembayaran::model::payment::{Payment, PaymentMethod, Installment};
+use crate::manajemen_pembayaran::model::payment::{MonthlyRevenue, MonthlyRevenueReport, Payment, PaymentAgingReport, PaymentMethod, Installment, NextInstallment, Refund, RefundLedger, ScheduledInstallment, PaymentMethodTotal, PaymentTotals};
+use crate::manajemen_pembayaran::service::installment_plan::{generate_installment_plan, next_unpaid_installment, InstallmentPlanLimits};
+use crate::manajemen_pembayaran::service::payment_aging::build_aging_report;
 use crate::manajemen_pembayaran::enums::payment_status::PaymentStatus;
 use crate::manajemen_pembayaran::repository::payment_repository::PembayaranRepository;
+use crate::manajemen_pembayaran::repository::payment_repository_trait::PaymentRepository;
+use crate::transaksi_penjualan::enums::status_transaksi::StatusTransaksi;
+use crate::common::audit::AuditTrail;
+use crate::common::pagination::ListQuery;
+use crate::common::money::{round_report_amount, sum_amounts};
+use crate::common::clock::{Clock, SystemClock};
+use crate::common::service_result::ServiceError;
+use rocket::http::Status;
 use sqlx::{Any, Pool};
+use std::sync::Arc;
 
-pub struct PaymentService;
+pub struct PaymentService {
+    repository: Arc<dyn PaymentRepository>,
+    clock: Arc<dyn Clock>,
+}
 
 #[derive(Debug)]
 pub enum PaymentError {
     DatabaseError(String),
     NotFound(String),
     InvalidInput(String),
+    Overpayment(f64),
+    Conflict(String),
+    Forbidden(String),
+}
+
+/// Peralihan otomatis CICILAN -> LUNAS saat cicilan sudah menutup `amount`.
+/// Aktif kecuali env `PAYMENT_AUTO_MARK_PAID` diisi `0`/`false`.
+pub fn auto_mark_paid_enabled() -> bool {
+    std::env::var("PAYMENT_AUTO_MARK_PAID")
+        .map(|value| !matches!(value.trim().to_lowercase().as_str(), "0" | "false" | "no"))
+        .unwrap_or(true)
+}
+
+pub const DEFAULT_PENDING_EXPIRY_MINUTES: i64 = 30;
+
+/// Umur maksimal payment MENUNGGU sebelum dianggap ditinggalkan, diatur lewat env
+/// `PAYMENT_PENDING_EXPIRY_MINUTES` (default 30 menit).
+pub fn pending_expiry_minutes() -> i64 {
+    std::env::var("PAYMENT_PENDING_EXPIRY_MINUTES")
+        .ok()
+        .and_then(|value| value.trim().parse::<i64>().ok())
+        .filter(|minutes| *minutes > 0)
+        .unwrap_or(DEFAULT_PENDING_EXPIRY_MINUTES)
+}
+
+/// Selisih nominal yang masih dianggap cocok saat mencocokkan mutasi bank, diatur lewat env
+/// `PAYMENT_MATCH_AMOUNT_TOLERANCE` (default 0: harus sama sampai ke sen).
+pub fn match_amount_tolerance() -> f64 {
+    std::env::var("PAYMENT_MATCH_AMOUNT_TOLERANCE")
+        .ok()
+        .and_then(|value| value.trim().parse::<f64>().ok())
+        .filter(|tolerance| tolerance.is_finite() && *tolerance >= 0.0)
+        .unwrap_or(0.0)
+}
+
+/// Sikap terhadap pembayaran yang melebihi total transaksi, diatur lewat env
+/// `PAYMENT_OVERPAYMENT_POLICY` (`warn` default, `reject` untuk menolak).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OverpaymentPolicy {
+    Warn,
+    Reject,
+}
+
+impl OverpaymentPolicy {
+    pub fn from_env() -> Self {
+        match std::env::var("PAYMENT_OVERPAYMENT_POLICY") {
+            Ok(value) if value.eq_ignore_ascii_case("reject") => OverpaymentPolicy::Reject,
+            _ => OverpaymentPolicy::Warn,
+        }
+    }
+}
+
+// Selisih di bawah setengah sen dianggap pembulatan, bukan kelebihan bayar
+const OVERPAYMENT_TOLERANCE: f64 = 0.005;
+
+impl fmt::Display for PaymentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaymentError::DatabaseError(msg) => write!(f, "Database error: {msg}"),
+            PaymentError::NotFound(msg) => write!(f, "{msg}"),
+            PaymentError::InvalidInput(msg) => write!(f, "{msg}"),
+            PaymentError::Overpayment(overage) => write!(f, "Payment exceeds transaction total by {overage:.2}"),
+            PaymentError::Conflict(msg) => write!(f, "{msg}"),
+            PaymentError::Forbidden(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+impl std::error::Error for PaymentError {}
+
+impl ServiceError for PaymentError {
+    fn status(&self) -> Status {
+        match self {
+            PaymentError::DatabaseError(_) => Status::InternalServerError,
+            PaymentError::NotFound(_) => Status::NotFound,
+            PaymentError::InvalidInput(_) => Status::BadRequest,
+            PaymentError::Overpayment(_) | PaymentError::Conflict(_) => Status::Conflict,
+            PaymentError::Forbidden(_) => Status::Forbidden,
+        }
+    }
 }
 
 impl PaymentService {
     pub fn new() -> Self {
-        PaymentService {}
+        Self::with_repository(Arc::new(PembayaranRepository))
+    }
+
+    pub fn with_repository(repository: Arc<dyn PaymentRepository>) -> Self {
+        PaymentService { repository, clock: Arc::new(SystemClock) }
+    }
+
+    /// Mengganti sumber waktu untuk kedaluwarsa, jatuh tempo dan umur piutang.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn repository_name(&self) -> &'static str {
+        self.repository.name()
     }
     
     pub async fn create_payment(&self, db: &State<Pool<Any>>, payment: Payment) -> Result<Payment, PaymentError> {
+        self.create_payment_with_policy(db, payment, OverpaymentPolicy::from_env()).await
+            .map(|(created, _overage)| created)
+    }
+
+    /// Seperti `create_payment_with_policy`, dengan `created_by`/`updated_by` diisi dari `actor`.
+    pub async fn create_payment_by(&self, db: &State<Pool<Any>>, mut payment: Payment, policy: OverpaymentPolicy, actor: Option<&str>) -> Result<(Payment, Option<f64>), PaymentError> {
+        payment.audit = AuditTrail::created_by(actor);
+        self.create_payment_with_policy(db, payment, policy).await
+    }
+
+    /// Membuat payment dan mengembalikan kelebihan bayar (jika ada) terhadap
+    /// transaksi yang dirujuk. Dengan `Reject`, kelebihan bayar menjadi error.
+    pub async fn create_payment_with_policy(&self, db: &State<Pool<Any>>, payment: Payment, policy: OverpaymentPolicy) -> Result<(Payment, Option<f64>), PaymentError> {
+        Self::validate_due_date(&payment)?;
+        let overage = self.check_overpayment(db, &payment.transaction_id, payment.amount).await?;
+        if let (Some(overage), OverpaymentPolicy::Reject) = (overage, policy) {
+            return Err(PaymentError::Overpayment(overage));
+        }
+
         let conn = db.acquire().await
             .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
         
-        PembayaranRepository::create(conn, &payment).await
-            .map_err(|e| PaymentError::DatabaseError(e.to_string()))
+        let created = self.repository.create(&payment, conn).await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+
+        Ok((created, overage))
+    }
+
+    /// Kelebihan bayar jika `new_amount` ditambahkan ke pembayaran yang sudah ada
+    /// untuk transaksi ini; `None` jika tidak berlebih atau transaksi tidak ditemukan.
+    pub async fn check_overpayment(&self, db: &State<Pool<Any>>, transaction_id: &str, new_amount: f64) -> Result<Option<f64>, PaymentError> {
+        let conn = db.acquire().await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+        let Some(transaction_total) = self.repository.find_transaction_total(transaction_id, conn).await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))? else {
+            return Ok(None);
+        };
+
+        let conn = db.acquire().await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+        let already_paid = self.repository.sum_amount_by_transaction(transaction_id, conn).await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+
+        let overage = already_paid + new_amount - transaction_total;
+        if overage > OVERPAYMENT_TOLERANCE {
+            Ok(Some(overage))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Membuat payment CICILAN sebesar `total` untuk transaksi yang belum punya
+    /// pembayaran, lengkap dengan jadwal `count` cicilan bulanan. Pengecekan "belum dibayar"
+    /// dan insert dilakukan repository dalam satu DB transaction.
+    pub async fn create_payment_plan(&self, db: &State<Pool<Any>>, transaction_id: &str, total: f64, method: PaymentMethod, count: u32) -> Result<(Payment, Vec<ScheduledInstallment>), PaymentError> {
+        let payment_id = self.generate_payment_id();
+        let now = self.clock.now();
+        let schedule = generate_installment_plan(&payment_id, total, count, now, InstallmentPlanLimits::from_env())?;
+        let payment = Payment {
+            id: payment_id,
+            transaction_id: transaction_id.to_string(),
+            amount: total,
+            method,
+            status: PaymentStatus::Installment,
+            payment_date: now,
+            installments: Vec::new(),
+            due_date: schedule.last().map(|item| item.due_date),
+            audit: Default::default(),
+        };
+
+        let conn = db.acquire().await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+        let created = self.repository.create_with_schedule(&payment, &schedule, OVERPAYMENT_TOLERANCE, conn).await
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => PaymentError::InvalidInput(format!("Transaction {transaction_id} is already paid or has recorded payments")),
+                e => PaymentError::DatabaseError(e.to_string()),
+            })?;
+
+        Ok((created, schedule))
+    }
+
+    pub async fn get_payment_totals(&self, db: &State<Pool<Any>>, transaction_id: &str) -> Result<PaymentTotals, PaymentError> {
+        let conn = db.acquire().await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+
+        let mut totals = self.repository.totals_by_transaction(transaction_id, conn).await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+        totals.paid_total = round_report_amount(totals.paid_total);
+        Ok(totals)
+    }
+
+    /// Rincian `get_payment_totals` per metode, mis. transaksi yang dibayar sebagian tunai
+    /// dan sebagian kartu. Metode yang tidak dipakai tidak ikut.
+    pub async fn get_payment_totals_by_method(&self, db: &State<Pool<Any>>, transaction_id: &str) -> Result<Vec<PaymentMethodTotal>, PaymentError> {
+        let conn = db.acquire().await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+
+        let mut totals = self.repository.totals_by_method_for_transaction(transaction_id, conn).await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+        for total in &mut totals {
+            total.paid_total = round_report_amount(total.paid_total);
+        }
+        Ok(totals)
     }
 
     pub async fn get_payment_by_id(&self, db: &State<Pool<Any>>, id: &str) -> Result<Payment, PaymentError> {
+        self.get_payment_by_id_with_installments(db, id, true).await
+    }
+
+    /// `with_installments = false` hanya membaca baris payment (cicilan dikembalikan kosong).
+    pub async fn get_payment_by_id_with_installments(&self, db: &State<Pool<Any>>, id: &str, with_installments: bool) -> Result<Payment, PaymentError> {
         let conn = db.acquire().await
             .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
         
-        PembayaranRepository::find_by_id(conn, id).await
+        let result = if with_installments {
+            self.repository.find_by_id(id, conn).await
+        } else {
+            self.repository.find_by_id_without_installments(id, conn).await
+        };
+        result
             .map_err(|e| match e {
                 sqlx::Error::RowNotFound => PaymentError::NotFound(format!("Payment with id {id} not found")),
                 _ => PaymentError::DatabaseError(e.to_string())
             })
     }
 
-    pub async fn get_all_payments(&self, db: &State<Pool<Any>>, filters: Option<HashMap<String, String>>) -> Result<Vec<Payment>, PaymentError> {
+    /// Listing payment; `sort` dan paginasi `list` diterapkan di SQL, `sort` yang tidak dikenal ditolak.
+    pub async fn get_all_payments(&self, db: &State<Pool<Any>>, filters: Option<HashMap<String, String>>, with_installments: bool, list: &ListQuery) -> Result<Vec<Payment>, PaymentError> {
+        let order_by = list.order_by(PembayaranRepository::SORT_COLUMNS, "id ASC")
+            .map_err(PaymentError::InvalidInput)?;
+        let order_by = order_by.as_deref().unwrap_or(PembayaranRepository::DEFAULT_ORDER_BY);
         let conn = db.acquire().await
             .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
         
-        PembayaranRepository::find_all(conn, filters).await
+        self.repository.find_all(filters, with_installments, order_by, list, conn).await
             .map_err(|e| PaymentError::DatabaseError(e.to_string()))
     }
 
+    pub async fn count_payments(&self, db: &State<Pool<Any>>, filters: Option<HashMap<String, String>>) -> Result<i64, PaymentError> {
+        let conn = db.acquire().await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+
+        self.repository.count(filters, conn).await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))
+    }
+
+    /// Kandidat payment untuk satu baris mutasi bank. Tanggal berformat `YYYY-MM-DD` dan
+    /// keduanya inklusif; nominal dicocokkan dengan `match_amount_tolerance`.
+    pub async fn find_statement_matches(
+        &self,
+        db: &State<Pool<Any>>,
+        method: Option<&str>,
+        date_from: Option<&str>,
+        date_to: Option<&str>,
+        amount: Option<f64>,
+    ) -> Result<Vec<Payment>, PaymentError> {
+        let parse_date = |value: &str| NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+            .map_err(|_| PaymentError::InvalidInput(format!("Invalid date '{value}'. Use YYYY-MM-DD")));
+        let date_from = date_from.map(parse_date).transpose()?;
+        let date_to = date_to.map(parse_date).transpose()?;
+        if date_from.zip(date_to).is_some_and(|(from, to)| from > to) {
+            return Err(PaymentError::InvalidInput("date_from must not be after date_to".to_string()));
+        }
+        if amount.is_some_and(|amount| !amount.is_finite() || amount < 0.0) {
+            return Err(PaymentError::InvalidInput("Amount must be a non-negative number".to_string()));
+        }
+
+        let mut filters = HashMap::new();
+        if let Some(method) = method {
+            filters.insert("method".to_string(), self.parse_payment_method(method)?.to_string());
+        }
+        if let Some(from) = date_from {
+            filters.insert("date_from".to_string(), format!("{}T00:00:00", from));
+        }
+        if let Some(to) = date_to.and_then(|to| to.succ_opt()) {
+            filters.insert("date_to".to_string(), format!("{}T00:00:00", to));
+        }
+
+        let conn = db.acquire().await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+        self.repository.find_match_candidates(Some(filters), amount, match_amount_tolerance(), conn).await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))
+    }
+
+    /// Semua payment (beserta cicilannya) untuk satu transaksi, lewat filter `transaction_id`.
+    pub async fn get_payments_by_transaction(&self, db: &State<Pool<Any>>, transaction_id: &str) -> Result<Vec<Payment>, PaymentError> {
+        let filters = HashMap::from([("transaction_id".to_string(), transaction_id.to_string())]);
+        self.get_all_payments(db, Some(filters), true, &ListQuery::default()).await
+    }
+
+    /// Seperti `update_payment`, dengan `updated_by` diisi dari `actor`; `created_by` tidak berubah.
+    pub async fn update_payment_by(&self, db: &State<Pool<Any>>, mut payment: Payment, actor: Option<&str>) -> Result<Payment, PaymentError> {
+        payment.audit.touch(actor);
+        self.update_payment(db, payment).await
+    }
+
     pub async fn update_payment(&self, db: &State<Pool<Any>>, payment: Payment) -> Result<Payment, PaymentError> {
+        Self::validate_due_date(&payment)?;
         let conn = db.acquire().await
             .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
         
-        PembayaranRepository::update(conn, &payment).await
+        self.repository.update(&payment, conn).await
             .map_err(|e| match e {
                 sqlx::Error::RowNotFound => PaymentError::NotFound(format!("Payment with id {} not found", payment.id)),
                 _ => PaymentError::DatabaseError(e.to_string())
             })
     }
     
+    /// Status yang diminta hanya berlaku untuk payment tanpa cicilan; kalau ada cicilan,
+    /// status yang dihitung ulang dari cicilan yang menang (lihat `Payment::recompute_status`).
     pub async fn update_payment_status(&self, db: &State<Pool<Any>>, payment_id: String, new_status: PaymentStatus, additional_amount: Option<f64>) -> Result<Payment, PaymentError> {
+        self.update_payment_status_by(db, payment_id, new_status, additional_amount, None).await
+    }
+
+    /// Seperti `update_payment_status`, dengan `updated_by` diisi dari `actor`.
+    pub async fn update_payment_status_by(&self, db: &State<Pool<Any>>, payment_id: String, new_status: PaymentStatus, additional_amount: Option<f64>, actor: Option<&str>) -> Result<Payment, PaymentError> {
+        let mut payment = self.get_payment_by_id(db, &payment_id).await?;
+        if let Some(amount) = additional_amount {
+            payment.installments.push(Self::new_installment(&payment_id, amount));
+        }
+        payment.status = new_status;
+        if auto_mark_paid_enabled() {
+            payment.recompute_status();
+        }
+
         let conn = db.acquire().await
             .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
         
-        PembayaranRepository::update_payment_status(conn, payment_id.clone(), new_status, additional_amount).await
+        self.repository.update_status(payment_id.clone(), payment.status, additional_amount, actor.map(str::to_string), conn).await
             .map_err(|e| match e {
                 sqlx::Error::RowNotFound => PaymentError::NotFound(format!("Payment with id {payment_id} not found")),
                 _ => PaymentError::DatabaseError(e.to_string())
@@ -72,36 +368,264 @@ impl PaymentService {
     }
     
     pub async fn delete_payment(&self, db: &State<Pool<Any>>, payment_id: &str) -> Result<(), PaymentError> {
+        self.delete_payment_with_force(db, payment_id, false).await
+    }
+
+    /// Payment yang melunasi transaksi SELESAI hanya boleh dihapus dengan `force`,
+    /// karena menghapusnya merusak rekonsiliasi transaksi tersebut. Payment yang tidak
+    /// ada menghasilkan `NotFound`, bukan sukses diam-diam.
+    pub async fn delete_payment_with_force(&self, db: &State<Pool<Any>>, payment_id: &str, force: bool) -> Result<(), PaymentError> {
+        let conn = db.acquire().await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+        let payment = self.repository.find_by_id_without_installments(payment_id, conn).await
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => PaymentError::NotFound(format!("Payment with id {payment_id} not found")),
+                e => PaymentError::DatabaseError(e.to_string()),
+            })?;
+        if !force {
+            self.ensure_not_linked_to_completed_transaction(db, &payment).await?;
+        }
+
         let conn = db.acquire().await
             .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
         
-        PembayaranRepository::delete(conn, payment_id).await
+        self.repository.delete(payment_id, conn).await
             .map_err(|e| PaymentError::DatabaseError(e.to_string()))
     }
     
+    async fn ensure_not_linked_to_completed_transaction(&self, db: &State<Pool<Any>>, payment: &Payment) -> Result<(), PaymentError> {
+        let conn = db.acquire().await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+        let status = self.repository.find_transaction_status(&payment.transaction_id, conn).await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+
+        if status.as_deref().and_then(StatusTransaksi::from_string) == Some(StatusTransaksi::Selesai) {
+            return Err(PaymentError::Conflict(format!(
+                "Payment {} settles completed transaction {}; pass force=true to delete it",
+                payment.id, payment.transaction_id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Mengganti metode pembayaran saja. Payment GAGAL sudah final sehingga metodenya tidak boleh diubah.
+    pub async fn update_payment_method(&self, db: &State<Pool<Any>>, payment_id: &str, method: PaymentMethod, actor: Option<&str>) -> Result<Payment, PaymentError> {
+        let conn = db.acquire().await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+        let payment = self.repository.find_by_id_without_installments(payment_id, conn).await
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => PaymentError::NotFound(format!("Payment with id {payment_id} not found")),
+                _ => PaymentError::DatabaseError(e.to_string()),
+            })?;
+
+        if payment.status == PaymentStatus::Failed {
+            return Err(PaymentError::Conflict(format!(
+                "Payment {payment_id} is {}; its method can no longer be changed",
+                payment.status
+            )));
+        }
+
+        // Pre-check supaya pesannya jelas; UPDATE-nya sendiri juga menolak payment yang sudah di-refund
+        let conn = db.acquire().await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+        let refunds = self.repository.find_refunds(payment_id, conn).await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+        if !refunds.is_empty() {
+            return Err(PaymentError::Conflict(format!(
+                "Payment {payment_id} has been refunded; its method can no longer be changed"
+            )));
+        }
+
+        let conn = db.acquire().await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+        self.repository.update_method(payment_id, &method, actor.map(str::to_string), conn).await
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => PaymentError::Conflict(format!(
+                    "Payment {payment_id} was refunded or deleted; its method was not changed"
+                )),
+                _ => PaymentError::DatabaseError(e.to_string()),
+            })
+    }
+
     pub async fn add_installment(&self, db: &State<Pool<Any>>, payment_id: &str, amount: f64) -> Result<Payment, PaymentError> {
+        self.add_installment_by(db, payment_id, amount, None).await
+    }
+
+    /// Seperti `add_installment`, dengan `updated_by` payment diisi dari `actor`.
+    pub async fn add_installment_by(&self, db: &State<Pool<Any>>, payment_id: &str, amount: f64, actor: Option<&str>) -> Result<Payment, PaymentError> {
+        // Pre-check supaya error NotFound/status jelas; pengecekan final ada di dalam DB transaction
         let payment: Payment = self.get_payment_by_id(db, payment_id).await?;
         
         if payment.status != PaymentStatus::Installment {
             return Err(PaymentError::InvalidInput("Cannot add installment to a payment that is not in INSTALLMENT status".to_string()));
         }
         
-        let installment = Installment {
-            id: format!("INST-{}", Uuid::new_v4()),
+        let conn = db.acquire().await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+        
+        self.repository.record_installment(&Self::new_installment(payment_id, amount), auto_mark_paid_enabled(), actor.map(str::to_string), conn).await
+            .map_err(|e| match e {
+                // Cicilan lain yang masuk bersamaan sudah melunasi payment ini
+                sqlx::Error::RowNotFound => PaymentError::InvalidInput(
+                    "Payment is no longer in INSTALLMENT status".to_string()
+                ),
+                e => PaymentError::DatabaseError(e.to_string()),
+            })
+    }
+
+    /// Cicilan terjadwal berikutnya yang perlu ditagihkan ke pelanggan; `None` bila payment
+    /// sudah lunas. Hanya untuk payment yang dibuat lewat `create_payment_plan`.
+    pub async fn get_next_installment(&self, db: &State<Pool<Any>>, payment_id: &str) -> Result<Option<NextInstallment>, PaymentError> {
+        let payment = self.get_payment_by_id(db, payment_id).await?;
+        let conn = db.acquire().await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+        let schedule = self.repository.find_schedule(payment_id, conn).await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+        if schedule.is_empty() {
+            return Err(PaymentError::InvalidInput(format!("Payment {payment_id} has no installment plan")));
+        }
+        if payment.status == PaymentStatus::Paid {
+            return Ok(None);
+        }
+
+        Ok(next_unpaid_installment(&schedule, payment.installments_total()))
+    }
+
+    /// Mencatat refund parsial; boleh berulang selama total refund tidak melebihi
+    /// `Payment::refundable_total`. Mengembalikan ledger setelah refund dicatat.
+    pub async fn refund_payment(&self, db: &State<Pool<Any>>, payment_id: &str, amount: f64, reason: &str, actor: Option<&str>) -> Result<RefundLedger, PaymentError> {
+        if !amount.is_finite() || amount <= 0.0 {
+            return Err(PaymentError::InvalidInput("Refund amount must be greater than 0".to_string()));
+        }
+        let reason = reason.trim();
+        if reason.is_empty() {
+            return Err(PaymentError::InvalidInput("Refund reason must not be empty".to_string()));
+        }
+
+        // Pre-check supaya pesan sisa saldo jelas; pengecekan final ada di dalam DB transaction
+        let ledger = self.get_refund_ledger(db, payment_id).await?;
+        if ledger.refundable_total <= 0.0 {
+            return Err(PaymentError::Conflict(format!("Payment {payment_id} has not collected anything to refund")));
+        }
+        if amount > ledger.remaining + OVERPAYMENT_TOLERANCE {
+            return Err(PaymentError::InvalidInput(format!(
+                "Refund of {amount:.2} exceeds the remaining refundable balance of {:.2}",
+                ledger.remaining
+            )));
+        }
+
+        let refund = Refund {
+            id: format!("RFD-{}", Uuid::new_v4()),
             payment_id: payment_id.to_string(),
             amount,
-            payment_date: Utc::now(),
+            reason: reason.to_string(),
+            refund_date: self.clock.now(),
+            created_by: actor.map(str::to_string),
         };
-        
         let conn = db.acquire().await
             .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
-        
-        let mut updated_payment = payment.clone();
-        updated_payment.installments.push(installment);
-        
-        PembayaranRepository::update(conn, &updated_payment).await
+        self.repository.record_refund(&refund, ledger.refundable_total, conn).await
+            .map_err(|e| match e {
+                // Refund lain yang masuk bersamaan sudah menghabiskan sisa saldo
+                sqlx::Error::RowNotFound => PaymentError::InvalidInput(
+                    "Refund exceeds the remaining refundable balance".to_string()
+                ),
+                e => PaymentError::DatabaseError(e.to_string()),
+            })?;
+
+        self.get_refund_ledger(db, payment_id).await
+    }
+
+    pub async fn get_refund_ledger(&self, db: &State<Pool<Any>>, payment_id: &str) -> Result<RefundLedger, PaymentError> {
+        let payment = self.get_payment_by_id(db, payment_id).await?;
+        let conn = db.acquire().await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+        let refunds = self.repository.find_refunds(payment_id, conn).await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+        Ok(RefundLedger::new(payment_id, payment.refundable_total(), refunds))
+    }
+
+    /// Menandai GAGAL semua payment MENUNGGU yang dibuat lebih dari `older_than` lalu,
+    /// mis. checkout yang ditinggalkan. Aman dipanggil berulang dari job terjadwal.
+    pub async fn expire_stale_pending(&self, db: &State<Pool<Any>>, older_than: Duration) -> Result<u64, PaymentError> {
+        if older_than <= Duration::zero() {
+            return Err(PaymentError::InvalidInput("Expiry age must be greater than 0".to_string()));
+        }
+        let cutoff = self.clock.now().checked_sub_signed(older_than).ok_or_else(|| {
+            PaymentError::InvalidInput(format!("Expiry age of {} minutes is out of range", older_than.num_minutes()))
+        })?;
+
+        let conn = db.acquire().await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+
+        self.repository.expire_pending_before(cutoff, conn).await
             .map_err(|e| PaymentError::DatabaseError(e.to_string()))
     }
+
+    /// Umur piutang CICILAN per waktu `clock`, dikelompokkan di `build_aging_report`.
+    pub async fn payment_aging_report(&self, db: &State<Pool<Any>>) -> Result<PaymentAgingReport, PaymentError> {
+        let now = self.clock.now();
+        let conn = db.acquire().await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+
+        let overdue = self.repository.find_overdue_installments(now, conn).await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+        Ok(build_aging_report(&overdue, now))
+    }
+
+    /// Pendapatan per bulan untuk `year`; tanpa `year` memakai tahun berjalan (UTC).
+    pub async fn monthly_revenue_report(&self, db: &State<Pool<Any>>, year: Option<i32>) -> Result<MonthlyRevenueReport, PaymentError> {
+        let year = year.unwrap_or_else(|| self.clock.now().year());
+        if !(1..=9999).contains(&year) {
+            return Err(PaymentError::InvalidInput(format!("Invalid year: {year}")));
+        }
+        let conn = db.acquire().await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+
+        let totals = self.repository.monthly_totals(year, conn).await
+            .map_err(|e| PaymentError::DatabaseError(e.to_string()))?;
+        Ok(Self::monthly_revenue_series(year, totals))
+    }
+
+    /// Melengkapi hasil query menjadi 12 bulan berurutan; bulan yang tidak ada diisi nol.
+    fn monthly_revenue_series(year: i32, totals: Vec<MonthlyRevenue>) -> MonthlyRevenueReport {
+        let mut by_month: HashMap<String, MonthlyRevenue> = totals.into_iter().map(|total| (total.month.clone(), total)).collect();
+        let months: Vec<MonthlyRevenue> = (1..=12)
+            .map(|month| {
+                let key = format!("{year:04}-{month:02}");
+                let mut bucket = by_month.remove(&key).unwrap_or(MonthlyRevenue { month: key, payment_count: 0, total: 0.0 });
+                bucket.total = round_report_amount(bucket.total);
+                bucket
+            })
+            .collect();
+
+        MonthlyRevenueReport {
+            year,
+            total: round_report_amount(sum_amounts(months.iter().map(|month| month.total))),
+            months,
+        }
+    }
+
+    /// Jatuh tempo tidak boleh sebelum tanggal bayar; payment tanpa `due_date` selalu lolos.
+    fn validate_due_date(payment: &Payment) -> Result<(), PaymentError> {
+        match payment.due_date {
+            Some(due_date) if due_date < payment.payment_date => Err(PaymentError::InvalidInput(format!(
+                "Due date {} must not be before payment date {}",
+                due_date.to_rfc3339(),
+                payment.payment_date.to_rfc3339()
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn new_installment(payment_id: &str, amount: f64) -> Installment {
+        Installment {
+            id: format!("INST-{}", Uuid::new_v4()),
+            payment_id: payment_id.to_string(),
+            amount,
+            payment_date: Utc::now(),
+        }
+    }
     
     pub fn generate_payment_id(&self) -> String {
         format!("PMT-{}", Uuid::new_v4())
@@ -139,7 +663,7 @@ mod tests {
     #[test]
     fn test_payment_service_creation() {
         let service = PaymentService::new();
-        assert_eq!(std::mem::size_of_val(&service), 0);
+        assert_eq!(service.repository_name(), "standard");
     }
 
     #[test]
@@ -238,6 +762,486 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_payment_error_display() {
+        assert_eq!(
+            PaymentError::DatabaseError("pool timed out".to_string()).to_string(),
+            "Database error: pool timed out"
+        );
+        assert_eq!(
+            PaymentError::NotFound("Payment with id PMT-1 not found".to_string()).to_string(),
+            "Payment with id PMT-1 not found"
+        );
+        assert_eq!(
+            PaymentError::InvalidInput("Invalid payment method: FOO".to_string()).to_string(),
+            "Invalid payment method: FOO"
+        );
+        assert_eq!(
+            PaymentError::Overpayment(1500.0).to_string(),
+            "Payment exceeds transaction total by 1500.00"
+        );
+
+        let boxed: Box<dyn std::error::Error> = Box::new(PaymentError::NotFound("missing".to_string()));
+        assert_eq!(boxed.to_string(), "missing");
+    }
+
+    #[tokio::test]
+    async fn test_delete_payment_blocked_for_completed_transaction_unless_forced() {
+        let (db_pool, transaction_id) = setup_overpayment_db(300000.0).await;
+        let service = PaymentService::new();
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+
+        let settled = service.create_payment(db, payment_for(&transaction_id, 300000.0)).await.unwrap();
+        sqlx::query("UPDATE transaksi SET status = 'SELESAI' WHERE id = $1")
+            .bind(transaction_id.parse::<i32>().unwrap())
+            .execute(&db_pool)
+            .await
+            .unwrap();
+
+        match service.delete_payment(db, &settled.id).await {
+            Err(PaymentError::Conflict(msg)) => assert!(msg.contains("force=true")),
+            other => panic!("Expected Conflict, got {other:?}"),
+        }
+        assert!(service.get_payment_by_id(db, &settled.id).await.is_ok());
+
+        service.delete_payment_with_force(db, &settled.id, true).await.unwrap();
+        assert!(matches!(service.get_payment_by_id(db, &settled.id).await, Err(PaymentError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_delete_payment_allowed_while_transaction_in_progress() {
+        let (db_pool, transaction_id) = setup_overpayment_db(300000.0).await;
+        let service = PaymentService::new();
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+
+        let pending = service.create_payment(db, payment_for(&transaction_id, 100000.0)).await.unwrap();
+        service.delete_payment(db, &pending.id).await.unwrap();
+        assert!(matches!(service.get_payment_by_id(db, &pending.id).await, Err(PaymentError::NotFound(_))));
+    }
+
+    async fn setup_overpayment_db(total_harga: f64) -> (Pool<Any>, String) {
+        sqlx::any::install_default_drivers();
+        let db_pool = sqlx::any::AnyPoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!("migrations/test").run(&db_pool).await.unwrap();
+
+        let row = sqlx::query("
+            INSERT INTO transaksi (id_pelanggan, nama_pelanggan, tanggal_transaksi, total_harga, status, catatan, created_at, updated_at)
+            VALUES (1, 'Alice', '2024-01-01 10:00:00', $1, 'MASIH_DIPROSES', '', '2024-01-01 10:00:00', '2024-01-01 10:00:00')
+            RETURNING id
+        ")
+            .bind(total_harga)
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        let transaction_id: i32 = sqlx::Row::get(&row, "id");
+
+        (db_pool, transaction_id.to_string())
+    }
+
+    fn payment_for(transaction_id: &str, amount: f64) -> Payment {
+        Payment {
+            id: format!("PMT-{}", Uuid::new_v4()),
+            transaction_id: transaction_id.to_string(),
+            amount,
+            method: PaymentMethod::Cash,
+            status: PaymentStatus::Paid,
+            payment_date: Utc::now(),
+            installments: Vec::new(),
+            due_date: None,
+            audit: Default::default(),
+        }
+    }
+
+    // Pool tanpa skema: kalau service sampai menjalankan SQL sendiri, test langsung gagal
+    async fn schemaless_pool() -> Pool<Any> {
+        sqlx::any::install_default_drivers();
+        sqlx::any::AnyPoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_service_with_mock_repository() {
+        use crate::manajemen_pembayaran::repository::payment_repository_trait::MockPaymentRepository;
+
+        let db_pool = schemaless_pool().await;
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let paid = payment_for("42", 100000.0);
+
+        let mut mock = MockPaymentRepository::new();
+        mock.expect_find_transaction_total().returning(|_, _| Box::pin(async { Ok(Some(100000.0)) }));
+        mock.expect_sum_amount_by_transaction().returning(|_, _| Box::pin(async { Ok(0.0) }));
+        mock.expect_create().times(1).returning(|payment, _| {
+            let payment = payment.clone();
+            Box::pin(async move { Ok(payment) })
+        });
+        let stored = paid.clone();
+        mock.expect_find_by_id().returning(move |id, _| {
+            let result = if id == stored.id { Ok(stored.clone()) } else { Err(sqlx::Error::RowNotFound) };
+            Box::pin(async move { result })
+        });
+        mock.expect_update().times(0);
+
+        let service = PaymentService::with_repository(Arc::new(mock));
+
+        let (created, overage) = service.create_payment_with_policy(db, paid.clone(), OverpaymentPolicy::Reject).await.unwrap();
+        assert_eq!(created.id, paid.id);
+        assert_eq!(overage, None);
+
+        assert!(matches!(service.get_payment_by_id(db, "PMT-MISSING").await, Err(PaymentError::NotFound(_))));
+
+        // Payment LUNAS tidak boleh ditambah cicilan, dan repository tidak di-update
+        let result = service.add_installment(db, &paid.id, 5000.0).await;
+        assert!(matches!(result, Err(PaymentError::InvalidInput(_))));
+    }
+
+    #[tokio::test]
+    async fn test_expiry_and_aging_use_injected_clock() {
+        use chrono::TimeZone;
+        use crate::common::clock::FixedClock;
+        use crate::manajemen_pembayaran::repository::payment_repository_trait::MockPaymentRepository;
+
+        let db_pool = schemaless_pool().await;
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let now = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+
+        let installment_due = |id: &str, days_overdue: i64| Payment {
+            id: id.to_string(),
+            status: PaymentStatus::Installment,
+            due_date: Some(now - Duration::days(days_overdue)),
+            ..payment_for("42", 1000.0)
+        };
+        let overdue = vec![installment_due("PMT-30", 30), installment_due("PMT-31", 31)];
+
+        let mut mock = MockPaymentRepository::new();
+        mock.expect_expire_pending_before()
+            .withf(move |cutoff, _| *cutoff == now - Duration::minutes(30))
+            .times(1)
+            .returning(|_, _| Box::pin(async { Ok(2) }));
+        mock.expect_find_overdue_installments()
+            .withf(move |as_of, _| *as_of == now)
+            .times(1)
+            .returning(move |_, _| {
+                let overdue = overdue.clone();
+                Box::pin(async move { Ok(overdue) })
+            });
+
+        let service = PaymentService::with_repository(Arc::new(mock)).with_clock(Arc::new(FixedClock(now)));
+
+        assert_eq!(service.expire_stale_pending(db, Duration::minutes(30)).await.unwrap(), 2);
+        // Batas waktu sebelum rentang `DateTime` ditolak tanpa menyentuh repository
+        assert!(matches!(
+            service.expire_stale_pending(db, Duration::minutes(1_000_000_000_000)).await,
+            Err(PaymentError::InvalidInput(_))
+        ));
+
+        // Tepat 30 hari masih di bucket pertama, 31 hari pindah ke bucket berikutnya
+        let report = service.payment_aging_report(db).await.unwrap();
+        assert_eq!(report.as_of, now);
+        assert_eq!(report.buckets[0].payments[0].payment_id, "PMT-30");
+        assert_eq!(report.buckets[1].payments[0].payment_id, "PMT-31");
+    }
+
+    #[tokio::test]
+    async fn test_due_date_before_payment_date_is_rejected() {
+        use crate::manajemen_pembayaran::repository::payment_repository_trait::MockPaymentRepository;
+
+        let db_pool = schemaless_pool().await;
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+
+        let mut mock = MockPaymentRepository::new();
+        mock.expect_find_transaction_total().returning(|_, _| Box::pin(async { Ok(None) }));
+        mock.expect_create().times(1).returning(|payment, _| {
+            let payment = payment.clone();
+            Box::pin(async move { Ok(payment) })
+        });
+        mock.expect_update().times(0);
+        let service = PaymentService::with_repository(Arc::new(mock));
+
+        let mut overdue = payment_for("42", 100000.0);
+        overdue.due_date = Some(overdue.payment_date - chrono::Duration::days(1));
+        let result = service.create_payment_with_policy(db, overdue.clone(), OverpaymentPolicy::Reject).await;
+        assert!(matches!(result, Err(PaymentError::InvalidInput(_))));
+        assert!(matches!(service.update_payment(db, overdue).await, Err(PaymentError::InvalidInput(_))));
+
+        let mut scheduled = payment_for("42", 100000.0);
+        scheduled.due_date = Some(scheduled.payment_date + chrono::Duration::days(30));
+        let (created, _) = service.create_payment_with_policy(db, scheduled.clone(), OverpaymentPolicy::Reject).await.unwrap();
+        assert_eq!(created.due_date, scheduled.due_date);
+    }
+
+    #[tokio::test]
+    async fn test_standard_and_optimized_repositories_return_identical_results() {
+        use crate::manajemen_pembayaran::repository::payment_repository_trait::payment_repository_for;
+
+        let mut payment = payment_for("placeholder", 300000.0);
+        payment.status = PaymentStatus::Installment;
+        payment.due_date = Some(payment.payment_date + chrono::Duration::days(30));
+        payment.installments = (0..2)
+            .map(|i| Installment {
+                id: format!("INST-{i}"),
+                payment_id: payment.id.clone(),
+                amount: 50000.0,
+                payment_date: payment.payment_date + chrono::Duration::days(i),
+            })
+            .collect();
+
+        let mut results = Vec::new();
+        for use_optimized in [false, true] {
+            let (db_pool, transaction_id) = setup_overpayment_db(300000.0).await;
+            let db: &State<Pool<Any>> = State::from(&db_pool);
+            let service = PaymentService::with_repository(payment_repository_for(use_optimized));
+            payment.transaction_id = transaction_id;
+
+            let created = service.create_payment(db, payment.clone()).await.unwrap();
+            let fetched = service.get_payment_by_id(db, &payment.id).await.unwrap();
+            let listed = service.get_all_payments(db, None, true, &ListQuery::default()).await.unwrap();
+            let missing = service.get_payment_by_id(db, "PMT-MISSING").await;
+            assert!(matches!(missing, Err(PaymentError::NotFound(_))), "{}", service.repository_name());
+
+            results.push((
+                serde_json::to_value(&created).unwrap(),
+                serde_json::to_value(&fetched).unwrap(),
+                serde_json::to_value(&listed).unwrap(),
+            ));
+        }
+
+        assert_eq!(results[0], results[1]);
+        assert_eq!(results[0].0, results[0].1);
+        assert_eq!(results[0].1["installments"].as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_overpayment_exact_coverage() {
+        let (db_pool, transaction_id) = setup_overpayment_db(300000.0).await;
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let service = PaymentService::new();
+
+        service.create_payment_with_policy(db, payment_for(&transaction_id, 100000.0), OverpaymentPolicy::Reject).await.unwrap();
+        let (_, overage) = service.create_payment_with_policy(db, payment_for(&transaction_id, 200000.0), OverpaymentPolicy::Reject).await.unwrap();
+
+        assert_eq!(overage, None);
+    }
+
+    #[tokio::test]
+    async fn test_overpayment_underpayment_is_allowed() {
+        let (db_pool, transaction_id) = setup_overpayment_db(300000.0).await;
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let service = PaymentService::new();
+
+        let (_, overage) = service.create_payment_with_policy(db, payment_for(&transaction_id, 100000.0), OverpaymentPolicy::Reject).await.unwrap();
+
+        assert_eq!(overage, None);
+        assert_eq!(service.check_overpayment(db, &transaction_id, 150000.0).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_overpayment_rejected_or_warned_by_policy() {
+        let (db_pool, transaction_id) = setup_overpayment_db(300000.0).await;
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let service = PaymentService::new();
+
+        service.create_payment_with_policy(db, payment_for(&transaction_id, 250000.0), OverpaymentPolicy::Reject).await.unwrap();
+
+        match service.create_payment_with_policy(db, payment_for(&transaction_id, 100000.0), OverpaymentPolicy::Reject).await {
+            Err(PaymentError::Overpayment(overage)) => assert_eq!(overage, 50000.0),
+            other => panic!("Expected Overpayment error, got {other:?}"),
+        }
+
+        let (created, overage) = service.create_payment_with_policy(db, payment_for(&transaction_id, 100000.0), OverpaymentPolicy::Warn).await.unwrap();
+        assert_eq!(overage, Some(50000.0));
+        assert_eq!(created.amount, 100000.0);
+    }
+
+    #[tokio::test]
+    async fn test_overpayment_skipped_for_unknown_transaction() {
+        let (db_pool, _) = setup_overpayment_db(100.0).await;
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let service = PaymentService::new();
+
+        assert_eq!(service.check_overpayment(db, "TXN-NOT-NUMERIC", 1_000_000.0).await.unwrap(), None);
+        assert_eq!(service.check_overpayment(db, "99999", 1_000_000.0).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_final_installment_marks_payment_paid() {
+        let (db_pool, transaction_id) = setup_overpayment_db(300000.0).await;
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let service = PaymentService::new();
+
+        let mut payment = payment_for(&transaction_id, 300000.0);
+        payment.status = PaymentStatus::Installment;
+        let payment = service.create_payment(db, payment).await.unwrap();
+
+        let after_first = service.add_installment(db, &payment.id, 100000.0).await.unwrap();
+        assert_eq!(after_first.status, PaymentStatus::Installment);
+        assert_eq!(after_first.installments.len(), 1);
+
+        service.add_installment(db, &payment.id, 100000.0).await.unwrap();
+        let paid_off = service.add_installment(db, &payment.id, 100000.0).await.unwrap();
+        assert_eq!(paid_off.status, PaymentStatus::Paid);
+        assert_eq!(paid_off.installments.len(), 3);
+        assert!((paid_off.installments_total() - 300000.0).abs() < 0.01);
+
+        // Sudah LUNAS, cicilan berikutnya ditolak
+        let result = service.add_installment(db, &payment.id, 1000.0).await;
+        assert!(matches!(result, Err(PaymentError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_payment_error_status_mapping() {
+        use crate::common::service_result::ServiceError;
+        use rocket::http::Status;
+
+        assert_eq!(PaymentError::DatabaseError("x".into()).status(), Status::InternalServerError);
+        assert_eq!(PaymentError::NotFound("x".into()).status(), Status::NotFound);
+        assert_eq!(PaymentError::InvalidInput("x".into()).status(), Status::BadRequest);
+        assert_eq!(PaymentError::Overpayment(10.0).status(), Status::Conflict);
+        assert_eq!(PaymentError::Conflict("x".into()).status(), Status::Conflict);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_payment_plans_create_only_one() {
+        let (db_pool, transaction_id) = setup_overpayment_db(300000.0).await;
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let service = PaymentService::new();
+
+        // Cek "belum dibayar" terpisah dari insert membuat keduanya lolos dan transaksi punya dua rencana
+        let (first, second) = tokio::join!(
+            service.create_payment_plan(db, &transaction_id, 300000.0, PaymentMethod::BankTransfer, 3),
+            service.create_payment_plan(db, &transaction_id, 300000.0, PaymentMethod::BankTransfer, 3),
+        );
+        let results = [first, second];
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+        assert!(results.iter().any(|result| matches!(result, Err(PaymentError::InvalidInput(_)))));
+
+        let plans: i64 = sqlx::query_scalar("SELECT CAST(COUNT(*) AS BIGINT) FROM payments WHERE transaction_id = $1")
+            .bind(&transaction_id)
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(plans, 1);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_installments_both_persist_and_pay_off() {
+        let (db_pool, transaction_id) = setup_overpayment_db(300000.0).await;
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let service = PaymentService::new();
+
+        let mut payment = payment_for(&transaction_id, 300000.0);
+        payment.status = PaymentStatus::Installment;
+        let payment = service.create_payment(db, payment).await.unwrap();
+
+        // Dengan read-modify-write lama keduanya bisa membaca 0 cicilan dan status tertinggal di CICILAN
+        let (first, second) = tokio::join!(
+            service.add_installment(db, &payment.id, 150000.0),
+            service.add_installment(db, &payment.id, 150000.0),
+        );
+        first.unwrap();
+        second.unwrap();
+
+        let stored = service.get_payment_by_id(db, &payment.id).await.unwrap();
+        assert_eq!(stored.installments.len(), 2);
+        assert!((stored.installments_total() - 300000.0).abs() < 0.01);
+        assert_eq!(stored.status, PaymentStatus::Paid);
+    }
+
+    #[tokio::test]
+    async fn test_sequential_partial_refunds_until_balance_exhausted() {
+        let (db_pool, transaction_id) = setup_overpayment_db(300000.0).await;
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let service = PaymentService::new();
+        let payment = service.create_payment(db, payment_for(&transaction_id, 300000.0)).await.unwrap();
+
+        let ledger = service.refund_payment(db, &payment.id, 100000.0, "Barang retur", Some("kasir")).await.unwrap();
+        assert_eq!((ledger.refunded_total, ledger.remaining), (100000.0, 200000.0));
+        let ledger = service.refund_payment(db, &payment.id, 150000.0, "Semen rusak", None).await.unwrap();
+        assert_eq!((ledger.refunded_total, ledger.remaining), (250000.0, 50000.0));
+        assert_eq!(ledger.refunds.len(), 2);
+        assert_eq!(ledger.refunds[0].reason, "Barang retur");
+        assert_eq!(ledger.refunds[0].created_by.as_deref(), Some("kasir"));
+
+        // Melebihi sisa 50rb ditolak tanpa mencatat apa pun
+        match service.refund_payment(db, &payment.id, 50000.01, "Kelebihan", None).await {
+            Err(PaymentError::InvalidInput(msg)) => assert!(msg.contains("remaining refundable balance of 50000.00"), "{msg}"),
+            other => panic!("expected InvalidInput, got {other:?}"),
+        }
+        assert_eq!(service.get_refund_ledger(db, &payment.id).await.unwrap().refunds.len(), 2);
+
+        let ledger = service.refund_payment(db, &payment.id, 50000.0, "Sisa", None).await.unwrap();
+        assert_eq!((ledger.refunded_total, ledger.remaining), (300000.0, 0.0));
+        assert!(matches!(service.refund_payment(db, &payment.id, 1.0, "Lagi", None).await, Err(PaymentError::InvalidInput(_))));
+
+        // Repository tetap menolak walau pre-check service dilewati
+        let bypass = Refund {
+            id: "RFD-bypass".to_string(),
+            payment_id: payment.id.clone(),
+            amount: 1.0,
+            reason: "Langsung".to_string(),
+            refund_date: Utc::now(),
+            created_by: None,
+        };
+        let conn = db_pool.acquire().await.unwrap();
+        assert!(matches!(PembayaranRepository::record_refund(conn, &bypass, 300000.0).await, Err(sqlx::Error::RowNotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_refund_rejects_invalid_input_and_uncollected_payments() {
+        let (db_pool, transaction_id) = setup_overpayment_db(300000.0).await;
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let service = PaymentService::new();
+
+        let mut pending = payment_for(&transaction_id, 100000.0);
+        pending.status = PaymentStatus::Pending;
+        let pending = service.create_payment(db, pending).await.unwrap();
+        assert!(matches!(service.refund_payment(db, &pending.id, 1000.0, "Batal", None).await, Err(PaymentError::Conflict(_))));
+
+        // CICILAN hanya bisa di-refund sebesar cicilan yang sudah masuk
+        let mut installment = payment_for(&transaction_id, 200000.0);
+        installment.status = PaymentStatus::Installment;
+        let installment = service.create_payment(db, installment).await.unwrap();
+        service.add_installment(db, &installment.id, 80000.0).await.unwrap();
+        assert!(matches!(service.refund_payment(db, &installment.id, 80000.01, "Retur", None).await, Err(PaymentError::InvalidInput(_))));
+        assert_eq!(service.refund_payment(db, &installment.id, 80000.0, "Retur", None).await.unwrap().remaining, 0.0);
+
+        assert!(matches!(service.refund_payment(db, &installment.id, 0.0, "Nol", None).await, Err(PaymentError::InvalidInput(_))));
+        assert!(matches!(service.refund_payment(db, &installment.id, 10.0, "  ", None).await, Err(PaymentError::InvalidInput(_))));
+        assert!(matches!(service.get_refund_ledger(db, "PMT-missing").await, Err(PaymentError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_manual_status_update_respects_computed_status() {
+        let (db_pool, transaction_id) = setup_overpayment_db(300000.0).await;
+        let db: &State<Pool<Any>> = State::from(&db_pool);
+        let service = PaymentService::new();
+
+        let mut payment = payment_for(&transaction_id, 300000.0);
+        payment.status = PaymentStatus::Installment;
+        let payment = service.create_payment(db, payment).await.unwrap();
+        service.add_installment(db, &payment.id, 100000.0).await.unwrap();
+
+        // Cicilan baru 100rb dari 300rb: permintaan LUNAS tidak menimpa status hasil hitungan
+        let updated = service.update_payment_status(db, payment.id.clone(), PaymentStatus::Paid, None).await.unwrap();
+        assert_eq!(updated.status, PaymentStatus::Installment);
+
+        // Tambahan yang melunasi sisa membuat status LUNAS walau yang diminta CICILAN
+        let updated = service.update_payment_status(db, payment.id.clone(), PaymentStatus::Installment, Some(200000.0)).await.unwrap();
+        assert_eq!(updated.status, PaymentStatus::Paid);
+        assert_eq!(updated.installments.len(), 2);
+
+        // Payment tanpa cicilan tetap mengikuti status yang diminta
+        let direct = service.create_payment(db, payment_for(&transaction_id, 50000.0)).await.unwrap();
+        let updated = service.update_payment_status(db, direct.id.clone(), PaymentStatus::Installment, None).await.unwrap();
+        assert_eq!(updated.status, PaymentStatus::Installment);
+    }
+
     #[test]
     fn test_installment_validation_logic() {
         let payment = Payment {
@@ -249,6 +1253,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: None,
+            audit: Default::default(),
         };
 
         assert_eq!(payment.status, PaymentStatus::Installment);
@@ -316,6 +1321,7 @@ mod tests {
                 },
             ],
             due_date: Some(Utc::now()),
+            audit: Default::default(),
         };
 
         assert_eq!(payment.installments.len(), 2);
@@ -336,6 +1342,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: None,
+            audit: Default::default(),
         };
 
         let cloned_payment = original_payment.clone();
@@ -370,6 +1377,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: None,
+            audit: Default::default(),
         };
         let db_error = sqlx::Error::PoolClosed;
         let payment_error = PaymentError::DatabaseError(db_error.to_string());
@@ -446,6 +1454,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: None,
+            audit: Default::default(),
         };
         
         let row_not_found_error = sqlx::Error::RowNotFound;
@@ -546,6 +1555,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: None,
+            audit: Default::default(),
         };
         
         if valid_payment.status != PaymentStatus::Installment {
@@ -563,6 +1573,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: None,
+            audit: Default::default(),
         };
         
         if invalid_payment.status != PaymentStatus::Installment {
@@ -635,6 +1646,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: Some(Utc::now()),
+            audit: Default::default(),
         };
         
         assert_eq!(payment.id, "PMT-PARAM-001");
@@ -721,6 +1733,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: None,
+            audit: Default::default(),
         };
         
         let repository_error = sqlx::Error::ColumnNotFound("invalid_column".to_string());
@@ -830,6 +1843,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: None,
+            audit: Default::default(),
         };
         
         let row_not_found = sqlx::Error::RowNotFound;
@@ -863,6 +1877,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: Some(Utc::now()),
+            audit: Default::default(),
         };
         
         let connection_error = sqlx::Error::PoolClosed;
@@ -970,6 +1985,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: None,
+            audit: Default::default(),
         };
         
         assert_eq!(valid_payment.status, PaymentStatus::Installment);
@@ -1021,6 +2037,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: None,
+            audit: Default::default(),
         };
         
         assert_ne!(invalid_payment.status, PaymentStatus::Installment);
@@ -1057,6 +2074,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: None,
+            audit: Default::default(),
         };
         
         let installment = Installment {
@@ -1096,6 +2114,7 @@ mod tests {
             payment_date: Utc::now(),
             installments: Vec::new(),
             due_date: Some(Utc::now()),
+            audit: Default::default(),
         };
         
         assert_eq!(test_payment.amount, 1250.0);
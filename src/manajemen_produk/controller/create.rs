@@ -1,8 +1,14 @@
+use rocket::data::{Data, ToByteUnit};
+use rocket::http::Status;
 use rocket::serde::json::Json;
 use rocket::{post, routes, Route, State};
-use crate::manajemen_produk::model::Produk;
+use crate::auth::guards::auth::AuthenticatedUser;
+use crate::common::audit::{actor, AuditTrail};
+use crate::common::csv::parse_csv_record;
+use crate::manajemen_produk::model::{Produk, ProdukBuilder};
 use crate::manajemen_produk::repository;
-use super::dto::{ProdukRequest, ProdukResponse, ApiResponse};
+use crate::manajemen_produk::repository::dto::RepositoryError;
+use super::dto::{ImportLineError, ProdukImportResponse, ProdukRequest, ProdukResponse, ApiResponse};
 use autometrics::autometrics;
 use sqlx::AnyPool;
 
@@ -10,18 +16,47 @@ use sqlx::AnyPool;
 #[post("/produk", format = "json", data = "<request>")]
 pub async fn tambah_produk(
     db: &State<AnyPool>,
+    user: Option<AuthenticatedUser>,
     request: Json<ProdukRequest>
 ) -> Json<ApiResponse<ProdukResponse>> {
-    // Validasi stok tidak boleh negatif
-    let stok = if request.stok < 0 { 0 } else { request.stok as u32 };
+    let (stok, stok_minimum) = match request.stok_dalam_unit() {
+        Ok(stok) => stok,
+        Err(error) => {
+            return Json(ApiResponse {
+                success: false,
+                message: Some(format!("Gagal menambahkan produk: Validation error: {}", error)),
+                data: None,
+            });
+        }
+    };
     
-    let produk = Produk::new(
-        request.nama.clone(),
-        request.kategori.clone(),
-        request.harga,
-        stok,
-        request.deskripsi.clone(),
-    );
+    let mut builder = ProdukBuilder::new(request.nama.clone(), request.kategori.clone())
+        .harga(request.harga)
+        .stok(stok)
+        .stok_minimum(stok_minimum)
+        .berat_gram(request.berat_gram)
+        .dimensi(request.panjang_cm, request.lebar_cm, request.tinggi_cm)
+        .harga_grosir(request.harga_grosir, request.min_qty_grosir)
+        .satuan(request.satuan.clone(), request.presisi_stok);
+    if let Some(deskripsi) = request.deskripsi.clone() {
+        builder = builder.deskripsi(deskripsi);
+    }
+    if let Some(harga_modal) = request.harga_modal {
+        builder = builder.harga_modal(harga_modal);
+    }
+    let produk = match builder.build() {
+        Ok(mut produk) => {
+            produk.audit = AuditTrail::created_by(actor(user.as_ref()));
+            produk
+        },
+        Err(errors) => {
+            return Json(ApiResponse {
+                success: false,
+                message: Some(format!("Gagal menambahkan produk: Validation error: {}", errors.join(", "))),
+                data: None,
+            });
+        }
+    };
 
     match repository::create::tambah_produk(db.inner(), &produk).await {
         Ok(id) => {
@@ -31,7 +66,7 @@ pub async fn tambah_produk(
                     Json(ApiResponse {
                         success: true,
                         message: Some("Berhasil menambahkan produk".to_string()),
-                        data: Some(ProdukResponse::from(created_produk)),
+                        data: Some(ProdukResponse::for_viewer(created_produk, user.as_ref())),
                     })
                 },
                 Ok(None) => {
@@ -60,8 +95,179 @@ pub async fn tambah_produk(
     }
 }
 
+// Batas body import CSV; limit `string` bawaan Rocket (8 KiB) terlalu kecil untuk katalog
+const IMPORT_CSV_LIMIT_MIB: u64 = 2;
+
+// Posisi kolom pada header CSV import; kolom dicocokkan lewat nama sehingga urutannya bebas
+struct KolomImport {
+    nama: usize,
+    kategori: usize,
+    harga: usize,
+    stok: usize,
+    deskripsi: Option<usize>,
+    sku: Option<usize>,
+    jumlah: usize,
+}
+
+impl KolomImport {
+    fn dari_header(header: &[String]) -> Result<Self, String> {
+        let kolom: Vec<String> = header.iter().map(|nama| nama.trim().to_lowercase()).collect();
+        let posisi = |nama: &str| kolom.iter().position(|k| k == nama);
+        let wajib = ["nama", "kategori", "harga", "stok"];
+        let hilang: Vec<&str> = wajib.iter().copied().filter(|nama| posisi(nama).is_none()).collect();
+        if !hilang.is_empty() {
+            return Err(format!("Kolom wajib tidak ada: {}", hilang.join(", ")));
+        }
+
+        Ok(KolomImport {
+            nama: posisi("nama").unwrap_or_default(),
+            kategori: posisi("kategori").unwrap_or_default(),
+            harga: posisi("harga").unwrap_or_default(),
+            stok: posisi("stok").unwrap_or_default(),
+            deskripsi: posisi("deskripsi"),
+            sku: posisi("sku"),
+            jumlah: kolom.len(),
+        })
+    }
+
+    fn parse_baris(&self, fields: &[String]) -> Result<(Option<String>, Produk), String> {
+        if fields.len() != self.jumlah {
+            return Err(format!("Jumlah kolom {}, seharusnya {}", fields.len(), self.jumlah));
+        }
+        let opsional = |index: Option<usize>| index.map(|i| fields[i].trim()).filter(|value| !value.is_empty());
+
+        let harga_text = fields[self.harga].trim();
+        let harga = harga_text.parse::<f64>().ok().filter(|harga| harga.is_finite())
+            .ok_or_else(|| format!("Harga '{}' bukan angka", harga_text))?;
+        let stok_text = fields[self.stok].trim();
+        let stok = stok_text.parse::<u32>()
+            .map_err(|_| format!("Stok '{}' harus bilangan bulat tidak negatif", stok_text))?;
+        let sku = opsional(self.sku).map(str::to_string);
+        if sku.as_ref().is_some_and(|sku| sku.len() > 64) {
+            return Err("SKU terlalu panjang (maksimal 64 karakter)".to_string());
+        }
+
+        let mut builder = ProdukBuilder::new(fields[self.nama].trim().to_string(), fields[self.kategori].trim().to_string())
+            .harga(harga)
+            .stok(stok);
+        if let Some(deskripsi) = opsional(self.deskripsi) {
+            builder = builder.deskripsi(deskripsi.to_string());
+        }
+        let produk = builder.build().map_err(|errors| errors.join(", "))?;
+        Ok((sku, produk))
+    }
+}
+
+// Hasil parse satu baris import: (nomor baris, sku, produk)
+pub type BarisImport = (usize, Option<String>, Produk);
+
+// Parse seluruh isi CSV import (header wajib: nama, kategori, harga, stok; opsional: deskripsi, sku).
+// Semua baris diperiksa sehingga setiap kesalahan dilaporkan sekaligus bersama nomor barisnya.
+// Baris kosong dilewati; BOM UTF-8 di awal file diabaikan.
+pub fn parse_produk_csv(body: &str) -> Result<Vec<BarisImport>, Vec<ImportLineError>> {
+    let line_error = |line: usize, message: String| vec![ImportLineError { line, message }];
+    let mut lines = body
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .enumerate()
+        .map(|(index, text)| (index + 1, text))
+        .filter(|(_, text)| !text.trim().is_empty());
+
+    let Some((header_line, header)) = lines.next() else {
+        return Err(line_error(1, "File CSV kosong".to_string()));
+    };
+    let kolom = parse_csv_record(header)
+        .and_then(|header| KolomImport::dari_header(&header))
+        .map_err(|message| line_error(header_line, message))?;
+
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    for (line, text) in lines {
+        match parse_csv_record(text).and_then(|fields| kolom.parse_baris(&fields)) {
+            Ok((sku, produk)) => rows.push((line, sku, produk)),
+            Err(message) => errors.push(ImportLineError { line, message }),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    if rows.is_empty() {
+        return Err(line_error(header_line, "File CSV tidak berisi data produk".to_string()));
+    }
+    Ok(rows)
+}
+
+fn import_gagal(status: Status, message: String, errors: Vec<ImportLineError>) -> (Status, Json<ApiResponse<ProdukImportResponse>>) {
+    (status, Json(ApiResponse {
+        success: false,
+        message: Some(message),
+        data: Some(ProdukImportResponse { imported: 0, ids: Vec::new(), errors }),
+    }))
+}
+
+#[autometrics]
+#[post("/produk/import.csv", data = "<body>")]
+pub async fn impor_produk_csv(
+    db: &State<AnyPool>,
+    user: Option<AuthenticatedUser>,
+    body: Data<'_>
+) -> (Status, Json<ApiResponse<ProdukImportResponse>>) {
+    let body = match body.open(IMPORT_CSV_LIMIT_MIB.mebibytes()).into_string().await {
+        Ok(body) if body.is_complete() => body.into_inner(),
+        Ok(_) => {
+            return import_gagal(
+                Status::PayloadTooLarge,
+                format!("File CSV melebihi batas {} MiB", IMPORT_CSV_LIMIT_MIB),
+                Vec::new(),
+            );
+        },
+        Err(e) => return import_gagal(Status::BadRequest, format!("Gagal membaca file CSV: {}", e), Vec::new()),
+    };
+
+    let parsed = match parse_produk_csv(&body) {
+        Ok(parsed) => parsed,
+        Err(errors) => {
+            return import_gagal(
+                Status::BadRequest,
+                format!("Gagal mengimpor produk: {} baris tidak valid", errors.len()),
+                errors,
+            );
+        }
+    };
+
+    let audit = AuditTrail::created_by(actor(user.as_ref()));
+    let lines: Vec<usize> = parsed.iter().map(|(line, _, _)| *line).collect();
+    let rows: Vec<(Option<String>, Produk)> = parsed
+        .into_iter()
+        .map(|(_, sku, mut produk)| {
+            produk.audit = audit.clone();
+            (sku, produk)
+        })
+        .collect();
+
+    match repository::create::tambah_produk_batch(db.inner(), &rows).await {
+        Ok(ids) => (Status::Ok, Json(ApiResponse {
+            success: true,
+            message: Some(format!("Berhasil mengimpor {} produk", ids.len())),
+            data: Some(ProdukImportResponse { imported: ids.len(), ids, errors: Vec::new() }),
+        })),
+        Err((index, e)) => {
+            let status = match e {
+                RepositoryError::DatabaseError(_) => Status::InternalServerError,
+                _ => Status::BadRequest,
+            };
+            import_gagal(
+                status,
+                "Gagal mengimpor produk, tidak ada produk yang disimpan".to_string(),
+                vec![ImportLineError { line: lines[index], message: e.to_string() }],
+            )
+        }
+    }
+}
+
 pub fn routes() -> Vec<Route> {
-    routes![tambah_produk]
+    routes![tambah_produk, impor_produk_csv]
 }
 
 #[cfg(test)]
@@ -90,7 +296,20 @@ mod tests {
                 kategori TEXT NOT NULL,
                 harga REAL NOT NULL,
                 stok INTEGER NOT NULL,
-                deskripsi TEXT
+                stok_minimum INTEGER NOT NULL DEFAULT 0,
+                harga_modal REAL,
+                berat_gram INTEGER,
+                panjang_cm INTEGER,
+                lebar_cm INTEGER,
+                tinggi_cm INTEGER,
+                harga_grosir REAL,
+                min_qty_grosir INTEGER,
+                satuan VARCHAR(20) NOT NULL DEFAULT 'pcs',
+                presisi_stok INTEGER NOT NULL DEFAULT 0,
+                created_by TEXT,
+                updated_by TEXT,
+                deskripsi TEXT,
+                sku VARCHAR(64) UNIQUE
             )
             "#
         )
@@ -149,7 +368,7 @@ mod tests {
         assert_eq!(produk_data.nama, "Laptop Gaming ASUS");
         assert_eq!(produk_data.kategori, "Elektronik");
         assert_eq!(produk_data.harga, 15000000.50);
-        assert_eq!(produk_data.stok, 10);
+        assert_eq!(produk_data.stok, 10.0);
         assert_eq!(produk_data.deskripsi, Some("Laptop gaming high-end dengan RTX 4080".to_string()));
     }
 
@@ -186,7 +405,7 @@ mod tests {
         assert_eq!(produk_data.nama, "Mouse Wireless");
         assert_eq!(produk_data.kategori, "Aksesoris");
         assert_eq!(produk_data.harga, 150000.0);
-        assert_eq!(produk_data.stok, 50);
+        assert_eq!(produk_data.stok, 50.0);
         assert_eq!(produk_data.deskripsi, None);
     }
 
@@ -220,7 +439,7 @@ mod tests {
         assert!(response_body.data.is_some());
         
         let produk_data = response_body.data.unwrap();
-        assert_eq!(produk_data.stok, 0); // Should be converted to 0
+        assert_eq!(produk_data.stok, 0.0); // Should be converted to 0
     }
 
     #[tokio::test]
@@ -304,7 +523,7 @@ mod tests {
         assert!(response_body.data.is_some());
         
         let produk_data = response_body.data.unwrap();
-        assert_eq!(produk_data.stok, 999999);
+        assert_eq!(produk_data.stok, 999999.0);
         assert!((produk_data.harga - 999999999.99).abs() < 1.0);
     }
 
@@ -338,7 +557,7 @@ mod tests {
         assert!(response_body.data.is_some());
         
         let produk_data = response_body.data.unwrap();
-        assert_eq!(produk_data.stok, 0);
+        assert_eq!(produk_data.stok, 0.0);
     }
 
     #[tokio::test]
@@ -468,4 +687,62 @@ mod tests {
             
         assert_eq!(total_count, 3);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_impor_produk_csv_reports_malformed_row_and_saves_nothing() {
+        use crate::manajemen_produk::controller::dto::{ImportLineError, ProdukImportResponse};
+        use sqlx::Row;
+
+        let db_pool = setup_test_db().await;
+        let rocket = rocket::build()
+            .manage(db_pool.clone())
+            .mount("/api", routes![impor_produk_csv]);
+        let client = Client::tracked(rocket).await.expect("Valid rocket instance");
+
+        let csv = "nama,kategori,harga,stok,deskripsi,sku\n\
+                   Semen Tiga Roda,Bahan Bangunan,65000,100,\"Semen 50kg, abu-abu\",SMN-50\n\
+                   Cat Tembok,Cat,abc,10,,CAT-01\n\
+                   Paku 5cm,Perkakas,15000,200,,\n";
+        let response = client.post("/api/produk/import.csv")
+            .header(rocket::http::ContentType::CSV)
+            .body(csv)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), rocket::http::Status::BadRequest);
+        let body: ApiResponse<ProdukImportResponse> = response.into_json().await.unwrap();
+        assert!(!body.success);
+        let report = body.data.unwrap();
+        assert_eq!(report.imported, 0);
+        assert_eq!(report.errors, vec![ImportLineError { line: 3, message: "Harga 'abc' bukan angka".to_string() }]);
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM produk").fetch_one(&db_pool).await.unwrap();
+        assert_eq!(count, 0);
+
+        let response = client.post("/api/produk/import.csv")
+            .header(rocket::http::ContentType::CSV)
+            .body(csv.replace("abc", "85000"))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let report = response.into_json::<ApiResponse<ProdukImportResponse>>().await.unwrap().data.unwrap();
+        assert_eq!(report.imported, 3);
+
+        let row = sqlx::query("SELECT deskripsi, sku FROM produk WHERE id = $1")
+            .bind(report.ids[0])
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(row.get::<String, _>("deskripsi"), "Semen 50kg, abu-abu");
+        assert_eq!(row.get::<String, _>("sku"), "SMN-50");
+
+        // Import ulang SKU yang sama ditolak seluruhnya, dengan nomor baris yang bentrok
+        let response = client.post("/api/produk/import.csv")
+            .header(rocket::http::ContentType::CSV)
+            .body("nama,kategori,harga,stok,sku\nSemen Gresik,Bahan Bangunan,60000,10,SMN-50\n")
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), rocket::http::Status::BadRequest);
+        let report = response.into_json::<ApiResponse<ProdukImportResponse>>().await.unwrap().data.unwrap();
+        assert_eq!(report.errors[0].line, 2);
+    }
+}
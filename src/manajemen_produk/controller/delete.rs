@@ -2,6 +2,7 @@ use rocket::serde::json::Json;
 use rocket::{delete, routes, Route, State};
 use crate::manajemen_produk::repository;
 use super::dto::ApiResponse;
+use crate::common::messages::{Lang, Messages};
 use autometrics::autometrics;
 use sqlx::AnyPool;
 
@@ -9,27 +10,28 @@ use sqlx::AnyPool;
 #[delete("/produk/<id>")]
 pub async fn hapus_produk(
     db: &State<AnyPool>,
-    id: i64
+    id: i64,
+    lang: Lang
 ) -> Json<ApiResponse<()>> {
     match repository::delete::hapus_produk(db.inner(), id).await {
         Ok(true) => {
             Json(ApiResponse {
                 success: true,
-                message: Some(format!("Produk dengan ID {} berhasil dihapus", id)),
+                message: Some(Messages::format("produk.deleted", lang, &[&id])),
                 data: None,
             })
         },
         Ok(false) => {
             Json(ApiResponse {
                 success: false,
-                message: Some(format!("Produk dengan ID {} tidak ditemukan", id)),
+                message: Some(Messages::format("produk.not_found", lang, &[&id])),
                 data: None,
             })
         },
         Err(e) => {
             Json(ApiResponse {
                 success: false,
-                message: Some(format!("Gagal menghapus produk: {}", e)),
+                message: Some(Messages::format("produk.delete_failed", lang, &[&e])),
                 data: None,
             })
         }
@@ -65,6 +67,18 @@ mod tests {
                 kategori TEXT NOT NULL,
                 harga REAL NOT NULL,
                 stok INTEGER NOT NULL,
+                stok_minimum INTEGER NOT NULL DEFAULT 0,
+                harga_modal REAL,
+                berat_gram INTEGER,
+                panjang_cm INTEGER,
+                lebar_cm INTEGER,
+                tinggi_cm INTEGER,
+                harga_grosir REAL,
+                min_qty_grosir INTEGER,
+                satuan VARCHAR(20) NOT NULL DEFAULT 'pcs',
+                presisi_stok INTEGER NOT NULL DEFAULT 0,
+                created_by TEXT,
+                updated_by TEXT,
                 deskripsi TEXT
             )
             "#
@@ -1,40 +1,284 @@
 use rocket::serde::{Deserialize, Serialize};
+use crate::auth::guards::auth::AuthenticatedUser;
+use crate::common::audit::AuditTrail;
+use crate::common::money;
 use crate::manajemen_produk::model::Produk;
+use crate::manajemen_produk::model::produk::{jumlah_ke_unit_stok, low_stock_threshold, skala_stok, MAX_PRESISI_STOK, unit_stok_ke_jumlah, DEFAULT_SATUAN};
 
+// `stok` dan `stok_minimum` dalam satuan jual; desimal hanya diterima sampai `presisi_stok` digit
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(crate = "rocket::serde")]
 pub struct ProdukRequest {
     pub nama: String,
     pub kategori: String,
     pub harga: f64,
-    pub stok: i32,
+    pub stok: f64,
     pub deskripsi: Option<String>,
+    #[serde(default)]
+    pub stok_minimum: f64,
+    #[serde(default)]
+    pub harga_modal: Option<f64>,
+    #[serde(default)]
+    pub berat_gram: Option<i32>,
+    #[serde(default)]
+    pub panjang_cm: Option<i32>,
+    #[serde(default)]
+    pub lebar_cm: Option<i32>,
+    #[serde(default)]
+    pub tinggi_cm: Option<i32>,
+    #[serde(default)]
+    pub harga_grosir: Option<f64>,
+    #[serde(default)]
+    pub min_qty_grosir: Option<u32>,
+    #[serde(default = "default_satuan")]
+    pub satuan: String,
+    #[serde(default)]
+    pub presisi_stok: u32,
+}
+
+fn default_satuan() -> String {
+    DEFAULT_SATUAN.to_string()
+}
+
+impl ProdukRequest {
+    // `(stok, stok_minimum)` dalam unit terkecil; stok negatif dianggap 0 seperti sebelumnya
+    pub fn stok_dalam_unit(&self) -> Result<(u32, u32), String> {
+        let stok = jumlah_ke_unit_stok(self.stok.max(0.0), self.presisi_stok)?;
+        let stok_minimum = jumlah_ke_unit_stok(self.stok_minimum, self.presisi_stok)?;
+        Ok((stok, stok_minimum))
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
-#[serde(crate = "rocket::serde")]
+#[serde(crate = "rocket::serde", rename_all = "snake_case")]
 pub struct ProdukResponse {
     pub id: Option<i64>,
     pub nama: String,
     pub kategori: String,
     pub harga: f64,
-    pub stok: u32,
+    pub stok: f64,
     pub deskripsi: Option<String>,
+    pub stok_minimum: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub harga_modal: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub berat_gram: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub panjang_cm: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lebar_cm: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tinggi_cm: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub harga_grosir: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_qty_grosir: Option<u32>,
+    #[serde(default = "default_satuan")]
+    pub satuan: String,
+    #[serde(default)]
+    pub presisi_stok: u32,
+    pub stock_status: String,
+    #[serde(flatten)]
+    pub audit: AuditTrail,
 }
 
 impl From<Produk> for ProdukResponse {
     fn from(produk: Produk) -> Self {
+        // Ambang milik produk dipakai bila diset, selain itu ambang global (dalam satuan jual)
+        let threshold = if produk.stok_minimum > 0 {
+            produk.stok_minimum
+        } else {
+            low_stock_threshold().saturating_mul(skala_stok(produk.presisi_stok))
+        };
+        let stock_status = produk.stock_status(threshold).to_string();
         Self {
             id: produk.id,
             nama: produk.nama,
             kategori: produk.kategori,
             harga: produk.harga,
-            stok: produk.stok,
+            stok: unit_stok_ke_jumlah(produk.stok, produk.presisi_stok),
             deskripsi: produk.deskripsi,
+            stok_minimum: unit_stok_ke_jumlah(produk.stok_minimum, produk.presisi_stok),
+            harga_modal: produk.harga_modal,
+            berat_gram: produk.berat_gram,
+            panjang_cm: produk.panjang_cm,
+            lebar_cm: produk.lebar_cm,
+            tinggi_cm: produk.tinggi_cm,
+            harga_grosir: produk.harga_grosir,
+            min_qty_grosir: produk.min_qty_grosir,
+            satuan: produk.satuan,
+            presisi_stok: produk.presisi_stok,
+            stock_status,
+            audit: produk.audit,
+        }
+    }
+}
+
+impl ProdukResponse {
+    // Sama seperti `From<Produk>`, tetapi field audit hanya ikut untuk admin
+    pub fn for_viewer(produk: Produk, user: Option<&AuthenticatedUser>) -> Self {
+        let mut response = Self::from(produk);
+        response.audit.redact_unless_admin(user);
+        response
+    }
+}
+
+// Satu baris laporan margin; hanya produk yang sudah punya `harga_modal`
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ProdukMargin {
+    pub id: Option<i64>,
+    pub nama: String,
+    pub kategori: String,
+    pub harga: f64,
+    pub harga_modal: f64,
+    pub margin: f64,
+    pub margin_persen: f64,
+}
+
+impl ProdukMargin {
+    /// Margin dihitung dalam sen; persentase terhadap harga jual (0 bila harga 0).
+    pub fn from_produk(produk: &Produk) -> Option<Self> {
+        let harga_modal = produk.harga_modal?;
+        let harga_sen = money::to_sen(produk.harga);
+        let margin_sen = harga_sen - money::to_sen(harga_modal);
+        let margin_persen = if harga_sen == 0 {
+            0.0
+        } else {
+            (margin_sen as f64 * 10000.0 / harga_sen as f64).round() / 100.0
+        };
+        Some(Self {
+            id: produk.id,
+            nama: produk.nama.clone(),
+            kategori: produk.kategori.clone(),
+            harga: produk.harga,
+            harga_modal,
+            margin: money::from_sen(margin_sen),
+            margin_persen,
+        })
+    }
+}
+
+// Nilai persediaan saat ini (harga * stok), total dan per kategori urut abjad.
+// Dijumlahkan dalam sen seperti baris TOTAL pada export CSV. `total_stok` dalam satuan produk
+// (2.5 kg dihitung 2.5, bukan 2500 gram), dijumlahkan dalam unit presisi terhalus agar tetap eksak.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ProdukValuation {
+    pub total_nilai: f64,
+    pub total_stok: f64,
+    pub per_kategori: Vec<KategoriValuation>,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct KategoriValuation {
+    pub kategori: String,
+    pub jumlah_produk: usize,
+    pub total_stok: f64,
+    pub nilai: f64,
+}
+
+impl ProdukValuation {
+    pub fn from_produk(produk_list: &[Produk]) -> Self {
+        let skala = skala_stok(MAX_PRESISI_STOK);
+        let mut per_kategori: std::collections::BTreeMap<&str, (usize, i64, i64)> = std::collections::BTreeMap::new();
+        for produk in produk_list {
+            let entry = per_kategori.entry(produk.kategori.as_str()).or_default();
+            entry.0 += 1;
+            entry.1 += (produk.stok_dalam_satuan() * skala as f64).round() as i64;
+            entry.2 += money::line_subtotal_sen(produk.harga, produk.stok_dalam_satuan());
+        }
+
+        let total_sen: i64 = per_kategori.values().map(|(_, _, nilai_sen)| nilai_sen).sum();
+        Self {
+            total_nilai: money::from_sen(total_sen),
+            total_stok: per_kategori.values().map(|(_, stok, _)| stok).sum::<i64>() as f64 / skala as f64,
+            per_kategori: per_kategori
+                .into_iter()
+                .map(|(kategori, (jumlah_produk, total_stok, nilai_sen))| KategoriValuation {
+                    kategori: kategori.to_string(),
+                    jumlah_produk,
+                    total_stok: total_stok as f64 / skala as f64,
+                    nilai: money::from_sen(nilai_sen),
+                })
+                .collect(),
         }
     }
 }
 
+// Payload scan kasir: cukup satu round trip untuk nama, harga, dan ketersediaan
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ProdukScanResponse {
+    pub sku: String,
+    pub produk: ProdukResponse,
+}
+
+/// Kesalahan parse/validasi satu baris import CSV; `line` dihitung dari 1 termasuk header.
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ImportLineError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Hasil import CSV. Import bersifat semua-atau-tidak-sama-sekali: bila `errors` tidak
+/// kosong, tidak ada produk yang disimpan.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ProdukImportResponse {
+    pub imported: usize,
+    pub ids: Vec<i64>,
+    pub errors: Vec<ImportLineError>,
+}
+
+/// `delta` dalam satuan jual: positif untuk barang masuk, negatif untuk barang keluar/retur ke supplier.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct AdjustStokRequest {
+    pub delta: f64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct RenameKategoriRequest {
+    pub from: String,
+    pub to: String,
+}
+
+/// `from`/`to` sudah dinormalisasi; `affected` adalah jumlah produk yang dipindahkan.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct RenameKategoriResponse {
+    pub from: String,
+    pub to: String,
+    pub affected: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReservasiRequest {
+    pub jumlah: f64,
+    pub ttl_seconds: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReservasiResponse {
+    pub id: String,
+    pub produk_id: i64,
+    pub jumlah: f64,
+    pub expires_at: String,
+    pub available_stock: f64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct TagRequest {
+    pub tag: String,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(crate = "rocket::serde")]
 pub struct ApiResponse<T> {
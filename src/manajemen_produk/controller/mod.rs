@@ -1,5 +1,8 @@
+use std::sync::Arc;
 use rocket::Route;
 use rocket::fairing::AdHoc;
+use crate::manajemen_produk::service::produk_dispatcher::ProdukDispatcher;
+use crate::manajemen_produk::service::produk_notifier::ProdukNotifier;
 
 // Fungsi untuk mengembalikan semua routes
 pub fn routes() -> Vec<Route> {
@@ -10,6 +13,8 @@ pub fn routes() -> Vec<Route> {
     all_routes.extend(read::routes());
     all_routes.extend(update::routes());
     all_routes.extend(delete::routes());
+    all_routes.extend(reservation::routes());
+    all_routes.extend(tag::routes());
     
     all_routes
 }
@@ -17,7 +22,10 @@ pub fn routes() -> Vec<Route> {
 // Route stage untuk digunakan di main.rs
 pub fn route_stage() -> AdHoc {
     AdHoc::on_ignite("Manajemen Produk Routes", |rocket| async {
-        rocket.mount("/api", routes())
+        let produk_dispatcher: Arc<dyn ProdukNotifier> = Arc::new(ProdukDispatcher::new());
+        rocket
+            .manage(produk_dispatcher)
+            .mount("/api", routes())
     })
 }
 
@@ -25,6 +33,8 @@ pub mod create;
 pub mod read;
 pub mod update;
 pub mod delete;
+pub mod reservation;
+pub mod tag;
 pub mod dto;
 
 // Re-export untuk kemudahan akses
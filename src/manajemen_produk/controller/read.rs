@@ -1,28 +1,83 @@
 use rocket::serde::json::Json;
+use rocket::http::Status;
 use rocket::{get, routes, Route, State};
+use crate::auth::guards::auth::AuthenticatedUser;
 use crate::manajemen_produk::repository;
-use super::dto::{ProdukResponse, ApiResponse};
+use crate::manajemen_produk::repository::read::{ProdukFilter, StokFilter};
+use crate::manajemen_produk::repository::dto::RepositoryError;
+use crate::manajemen_produk::model::Produk;
+use crate::common::csv::{csv_field, CsvExport};
+use crate::common::money;
+use crate::common::pagination::ListQuery;
+use super::dto::{ProdukResponse, ProdukScanResponse, ProdukMargin, ProdukValuation, ApiResponse};
+use crate::common::messages::{Lang, Messages};
 use autometrics::autometrics;
 use sqlx::AnyPool;
 
+/// `kategori`, `low_stock_only`/`out_of_stock_only` dan `keyword` bisa digabung; kedua flag stok
+/// sekaligus ditolak.
 #[autometrics]
-#[get("/produk")]
-pub async fn list_produk(db: &State<AnyPool>) -> Json<ApiResponse<Vec<ProdukResponse>>> {
-    match repository::read::ambil_semua_produk(db.inner()).await {
+#[get("/produk?<kategori>&<low_stock_only>&<out_of_stock_only>&<list..>")]
+pub async fn list_produk(
+    db: &State<AnyPool>,
+    kategori: Option<String>,
+    low_stock_only: Option<bool>,
+    out_of_stock_only: Option<bool>,
+    list: ListQuery,
+    lang: Lang,
+    user: Option<AuthenticatedUser>
+) -> Json<ApiResponse<Vec<ProdukResponse>>> {
+    let Ok(stok) = StokFilter::from_flags(low_stock_only.unwrap_or(false), out_of_stock_only.unwrap_or(false)) else {
+        return Json(ApiResponse {
+            success: false,
+            message: Some(Messages::get("produk.stok_filter_conflict", lang).to_string()),
+            data: None,
+        });
+    };
+
+    // Dengan keyword, urutan relevansi dari cari_produk dipertahankan kecuali `sort` diminta.
+    let filter = ProdukFilter { kategori: kategori.as_deref(), stok };
+    let result = match list.keyword() {
+        Some(keyword) => repository::read::cari_produk(db.inner(), &keyword, &filter, &list).await,
+        None => repository::read::ambil_produk_dengan_filter(db.inner(), &filter, &list).await,
+    };
+    match result {
+        Err(RepositoryError::ValidationError(message)) => Json(ApiResponse {
+            success: false,
+            message: Some(message),
+            data: None,
+        }),
         Ok(produk_list) => {
             let response_list = produk_list.into_iter()
-                .map(ProdukResponse::from)
+                .map(|produk| ProdukResponse::for_viewer(produk, user.as_ref()))
                 .collect();
                 
             Json(ApiResponse {
                 success: true,
-                message: Some("Berhasil mengambil daftar produk".to_string()),
+                message: Some(Messages::get("produk.list_ok", lang).to_string()),
                 data: Some(response_list),
             })
         },
         Err(e) => Json(ApiResponse {
             success: false,
-            message: Some(format!("Gagal mengambil daftar produk: {}", e)),
+            message: Some(Messages::format("produk.list_failed", lang, &[&e])),
+            data: None,
+        }),
+    }
+}
+
+#[autometrics]
+#[get("/produk/low-stock")]
+pub async fn list_produk_stok_rendah(db: &State<AnyPool>, lang: Lang, user: Option<AuthenticatedUser>) -> Json<ApiResponse<Vec<ProdukResponse>>> {
+    match repository::read::ambil_produk_stok_rendah(db.inner()).await {
+        Ok(produk_list) => Json(ApiResponse {
+            success: true,
+            message: Some(Messages::get("produk.list_ok", lang).to_string()),
+            data: Some(produk_list.into_iter().map(|produk| ProdukResponse::for_viewer(produk, user.as_ref())).collect()),
+        }),
+        Err(e) => Json(ApiResponse {
+            success: false,
+            message: Some(Messages::format("produk.list_failed", lang, &[&e])),
             data: None,
         }),
     }
@@ -30,28 +85,125 @@ pub async fn list_produk(db: &State<AnyPool>) -> Json<ApiResponse<Vec<ProdukResp
 
 #[autometrics]
 #[get("/produk/<id>")]
-pub async fn detail_produk(db: &State<AnyPool>, id: i64) -> Json<ApiResponse<ProdukResponse>> {
+pub async fn detail_produk(db: &State<AnyPool>, id: i64, lang: Lang, user: Option<AuthenticatedUser>) -> Json<ApiResponse<ProdukResponse>> {
     match repository::read::ambil_produk_by_id(db.inner(), id).await {
         Ok(Some(produk)) => Json(ApiResponse {
             success: true,
-            message: Some("Berhasil mengambil detail produk".to_string()),
-            data: Some(ProdukResponse::from(produk)),
+            message: Some(Messages::get("produk.detail_ok", lang).to_string()),
+            data: Some(ProdukResponse::for_viewer(produk, user.as_ref())),
         }),
         Ok(None) => Json(ApiResponse {
             success: false,
-            message: Some(format!("Produk dengan ID {} tidak ditemukan", id)),
+            message: Some(Messages::format("produk.not_found", lang, &[&id])),
+            data: None,
+        }),
+        Err(e) => Json(ApiResponse {
+            success: false,
+            message: Some(Messages::format("produk.detail_failed", lang, &[&e])),
+            data: None,
+        }),
+    }
+}
+
+#[autometrics]
+#[get("/produk/scan/<sku>")]
+pub async fn scan_produk(db: &State<AnyPool>, sku: &str, lang: Lang, user: Option<AuthenticatedUser>) -> (Status, Json<ApiResponse<ProdukScanResponse>>) {
+    match repository::read::ambil_produk_by_sku(db.inner(), sku).await {
+        Ok(Some(produk)) => (Status::Ok, Json(ApiResponse {
+            success: true,
+            message: Some(Messages::get("produk.detail_ok", lang).to_string()),
+            data: Some(ProdukScanResponse {
+                sku: sku.trim().to_string(),
+                produk: ProdukResponse::for_viewer(produk, user.as_ref()),
+            }),
+        })),
+        Ok(None) => (Status::NotFound, Json(ApiResponse {
+            success: false,
+            message: Some(Messages::format("produk.sku_not_found", lang, &[&sku.trim()])),
+            data: None,
+        })),
+        Err(e) => (Status::InternalServerError, Json(ApiResponse {
+            success: false,
+            message: Some(Messages::format("produk.detail_failed", lang, &[&e])),
+            data: None,
+        })),
+    }
+}
+
+#[autometrics]
+#[get("/reports/margins?<kategori>")]
+pub async fn laporan_margin(db: &State<AnyPool>, kategori: Option<String>, lang: Lang) -> Json<ApiResponse<Vec<ProdukMargin>>> {
+    let filter = ProdukFilter { kategori: kategori.as_deref(), stok: None };
+    match repository::read::ambil_produk_dengan_filter(db.inner(), &filter, &ListQuery::default()).await {
+        Ok(produk_list) => Json(ApiResponse {
+            success: true,
+            message: Some(Messages::get("produk.margin_ok", lang).to_string()),
+            data: Some(produk_list.iter().filter_map(ProdukMargin::from_produk).collect()),
+        }),
+        Err(e) => Json(ApiResponse {
+            success: false,
+            message: Some(Messages::format("produk.list_failed", lang, &[&e])),
             data: None,
         }),
+    }
+}
+
+// Nilai persediaan per saat ini; belum ada ledger mutasi stok, jadi nilai historis
+// (per tanggal tertentu) belum bisa direkonstruksi.
+#[autometrics]
+#[get("/produk/valuation")]
+pub async fn valuasi_produk(db: &State<AnyPool>, lang: Lang) -> Json<ApiResponse<ProdukValuation>> {
+    match repository::read::ambil_semua_produk(db.inner()).await {
+        Ok(produk_list) => Json(ApiResponse {
+            success: true,
+            message: Some(Messages::get("produk.valuation_ok", lang).to_string()),
+            data: Some(ProdukValuation::from_produk(&produk_list)),
+        }),
         Err(e) => Json(ApiResponse {
             success: false,
-            message: Some(format!("Gagal mengambil detail produk: {}", e)),
+            message: Some(Messages::format("produk.list_failed", lang, &[&e])),
             data: None,
         }),
     }
 }
 
+// Isi CSV export: satu baris per produk dengan `nilai` = harga * stok (dalam satuan jual), ditutup baris TOTAL
+pub fn produk_export_csv(produk_list: &[(Option<String>, Produk)]) -> String {
+    let mut csv = String::from("id,sku,nama,kategori,harga,stok,nilai\n");
+    let mut total_sen: i64 = 0;
+    for (sku, produk) in produk_list {
+        let nilai_sen = money::line_subtotal_sen(produk.harga, produk.stok_dalam_satuan());
+        total_sen += nilai_sen;
+        csv.push_str(&format!(
+            "{},{},{},{},{:.2},{},{:.2}\n",
+            produk.id.map(|id| id.to_string()).unwrap_or_default(),
+            csv_field(sku.as_deref().unwrap_or("")),
+            csv_field(&produk.nama),
+            csv_field(&produk.kategori),
+            produk.harga,
+            produk.stok_dalam_satuan(),
+            money::from_sen(nilai_sen),
+        ));
+    }
+    csv.push_str(&format!("TOTAL,,,,,,{:.2}\n", money::from_sen(total_sen)));
+    csv
+}
+
+#[autometrics]
+#[get("/produk/export.csv")]
+pub async fn export_produk_csv(db: &State<AnyPool>, lang: Lang) -> Result<CsvExport, (Status, Json<ApiResponse<()>>)> {
+    match repository::read::ambil_produk_untuk_export(db.inner()).await {
+        Ok(produk_list) => Ok(CsvExport::attachment(produk_export_csv(&produk_list), "produk.csv")),
+        Err(e) => Err((Status::InternalServerError, Json(ApiResponse {
+            success: false,
+            message: Some(Messages::format("produk.list_failed", lang, &[&e])),
+            data: None,
+        }))),
+    }
+}
+
 pub fn routes() -> Vec<Route> {
-    routes![list_produk, detail_produk]
+    routes![list_produk, list_produk_stok_rendah, export_produk_csv, valuasi_produk, scan_produk, detail_produk, laporan_margin]
 }
 
 #[cfg(test)]
@@ -60,7 +212,8 @@ mod tests {
     use rocket::local::asynchronous::Client;
     use rocket::{Build, Rocket};
     use sqlx::{any::{AnyPoolOptions, install_default_drivers}, AnyPool};
-    use crate::manajemen_produk::controller::dto::{ApiResponse, ProdukResponse};
+    use crate::manajemen_produk::controller::dto::{ApiResponse, ProdukResponse, ProdukScanResponse};
+    use crate::manajemen_produk::model::produk::DEFAULT_SATUAN;
 
     async fn setup_test_db() -> AnyPool {
         install_default_drivers();
@@ -79,7 +232,20 @@ mod tests {
                 kategori TEXT NOT NULL,
                 harga REAL NOT NULL,
                 stok INTEGER NOT NULL,
-                deskripsi TEXT
+                stok_minimum INTEGER NOT NULL DEFAULT 0,
+                harga_modal REAL,
+                berat_gram INTEGER,
+                panjang_cm INTEGER,
+                lebar_cm INTEGER,
+                tinggi_cm INTEGER,
+                harga_grosir REAL,
+                min_qty_grosir INTEGER,
+                satuan VARCHAR(20) NOT NULL DEFAULT 'pcs',
+                presisi_stok INTEGER NOT NULL DEFAULT 0,
+                created_by TEXT,
+                updated_by TEXT,
+                deskripsi TEXT,
+                sku TEXT UNIQUE
             )
             "#
         )
@@ -123,7 +289,7 @@ mod tests {
         
         let rocket = rocket::build()
             .manage(db_pool.clone())
-            .mount("/api", routes![list_produk, detail_produk]);
+            .mount("/api", routes![list_produk, scan_produk, detail_produk, laporan_margin, valuasi_produk]);
             
         let client = Client::tracked(rocket)
             .await
@@ -162,7 +328,7 @@ mod tests {
         assert_eq!(first_product.nama, "Laptop Gaming");
         assert_eq!(first_product.kategori, "Elektronik");
         assert!((first_product.harga - 15000000.50).abs() < f64::EPSILON);
-        assert_eq!(first_product.stok, 10);
+        assert_eq!(first_product.stok, 10.0);
         assert_eq!(first_product.deskripsi, Some("Laptop gaming high-end dengan RTX 4080".to_string()));
 
         // Verify second product (with None description)
@@ -170,13 +336,13 @@ mod tests {
         assert_eq!(second_product.nama, "Mouse Wireless");
         assert_eq!(second_product.kategori, "Aksesoris");
         assert!((second_product.harga - 150000.00).abs() < f64::EPSILON);
-        assert_eq!(second_product.stok, 50);
+        assert_eq!(second_product.stok, 50.0);
         assert_eq!(second_product.deskripsi, None);
 
         // Verify third product (with zero stock)
         let third_product = &products[2];
         assert_eq!(third_product.nama, "Keyboard Mechanical");
-        assert_eq!(third_product.stok, 0);
+        assert_eq!(third_product.stok, 0.0);
     }
 
     #[tokio::test]
@@ -265,7 +431,7 @@ mod tests {
         assert_eq!(found_product.nama, "Laptop Gaming");
         assert_eq!(found_product.kategori, "Elektronik");
         assert!((found_product.harga - 15000000.50).abs() < f64::EPSILON);
-        assert_eq!(found_product.stok, 10);
+        assert_eq!(found_product.stok, 10.0);
     }
 
     #[tokio::test]
@@ -292,6 +458,31 @@ mod tests {
         assert!(response_body.data.is_none());
     }
 
+    #[tokio::test]
+    async fn test_detail_produk_nonexistent_localized_by_accept_language() {
+        let (client, _db_pool) = setup_rocket_client().await;
+
+        let english: ApiResponse<ProdukResponse> = client
+            .get("/api/produk/999999")
+            .header(rocket::http::Header::new("Accept-Language", "en-US,en;q=0.9"))
+            .dispatch()
+            .await
+            .into_json()
+            .await
+            .expect("Valid JSON response");
+        assert_eq!(english.message.as_deref(), Some("Product with ID 999999 not found"));
+
+        let indonesian: ApiResponse<ProdukResponse> = client
+            .get("/api/produk/999999")
+            .header(rocket::http::Header::new("Accept-Language", "id-ID"))
+            .dispatch()
+            .await
+            .into_json()
+            .await
+            .expect("Valid JSON response");
+        assert_eq!(indonesian.message.as_deref(), Some("Produk dengan ID 999999 tidak ditemukan"));
+    }
+
     #[tokio::test]
     async fn test_detail_produk_zero_stock() {
         let (client, db_pool) = setup_rocket_client().await;
@@ -320,7 +511,8 @@ mod tests {
 
         let found_product = response_body.data.unwrap();
         assert_eq!(found_product.nama, "Keyboard Mechanical");
-        assert_eq!(found_product.stok, 0);
+        assert_eq!(found_product.stok, 0.0);
+        assert_eq!(found_product.stock_status, "out_of_stock");
     }
 
     #[tokio::test]
@@ -459,7 +651,7 @@ mod tests {
         let product = &products[0];
         assert_eq!(product.nama, "Server Enterprise");
         assert!((product.harga - 999999999.99).abs() < 1.0); // Floating point comparison
-        assert_eq!(product.stok, 999999);
+        assert_eq!(product.stok, 999999.0);
     }
 
     #[tokio::test]
@@ -577,4 +769,210 @@ mod tests {
         assert!(!product.kategori.is_empty());
         assert!(product.harga >= 0.0);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_export_produk_csv_includes_valuation_and_total_row() {
+        let db_pool = setup_test_db().await;
+        let client = Client::tracked(
+            rocket::build()
+                .manage(db_pool.clone())
+                .mount("/api", routes![export_produk_csv, detail_produk]),
+        ).await.expect("Valid rocket instance");
+
+        for (nama, harga, stok, presisi_stok, sku) in [
+            ("Semen, 50kg", 65000.0, 4, 0, Some("SMN-50")),
+            ("Paku 5cm", 1500.25, 10, 0, None),
+            ("Pasir Cor", 300000.0, 2500, 3, None),
+        ] {
+            sqlx::query("INSERT INTO produk (nama, kategori, harga, stok, presisi_stok, sku) VALUES ($1, $2, $3, $4, $5, $6)")
+                .bind(nama)
+                .bind("Bahan Bangunan")
+                .bind(harga)
+                .bind(stok)
+                .bind(presisi_stok)
+                .bind(sku)
+                .execute(&db_pool)
+                .await
+                .expect("Failed to insert produk");
+        }
+
+        let response = client.get("/api/produk/export.csv").dispatch().await;
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        assert_eq!(response.content_type(), Some(rocket::http::ContentType::CSV));
+        assert_eq!(
+            response.headers().get_one("Content-Disposition"),
+            Some("attachment; filename=\"produk.csv\"")
+        );
+
+        let body = response.into_string().await.unwrap();
+        let lines: Vec<&str> = body.lines().collect();
+        assert_eq!(lines, vec![
+            "id,sku,nama,kategori,harga,stok,nilai",
+            "1,SMN-50,\"Semen, 50kg\",Bahan Bangunan,65000.00,4,260000.00",
+            "2,,Paku 5cm,Bahan Bangunan,1500.25,10,15002.50",
+            "3,,Pasir Cor,Bahan Bangunan,300000.00,2.5,750000.00",
+            "TOTAL,,,,,,1025002.50",
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_scan_produk_returns_product_and_availability() {
+        let (client, db_pool) = setup_rocket_client().await;
+
+        sqlx::query("INSERT INTO produk (nama, kategori, harga, stok, stok_minimum, sku) VALUES ($1, $2, $3, $4, $5, $6)")
+            .bind("Semen Tiga Roda 50kg")
+            .bind("Bahan Bangunan")
+            .bind(65000.0)
+            .bind(4)
+            .bind(5)
+            .bind("8991234567890")
+            .execute(&db_pool)
+            .await
+            .expect("Failed to insert produk");
+
+        let response = client.get("/api/produk/scan/8991234567890").dispatch().await;
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let body: ApiResponse<ProdukScanResponse> = response.into_json().await.expect("Valid JSON response");
+        assert!(body.success);
+        let scan = body.data.unwrap();
+        assert_eq!(scan.sku, "8991234567890");
+        assert_eq!(scan.produk.nama, "Semen Tiga Roda 50kg");
+        assert_eq!(scan.produk.harga, 65000.0);
+        assert_eq!(scan.produk.stok, 4.0);
+        assert_eq!(scan.produk.stock_status, "low");
+
+        let response = client.get("/api/produk/scan/0000000000000").dispatch().await;
+        assert_eq!(response.status(), rocket::http::Status::NotFound);
+        let body: ApiResponse<ProdukScanResponse> = response.into_json().await.expect("Valid JSON response");
+        assert!(!body.success);
+        assert!(body.data.is_none());
+    }
+
+    #[test]
+    fn test_produk_response_json_keys() {
+        let response = ProdukResponse {
+            id: Some(1),
+            nama: "Laptop Gaming".to_string(),
+            kategori: "Elektronik".to_string(),
+            harga: 15_000_000.0,
+            stok: 10.0,
+            deskripsi: None,
+            stok_minimum: 0.0,
+            harga_modal: None,
+            berat_gram: None,
+            panjang_cm: None,
+            lebar_cm: None,
+            tinggi_cm: None,
+            harga_grosir: None,
+            min_qty_grosir: None,
+            satuan: DEFAULT_SATUAN.to_string(),
+            presisi_stok: 0,
+            stock_status: "in_stock".to_string(),
+            audit: Default::default(),
+        };
+
+        let value = rocket::serde::json::serde_json::to_value(&response).unwrap();
+        let mut keys: Vec<&str> = value.as_object().unwrap().keys().map(|k| k.as_str()).collect();
+        keys.sort();
+        assert_eq!(keys, vec!["deskripsi", "harga", "id", "kategori", "nama", "presisi_stok", "satuan", "stock_status", "stok", "stok_minimum"]);
+
+        let round_trip: ProdukResponse = rocket::serde::json::serde_json::from_value(value).unwrap();
+        assert_eq!(round_trip.nama, "Laptop Gaming");
+        assert_eq!(round_trip.stock_status, "in_stock");
+    }
+
+    async fn list(client: &Client, query: &str) -> ApiResponse<Vec<ProdukResponse>> {
+        client.get(format!("/api/produk?{}", query)).dispatch().await.into_json().await.expect("Valid JSON response")
+    }
+
+    fn names(response: ApiResponse<Vec<ProdukResponse>>) -> Vec<String> {
+        assert!(response.success, "unexpected failure: {:?}", response.message);
+        response.data.unwrap().into_iter().map(|produk| produk.nama).collect()
+    }
+
+    #[tokio::test]
+    async fn test_list_produk_low_stock_only_composes_with_kategori_and_keyword() {
+        let (client, db_pool) = setup_rocket_client().await;
+        insert_test_data(&db_pool).await;
+        sqlx::query("UPDATE produk SET stok_minimum = 40").execute(&db_pool).await.unwrap();
+
+        assert_eq!(names(list(&client, "low_stock_only=true&kategori=smartphone").await), vec!["iPhone 15", "Samsung Galaxy S24"]);
+        assert_eq!(names(list(&client, "low_stock_only=true&kategori=Aksesoris").await), vec!["Keyboard Mechanical"]);
+        assert_eq!(names(list(&client, "low_stock_only=true&kategori=Smartphone&keyword=samsung").await), vec!["Samsung Galaxy S24"]);
+        assert_eq!(names(list(&client, "kategori=Aksesoris").await), vec!["Mouse Wireless", "Keyboard Mechanical"]);
+        assert_eq!(names(list(&client, "out_of_stock_only=true").await), vec!["Keyboard Mechanical"]);
+        assert_eq!(names(list(&client, "low_stock_only=false").await).len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_list_produk_rejects_conflicting_stock_flags() {
+        let (client, db_pool) = setup_rocket_client().await;
+        insert_test_data(&db_pool).await;
+
+        let response = list(&client, "low_stock_only=true&out_of_stock_only=true").await;
+        assert!(!response.success);
+        assert!(response.data.is_none());
+        assert_eq!(response.message.as_deref(), Some("low_stock_only dan out_of_stock_only tidak bisa dipakai bersamaan"));
+    }
+
+    #[test]
+    fn test_produk_margin_computation() {
+        let mut produk = Produk::new("Semen".into(), "Material".into(), 60000.0, 10, None);
+        assert!(ProdukMargin::from_produk(&produk).is_none());
+
+        produk.harga_modal = Some(45000.10);
+        let margin = ProdukMargin::from_produk(&produk).unwrap();
+        assert_eq!(margin.margin, 14999.9);
+        assert_eq!(margin.margin_persen, 25.0);
+
+        produk.harga = 0.0;
+        produk.harga_modal = Some(0.0);
+        assert_eq!(ProdukMargin::from_produk(&produk).unwrap().margin_persen, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_laporan_margin_filters_by_kategori_and_skips_missing_cost() {
+        let (client, db_pool) = setup_rocket_client().await;
+        insert_test_data(&db_pool).await;
+        sqlx::query("UPDATE produk SET harga_modal = 100000 WHERE nama = 'Mouse Wireless'").execute(&db_pool).await.unwrap();
+        sqlx::query("UPDATE produk SET harga_modal = 10000000 WHERE nama = 'iPhone 15'").execute(&db_pool).await.unwrap();
+
+        let all: ApiResponse<Vec<ProdukMargin>> = client.get("/api/reports/margins").dispatch().await.into_json().await.unwrap();
+        assert!(all.success);
+        let all = all.data.unwrap();
+        assert_eq!(all.iter().map(|m| m.nama.as_str()).collect::<Vec<_>>(), vec!["Mouse Wireless", "iPhone 15"]);
+        assert_eq!(all[0].margin, 50000.0);
+        assert!((all[0].margin_persen - 33.33).abs() < 1e-9);
+
+        let filtered: ApiResponse<Vec<ProdukMargin>> = client.get("/api/reports/margins?kategori=Smartphone").dispatch().await.into_json().await.unwrap();
+        let filtered = filtered.data.unwrap();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].margin, 5000000.0);
+    }
+
+    #[tokio::test]
+    async fn test_valuasi_produk_totals_and_groups_by_kategori() {
+        use crate::manajemen_produk::controller::dto::{KategoriValuation, ProdukValuation};
+
+        let (client, db_pool) = setup_rocket_client().await;
+        insert_test_data(&db_pool).await;
+        // Kabel timbangan: 2,5 m disimpan sebagai 2500 unit
+        sqlx::query("INSERT INTO produk (nama, kategori, harga, stok, satuan, presisi_stok) VALUES ('Kabel NYM', 'Aksesoris', 10000, 2500, 'm', 3)")
+            .execute(&db_pool)
+            .await
+            .unwrap();
+
+        let response: ApiResponse<ProdukValuation> = client.get("/api/produk/valuation").dispatch().await.into_json().await.unwrap();
+        assert!(response.success);
+        let valuation = response.data.unwrap();
+
+        // 15.000.000,50 x 10 + 150.000 x 50 + 10.000 x 2,5 + 15.000.000 x 25 + 12.000.000 x 30 (keyboard stok 0)
+        assert_eq!(valuation.total_nilai, 892_525_005.0);
+        assert_eq!(valuation.total_stok, 117.5);
+        assert_eq!(valuation.per_kategori, vec![
+            KategoriValuation { kategori: "Aksesoris".to_string(), jumlah_produk: 3, total_stok: 52.5, nilai: 7_525_000.0 },
+            KategoriValuation { kategori: "Elektronik".to_string(), jumlah_produk: 1, total_stok: 10.0, nilai: 150_000_005.0 },
+            KategoriValuation { kategori: "Smartphone".to_string(), jumlah_produk: 2, total_stok: 55.0, nilai: 735_000_000.0 },
+        ]);
+    }
+}
@@ -0,0 +1,248 @@
+use rocket::serde::json::Json;
+use rocket::{delete, get, post, routes, Route, State};
+use chrono::{Duration, Utc};
+use crate::manajemen_produk::model::reservation::reservation_ttl_secs;
+use crate::manajemen_produk::repository;
+use crate::manajemen_produk::repository::dto::RepositoryError;
+use super::dto::{ApiResponse, ReservasiRequest, ReservasiResponse};
+use autometrics::autometrics;
+use sqlx::AnyPool;
+
+#[autometrics]
+#[post("/produk/<id>/reserve", format = "json", data = "<request>")]
+pub async fn reserve_produk(
+    db: &State<AnyPool>,
+    id: i64,
+    request: Json<ReservasiRequest>
+) -> Json<ApiResponse<ReservasiResponse>> {
+    let ttl_seconds = request.ttl_seconds.unwrap_or_else(reservation_ttl_secs);
+    if ttl_seconds <= 0 {
+        return Json(ApiResponse {
+            success: false,
+            message: Some("Validasi gagal: ttl_seconds harus lebih dari 0".to_string()),
+            data: None,
+        });
+    }
+
+    let now = Utc::now();
+    match repository::reservation::buat_reservasi(db.inner(), id, request.jumlah, Duration::seconds(ttl_seconds), now).await {
+        Ok(reservation) => {
+            let available_stock = repository::reservation::available_stock(db.inner(), id, now)
+                .await
+                .unwrap_or(0.0);
+            Json(ApiResponse {
+                success: true,
+                message: Some("Berhasil mereservasi produk".to_string()),
+                data: Some(ReservasiResponse {
+                    id: reservation.id,
+                    produk_id: reservation.produk_id,
+                    jumlah: reservation.jumlah,
+                    expires_at: reservation.expires_at,
+                    available_stock,
+                }),
+            })
+        },
+        Err(RepositoryError::NotFound) => Json(ApiResponse {
+            success: false,
+            message: Some(format!("Produk dengan ID {} tidak ditemukan", id)),
+            data: None,
+        }),
+        Err(e) => Json(ApiResponse {
+            success: false,
+            message: Some(format!("Gagal mereservasi produk: {}", e)),
+            data: None,
+        }),
+    }
+}
+
+#[autometrics]
+#[get("/produk/<id>/available-stock")]
+pub async fn available_stock_produk(db: &State<AnyPool>, id: i64) -> Json<ApiResponse<f64>> {
+    match repository::reservation::available_stock(db.inner(), id, Utc::now()).await {
+        Ok(available) => Json(ApiResponse {
+            success: true,
+            message: Some("Berhasil mengambil stok tersedia".to_string()),
+            data: Some(available),
+        }),
+        Err(RepositoryError::NotFound) => Json(ApiResponse {
+            success: false,
+            message: Some(format!("Produk dengan ID {} tidak ditemukan", id)),
+            data: None,
+        }),
+        Err(e) => Json(ApiResponse {
+            success: false,
+            message: Some(format!("Gagal mengambil stok tersedia: {}", e)),
+            data: None,
+        }),
+    }
+}
+
+#[autometrics]
+#[delete("/produk/reservations/<reservation_id>")]
+pub async fn release_reservasi(
+    db: &State<AnyPool>,
+    reservation_id: String
+) -> Json<ApiResponse<()>> {
+    match repository::reservation::hapus_reservasi(db.inner(), &reservation_id).await {
+        Ok(_) => Json(ApiResponse {
+            success: true,
+            message: Some(format!("Reservasi {} berhasil dilepas", reservation_id)),
+            data: None,
+        }),
+        Err(RepositoryError::NotFound) => Json(ApiResponse {
+            success: false,
+            message: Some(format!("Reservasi {} tidak ditemukan", reservation_id)),
+            data: None,
+        }),
+        Err(e) => Json(ApiResponse {
+            success: false,
+            message: Some(format!("Gagal melepas reservasi: {}", e)),
+            data: None,
+        }),
+    }
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![reserve_produk, available_stock_produk, release_reservasi]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::local::asynchronous::Client;
+    use sqlx::any::{AnyPoolOptions, install_default_drivers};
+
+    async fn setup_rocket_client() -> (Client, AnyPool) {
+        install_default_drivers();
+        let db_pool = AnyPoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to connect to test DB");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS produk (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                nama TEXT NOT NULL,
+                kategori TEXT NOT NULL,
+                harga REAL NOT NULL,
+                stok INTEGER NOT NULL,
+                stok_minimum INTEGER NOT NULL DEFAULT 0,
+                harga_modal REAL,
+                berat_gram INTEGER,
+                panjang_cm INTEGER,
+                lebar_cm INTEGER,
+                tinggi_cm INTEGER,
+                harga_grosir REAL,
+                min_qty_grosir INTEGER,
+                satuan VARCHAR(20) NOT NULL DEFAULT 'pcs',
+                presisi_stok INTEGER NOT NULL DEFAULT 0,
+                created_by TEXT,
+                updated_by TEXT,
+                deskripsi TEXT
+            )
+            "#
+        )
+        .execute(&db_pool)
+        .await
+        .expect("Failed to create produk table");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS produk_reservations (
+                id TEXT PRIMARY KEY NOT NULL,
+                produk_id INTEGER NOT NULL,
+                jumlah INTEGER NOT NULL,
+                expires_at TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )
+            "#
+        )
+        .execute(&db_pool)
+        .await
+        .expect("Failed to create produk_reservations table");
+
+        sqlx::query("INSERT INTO produk (nama, kategori, harga, stok, deskripsi) VALUES ($1, $2, $3, $4, $5)")
+            .bind("Cat Tembok")
+            .bind("Bahan Bangunan")
+            .bind(120000.0)
+            .bind(2)
+            .bind(None::<String>)
+            .execute(&db_pool)
+            .await
+            .expect("Failed to insert produk");
+
+        let rocket = rocket::build()
+            .manage(db_pool.clone())
+            .mount("/api", routes());
+
+        let client = Client::tracked(rocket)
+            .await
+            .expect("Valid rocket instance");
+
+        (client, db_pool)
+    }
+
+    async fn get_available(client: &Client) -> f64 {
+        let response: ApiResponse<f64> = client
+            .get("/api/produk/1/available-stock")
+            .dispatch()
+            .await
+            .into_json()
+            .await
+            .expect("Valid JSON response");
+        response.data.expect("Available stock should be present")
+    }
+
+    #[tokio::test]
+    async fn test_reserve_then_release_via_api() {
+        let (client, _db_pool) = setup_rocket_client().await;
+        assert_eq!(get_available(&client).await, 2.0);
+
+        let response: ApiResponse<ReservasiResponse> = client
+            .post("/api/produk/1/reserve")
+            .header(rocket::http::ContentType::JSON)
+            .body(r#"{"jumlah": 2}"#)
+            .dispatch()
+            .await
+            .into_json()
+            .await
+            .expect("Valid JSON response");
+        assert!(response.success);
+        let reservation = response.data.unwrap();
+        assert_eq!(reservation.available_stock, 0.0);
+        assert_eq!(get_available(&client).await, 0.0);
+
+        // Unit terakhir sudah di-hold, keranjang lain ditolak
+        let rejected: ApiResponse<ReservasiResponse> = client
+            .post("/api/produk/1/reserve")
+            .header(rocket::http::ContentType::JSON)
+            .body(r#"{"jumlah": 1}"#)
+            .dispatch()
+            .await
+            .into_json()
+            .await
+            .expect("Valid JSON response");
+        assert!(!rejected.success);
+
+        let released: ApiResponse<()> = client
+            .delete(format!("/api/produk/reservations/{}", reservation.id))
+            .dispatch()
+            .await
+            .into_json()
+            .await
+            .expect("Valid JSON response");
+        assert!(released.success);
+        assert_eq!(get_available(&client).await, 2.0);
+
+        let released_again: ApiResponse<()> = client
+            .delete(format!("/api/produk/reservations/{}", reservation.id))
+            .dispatch()
+            .await
+            .into_json()
+            .await
+            .expect("Valid JSON response");
+        assert!(!released_again.success);
+    }
+}
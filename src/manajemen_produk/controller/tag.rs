@@ -0,0 +1,199 @@
+use rocket::serde::json::Json;
+use rocket::http::Status;
+use rocket::{delete, get, post, routes, Route, State};
+use crate::auth::guards::auth::AuthenticatedUser;
+use crate::manajemen_produk::repository;
+use crate::manajemen_produk::repository::dto::RepositoryError;
+use super::dto::{ApiResponse, ProdukResponse, TagRequest};
+use autometrics::autometrics;
+use sqlx::AnyPool;
+
+fn tag_error<T>(error: RepositoryError, not_found: String, action: &str) -> (Status, Json<ApiResponse<T>>) {
+    let (status, message) = match error {
+        RepositoryError::NotFound => (Status::NotFound, not_found),
+        RepositoryError::ValidationError(msg) => (Status::BadRequest, format!("Validasi gagal: {}", msg)),
+        e => (Status::InternalServerError, format!("Gagal {}: {}", action, e)),
+    };
+    (status, Json(ApiResponse { success: false, message: Some(message), data: None }))
+}
+
+#[autometrics]
+#[post("/produk/<id>/tags", format = "json", data = "<request>")]
+pub async fn tambah_tag_produk(
+    db: &State<AnyPool>,
+    id: i64,
+    request: Json<TagRequest>
+) -> (Status, Json<ApiResponse<Vec<String>>>) {
+    match repository::tag::tambah_tag(db.inner(), id, &request.tag).await {
+        Ok(tags) => (Status::Ok, Json(ApiResponse {
+            success: true,
+            message: Some("Berhasil menambahkan tag produk".to_string()),
+            data: Some(tags),
+        })),
+        Err(e) => tag_error(e, format!("Produk dengan ID {} tidak ditemukan", id), "menambahkan tag produk"),
+    }
+}
+
+#[autometrics]
+#[delete("/produk/<id>/tags/<tag>")]
+pub async fn hapus_tag_produk(
+    db: &State<AnyPool>,
+    id: i64,
+    tag: &str
+) -> (Status, Json<ApiResponse<Vec<String>>>) {
+    match repository::tag::hapus_tag(db.inner(), id, tag).await {
+        Ok(tags) => (Status::Ok, Json(ApiResponse {
+            success: true,
+            message: Some("Berhasil menghapus tag produk".to_string()),
+            data: Some(tags),
+        })),
+        Err(e) => tag_error(e, format!("Tag '{}' tidak terpasang pada produk dengan ID {}", tag.trim(), id), "menghapus tag produk"),
+    }
+}
+
+// rank 2: `/produk/<id>/available-stock` juga cocok dengan path ini
+#[autometrics]
+#[get("/produk/tag/<tag>", rank = 2)]
+pub async fn list_produk_by_tag(
+    db: &State<AnyPool>,
+    tag: &str,
+    user: Option<AuthenticatedUser>
+) -> (Status, Json<ApiResponse<Vec<ProdukResponse>>>) {
+    match repository::tag::ambil_produk_by_tag(db.inner(), tag).await {
+        Ok(produk_list) => (Status::Ok, Json(ApiResponse {
+            success: true,
+            message: Some("Berhasil mengambil daftar produk".to_string()),
+            data: Some(produk_list.into_iter().map(|produk| ProdukResponse::for_viewer(produk, user.as_ref())).collect()),
+        })),
+        Err(e) => tag_error(e, String::new(), "mengambil daftar produk"),
+    }
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![tambah_tag_produk, hapus_tag_produk, list_produk_by_tag]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::http::ContentType;
+    use rocket::local::asynchronous::Client;
+    use sqlx::any::{AnyPoolOptions, install_default_drivers};
+
+    async fn setup_rocket_client() -> Client {
+        install_default_drivers();
+        let db_pool = AnyPoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to connect to test DB");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS produk (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                nama TEXT NOT NULL,
+                kategori TEXT NOT NULL,
+                harga REAL NOT NULL,
+                stok INTEGER NOT NULL,
+                stok_minimum INTEGER NOT NULL DEFAULT 0,
+                harga_modal REAL,
+                berat_gram INTEGER,
+                panjang_cm INTEGER,
+                lebar_cm INTEGER,
+                tinggi_cm INTEGER,
+                harga_grosir REAL,
+                min_qty_grosir INTEGER,
+                satuan VARCHAR(20) NOT NULL DEFAULT 'pcs',
+                presisi_stok INTEGER NOT NULL DEFAULT 0,
+                created_by TEXT,
+                updated_by TEXT,
+                deskripsi TEXT
+            )
+            "#
+        )
+        .execute(&db_pool)
+        .await
+        .expect("Failed to create produk table");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS produk_tags (
+                produk_id INTEGER NOT NULL,
+                tag TEXT NOT NULL,
+                PRIMARY KEY (produk_id, tag)
+            )
+            "#
+        )
+        .execute(&db_pool)
+        .await
+        .expect("Failed to create produk_tags table");
+
+        for (nama, kategori) in [("Semen Gresik", "Bahan Bangunan"), ("Cat Tembok", "Cat"), ("Paku Beton", "Perkakas")] {
+            sqlx::query("INSERT INTO produk (nama, kategori, harga, stok, deskripsi) VALUES ($1, $2, $3, $4, $5)")
+                .bind(nama)
+                .bind(kategori)
+                .bind(50000.0)
+                .bind(10)
+                .bind(None::<String>)
+                .execute(&db_pool)
+                .await
+                .expect("Failed to insert produk");
+        }
+
+        let rocket = rocket::build()
+            .manage(db_pool)
+            .mount("/api", routes());
+        Client::tracked(rocket).await.expect("Valid rocket instance")
+    }
+
+    async fn tag(client: &Client, id: i64, tag: &str) -> (Status, ApiResponse<Vec<String>>) {
+        let response = client
+            .post(format!("/api/produk/{}/tags", id))
+            .header(ContentType::JSON)
+            .body(format!(r#"{{"tag": "{}"}}"#, tag))
+            .dispatch()
+            .await;
+        (response.status(), response.into_json().await.expect("Valid JSON response"))
+    }
+
+    async fn names_by_tag(client: &Client, tag: &str) -> Vec<String> {
+        let response: ApiResponse<Vec<ProdukResponse>> = client
+            .get(format!("/api/produk/tag/{}", tag))
+            .dispatch()
+            .await
+            .into_json()
+            .await
+            .expect("Valid JSON response");
+        response.data.unwrap().into_iter().map(|produk| produk.nama).collect()
+    }
+
+    #[tokio::test]
+    async fn test_tag_produk_then_filter_by_tag() {
+        let client = setup_rocket_client().await;
+
+        let (status, body) = tag(&client, 1, "Promo").await;
+        assert_eq!(status, Status::Ok);
+        assert_eq!(body.data.unwrap(), vec!["promo"]);
+        tag(&client, 1, "new-arrival").await;
+        tag(&client, 2, "promo").await;
+
+        assert_eq!(names_by_tag(&client, "promo").await, vec!["Semen Gresik", "Cat Tembok"]);
+        assert_eq!(names_by_tag(&client, "new-arrival").await, vec!["Semen Gresik"]);
+        assert!(names_by_tag(&client, "clearance").await.is_empty());
+
+        let (status, _) = tag(&client, 99, "promo").await;
+        assert_eq!(status, Status::NotFound);
+        let (status, _) = tag(&client, 3, "diskon besar").await;
+        assert_eq!(status, Status::BadRequest);
+
+        let response = client.delete("/api/produk/1/tags/promo").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let body: ApiResponse<Vec<String>> = response.into_json().await.unwrap();
+        assert_eq!(body.data.unwrap(), vec!["new-arrival"]);
+        assert_eq!(names_by_tag(&client, "promo").await, vec!["Cat Tembok"]);
+
+        let response = client.delete("/api/produk/1/tags/promo").dispatch().await;
+        assert_eq!(response.status(), Status::NotFound);
+    }
+}
@@ -1,37 +1,65 @@
 use rocket::serde::json::Json;
-use rocket::{put, routes, Route, State};
+use rocket::http::Status;
+use rocket::{post, put, routes, Route, State};
+use crate::auth::guards::auth::AuthenticatedUser;
+use crate::common::audit::actor;
 use crate::manajemen_produk::model::{ProdukBuilder};
+use crate::manajemen_produk::model::builder::normalize_kategori;
 use crate::manajemen_produk::repository;
-use super::dto::{ProdukRequest, ProdukResponse, ApiResponse};
+use crate::manajemen_produk::repository::dto::RepositoryError;
+use crate::manajemen_produk::service::produk_notifier::{notify_stok_change, ProdukNotifier};
+use super::dto::{AdjustStokRequest, ProdukRequest, ProdukResponse, ApiResponse, RenameKategoriRequest, RenameKategoriResponse};
 use autometrics::autometrics;
 use sqlx::AnyPool;
+use std::sync::Arc;
 
 #[autometrics]
 #[put("/produk/<id>", format = "json", data = "<request>")]
 pub async fn update_produk(
     db: &State<AnyPool>,
     id: i64,
+    user: Option<AuthenticatedUser>,
     request: Json<ProdukRequest>
 ) -> Json<ApiResponse<ProdukResponse>> {
     // Check if product exists
     match repository::read::ambil_produk_by_id(db.inner(), id).await {
-        Ok(Some(_)) => {
+        Ok(Some(existing)) => {
+            let (stok, stok_minimum) = match request.stok_dalam_unit() {
+                Ok(stok) => stok,
+                Err(error) => {
+                    return Json(ApiResponse {
+                        success: false,
+                        message: Some(format!("Validasi gagal: {}", error)),
+                        data: None,
+                    });
+                }
+            };
             // Using builder to create updated product
-            let updated_produk = ProdukBuilder::new(request.nama.clone(), request.kategori.clone())
+            let mut builder = ProdukBuilder::new(request.nama.clone(), request.kategori.clone())
                 .id(id)
                 .harga(request.harga)
-                .stok(request.stok.try_into().unwrap_or(0))
-                .deskripsi(request.deskripsi.clone().unwrap_or_default())
-                .build();
+                .stok(stok)
+                .stok_minimum(stok_minimum)
+                .berat_gram(request.berat_gram)
+                .dimensi(request.panjang_cm, request.lebar_cm, request.tinggi_cm)
+                .harga_grosir(request.harga_grosir, request.min_qty_grosir)
+                .satuan(request.satuan.clone(), request.presisi_stok)
+                .deskripsi(request.deskripsi.clone().unwrap_or_default());
+            if let Some(harga_modal) = request.harga_modal {
+                builder = builder.harga_modal(harga_modal);
+            }
+            let updated_produk = builder.build();
                 
             match updated_produk {
-                Ok(updated_produk) => {
+                Ok(mut updated_produk) => {
+                    updated_produk.audit = existing.audit;
+                    updated_produk.audit.touch(actor(user.as_ref()));
                     match repository::update::update_produk(db.inner(), id, &updated_produk).await {
                         Ok(true) => {
                             Json(ApiResponse {
                                 success: true,
                                 message: Some("Berhasil memperbarui produk".to_string()),
-                                data: Some(ProdukResponse::from(updated_produk)),
+                                data: Some(ProdukResponse::for_viewer(updated_produk, user.as_ref())),
                             })
                         },
                         Ok(false) => {
@@ -80,11 +108,13 @@ pub async fn update_produk(
 #[put("/produk/<id>/stok", format = "json", data = "<stok_baru>")]
 pub async fn update_stok_produk(
     db: &State<AnyPool>,
+    notifier: &State<Arc<dyn ProdukNotifier>>,
     id: i64,
-    stok_baru: Json<u32>
+    stok_baru: Json<f64>
 ) -> Json<ApiResponse<ProdukResponse>> {
     match repository::update::update_stok(db.inner(), id, *stok_baru).await {
-        Ok(true) => {
+        Ok(change) => {
+            notify_stok_change(notifier.inner().as_ref(), &change).await;
             // Get updated product to return in response
             match repository::read::ambil_produk_by_id(db.inner(), id).await {
                 Ok(Some(updated_produk)) => {
@@ -103,7 +133,7 @@ pub async fn update_stok_produk(
                 }
             }
         },
-        Ok(false) => {
+        Err(RepositoryError::NotFound) => {
             Json(ApiResponse {
                 success: false,
                 message: Some(format!("Produk dengan ID {} tidak ditemukan", id)),
@@ -120,8 +150,76 @@ pub async fn update_stok_produk(
     }
 }
 
+#[autometrics]
+#[post("/produk/<id>/stok/adjust", format = "json", data = "<request>")]
+pub async fn adjust_stok_produk(
+    db: &State<AnyPool>,
+    notifier: &State<Arc<dyn ProdukNotifier>>,
+    id: i64,
+    user: Option<AuthenticatedUser>,
+    request: Json<AdjustStokRequest>
+) -> Json<ApiResponse<ProdukResponse>> {
+    match repository::update::adjust_stok(db.inner(), id, request.delta, actor(user.as_ref())).await {
+        Ok(change) => {
+            notify_stok_change(notifier.inner().as_ref(), &change).await;
+            match repository::read::ambil_produk_by_id(db.inner(), id).await {
+                Ok(Some(updated_produk)) => Json(ApiResponse {
+                    success: true,
+                    message: Some("Berhasil menyesuaikan stok produk".to_string()),
+                    data: Some(ProdukResponse::for_viewer(updated_produk, user.as_ref())),
+                }),
+                _ => Json(ApiResponse {
+                    success: true,
+                    message: Some("Stok berhasil disesuaikan tetapi gagal mengambil data".to_string()),
+                    data: None,
+                }),
+            }
+        },
+        Err(RepositoryError::NotFound) => Json(ApiResponse {
+            success: false,
+            message: Some(format!("Produk dengan ID {} tidak ditemukan", id)),
+            data: None,
+        }),
+        Err(e) => Json(ApiResponse {
+            success: false,
+            message: Some(format!("Gagal menyesuaikan stok: {}", e)),
+            data: None,
+        }),
+    }
+}
+
+#[autometrics]
+#[post("/produk/kategori/rename", format = "json", data = "<request>")]
+pub async fn rename_kategori_produk(
+    db: &State<AnyPool>,
+    user: Option<AuthenticatedUser>,
+    request: Json<RenameKategoriRequest>
+) -> (Status, Json<ApiResponse<RenameKategoriResponse>>) {
+    match repository::update::rename_kategori(db.inner(), &request.from, &request.to, actor(user.as_ref())).await {
+        Ok(affected) => (Status::Ok, Json(ApiResponse {
+            success: true,
+            message: Some(format!("Berhasil memindahkan {} produk", affected)),
+            data: Some(RenameKategoriResponse {
+                from: normalize_kategori(&request.from),
+                to: normalize_kategori(&request.to),
+                affected,
+            }),
+        })),
+        Err(RepositoryError::ValidationError(msg)) => (Status::BadRequest, Json(ApiResponse {
+            success: false,
+            message: Some(format!("Validasi gagal: {}", msg)),
+            data: None,
+        })),
+        Err(e) => (Status::InternalServerError, Json(ApiResponse {
+            success: false,
+            message: Some(format!("Gagal mengganti nama kategori: {}", e)),
+            data: None,
+        })),
+    }
+}
+
 pub fn routes() -> Vec<Route> {
-    routes![update_produk, update_stok_produk]
+    routes![update_produk, update_stok_produk, adjust_stok_produk, rename_kategori_produk]
 }
 
 #[cfg(test)]
@@ -132,6 +230,10 @@ mod tests {
     use sqlx::{any::{AnyPoolOptions, install_default_drivers}, AnyPool, Row};
     use serde_json::json;
     use crate::manajemen_produk::controller::dto::{ProdukRequest, ApiResponse, ProdukResponse};
+    use crate::manajemen_produk::model::{StokEvent, StokEventKind};
+    use crate::manajemen_produk::service::produk_dispatcher::ProdukDispatcher;
+    use crate::manajemen_produk::service::produk_observer::ProdukObserver;
+    use std::sync::Mutex;
 
     async fn setup_test_db() -> AnyPool {
         install_default_drivers();
@@ -150,6 +252,18 @@ mod tests {
                 kategori TEXT NOT NULL,
                 harga REAL NOT NULL,
                 stok INTEGER NOT NULL,
+                stok_minimum INTEGER NOT NULL DEFAULT 0,
+                harga_modal REAL,
+                berat_gram INTEGER,
+                panjang_cm INTEGER,
+                lebar_cm INTEGER,
+                tinggi_cm INTEGER,
+                harga_grosir REAL,
+                min_qty_grosir INTEGER,
+                satuan VARCHAR(20) NOT NULL DEFAULT 'pcs',
+                presisi_stok INTEGER NOT NULL DEFAULT 0,
+                created_by TEXT,
+                updated_by TEXT,
                 deskripsi TEXT
             )
             "#
@@ -186,7 +300,8 @@ mod tests {
         
         let rocket = rocket::build()
             .manage(db_pool.clone())
-            .mount("/api", routes![update_produk, update_stok_produk]);
+            .manage(Arc::new(ProdukDispatcher::new()) as Arc<dyn ProdukNotifier>)
+            .mount("/api", routes![update_produk, update_stok_produk, adjust_stok_produk, rename_kategori_produk]);
             
         let client = Client::tracked(rocket)
             .await
@@ -231,7 +346,7 @@ mod tests {
         assert_eq!(produk_data.nama, "Updated Laptop Gaming");
         assert_eq!(produk_data.kategori, "Elektronik");
         assert_eq!(produk_data.harga, 15000000.50);
-        assert_eq!(produk_data.stok, 25);
+        assert_eq!(produk_data.stok, 25.0);
         assert_eq!(produk_data.deskripsi, Some("Updated laptop gaming with RTX 4080".to_string()));
 
         // Verify in database
@@ -316,7 +431,7 @@ mod tests {
         assert!(response_body.data.is_some());
         
         let produk_data = response_body.data.unwrap();
-        assert_eq!(produk_data.stok, 0); // Should be converted to 0
+        assert_eq!(produk_data.stok, 0.0); // Should be converted to 0
     }
 
     #[tokio::test]
@@ -353,6 +468,35 @@ mod tests {
         assert!(response_body.message.unwrap().contains("Validasi gagal"));
     }
 
+    #[tokio::test]
+    async fn test_update_produk_whitespace_only_name_should_fail() {
+        let (client, db_pool) = setup_rocket_client().await;
+        let product_id = insert_test_produk(&db_pool).await;
+
+        let request_body = json!({
+            "nama": "   ",
+            "kategori": "Test",
+            "harga": 1000.0,
+            "stok": 1,
+            "deskripsi": "Test product"
+        });
+
+        let response = client
+            .put(format!("/api/produk/{}", product_id))
+            .header(rocket::http::ContentType::JSON)
+            .body(request_body.to_string())
+            .dispatch()
+            .await;
+
+        let response_body: ApiResponse<ProdukResponse> = response
+            .into_json()
+            .await
+            .expect("Valid JSON response");
+
+        assert!(!response_body.success);
+        assert!(response_body.message.unwrap().contains("Validasi gagal"));
+    }
+
     #[tokio::test]
     async fn test_update_produk_negative_price_should_fail() {
         let (client, db_pool) = setup_rocket_client().await;
@@ -455,7 +599,7 @@ mod tests {
         assert!(response_body.data.is_some());
         
         let produk_data = response_body.data.unwrap();
-        assert_eq!(produk_data.stok, 999999);
+        assert_eq!(produk_data.stok, 999999.0);
         assert!((produk_data.harga - 1999999999.99).abs() < 1.0);
     }
 
@@ -507,7 +651,7 @@ mod tests {
         assert!(response_body.data.is_some());
         
         let produk_data = response_body.data.unwrap();
-        assert_eq!(produk_data.stok, 150);
+        assert_eq!(produk_data.stok, 150.0);
 
         // Verify in database
         let row = sqlx::query("SELECT stok FROM produk WHERE id = $1")
@@ -546,7 +690,7 @@ mod tests {
         assert!(response_body.data.is_some());
         
         let produk_data = response_body.data.unwrap();
-        assert_eq!(produk_data.stok, 0);
+        assert_eq!(produk_data.stok, 0.0);
     }
 
     #[tokio::test]
@@ -575,7 +719,7 @@ mod tests {
         assert!(response_body.data.is_some());
         
         let produk_data = response_body.data.unwrap();
-        assert_eq!(produk_data.stok, 999999);
+        assert_eq!(produk_data.stok, 999999.0);
     }
 
     #[tokio::test]
@@ -637,7 +781,7 @@ mod tests {
         assert_eq!(response2.status(), rocket::http::Status::Ok);
         let response_body2: ApiResponse<ProdukResponse> = response2.into_json().await.unwrap();
         assert!(response_body2.success);
-        assert_eq!(response_body2.data.unwrap().stok, 25);
+        assert_eq!(response_body2.data.unwrap().stok, 25.0);
 
         // Third update - full product update again
         let request_body3 = json!({
@@ -664,7 +808,7 @@ mod tests {
         assert_eq!(final_data.nama, "Final Update");
         assert_eq!(final_data.kategori, "Final Category");
         assert_eq!(final_data.harga, 200000.0);
-        assert_eq!(final_data.stok, 30);
+        assert_eq!(final_data.stok, 30.0);
 
         // Verify final state in database
         let row = sqlx::query("SELECT * FROM produk WHERE id = $1")
@@ -683,4 +827,171 @@ mod tests {
         assert_eq!(harga, 200000.0);
         assert_eq!(stok, 30);
     }
+
+    async fn adjust_stok(client: &Client, product_id: i64, delta: i32) -> ApiResponse<ProdukResponse> {
+        client
+            .post(format!("/api/produk/{}/stok/adjust", product_id))
+            .header(rocket::http::ContentType::JSON)
+            .body(json!({ "delta": delta }).to_string())
+            .dispatch()
+            .await
+            .into_json()
+            .await
+            .expect("Valid JSON response")
+    }
+
+    async fn stok_in_db(pool: &AnyPool, product_id: i64) -> i32 {
+        sqlx::query("SELECT stok FROM produk WHERE id = $1")
+            .bind(product_id)
+            .fetch_one(pool)
+            .await
+            .expect("Failed to fetch product")
+            .get("stok")
+    }
+
+    #[tokio::test]
+    async fn test_adjust_stok_positive_delta() {
+        let (client, db_pool) = setup_rocket_client().await;
+        let product_id = insert_test_produk(&db_pool).await;
+
+        let response = adjust_stok(&client, product_id, 15).await;
+        assert!(response.success);
+        assert_eq!(response.data.unwrap().stok, 65.0);
+        assert_eq!(stok_in_db(&db_pool, product_id).await, 65);
+    }
+
+    #[tokio::test]
+    async fn test_adjust_stok_negative_delta_within_range() {
+        let (client, db_pool) = setup_rocket_client().await;
+        let product_id = insert_test_produk(&db_pool).await;
+
+        let response = adjust_stok(&client, product_id, -50).await;
+        assert!(response.success);
+        assert_eq!(response.data.unwrap().stok, 0.0);
+        assert_eq!(stok_in_db(&db_pool, product_id).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_adjust_stok_negative_delta_that_would_underflow() {
+        let (client, db_pool) = setup_rocket_client().await;
+        let product_id = insert_test_produk(&db_pool).await;
+
+        let response = adjust_stok(&client, product_id, -51).await;
+        assert!(!response.success);
+        assert!(response.data.is_none());
+        assert!(response.message.unwrap().contains("tidak boleh menjadi negatif"));
+        assert_eq!(stok_in_db(&db_pool, product_id).await, 50);
+
+        let missing = adjust_stok(&client, 9999, 1).await;
+        assert!(!missing.success);
+        assert_eq!(missing.message.unwrap(), "Produk dengan ID 9999 tidak ditemukan");
+    }
+
+    struct RecordingObserver {
+        events: Arc<Mutex<Vec<StokEvent>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl ProdukObserver for RecordingObserver {
+        async fn on_stok_event(&self, event: &StokEvent) {
+            self.events.lock().unwrap().push(*event);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_out_of_stock_event_fires_exactly_when_stok_hits_zero() {
+        let db_pool = setup_test_db().await;
+        let product_id = insert_test_produk(&db_pool).await;
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let dispatcher = ProdukDispatcher::new();
+        dispatcher.register(Arc::new(RecordingObserver { events: events.clone() }));
+        let rocket = rocket::build()
+            .manage(db_pool.clone())
+            .manage(Arc::new(dispatcher) as Arc<dyn ProdukNotifier>)
+            .mount("/api", routes![update_stok_produk, adjust_stok_produk]);
+        let client = Client::tracked(rocket).await.expect("Valid rocket instance");
+
+        assert!(adjust_stok(&client, product_id, -30).await.success);
+        assert!(events.lock().unwrap().is_empty());
+
+        assert!(adjust_stok(&client, product_id, -20).await.success);
+        assert!(!adjust_stok(&client, product_id, -1).await.success);
+        assert_eq!(events.lock().unwrap().as_slice(), &[StokEvent {
+            produk_id: product_id,
+            kind: StokEventKind::OutOfStock,
+            stok_sebelum: 20,
+            stok_sesudah: 0,
+        }]);
+
+        let response = client
+            .put(format!("/api/produk/{}/stok", product_id))
+            .header(rocket::http::ContentType::JSON)
+            .body("12")
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let kinds: Vec<StokEventKind> = events.lock().unwrap().iter().map(|event| event.kind).collect();
+        assert_eq!(kinds, vec![StokEventKind::OutOfStock, StokEventKind::Restocked]);
+    }
+
+    async fn insert_produk_kategori(pool: &AnyPool, nama: &str, kategori: &str) -> i64 {
+        sqlx::query("INSERT INTO produk (nama, kategori, harga, stok) VALUES ($1, $2, 10000.0, 5) RETURNING id")
+            .bind(nama)
+            .bind(kategori)
+            .fetch_one(pool)
+            .await
+            .expect("Failed to insert product")
+            .get("id")
+    }
+
+    async fn kategori_in_db(pool: &AnyPool, product_id: i64) -> String {
+        sqlx::query("SELECT kategori FROM produk WHERE id = $1")
+            .bind(product_id)
+            .fetch_one(pool)
+            .await
+            .expect("Failed to fetch product")
+            .get("kategori")
+    }
+
+    #[tokio::test]
+    async fn test_rename_kategori_moves_all_products() {
+        let (client, db_pool) = setup_rocket_client().await;
+        let semen = insert_produk_kategori(&db_pool, "Semen Gresik", "Bahan Bangunan").await;
+        let pasir = insert_produk_kategori(&db_pool, "Pasir Cor", "bahan bangunan").await;
+        let cat = insert_produk_kategori(&db_pool, "Cat Tembok", "Cat").await;
+
+        let response = client
+            .post("/api/produk/kategori/rename")
+            .header(rocket::http::ContentType::JSON)
+            .body(json!({ "from": "  BAHAN   bangunan ", "to": "material  bangunan" }).to_string())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let body: ApiResponse<RenameKategoriResponse> = response.into_json().await.expect("Valid JSON response");
+        let data = body.data.unwrap();
+        assert_eq!((data.from.as_str(), data.to.as_str(), data.affected), ("Bahan Bangunan", "Material Bangunan", 2));
+
+        assert_eq!(kategori_in_db(&db_pool, semen).await, "Material Bangunan");
+        assert_eq!(kategori_in_db(&db_pool, pasir).await, "Material Bangunan");
+        assert_eq!(kategori_in_db(&db_pool, cat).await, "Cat");
+
+        // Menggabungkan ke kategori yang sudah punya produk bernama sama ditolak
+        insert_produk_kategori(&db_pool, "Cat Tembok", "Material Bangunan").await;
+        let response = client
+            .post("/api/produk/kategori/rename")
+            .header(rocket::http::ContentType::JSON)
+            .body(json!({ "from": "Cat", "to": "Material Bangunan" }).to_string())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), rocket::http::Status::BadRequest);
+        assert_eq!(kategori_in_db(&db_pool, cat).await, "Cat");
+
+        let response = client
+            .post("/api/produk/kategori/rename")
+            .header(rocket::http::ContentType::JSON)
+            .body(json!({ "from": "Cat", "to": "   " }).to_string())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), rocket::http::Status::BadRequest);
+    }
 }
\ No newline at end of file
@@ -1,4 +1,5 @@
 pub mod model;
 pub mod controller;
 pub mod repository;
-pub mod validation;
\ No newline at end of file
+pub mod validation;
+pub mod service;
\ No newline at end of file
@@ -7,9 +7,33 @@
 // - `harga()`: Menetapkan harga produk
 // - `stok()`: Menetapkan stok produk
 // - `deskripsi()`: Menetapkan deskripsi produk (opsional)
-// - `build()`: Membuat Produk dan memvalidasinya, mengembalikan Result
-
+// - `stok_minimum()`: Menetapkan ambang stok rendah produk (default 0)
+// - `harga_modal()`: Menetapkan harga modal produk (opsional)
+// - `berat_gram()`: Menetapkan berat produk dalam gram (opsional)
+// - `dimensi()`: Menetapkan panjang/lebar/tinggi produk dalam cm (opsional)
+// - `harga_grosir()`: Menetapkan harga grosir dan jumlah minimalnya (opsional)
+// - `satuan()`: Menetapkan satuan jual dan presisi desimal stoknya (default "pcs", 0)
+// - `build()`: Merapikan nama/kategori, lalu membuat Produk dan memvalidasinya, mengembalikan Result
+
+use crate::common::audit::AuditTrail;
 use crate::manajemen_produk::model::Produk;
+use crate::manajemen_produk::model::produk::DEFAULT_SATUAN;
+
+// Spasi berlebih dirapatkan dan tiap kata dijadikan Title Case,
+// sehingga " elektronik  RUMAH " dan "Elektronik Rumah" dianggap kategori yang sama
+pub fn normalize_kategori(kategori: &str) -> String {
+    kategori
+        .split_whitespace()
+        .map(|kata| {
+            let mut chars = kata.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars.flat_map(|c| c.to_lowercase())).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
 
 pub struct ProdukBuilder {
     id: Option<i64>,
@@ -18,6 +42,16 @@ pub struct ProdukBuilder {
     harga: f64,
     stok: u32,
     deskripsi: Option<String>,
+    stok_minimum: u32,
+    harga_modal: Option<f64>,
+    berat_gram: Option<i32>,
+    panjang_cm: Option<i32>,
+    lebar_cm: Option<i32>,
+    tinggi_cm: Option<i32>,
+    harga_grosir: Option<f64>,
+    min_qty_grosir: Option<u32>,
+    satuan: String,
+    presisi_stok: u32,
 }
 
 impl ProdukBuilder {
@@ -29,6 +63,16 @@ impl ProdukBuilder {
             harga: 0.0,
             stok: 0,
             deskripsi: None,
+            stok_minimum: 0,
+            harga_modal: None,
+            berat_gram: None,
+            panjang_cm: None,
+            lebar_cm: None,
+            tinggi_cm: None,
+            harga_grosir: None,
+            min_qty_grosir: None,
+            satuan: DEFAULT_SATUAN.to_string(),
+            presisi_stok: 0,
         }
     }
     
@@ -52,14 +96,59 @@ impl ProdukBuilder {
         self
     }
     
+    pub fn stok_minimum(mut self, stok_minimum: u32) -> Self {
+        self.stok_minimum = stok_minimum;
+        self
+    }
+    
+    pub fn harga_modal(mut self, harga_modal: f64) -> Self {
+        self.harga_modal = Some(harga_modal);
+        self
+    }
+    
+    pub fn berat_gram(mut self, berat_gram: Option<i32>) -> Self {
+        self.berat_gram = berat_gram;
+        self
+    }
+    
+    pub fn dimensi(mut self, panjang_cm: Option<i32>, lebar_cm: Option<i32>, tinggi_cm: Option<i32>) -> Self {
+        self.panjang_cm = panjang_cm;
+        self.lebar_cm = lebar_cm;
+        self.tinggi_cm = tinggi_cm;
+        self
+    }
+    
+    pub fn harga_grosir(mut self, harga_grosir: Option<f64>, min_qty_grosir: Option<u32>) -> Self {
+        self.harga_grosir = harga_grosir;
+        self.min_qty_grosir = min_qty_grosir;
+        self
+    }
+    
+    pub fn satuan(mut self, satuan: String, presisi_stok: u32) -> Self {
+        self.satuan = satuan;
+        self.presisi_stok = presisi_stok;
+        self
+    }
+    
     pub fn build(self) -> Result<Produk, Vec<String>> {
         let produk = Produk {
             id: self.id,
-            nama: self.nama,
-            kategori: self.kategori,
+            nama: self.nama.trim().to_string(),
+            kategori: normalize_kategori(&self.kategori),
             harga: self.harga,
             stok: self.stok,
             deskripsi: self.deskripsi,
+            stok_minimum: self.stok_minimum,
+            harga_modal: self.harga_modal,
+            berat_gram: self.berat_gram,
+            panjang_cm: self.panjang_cm,
+            lebar_cm: self.lebar_cm,
+            tinggi_cm: self.tinggi_cm,
+            harga_grosir: self.harga_grosir,
+            min_qty_grosir: self.min_qty_grosir,
+            satuan: self.satuan.trim().to_lowercase(),
+            presisi_stok: self.presisi_stok,
+            audit: AuditTrail::default(),
         };
         
         match produk.validate() {
@@ -114,7 +203,7 @@ mod tests {
             Ok(produk) => {
                 assert_eq!(produk.id, Some(123));
                 assert_eq!(produk.nama, "ID Product");
-                assert_eq!(produk.kategori, "ID Category");
+                assert_eq!(produk.kategori, "Id Category");
                 assert_eq!(produk.harga, 600.0);
                 assert_eq!(produk.stok, 6);
             }
@@ -368,7 +457,7 @@ mod tests {
         match result {
             Ok(produk) => {
                 assert_eq!(produk.nama, special_chars);
-                assert_eq!(produk.kategori, special_chars);
+                assert_eq!(produk.kategori, "特殊字符 🚀 Émojis & Symbols!@#$%^&*()");
                 assert_eq!(produk.deskripsi, Some(special_chars.to_string()));
             }
             Err(_) => {
@@ -441,4 +530,28 @@ mod tests {
             Err(_) => {} // Validation might fail, that's ok
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_builder_trims_nama_and_normalizes_kategori() {
+        let produk = ProdukBuilder::new("  Semen Gresik 40kg \t".to_string(), "  bahan   BANGUNAN ".to_string())
+            .harga(65000.0)
+            .stok(5)
+            .build()
+            .expect("Builder should accept padded input");
+
+        assert_eq!(produk.nama, "Semen Gresik 40kg");
+        assert_eq!(produk.kategori, "Bahan Bangunan");
+        assert_eq!(normalize_kategori("elektronik"), normalize_kategori(" ELEKTRONIK "));
+    }
+
+    #[test]
+    fn test_builder_rejects_whitespace_only_nama_and_kategori() {
+        let errors = ProdukBuilder::new("   \t ".to_string(), " \n ".to_string())
+            .harga(100.0)
+            .build()
+            .expect_err("Whitespace-only fields should fail validation");
+
+        assert!(errors.iter().any(|e| e.contains("Nama produk tidak boleh kosong")));
+        assert!(errors.iter().any(|e| e.contains("Kategori produk tidak boleh kosong")));
+    }
+}
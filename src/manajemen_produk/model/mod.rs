@@ -1,5 +1,10 @@
 pub mod produk;
 pub mod builder;
+pub mod reservation;
+pub mod tag;
+pub mod stok_event;
 
 pub use produk::Produk;
-pub use builder::ProdukBuilder;
\ No newline at end of file
+pub use builder::ProdukBuilder;
+pub use reservation::ProdukReservation;
+pub use stok_event::{StokChange, StokEvent, StokEventKind};
\ No newline at end of file
@@ -7,11 +7,76 @@
 // - `harga`: Harga produk dalam bentuk float (wajib)
 // - `stok`: Jumlah stok tersedia (wajib)
 // - `deskripsi`: Deskripsi tambahan produk (opsional)
+// - `stok_minimum`: Ambang stok rendah milik produk ini (default 0)
+// - `harga_modal`: Harga beli/modal untuk laporan margin (opsional)
+// - `berat_gram`: Berat per unit dalam gram untuk ongkos kirim (opsional)
+// - `panjang_cm`, `lebar_cm`, `tinggi_cm`: Dimensi per unit dalam cm (opsional)
+// - `harga_grosir`, `min_qty_grosir`: Harga grosir per unit dan jumlah minimal per baris
+//   transaksi agar harga grosir berlaku (opsional, keduanya harus diisi)
+// - `satuan`: Satuan jual produk, mis. "pcs", "sak", "kg" (default "pcs")
+// - `presisi_stok`: Jumlah digit desimal stok dan jumlah jual; 0 untuk barang hitungan,
+//   mis. 3 untuk pasir per kg. `stok` dan `stok_minimum` disimpan sebagai bilangan bulat
+//   dalam unit terkecil (10^-presisi_stok satuan), sehingga saldo stok tetap eksak
+// - `audit`: Username pembuat/pengubah terakhir (created_by/updated_by)
 
 // # Methods
 // - `with_id()`: Constructor untuk produk yang sudah ada di database
 // - `new()`: Constructor untuk produk baru
 // - `validate()`: Validasi data produk sebelum disimpan
+// - `stock_status()`: Label status stok ("out_of_stock", "low", "in_stock")
+// - `harga_untuk_jumlah()`: Harga per unit untuk satu baris sejumlah `jumlah` (grosir atau eceran)
+// - `stok_dalam_satuan()`: Stok dalam satuan jual (desimal untuk barang timbangan)
+
+use crate::common::audit::AuditTrail;
+
+pub const DEFAULT_LOW_STOCK_THRESHOLD: u32 = 10;
+pub const DEFAULT_SATUAN: &str = "pcs";
+pub const MAX_PRESISI_STOK: u32 = 3;
+
+// Batas stok "low", bisa diatur lewat env PRODUK_LOW_STOCK_THRESHOLD
+pub fn low_stock_threshold() -> u32 {
+    std::env::var("PRODUK_LOW_STOCK_THRESHOLD")
+        .ok()
+        .and_then(|value| value.parse::<u32>().ok())
+        .unwrap_or(DEFAULT_LOW_STOCK_THRESHOLD)
+}
+
+// Harga modal di atas harga jual ditolak, kecuali env PRODUK_ALLOW_MODAL_ABOVE_HARGA diisi `1`/`true`
+pub fn modal_above_harga_allowed() -> bool {
+    std::env::var("PRODUK_ALLOW_MODAL_ABOVE_HARGA")
+        .map(|value| matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+// Faktor unit terkecil per satuan untuk `presisi_stok`: 1 untuk barang hitungan, 1000 untuk 3 desimal
+pub fn skala_stok(presisi_stok: u32) -> u32 {
+    10u32.pow(presisi_stok.min(MAX_PRESISI_STOK))
+}
+
+// Jumlah dalam satuan (mis. 2.5 kg) ke unit terkecil; ditolak bila negatif atau punya digit
+// desimal lebih banyak dari `presisi_stok`, jadi barang hitungan tetap hanya menerima bilangan bulat
+pub fn jumlah_ke_unit_stok(jumlah: f64, presisi_stok: u32) -> Result<u32, String> {
+    if !jumlah.is_finite() || jumlah < 0.0 {
+        return Err("Jumlah tidak boleh negatif".to_string());
+    }
+    let scaled = jumlah * skala_stok(presisi_stok) as f64;
+    let rounded = scaled.round();
+    // Toleransi untuk angka seperti 1.15 * 100 = 114.99999999999999
+    if (scaled - rounded).abs() > 1e-6 {
+        return Err(match presisi_stok {
+            0 => format!("Jumlah {} harus bilangan bulat", jumlah),
+            presisi => format!("Jumlah {} melebihi {} digit desimal", jumlah, presisi),
+        });
+    }
+    if rounded > u32::MAX as f64 {
+        return Err(format!("Jumlah {} terlalu besar", jumlah));
+    }
+    Ok(rounded as u32)
+}
+
+pub fn unit_stok_ke_jumlah(unit: u32, presisi_stok: u32) -> f64 {
+    unit as f64 / skala_stok(presisi_stok) as f64
+}
 
 #[derive(Debug, Clone, sqlx::FromRow)]
 pub struct Produk {
@@ -21,6 +86,18 @@ pub struct Produk {
     pub harga: f64,
     pub stok: u32,
     pub deskripsi: Option<String>,
+    pub stok_minimum: u32,
+    pub harga_modal: Option<f64>,
+    pub berat_gram: Option<i32>,
+    pub panjang_cm: Option<i32>,
+    pub lebar_cm: Option<i32>,
+    pub tinggi_cm: Option<i32>,
+    pub harga_grosir: Option<f64>,
+    pub min_qty_grosir: Option<u32>,
+    pub satuan: String,
+    pub presisi_stok: u32,
+    #[sqlx(skip)]
+    pub audit: AuditTrail,
 }
 
 impl Produk {
@@ -39,6 +116,17 @@ impl Produk {
             harga,
             stok,
             deskripsi,
+            stok_minimum: 0,
+            harga_modal: None,
+            berat_gram: None,
+            panjang_cm: None,
+            lebar_cm: None,
+            tinggi_cm: None,
+            harga_grosir: None,
+            min_qty_grosir: None,
+            satuan: DEFAULT_SATUAN.to_string(),
+            presisi_stok: 0,
+            audit: AuditTrail::default(),
         }
     }
     
@@ -56,6 +144,17 @@ impl Produk {
             harga,
             stok,
             deskripsi,
+            stok_minimum: 0,
+            harga_modal: None,
+            berat_gram: None,
+            panjang_cm: None,
+            lebar_cm: None,
+            tinggi_cm: None,
+            harga_grosir: None,
+            min_qty_grosir: None,
+            satuan: DEFAULT_SATUAN.to_string(),
+            presisi_stok: 0,
+            audit: AuditTrail::default(),
         }
     }
     
@@ -65,6 +164,30 @@ impl Produk {
         let validator = ProdukValidator::default();
         validator.validate(self)
     }
+
+    // Stok 0 -> out_of_stock, di bawah threshold -> low, selebihnya in_stock
+    pub fn stock_status(&self, low_threshold: u32) -> &'static str {
+        if self.stok == 0 {
+            "out_of_stock"
+        } else if self.stok < low_threshold {
+            "low"
+        } else {
+            "in_stock"
+        }
+    }
+
+    // Harga grosir hanya berlaku bila harga dan jumlah minimalnya sama-sama diisi dan
+    // jumlah satu baris mencapai jumlah minimal tersebut; selain itu harga eceran
+    pub fn harga_untuk_jumlah(&self, jumlah: f64) -> f64 {
+        match (self.harga_grosir, self.min_qty_grosir) {
+            (Some(harga_grosir), Some(min_qty)) if min_qty > 0 && jumlah >= min_qty as f64 => harga_grosir,
+            _ => self.harga,
+        }
+    }
+
+    pub fn stok_dalam_satuan(&self) -> f64 {
+        unit_stok_ke_jumlah(self.stok, self.presisi_stok)
+    }
 }
 
 fn setup_test_products() -> Vec<Produk> {
@@ -368,4 +491,44 @@ mod tests {
         assert_eq!(produk.stok, 15);
         assert_eq!(produk.deskripsi.as_ref().unwrap().as_str(), "Access description");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_produk_stock_status_boundaries() {
+        let threshold = 5;
+        let with_stok = |stok| Produk::new("Semen".to_string(), "Material".to_string(), 1.0, stok, None);
+
+        assert_eq!(with_stok(0).stock_status(threshold), "out_of_stock");
+        assert_eq!(with_stok(threshold - 1).stock_status(threshold), "low");
+        assert_eq!(with_stok(threshold).stock_status(threshold), "in_stock");
+        assert_eq!(with_stok(threshold + 1).stock_status(threshold), "in_stock");
+    }
+
+    #[test]
+    fn test_produk_harga_untuk_jumlah_grosir_threshold() {
+        let mut produk = Produk::new("Semen".to_string(), "Material".to_string(), 65000.0, 100, None);
+        assert_eq!(produk.harga_untuk_jumlah(50.0), 65000.0);
+
+        produk.harga_grosir = Some(60000.0);
+        produk.min_qty_grosir = Some(10);
+        assert_eq!(produk.harga_untuk_jumlah(9.5), 65000.0);
+        assert_eq!(produk.harga_untuk_jumlah(10.0), 60000.0);
+        assert_eq!(produk.harga_untuk_jumlah(25.0), 60000.0);
+    }
+
+    #[test]
+    fn test_jumlah_ke_unit_stok_by_presisi() {
+        assert_eq!(jumlah_ke_unit_stok(12.0, 0), Ok(12));
+        assert!(jumlah_ke_unit_stok(2.5, 0).unwrap_err().contains("bilangan bulat"));
+
+        assert_eq!(jumlah_ke_unit_stok(2.5, 3), Ok(2500));
+        assert_eq!(jumlah_ke_unit_stok(1.15, 2), Ok(115));
+        assert_eq!(jumlah_ke_unit_stok(0.001, 3), Ok(1));
+        assert!(jumlah_ke_unit_stok(0.0005, 3).unwrap_err().contains("3 digit desimal"));
+        assert!(jumlah_ke_unit_stok(-1.0, 3).is_err());
+
+        let mut pasir = Produk::new("Pasir".to_string(), "Material".to_string(), 250000.0, 12500, None);
+        pasir.satuan = "kg".to_string();
+        pasir.presisi_stok = 3;
+        assert_eq!(pasir.stok_dalam_satuan(), 12.5);
+    }
+}
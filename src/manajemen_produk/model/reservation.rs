@@ -0,0 +1,30 @@
+// Hold sementara atas sejumlah unit produk untuk keranjang yang belum checkout.
+// Reservasi mengurangi stok "tersedia" (stok - reservasi aktif), bukan stok fisik.
+
+// # Fields
+// - `id`: ID reservasi (UUID)
+// - `produk_id`: ID produk yang di-hold
+// - `jumlah`: Jumlah yang di-hold dalam satuan produk (mis. 2.5 kg); tersimpan sebagai unit stok terkecil
+// - `expires_at`: Batas waktu hold (RFC 3339, UTC); setelah lewat, hold tidak dihitung lagi
+
+use rocket::serde::{Deserialize, Serialize};
+
+pub const DEFAULT_RESERVATION_TTL_SECS: i64 = 900;
+
+// Lama hold default, bisa diatur lewat env PRODUK_RESERVATION_TTL_SECS
+pub fn reservation_ttl_secs() -> i64 {
+    std::env::var("PRODUK_RESERVATION_TTL_SECS")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|value| *value > 0)
+        .unwrap_or(DEFAULT_RESERVATION_TTL_SECS)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ProdukReservation {
+    pub id: String,
+    pub produk_id: i64,
+    pub jumlah: f64,
+    pub expires_at: String,
+}
@@ -0,0 +1,61 @@
+use serde::Serialize;
+
+// Perubahan stok satu produk (nilai sebelum dan sesudah) hasil operasi repository.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StokChange {
+    pub produk_id: i64,
+    pub sebelum: u32,
+    pub sesudah: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum StokEventKind {
+    // Stok naik dari 0 menjadi lebih dari 0
+    Restocked,
+    // Stok turun dari lebih dari 0 menjadi 0
+    OutOfStock,
+}
+
+// Event domain yang dikirim ke `ProdukNotifier` saat stok melewati batas nol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct StokEvent {
+    pub produk_id: i64,
+    pub kind: StokEventKind,
+    pub stok_sebelum: u32,
+    pub stok_sesudah: u32,
+}
+
+impl StokChange {
+    pub fn new(produk_id: i64, sebelum: u32, sesudah: u32) -> Self {
+        Self { produk_id, sebelum, sesudah }
+    }
+
+    // `None` bila perubahan tidak melewati nol (mis. 5 -> 3 atau 0 -> 0)
+    pub fn event(&self) -> Option<StokEvent> {
+        let kind = match (self.sebelum, self.sesudah) {
+            (0, sesudah) if sesudah > 0 => StokEventKind::Restocked,
+            (sebelum, 0) if sebelum > 0 => StokEventKind::OutOfStock,
+            _ => return None,
+        };
+        Some(StokEvent {
+            produk_id: self.produk_id,
+            kind,
+            stok_sebelum: self.sebelum,
+            stok_sesudah: self.sesudah,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_only_when_crossing_zero() {
+        assert_eq!(StokChange::new(1, 0, 5).event().map(|e| e.kind), Some(StokEventKind::Restocked));
+        assert_eq!(StokChange::new(1, 3, 0).event().map(|e| e.kind), Some(StokEventKind::OutOfStock));
+        assert_eq!(StokChange::new(1, 5, 3).event(), None);
+        assert_eq!(StokChange::new(1, 3, 8).event(), None);
+        assert_eq!(StokChange::new(1, 0, 0).event(), None);
+    }
+}
@@ -0,0 +1,19 @@
+// Tag bebas untuk mengelompokkan produk di luar kategori (satu produk bisa punya banyak tag).
+// Tag disimpan dalam bentuk ter-normalisasi: huruf kecil, tanpa spasi di ujung, hanya
+// huruf/angka, `-` dan `_`, sehingga "Promo" dan "promo " adalah tag yang sama.
+
+pub const MAX_TAG_LENGTH: usize = 50;
+
+pub fn normalisasi_tag(tag: &str) -> Result<String, String> {
+    let tag = tag.trim().to_lowercase();
+    if tag.is_empty() {
+        return Err("Tag tidak boleh kosong".to_string());
+    }
+    if tag.chars().count() > MAX_TAG_LENGTH {
+        return Err(format!("Tag terlalu panjang (maksimal {} karakter)", MAX_TAG_LENGTH));
+    }
+    if !tag.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("Tag '{}' hanya boleh berisi huruf, angka, '-' dan '_'", tag));
+    }
+    Ok(tag)
+}
@@ -1,15 +1,19 @@
 use crate::manajemen_produk::model::Produk;
-use crate::manajemen_produk::repository::dto::{validate_produk, RepositoryError};
-use sqlx::{AnyPool, Row};
+use crate::manajemen_produk::repository::dto::{cek_duplikat_nama, validate_produk, RepositoryError};
+use crate::manajemen_produk::repository::read::ambil_produk_by_sku;
+use sqlx::{AnyPool, Connection, Row};
 
 pub async fn tambah_produk(pool: &AnyPool, produk: &Produk) -> Result<i64, RepositoryError> {
     // Validasi terlebih dahulu
     validate_produk(produk)?;
+    cek_duplikat_nama(pool, produk, None).await?;
     
     let result = sqlx::query(
         r#"
-        INSERT INTO produk (nama, kategori, harga, stok, deskripsi)
-        VALUES ($1, $2, $3, $4, $5)
+        INSERT INTO produk (nama, kategori, harga, stok, deskripsi, stok_minimum, harga_modal,
+                            berat_gram, panjang_cm, lebar_cm, tinggi_cm, harga_grosir, min_qty_grosir,
+                            satuan, presisi_stok, created_by, updated_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
         RETURNING id
         "#
     )
@@ -18,18 +22,128 @@ pub async fn tambah_produk(pool: &AnyPool, produk: &Produk) -> Result<i64, Repos
     .bind(produk.harga)
     .bind(produk.stok as i32)
     .bind(&produk.deskripsi)
+    .bind(produk.stok_minimum as i32)
+    .bind(produk.harga_modal)
+    .bind(produk.berat_gram)
+    .bind(produk.panjang_cm)
+    .bind(produk.lebar_cm)
+    .bind(produk.tinggi_cm)
+    .bind(produk.harga_grosir)
+    .bind(produk.min_qty_grosir.map(|min_qty| min_qty as i32))
+    .bind(&produk.satuan)
+    .bind(produk.presisi_stok as i32)
+    .bind(&produk.audit.created_by)
+    .bind(&produk.audit.updated_by)
     .fetch_one(pool)
     .await?;
     
     Ok(result.get("id"))
 }
 
+// Import berulang: SKU yang sudah ada di-update di tempat (id tetap), SKU baru di-insert.
+// `ON CONFLICT ... DO UPDATE` didukung Postgres maupun sqlite >= 3.24, jadi tidak perlu
+// bercabang per driver; `INSERT OR REPLACE` sengaja tidak dipakai karena mengganti id.
+// Berat/dimensi tidak ada di file import, jadi nilai yang sudah tersimpan dibiarkan.
+pub async fn upsert_produk_by_sku(pool: &AnyPool, sku: &str, produk: &Produk) -> Result<i64, RepositoryError> {
+    let sku = sku.trim();
+    if sku.is_empty() {
+        return Err(RepositoryError::ValidationError("SKU tidak boleh kosong".to_string()));
+    }
+    if sku.len() > 64 {
+        return Err(RepositoryError::ValidationError("SKU terlalu panjang (maksimal 64 karakter)".to_string()));
+    }
+    validate_produk(produk)?;
+
+    let existing_id = ambil_produk_by_sku(pool, sku).await?.and_then(|existing| existing.id);
+    cek_duplikat_nama(pool, produk, existing_id).await?;
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO produk (nama, kategori, harga, stok, deskripsi, sku, stok_minimum, harga_modal)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (sku) DO UPDATE SET
+            nama = EXCLUDED.nama,
+            kategori = EXCLUDED.kategori,
+            harga = EXCLUDED.harga,
+            stok = EXCLUDED.stok,
+            deskripsi = EXCLUDED.deskripsi,
+            stok_minimum = EXCLUDED.stok_minimum,
+            harga_modal = EXCLUDED.harga_modal
+        RETURNING id
+        "#
+    )
+    .bind(&produk.nama)
+    .bind(&produk.kategori)
+    .bind(produk.harga)
+    .bind(produk.stok as i32)
+    .bind(&produk.deskripsi)
+    .bind(sku)
+    .bind(produk.stok_minimum as i32)
+    .bind(produk.harga_modal)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(result.get("id"))
+}
+
+// Import CSV: semua baris di-insert dalam satu transaksi, jadi import gagal total atau
+// berhasil total. Error memuat indeks baris (urutan `rows`) yang menggagalkan import;
+// duplikat nama/SKU di dalam file yang sama ikut terdeteksi karena dicek di transaksi yang sama.
+pub async fn tambah_produk_batch(
+    pool: &AnyPool,
+    rows: &[(Option<String>, Produk)],
+) -> Result<Vec<i64>, (usize, RepositoryError)> {
+    let mut conn = pool.acquire().await.map_err(|e| (0, e.into()))?;
+    let mut tx = conn.begin().await.map_err(|e| (0, e.into()))?;
+    let mut ids = Vec::with_capacity(rows.len());
+
+    for (index, (sku, produk)) in rows.iter().enumerate() {
+        validate_produk(produk).map_err(|e| (index, e))?;
+        cek_duplikat_nama(&mut *tx, produk, None).await.map_err(|e| (index, e))?;
+        if let Some(sku) = sku {
+            let existing = sqlx::query("SELECT id FROM produk WHERE sku = $1")
+                .bind(sku)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| (index, e.into()))?;
+            if existing.is_some() {
+                return Err((index, RepositoryError::Conflict(format!("SKU '{}' sudah dipakai produk lain", sku))));
+            }
+        }
+
+        let row = sqlx::query(
+            r#"
+            INSERT INTO produk (nama, kategori, harga, stok, deskripsi, sku, stok_minimum, created_by, updated_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING id
+            "#
+        )
+        .bind(&produk.nama)
+        .bind(&produk.kategori)
+        .bind(produk.harga)
+        .bind(produk.stok as i32)
+        .bind(&produk.deskripsi)
+        .bind(sku)
+        .bind(produk.stok_minimum as i32)
+        .bind(&produk.audit.created_by)
+        .bind(&produk.audit.updated_by)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| (index, e.into()))?;
+        ids.push(row.get("id"));
+    }
+
+    tx.commit().await.map_err(|e| (rows.len().saturating_sub(1), e.into()))?;
+    Ok(ids)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use sqlx::{any::{AnyPoolOptions, install_default_drivers}, Row};
 
     use crate::manajemen_produk::model::Produk;
+    use crate::manajemen_produk::model::produk::DEFAULT_SATUAN;
 
     async fn setup_test_db() -> sqlx::Pool<sqlx::Any> {
         install_default_drivers();
@@ -48,7 +162,20 @@ mod tests {
                 kategori TEXT NOT NULL,
                 harga REAL NOT NULL,
                 stok INTEGER NOT NULL,
-                deskripsi TEXT
+                stok_minimum INTEGER NOT NULL DEFAULT 0,
+                harga_modal REAL,
+                berat_gram INTEGER,
+                panjang_cm INTEGER,
+                lebar_cm INTEGER,
+                tinggi_cm INTEGER,
+                harga_grosir REAL,
+                min_qty_grosir INTEGER,
+                satuan VARCHAR(20) NOT NULL DEFAULT 'pcs',
+                presisi_stok INTEGER NOT NULL DEFAULT 0,
+                created_by TEXT,
+                updated_by TEXT,
+                deskripsi TEXT,
+                sku TEXT UNIQUE
             )
             "#
         )
@@ -70,6 +197,17 @@ mod tests {
             harga: 15000000.50, // Menggunakan f64 langsung
             stok: 10,
             deskripsi: Some("Laptop gaming high-end dengan RTX 4080".to_string()),
+            stok_minimum: 0,
+            harga_modal: None,
+            berat_gram: None,
+            panjang_cm: None,
+            lebar_cm: None,
+            tinggi_cm: None,
+            harga_grosir: None,
+            min_qty_grosir: None,
+            satuan: DEFAULT_SATUAN.to_string(),
+            presisi_stok: 0,
+            audit: Default::default(),
         };
 
         let result = tambah_produk(&db_pool, &produk).await;
@@ -107,6 +245,17 @@ mod tests {
             harga: 150000.00, // f64
             stok: 50,
             deskripsi: None, // No description
+            stok_minimum: 0,
+            harga_modal: None,
+            berat_gram: None,
+            panjang_cm: None,
+            lebar_cm: None,
+            tinggi_cm: None,
+            harga_grosir: None,
+            min_qty_grosir: None,
+            satuan: DEFAULT_SATUAN.to_string(),
+            presisi_stok: 0,
+            audit: Default::default(),
         };
 
         let result = tambah_produk(&db_pool, &produk).await;
@@ -142,6 +291,17 @@ mod tests {
             harga: 750000.99, // f64
             stok: 0, // Zero stock
             deskripsi: Some("Keyboard mechanical blue switch".to_string()),
+            stok_minimum: 0,
+            harga_modal: None,
+            berat_gram: None,
+            panjang_cm: None,
+            lebar_cm: None,
+            tinggi_cm: None,
+            harga_grosir: None,
+            min_qty_grosir: None,
+            satuan: DEFAULT_SATUAN.to_string(),
+            presisi_stok: 0,
+            audit: Default::default(),
         };
 
         let result = tambah_produk(&db_pool, &produk).await;
@@ -169,6 +329,17 @@ mod tests {
             harga: 999999999.99, // f64 - Large price
             stok: 999999, // Large stock
             deskripsi: Some("High-end enterprise server with redundant systems and 24/7 support warranty".to_string()),
+            stok_minimum: 0,
+            harga_modal: None,
+            berat_gram: None,
+            panjang_cm: None,
+            lebar_cm: None,
+            tinggi_cm: None,
+            harga_grosir: None,
+            min_qty_grosir: None,
+            satuan: DEFAULT_SATUAN.to_string(),
+            presisi_stok: 0,
+            audit: Default::default(),
         };
 
         let result = tambah_produk(&db_pool, &produk).await;
@@ -198,6 +369,17 @@ mod tests {
             harga: 15000000.00, // f64
             stok: 25,
             deskripsi: Some("Latest iPhone model".to_string()),
+            stok_minimum: 0,
+            harga_modal: None,
+            berat_gram: None,
+            panjang_cm: None,
+            lebar_cm: None,
+            tinggi_cm: None,
+            harga_grosir: None,
+            min_qty_grosir: None,
+            satuan: DEFAULT_SATUAN.to_string(),
+            presisi_stok: 0,
+            audit: Default::default(),
         };
 
         let produk2 = Produk {
@@ -207,6 +389,17 @@ mod tests {
             harga: 12000000.00, // f64
             stok: 30,
             deskripsi: Some("Latest Samsung flagship".to_string()),
+            stok_minimum: 0,
+            harga_modal: None,
+            berat_gram: None,
+            panjang_cm: None,
+            lebar_cm: None,
+            tinggi_cm: None,
+            harga_grosir: None,
+            min_qty_grosir: None,
+            satuan: DEFAULT_SATUAN.to_string(),
+            presisi_stok: 0,
+            audit: Default::default(),
         };
 
         let result1 = tambah_produk(&db_pool, &produk1).await;
@@ -241,6 +434,17 @@ mod tests {
             harga: 45000.50, // f64
             stok: 100,
             deskripsi: Some("Premium coffee blend with special ingredients: açaí, ginseng & organic milk".to_string()),
+            stok_minimum: 0,
+            harga_modal: None,
+            berat_gram: None,
+            panjang_cm: None,
+            lebar_cm: None,
+            tinggi_cm: None,
+            harga_grosir: None,
+            min_qty_grosir: None,
+            satuan: DEFAULT_SATUAN.to_string(),
+            presisi_stok: 0,
+            audit: Default::default(),
         };
 
         let result = tambah_produk(&db_pool, &produk).await;
@@ -259,4 +463,64 @@ mod tests {
         assert_eq!(nama, "Café Latte & Cappuccino™");
         assert_eq!(kategori, "Minuman & Makanan");
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_tambah_produk_duplicate_nama_in_kategori_rejected() {
+        let db_pool = setup_test_db().await;
+
+        let produk = Produk::new("Semen Gresik".to_string(), "Bahan Bangunan".to_string(), 65000.0, 10, None);
+        tambah_produk(&db_pool, &produk).await.expect("First insert should succeed");
+
+        let duplikat = Produk::new("semen gresik".to_string(), "Bahan Bangunan".to_string(), 70000.0, 5, None);
+        let result = tambah_produk(&db_pool, &duplikat).await;
+        assert!(matches!(result, Err(RepositoryError::Conflict(_))));
+
+        // Nama sama di kategori lain tetap boleh
+        let kategori_lain = Produk::new("Semen Gresik".to_string(), "Promo".to_string(), 60000.0, 5, None);
+        assert!(tambah_produk(&db_pool, &kategori_lain).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_upsert_produk_by_sku_twice_updates_same_row() {
+        let db_pool = setup_test_db().await;
+
+        let produk = Produk::new(
+            "Semen Tiga Roda".to_string(),
+            "Bahan Bangunan".to_string(),
+            65000.0,
+            40,
+            None,
+        );
+        let first_id = upsert_produk_by_sku(&db_pool, "SMN-TR-50", &produk).await.unwrap();
+
+        let reimported = Produk::new(
+            "Semen Tiga Roda 50kg".to_string(),
+            "Bahan Bangunan".to_string(),
+            67500.0,
+            55,
+            Some("Harga baru dari supplier".to_string()),
+        );
+        let second_id = upsert_produk_by_sku(&db_pool, " SMN-TR-50 ", &reimported).await.unwrap();
+        assert_eq!(first_id, second_id);
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM produk WHERE sku = $1")
+            .bind("SMN-TR-50")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+
+        let row = sqlx::query("SELECT nama, harga, stok, deskripsi FROM produk WHERE id = $1")
+            .bind(first_id)
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(row.get::<String, _>("nama"), "Semen Tiga Roda 50kg");
+        assert!((row.get::<f64, _>("harga") - 67500.0).abs() < f64::EPSILON);
+        assert_eq!(row.get::<i32, _>("stok"), 55);
+        assert_eq!(row.get::<String, _>("deskripsi"), "Harga baru dari supplier");
+
+        let result = upsert_produk_by_sku(&db_pool, "   ", &produk).await;
+        assert!(matches!(result, Err(RepositoryError::ValidationError(_))));
+    }
+}
@@ -1,7 +1,9 @@
 use sqlx::{AnyPool, Row};
 use std::error::Error as StdError;
 use std::fmt;
+use crate::common::audit::AuditTrail;
 use crate::manajemen_produk::model::Produk;
+use crate::manajemen_produk::model::produk::DEFAULT_SATUAN;
 use rocket::State;
 
 // Error types
@@ -10,6 +12,7 @@ pub enum RepositoryError {
     NotFound,
     DatabaseError(sqlx::Error),
     ValidationError(String),
+    Conflict(String),
     Other(String),
 }
 
@@ -19,6 +22,7 @@ impl fmt::Display for RepositoryError {
             RepositoryError::NotFound => write!(f, "Record not found"),
             RepositoryError::DatabaseError(e) => write!(f, "Database error: {}", e),
             RepositoryError::ValidationError(msg) => write!(f, "Validation error: {}", msg),
+            RepositoryError::Conflict(msg) => write!(f, "Conflict: {}", msg),
             RepositoryError::Other(msg) => write!(f, "{}", msg),
         }
     }
@@ -63,16 +67,75 @@ pub fn validate_produk(produk: &Produk) -> Result<(), RepositoryError> {
     Ok(())
 }
 
+// Toko yang memang menjual produk bernama sama dalam satu kategori bisa mematikan
+// pengecekan duplikat lewat env PRODUK_ALLOW_DUPLICATE_NAMA=true
+pub fn allow_duplicate_nama() -> bool {
+    std::env::var("PRODUK_ALLOW_DUPLICATE_NAMA")
+        .map(|value| matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+// Menolak pasangan (nama, kategori) yang sudah dipakai produk lain; `exclude_id` untuk baris yang sedang di-update.
+// Menerima pool maupun koneksi transaksi, supaya baris yang baru di-insert dalam transaksi yang sama ikut terlihat.
+pub async fn cek_duplikat_nama<'e, E>(
+    executor: E,
+    produk: &Produk,
+    exclude_id: Option<i64>,
+) -> Result<(), RepositoryError>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Any>,
+{
+    if allow_duplicate_nama() {
+        return Ok(());
+    }
+
+    let existing = sqlx::query(
+        "SELECT id FROM produk WHERE LOWER(nama) = LOWER($1) AND LOWER(kategori) = LOWER($2) AND id <> $3 LIMIT 1"
+    )
+    .bind(produk.nama.trim())
+    .bind(produk.kategori.trim())
+    .bind(exclude_id.unwrap_or(-1))
+    .fetch_optional(executor)
+    .await?;
+
+    match existing {
+        Some(row) => Err(RepositoryError::Conflict(format!(
+            "Produk '{}' sudah ada di kategori '{}' (ID {})",
+            produk.nama,
+            produk.kategori,
+            row.try_get::<i64, _>("id")?
+        ))),
+        None => Ok(()),
+    }
+}
+
 // Convert database row to Produk - support untuk AnyRow
 pub fn row_to_produk(row: &sqlx::any::AnyRow) -> Result<Produk, sqlx::Error> {
-    Ok(Produk::with_id(
+    let mut produk = Produk::with_id(
         row.try_get("id")?,
         row.try_get("nama")?,
         row.try_get("kategori")?,
         row.try_get("harga")?,
         row.try_get::<i32, _>("stok")? as u32,
-        row.try_get("deskripsi")?,
-    ))
+        // Driver Any tidak bisa decode NULL ke Option<String>; NULL dibaca sebagai None
+        row.try_get::<String, _>("deskripsi").ok(),
+    );
+    // Query lama yang tidak memilih kolom stok_minimum tetap didukung (default 0)
+    produk.stok_minimum = row.try_get::<i32, _>("stok_minimum").map(|v| v.max(0) as u32).unwrap_or(0);
+    produk.harga_modal = row.try_get::<f64, _>("harga_modal").ok();
+    produk.berat_gram = row.try_get::<i32, _>("berat_gram").ok();
+    produk.panjang_cm = row.try_get::<i32, _>("panjang_cm").ok();
+    produk.lebar_cm = row.try_get::<i32, _>("lebar_cm").ok();
+    produk.tinggi_cm = row.try_get::<i32, _>("tinggi_cm").ok();
+    produk.harga_grosir = row.try_get::<f64, _>("harga_grosir").ok();
+    produk.min_qty_grosir = row.try_get::<i32, _>("min_qty_grosir").ok().map(|v| v.max(0) as u32);
+    produk.satuan = row.try_get::<String, _>("satuan").unwrap_or_else(|_| DEFAULT_SATUAN.to_string());
+    produk.presisi_stok = row.try_get::<i32, _>("presisi_stok").map(|v| v.max(0) as u32).unwrap_or(0);
+    produk.audit = AuditTrail {
+        created_by: row.try_get("created_by").ok(),
+        updated_by: row.try_get("updated_by").ok(),
+    };
+    Ok(produk)
 }
 
 // Statistics helper
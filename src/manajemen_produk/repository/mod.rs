@@ -3,6 +3,8 @@ pub mod create;
 pub mod read;
 pub mod update;
 pub mod delete;
+pub mod reservation;
+pub mod tag;
 
 pub struct ProdukRepository;
 
@@ -19,4 +21,6 @@ pub use dto::{
 pub use create::*;
 pub use read::*;
 pub use update::*;
-pub use delete::*;
\ No newline at end of file
+pub use delete::*;
+pub use reservation::*;
+pub use tag::*;
\ No newline at end of file
@@ -1,10 +1,14 @@
+use crate::common::audit::AuditTrail;
+use crate::common::pagination::{escape_like, ListQuery, SortColumn};
 use crate::manajemen_produk::model::Produk;
-use crate::manajemen_produk::repository::dto::{RepositoryError};
-use sqlx::{AnyPool, Row};
+use crate::manajemen_produk::model::produk::DEFAULT_SATUAN;
+use crate::manajemen_produk::repository::dto::{row_to_produk, RepositoryError};
+use std::collections::HashMap;
+use sqlx::{AnyConnection, AnyPool, Row};
 
 // SOLUSI 1: Menggunakan try_get dengan handling NULL secara manual
 pub async fn ambil_semua_produk(pool: &AnyPool) -> Result<Vec<Produk>, RepositoryError> {
-    let rows = sqlx::query("SELECT id, nama, kategori, CAST(harga as DOUBLE PRECISION) as harga, stok, deskripsi FROM produk ORDER BY id")
+    let rows = sqlx::query("SELECT id, nama, kategori, CAST(harga as DOUBLE PRECISION) as harga, stok, deskripsi, stok_minimum, harga_modal, berat_gram, panjang_cm, lebar_cm, tinggi_cm, harga_grosir, min_qty_grosir, satuan, presisi_stok, created_by, updated_by FROM produk ORDER BY id")
         .fetch_all(pool)
         .await?;
     
@@ -16,38 +20,352 @@ pub async fn ambil_semua_produk(pool: &AnyPool) -> Result<Vec<Produk>, Repositor
             Err(_) => None, // Jika error (termasuk NULL), set ke None
         };
         
-        products.push(Produk::with_id(
+        let mut produk = Produk::with_id(
             row.try_get("id")?,
             row.try_get("nama")?,
             row.try_get("kategori")?,
             row.try_get("harga")?,
             row.try_get::<i32, _>("stok")? as u32,
             row.try_get("deskripsi").map_or(None, |v: String| Some(v)),
-        ));
+        );
+        produk.stok_minimum = row.try_get::<i32, _>("stok_minimum")? as u32;
+        produk.harga_modal = row.try_get::<f64, _>("harga_modal").ok();
+        produk.berat_gram = row.try_get::<i32, _>("berat_gram").ok();
+        produk.panjang_cm = row.try_get::<i32, _>("panjang_cm").ok();
+        produk.lebar_cm = row.try_get::<i32, _>("lebar_cm").ok();
+        produk.tinggi_cm = row.try_get::<i32, _>("tinggi_cm").ok();
+        produk.harga_grosir = row.try_get::<f64, _>("harga_grosir").ok();
+        produk.min_qty_grosir = row.try_get::<i32, _>("min_qty_grosir").ok().map(|v| v.max(0) as u32);
+        produk.satuan = row.try_get::<String, _>("satuan").unwrap_or_else(|_| DEFAULT_SATUAN.to_string());
+        produk.presisi_stok = row.try_get::<i32, _>("presisi_stok").map(|v| v.max(0) as u32).unwrap_or(0);
+        produk.audit = AuditTrail {
+            created_by: row.try_get("created_by").ok(),
+            updated_by: row.try_get("updated_by").ok(),
+        };
+        products.push(produk);
     }
     
     Ok(products)
 }
 
-pub async fn ambil_produk_by_id(pool: &AnyPool, id: i64) -> Result<Option<Produk>, RepositoryError> {
-    let row = sqlx::query("SELECT id, nama, kategori, CAST(harga as DOUBLE PRECISION) as harga, stok, deskripsi FROM produk WHERE id = $1")
-        .bind(id)
-        .fetch_optional(pool)
+/// Predikat stok untuk listing produk; hanya satu yang boleh aktif.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StokFilter {
+    LowStockOnly,
+    OutOfStockOnly,
+}
+
+impl StokFilter {
+    /// Flag query `low_stock_only`/`out_of_stock_only` -> filter; keduanya sekaligus ditolak.
+    pub fn from_flags(low_stock_only: bool, out_of_stock_only: bool) -> Result<Option<Self>, String> {
+        match (low_stock_only, out_of_stock_only) {
+            (true, true) => Err("low_stock_only dan out_of_stock_only tidak bisa dipakai bersamaan".to_string()),
+            (true, false) => Ok(Some(StokFilter::LowStockOnly)),
+            (false, true) => Ok(Some(StokFilter::OutOfStockOnly)),
+            (false, false) => Ok(None),
+        }
+    }
+
+    fn predicate(self) -> &'static str {
+        match self {
+            StokFilter::LowStockOnly => "stok < stok_minimum",
+            StokFilter::OutOfStockOnly => "stok = 0",
+        }
+    }
+}
+
+/// Kondisi tambahan untuk listing produk, digabung dengan AND. `kategori` dicocokkan tanpa
+/// membedakan huruf besar/kecil.
+#[derive(Debug, Default)]
+pub struct ProdukFilter<'a> {
+    pub kategori: Option<&'a str>,
+    pub stok: Option<StokFilter>,
+}
+
+/// Nilai `sort` listing produk; nama dan kategori diurutkan tanpa membedakan huruf besar/kecil.
+pub const PRODUK_SORT_COLUMNS: &[SortColumn] = &[
+    ("nama", "LOWER(nama)"),
+    ("kategori", "LOWER(kategori)"),
+    ("harga", "harga"),
+    ("stok", "stok"),
+];
+
+/// `ORDER BY` dari `sort` listing; `None` bila tidak diminta.
+fn urutan_sort(list: &ListQuery) -> Result<Option<String>, RepositoryError> {
+    list.order_by(PRODUK_SORT_COLUMNS, "id").map_err(RepositoryError::ValidationError)
+}
+
+/// Listing dengan `sort` dan paginasi dari `list` (tanpa `sort` urut berdasarkan id).
+pub async fn ambil_produk_dengan_filter(pool: &AnyPool, filter: &ProdukFilter<'_>, list: &ListQuery) -> Result<Vec<Produk>, RepositoryError> {
+    let order_by = urutan_sort(list)?.unwrap_or_else(|| "id".to_string());
+    let mut sql = "SELECT id, nama, kategori, CAST(harga as DOUBLE PRECISION) as harga, stok, deskripsi, stok_minimum, harga_modal, berat_gram, panjang_cm, lebar_cm, tinggi_cm, harga_grosir, min_qty_grosir, satuan, presisi_stok, created_by, updated_by FROM produk WHERE 1=1".to_string();
+    let kategori = filter.kategori.map(str::trim).filter(|k| !k.is_empty());
+    if kategori.is_some() {
+        sql.push_str(" AND LOWER(kategori) = LOWER($1)");
+    }
+    if let Some(stok) = filter.stok {
+        sql.push_str(" AND ");
+        sql.push_str(stok.predicate());
+    }
+    sql.push_str(&format!(" ORDER BY {}{}", order_by, list.limit_clause()));
+
+    let mut query = sqlx::query(&sql);
+    if let Some(kategori) = kategori {
+        query = query.bind(kategori);
+    }
+    let rows = query.fetch_all(pool).await?;
+
+    let mut products = Vec::with_capacity(rows.len());
+    for row in rows {
+        products.push(row_to_produk(&row)?);
+    }
+    Ok(products)
+}
+
+/// Pencarian keyword di nama, kategori dan deskripsi (tanpa membedakan huruf besar/kecil),
+/// diurutkan berdasarkan relevansi nama: sama persis, lalu awalan, lalu mengandung keyword,
+/// lalu yang hanya cocok di kategori/deskripsi. Urutan dalam satu tingkat tetap berdasarkan id.
+/// `sort` dari `list` menggantikan urutan relevansi; paginasinya diterapkan di SQL.
+pub async fn cari_produk(pool: &AnyPool, keyword: &str, filter: &ProdukFilter<'_>, list: &ListQuery) -> Result<Vec<Produk>, RepositoryError> {
+    let keyword = keyword.trim().to_lowercase();
+    let escaped = escape_like(&keyword);
+    let kategori = filter.kategori.map(str::trim).filter(|k| !k.is_empty());
+
+    // Placeholder ditulis manual sebagai $n (lihat ambil_produk_dengan_filter); urutannya
+    // harus sama dengan urutan bind di bawah.
+    let mut sql = "SELECT id, nama, kategori, CAST(harga as DOUBLE PRECISION) as harga, stok, deskripsi, stok_minimum, harga_modal, berat_gram, panjang_cm, lebar_cm, tinggi_cm, harga_grosir, min_qty_grosir, satuan, presisi_stok, created_by, updated_by FROM produk \
+        WHERE (LOWER(nama) LIKE $1 ESCAPE '\\' OR LOWER(kategori) LIKE $1 ESCAPE '\\' OR LOWER(COALESCE(deskripsi, '')) LIKE $1 ESCAPE '\\')".to_string();
+    let mut param = 1;
+    if kategori.is_some() {
+        param += 1;
+        sql.push_str(&format!(" AND LOWER(kategori) = LOWER(${})", param));
+    }
+    if let Some(stok) = filter.stok {
+        sql.push_str(" AND ");
+        sql.push_str(stok.predicate());
+    }
+    // Placeholder relevansi hanya ditulis (dan di-bind) bila `sort` tidak diminta
+    let urutan = urutan_sort(list)?;
+    match &urutan {
+        Some(order_by) => sql.push_str(&format!(" ORDER BY {}", order_by)),
+        None => sql.push_str(&format!(
+            " ORDER BY CASE WHEN LOWER(nama) = ${} THEN 0 WHEN LOWER(nama) LIKE ${} ESCAPE '\\' THEN 1 WHEN LOWER(nama) LIKE $1 ESCAPE '\\' THEN 2 ELSE 3 END, id",
+            param + 1,
+            param + 2
+        )),
+    }
+    sql.push_str(&list.limit_clause());
+
+    let mut query = sqlx::query(&sql).bind(format!("%{}%", escaped));
+    if let Some(kategori) = kategori {
+        query = query.bind(kategori);
+    }
+    if urutan.is_none() {
+        query = query.bind(keyword).bind(format!("{}%", escaped));
+    }
+    let rows = query.fetch_all(pool).await?;
+
+    let mut products = Vec::with_capacity(rows.len());
+    for row in rows {
+        products.push(row_to_produk(&row)?);
+    }
+    Ok(products)
+}
+
+// Produk yang stoknya sudah di bawah ambang masing-masing; stok_minimum 0 berarti tidak pernah masuk daftar
+pub async fn ambil_produk_stok_rendah(pool: &AnyPool) -> Result<Vec<Produk>, RepositoryError> {
+    let rows = sqlx::query("SELECT id, nama, kategori, CAST(harga as DOUBLE PRECISION) as harga, stok, deskripsi, stok_minimum, harga_modal, berat_gram, panjang_cm, lebar_cm, tinggi_cm, harga_grosir, min_qty_grosir, satuan, presisi_stok, created_by, updated_by FROM produk WHERE stok < stok_minimum ORDER BY stok, id")
+        .fetch_all(pool)
         .await?;
-    
-    match row {
-        Some(row) => Ok(Some(Produk::with_id(
+
+    let mut products = Vec::new();
+    for row in rows {
+        let mut produk = Produk::with_id(
             row.try_get("id")?,
             row.try_get("nama")?,
             row.try_get("kategori")?,
             row.try_get("harga")?,
             row.try_get::<i32, _>("stok")? as u32,
             row.try_get("deskripsi").map_or(None, |v: String| Some(v)),
-        ))),
+        );
+        produk.stok_minimum = row.try_get::<i32, _>("stok_minimum")? as u32;
+        produk.harga_modal = row.try_get::<f64, _>("harga_modal").ok();
+        produk.berat_gram = row.try_get::<i32, _>("berat_gram").ok();
+        produk.panjang_cm = row.try_get::<i32, _>("panjang_cm").ok();
+        produk.lebar_cm = row.try_get::<i32, _>("lebar_cm").ok();
+        produk.tinggi_cm = row.try_get::<i32, _>("tinggi_cm").ok();
+        produk.harga_grosir = row.try_get::<f64, _>("harga_grosir").ok();
+        produk.min_qty_grosir = row.try_get::<i32, _>("min_qty_grosir").ok().map(|v| v.max(0) as u32);
+        produk.satuan = row.try_get::<String, _>("satuan").unwrap_or_else(|_| DEFAULT_SATUAN.to_string());
+        produk.presisi_stok = row.try_get::<i32, _>("presisi_stok").map(|v| v.max(0) as u32).unwrap_or(0);
+        produk.audit = AuditTrail {
+            created_by: row.try_get("created_by").ok(),
+            updated_by: row.try_get("updated_by").ok(),
+        };
+        products.push(produk);
+    }
+
+    Ok(products)
+}
+
+// Semua produk beserta SKU-nya (bisa kosong) untuk export, tanpa paginasi
+pub async fn ambil_produk_untuk_export(pool: &AnyPool) -> Result<Vec<(Option<String>, Produk)>, RepositoryError> {
+    let rows = sqlx::query("SELECT id, sku, nama, kategori, CAST(harga as DOUBLE PRECISION) as harga, stok, satuan, presisi_stok FROM produk ORDER BY id")
+        .fetch_all(pool)
+        .await?;
+
+    let mut products = Vec::new();
+    for row in rows {
+        let mut produk = Produk::with_id(
+            row.try_get("id")?,
+            row.try_get("nama")?,
+            row.try_get("kategori")?,
+            row.try_get("harga")?,
+            row.try_get::<i32, _>("stok")? as u32,
+            None,
+        );
+        produk.satuan = row.try_get::<String, _>("satuan").unwrap_or_else(|_| DEFAULT_SATUAN.to_string());
+        produk.presisi_stok = row.try_get::<i32, _>("presisi_stok").map(|v| v.max(0) as u32).unwrap_or(0);
+        products.push((row.try_get("sku").ok(), produk));
+    }
+
+    Ok(products)
+}
+
+pub async fn ambil_produk_by_id(pool: &AnyPool, id: i64) -> Result<Option<Produk>, RepositoryError> {
+    let row = sqlx::query("SELECT id, nama, kategori, CAST(harga as DOUBLE PRECISION) as harga, stok, deskripsi, stok_minimum, harga_modal, berat_gram, panjang_cm, lebar_cm, tinggi_cm, harga_grosir, min_qty_grosir, satuan, presisi_stok, created_by, updated_by FROM produk WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    
+    match row {
+        Some(row) => {
+            let mut produk = Produk::with_id(
+                row.try_get("id")?,
+                row.try_get("nama")?,
+                row.try_get("kategori")?,
+                row.try_get("harga")?,
+                row.try_get::<i32, _>("stok")? as u32,
+                row.try_get("deskripsi").map_or(None, |v: String| Some(v)),
+            );
+            produk.stok_minimum = row.try_get::<i32, _>("stok_minimum")? as u32;
+            produk.harga_modal = row.try_get::<f64, _>("harga_modal").ok();
+            produk.berat_gram = row.try_get::<i32, _>("berat_gram").ok();
+            produk.panjang_cm = row.try_get::<i32, _>("panjang_cm").ok();
+            produk.lebar_cm = row.try_get::<i32, _>("lebar_cm").ok();
+            produk.tinggi_cm = row.try_get::<i32, _>("tinggi_cm").ok();
+            produk.harga_grosir = row.try_get::<f64, _>("harga_grosir").ok();
+            produk.min_qty_grosir = row.try_get::<i32, _>("min_qty_grosir").ok().map(|v| v.max(0) as u32);
+            produk.satuan = row.try_get::<String, _>("satuan").unwrap_or_else(|_| DEFAULT_SATUAN.to_string());
+            produk.presisi_stok = row.try_get::<i32, _>("presisi_stok").map(|v| v.max(0) as u32).unwrap_or(0);
+            produk.audit = AuditTrail {
+                created_by: row.try_get("created_by").ok(),
+                updated_by: row.try_get("updated_by").ok(),
+            };
+            Ok(Some(produk))
+        },
+        None => Ok(None),
+    }
+}
+
+// Lookup untuk scan barcode; SKU dirapikan dulu seperti saat upsert
+pub async fn ambil_produk_by_sku(pool: &AnyPool, sku: &str) -> Result<Option<Produk>, RepositoryError> {
+    let row = sqlx::query("SELECT id, nama, kategori, CAST(harga as DOUBLE PRECISION) as harga, stok, deskripsi, stok_minimum, harga_modal, berat_gram, panjang_cm, lebar_cm, tinggi_cm, harga_grosir, min_qty_grosir, satuan, presisi_stok, created_by, updated_by FROM produk WHERE sku = $1")
+        .bind(sku.trim())
+        .fetch_optional(pool)
+        .await?;
+
+    match row {
+        Some(row) => {
+            let mut produk = Produk::with_id(
+                row.try_get("id")?,
+                row.try_get("nama")?,
+                row.try_get("kategori")?,
+                row.try_get("harga")?,
+                row.try_get::<i32, _>("stok")? as u32,
+                row.try_get("deskripsi").map_or(None, |v: String| Some(v)),
+            );
+            produk.stok_minimum = row.try_get::<i32, _>("stok_minimum")? as u32;
+            produk.harga_modal = row.try_get::<f64, _>("harga_modal").ok();
+            produk.berat_gram = row.try_get::<i32, _>("berat_gram").ok();
+            produk.panjang_cm = row.try_get::<i32, _>("panjang_cm").ok();
+            produk.lebar_cm = row.try_get::<i32, _>("lebar_cm").ok();
+            produk.tinggi_cm = row.try_get::<i32, _>("tinggi_cm").ok();
+            produk.harga_grosir = row.try_get::<f64, _>("harga_grosir").ok();
+            produk.min_qty_grosir = row.try_get::<i32, _>("min_qty_grosir").ok().map(|v| v.max(0) as u32);
+            produk.satuan = row.try_get::<String, _>("satuan").unwrap_or_else(|_| DEFAULT_SATUAN.to_string());
+            produk.presisi_stok = row.try_get::<i32, _>("presisi_stok").map(|v| v.max(0) as u32).unwrap_or(0);
+            produk.audit = AuditTrail {
+                created_by: row.try_get("created_by").ok(),
+                updated_by: row.try_get("updated_by").ok(),
+            };
+            Ok(Some(produk))
+        },
         None => Ok(None),
     }
 }
 
+// Berat per unit (gram) produk-produk pada `ids`; produk tanpa berat bernilai None,
+// produk yang tidak ada tidak masuk ke map
+pub async fn ambil_berat_produk(conn: &mut AnyConnection, ids: &[i64]) -> Result<HashMap<i64, Option<i32>>, RepositoryError> {
+    let mut berat = HashMap::with_capacity(ids.len());
+    for &id in ids {
+        if berat.contains_key(&id) {
+            continue;
+        }
+        let row = sqlx::query("SELECT berat_gram FROM produk WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await?;
+        if let Some(row) = row {
+            berat.insert(id, row.try_get::<i32, _>("berat_gram").ok());
+        }
+    }
+    Ok(berat)
+}
+
+// Presisi stok produk-produk pada `ids` untuk mengubah jumlah jual ke unit terkecil;
+// produk yang tidak ada tidak masuk ke map
+pub async fn ambil_presisi_stok(conn: &mut AnyConnection, ids: &[i64]) -> Result<HashMap<i64, u32>, RepositoryError> {
+    let mut presisi = HashMap::with_capacity(ids.len());
+    for &id in ids {
+        if presisi.contains_key(&id) {
+            continue;
+        }
+        let row = sqlx::query("SELECT presisi_stok FROM produk WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await?;
+        if let Some(row) = row {
+            presisi.insert(id, row.try_get::<i32, _>("presisi_stok")?.max(0) as u32);
+        }
+    }
+    Ok(presisi)
+}
+
+// Harga eceran dan harga grosir `(harga, min_qty)` produk-produk pada `ids`. Produk yang
+// tidak ditemukan tidak masuk ke map; harga grosir `None` bila salah satu kolomnya kosong
+pub async fn ambil_harga_grosir_produk(conn: &mut AnyConnection, ids: &[i64]) -> Result<HashMap<i64, (f64, Option<(f64, u32)>)>, RepositoryError> {
+    let mut harga_produk = HashMap::with_capacity(ids.len());
+    for &id in ids {
+        if harga_produk.contains_key(&id) {
+            continue;
+        }
+        let row = sqlx::query("SELECT CAST(harga as DOUBLE PRECISION) as harga, harga_grosir, min_qty_grosir FROM produk WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await?;
+        if let Some(row) = row {
+            let harga = row.try_get::<f64, _>("harga")?;
+            let grosir = row.try_get::<f64, _>("harga_grosir").ok()
+                .zip(row.try_get::<i32, _>("min_qty_grosir").ok())
+                .map(|(harga_grosir, min_qty)| (harga_grosir, min_qty.max(0) as u32));
+            harga_produk.insert(id, (harga, grosir));
+        }
+    }
+    Ok(harga_produk)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -71,6 +389,18 @@ mod tests {
                 kategori TEXT NOT NULL,
                 harga REAL NOT NULL,
                 stok INTEGER NOT NULL,
+                stok_minimum INTEGER NOT NULL DEFAULT 0,
+                harga_modal REAL,
+                berat_gram INTEGER,
+                panjang_cm INTEGER,
+                lebar_cm INTEGER,
+                tinggi_cm INTEGER,
+                harga_grosir REAL,
+                min_qty_grosir INTEGER,
+                satuan VARCHAR(20) NOT NULL DEFAULT 'pcs',
+                presisi_stok INTEGER NOT NULL DEFAULT 0,
+                created_by TEXT,
+                updated_by TEXT,
                 deskripsi TEXT
             )
             "#
@@ -358,4 +688,87 @@ mod tests {
             assert_eq!(individual_product.deskripsi, product.deskripsi);
         }
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_ambil_produk_stok_rendah_uses_per_product_threshold() {
+        let db_pool = setup_test_db().await;
+
+        // (nama, stok, stok_minimum)
+        let test_products = vec![
+            ("Semen Gresik", 8, 10),    // di bawah ambang sendiri
+            ("Cat Tembok", 8, 5),       // stok sama, ambang lebih rendah
+            ("Pasir Bangka", 20, 20),   // tepat di ambang, belum rendah
+            ("Paku Beton", 0, 0),       // tanpa ambang tidak pernah masuk daftar
+            ("Keramik Putih", 3, 50),
+        ];
+        for (nama, stok, stok_minimum) in test_products {
+            sqlx::query("INSERT INTO produk (nama, kategori, harga, stok, stok_minimum) VALUES ($1, $2, $3, $4, $5)")
+                .bind(nama)
+                .bind("Bahan Bangunan")
+                .bind(10000.0)
+                .bind(stok)
+                .bind(stok_minimum)
+                .execute(&db_pool)
+                .await
+                .expect("Failed to insert test data");
+        }
+
+        let low_stock = ambil_produk_stok_rendah(&db_pool).await.unwrap();
+        let names: Vec<&str> = low_stock.iter().map(|p| p.nama.as_str()).collect();
+        assert_eq!(names, vec!["Keramik Putih", "Semen Gresik"]);
+        assert_eq!(low_stock[1].stok_minimum, 10);
+
+        let cat = ambil_produk_by_id(&db_pool, 2).await.unwrap().unwrap();
+        assert_eq!(cat.stok_minimum, 5);
+    }
+
+    #[tokio::test]
+    async fn test_cari_produk_orders_exact_then_prefix_then_contains() {
+        let db_pool = setup_test_db().await;
+
+        // (nama, kategori, deskripsi)
+        let test_products = vec![
+            ("Perekat Keramik", "Semen Instan", None),  // hanya cocok di kategori
+            ("Lem Semen Putih", "Perekat", None),       // nama mengandung keyword
+            ("Semen Gresik", "Bahan Bangunan", None),   // nama diawali keyword
+            ("Pasir Bangka", "Bahan Bangunan", Some("Campuran semen")),
+            ("Semen", "Bahan Bangunan", None),          // nama sama persis
+            ("Paku Beton", "Bahan Bangunan", None),
+        ];
+        for (nama, kategori, deskripsi) in test_products {
+            sqlx::query("INSERT INTO produk (nama, kategori, harga, stok, deskripsi) VALUES ($1, $2, $3, $4, $5)")
+                .bind(nama)
+                .bind(kategori)
+                .bind(10000.0)
+                .bind(10)
+                .bind(deskripsi)
+                .execute(&db_pool)
+                .await
+                .expect("Failed to insert test data");
+        }
+
+        let hasil = cari_produk(&db_pool, " SEMEN ", &ProdukFilter::default(), &ListQuery::default()).await.unwrap();
+        let names: Vec<&str> = hasil.iter().map(|p| p.nama.as_str()).collect();
+        assert_eq!(names, vec!["Semen", "Semen Gresik", "Lem Semen Putih", "Perekat Keramik", "Pasir Bangka"]);
+
+        let filter = ProdukFilter { kategori: Some("bahan bangunan"), stok: None };
+        let hasil = cari_produk(&db_pool, "semen", &filter, &ListQuery::default()).await.unwrap();
+        let names: Vec<&str> = hasil.iter().map(|p| p.nama.as_str()).collect();
+        assert_eq!(names, vec!["Semen", "Semen Gresik", "Pasir Bangka"]);
+
+        // `sort` menggantikan urutan relevansi, paginasinya dilakukan di SQL
+        let list = ListQuery { sort: Some("nama_desc".to_string()), page: Some(2), limit: Some(2), ..Default::default() };
+        let hasil = cari_produk(&db_pool, "semen", &ProdukFilter::default(), &list).await.unwrap();
+        let names: Vec<&str> = hasil.iter().map(|p| p.nama.as_str()).collect();
+        assert_eq!(names, vec!["Perekat Keramik", "Pasir Bangka"]);
+
+        let list = ListQuery { sort: Some("nama; DROP TABLE produk".to_string()), ..Default::default() };
+        assert!(matches!(
+            cari_produk(&db_pool, "semen", &ProdukFilter::default(), &list).await,
+            Err(RepositoryError::ValidationError(_))
+        ));
+
+        // `%` dicocokkan sebagai karakter biasa, bukan wildcard
+        assert!(cari_produk(&db_pool, "%", &ProdukFilter::default(), &ListQuery::default()).await.unwrap().is_empty());
+    }
+}
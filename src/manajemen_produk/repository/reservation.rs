@@ -0,0 +1,319 @@
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use sqlx::{AnyConnection, AnyPool, Row};
+use uuid::Uuid;
+use crate::manajemen_produk::model::ProdukReservation;
+use crate::manajemen_produk::model::produk::{jumlah_ke_unit_stok, unit_stok_ke_jumlah};
+use crate::manajemen_produk::repository::dto::RepositoryError;
+use crate::manajemen_produk::repository::update::lock_produk_for_update;
+
+// Format tetap (detik, akhiran Z) supaya perbandingan teks `expires_at` sama dengan urutan waktu
+fn format_waktu(waktu: DateTime<Utc>) -> String {
+    waktu.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+async fn hapus_kedaluwarsa(conn: &mut AnyConnection, now: DateTime<Utc>) -> Result<u64, RepositoryError> {
+    let result = sqlx::query("DELETE FROM produk_reservations WHERE expires_at <= $1")
+        .bind(format_waktu(now))
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+// Stok tersedia dalam unit stok terkecil, beserta `presisi_stok` produk untuk konversi satuan
+async fn stok_tersedia(conn: &mut AnyConnection, produk_id: i64, now: DateTime<Utc>) -> Result<(u32, u32), RepositoryError> {
+    let stok_row = sqlx::query("SELECT stok, presisi_stok FROM produk WHERE id = $1")
+        .bind(produk_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or(RepositoryError::NotFound)?;
+    let stok = stok_row.try_get::<i32, _>("stok")? as i64;
+    let presisi_stok = stok_row.try_get::<i32, _>("presisi_stok")?.max(0) as u32;
+
+    let reserved_row = sqlx::query(
+        "SELECT CAST(COALESCE(SUM(jumlah), 0) AS BIGINT) AS reserved FROM produk_reservations WHERE produk_id = $1 AND expires_at > $2"
+    )
+        .bind(produk_id)
+        .bind(format_waktu(now))
+        .fetch_one(&mut *conn)
+        .await?;
+    let reserved: i64 = reserved_row.try_get("reserved")?;
+
+    Ok(((stok - reserved).max(0) as u32, presisi_stok))
+}
+
+// Membersihkan hold yang sudah lewat `expires_at`
+pub async fn hapus_reservasi_kedaluwarsa(pool: &AnyPool, now: DateTime<Utc>) -> Result<u64, RepositoryError> {
+    let mut conn = pool.acquire().await?;
+    hapus_kedaluwarsa(&mut conn, now).await
+}
+
+// Stok fisik dikurangi reservasi yang masih aktif, dalam satuan produk (mis. 2.5 kg)
+pub async fn available_stock(pool: &AnyPool, produk_id: i64, now: DateTime<Utc>) -> Result<f64, RepositoryError> {
+    let mut conn = pool.acquire().await?;
+    let (tersedia, presisi_stok) = stok_tersedia(&mut conn, produk_id, now).await?;
+    Ok(unit_stok_ke_jumlah(tersedia, presisi_stok))
+}
+
+pub async fn buat_reservasi(
+    pool: &AnyPool,
+    produk_id: i64,
+    jumlah: f64,
+    ttl: Duration,
+    now: DateTime<Utc>,
+) -> Result<ProdukReservation, RepositoryError> {
+    if jumlah <= 0.0 {
+        return Err(RepositoryError::ValidationError("Jumlah reservasi harus lebih dari 0".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    // Baris produk dikunci dulu supaya dua reservasi bersamaan tidak sama-sama melihat
+    // stok tersedia yang lama dan melebihi stok fisik
+    lock_produk_for_update(&mut tx, &[produk_id]).await?;
+    hapus_kedaluwarsa(&mut tx, now).await?;
+    let (tersedia, presisi_stok) = stok_tersedia(&mut tx, produk_id, now).await?;
+    let unit = jumlah_ke_unit_stok(jumlah, presisi_stok).map_err(RepositoryError::ValidationError)?;
+    if unit > tersedia {
+        return Err(RepositoryError::ValidationError(format!(
+            "Stok tersedia tidak mencukupi (tersedia: {}, diminta: {})",
+            unit_stok_ke_jumlah(tersedia, presisi_stok), jumlah
+        )));
+    }
+
+    let reservation = ProdukReservation {
+        id: Uuid::new_v4().to_string(),
+        produk_id,
+        jumlah,
+        expires_at: format_waktu(now + ttl),
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO produk_reservations (id, produk_id, jumlah, expires_at, created_at)
+        VALUES ($1, $2, $3, $4, $5)
+        "#
+    )
+    .bind(&reservation.id)
+    .bind(reservation.produk_id)
+    .bind(unit as i32)
+    .bind(&reservation.expires_at)
+    .bind(format_waktu(now))
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(reservation)
+}
+
+pub async fn hapus_reservasi(pool: &AnyPool, reservation_id: &str) -> Result<bool, RepositoryError> {
+    let result = sqlx::query("DELETE FROM produk_reservations WHERE id = $1")
+        .bind(reservation_id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        Err(RepositoryError::NotFound)
+    } else {
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::any::{AnyPoolOptions, install_default_drivers};
+
+    async fn setup_test_db() -> AnyPool {
+        install_default_drivers();
+        let db_pool = AnyPoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to connect to test DB");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS produk (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                nama TEXT NOT NULL,
+                kategori TEXT NOT NULL,
+                harga REAL NOT NULL,
+                stok INTEGER NOT NULL,
+                stok_minimum INTEGER NOT NULL DEFAULT 0,
+                harga_modal REAL,
+                berat_gram INTEGER,
+                panjang_cm INTEGER,
+                lebar_cm INTEGER,
+                tinggi_cm INTEGER,
+                harga_grosir REAL,
+                min_qty_grosir INTEGER,
+                satuan VARCHAR(20) NOT NULL DEFAULT 'pcs',
+                presisi_stok INTEGER NOT NULL DEFAULT 0,
+                created_by TEXT,
+                updated_by TEXT,
+                deskripsi TEXT
+            )
+            "#
+        )
+        .execute(&db_pool)
+        .await
+        .expect("Failed to create produk table");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS produk_reservations (
+                id TEXT PRIMARY KEY NOT NULL,
+                produk_id INTEGER NOT NULL,
+                jumlah INTEGER NOT NULL,
+                expires_at TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )
+            "#
+        )
+        .execute(&db_pool)
+        .await
+        .expect("Failed to create produk_reservations table");
+
+        db_pool
+    }
+
+    async fn insert_produk(pool: &AnyPool, stok: i32) -> i64 {
+        sqlx::query("INSERT INTO produk (nama, kategori, harga, stok, deskripsi) VALUES ($1, $2, $3, $4, $5)")
+            .bind("Semen Gresik")
+            .bind("Bahan Bangunan")
+            .bind(65000.0)
+            .bind(stok)
+            .bind(None::<String>)
+            .execute(pool)
+            .await
+            .expect("Failed to insert produk");
+
+        sqlx::query_scalar("SELECT MAX(id) FROM produk")
+            .fetch_one(pool)
+            .await
+            .expect("Failed to read produk id")
+    }
+
+    #[tokio::test]
+    async fn test_reserve_reduces_available_stock_not_physical_stock() {
+        let pool = setup_test_db().await;
+        let produk_id = insert_produk(&pool, 5).await;
+        let now = Utc::now();
+
+        let reservation = buat_reservasi(&pool, produk_id, 3.0, Duration::minutes(15), now).await.unwrap();
+        assert_eq!(reservation.jumlah, 3.0);
+        assert_eq!(available_stock(&pool, produk_id, now).await.unwrap(), 2.0);
+
+        let stok: i32 = sqlx::query_scalar("SELECT stok FROM produk WHERE id = $1")
+            .bind(produk_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(stok, 5);
+
+        // Keranjang kedua tidak bisa mengambil lebih dari sisa yang tersedia
+        let result = buat_reservasi(&pool, produk_id, 3.0, Duration::minutes(15), now).await;
+        assert!(matches!(result, Err(RepositoryError::ValidationError(_))));
+
+        hapus_reservasi(&pool, &reservation.id).await.unwrap();
+        assert_eq!(available_stock(&pool, produk_id, now).await.unwrap(), 5.0);
+    }
+
+    #[tokio::test]
+    async fn test_reserve_weighted_produk_in_decimal_satuan() {
+        let pool = setup_test_db().await;
+        let produk_id = insert_produk(&pool, 12500).await;
+        sqlx::query("UPDATE produk SET satuan = 'kg', presisi_stok = 3 WHERE id = $1")
+            .bind(produk_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let now = Utc::now();
+
+        // 12.5 kg tersimpan sebagai 12500 unit gram; reservasi 2.5 kg menahan 2500 unit
+        let reservation = buat_reservasi(&pool, produk_id, 2.5, Duration::minutes(15), now).await.unwrap();
+        assert_eq!(reservation.jumlah, 2.5);
+        assert_eq!(available_stock(&pool, produk_id, now).await.unwrap(), 10.0);
+        let unit: i32 = sqlx::query_scalar("SELECT jumlah FROM produk_reservations WHERE id = $1")
+            .bind(&reservation.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(unit, 2500);
+
+        let result = buat_reservasi(&pool, produk_id, 0.0005, Duration::minutes(15), now).await;
+        assert!(matches!(result, Err(RepositoryError::ValidationError(_))));
+        let result = buat_reservasi(&pool, produk_id, 10.001, Duration::minutes(15), now).await;
+        assert!(matches!(result, Err(RepositoryError::ValidationError(_))));
+        buat_reservasi(&pool, produk_id, 10.0, Duration::minutes(15), now).await.unwrap();
+        assert_eq!(available_stock(&pool, produk_id, now).await.unwrap(), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_reservations_never_exceed_stock() {
+        install_default_drivers();
+        let path = std::env::temp_dir().join(format!("buildingstore_reservasi_{}.db", Uuid::new_v4().simple()));
+        let pool = AnyPoolOptions::new()
+            .max_connections(8)
+            .connect(&format!("sqlite://{}?mode=rwc", path.display()))
+            .await
+            .unwrap();
+        sqlx::migrate!("migrations/test").run(&pool).await.unwrap();
+        let produk_id = 1;
+        sqlx::query("INSERT INTO produk (id, nama, kategori, harga, stok) VALUES ($1, 'Semen Gresik', 'Bahan Bangunan', 65000, 5)")
+            .bind(produk_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let now = Utc::now();
+
+        let attempts = (0..12).map(|_| {
+            let pool = pool.clone();
+            tokio::spawn(async move { buat_reservasi(&pool, produk_id, 1.0, Duration::minutes(15), now).await })
+        });
+        let results: Vec<_> = rocket::futures::future::join_all(attempts)
+            .await
+            .into_iter()
+            .map(|joined| joined.unwrap())
+            .collect();
+
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 5);
+        assert!(results.iter().all(|result| matches!(result, Ok(_) | Err(RepositoryError::ValidationError(_)))));
+        assert_eq!(available_stock(&pool, produk_id, now).await.unwrap(), 0.0);
+
+        pool.close().await;
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_expired_reservation_is_released() {
+        let pool = setup_test_db().await;
+        let produk_id = insert_produk(&pool, 4).await;
+        let now = Utc::now();
+
+        buat_reservasi(&pool, produk_id, 4.0, Duration::minutes(5), now).await.unwrap();
+        assert_eq!(available_stock(&pool, produk_id, now).await.unwrap(), 0.0);
+
+        let later = now + Duration::minutes(6);
+        assert_eq!(available_stock(&pool, produk_id, later).await.unwrap(), 4.0);
+        assert_eq!(hapus_reservasi_kedaluwarsa(&pool, later).await.unwrap(), 1);
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM produk_reservations")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_reserve_unknown_produk_and_release_unknown_reservation() {
+        let pool = setup_test_db().await;
+
+        let result = buat_reservasi(&pool, 999, 1.0, Duration::minutes(5), Utc::now()).await;
+        assert!(matches!(result, Err(RepositoryError::NotFound)));
+
+        let result = hapus_reservasi(&pool, "RSV-TIDAK-ADA").await;
+        assert!(matches!(result, Err(RepositoryError::NotFound)));
+    }
+}
@@ -0,0 +1,213 @@
+use sqlx::{AnyConnection, AnyPool, Row};
+use crate::manajemen_produk::model::Produk;
+use crate::manajemen_produk::model::tag::normalisasi_tag;
+use crate::manajemen_produk::repository::dto::{row_to_produk, RepositoryError};
+
+async fn pastikan_produk_ada(conn: &mut AnyConnection, produk_id: i64) -> Result<(), RepositoryError> {
+    sqlx::query("SELECT id FROM produk WHERE id = $1")
+        .bind(produk_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or(RepositoryError::NotFound)?;
+    Ok(())
+}
+
+async fn tag_milik(conn: &mut AnyConnection, produk_id: i64) -> Result<Vec<String>, RepositoryError> {
+    let rows = sqlx::query("SELECT tag FROM produk_tags WHERE produk_id = $1 ORDER BY tag")
+        .bind(produk_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    rows.iter()
+        .map(|row| row.try_get::<String, _>("tag").map_err(RepositoryError::from))
+        .collect()
+}
+
+// Semua tag milik produk, urut abjad
+pub async fn ambil_tag_produk(pool: &AnyPool, produk_id: i64) -> Result<Vec<String>, RepositoryError> {
+    let mut conn = pool.acquire().await?;
+    pastikan_produk_ada(&mut conn, produk_id).await?;
+    tag_milik(&mut conn, produk_id).await
+}
+
+// Menambahkan tag ke produk; tag yang sudah terpasang tidak dianggap error.
+// Mengembalikan daftar tag produk setelah perubahan.
+pub async fn tambah_tag(pool: &AnyPool, produk_id: i64, tag: &str) -> Result<Vec<String>, RepositoryError> {
+    let tag = normalisasi_tag(tag).map_err(RepositoryError::ValidationError)?;
+    let mut tx = pool.begin().await?;
+
+    pastikan_produk_ada(&mut tx, produk_id).await?;
+    sqlx::query(
+        r#"
+        INSERT INTO produk_tags (produk_id, tag)
+        SELECT $1, $2
+        WHERE NOT EXISTS (SELECT 1 FROM produk_tags WHERE produk_id = $1 AND tag = $2)
+        "#
+    )
+    .bind(produk_id)
+    .bind(&tag)
+    .execute(&mut *tx)
+    .await?;
+
+    let tags = tag_milik(&mut tx, produk_id).await?;
+    tx.commit().await?;
+    Ok(tags)
+}
+
+// Melepas tag dari produk; `NotFound` bila produk tidak ada atau tag tidak terpasang.
+// Mengembalikan daftar tag produk setelah perubahan.
+pub async fn hapus_tag(pool: &AnyPool, produk_id: i64, tag: &str) -> Result<Vec<String>, RepositoryError> {
+    let tag = normalisasi_tag(tag).map_err(RepositoryError::ValidationError)?;
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query("DELETE FROM produk_tags WHERE produk_id = $1 AND tag = $2")
+        .bind(produk_id)
+        .bind(&tag)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(RepositoryError::NotFound);
+    }
+
+    let tags = tag_milik(&mut tx, produk_id).await?;
+    tx.commit().await?;
+    Ok(tags)
+}
+
+// Produk yang memiliki tag tertentu, urut berdasarkan id
+pub async fn ambil_produk_by_tag(pool: &AnyPool, tag: &str) -> Result<Vec<Produk>, RepositoryError> {
+    let tag = normalisasi_tag(tag).map_err(RepositoryError::ValidationError)?;
+    let rows = sqlx::query(
+        r#"
+        SELECT p.id, p.nama, p.kategori, CAST(p.harga as DOUBLE PRECISION) as harga, p.stok, p.deskripsi,
+               p.stok_minimum, p.harga_modal, p.berat_gram, p.panjang_cm, p.lebar_cm, p.tinggi_cm, p.harga_grosir, p.min_qty_grosir, p.satuan, p.presisi_stok, p.created_by, p.updated_by
+        FROM produk p
+        JOIN produk_tags t ON t.produk_id = p.id
+        WHERE t.tag = $1
+        ORDER BY p.id
+        "#
+    )
+    .bind(&tag)
+    .fetch_all(pool)
+    .await?;
+
+    let mut products = Vec::with_capacity(rows.len());
+    for row in rows {
+        products.push(row_to_produk(&row)?);
+    }
+    Ok(products)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::any::{AnyPoolOptions, install_default_drivers};
+
+    async fn setup_test_db() -> AnyPool {
+        install_default_drivers();
+        let db_pool = AnyPoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to connect to test DB");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS produk (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                nama TEXT NOT NULL,
+                kategori TEXT NOT NULL,
+                harga REAL NOT NULL,
+                stok INTEGER NOT NULL,
+                stok_minimum INTEGER NOT NULL DEFAULT 0,
+                harga_modal REAL,
+                berat_gram INTEGER,
+                panjang_cm INTEGER,
+                lebar_cm INTEGER,
+                tinggi_cm INTEGER,
+                harga_grosir REAL,
+                min_qty_grosir INTEGER,
+                satuan VARCHAR(20) NOT NULL DEFAULT 'pcs',
+                presisi_stok INTEGER NOT NULL DEFAULT 0,
+                created_by TEXT,
+                updated_by TEXT,
+                deskripsi TEXT
+            )
+            "#
+        )
+        .execute(&db_pool)
+        .await
+        .expect("Failed to create produk table");
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS produk_tags (
+                produk_id INTEGER NOT NULL,
+                tag TEXT NOT NULL,
+                PRIMARY KEY (produk_id, tag)
+            )
+            "#
+        )
+        .execute(&db_pool)
+        .await
+        .expect("Failed to create produk_tags table");
+
+        db_pool
+    }
+
+    async fn insert_produk(pool: &AnyPool, nama: &str) -> i64 {
+        sqlx::query("INSERT INTO produk (nama, kategori, harga, stok, deskripsi) VALUES ($1, $2, $3, $4, $5)")
+            .bind(nama)
+            .bind("Bahan Bangunan")
+            .bind(65000.0)
+            .bind(10)
+            .bind(None::<String>)
+            .execute(pool)
+            .await
+            .expect("Failed to insert produk");
+
+        let row = sqlx::query("SELECT MAX(id) AS id FROM produk")
+            .fetch_one(pool)
+            .await
+            .expect("Failed to read produk id");
+        row.try_get("id").expect("id column")
+    }
+
+    #[tokio::test]
+    async fn test_tambah_dan_hapus_tag_dinormalisasi_dan_idempoten() {
+        let pool = setup_test_db().await;
+        let semen = insert_produk(&pool, "Semen Gresik").await;
+
+        assert_eq!(tambah_tag(&pool, semen, " Promo ").await.unwrap(), vec!["promo"]);
+        assert_eq!(tambah_tag(&pool, semen, "promo").await.unwrap(), vec!["promo"]);
+        assert_eq!(tambah_tag(&pool, semen, "new-arrival").await.unwrap(), vec!["new-arrival", "promo"]);
+
+        assert!(matches!(tambah_tag(&pool, semen, "  ").await, Err(RepositoryError::ValidationError(_))));
+        assert!(matches!(tambah_tag(&pool, semen, "diskon 50%").await, Err(RepositoryError::ValidationError(_))));
+        assert!(matches!(tambah_tag(&pool, 999, "promo").await, Err(RepositoryError::NotFound)));
+
+        assert_eq!(hapus_tag(&pool, semen, "PROMO").await.unwrap(), vec!["new-arrival"]);
+        assert!(matches!(hapus_tag(&pool, semen, "promo").await, Err(RepositoryError::NotFound)));
+        assert_eq!(ambil_tag_produk(&pool, semen).await.unwrap(), vec!["new-arrival"]);
+    }
+
+    #[tokio::test]
+    async fn test_ambil_produk_by_tag() {
+        let pool = setup_test_db().await;
+        let semen = insert_produk(&pool, "Semen Gresik").await;
+        let cat = insert_produk(&pool, "Cat Tembok").await;
+        insert_produk(&pool, "Paku Beton").await;
+
+        tambah_tag(&pool, cat, "promo").await.unwrap();
+        tambah_tag(&pool, semen, "promo").await.unwrap();
+        tambah_tag(&pool, semen, "new-arrival").await.unwrap();
+
+        let promo = ambil_produk_by_tag(&pool, "Promo").await.unwrap();
+        let names: Vec<&str> = promo.iter().map(|p| p.nama.as_str()).collect();
+        assert_eq!(names, vec!["Semen Gresik", "Cat Tembok"]);
+        assert_eq!(promo[0].kategori, "Bahan Bangunan");
+
+        assert_eq!(ambil_produk_by_tag(&pool, "new-arrival").await.unwrap().len(), 1);
+        assert!(ambil_produk_by_tag(&pool, "clearance").await.unwrap().is_empty());
+    }
+}
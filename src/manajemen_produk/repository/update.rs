@@ -1,16 +1,23 @@
-use crate::manajemen_produk::model::Produk;
-use crate::manajemen_produk::repository::dto::{validate_produk, RepositoryError};
-use sqlx::AnyPool;
+use crate::manajemen_produk::model::{Produk, StokChange};
+use crate::manajemen_produk::model::builder::normalize_kategori;
+use crate::manajemen_produk::model::produk::jumlah_ke_unit_stok;
+use crate::manajemen_produk::repository::dto::{allow_duplicate_nama, cek_duplikat_nama, validate_produk, RepositoryError};
+use crate::common::sql_dialect::DbKind;
+use sqlx::{AnyConnection, AnyPool, Row};
 
 pub async fn update_produk(pool: &AnyPool, id: i64, produk: &Produk) -> Result<bool, RepositoryError> {
     // Validasi input
     validate_produk(produk)?;
+    cek_duplikat_nama(pool, produk, Some(id)).await?;
     
     let result = sqlx::query(
         r#"
         UPDATE produk 
-        SET nama = $1, kategori = $2, harga = $3, stok = $4, deskripsi = $5
-        WHERE id = $6
+        SET nama = $1, kategori = $2, harga = $3, stok = $4, deskripsi = $5, stok_minimum = $6,
+            harga_modal = $7, berat_gram = $8, panjang_cm = $9, lebar_cm = $10, tinggi_cm = $11,
+            harga_grosir = $12, min_qty_grosir = $13, satuan = $14, presisi_stok = $15,
+            updated_by = COALESCE($16, updated_by)
+        WHERE id = $17
         "#
     )
     .bind(&produk.nama)
@@ -18,6 +25,17 @@ pub async fn update_produk(pool: &AnyPool, id: i64, produk: &Produk) -> Result<b
     .bind(produk.harga)
     .bind(produk.stok as i32)
     .bind(&produk.deskripsi)
+    .bind(produk.stok_minimum as i32)
+    .bind(produk.harga_modal)
+    .bind(produk.berat_gram)
+    .bind(produk.panjang_cm)
+    .bind(produk.lebar_cm)
+    .bind(produk.tinggi_cm)
+    .bind(produk.harga_grosir)
+    .bind(produk.min_qty_grosir.map(|min_qty| min_qty as i32))
+    .bind(&produk.satuan)
+    .bind(produk.presisi_stok as i32)
+    .bind(&produk.audit.updated_by)
     .bind(id)
     .execute(pool)
     .await?;
@@ -29,17 +47,135 @@ pub async fn update_produk(pool: &AnyPool, id: i64, produk: &Produk) -> Result<b
     }
 }
 
-pub async fn update_stok(pool: &AnyPool, id: i64, new_stok: u32) -> Result<bool, RepositoryError> {
-    let result = sqlx::query("UPDATE produk SET stok = $1 WHERE id = $2")
-        .bind(new_stok as i32)
+// Cek dan kurangi stok dalam satu UPDATE bersyarat sehingga dua checkout bersamaan tidak
+// bisa sama-sama lolos; menerima koneksi agar bisa dipakai di dalam DB transaction pemanggil.
+// Event stok dari `StokChange` sebaiknya dikirim pemanggil setelah transaction di-commit.
+pub async fn decrement_stok(conn: &mut AnyConnection, id: i64, jumlah: u32) -> Result<StokChange, RepositoryError> {
+    let row = sqlx::query("UPDATE produk SET stok = stok - $1 WHERE id = $2 AND stok >= $1 RETURNING stok")
+        .bind(jumlah as i32)
         .bind(id)
-        .execute(pool)
+        .fetch_optional(&mut *conn)
         .await?;
-    
-    if result.rows_affected() == 0 {
-        Err(RepositoryError::NotFound)
-    } else {
-        Ok(true)
+
+    if let Some(row) = row {
+        let sesudah = row.try_get::<i32, _>("stok")? as u32;
+        return Ok(StokChange::new(id, sesudah + jumlah, sesudah));
+    }
+
+    let exists = sqlx::query("SELECT id FROM produk WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    match exists {
+        Some(_) => Err(RepositoryError::ValidationError(format!("Stok produk dengan ID {} tidak mencukupi", id))),
+        None => Err(RepositoryError::NotFound),
+    }
+}
+
+// Mengunci baris produk sampai transaction pemanggil selesai, sebelum stoknya dikurangi.
+// Perilaku per driver:
+// - PostgreSQL: `SELECT ... FOR UPDATE` per id, diurutkan naik agar dua checkout dengan
+//   produk yang sama selalu mengunci dengan urutan yang sama dan tidak saling deadlock.
+// - SQLite tidak mengenal `FOR UPDATE`; penulisan sudah diserialisasi per database
+//   (satu writer), jadi fungsi ini tidak melakukan apa-apa.
+// Id yang tidak ada dilewati; `decrement_stok` yang melaporkan `NotFound`.
+pub async fn lock_produk_for_update(conn: &mut AnyConnection, ids: &[i64]) -> Result<(), RepositoryError> {
+    if DbKind::of(conn) != DbKind::Postgres {
+        return Ok(());
+    }
+
+    let mut ids = ids.to_vec();
+    ids.sort_unstable();
+    ids.dedup();
+    for id in ids {
+        sqlx::query("SELECT id FROM produk WHERE id = $1 FOR UPDATE")
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+// Kebalikan `decrement_stok`, mis. saat penjualan di-void; `NotFound` bila produk sudah tidak ada.
+pub async fn increment_stok(conn: &mut AnyConnection, id: i64, jumlah: u32) -> Result<StokChange, RepositoryError> {
+    let row = sqlx::query("UPDATE produk SET stok = stok + $1 WHERE id = $2 RETURNING stok")
+        .bind(jumlah as i32)
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or(RepositoryError::NotFound)?;
+
+    let sesudah = row.try_get::<i32, _>("stok")? as u32;
+    Ok(StokChange::new(id, sesudah.saturating_sub(jumlah), sesudah))
+}
+
+// Menimpa stok dengan nilai absolut dalam satuan jual (desimal untuk barang timbangan, lihat
+// `Produk::presisi_stok`); stok lama dibaca dalam transaction yang sama agar perpindahan
+// dari/ke nol bisa dideteksi.
+pub async fn update_stok(pool: &AnyPool, id: i64, new_stok: f64) -> Result<StokChange, RepositoryError> {
+    let mut tx = pool.begin().await?;
+    let row = sqlx::query("SELECT stok, presisi_stok FROM produk WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(RepositoryError::NotFound)?;
+    let sebelum = row.try_get::<i32, _>("stok")? as u32;
+    let presisi_stok = row.try_get::<i32, _>("presisi_stok")?.max(0) as u32;
+    let new_stok = jumlah_ke_unit_stok(new_stok, presisi_stok)
+        .and_then(unit_ke_i32)
+        .map_err(RepositoryError::ValidationError)?;
+
+    sqlx::query("UPDATE produk SET stok = $1 WHERE id = $2")
+        .bind(new_stok)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(StokChange::new(id, sebelum, new_stok as u32))
+}
+
+fn unit_ke_i32(unit: u32) -> Result<i32, String> {
+    i32::try_from(unit).map_err(|_| "Jumlah stok terlalu besar".to_string())
+}
+
+// Penyesuaian relatif (barang masuk/retur) dalam satu UPDATE bersyarat, jadi tidak menimpa
+// penjualan yang terjadi bersamaan seperti `update_stok`. `delta` dalam satuan jual seperti
+// `update_stok`; stok sebelum dihitung dari `delta`.
+pub async fn adjust_stok(pool: &AnyPool, id: i64, delta: f64, updated_by: Option<&str>) -> Result<StokChange, RepositoryError> {
+    let presisi_stok = sqlx::query("SELECT presisi_stok FROM produk WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(RepositoryError::NotFound)?
+        .try_get::<i32, _>("presisi_stok")?
+        .max(0) as u32;
+    let unit = jumlah_ke_unit_stok(delta.abs(), presisi_stok)
+        .and_then(unit_ke_i32)
+        .map_err(RepositoryError::ValidationError)?;
+    let delta = if delta < 0.0 { -unit } else { unit };
+
+    let row = sqlx::query(
+        "UPDATE produk SET stok = stok + $1, updated_by = COALESCE($2, updated_by) WHERE id = $3 AND stok + $1 >= 0 RETURNING stok"
+    )
+        .bind(delta)
+        .bind(updated_by)
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+    if let Some(row) = row {
+        let stok: i32 = row.try_get("stok")?;
+        return Ok(StokChange::new(id, (stok - delta) as u32, stok as u32));
+    }
+
+    let exists = sqlx::query("SELECT id FROM produk WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    match exists {
+        Some(_) => Err(RepositoryError::ValidationError(format!("Stok produk dengan ID {} tidak boleh menjadi negatif", id))),
+        None => Err(RepositoryError::NotFound),
     }
 }
 
@@ -61,11 +197,57 @@ pub async fn update_harga(pool: &AnyPool, id: i64, new_harga: f64) -> Result<boo
     }
 }
 
+// Memindahkan semua produk dari satu kategori ke kategori lain dalam satu UPDATE. Kedua nama
+// dinormalisasi seperti di builder dan `from` dicocokkan tanpa membedakan huruf besar/kecil.
+// Mengembalikan jumlah produk yang dipindahkan.
+pub async fn rename_kategori(pool: &AnyPool, from: &str, to: &str, updated_by: Option<&str>) -> Result<u64, RepositoryError> {
+    let from = normalize_kategori(from);
+    let to = normalize_kategori(to);
+    if from.is_empty() || to.is_empty() {
+        return Err(RepositoryError::ValidationError("Kategori asal dan tujuan tidak boleh kosong".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    // Menggabungkan ke kategori yang sudah ada tidak boleh menghasilkan nama kembar
+    if !allow_duplicate_nama() {
+        let bentrok = sqlx::query(
+            r#"
+            SELECT a.nama FROM produk a
+            JOIN produk b ON LOWER(b.nama) = LOWER(a.nama) AND b.id <> a.id
+            WHERE LOWER(a.kategori) = LOWER($1) AND LOWER(b.kategori) = LOWER($2)
+            LIMIT 1
+            "#
+        )
+        .bind(&from)
+        .bind(&to)
+        .fetch_optional(&mut *tx)
+        .await?;
+        if let Some(row) = bentrok {
+            let nama: String = row.try_get("nama")?;
+            return Err(RepositoryError::ValidationError(format!(
+                "Produk '{}' sudah ada di kategori '{}'", nama, to
+            )));
+        }
+    }
+
+    let result = sqlx::query("UPDATE produk SET kategori = $1, updated_by = COALESCE($2, updated_by) WHERE LOWER(kategori) = LOWER($3)")
+        .bind(&to)
+        .bind(updated_by)
+        .bind(&from)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(result.rows_affected())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use sqlx::{any::{AnyPoolOptions, install_default_drivers}, Row};
     use crate::manajemen_produk::model::Produk;
+    use crate::manajemen_produk::model::produk::DEFAULT_SATUAN;
 
     async fn setup_test_db() -> sqlx::Pool<sqlx::Any> {
         install_default_drivers();
@@ -84,6 +266,18 @@ mod tests {
                 kategori TEXT NOT NULL,
                 harga REAL NOT NULL,
                 stok INTEGER NOT NULL,
+                stok_minimum INTEGER NOT NULL DEFAULT 0,
+                harga_modal REAL,
+                berat_gram INTEGER,
+                panjang_cm INTEGER,
+                lebar_cm INTEGER,
+                tinggi_cm INTEGER,
+                harga_grosir REAL,
+                min_qty_grosir INTEGER,
+                satuan VARCHAR(20) NOT NULL DEFAULT 'pcs',
+                presisi_stok INTEGER NOT NULL DEFAULT 0,
+                created_by TEXT,
+                updated_by TEXT,
                 deskripsi TEXT
             )
             "#
@@ -127,6 +321,17 @@ mod tests {
             harga: 15000000.99,
             stok: 25,
             deskripsi: Some("Updated description for laptop".to_string()),
+            stok_minimum: 0,
+            harga_modal: None,
+            berat_gram: None,
+            panjang_cm: None,
+            lebar_cm: None,
+            tinggi_cm: None,
+            harga_grosir: None,
+            min_qty_grosir: None,
+            satuan: DEFAULT_SATUAN.to_string(),
+            presisi_stok: 0,
+            audit: Default::default(),
         };
 
         let result = update_produk(&db_pool, product_id, &updated_produk).await;
@@ -164,6 +369,17 @@ mod tests {
             harga: 100000.0,
             stok: 10,
             deskripsi: None,
+            stok_minimum: 0,
+            harga_modal: None,
+            berat_gram: None,
+            panjang_cm: None,
+            lebar_cm: None,
+            tinggi_cm: None,
+            harga_grosir: None,
+            min_qty_grosir: None,
+            satuan: DEFAULT_SATUAN.to_string(),
+            presisi_stok: 0,
+            audit: Default::default(),
         };
 
         let result = update_produk(&db_pool, 999, &produk).await;
@@ -175,15 +391,34 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_update_produk_to_duplicate_nama_rejected() {
+        let db_pool = setup_test_db().await;
+        let existing_id = insert_test_produk(&db_pool).await;
+
+        let other = Produk::new("Other Product".to_string(), "Test Category".to_string(), 5000.0, 1, None);
+        let other_id = crate::manajemen_produk::repository::create::tambah_produk(&db_pool, &other)
+            .await
+            .expect("Failed to insert second product");
+
+        let renamed = Produk::with_id(other_id, "Test Product".to_string(), "Test Category".to_string(), 5000.0, 1, None);
+        let result = update_produk(&db_pool, other_id, &renamed).await;
+        assert!(matches!(result, Err(RepositoryError::Conflict(_))));
+
+        // Menyimpan ulang baris yang sama dengan nama miliknya sendiri bukan duplikat
+        let unchanged = Produk::with_id(existing_id, "Test Product".to_string(), "Test Category".to_string(), 120000.0, 40, None);
+        assert!(update_produk(&db_pool, existing_id, &unchanged).await.is_ok());
+    }
+
     #[tokio::test]
     async fn test_update_stok_valid() {
         let db_pool = setup_test_db().await;
         let product_id = insert_test_produk(&db_pool).await;
         
-        let new_stok = 100u32;
+        let new_stok = 100.0;
         let result = update_stok(&db_pool, product_id, new_stok).await;
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), true);
+        assert_eq!(result.unwrap(), StokChange::new(product_id, 50, 100));
 
         // Verify the stock update
         let row = sqlx::query("SELECT stok FROM produk WHERE id = $1")
@@ -200,7 +435,7 @@ mod tests {
     async fn test_update_stok_not_found() {
         let db_pool = setup_test_db().await;
         
-        let result = update_stok(&db_pool, 999, 100).await;
+        let result = update_stok(&db_pool, 999, 100.0).await;
         assert!(result.is_err());
         
         match result.unwrap_err() {
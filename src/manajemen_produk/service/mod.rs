@@ -0,0 +1,3 @@
+pub mod produk_dispatcher;
+pub mod produk_observer;
+pub mod produk_notifier;
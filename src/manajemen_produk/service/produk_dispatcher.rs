@@ -0,0 +1,88 @@
+use std::sync::{Arc, Mutex};
+use futures::future::join_all;
+use async_trait::async_trait;
+use crate::manajemen_produk::model::StokEvent;
+use crate::manajemen_produk::service::produk_notifier::ProdukNotifier;
+use crate::manajemen_produk::service::produk_observer::ProdukObserver;
+
+#[derive(Clone, Default)]
+pub struct ProdukDispatcher {
+    observers: Arc<Mutex<Vec<Arc<dyn ProdukObserver + Send + Sync>>>>,
+}
+
+impl ProdukDispatcher {
+    pub fn new() -> Self {
+        Self {
+            observers: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub fn register(&self, observer: Arc<dyn ProdukObserver + Send + Sync>) {
+        self.observers.lock().unwrap().push(observer);
+    }
+
+    async fn notify_observers_on_stok_event(&self, event: &StokEvent) {
+        let observers_to_notify = self.observers.lock().unwrap().clone();
+
+        if observers_to_notify.is_empty() {
+            return;
+        }
+
+        let futures = observers_to_notify.into_iter()
+            .map(|observer_arc| {
+                async move {
+                    observer_arc.on_stok_event(event).await;
+                }
+            });
+        join_all(futures).await;
+    }
+}
+
+#[async_trait]
+impl ProdukNotifier for ProdukDispatcher {
+    async fn notify_stok_event(&self, event: &StokEvent) {
+        self.notify_observers_on_stok_event(event).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manajemen_produk::model::{StokChange, StokEventKind};
+
+    struct RecordingObserver {
+        events: Arc<Mutex<Vec<StokEvent>>>,
+    }
+
+    #[async_trait]
+    impl ProdukObserver for RecordingObserver {
+        async fn on_stok_event(&self, event: &StokEvent) {
+            self.events.lock().unwrap().push(*event);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_notify_all_registered_observers() {
+        let dispatcher = ProdukDispatcher::new();
+        let notifier: Arc<dyn ProdukNotifier> = Arc::new(dispatcher.clone());
+
+        let first = Arc::new(Mutex::new(Vec::new()));
+        let second = Arc::new(Mutex::new(Vec::new()));
+        dispatcher.register(Arc::new(RecordingObserver { events: first.clone() }));
+        dispatcher.register(Arc::new(RecordingObserver { events: second.clone() }));
+
+        let event = StokChange::new(7, 2, 0).event().unwrap();
+        notifier.notify_stok_event(&event).await;
+
+        assert_eq!(first.lock().unwrap().as_slice(), &[event]);
+        assert_eq!(second.lock().unwrap()[0].kind, StokEventKind::OutOfStock);
+    }
+
+    #[tokio::test]
+    async fn test_notify_no_observers() {
+        let dispatcher = ProdukDispatcher::new();
+        let event = StokChange::new(7, 0, 3).event().unwrap();
+
+        dispatcher.notify_stok_event(&event).await;
+    }
+}
@@ -0,0 +1,16 @@
+use crate::manajemen_produk::model::{StokChange, StokEvent};
+use::async_trait::async_trait;
+use mockall::automock;
+
+#[async_trait]
+#[automock]
+pub trait ProdukNotifier: Send + Sync {
+    async fn notify_stok_event(&self, event: &StokEvent);
+}
+
+// Mengirim event ke subscriber hanya bila stok melewati nol (habis atau tersedia lagi)
+pub async fn notify_stok_change(notifier: &dyn ProdukNotifier, change: &StokChange) {
+    if let Some(event) = change.event() {
+        notifier.notify_stok_event(&event).await;
+    }
+}
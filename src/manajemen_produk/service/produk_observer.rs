@@ -0,0 +1,7 @@
+use crate::manajemen_produk::model::StokEvent;
+use::async_trait::async_trait;
+
+#[async_trait]
+pub trait ProdukObserver: Send + Sync {
+    async fn on_stok_event(&self, event: &StokEvent);
+}
@@ -1,4 +1,5 @@
 use crate::manajemen_produk::model::Produk;
+use crate::manajemen_produk::model::produk::{modal_above_harga_allowed, MAX_PRESISI_STOK};
 
 pub trait ValidationRule {
     fn validate(&self, produk: &Produk) -> Result<(), String>;
@@ -58,6 +59,77 @@ impl ValidationRule for DeskripsiMaxLength {
     }
 }
 
+pub struct HargaModalValid {
+    pub allow_above_harga: bool,
+}
+
+impl HargaModalValid {
+    pub fn from_env() -> Self {
+        Self { allow_above_harga: modal_above_harga_allowed() }
+    }
+}
+
+impl ValidationRule for HargaModalValid {
+    fn validate(&self, produk: &Produk) -> Result<(), String> {
+        match produk.harga_modal {
+            Some(modal) if modal < 0.0 => Err("Harga modal tidak boleh negatif".to_string()),
+            Some(modal) if modal > produk.harga && !self.allow_above_harga => {
+                Err("Harga modal tidak boleh melebihi harga jual".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// Berat dan dimensi boleh kosong, tetapi bila diisi tidak boleh negatif
+pub struct BeratDimensiNonNegatif;
+impl ValidationRule for BeratDimensiNonNegatif {
+    fn validate(&self, produk: &Produk) -> Result<(), String> {
+        if produk.berat_gram.is_some_and(|berat| berat < 0) {
+            return Err("Berat produk tidak boleh negatif".to_string());
+        }
+        let dimensi = [produk.panjang_cm, produk.lebar_cm, produk.tinggi_cm];
+        if dimensi.iter().flatten().any(|ukuran| *ukuran < 0) {
+            return Err("Dimensi produk tidak boleh negatif".to_string());
+        }
+        Ok(())
+    }
+}
+
+// Harga grosir dan jumlah minimalnya diisi berpasangan; harga grosir tidak boleh negatif
+// maupun melebihi harga eceran, dan jumlah minimal harus lebih dari 1
+pub struct HargaGrosirValid;
+impl ValidationRule for HargaGrosirValid {
+    fn validate(&self, produk: &Produk) -> Result<(), String> {
+        match (produk.harga_grosir, produk.min_qty_grosir) {
+            (None, None) => Ok(()),
+            (Some(_), None) | (None, Some(_)) => {
+                Err("Harga grosir dan jumlah minimal grosir harus diisi bersamaan".to_string())
+            }
+            (Some(harga), _) if harga < 0.0 => Err("Harga grosir tidak boleh negatif".to_string()),
+            (Some(harga), _) if harga > produk.harga => {
+                Err("Harga grosir tidak boleh melebihi harga eceran".to_string())
+            }
+            (_, Some(min_qty)) if min_qty < 2 => Err("Jumlah minimal grosir harus lebih dari 1".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Satuan wajib diisi dan presisi stok dibatasi agar stok dalam unit terkecil tetap muat di u32
+pub struct SatuanValid;
+impl ValidationRule for SatuanValid {
+    fn validate(&self, produk: &Produk) -> Result<(), String> {
+        if produk.satuan.trim().is_empty() {
+            return Err("Satuan produk tidak boleh kosong".to_string());
+        }
+        if produk.presisi_stok > MAX_PRESISI_STOK {
+            return Err(format!("Presisi stok maksimal {} digit desimal", MAX_PRESISI_STOK));
+        }
+        Ok(())
+    }
+}
+
 #[test]
 fn test_nama_not_empty() {
     let strategy = NamaNotEmpty;
@@ -160,4 +232,80 @@ fn test_harga_non_negatif_strategy() {
 
     produk.harga = 1000.0;
     assert!(strategy.validate(&produk).is_ok());
-}
\ No newline at end of file
+}
+#[test]
+fn test_harga_modal_valid() {
+    let strategy = HargaModalValid { allow_above_harga: false };
+    let mut produk = Produk::new("Semen".into(), "Material".into(), 50000.0, 10, None);
+    assert!(strategy.validate(&produk).is_ok());
+
+    produk.harga_modal = Some(45000.0);
+    assert!(strategy.validate(&produk).is_ok());
+    produk.harga_modal = Some(50000.0);
+    assert!(strategy.validate(&produk).is_ok());
+
+    produk.harga_modal = Some(55000.0);
+    assert_eq!(strategy.validate(&produk).unwrap_err(), "Harga modal tidak boleh melebihi harga jual");
+    assert!(HargaModalValid { allow_above_harga: true }.validate(&produk).is_ok());
+
+    produk.harga_modal = Some(-1.0);
+    assert_eq!(HargaModalValid { allow_above_harga: true }.validate(&produk).unwrap_err(), "Harga modal tidak boleh negatif");
+}
+
+#[test]
+fn test_berat_dimensi_non_negatif() {
+    let strategy = BeratDimensiNonNegatif;
+    let mut produk = Produk::new("Semen".into(), "Material".into(), 50000.0, 10, None);
+    assert!(strategy.validate(&produk).is_ok());
+
+    produk.berat_gram = Some(0);
+    produk.panjang_cm = Some(60);
+    produk.tinggi_cm = Some(12);
+    assert!(strategy.validate(&produk).is_ok());
+
+    produk.lebar_cm = Some(-1);
+    assert_eq!(strategy.validate(&produk).unwrap_err(), "Dimensi produk tidak boleh negatif");
+
+    produk.berat_gram = Some(-50);
+    assert_eq!(strategy.validate(&produk).unwrap_err(), "Berat produk tidak boleh negatif");
+}
+
+#[test]
+fn test_harga_grosir_valid() {
+    let strategy = HargaGrosirValid;
+    let mut produk = Produk::new("Semen".into(), "Material".into(), 65000.0, 10, None);
+    assert!(strategy.validate(&produk).is_ok());
+
+    produk.harga_grosir = Some(60000.0);
+    assert_eq!(strategy.validate(&produk).unwrap_err(), "Harga grosir dan jumlah minimal grosir harus diisi bersamaan");
+
+    produk.min_qty_grosir = Some(10);
+    assert!(strategy.validate(&produk).is_ok());
+
+    produk.min_qty_grosir = Some(1);
+    assert_eq!(strategy.validate(&produk).unwrap_err(), "Jumlah minimal grosir harus lebih dari 1");
+
+    produk.min_qty_grosir = Some(10);
+    produk.harga_grosir = Some(70000.0);
+    assert_eq!(strategy.validate(&produk).unwrap_err(), "Harga grosir tidak boleh melebihi harga eceran");
+
+    produk.harga_grosir = Some(-1.0);
+    assert_eq!(strategy.validate(&produk).unwrap_err(), "Harga grosir tidak boleh negatif");
+}
+
+#[test]
+fn test_satuan_valid() {
+    let strategy = SatuanValid;
+    let mut produk = Produk::new("Pasir".into(), "Material".into(), 250000.0, 10, None);
+    assert!(strategy.validate(&produk).is_ok());
+
+    produk.satuan = "kg".to_string();
+    produk.presisi_stok = 3;
+    assert!(strategy.validate(&produk).is_ok());
+
+    produk.presisi_stok = 4;
+    assert_eq!(strategy.validate(&produk).unwrap_err(), "Presisi stok maksimal 3 digit desimal");
+
+    produk.satuan = "  ".to_string();
+    assert_eq!(strategy.validate(&produk).unwrap_err(), "Satuan produk tidak boleh kosong");
+}
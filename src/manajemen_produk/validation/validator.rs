@@ -6,6 +6,10 @@ use super::rules::{
     HargaNonNegatif,
     StokNonNegatif,
     DeskripsiMaxLength,
+    HargaModalValid,
+    BeratDimensiNonNegatif,
+    HargaGrosirValid,
+    SatuanValid,
 };
 
 pub struct ProdukValidator {
@@ -21,6 +25,10 @@ impl ProdukValidator {
                 Box::new(HargaNonNegatif),
                 Box::new(StokNonNegatif),
                 Box::new(DeskripsiMaxLength),
+                Box::new(HargaModalValid::from_env()),
+                Box::new(BeratDimensiNonNegatif),
+                Box::new(HargaGrosirValid),
+                Box::new(SatuanValid),
             ],
         }
     }
@@ -1,20 +1,63 @@
 use autometrics::autometrics;
-use rocket::{get, post, put, delete, routes, State, http::Status};
+use rocket::{get, post, put, patch, delete, routes, State, http::Status};
 use rocket::serde::{json::Json, Deserialize, Serialize};
 use sqlx::{Any, Pool};
 use std::sync::Arc;
 
-use crate::manajemen_supplier::model::supplier::Supplier;
-use crate::manajemen_supplier::model::supplier_transaction::SupplierTransaction;
-use crate::manajemen_supplier::service::supplier_service::SupplierService;
+use crate::auth::guards::auth::AuthenticatedUser;
+use crate::common::audit::actor;
+use crate::common::csv::{csv_field, CsvExport};
+use crate::common::messages::{Lang, Messages};
+use crate::common::timestamp;
+use crate::common::service_result::{ServiceError, ServiceResult};
+use crate::manajemen_supplier::model::supplier::{Supplier, SupplierContact, SupplierStats};
+use crate::manajemen_supplier::model::supplier_transaction::{SupplierPerformance, SupplierTransaction};
+use crate::manajemen_supplier::service::supplier_service::{SupplierError, SupplierService};
+use crate::common::pagination::ListQuery;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(crate = "rocket::serde")]
+#[serde(crate = "rocket::serde", rename_all = "snake_case")]
 pub struct SupplierRequest {
     pub name: String,
     pub jenis_barang: String,
     pub jumlah_barang: i32,
     pub resi: String,
+    #[serde(flatten)]
+    pub contact: SupplierContact,
+}
+
+/// Body PATCH: field yang tidak dikirim mempertahankan nilai lama. Kontak dikosongkan
+/// dengan mengirim string kosong.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(crate = "rocket::serde", rename_all = "snake_case")]
+pub struct SupplierPatchRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jenis_barang: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jumlah_barang: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resi: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+impl SupplierPatchRequest {
+    pub fn overlay(self, current: Supplier) -> SupplierRequest {
+        SupplierRequest {
+            name: self.name.unwrap_or(current.name),
+            jenis_barang: self.jenis_barang.unwrap_or(current.jenis_barang),
+            jumlah_barang: self.jumlah_barang.unwrap_or(current.jumlah_barang),
+            resi: self.resi.unwrap_or(current.resi),
+            contact: SupplierContact {
+                phone: self.phone.or(current.contact.phone),
+                email: self.email.or(current.contact.email),
+            },
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -32,36 +75,23 @@ pub async fn save_supplier(
     request_data: Json<SupplierRequest>,
     db_pool: &State<Pool<Any>>,
     service: &State<Arc<dyn SupplierService>>,
-) -> (Status, Json<ApiResponse<Supplier>>) {
-    let jumlah_barang: i32 = if request_data.jumlah_barang < 0 { 0 } else { request_data.jumlah_barang as i32 };
-    match service.inner().save_supplier(
+    user: Option<AuthenticatedUser>,
+    lang: Lang,
+) -> ServiceResult<Supplier, SupplierError> {
+    let result = service.inner().save_supplier(
         db_pool.inner().clone(),
         request_data.name.clone(),
         request_data.jenis_barang.clone(),
-        jumlah_barang,
+        request_data.jumlah_barang,
         request_data.resi.clone(),
-    ).await {
-        Ok(saved_supplier) => {
-            (
-                Status::Created,
-                Json(ApiResponse {
-                    success: true,
-                    message: Some("Supplier created successfully".to_string()),
-                    data: Some(saved_supplier),
-                }),
-            )
-        }
-        Err(service_error_msg) => {
-            (
-                Status::InternalServerError,
-                Json(ApiResponse {
-                    success: false,
-                    message: Some(service_error_msg),
-                    data: None::<Supplier>,
-                }),
-            )
-        }
-    }
+        request_data.contact.clone(),
+        actor(user.as_ref()).map(str::to_string),
+    ).await
+        .map(|mut saved_supplier| {
+            saved_supplier.audit.redact_unless_admin(user.as_ref());
+            saved_supplier
+        });
+    ServiceResult::new(result, Messages::get("supplier.created", lang)).with_status(Status::Created)
 }
 
 #[autometrics]
@@ -70,35 +100,18 @@ pub async fn get_supplier(
     suppliers_id: String,
     db_pool: &State<Pool<Any>>,
     service: &State<Arc<dyn SupplierService>>,
-) -> (Status, Json<ApiResponse<Supplier>>) {
-    match service.inner().get_supplier(db_pool.inner().clone(), &suppliers_id).await {
-        Ok(Some(supplier_model)) => (
-            Status::Ok,
-            Json(ApiResponse {
-                success: true,
-                message: Some("Supplier found successfully.".to_string()),
-                data: Some(supplier_model),
-            }),
-        ),
-        Ok(None) => (
-            Status::NotFound,
-            Json(ApiResponse {
-                success: false,
-                message: Some(format!("Supplier with ID '{suppliers_id}' not found.")),
-                data: None::<Supplier>,
-            }),
-        ),
-        Err(service_error_msg) => {
-            (
-                Status::InternalServerError,
-                Json(ApiResponse {
-                    success: false,
-                    message: Some(service_error_msg),
-                    data: None::<Supplier>,
-                }),
-            )
+    user: Option<AuthenticatedUser>,
+    lang: Lang,
+) -> ServiceResult<Supplier, SupplierError> {
+    let result = match service.inner().get_supplier(db_pool.inner().clone(), &suppliers_id).await {
+        Ok(Some(mut supplier_model)) => {
+            supplier_model.audit.redact_unless_admin(user.as_ref());
+            Ok(supplier_model)
         }
-    }
+        Ok(None) => Err(SupplierError::NotFound(Messages::format("supplier.not_found", lang, &[&suppliers_id]))),
+        Err(service_error_msg) => Err(SupplierError::Internal(service_error_msg)),
+    };
+    ServiceResult::new(result, Messages::get("supplier.found", lang))
 }
 
 #[autometrics]
@@ -108,97 +121,113 @@ pub async fn update_supplier(
     request_data: Json<SupplierRequest>,
     db_pool: &State<Pool<Any>>,
     service: &State<Arc<dyn SupplierService>>,
-) -> (Status, Json<ApiResponse<Supplier>>) {
-    match service.inner().update_supplier(
+    user: Option<AuthenticatedUser>,
+    lang: Lang,
+) -> ServiceResult<Supplier, SupplierError> {
+    replace_supplier(id, request_data.into_inner(), db_pool, service, user, lang).await
+}
+
+/// Memuat supplier saat ini, menimpa field yang dikirim, lalu menyimpan lewat jalur
+/// yang sama dengan PUT sehingga validasinya ikut terpakai.
+#[autometrics]
+#[patch("/suppliers/<id>", format = "json", data = "<request_data>")]
+pub async fn patch_supplier(
+    id: String,
+    request_data: Json<SupplierPatchRequest>,
+    db_pool: &State<Pool<Any>>,
+    service: &State<Arc<dyn SupplierService>>,
+    user: Option<AuthenticatedUser>,
+    lang: Lang,
+) -> ServiceResult<Supplier, SupplierError> {
+    match service.inner().get_supplier(db_pool.inner().clone(), &id).await {
+        Ok(Some(current)) => {
+            let merged = request_data.into_inner().overlay(current);
+            replace_supplier(id, merged, db_pool, service, user, lang).await
+        }
+        Ok(None) => ServiceResult::new(
+            Err(SupplierError::NotFound(Messages::format("supplier.not_found", lang, &[&id]))),
+            "",
+        ),
+        Err(service_error_msg) => ServiceResult::new(Err(SupplierError::Internal(service_error_msg)), ""),
+    }
+}
+
+async fn replace_supplier(
+    id: String,
+    request_data: SupplierRequest,
+    db_pool: &State<Pool<Any>>,
+    service: &State<Arc<dyn SupplierService>>,
+    user: Option<AuthenticatedUser>,
+    lang: Lang,
+) -> ServiceResult<Supplier, SupplierError> {
+    let updated = service.inner().update_supplier(
         db_pool.inner().clone(),
         id.clone(),
         request_data.name.clone(),
         request_data.jenis_barang.clone(),
         request_data.jumlah_barang,
         request_data.resi.clone(),
-    ).await {
-        Ok(()) => {
-            match service.inner().get_supplier(db_pool.inner().clone(), &id).await {
-                Ok(Some(updated_supplier_model)) => {
-                    (
-                        Status::Ok,
-                        Json(ApiResponse {
-                            success: true,
-                            message: Some("Supplier updated successfully.".to_string()),
-                            data: Some(updated_supplier_model),
-                        }),
-                    )
-                }
-                Ok(None) => {
-                    (
-                        Status::NotFound,
-                        Json(ApiResponse {
-                            success: false,
-                            message: Some(format!("Supplier with ID '{id}' not found after update.")),
-                            data: None::<Supplier>,
-                        }),
-                    )
-                }
-                Err(e) => {
-                    (
-                        Status::InternalServerError,
-                        Json(ApiResponse {
-                            success: false,
-                            message: Some(format!("Error fetching supplier after update: {e}")),
-                            data: None::<Supplier>,
-                        }),
-                    )
-                }
+        request_data.contact.clone(),
+        actor(user.as_ref()).map(str::to_string),
+    ).await;
+
+    let result = match updated {
+        Ok(()) => match service.inner().get_supplier(db_pool.inner().clone(), &id).await {
+            Ok(Some(mut updated_supplier_model)) => {
+                updated_supplier_model.audit.redact_unless_admin(user.as_ref());
+                Ok(updated_supplier_model)
             }
-        }
-        Err(service_error_msg) => {
-            let status_code = if service_error_msg.to_lowercase().contains("not found") {
-                Status::NotFound
-            } else {
-                Status::InternalServerError
-            };
-            (
-                status_code,
-                Json(ApiResponse {
-                    success: false,
-                    message: Some(service_error_msg),
-                    data: None::<Supplier>,
-                }),
-            )
-        }
-    }
+            Ok(None) => Err(SupplierError::NotFound(Messages::format("supplier.not_found_after_update", lang, &[&id]))),
+            Err(e) => Err(SupplierError::Internal(Messages::format("supplier.fetch_after_update_failed", lang, &[&e]))),
+        },
+        Err(error) => Err(error),
+    };
+    ServiceResult::new(result, Messages::get("supplier.updated", lang))
 }
 
 #[autometrics]
-#[delete("/suppliers/<id>")]
+#[delete("/suppliers/<id>?<cascade>")]
 pub async fn delete_supplier(
     id: String,
+    cascade: Option<bool>,
+    db_pool: &State<Pool<Any>>,
+    service: &State<Arc<dyn SupplierService>>,
+    lang: Lang,
+) -> ServiceResult<(), SupplierError> {
+    // Default: tolak (409) jika supplier masih punya transaksi, kecuali ?cascade=true
+    let cascade = cascade.unwrap_or(false);
+    let result = service.inner().delete_supplier(db_pool.inner().clone(), &id, cascade).await;
+    ServiceResult::new(result, Messages::format("supplier.deleted", lang, &[&id]))
+}
+
+#[autometrics]
+#[get("/suppliers?<list..>")]
+pub async fn get_all_suppliers(
+    list: ListQuery,
     db_pool: &State<Pool<Any>>,
     service: &State<Arc<dyn SupplierService>>,
-) -> (Status, Json<ApiResponse<()>>) {
-    match service.inner().delete_supplier(db_pool.inner().clone(), &id).await {
-        Ok(()) => {
+    user: Option<AuthenticatedUser>,
+    lang: Lang,
+) -> (Status, Json<ApiResponse<Vec<Supplier>>>) {
+    match service.inner().get_all_suppliers(db_pool.inner().clone(), &list).await {
+        Ok(mut suppliers_vec) => {
+            suppliers_vec.iter_mut().for_each(|supplier| supplier.audit.redact_unless_admin(user.as_ref()));
             (
                 Status::Ok,
                 Json(ApiResponse {
                     success: true,
-                    message: Some(format!("Supplier with ID '{id}' deleted successfully.")),
-                    data: None::<()>,
+                    message: Some(Messages::get("supplier.list_ok", lang).to_string()),
+                    data: Some(suppliers_vec),
                 }),
             )
         }
-        Err(service_error_msg) => {
-            let status_code = if service_error_msg.to_lowercase().contains("not found") {
-                Status::NotFound
-            } else {
-                Status::InternalServerError
-            };
+        Err(error) => {
             (
-                status_code,
+                error.status(),
                 Json(ApiResponse {
                     success: false,
-                    message: Some(service_error_msg),
-                    data: None::<()>,
+                    message: Some(error.to_string()),
+                    data: None::<Vec<Supplier>>,
                 }),
             )
         }
@@ -206,18 +235,19 @@ pub async fn delete_supplier(
 }
 
 #[autometrics]
-#[get("/suppliers")]
-pub async fn get_all_suppliers(
+#[get("/supplier-transactions")]
+pub async fn get_all_supplier_transactions(
     db_pool: &State<Pool<Any>>,
     service: &State<Arc<dyn SupplierService>>,
-) -> (Status, Json<ApiResponse<Vec<Supplier>>>) {
-    match service.inner().get_all_suppliers(db_pool.inner().clone()).await {
-        Ok(suppliers_vec) => (
+    lang: Lang,
+) -> (Status, Json<ApiResponse<Vec<SupplierTransaction>>>) {
+    match service.inner().get_all_supplier_transactions(db_pool.inner().clone()).await {
+        Ok(transactions_vec) => (
             Status::Ok,
             Json(ApiResponse {
                 success: true,
-                message: Some("Suppliers retrieved successfully.".to_string()),
-                data: Some(suppliers_vec),
+                message: Some(Messages::get("supplier.transactions_ok", lang).to_string()),
+                data: Some(transactions_vec),
             }),
         ),
         Err(service_error_msg) => {
@@ -226,7 +256,7 @@ pub async fn get_all_suppliers(
                 Json(ApiResponse {
                     success: false,
                     message: Some(service_error_msg),
-                    data: None::<Vec<Supplier>>,
+                    data: None::<Vec<SupplierTransaction>>,
                 }),
             )
         }
@@ -234,30 +264,143 @@ pub async fn get_all_suppliers(
 }
 
 #[autometrics]
-#[get("/supplier-transactions")]
-pub async fn get_all_supplier_transactions(
+#[post("/suppliers/<keep_id>/merge/<dup_id>")]
+pub async fn merge_suppliers(
+    keep_id: String,
+    dup_id: String,
     db_pool: &State<Pool<Any>>,
     service: &State<Arc<dyn SupplierService>>,
-) -> (Status, Json<ApiResponse<Vec<SupplierTransaction>>>) {
-    match service.inner().get_all_supplier_transactions(db_pool.inner().clone()).await {
-        Ok(transactions_vec) => (
+    lang: Lang,
+) -> ServiceResult<(), SupplierError> {
+    match service.inner().merge_suppliers(db_pool.inner().clone(), &keep_id, &dup_id).await {
+        Ok(moved) => ServiceResult::new(
+            Ok(()),
+            Messages::format("supplier.merged", lang, &[&dup_id, &keep_id, &moved]),
+        ),
+        Err(error) => ServiceResult::new(Err(error), ""),
+    }
+}
+
+#[autometrics]
+#[post("/supplier-transactions/<id>/void")]
+pub async fn void_supplier_transaction(
+    id: String,
+    db_pool: &State<Pool<Any>>,
+    service: &State<Arc<dyn SupplierService>>,
+    lang: Lang,
+) -> ServiceResult<SupplierTransaction, SupplierError> {
+    let result = service.inner().void_supplier_transaction(db_pool.inner().clone(), &id).await;
+    ServiceResult::new(result, Messages::format("supplier.transaction_voided", lang, &[&id]))
+}
+
+#[autometrics]
+#[get("/suppliers/<id>/performance")]
+pub async fn get_supplier_performance(
+    id: String,
+    db_pool: &State<Pool<Any>>,
+    service: &State<Arc<dyn SupplierService>>,
+    lang: Lang,
+) -> (Status, Json<ApiResponse<SupplierPerformance>>) {
+    match service.inner().get_supplier_performance(db_pool.inner().clone(), &id).await {
+        Ok(Some(performance)) => (
             Status::Ok,
             Json(ApiResponse {
                 success: true,
-                message: Some("Supplier transactions retrieved successfully.".to_string()),
-                data: Some(transactions_vec),
+                message: Some(Messages::get("supplier.performance_ok", lang).to_string()),
+                data: Some(performance),
+            }),
+        ),
+        Ok(None) => (
+            Status::NotFound,
+            Json(ApiResponse {
+                success: false,
+                message: Some(Messages::format("supplier.not_found", lang, &[&id])),
+                data: None::<SupplierPerformance>,
+            }),
+        ),
+        Err(service_error_msg) => (
+            Status::InternalServerError,
+            Json(ApiResponse {
+                success: false,
+                message: Some(service_error_msg),
+                data: None::<SupplierPerformance>,
+            }),
+        ),
+    }
+}
+
+/// Isi CSV transaksi supplier: satu baris per transaksi, termasuk yang sudah di-void.
+pub fn supplier_transactions_csv(transactions: &[SupplierTransaction]) -> String {
+    let mut csv = String::from("id,tanggal,jenis_barang,jumlah_barang,pengiriman_info\n");
+    for transaction in transactions {
+        csv.push_str(&format!(
+            "{},{},{},{},{}\n",
+            csv_field(&transaction.id),
+            timestamp::format(&transaction.tanggal_transaksi),
+            csv_field(&transaction.jenis_barang),
+            transaction.jumlah_barang,
+            csv_field(&transaction.pengiriman_info),
+        ));
+    }
+    csv
+}
+
+#[autometrics]
+#[get("/suppliers/<id>/transactions/export.csv")]
+pub async fn export_supplier_transactions_csv(
+    id: String,
+    db_pool: &State<Pool<Any>>,
+    service: &State<Arc<dyn SupplierService>>,
+    lang: Lang,
+) -> Result<CsvExport, (Status, Json<ApiResponse<()>>)> {
+    match service.inner().get_supplier_transactions(db_pool.inner().clone(), &id).await {
+        Ok(Some(transactions)) => Ok(CsvExport::attachment(
+            supplier_transactions_csv(&transactions),
+            &format!("supplier-{id}-transactions.csv"),
+        )),
+        Ok(None) => Err((
+            Status::NotFound,
+            Json(ApiResponse {
+                success: false,
+                message: Some(Messages::format("supplier.not_found", lang, &[&id])),
+                data: None,
+            }),
+        )),
+        Err(service_error_msg) => Err((
+            Status::InternalServerError,
+            Json(ApiResponse {
+                success: false,
+                message: Some(service_error_msg),
+                data: None,
+            }),
+        )),
+    }
+}
+
+#[autometrics]
+#[get("/suppliers/stats")]
+pub async fn get_supplier_stats(
+    db_pool: &State<Pool<Any>>,
+    service: &State<Arc<dyn SupplierService>>,
+    lang: Lang,
+) -> (Status, Json<ApiResponse<SupplierStats>>) {
+    match service.inner().get_supplier_stats(db_pool.inner().clone()).await {
+        Ok(stats) => (
+            Status::Ok,
+            Json(ApiResponse {
+                success: true,
+                message: Some(Messages::get("supplier.stats_ok", lang).to_string()),
+                data: Some(stats),
+            }),
+        ),
+        Err(service_error_msg) => (
+            Status::InternalServerError,
+            Json(ApiResponse {
+                success: false,
+                message: Some(service_error_msg),
+                data: None::<SupplierStats>,
             }),
         ),
-        Err(service_error_msg) => {
-            (
-                Status::InternalServerError,
-                Json(ApiResponse {
-                    success: false,
-                    message: Some(service_error_msg),
-                    data: None::<Vec<SupplierTransaction>>,
-                }),
-            )
-        }
     }
 }
 
@@ -266,9 +409,15 @@ pub fn supplier_routes() -> Vec<rocket::Route> {
         save_supplier,
         get_supplier,
         update_supplier,
+        patch_supplier,
         delete_supplier,
         get_all_suppliers,
-        get_all_supplier_transactions
+        get_all_supplier_transactions,
+        merge_suppliers,
+        void_supplier_transaction,
+        get_supplier_performance,
+        export_supplier_transactions_csv,
+        get_supplier_stats
     ]
 }
 
@@ -276,7 +425,7 @@ pub fn supplier_routes() -> Vec<rocket::Route> {
 mod tests {
     use super::*;
     use rocket::local::asynchronous::Client;
-    use rocket::http::Status;
+    use rocket::http::{Header, Status};
     use rocket::{uri, Rocket, async_test};
     use sqlx::any::{install_default_drivers, AnyPoolOptions};
     use std::sync::Arc;
@@ -312,7 +461,8 @@ mod tests {
             jenis_barang: supplier.jenis_barang.clone(),
             jumlah_barang: supplier.jumlah_barang,
             pengiriman_info: format!("Integ Test Info for {supplier_resi}", supplier_resi = supplier.resi),
-            tanggal_transaksi: Utc::now().to_rfc3339(),
+            tanggal_transaksi: Utc::now(),
+            voided: false,
         }
     }
 
@@ -353,9 +503,15 @@ async fn setup_rocket_instance_for_supplier_tests() -> Rocket<rocket::Build> {
             save_supplier,
             get_supplier,
             update_supplier,
+            patch_supplier,
             delete_supplier,
             get_all_suppliers,
-            get_all_supplier_transactions
+            get_all_supplier_transactions,
+            merge_suppliers,
+            void_supplier_transaction,
+            get_supplier_performance,
+            export_supplier_transactions_csv,
+            get_supplier_stats
         ])
 }
 
@@ -365,6 +521,7 @@ async fn setup_rocket_instance_for_supplier_tests() -> Rocket<rocket::Build> {
             jenis_barang: "Integration Goods".to_string(),
             jumlah_barang: 150,
             resi: format!("INTEG-RESI-{name_suffix}"),
+            contact: SupplierContact::default(),
         }
     }
 
@@ -401,18 +558,99 @@ async fn setup_rocket_instance_for_supplier_tests() -> Rocket<rocket::Build> {
         assert_eq!(fetched_supplier.jenis_barang, create_req.jenis_barang);
     }
 
+    #[async_test]
+    async fn test_integ_create_supplier_rejects_negative_jumlah_barang() {
+        let rocket_instance = setup_rocket_instance_for_supplier_tests().await;
+        let client = Client::tracked(rocket_instance).await.expect("Valid Rocket instance");
+
+        let mut create_req = sample_supplier_request("NegativeQty");
+        create_req.jumlah_barang = -5;
+
+        let post_response = client.post(uri!(save_supplier))
+            .json(&create_req)
+            .dispatch()
+            .await;
+
+        assert_eq!(post_response.status(), Status::BadRequest);
+        let post_api_resp = deserialize_response_body::<Supplier>(post_response).await;
+        assert!(!post_api_resp.success);
+        assert!(post_api_resp.data.is_none());
+        assert!(post_api_resp.message.unwrap().contains("jumlah_barang"));
+
+        // Tidak ada supplier yang tersimpan dengan jumlah dinolkan
+        let list_response = client.get("/suppliers").dispatch().await;
+        let list_api_resp = deserialize_response_body::<Vec<Supplier>>(list_response).await;
+        assert!(list_api_resp.data.unwrap_or_default().iter().all(|s| s.name != create_req.name));
+    }
+
+    #[async_test]
+    async fn test_integ_supplier_contact_is_persisted_and_clearable() {
+        let rocket_instance = setup_rocket_instance_for_supplier_tests().await;
+        let client = Client::tracked(rocket_instance).await.expect("Valid Rocket instance");
+
+        let mut create_req = sample_supplier_request("Contact");
+        create_req.contact = SupplierContact {
+            phone: Some("0812-3456-7890".to_string()),
+            email: Some("sales@contact.co.id".to_string()),
+        };
+        let post_response = client.post(uri!(save_supplier)).json(&create_req).dispatch().await;
+        assert_eq!(post_response.status(), Status::Created);
+        let created = deserialize_response_body::<Supplier>(post_response).await.data.unwrap();
+
+        let fetched = deserialize_response_body::<Supplier>(
+            client.get(uri!(get_supplier(suppliers_id = created.id.clone()))).dispatch().await
+        ).await.data.unwrap();
+        assert_eq!(fetched.contact, create_req.contact);
+
+        // PUT mengganti seluruh data, jadi kontak yang tidak dikirim menjadi null
+        let update_req = sample_supplier_request("ContactCleared");
+        let put_response = client.put(uri!(update_supplier(id = created.id.clone()))).json(&update_req).dispatch().await;
+        assert_eq!(put_response.status(), Status::Ok);
+        let body = put_response.into_string().await.unwrap();
+        assert!(!body.contains("\"phone\"") && !body.contains("\"email\""));
+
+        let fetched = deserialize_response_body::<Supplier>(
+            client.get(uri!(get_supplier(suppliers_id = created.id))).dispatch().await
+        ).await.data.unwrap();
+        assert_eq!(fetched.contact, SupplierContact::default());
+    }
+
+    #[async_test]
+    async fn test_integ_create_supplier_rejects_invalid_email() {
+        let rocket_instance = setup_rocket_instance_for_supplier_tests().await;
+        let client = Client::tracked(rocket_instance).await.expect("Valid Rocket instance");
+
+        let mut create_req = sample_supplier_request("InvalidEmail");
+        create_req.contact.email = Some("sales-at-contact.co.id".to_string());
+
+        let post_response = client.post(uri!(save_supplier)).json(&create_req).dispatch().await;
+        assert_eq!(post_response.status(), Status::BadRequest);
+        let post_api_resp = deserialize_response_body::<Supplier>(post_response).await;
+        assert!(!post_api_resp.success);
+        assert!(post_api_resp.message.unwrap().contains("email"));
+    }
+
     #[async_test]
     async fn test_integ_get_supplier_not_found() {
         let rocket_instance = setup_rocket_instance_for_supplier_tests().await;
         let client = Client::tracked(rocket_instance).await.expect("Valid Rocket instance");
 
         let non_existent_id = format!("SUP-INTEG-{}", Uuid::new_v4()); // Expression, not a simple var
-        let response = client.get(uri!(get_supplier(suppliers_id = non_existent_id))).dispatch().await;
+        let response = client.get(uri!(get_supplier(suppliers_id = non_existent_id.clone()))).dispatch().await;
 
         assert_eq!(response.status(), Status::NotFound);
         let api_resp = deserialize_response_body::<Supplier>(response).await;
         assert!(!api_resp.success);
-        assert!(api_resp.message.is_some() && api_resp.message.unwrap().contains("not found"));
+        assert!(api_resp.message.is_some() && api_resp.message.unwrap().contains("tidak ditemukan"));
+
+        let response = client
+            .get(uri!(get_supplier(suppliers_id = non_existent_id)))
+            .header(Header::new("Accept-Language", "en-US,en;q=0.9"))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+        let api_resp = deserialize_response_body::<Supplier>(response).await;
+        assert!(api_resp.message.unwrap().contains("not found"));
     }
 
 
@@ -432,6 +670,7 @@ async fn setup_rocket_instance_for_supplier_tests() -> Rocket<rocket::Build> {
             jenis_barang: "Updated Goods".to_string(),
             jumlah_barang: 200,
             resi: "UPDATED-RESI-001".to_string(),
+            contact: SupplierContact::default(),
         };
         let update_response = client.put(uri!(update_supplier(id = supplier_id_to_update.clone())))
             .json(&update_payload)
@@ -453,6 +692,59 @@ async fn setup_rocket_instance_for_supplier_tests() -> Rocket<rocket::Build> {
         assert_eq!(fetched_supplier.jumlah_barang, 200);
     }
 
+    #[async_test]
+    async fn test_integ_patch_supplier_name_only() {
+        let rocket_instance = setup_rocket_instance_for_supplier_tests().await;
+        let client = Client::tracked(rocket_instance).await.expect("Valid Rocket instance");
+
+        let mut create_req = sample_supplier_request("PatchName");
+        create_req.contact.phone = Some("0812-3456-7890".to_string());
+        let created = deserialize_response_body::<Supplier>(
+            client.post(uri!(save_supplier)).json(&create_req).dispatch().await
+        ).await.data.unwrap();
+
+        let patch = SupplierPatchRequest { name: Some("Patched Name".to_string()), ..Default::default() };
+        let response = client.patch(uri!(patch_supplier(id = created.id.clone()))).json(&patch).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let patched = deserialize_response_body::<Supplier>(response).await.data.unwrap();
+        assert_eq!(patched.name, "Patched Name");
+        assert_eq!(patched.jenis_barang, create_req.jenis_barang);
+        assert_eq!(patched.jumlah_barang, create_req.jumlah_barang);
+        assert_eq!(patched.resi, create_req.resi);
+        assert_eq!(patched.contact, create_req.contact);
+    }
+
+    #[async_test]
+    async fn test_integ_patch_supplier_resi_only_and_validation() {
+        let rocket_instance = setup_rocket_instance_for_supplier_tests().await;
+        let client = Client::tracked(rocket_instance).await.expect("Valid Rocket instance");
+
+        let create_req = sample_supplier_request("PatchResi");
+        let created = deserialize_response_body::<Supplier>(
+            client.post(uri!(save_supplier)).json(&create_req).dispatch().await
+        ).await.data.unwrap();
+
+        let response = client.patch(uri!(patch_supplier(id = created.id.clone())))
+            .json(&rocket::serde::json::json!({ "resi": "RESI-PATCHED-01" }))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+
+        let fetched = deserialize_response_body::<Supplier>(
+            client.get(uri!(get_supplier(suppliers_id = created.id.clone()))).dispatch().await
+        ).await.data.unwrap();
+        assert_eq!(fetched.resi, "RESI-PATCHED-01");
+        assert_eq!(fetched.name, create_req.name);
+        assert_eq!(fetched.jumlah_barang, create_req.jumlah_barang);
+
+        let invalid = SupplierPatchRequest { jumlah_barang: Some(-1), ..Default::default() };
+        let response = client.patch(uri!(patch_supplier(id = created.id.clone()))).json(&invalid).dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest);
+
+        let missing = client.patch(uri!(patch_supplier(id = "SUP-MISSING"))).json(&SupplierPatchRequest::default()).dispatch().await;
+        assert_eq!(missing.status(), Status::NotFound);
+    }
+
     #[async_test]
     async fn test_integ_delete_supplier() {
         let rocket_instance = setup_rocket_instance_for_supplier_tests().await;
@@ -464,11 +756,11 @@ async fn setup_rocket_instance_for_supplier_tests() -> Rocket<rocket::Build> {
         let created_supplier = deserialize_response_body::<Supplier>(post_response).await.data.unwrap();
         let supplier_id_to_delete = created_supplier.id.clone();
 
-        let delete_response = client.delete(uri!(delete_supplier(id = supplier_id_to_delete.clone()))).dispatch().await;
+        let delete_response = client.delete(uri!(delete_supplier(id = supplier_id_to_delete.clone(), cascade = _))).dispatch().await;
         assert_eq!(delete_response.status(), Status::Ok);
         let delete_api_resp = deserialize_response_body::<()>(delete_response).await;
         assert!(delete_api_resp.success);
-        assert!(delete_api_resp.message.unwrap().contains("deleted successfully"));
+        assert!(delete_api_resp.message.unwrap().contains("berhasil dihapus"));
 
         let get_response_after_delete = client.get(uri!(get_supplier(suppliers_id = supplier_id_to_delete))).dispatch().await;
         assert_eq!(get_response_after_delete.status(), Status::NotFound);
@@ -479,7 +771,7 @@ async fn setup_rocket_instance_for_supplier_tests() -> Rocket<rocket::Build> {
         let rocket_instance = setup_rocket_instance_for_supplier_tests().await;
         let client = Client::tracked(rocket_instance).await.expect("Valid Rocket instance");
 
-        let response = client.get(uri!(get_all_suppliers)).dispatch().await;
+        let response = client.get("/suppliers").dispatch().await;
         assert_eq!(response.status(), Status::Ok);
 
         let api_resp = deserialize_response_body::<Vec<Supplier>>(response).await;
@@ -503,7 +795,7 @@ async fn setup_rocket_instance_for_supplier_tests() -> Rocket<rocket::Build> {
         assert_eq!(resp2.status(), Status::Created);
         let supplier2_id = deserialize_response_body::<Supplier>(resp2).await.data.unwrap().id;
 
-        let response = client.get(uri!(get_all_suppliers)).dispatch().await;
+        let response = client.get("/suppliers").dispatch().await;
         assert_eq!(response.status(), Status::Ok);
 
         let api_resp = deserialize_response_body::<Vec<Supplier>>(response).await;
@@ -513,6 +805,18 @@ async fn setup_rocket_instance_for_supplier_tests() -> Rocket<rocket::Build> {
 
         assert!(suppliers.iter().any(|s| s.id == supplier1_id));
         assert!(suppliers.iter().any(|s| s.id == supplier2_id));
+
+        let response = client.get("/suppliers?sort=name_desc&limit=1").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let page = deserialize_response_body::<Vec<Supplier>>(response).await.data.expect("Data should be present");
+        assert_eq!(page.iter().map(|s| s.id.clone()).collect::<Vec<_>>(), vec![supplier2_id]);
+
+        let response = client.get("/suppliers?keyword=getall1").dispatch().await;
+        let found = deserialize_response_body::<Vec<Supplier>>(response).await.data.expect("Data should be present");
+        assert_eq!(found.iter().map(|s| s.id.clone()).collect::<Vec<_>>(), vec![supplier1_id]);
+
+        let response = client.get("/suppliers?sort=resi").dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest);
     }
 
     #[async_test]
@@ -565,4 +869,309 @@ async fn setup_rocket_instance_for_supplier_tests() -> Rocket<rocket::Build> {
         assert!(transactions.iter().any(|t| t.id == transaction2_id));
         assert!(transactions.iter().all(|t| t.supplier_id == created_supplier.id));
     }
-}
\ No newline at end of file
+
+    #[async_test]
+    async fn test_integ_delete_supplier_with_transactions_is_blocked_by_default() {
+        let rocket_instance_build = setup_rocket_instance_for_supplier_tests().await;
+        let db_pool_for_seeding = rocket_instance_build.state::<Pool<Any>>().unwrap().clone();
+        let client = Client::tracked(rocket_instance_build).await.expect("Valid Rocket instance");
+
+        let post_response = client.post(uri!(save_supplier)).json(&sample_supplier_request("DeleteBlocked")).dispatch().await;
+        assert_eq!(post_response.status(), Status::Created);
+        let created_supplier = deserialize_response_body::<Supplier>(post_response).await.data.unwrap();
+
+        let conn = db_pool_for_seeding.acquire().await.unwrap();
+        SupplierTransactionRepositoryImpl::new()
+            .save(create_test_transaction_model(&created_supplier), conn)
+            .await
+            .expect("Failed to save transaction for test");
+
+        let delete_response = client.delete(uri!(delete_supplier(id = created_supplier.id.clone(), cascade = _))).dispatch().await;
+        assert_eq!(delete_response.status(), Status::Conflict);
+        let delete_api_resp = deserialize_response_body::<()>(delete_response).await;
+        assert!(!delete_api_resp.success);
+        assert!(delete_api_resp.message.unwrap().contains("cascade=true"));
+
+        let get_response = client.get(uri!(get_supplier(suppliers_id = created_supplier.id.clone()))).dispatch().await;
+        assert_eq!(get_response.status(), Status::Ok);
+
+        let trx_response = client.get(uri!(get_all_supplier_transactions)).dispatch().await;
+        let transactions = deserialize_response_body::<Vec<SupplierTransaction>>(trx_response).await.data.unwrap();
+        assert_eq!(transactions.len(), 1);
+    }
+
+    #[async_test]
+    async fn test_integ_delete_supplier_with_transactions_cascade() {
+        let rocket_instance_build = setup_rocket_instance_for_supplier_tests().await;
+        let db_pool_for_seeding = rocket_instance_build.state::<Pool<Any>>().unwrap().clone();
+        let client = Client::tracked(rocket_instance_build).await.expect("Valid Rocket instance");
+
+        let post_response = client.post(uri!(save_supplier)).json(&sample_supplier_request("DeleteCascade")).dispatch().await;
+        assert_eq!(post_response.status(), Status::Created);
+        let created_supplier = deserialize_response_body::<Supplier>(post_response).await.data.unwrap();
+
+        let transaction_repo_direct = SupplierTransactionRepositoryImpl::new();
+        for _ in 0..2 {
+            let conn = db_pool_for_seeding.acquire().await.unwrap();
+            transaction_repo_direct
+                .save(create_test_transaction_model(&created_supplier), conn)
+                .await
+                .expect("Failed to save transaction for test");
+        }
+
+        let delete_response = client.delete(uri!(delete_supplier(id = created_supplier.id.clone(), cascade = Some(true)))).dispatch().await;
+        assert_eq!(delete_response.status(), Status::Ok);
+        assert!(deserialize_response_body::<()>(delete_response).await.success);
+
+        let get_response = client.get(uri!(get_supplier(suppliers_id = created_supplier.id.clone()))).dispatch().await;
+        assert_eq!(get_response.status(), Status::NotFound);
+
+        let trx_response = client.get(uri!(get_all_supplier_transactions)).dispatch().await;
+        let transactions = deserialize_response_body::<Vec<SupplierTransaction>>(trx_response).await.data.unwrap();
+        assert!(transactions.is_empty());
+    }
+
+    #[async_test]
+    async fn test_integ_merge_suppliers_moves_transactions() {
+        let rocket_instance_build = setup_rocket_instance_for_supplier_tests().await;
+        let db_pool_for_seeding = rocket_instance_build.state::<Pool<Any>>().unwrap().clone();
+        let client = Client::tracked(rocket_instance_build).await.expect("Valid Rocket instance");
+
+        let keep_resp = client.post(uri!(save_supplier)).json(&sample_supplier_request("MergeKeep")).dispatch().await;
+        let keep = deserialize_response_body::<Supplier>(keep_resp).await.data.unwrap();
+        let dup_resp = client.post(uri!(save_supplier)).json(&sample_supplier_request("MergeDup")).dispatch().await;
+        let dup = deserialize_response_body::<Supplier>(dup_resp).await.data.unwrap();
+
+        let transaction_repo_direct = SupplierTransactionRepositoryImpl::new();
+        for supplier in [&keep, &dup, &dup] {
+            let conn = db_pool_for_seeding.acquire().await.unwrap();
+            transaction_repo_direct
+                .save(create_test_transaction_model(supplier), conn)
+                .await
+                .expect("Failed to save transaction for test");
+        }
+
+        let merge_response = client
+            .post(uri!(merge_suppliers(keep_id = keep.id.clone(), dup_id = dup.id.clone())))
+            .header(Header::new("Accept-Language", "en"))
+            .dispatch()
+            .await;
+        assert_eq!(merge_response.status(), Status::Ok);
+        let merge_api_resp = deserialize_response_body::<()>(merge_response).await;
+        assert!(merge_api_resp.success);
+        assert!(merge_api_resp.message.unwrap().contains("2 transaction(s) moved"));
+
+        let get_dup_response = client.get(uri!(get_supplier(suppliers_id = dup.id.clone()))).dispatch().await;
+        assert_eq!(get_dup_response.status(), Status::NotFound);
+
+        let trx_response = client.get(uri!(get_all_supplier_transactions)).dispatch().await;
+        let transactions = deserialize_response_body::<Vec<SupplierTransaction>>(trx_response).await.data.unwrap();
+        assert_eq!(transactions.len(), 3);
+        assert!(transactions.iter().all(|t| t.supplier_id == keep.id && t.supplier_name == keep.name));
+    }
+
+    #[async_test]
+    async fn test_integ_merge_suppliers_validation() {
+        let rocket_instance = setup_rocket_instance_for_supplier_tests().await;
+        let client = Client::tracked(rocket_instance).await.expect("Valid Rocket instance");
+
+        let keep_resp = client.post(uri!(save_supplier)).json(&sample_supplier_request("MergeSelf")).dispatch().await;
+        let keep = deserialize_response_body::<Supplier>(keep_resp).await.data.unwrap();
+
+        let self_merge = client.post(uri!(merge_suppliers(keep_id = keep.id.clone(), dup_id = keep.id.clone()))).dispatch().await;
+        assert_eq!(self_merge.status(), Status::BadRequest);
+
+        let missing_dup = client.post(uri!(merge_suppliers(keep_id = keep.id.clone(), dup_id = "SUP-MISSING"))).dispatch().await;
+        assert_eq!(missing_dup.status(), Status::NotFound);
+
+        let get_keep = client.get(uri!(get_supplier(suppliers_id = keep.id.clone()))).dispatch().await;
+        assert_eq!(get_keep.status(), Status::Ok);
+    }
+
+    #[async_test]
+    async fn test_integ_void_supplier_transaction_reverses_stock() {
+        let rocket_instance_build = setup_rocket_instance_for_supplier_tests().await;
+        let db_pool_for_seeding = rocket_instance_build.state::<Pool<Any>>().unwrap().clone();
+        let client = Client::tracked(rocket_instance_build).await.expect("Valid Rocket instance");
+
+        let create_resp = client.post(uri!(save_supplier)).json(&sample_supplier_request("Void")).dispatch().await;
+        let supplier = deserialize_response_body::<Supplier>(create_resp).await.data.unwrap();
+        assert_eq!(supplier.jumlah_barang, 150);
+
+        let mut delivery = create_test_transaction_model(&supplier);
+        delivery.jumlah_barang = 40;
+        let conn = db_pool_for_seeding.acquire().await.unwrap();
+        SupplierTransactionRepositoryImpl::new()
+            .save(delivery.clone(), conn)
+            .await
+            .expect("Failed to save transaction for test");
+
+        let void_response = client.post(uri!(void_supplier_transaction(id = delivery.id.clone()))).dispatch().await;
+        assert_eq!(void_response.status(), Status::Ok);
+        let voided = deserialize_response_body::<SupplierTransaction>(void_response).await.data.unwrap();
+        assert!(voided.voided);
+        assert_eq!(voided.jumlah_barang, 40);
+
+        let get_response = client.get(uri!(get_supplier(suppliers_id = supplier.id.clone()))).dispatch().await;
+        let reversed = deserialize_response_body::<Supplier>(get_response).await.data.unwrap();
+        assert_eq!(reversed.jumlah_barang, 110);
+
+        let trx_response = client.get(uri!(get_all_supplier_transactions)).dispatch().await;
+        let transactions = deserialize_response_body::<Vec<SupplierTransaction>>(trx_response).await.data.unwrap();
+        assert!(transactions.iter().any(|t| t.id == delivery.id && t.voided));
+
+        let second_void = client.post(uri!(void_supplier_transaction(id = delivery.id.clone()))).dispatch().await;
+        assert_eq!(second_void.status(), Status::Conflict);
+
+        let get_again = client.get(uri!(get_supplier(suppliers_id = supplier.id.clone()))).dispatch().await;
+        let unchanged = deserialize_response_body::<Supplier>(get_again).await.data.unwrap();
+        assert_eq!(unchanged.jumlah_barang, 110);
+
+        let missing = client.post(uri!(void_supplier_transaction(id = "TRX-MISSING"))).dispatch().await;
+        assert_eq!(missing.status(), Status::NotFound);
+    }
+
+    #[async_test]
+    async fn test_integ_supplier_stats_groups_by_jenis_barang() {
+        let rocket_instance = setup_rocket_instance_for_supplier_tests().await;
+        let client = Client::tracked(rocket_instance).await.expect("Valid Rocket instance");
+
+        let response = client.get(uri!(get_supplier_stats)).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let empty = deserialize_response_body::<SupplierStats>(response).await.data.unwrap();
+        assert_eq!(empty, SupplierStats { total_suppliers: 0, by_jenis_barang: vec![] });
+
+        let seeds = [("Semen", 100), ("Besi", 40), ("Semen", 250), ("Cat", 15), ("Besi", 60)];
+        for (index, (jenis, jumlah)) in seeds.iter().enumerate() {
+            let mut request = sample_supplier_request(&format!("Stats{index}"));
+            request.jenis_barang = jenis.to_string();
+            request.jumlah_barang = *jumlah;
+            let resp = client.post(uri!(save_supplier)).json(&request).dispatch().await;
+            assert_eq!(resp.status(), Status::Created);
+        }
+
+        let response = client.get(uri!(get_supplier_stats)).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let stats = deserialize_response_body::<SupplierStats>(response).await.data.unwrap();
+        assert_eq!(stats.total_suppliers, 5);
+        let grouped: Vec<(&str, i64, i64)> = stats.by_jenis_barang.iter()
+            .map(|g| (g.jenis.as_str(), g.count, g.total_jumlah))
+            .collect();
+        assert_eq!(grouped, vec![("Besi", 2, 100), ("Cat", 1, 15), ("Semen", 2, 350)]);
+    }
+
+    #[async_test]
+    async fn test_integ_supplier_performance_metrics() {
+        let rocket_instance_build = setup_rocket_instance_for_supplier_tests().await;
+        let db_pool_for_seeding = rocket_instance_build.state::<Pool<Any>>().unwrap().clone();
+        let client = Client::tracked(rocket_instance_build).await.expect("Valid Rocket instance");
+
+        let create_resp = client.post(uri!(save_supplier)).json(&sample_supplier_request("Performance")).dispatch().await;
+        let supplier = deserialize_response_body::<Supplier>(create_resp).await.data.unwrap();
+
+        let deliveries = [
+            (10, "2024-01-05T08:00:00+00:00", false),
+            (25, "2024-02-10T08:00:00+00:00", false),
+            (40, "2024-03-15T08:00:00+00:00", false),
+            (500, "2024-04-01T08:00:00+00:00", true),
+        ];
+        for (jumlah_barang, tanggal, voided) in deliveries {
+            let mut delivery = create_test_transaction_model(&supplier);
+            delivery.jumlah_barang = jumlah_barang;
+            delivery.tanggal_transaksi = crate::common::timestamp::parse(tanggal).unwrap();
+            let conn = db_pool_for_seeding.acquire().await.unwrap();
+            SupplierTransactionRepositoryImpl::new()
+                .save(delivery.clone(), conn)
+                .await
+                .expect("Failed to save transaction for test");
+            if voided {
+                let conn = db_pool_for_seeding.acquire().await.unwrap();
+                SupplierTransactionRepositoryImpl::new().void(&delivery.id, conn).await.unwrap();
+            }
+        }
+
+        let response = client.get(uri!(get_supplier_performance(id = supplier.id.clone()))).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let performance = deserialize_response_body::<SupplierPerformance>(response).await.data.unwrap();
+        assert_eq!(performance, SupplierPerformance {
+            supplier_id: supplier.id.clone(),
+            total_jumlah_barang: 75,
+            jumlah_pengiriman: 3,
+            pengiriman_pertama: crate::common::timestamp::parse("2024-01-05T08:00:00Z"),
+            pengiriman_terakhir: crate::common::timestamp::parse("2024-03-15T08:00:00Z"),
+            rata_rata_jumlah_barang: 25.0,
+        });
+
+        let empty_resp = client.post(uri!(save_supplier)).json(&sample_supplier_request("NoDeliveries")).dispatch().await;
+        let empty_supplier = deserialize_response_body::<Supplier>(empty_resp).await.data.unwrap();
+        let response = client.get(uri!(get_supplier_performance(id = empty_supplier.id.clone()))).dispatch().await;
+        let performance = deserialize_response_body::<SupplierPerformance>(response).await.data.unwrap();
+        assert_eq!(performance.jumlah_pengiriman, 0);
+        assert_eq!(performance.pengiriman_pertama, None);
+        assert_eq!(performance.rata_rata_jumlah_barang, 0.0);
+
+        let missing = client.get(uri!(get_supplier_performance(id = "SUP-MISSING"))).dispatch().await;
+        assert_eq!(missing.status(), Status::NotFound);
+    }
+
+    #[async_test]
+    async fn test_integ_export_supplier_transactions_csv() {
+        let rocket_instance_build = setup_rocket_instance_for_supplier_tests().await;
+        let db_pool_for_seeding = rocket_instance_build.state::<Pool<Any>>().unwrap().clone();
+        let client = Client::tracked(rocket_instance_build).await.expect("Valid Rocket instance");
+
+        let create_resp = client.post(uri!(save_supplier)).json(&sample_supplier_request("Export")).dispatch().await;
+        let supplier = deserialize_response_body::<Supplier>(create_resp).await.data.unwrap();
+
+        let deliveries = [
+            ("TRX-EXP-1", 10, "2024-01-05T08:00:00+00:00", "Resi A"),
+            ("TRX-EXP-2", 25, "2024-02-10T08:00:00+00:00", "Resi \"B\", gudang 2"),
+        ];
+        for (id, jumlah_barang, tanggal, info) in deliveries {
+            let mut delivery = create_test_transaction_model(&supplier);
+            delivery.id = id.to_string();
+            delivery.jumlah_barang = jumlah_barang;
+            delivery.pengiriman_info = info.to_string();
+            delivery.tanggal_transaksi = crate::common::timestamp::parse(tanggal).unwrap();
+            let conn = db_pool_for_seeding.acquire().await.unwrap();
+            SupplierTransactionRepositoryImpl::new().save(delivery, conn).await.unwrap();
+        }
+
+        let response = client.get(uri!(export_supplier_transactions_csv(id = supplier.id.clone()))).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(rocket::http::ContentType::CSV));
+        assert_eq!(
+            response.headers().get_one("Content-Disposition"),
+            Some(format!("attachment; filename=\"supplier-{}-transactions.csv\"", supplier.id).as_str())
+        );
+        assert_eq!(
+            response.into_string().await.unwrap(),
+            "id,tanggal,jenis_barang,jumlah_barang,pengiriman_info\n\
+             TRX-EXP-2,2024-02-10T08:00:00Z,Integration Goods,25,\"Resi \"\"B\"\", gudang 2\"\n\
+             TRX-EXP-1,2024-01-05T08:00:00Z,Integration Goods,10,Resi A\n"
+        );
+
+        let missing = client.get(uri!(export_supplier_transactions_csv(id = "SUP-MISSING"))).dispatch().await;
+        assert_eq!(missing.status(), Status::NotFound);
+    }
+
+    #[test]
+    fn test_supplier_request_json_keys() {
+        let request = SupplierRequest {
+            name: "PT Sumber Makmur".to_string(),
+            jenis_barang: "Semen".to_string(),
+            jumlah_barang: 50,
+            resi: "RESI-001".to_string(),
+            contact: SupplierContact::default(),
+        };
+
+        let value = rocket::serde::json::serde_json::to_value(&request).unwrap();
+        let mut keys: Vec<&str> = value.as_object().unwrap().keys().map(|k| k.as_str()).collect();
+        keys.sort();
+        assert_eq!(keys, vec!["jenis_barang", "jumlah_barang", "name", "resi"]);
+
+        let round_trip: SupplierRequest = rocket::serde::json::serde_json::from_value(value).unwrap();
+        assert_eq!(round_trip.jenis_barang, "Semen");
+        assert_eq!(round_trip.jumlah_barang, 50);
+    }
+}
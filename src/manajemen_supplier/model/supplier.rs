@@ -1,4 +1,7 @@
 use serde::{Deserialize, Serialize};
+
+use crate::common::audit::AuditTrail;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Supplier {
     pub id: String,
@@ -6,7 +9,79 @@ pub struct Supplier {
     pub jenis_barang: String,
     pub jumlah_barang: i32,
     pub resi: String,
+    #[serde(with = "crate::common::timestamp::rfc3339_string")]
     pub updated_at: String,
+    #[serde(flatten)]
+    pub contact: SupplierContact,
+    #[serde(flatten)]
+    pub audit: AuditTrail,
+}
+
+/// Kontak supplier; keduanya opsional, tetapi jika diisi harus berformat valid.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SupplierContact {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+// Batas digit mengikuti E.164 (maks. 15) dengan minimum nomor lokal terpendek
+const PHONE_MIN_DIGITS: usize = 8;
+const PHONE_MAX_DIGITS: usize = 15;
+
+impl SupplierContact {
+    /// Memangkas spasi, mengubah string kosong menjadi `None`, lalu memvalidasi format.
+    pub fn normalized(self) -> Result<Self, String> {
+        let phone = non_empty(self.phone);
+        let email = non_empty(self.email);
+
+        if let Some(phone) = &phone {
+            let valid_chars = phone.chars().all(|c| c.is_ascii_digit() || matches!(c, '+' | '-' | ' ' | '(' | ')'));
+            let digits = phone.chars().filter(char::is_ascii_digit).count();
+            if !valid_chars || !(PHONE_MIN_DIGITS..=PHONE_MAX_DIGITS).contains(&digits) {
+                return Err(format!(
+                    "Validation error: phone must contain {PHONE_MIN_DIGITS}-{PHONE_MAX_DIGITS} digits (got '{phone}')."
+                ));
+            }
+        }
+        if let Some(email) = &email {
+            if !is_valid_email(email) {
+                return Err(format!("Validation error: email '{email}' is not a valid address."));
+            }
+        }
+
+        Ok(Self { phone, email })
+    }
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+fn is_valid_email(email: &str) -> bool {
+    let Some((local, domain)) = email.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !domain.contains('@')
+        && !email.chars().any(char::is_whitespace)
+        && domain.contains('.')
+        && domain.split('.').all(|label| !label.is_empty())
+}
+
+/// Rekap supplier per `jenis_barang` untuk dashboard procurement.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JenisBarangStats {
+    pub jenis: String,
+    pub count: i64,
+    pub total_jumlah: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SupplierStats {
+    pub total_suppliers: i64,
+    pub by_jenis_barang: Vec<JenisBarangStats>,
 }
 
 #[cfg(test)]
@@ -26,6 +101,8 @@ mod tests {
             jumlah_barang: 1000,
             resi: "2306206282".to_string(),
             updated_at: Utc::now().to_rfc3339(),
+            contact: Default::default(),
+            audit: Default::default(),
         };
 
         assert_eq!(supplier.name, "PT. Ayam");
@@ -33,4 +110,28 @@ mod tests {
         assert_eq!(supplier.jumlah_barang, 1000);
         assert_eq!(supplier.resi, "2306206282");
     }
+
+    #[test]
+    fn test_supplier_contact_validation() {
+        let contact = SupplierContact {
+            phone: Some(" +62 812-3456-7890 ".to_string()),
+            email: Some("sales@ayam.co.id".to_string()),
+        }
+        .normalized()
+        .unwrap();
+        assert_eq!(contact.phone.as_deref(), Some("+62 812-3456-7890"));
+        assert_eq!(contact.email.as_deref(), Some("sales@ayam.co.id"));
+
+        for email in ["ayam.co.id", "sales@", "@ayam.co.id", "sales@ayam", "sales@@ayam.id", "sa les@ayam.id", "sales@ayam..id"] {
+            let invalid = SupplierContact { phone: None, email: Some(email.to_string()) };
+            assert!(invalid.normalized().unwrap_err().contains("Validation error"), "email {email}");
+        }
+        for phone in ["1234567", "1234567890123456", "0812-ABC-7890"] {
+            let invalid = SupplierContact { phone: Some(phone.to_string()), email: None };
+            assert!(invalid.normalized().is_err(), "phone {phone}");
+        }
+
+        let blank = SupplierContact { phone: Some("  ".to_string()), email: None }.normalized().unwrap();
+        assert_eq!(blank, SupplierContact::default());
+    }
 }
@@ -1,5 +1,8 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::common::timestamp;
+
 use super::supplier::Supplier;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -10,7 +13,23 @@ pub struct SupplierTransaction {
     pub jenis_barang: String,
     pub jumlah_barang: i32,
     pub pengiriman_info: String,
-    pub tanggal_transaksi: String,
+    #[serde(with = "crate::common::timestamp::rfc3339")]
+    pub tanggal_transaksi: DateTime<Utc>,
+    #[serde(default)]
+    pub voided: bool,
+}
+
+/// Ringkasan pengiriman satu supplier; transaksi yang di-void tidak dihitung.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SupplierPerformance {
+    pub supplier_id: String,
+    pub total_jumlah_barang: i64,
+    pub jumlah_pengiriman: i64,
+    #[serde(default, with = "crate::common::timestamp::option_rfc3339")]
+    pub pengiriman_pertama: Option<DateTime<Utc>>,
+    #[serde(default, with = "crate::common::timestamp::option_rfc3339")]
+    pub pengiriman_terakhir: Option<DateTime<Utc>>,
+    pub rata_rata_jumlah_barang: f64,
 }
 
 impl SupplierTransaction {
@@ -22,7 +41,8 @@ impl SupplierTransaction {
             jenis_barang: supplier.jenis_barang.clone(),
             jumlah_barang: supplier.jumlah_barang,
             pengiriman_info: supplier.resi.clone(),
-            tanggal_transaksi: supplier.updated_at.clone(),
+            tanggal_transaksi: timestamp::parse(&supplier.updated_at).unwrap_or_else(Utc::now),
+            voided: false,
         }
     }
 }
@@ -30,11 +50,11 @@ impl SupplierTransaction {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::Utc;
+    use chrono::TimeZone;
 
     #[test]
     fn test_supplier_to_transaction() {
-        let now = Utc::now();
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap();
 
         let supplier = Supplier {
             id: "SUP-001".to_string(),
@@ -43,6 +63,8 @@ mod tests {
             jumlah_barang: 100,
             resi: "2306206282".to_string(),
             updated_at: now.to_rfc3339(),
+            contact: Default::default(),
+            audit: Default::default(),
         };
 
         let transaksi = SupplierTransaction::from_supplier("STRX-001".to_string(), &supplier);
@@ -52,6 +74,33 @@ mod tests {
         assert_eq!(transaksi.jenis_barang, "Ayam");
         assert_eq!(transaksi.jumlah_barang, 100);
         assert_eq!(transaksi.pengiriman_info, "2306206282");
-        assert_eq!(transaksi.tanggal_transaksi, now.to_rfc3339());
+        assert_eq!(transaksi.tanggal_transaksi, now);
+    }
+
+    #[test]
+    fn test_tanggal_transaksi_serializes_with_z_suffix() {
+        let transaksi = SupplierTransaction {
+            id: "STRX-TS".to_string(),
+            supplier_id: "SUP-TS".to_string(),
+            supplier_name: "PT. Ayam".to_string(),
+            jenis_barang: "Ayam".to_string(),
+            jumlah_barang: 10,
+            pengiriman_info: "RESI".to_string(),
+            tanggal_transaksi: Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap(),
+            voided: false,
+        };
+
+        let value = serde_json::to_value(&transaksi).unwrap();
+        assert_eq!(value["tanggal_transaksi"], "2024-01-01T10:00:00Z");
+
+        let round_trip: SupplierTransaction = serde_json::from_value(value).unwrap();
+        assert_eq!(round_trip, transaksi);
+
+        let legacy: SupplierTransaction = serde_json::from_value(serde_json::json!({
+            "id": "STRX-TS", "supplier_id": "SUP-TS", "supplier_name": "PT. Ayam",
+            "jenis_barang": "Ayam", "jumlah_barang": 10, "pengiriman_info": "RESI",
+            "tanggal_transaksi": "2024-01-01T17:00:00+07:00",
+        })).unwrap();
+        assert_eq!(legacy.tanggal_transaksi, transaksi.tanggal_transaksi);
     }
 }
@@ -14,7 +14,8 @@ impl SupplierTransactionFactory {
             jenis_barang: supplier.jenis_barang.clone(),
             jumlah_barang: supplier.jumlah_barang,
             pengiriman_info: supplier.resi.clone(),
-            tanggal_transaksi: Utc::now().to_rfc3339(),
+            tanggal_transaksi: Utc::now(),
+            voided: false,
         }
     }
 }
@@ -39,6 +40,8 @@ mod tests {
             jumlah_barang: 1000,
             resi: "2306206282".to_string(),
             updated_at: now.to_rfc3339(),
+            contact: Default::default(),
+            audit: Default::default(),
         };
 
         let transaction = SupplierTransactionFactory::create_from_supplier(&supplier);
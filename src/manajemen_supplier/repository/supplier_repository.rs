@@ -1,14 +1,37 @@
 use async_trait::async_trait;
 use mockall::automock;
-use crate::manajemen_supplier::model::supplier::Supplier;
+use crate::manajemen_supplier::model::supplier::{JenisBarangStats, Supplier};
+use crate::common::pagination::{ListQuery, SortColumn};
 use sqlx::{Any, pool::PoolConnection};
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeleteSupplierOutcome {
+    Deleted,
+    BlockedByTransactions(i64),
+}
+
+/// Urutan default listing supplier; `id` sebagai pemecah seri.
+pub const DEFAULT_SUPPLIER_ORDER_BY: &str = "name ASC, id ASC";
+
+/// Nilai `sort` listing supplier untuk `ListQuery::order_by`.
+pub const SUPPLIER_SORT_COLUMNS: &[SortColumn] = &[
+    ("name", "LOWER(name)"),
+    ("jumlah_barang", "jumlah_barang"),
+    ("updated_at", "updated_at"),
+];
+
 #[async_trait]
 #[automock]
 pub trait SupplierRepository: Send + Sync {
     async fn save(&self, supplier: Supplier, db: PoolConnection<Any>) -> Result<Supplier, sqlx::Error>;
     async fn find_by_id(&self, id: &str, db: PoolConnection<Any>) -> Result<Supplier, sqlx::Error>;
     async fn update(&self, supplier: Supplier, db: PoolConnection<Any>) -> Result<(), sqlx::Error>;
-    async fn delete(&self, id: &str, db: PoolConnection<Any>) -> Result<(), sqlx::Error>;
-    async fn find_all(&self, db: PoolConnection<Any>) -> Result<Vec<Supplier>, sqlx::Error>;
+    async fn delete(&self, id: &str, cascade: bool, db: PoolConnection<Any>) -> Result<DeleteSupplierOutcome, sqlx::Error>;
+    /// Listing dengan keyword `list` (name/jenis_barang) dan paginasinya diterapkan di SQL.
+    async fn find_all(&self, order_by: &str, list: &ListQuery, db: PoolConnection<Any>) -> Result<Vec<Supplier>, sqlx::Error>;
+    /// Memindahkan semua transaksi `dup_id` ke `keep_id` lalu menghapus `dup_id`,
+    /// mengembalikan jumlah transaksi yang dipindahkan.
+    async fn merge(&self, keep_id: &str, dup_id: &str, db: PoolConnection<Any>) -> Result<u64, sqlx::Error>;
+    /// Jumlah supplier dan total `jumlah_barang` per `jenis_barang`, urut nama jenis.
+    async fn stats_by_jenis_barang(&self, db: PoolConnection<Any>) -> Result<Vec<JenisBarangStats>, sqlx::Error>;
 }
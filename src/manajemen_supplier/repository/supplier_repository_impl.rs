@@ -1,6 +1,8 @@
-use sqlx::{Any, pool::PoolConnection, any::AnyRow, Row};
-use crate::manajemen_supplier::model::supplier::Supplier;
-use crate::manajemen_supplier::repository::supplier_repository::SupplierRepository;
+use sqlx::{Any, pool::PoolConnection, any::AnyRow, Connection, Row};
+use crate::common::audit::AuditTrail;
+use crate::common::pagination::{escape_like, ListQuery};
+use crate::manajemen_supplier::model::supplier::{JenisBarangStats, Supplier, SupplierContact};
+use crate::manajemen_supplier::repository::supplier_repository::{DeleteSupplierOutcome, SupplierRepository};
 
 use async_trait::async_trait;
 
@@ -18,6 +20,14 @@ impl SupplierRepositoryImpl {
         let jumlah_barang: i32 = row.get("jumlah_barang");
         let resi: String = row.get("resi");
         let updated_at: String = row.get("updated_at");
+        let contact = SupplierContact {
+            phone: row.try_get("phone").ok(),
+            email: row.try_get("email").ok(),
+        };
+        let audit = AuditTrail {
+            created_by: row.try_get("created_by").ok(),
+            updated_by: row.try_get("updated_by").ok(),
+        };
 
         Ok(Supplier {
             id,
@@ -26,6 +36,8 @@ impl SupplierRepositoryImpl {
             jumlah_barang,
             resi,
             updated_at,
+            contact,
+            audit,
         })
     }
 }
@@ -34,8 +46,8 @@ impl SupplierRepositoryImpl {
 impl SupplierRepository for SupplierRepositoryImpl {
     async fn save(&self, supplier: Supplier, mut db: PoolConnection<Any>) -> Result<Supplier, sqlx::Error> {
         let query = "
-            INSERT INTO suppliers (id, name, jenis_barang, jumlah_barang, resi, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $6)
+            INSERT INTO suppliers (id, name, jenis_barang, jumlah_barang, resi, updated_at, phone, email, created_by, updated_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
         ";
 
         sqlx::query(query)
@@ -45,6 +57,10 @@ impl SupplierRepository for SupplierRepositoryImpl {
             .bind(supplier.jumlah_barang)
             .bind(&supplier.resi)
             .bind(&supplier.updated_at)
+            .bind(&supplier.contact.phone)
+            .bind(&supplier.contact.email)
+            .bind(&supplier.audit.created_by)
+            .bind(&supplier.audit.updated_by)
             .execute(&mut *db)
             .await?;
 
@@ -69,8 +85,11 @@ impl SupplierRepository for SupplierRepositoryImpl {
                 jenis_barang = $2,
                 jumlah_barang = $3,
                 resi = $4,
-                updated_at = $5
-            WHERE id = $6
+                updated_at = $5,
+                phone = $6,
+                email = $7,
+                updated_by = COALESCE($8, updated_by)
+            WHERE id = $9
         ";
 
         let result = sqlx::query(query)
@@ -79,6 +98,9 @@ impl SupplierRepository for SupplierRepositoryImpl {
             .bind(supplier.jumlah_barang)
             .bind(&supplier.resi)
             .bind(supplier.updated_at)
+            .bind(supplier.contact.phone)
+            .bind(supplier.contact.email)
+            .bind(supplier.audit.updated_by)
             .bind(&supplier.id)
             .execute(&mut *db)
             .await?;
@@ -90,24 +112,54 @@ impl SupplierRepository for SupplierRepositoryImpl {
         Ok(())
     }
 
-    async fn delete(&self, id: &str, mut db: PoolConnection<Any>) -> Result<(), sqlx::Error> {
-        let query = "DELETE FROM suppliers WHERE id = $1";
+    // Default-nya menolak hapus jika supplier masih punya transaksi; cascade = true
+    // menghapus transaksi tersebut dalam DB transaction yang sama.
+    async fn delete(&self, id: &str, cascade: bool, mut db: PoolConnection<Any>) -> Result<DeleteSupplierOutcome, sqlx::Error> {
+        let mut tx = db.begin().await?;
 
-        let result = sqlx::query(query)
+        let transaction_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM supplier_transactions WHERE supplier_id = $1")
             .bind(id)
-            .execute(&mut *db)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        if transaction_count > 0 {
+            if !cascade {
+                tx.rollback().await?;
+                return Ok(DeleteSupplierOutcome::BlockedByTransactions(transaction_count));
+            }
+
+            sqlx::query("DELETE FROM supplier_transactions WHERE supplier_id = $1")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        let result = sqlx::query("DELETE FROM suppliers WHERE id = $1")
+            .bind(id)
+            .execute(&mut *tx)
             .await?;
 
         if result.rows_affected() == 0 {
             return Err(sqlx::Error::RowNotFound);
         }
 
-        Ok(())
+        tx.commit().await?;
+        Ok(DeleteSupplierOutcome::Deleted)
     }
 
-    async fn find_all(&self, mut db: PoolConnection<Any>) -> Result<Vec<Supplier>, sqlx::Error> {
-        let query = "SELECT * FROM suppliers";
-        let rows = sqlx::query(query)
+    async fn find_all(&self, order_by: &str, list: &ListQuery, mut db: PoolConnection<Any>) -> Result<Vec<Supplier>, sqlx::Error> {
+        let keyword = list.keyword();
+        let where_sql = if keyword.is_some() {
+            " WHERE (LOWER(name) LIKE $1 ESCAPE '\\' OR LOWER(jenis_barang) LIKE $1 ESCAPE '\\')"
+        } else {
+            ""
+        };
+        let query = format!("SELECT * FROM suppliers{where_sql} ORDER BY {order_by}{}", list.limit_clause());
+        let mut query = sqlx::query(&query);
+        if let Some(keyword) = keyword {
+            query = query.bind(format!("%{}%", escape_like(&keyword)));
+        }
+        let rows = query
             .fetch_all(&mut *db)
             .await?;
 
@@ -117,6 +169,61 @@ impl SupplierRepository for SupplierRepositoryImpl {
         }
         Ok(suppliers)
     }
+
+    async fn merge(&self, keep_id: &str, dup_id: &str, mut db: PoolConnection<Any>) -> Result<u64, sqlx::Error> {
+        let mut tx = db.begin().await?;
+
+        let keep_name: String = sqlx::query_scalar("SELECT name FROM suppliers WHERE id = $1")
+            .bind(keep_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        let dup_exists: Option<String> = sqlx::query_scalar("SELECT id FROM suppliers WHERE id = $1")
+            .bind(dup_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        if dup_exists.is_none() {
+            return Err(sqlx::Error::RowNotFound);
+        }
+
+        let moved = sqlx::query("UPDATE supplier_transactions SET supplier_id = $1, supplier_name = $2 WHERE supplier_id = $3")
+            .bind(keep_id)
+            .bind(&keep_name)
+            .bind(dup_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        sqlx::query("DELETE FROM suppliers WHERE id = $1")
+            .bind(dup_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(moved)
+    }
+
+    async fn stats_by_jenis_barang(&self, mut db: PoolConnection<Any>) -> Result<Vec<JenisBarangStats>, sqlx::Error> {
+        let rows = sqlx::query(
+            "
+            SELECT jenis_barang,
+                   CAST(COUNT(*) AS BIGINT) AS jumlah_supplier,
+                   CAST(COALESCE(SUM(jumlah_barang), 0) AS BIGINT) AS total_jumlah
+            FROM suppliers
+            GROUP BY jenis_barang
+            ORDER BY jenis_barang ASC
+            "
+        )
+            .fetch_all(&mut *db)
+            .await?;
+
+        rows.into_iter()
+            .map(|row| Ok(JenisBarangStats {
+                jenis: row.try_get("jenis_barang")?,
+                count: row.try_get("jumlah_supplier")?,
+                total_jumlah: row.try_get("total_jumlah")?,
+            }))
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -127,7 +234,7 @@ mod tests {
     use uuid::Uuid;
 
     use crate::manajemen_supplier::model::supplier::Supplier;
-    use crate::manajemen_supplier::repository::supplier_repository::SupplierRepository;
+    use crate::manajemen_supplier::repository::supplier_repository::{SupplierRepository, DEFAULT_SUPPLIER_ORDER_BY};
 
     async fn setup_repository() -> (SupplierRepositoryImpl, sqlx::Pool<Any>) {
         install_default_drivers();
@@ -157,6 +264,8 @@ mod tests {
             jumlah_barang: 1000,
             resi: "2306206282".to_string(),
             updated_at: Utc::now().to_rfc3339(),
+            contact: Default::default(),
+            audit: Default::default(),
         };
 
         let db_conn = db_pool.acquire().await.unwrap();
@@ -180,6 +289,8 @@ async fn test_find_supplier_by_id() {
         jumlah_barang: 1000,
         resi: "2306206282".to_string(),
         updated_at: Utc::now().to_rfc3339(),
+        contact: Default::default(),
+        audit: Default::default(),
     };
 
     let db_conn = db_pool.acquire().await.unwrap();
@@ -220,6 +331,8 @@ async fn test_find_supplier_by_id() {
             jumlah_barang: 1000,
             resi: "2306206282".to_string(),
             updated_at: Utc::now().to_rfc3339(),
+            contact: Default::default(),
+            audit: Default::default(),
         };
 
         let db_conn = db_pool.acquire().await.unwrap();
@@ -249,15 +362,17 @@ async fn test_find_supplier_by_id() {
             jumlah_barang: 1000,
             resi: "2306206282".to_string(),
             updated_at: Utc::now().to_rfc3339(),
+            contact: Default::default(),
+            audit: Default::default(),
         };
 
         let db_conn = db_pool.acquire().await.unwrap();
         repository.save(supplier.clone(), db_conn).await.unwrap();
 
         let db_conn = db_pool.acquire().await.unwrap();
-        let result = repository.delete(&supplier_id, db_conn).await;
+        let result = repository.delete(&supplier_id, false, db_conn).await;
 
-        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), DeleteSupplierOutcome::Deleted);
         let db_conn = db_pool.acquire().await.unwrap();
         let result = repository.find_by_id(&supplier_id, db_conn).await;
         assert!(result.is_err());
@@ -271,7 +386,7 @@ async fn test_find_supplier_by_id() {
     async fn test_find_all_suppliers_empty() {
         let (repository, db_pool) = setup_repository().await;
         let db_conn = db_pool.acquire().await.unwrap();
-        let result = repository.find_all(db_conn).await;
+        let result = repository.find_all(DEFAULT_SUPPLIER_ORDER_BY, &ListQuery::default(), db_conn).await;
         
         assert!(result.is_ok());
         let suppliers = result.unwrap();
@@ -289,6 +404,8 @@ async fn test_find_supplier_by_id() {
             jumlah_barang: 1000,
             resi: "2306206282".to_string(),
             updated_at: Utc::now().to_rfc3339(),
+            contact: Default::default(),
+            audit: Default::default(),
         };
 
         let supplier2 = Supplier {
@@ -298,6 +415,8 @@ async fn test_find_supplier_by_id() {
             jumlah_barang: 500,
             resi: "2306206283".to_string(),
             updated_at: Utc::now().to_rfc3339(),
+            contact: Default::default(),
+            audit: Default::default(),
         };
 
         let db_conn = db_pool.acquire().await.unwrap();
@@ -307,7 +426,7 @@ async fn test_find_supplier_by_id() {
         repository.save(supplier2.clone(), db_conn).await.unwrap();
 
         let db_conn = db_pool.acquire().await.unwrap();
-        let result = repository.find_all(db_conn).await;
+        let result = repository.find_all(DEFAULT_SUPPLIER_ORDER_BY, &ListQuery::default(), db_conn).await;
         
         assert!(result.is_ok());
         let suppliers = result.unwrap();
@@ -318,6 +437,75 @@ async fn test_find_supplier_by_id() {
         assert!(ids.contains(&supplier2.id));
     }
 
+    #[tokio::test]
+    async fn test_find_all_suppliers_default_order_is_deterministic() {
+        let (repository, db_pool) = setup_repository().await;
+
+        let mut expected_ids = Vec::new();
+        for name in ["PT. Sapi", "PT. Ayam", "PT. Sapi", "PT. Ayam"] {
+            let supplier = Supplier {
+                id: format!("SUP-{}", Uuid::new_v4()),
+                name: name.to_string(),
+                jenis_barang: "campur".to_string(),
+                jumlah_barang: 10,
+                resi: "RESI-ORDER".to_string(),
+                updated_at: Utc::now().to_rfc3339(),
+                contact: Default::default(),
+                audit: Default::default(),
+            };
+            expected_ids.push((supplier.name.clone(), supplier.id.clone()));
+            let db_conn = db_pool.acquire().await.unwrap();
+            repository.save(supplier, db_conn).await.unwrap();
+        }
+        expected_ids.sort();
+        let expected_ids: Vec<String> = expected_ids.into_iter().map(|(_, id)| id).collect();
+
+        let db_conn = db_pool.acquire().await.unwrap();
+        let first: Vec<String> = repository.find_all(DEFAULT_SUPPLIER_ORDER_BY, &ListQuery::default(), db_conn).await.unwrap().into_iter().map(|s| s.id).collect();
+        let db_conn = db_pool.acquire().await.unwrap();
+        let second: Vec<String> = repository.find_all(DEFAULT_SUPPLIER_ORDER_BY, &ListQuery::default(), db_conn).await.unwrap().into_iter().map(|s| s.id).collect();
+
+        assert_eq!(first, expected_ids);
+        assert_eq!(first, second);
+    }
+
+    #[tokio::test]
+    async fn test_find_all_suppliers_filters_sorts_and_paginates_in_sql() {
+        let (repository, db_pool) = setup_repository().await;
+
+        for (name, jenis_barang, jumlah_barang) in [("PT. Semen Jaya", "semen", 30), ("CV. Pasir", "pasir", 10), ("PT. Batu", "semen putih", 20), ("UD. 50%", "cat", 5)] {
+            let supplier = Supplier {
+                id: format!("SUP-{}", Uuid::new_v4()),
+                name: name.to_string(),
+                jenis_barang: jenis_barang.to_string(),
+                jumlah_barang,
+                resi: "RESI-LIST".to_string(),
+                updated_at: Utc::now().to_rfc3339(),
+                contact: Default::default(),
+                audit: Default::default(),
+            };
+            let db_conn = db_pool.acquire().await.unwrap();
+            repository.save(supplier, db_conn).await.unwrap();
+        }
+        let names = |suppliers: Vec<Supplier>| suppliers.into_iter().map(|s| s.name).collect::<Vec<_>>();
+
+        let list = ListQuery { keyword: Some("SEMEN".to_string()), ..Default::default() };
+        let db_conn = db_pool.acquire().await.unwrap();
+        let found = repository.find_all("jumlah_barang ASC, id ASC", &list, db_conn).await.unwrap();
+        assert_eq!(names(found), vec!["PT. Batu", "PT. Semen Jaya"]);
+
+        let list = ListQuery { page: Some(2), limit: Some(2), ..Default::default() };
+        let db_conn = db_pool.acquire().await.unwrap();
+        let page = repository.find_all("jumlah_barang DESC, id ASC", &list, db_conn).await.unwrap();
+        assert_eq!(names(page), vec!["CV. Pasir", "UD. 50%"]);
+
+        // `%` di keyword dicocokkan sebagai karakter biasa
+        let list = ListQuery { keyword: Some("%".to_string()), ..Default::default() };
+        let db_conn = db_pool.acquire().await.unwrap();
+        let found = repository.find_all(DEFAULT_SUPPLIER_ORDER_BY, &list, db_conn).await.unwrap();
+        assert_eq!(names(found), vec!["UD. 50%"]);
+    }
+
     #[tokio::test]
     async fn test_update_nonexistent_supplier() {
         let (repository, db_pool) = setup_repository().await;
@@ -329,6 +517,8 @@ async fn test_find_supplier_by_id() {
             jumlah_barang: 0,
             resi: "000".to_string(),
             updated_at: Utc::now().to_rfc3339(),
+            contact: Default::default(),
+            audit: Default::default(),
         };
 
         let db_conn = db_pool.acquire().await.unwrap();
@@ -345,7 +535,7 @@ async fn test_find_supplier_by_id() {
     async fn test_delete_nonexistent_supplier() {
         let (repository, db_pool) = setup_repository().await;
         let db_conn = db_pool.acquire().await.unwrap();
-        let result = repository.delete("non-existent-id", db_conn).await;
+        let result = repository.delete("non-existent-id", false, db_conn).await;
         
         assert!(result.is_err());
         match result.unwrap_err() {
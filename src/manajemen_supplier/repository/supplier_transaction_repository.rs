@@ -1,8 +1,13 @@
 use async_trait::async_trait;
 use sqlx::{Any, pool::PoolConnection};
-use crate::manajemen_supplier::model::supplier_transaction::SupplierTransaction;
+use crate::manajemen_supplier::model::supplier_transaction::{SupplierPerformance, SupplierTransaction};
 use mockall::automock;
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum VoidSupplierTransactionOutcome {
+    Voided(SupplierTransaction),
+    AlreadyVoided,
+}
 
 #[automock]
 #[async_trait]
@@ -11,4 +16,8 @@ pub trait SupplierTransactionRepository: Send + Sync {
     async fn find_by_id(&self, id: &str, db: PoolConnection<Any>) -> Result<SupplierTransaction, sqlx::Error>;
     async fn find_by_supplier_id(&self, supplier_id: &str, db: PoolConnection<Any>) -> Result<Vec<SupplierTransaction>, sqlx::Error>;
     async fn find_all(&self, db: PoolConnection<Any>) -> Result<Vec<SupplierTransaction>, sqlx::Error>;
+    /// Menandai transaksi sebagai voided dan mengurangi kembali `jumlah_barang`
+    /// supplier (jika supplier masih ada) dalam satu DB transaction.
+    async fn void(&self, id: &str, db: PoolConnection<Any>) -> Result<VoidSupplierTransactionOutcome, sqlx::Error>;
+    async fn performance_by_supplier_id(&self, supplier_id: &str, db: PoolConnection<Any>) -> Result<SupplierPerformance, sqlx::Error>;
 }
\ No newline at end of file
@@ -1,7 +1,9 @@
-use sqlx::{Any, pool::PoolConnection, any::AnyRow, Row};
+use sqlx::{Any, Connection, pool::PoolConnection, any::AnyRow, Row};
 use async_trait::async_trait;
-use crate::manajemen_supplier::model::supplier_transaction::SupplierTransaction;
-use crate::manajemen_supplier::repository::supplier_transaction_repository::SupplierTransactionRepository;
+use chrono::{DateTime, Utc};
+use crate::common::timestamp;
+use crate::manajemen_supplier::model::supplier_transaction::{SupplierPerformance, SupplierTransaction};
+use crate::manajemen_supplier::repository::supplier_transaction_repository::{SupplierTransactionRepository, VoidSupplierTransactionOutcome};
 
 pub struct SupplierTransactionRepositoryImpl;
 
@@ -10,6 +12,13 @@ impl SupplierTransactionRepositoryImpl {
         Self
     }
 
+    fn parse_tanggal(column: &str, value: &str) -> Result<DateTime<Utc>, sqlx::Error> {
+        timestamp::parse(value).ok_or_else(|| sqlx::Error::ColumnDecode {
+            index: column.to_string(),
+            source: format!("invalid timestamp '{value}'").into(),
+        })
+    }
+
     fn parse_row_to_transaction(row: AnyRow) -> Result<SupplierTransaction, sqlx::Error> {
         let id: String = row.get("id");
         let supplier_id: String = row.get("supplier_id");
@@ -18,6 +27,8 @@ impl SupplierTransactionRepositoryImpl {
         let jumlah_barang: i32 = row.get("jumlah_barang");
         let pengiriman_info: String = row.get("pengiriman_info");
         let tanggal_transaksi: String = row.get("tanggal_transaksi");
+        let tanggal_transaksi = Self::parse_tanggal("tanggal_transaksi", &tanggal_transaksi)?;
+        let voided: i32 = row.try_get("voided").unwrap_or(0);
 
         Ok(SupplierTransaction {
             id,
@@ -27,6 +38,7 @@ impl SupplierTransactionRepositoryImpl {
             jumlah_barang,
             pengiriman_info,
             tanggal_transaksi,
+            voided: voided != 0,
         })
     }
 }
@@ -46,7 +58,7 @@ impl SupplierTransactionRepository for SupplierTransactionRepositoryImpl {
             .bind(&transaction.jenis_barang)
             .bind(transaction.jumlah_barang)
             .bind(&transaction.pengiriman_info)
-            .bind(&transaction.tanggal_transaksi)
+            .bind(timestamp::format_for_storage(&transaction.tanggal_transaksi))
             .execute(&mut *db)
             .await?;
 
@@ -65,7 +77,7 @@ impl SupplierTransactionRepository for SupplierTransactionRepositoryImpl {
     }
 
     async fn find_by_supplier_id(&self, supplier_id: &str, mut db: PoolConnection<Any>) -> Result<Vec<SupplierTransaction>, sqlx::Error> {
-        let query = "SELECT * FROM supplier_transactions WHERE supplier_id = $1";
+        let query = "SELECT * FROM supplier_transactions WHERE supplier_id = $1 ORDER BY tanggal_transaksi DESC, id ASC";
 
         let rows = sqlx::query(query)
             .bind(supplier_id)
@@ -81,7 +93,7 @@ impl SupplierTransactionRepository for SupplierTransactionRepositoryImpl {
     }
 
     async fn find_all(&self, mut db: PoolConnection<Any>) -> Result<Vec<SupplierTransaction>, sqlx::Error> {
-        let query = "SELECT * FROM supplier_transactions";
+        let query = "SELECT * FROM supplier_transactions ORDER BY tanggal_transaksi DESC, id ASC";
         let rows = sqlx::query(query)
             .fetch_all(&mut *db)
             .await?;
@@ -92,6 +104,85 @@ impl SupplierTransactionRepository for SupplierTransactionRepositoryImpl {
         }
         Ok(suppliers)
     }
+
+    async fn void(&self, id: &str, mut db: PoolConnection<Any>) -> Result<VoidSupplierTransactionOutcome, sqlx::Error> {
+        let mut tx = db.begin().await?;
+
+        // Klaim bersyarat: dari dua void bersamaan hanya satu yang mendapat baris kembali,
+        // sehingga stok supplier tidak dikurangi dua kali
+        let claimed = sqlx::query("UPDATE supplier_transactions SET voided = 1 WHERE id = $1 AND voided = 0 RETURNING *")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        let transaction = match claimed {
+            Some(row) => Self::parse_row_to_transaction(row)?,
+            None => {
+                let exists = sqlx::query("SELECT id FROM supplier_transactions WHERE id = $1")
+                    .bind(id)
+                    .fetch_optional(&mut *tx)
+                    .await?;
+                tx.rollback().await?;
+                return match exists {
+                    Some(_) => Ok(VoidSupplierTransactionOutcome::AlreadyVoided),
+                    None => Err(sqlx::Error::RowNotFound),
+                };
+            }
+        };
+
+        // Stok supplier tidak boleh negatif setelah pembalikan
+        sqlx::query(
+            "UPDATE suppliers SET jumlah_barang = CASE WHEN jumlah_barang > $1 THEN jumlah_barang - $1 ELSE 0 END WHERE id = $2"
+        )
+            .bind(transaction.jumlah_barang)
+            .bind(&transaction.supplier_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(VoidSupplierTransactionOutcome::Voided(transaction))
+    }
+
+    async fn performance_by_supplier_id(&self, supplier_id: &str, mut db: PoolConnection<Any>) -> Result<SupplierPerformance, sqlx::Error> {
+        let row = sqlx::query(
+            "
+            SELECT CAST(COALESCE(SUM(jumlah_barang), 0) AS BIGINT) AS total_jumlah_barang,
+                   CAST(COUNT(*) AS BIGINT) AS jumlah_pengiriman
+            FROM supplier_transactions
+            WHERE supplier_id = $1 AND voided = 0
+            "
+        )
+            .bind(supplier_id)
+            .fetch_one(&mut *db)
+            .await?;
+
+        // MIN/MAX teks tidak bisa dipercaya untuk baris lama dengan format campuran
+        let tanggal_rows = sqlx::query("SELECT tanggal_transaksi FROM supplier_transactions WHERE supplier_id = $1 AND voided = 0")
+            .bind(supplier_id)
+            .fetch_all(&mut *db)
+            .await?;
+        let mut tanggal = Vec::with_capacity(tanggal_rows.len());
+        for tanggal_row in tanggal_rows {
+            let value: String = tanggal_row.try_get("tanggal_transaksi")?;
+            tanggal.push(Self::parse_tanggal("tanggal_transaksi", &value)?);
+        }
+
+        let total_jumlah_barang: i64 = row.try_get("total_jumlah_barang")?;
+        let jumlah_pengiriman: i64 = row.try_get("jumlah_pengiriman")?;
+        let rata_rata_jumlah_barang = if jumlah_pengiriman > 0 {
+            total_jumlah_barang as f64 / jumlah_pengiriman as f64
+        } else {
+            0.0
+        };
+
+        Ok(SupplierPerformance {
+            supplier_id: supplier_id.to_string(),
+            total_jumlah_barang,
+            jumlah_pengiriman,
+            pengiriman_pertama: tanggal.iter().min().copied(),
+            pengiriman_terakhir: tanggal.iter().max().copied(),
+            rata_rata_jumlah_barang,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -131,6 +222,8 @@ mod tests {
             jumlah_barang: 100,
             resi: "RESI-TEST-001".to_string(),
             updated_at: Utc::now().to_rfc3339(),
+            contact: Default::default(),
+            audit: Default::default(),
         }
     }
 
@@ -142,7 +235,8 @@ mod tests {
             jenis_barang: supplier.jenis_barang.clone(),
             jumlah_barang: supplier.jumlah_barang, 
             pengiriman_info: format!("Info for {}", supplier.resi.clone()),
-            tanggal_transaksi: Utc::now().to_rfc3339(),
+            tanggal_transaksi: Utc::now(),
+            voided: false,
         }
     }
 
@@ -286,4 +380,77 @@ mod tests {
         assert!(ids.contains(&transaction2_s2.id));
         assert!(ids.contains(&transaction3_s1.id));
     }
+
+    #[tokio::test]
+    async fn test_void_reverses_stock_once_and_reports_already_voided() {
+        let (transaction_repo, supplier_repo, db_pool) = setup_repository().await;
+        let supplier = create_supplier();
+        supplier_repo.save(supplier.clone(), db_pool.acquire().await.unwrap()).await.unwrap();
+        let mut transaksi = create_transaction(&supplier);
+        transaksi.jumlah_barang = 40;
+        transaction_repo.save(transaksi.clone(), db_pool.acquire().await.unwrap()).await.unwrap();
+
+        let outcome = transaction_repo.void(&transaksi.id, db_pool.acquire().await.unwrap()).await.unwrap();
+        match outcome {
+            VoidSupplierTransactionOutcome::Voided(voided) => {
+                assert!(voided.voided);
+                assert_eq!(voided.jumlah_barang, 40);
+            }
+            VoidSupplierTransactionOutcome::AlreadyVoided => panic!("First void should succeed"),
+        }
+
+        let outcome = transaction_repo.void(&transaksi.id, db_pool.acquire().await.unwrap()).await.unwrap();
+        assert!(matches!(outcome, VoidSupplierTransactionOutcome::AlreadyVoided));
+        let jumlah_barang: i32 = sqlx::query_scalar("SELECT jumlah_barang FROM suppliers WHERE id = $1")
+            .bind(&supplier.id)
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(jumlah_barang, 60);
+
+        let missing = transaction_repo.void("TRX-NON-EXISTENT", db_pool.acquire().await.unwrap()).await;
+        assert!(matches!(missing, Err(sqlx::Error::RowNotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_tanggal_transaksi_round_trips_and_reads_legacy_formats() {
+        use chrono::TimeZone;
+        let (transaction_repo, supplier_repo, db_pool) = setup_repository().await;
+        let supplier = create_supplier();
+        supplier_repo.save(supplier.clone(), db_pool.acquire().await.unwrap()).await.unwrap();
+
+        let mut transaksi = create_transaction(&supplier);
+        transaksi.tanggal_transaksi = Utc.with_ymd_and_hms(2024, 1, 5, 8, 0, 0).unwrap() + chrono::Duration::nanoseconds(123_456_789);
+        transaction_repo.save(transaksi.clone(), db_pool.acquire().await.unwrap()).await.unwrap();
+        let found = transaction_repo.find_by_id(&transaksi.id, db_pool.acquire().await.unwrap()).await.unwrap();
+        assert_eq!(found.tanggal_transaksi, transaksi.tanggal_transaksi);
+
+        for (id, legacy) in [("TRX-LEGACY-1", "2024-02-10T08:00:00+00:00"), ("TRX-LEGACY-2", "2024-03-15 08:00:00")] {
+            sqlx::query(
+                "INSERT INTO supplier_transactions (id, supplier_id, supplier_name, jenis_barang, jumlah_barang, pengiriman_info, tanggal_transaksi)
+                 VALUES ($1, $2, 'PT', 'Barang', 1, 'RESI', $3)"
+            )
+                .bind(id)
+                .bind(&supplier.id)
+                .bind(legacy)
+                .execute(&db_pool)
+                .await
+                .unwrap();
+        }
+
+        let legacy = transaction_repo.find_by_id("TRX-LEGACY-2", db_pool.acquire().await.unwrap()).await.unwrap();
+        assert_eq!(legacy.tanggal_transaksi, Utc.with_ymd_and_hms(2024, 3, 15, 8, 0, 0).unwrap());
+
+        let performance = transaction_repo.performance_by_supplier_id(&supplier.id, db_pool.acquire().await.unwrap()).await.unwrap();
+        assert_eq!(performance.pengiriman_pertama, Some(transaksi.tanggal_transaksi));
+        assert_eq!(performance.pengiriman_terakhir, Some(legacy.tanggal_transaksi));
+
+        sqlx::query("INSERT INTO supplier_transactions (id, supplier_id, supplier_name, jenis_barang, jumlah_barang, pengiriman_info, tanggal_transaksi) VALUES ('TRX-BAD', $1, 'PT', 'Barang', 1, 'RESI', 'kemarin')")
+            .bind(&supplier.id)
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        let bad = transaction_repo.find_by_id("TRX-BAD", db_pool.acquire().await.unwrap()).await;
+        assert!(matches!(bad, Err(sqlx::Error::ColumnDecode { .. })));
+    }
 }
\ No newline at end of file
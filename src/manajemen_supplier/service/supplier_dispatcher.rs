@@ -82,6 +82,8 @@ mod tests {
             jumlah_barang: 100,
             resi: "DISPRESI123".to_string(),
             updated_at: Utc::now().to_rfc3339(),
+            contact: Default::default(),
+            audit: Default::default(),
         }
     }
 
@@ -1,7 +1,43 @@
-use crate::manajemen_supplier::model::{supplier::Supplier, supplier_transaction::SupplierTransaction};
+use crate::manajemen_supplier::model::{supplier::{Supplier, SupplierContact, SupplierStats}, supplier_transaction::{SupplierPerformance, SupplierTransaction}};
 use async_trait::async_trait;
 use mockall::automock;
 use sqlx::{Any, Pool};
+use std::fmt;
+use rocket::http::Status;
+use crate::common::pagination::ListQuery;
+use crate::common::service_result::ServiceError;
+
+/// Error `SupplierService` yang jenisnya ditentukan di tempat error terjadi, sehingga handler
+/// cukup meneruskan status HTTP-nya tanpa mencocokkan isi pesan.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SupplierError {
+    NotFound(String),
+    Validation(String),
+    Conflict(String),
+    Internal(String),
+}
+
+impl fmt::Display for SupplierError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SupplierError::NotFound(message)
+            | SupplierError::Validation(message)
+            | SupplierError::Conflict(message)
+            | SupplierError::Internal(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl ServiceError for SupplierError {
+    fn status(&self) -> Status {
+        match self {
+            SupplierError::NotFound(_) => Status::NotFound,
+            SupplierError::Validation(_) => Status::BadRequest,
+            SupplierError::Conflict(_) => Status::Conflict,
+            SupplierError::Internal(_) => Status::InternalServerError,
+        }
+    }
+}
 
 #[async_trait]
 #[automock]
@@ -13,7 +49,9 @@ pub trait SupplierService: Send + Sync {
         jenis_barang: String,
         jumlah_barang: i32,
         resi: String,
-    ) -> Result<Supplier, String>;
+        contact: SupplierContact,
+        actor: Option<String>,
+    ) -> Result<Supplier, SupplierError>;
 
     async fn update_supplier(
         &self,
@@ -23,11 +61,23 @@ pub trait SupplierService: Send + Sync {
         jenis_barang: String,
         jumlah_barang: i32,
         resi: String,
-    ) -> Result<(), String>;
+        contact: SupplierContact,
+        actor: Option<String>,
+    ) -> Result<(), SupplierError>;
 
-    async fn delete_supplier(&self, db_pool: Pool<Any>, id: &str) -> Result<(), String>;
+    /// Secara default penghapusan ditolak jika supplier masih memiliki transaksi;
+    /// `cascade = true` ikut menghapus transaksi tersebut dalam satu DB transaction.
+    async fn delete_supplier(&self, db_pool: Pool<Any>, id: &str, cascade: bool) -> Result<(), SupplierError>;
     async fn get_supplier(&self, db_pool: Pool<Any>, id: &str) -> Result<Option<Supplier>, String>;
-    async fn get_all_suppliers(&self, db_pool: Pool<Any>) -> Result<Vec<Supplier>, String>;
+    /// `sort`, `keyword` dan paginasi `list` diterapkan di SQL; `sort` yang tidak dikenal ditolak.
+    async fn get_all_suppliers(&self, db_pool: Pool<Any>, list: &ListQuery) -> Result<Vec<Supplier>, SupplierError>;
     async fn get_all_supplier_transactions(&self, db_pool: Pool<Any>) -> Result<Vec<SupplierTransaction>, String>;
+    /// Transaksi satu supplier, terbaru lebih dulu; `Ok(None)` jika supplier tidak ditemukan.
+    async fn get_supplier_transactions(&self, db_pool: Pool<Any>, id: &str) -> Result<Option<Vec<SupplierTransaction>>, String>;
+    async fn merge_suppliers(&self, db_pool: Pool<Any>, keep_id: &str, dup_id: &str) -> Result<u64, SupplierError>;
+    async fn void_supplier_transaction(&self, db_pool: Pool<Any>, id: &str) -> Result<SupplierTransaction, SupplierError>;
+    /// `Ok(None)` jika supplier tidak ditemukan.
+    async fn get_supplier_performance(&self, db_pool: Pool<Any>, id: &str) -> Result<Option<SupplierPerformance>, String>;
+    async fn get_supplier_stats(&self, db_pool: Pool<Any>) -> Result<SupplierStats, String>;
 
 }
\ No newline at end of file
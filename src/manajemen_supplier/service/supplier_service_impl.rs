@@ -4,12 +4,14 @@ use sqlx::{Any, Pool, Error as SqlxError};
 use chrono::Utc; 
 use uuid::Uuid; 
 
-use crate::manajemen_supplier::model::supplier::Supplier;
-use crate::manajemen_supplier::model::supplier_transaction::SupplierTransaction;
-use crate::manajemen_supplier::repository::supplier_repository::SupplierRepository;
-use crate::manajemen_supplier::repository::supplier_transaction_repository::SupplierTransactionRepository;
+use crate::common::audit::AuditTrail;
+use crate::common::pagination::ListQuery;
+use crate::manajemen_supplier::model::supplier::{Supplier, SupplierContact, SupplierStats};
+use crate::manajemen_supplier::model::supplier_transaction::{SupplierPerformance, SupplierTransaction};
+use crate::manajemen_supplier::repository::supplier_repository::{DeleteSupplierOutcome, SupplierRepository, DEFAULT_SUPPLIER_ORDER_BY, SUPPLIER_SORT_COLUMNS};
+use crate::manajemen_supplier::repository::supplier_transaction_repository::{SupplierTransactionRepository, VoidSupplierTransactionOutcome};
 use crate::manajemen_supplier::service::supplier_notifier::SupplierNotifier;
-use crate::manajemen_supplier::service::supplier_service::SupplierService;
+use crate::manajemen_supplier::service::supplier_service::{SupplierError, SupplierService};
 
 pub struct SupplierServiceImpl {
     supplier_repo: Arc<dyn SupplierRepository>,
@@ -17,6 +19,17 @@ pub struct SupplierServiceImpl {
     dispatcher: Arc<dyn SupplierNotifier>,
 }
 
+// Jumlah negatif ditolak, bukan dinolkan, supaya kesalahan klien tidak tersembunyi
+fn validate_jumlah_barang(jumlah_barang: i32) -> Result<(), SupplierError> {
+    if jumlah_barang < 0 {
+        return Err(SupplierError::Validation(format!(
+            "Service: Validation error: jumlah_barang must not be negative (got {}).",
+            jumlah_barang
+        )));
+    }
+    Ok(())
+}
+
 impl SupplierServiceImpl {
     pub fn new(
         supplier_repo: Arc<dyn SupplierRepository>,
@@ -36,9 +49,13 @@ impl SupplierService for SupplierServiceImpl {
         jenis_barang: String,
         jumlah_barang: i32,
         resi: String,
-    ) -> Result<Supplier, String> {
+        contact: SupplierContact,
+        actor: Option<String>,
+    ) -> Result<Supplier, SupplierError> {
+        validate_jumlah_barang(jumlah_barang)?;
+        let contact = contact.normalized().map_err(|e| SupplierError::Validation(format!("Service: {e}")))?;
         let conn = db_pool.acquire().await
-            .map_err(|e| format!("Service: Failed to acquire DB connection: {}", e))?;
+            .map_err(|e| SupplierError::Internal(format!("Service: Failed to acquire DB connection: {}", e)))?;
         
         let supplier_to_save = Supplier {
             id: Uuid::new_v4().to_string(), 
@@ -47,10 +64,12 @@ impl SupplierService for SupplierServiceImpl {
             jumlah_barang,
             resi,
             updated_at: Utc::now().to_rfc3339(), 
+            contact,
+            audit: AuditTrail::created_by(actor.as_deref()),
         };
         
         let saved_supplier = self.supplier_repo.save(supplier_to_save, conn).await
-            .map_err(|e| format!("Service: Repository save error: {}", e))?;
+            .map_err(|e| SupplierError::Internal(format!("Service: Repository save error: {}", e)))?;
 
         self.dispatcher.notify_supplier_saved(&saved_supplier).await;
         Ok(saved_supplier)
@@ -64,9 +83,13 @@ impl SupplierService for SupplierServiceImpl {
         jenis_barang: String,
         jumlah_barang: i32,
         resi: String,
-    ) -> Result<(), String> { 
+        contact: SupplierContact,
+        actor: Option<String>,
+    ) -> Result<(), SupplierError> { 
+        validate_jumlah_barang(jumlah_barang)?;
+        let contact = contact.normalized().map_err(|e| SupplierError::Validation(format!("Service: {e}")))?;
         let conn = db_pool.acquire().await
-            .map_err(|e| format!("Service: Failed to acquire DB connection: {}", e))?;
+            .map_err(|e| SupplierError::Internal(format!("Service: Failed to acquire DB connection: {}", e)))?;
         let supplier_to_update = Supplier {
             id,
             name,
@@ -74,26 +97,108 @@ impl SupplierService for SupplierServiceImpl {
             jumlah_barang,
             resi,
             updated_at: Utc::now().to_rfc3339(), 
+            contact,
+            audit: AuditTrail { created_by: None, updated_by: actor },
         };
         
         self.supplier_repo.update(supplier_to_update, conn).await
             .map_err(|e| match e {
-                SqlxError::RowNotFound => "Service: Supplier not found for update.".to_string(),
-                _ => format!("Service: Repository update error: {}", e),
+                SqlxError::RowNotFound => SupplierError::NotFound("Service: Supplier not found for update.".to_string()),
+                _ => SupplierError::Internal(format!("Service: Repository update error: {}", e)),
             })
     }
 
-    async fn delete_supplier(&self, db_pool: Pool<Any>, id: &str) -> Result<(), String> {
+    async fn delete_supplier(&self, db_pool: Pool<Any>, id: &str, cascade: bool) -> Result<(), SupplierError> {
         let conn = db_pool.acquire().await
-            .map_err(|e| format!("Service: Failed to acquire DB connection: {}", e))?;
+            .map_err(|e| SupplierError::Internal(format!("Service: Failed to acquire DB connection: {}", e)))?;
 
-        self.supplier_repo.delete(id, conn).await
+        let outcome = self.supplier_repo.delete(id, cascade, conn).await
             .map_err(|e| match e {
-                SqlxError::RowNotFound => "Service: Supplier not found for delete.".to_string(),
-                _ => format!("Service: Repository delete error: {}", e),
+                SqlxError::RowNotFound => SupplierError::NotFound("Service: Supplier not found for delete.".to_string()),
+                _ => SupplierError::Internal(format!("Service: Repository delete error: {}", e)),
+            })?;
+
+        match outcome {
+            DeleteSupplierOutcome::Deleted => Ok(()),
+            DeleteSupplierOutcome::BlockedByTransactions(count) => Err(SupplierError::Conflict(format!(
+                "Service: Supplier still has {} transaction(s); use cascade=true to delete them.",
+                count
+            ))),
+        }
+    }
+
+    async fn merge_suppliers(&self, db_pool: Pool<Any>, keep_id: &str, dup_id: &str) -> Result<u64, SupplierError> {
+        if keep_id == dup_id {
+            return Err(SupplierError::Validation("Service: Cannot merge a supplier into itself.".to_string()));
+        }
+
+        let conn = db_pool.acquire().await
+            .map_err(|e| SupplierError::Internal(format!("Service: Failed to acquire DB connection: {}", e)))?;
+
+        self.supplier_repo.merge(keep_id, dup_id, conn).await
+            .map_err(|e| match e {
+                SqlxError::RowNotFound => SupplierError::NotFound("Service: Supplier not found for merge.".to_string()),
+                _ => SupplierError::Internal(format!("Service: Repository merge error: {}", e)),
             })
     }
 
+    async fn void_supplier_transaction(&self, db_pool: Pool<Any>, id: &str) -> Result<SupplierTransaction, SupplierError> {
+        let conn = db_pool.acquire().await
+            .map_err(|e| SupplierError::Internal(format!("Service: Failed to acquire DB connection: {}", e)))?;
+
+        let outcome = self.transaction_repo.void(id, conn).await
+            .map_err(|e| match e {
+                SqlxError::RowNotFound => SupplierError::NotFound("Service: Supplier transaction not found for void.".to_string()),
+                _ => SupplierError::Internal(format!("Service: Repository void error: {}", e)),
+            })?;
+
+        match outcome {
+            VoidSupplierTransactionOutcome::Voided(transaction) => Ok(transaction),
+            VoidSupplierTransactionOutcome::AlreadyVoided => {
+                Err(SupplierError::Conflict("Service: Supplier transaction is already voided.".to_string()))
+            }
+        }
+    }
+
+    async fn get_supplier_transactions(&self, db_pool: Pool<Any>, id: &str) -> Result<Option<Vec<SupplierTransaction>>, String> {
+        if self.get_supplier(db_pool.clone(), id).await?.is_none() {
+            return Ok(None);
+        }
+
+        let conn = db_pool.acquire().await
+            .map_err(|e| format!("Service: Failed to acquire DB connection: {}", e))?;
+
+        self.transaction_repo.find_by_supplier_id(id, conn).await
+            .map(Some)
+            .map_err(|e| format!("Service: Repository error: {}", e))
+    }
+
+    async fn get_supplier_performance(&self, db_pool: Pool<Any>, id: &str) -> Result<Option<SupplierPerformance>, String> {
+        if self.get_supplier(db_pool.clone(), id).await?.is_none() {
+            return Ok(None);
+        }
+
+        let conn = db_pool.acquire().await
+            .map_err(|e| format!("Service: Failed to acquire DB connection: {}", e))?;
+
+        self.transaction_repo.performance_by_supplier_id(id, conn).await
+            .map(Some)
+            .map_err(|e| format!("Service: Repository error: {}", e))
+    }
+
+    async fn get_supplier_stats(&self, db_pool: Pool<Any>) -> Result<SupplierStats, String> {
+        let conn = db_pool.acquire().await
+            .map_err(|e| format!("Service: Failed to acquire DB connection: {}", e))?;
+
+        let by_jenis_barang = self.supplier_repo.stats_by_jenis_barang(conn).await
+            .map_err(|e| format!("Service: Repository error: {}", e))?;
+
+        Ok(SupplierStats {
+            total_suppliers: by_jenis_barang.iter().map(|stats| stats.count).sum(),
+            by_jenis_barang,
+        })
+    }
+
     async fn get_supplier(&self, db_pool: Pool<Any>, id: &str) -> Result<Option<Supplier>, String> {
         let conn = match db_pool.acquire().await {
             Ok(c) => c,
@@ -110,18 +215,21 @@ impl SupplierService for SupplierServiceImpl {
         }
     }
 
-    async fn get_all_suppliers(&self, db_pool: Pool<Any>) -> Result<Vec<Supplier>, String> {
+    async fn get_all_suppliers(&self, db_pool: Pool<Any>, list: &ListQuery) -> Result<Vec<Supplier>, SupplierError> {
+        let order_by = list.order_by(SUPPLIER_SORT_COLUMNS, "id ASC")
+            .map_err(|e| SupplierError::Validation(format!("Service: Validation error: {e}")))?;
+        let order_by = order_by.as_deref().unwrap_or(DEFAULT_SUPPLIER_ORDER_BY);
         let conn = match db_pool.acquire().await {
             Ok(c) => c,
             Err(e) => {
-                return Err(format!("Service: Failed to acquire DB connection: {e}"));
+                return Err(SupplierError::Internal(format!("Service: Failed to acquire DB connection: {e}")));
             }
         };
 
-        match self.supplier_repo.find_all(conn).await {
+        match self.supplier_repo.find_all(order_by, list, conn).await {
             Ok(s) => Ok(s),
             Err(e) => {
-                return Err(format!("Service: Repository error: {e}"));
+                return Err(SupplierError::Internal(format!("Service: Repository error: {e}")));
             }
         }
     }
@@ -170,7 +278,40 @@ mod tests {
             jumlah_barang: 10,
             resi: "Test Resi".to_string(),
             updated_at: Utc::now().to_rfc3339(),
+            contact: Default::default(),
+            audit: Default::default(),
+        }
+    }
+
+
+    #[test]
+    fn test_supplier_error_status_and_message() {
+        use crate::common::service_result::ServiceError;
+        use rocket::http::Status;
+
+        let cases = [
+            (SupplierError::NotFound("Service: Supplier not found for update.".to_string()), Status::NotFound),
+            (SupplierError::Validation("Service: Cannot merge a supplier into itself.".to_string()), Status::BadRequest),
+            (SupplierError::Conflict("Service: Supplier transaction is already voided.".to_string()), Status::Conflict),
+            (SupplierError::Internal("Service: Repository update error: disk full".to_string()), Status::InternalServerError),
+        ];
+        for (error, status) in &cases {
+            assert_eq!(error.status(), *status, "{error}");
         }
+        assert_eq!(cases[1].0.to_string(), "Service: Cannot merge a supplier into itself.");
+    }
+
+    #[tokio::test]
+    async fn test_merge_suppliers_into_itself_is_validation_error() {
+        let service = SupplierServiceImpl::new(
+            Arc::new(MockSupplierRepository::new()),
+            Arc::new(MockSupplierTransactionRepository::new()),
+            Arc::new(MockSupplierNotifier::new()),
+        );
+        let pool = create_dummy_pool().await;
+
+        let result = service.merge_suppliers(pool, "sup-1", "sup-1").await;
+        assert_eq!(result.unwrap_err(), SupplierError::Validation("Service: Cannot merge a supplier into itself.".to_string()));
     }
 
     #[tokio::test]
@@ -207,7 +348,7 @@ mod tests {
         );
         let pool = create_dummy_pool().await;
 
-        let result = service.save_supplier(pool, name.clone(), jenis_barang.clone(), jumlah_barang, resi.clone()).await;
+        let result = service.save_supplier(pool, name.clone(), jenis_barang.clone(), jumlah_barang, resi.clone(), SupplierContact::default(), Some("admin".to_string())).await;
         assert!(result.is_ok());
         let saved_supplier = result.unwrap();
         assert_eq!(saved_supplier.name, name);
@@ -275,7 +416,7 @@ mod tests {
 
         mock_repo.expect_find_all()
             .times(1)
-            .returning(move |_conn| {
+            .returning(move |_order_by, _list, _conn| {
                 let suppliers = expected_suppliers_cl.clone();
                 Box::pin(async move { Ok(suppliers) })
             });
@@ -283,7 +424,7 @@ mod tests {
         let service = SupplierServiceImpl::new(Arc::new(mock_repo), Arc::new(mock_transaction_repo), Arc::new(mock_notifier));
         let pool = create_dummy_pool().await;
 
-        let result = service.get_all_suppliers(pool).await;
+        let result = service.get_all_suppliers(pool, &ListQuery::default()).await;
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), expected_suppliers);
     }
@@ -296,15 +437,29 @@ mod tests {
 
         mock_repo.expect_find_all()
             .times(1)
-            .returning(move |_conn| Box::pin(async move { Ok(Vec::new()) }));
+            .returning(move |_order_by, _list, _conn| Box::pin(async move { Ok(Vec::new()) }));
 
         let service = SupplierServiceImpl::new(Arc::new(mock_repo), Arc::new(mock_transaction_repo), Arc::new(mock_notifier));
         let pool = create_dummy_pool().await;
 
-        let result = service.get_all_suppliers(pool).await;
+        let result = service.get_all_suppliers(pool, &ListQuery::default()).await;
         assert!(result.is_ok());
         assert!(result.unwrap().is_empty());
     }
+
+    #[tokio::test]
+    async fn test_get_all_suppliers_rejects_unknown_sort_before_querying() {
+        let mock_repo = MockSupplierRepository::new();
+        let mock_notifier = MockSupplierNotifier::new();
+        let mock_transaction_repo = MockSupplierTransactionRepository::new();
+
+        let service = SupplierServiceImpl::new(Arc::new(mock_repo), Arc::new(mock_transaction_repo), Arc::new(mock_notifier));
+        let pool = create_dummy_pool().await;
+
+        let list = ListQuery { sort: Some("resi".to_string()), ..Default::default() };
+        let error = service.get_all_suppliers(pool, &list).await.unwrap_err();
+        assert_eq!(error, SupplierError::Validation("Service: Validation error: Invalid sort parameter: resi".to_string()));
+    }
     
     #[tokio::test]
     async fn test_update_supplier_success() {
@@ -331,7 +486,7 @@ mod tests {
 
         let service = SupplierServiceImpl::new(Arc::new(mock_repo), Arc::new(mock_transaction_repo), Arc::new(mock_notifier));
         let pool = create_dummy_pool().await;
-        let result = service.update_supplier(pool, id.clone(), name, jenis_barang, jumlah_barang, resi).await;
+        let result = service.update_supplier(pool, id.clone(), name, jenis_barang, jumlah_barang, resi, SupplierContact::default(), None).await;
         assert!(result.is_ok());
     }
 
@@ -347,9 +502,9 @@ mod tests {
 
         let service = SupplierServiceImpl::new(Arc::new(mock_repo), Arc::new(mock_transaction_repo), Arc::new(mock_notifier));
         let pool = create_dummy_pool().await;
-        let result = service.update_supplier(pool, "non-existent".to_string(), "N".to_string(), "J".to_string(), 1, "R".to_string()).await;
+        let result = service.update_supplier(pool, "non-existent".to_string(), "N".to_string(), "J".to_string(), 1, "R".to_string(), SupplierContact::default(), None).await;
         assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Service: Supplier not found for update.");
+        assert_eq!(result.unwrap_err(), SupplierError::NotFound("Service: Supplier not found for update.".to_string()));
     }
 
     #[tokio::test]
@@ -360,16 +515,53 @@ mod tests {
         let supplier_id = "sup-to-delete";
 
         mock_repo.expect_delete()
-            .with(eq(supplier_id), always())
+            .with(eq(supplier_id), eq(false), always())
             .times(1)
-            .returning(|_, _| Box::pin(async { Ok(()) }));
+            .returning(|_, _, _| Box::pin(async { Ok(DeleteSupplierOutcome::Deleted) }));
 
         let service = SupplierServiceImpl::new(Arc::new(mock_repo), Arc::new(mock_transaction_repo), Arc::new(mock_notifier));
         let pool = create_dummy_pool().await;
-        let result = service.delete_supplier(pool, supplier_id).await;
+        let result = service.delete_supplier(pool, supplier_id, false).await;
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_delete_supplier_blocked_by_transactions() {
+        let mut mock_repo = MockSupplierRepository::new();
+        let mock_notifier = MockSupplierNotifier::new();
+        let mock_transaction_repo = MockSupplierTransactionRepository::new();
+
+        mock_repo.expect_delete()
+            .with(eq("sup-with-trx"), eq(false), always())
+            .times(1)
+            .returning(|_, _, _| Box::pin(async { Ok(DeleteSupplierOutcome::BlockedByTransactions(2)) }));
+
+        let service = SupplierServiceImpl::new(Arc::new(mock_repo), Arc::new(mock_transaction_repo), Arc::new(mock_notifier));
+        let pool = create_dummy_pool().await;
+        let result = service.delete_supplier(pool, "sup-with-trx", false).await;
+        assert_eq!(
+            result.unwrap_err(),
+            SupplierError::Conflict("Service: Supplier still has 2 transaction(s); use cascade=true to delete them.".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_void_supplier_transaction_already_voided() {
+        let mock_repo = MockSupplierRepository::new();
+        let mock_notifier = MockSupplierNotifier::new();
+        let mut mock_transaction_repo = MockSupplierTransactionRepository::new();
+
+        mock_transaction_repo.expect_void()
+            .with(eq("trx-voided"), always())
+            .times(1)
+            .returning(|_, _| Ok(VoidSupplierTransactionOutcome::AlreadyVoided));
+
+        let service = SupplierServiceImpl::new(Arc::new(mock_repo), Arc::new(mock_transaction_repo), Arc::new(mock_notifier));
+        let pool = create_dummy_pool().await;
+        let result = service.void_supplier_transaction(pool, "trx-voided").await;
+        assert_eq!(result.unwrap_err(), SupplierError::Conflict("Service: Supplier transaction is already voided.".to_string()));
+    }
+
     #[tokio::test]
     async fn test_delete_supplier_not_found() {
         let mut mock_repo = MockSupplierRepository::new();
@@ -378,13 +570,13 @@ mod tests {
 
         mock_repo.expect_delete()
             .times(1)
-            .returning(|_, _| Box::pin(async { Err(SqlxError::RowNotFound) }));
+            .returning(|_, _, _| Box::pin(async { Err(SqlxError::RowNotFound) }));
 
         let service = SupplierServiceImpl::new(Arc::new(mock_repo), Arc::new(mock_transaction_repo), Arc::new(mock_notifier));
         let pool = create_dummy_pool().await;
-        let result = service.delete_supplier(pool, "non-existent").await;
+        let result = service.delete_supplier(pool, "non-existent", false).await;
         assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Service: Supplier not found for delete.");
+        assert_eq!(result.unwrap_err(), SupplierError::NotFound("Service: Supplier not found for delete.".to_string()));
     }
 
      #[tokio::test]
@@ -451,9 +643,11 @@ mod tests {
         let pool = AnyPoolOptions::new().max_connections(1).connect("sqlite::memory:").await.expect("Pool creation failed");
         pool.close().await;
 
-        let result = service.get_all_suppliers(pool).await;
-        assert!(result.is_err());
-        let err_msg = result.unwrap_err();
+        let result = service.get_all_suppliers(pool, &ListQuery::default()).await;
+        let err_msg = match result.unwrap_err() {
+            SupplierError::Internal(message) => message,
+            other => panic!("expected internal error, got {other:?}"),
+        };
         assert!(err_msg.starts_with("Service: Failed to acquire DB connection:"));
         assert!(err_msg.contains("closed pool"));
     }
@@ -466,14 +660,16 @@ mod tests {
 
         mock_repo.expect_find_all()
             .times(1)
-            .returning(|_conn| Box::pin(async { Err(SqlxError::PoolTimedOut) }));
+            .returning(|_order_by, _list, _conn| Box::pin(async { Err(SqlxError::PoolTimedOut) }));
 
         let service = SupplierServiceImpl::new(Arc::new(mock_repo), Arc::new(mock_transaction_repo), Arc::new(mock_notifier));
         let pool = create_dummy_pool().await;
 
-        let result = service.get_all_suppliers(pool).await;
-        assert!(result.is_err());
-        let err_msg = result.unwrap_err();
+        let result = service.get_all_suppliers(pool, &ListQuery::default()).await;
+        let err_msg = match result.unwrap_err() {
+            SupplierError::Internal(message) => message,
+            other => panic!("expected internal error, got {other:?}"),
+        };
         assert!(err_msg.starts_with("Service: Repository error:"));
         assert!(err_msg.contains("pool timed out"));
     }
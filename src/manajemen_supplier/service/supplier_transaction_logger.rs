@@ -74,6 +74,8 @@ mod tests {
             jumlah_barang: 42,
             resi: "LOGRESI001".to_string(),
             updated_at: Utc::now().to_rfc3339(),
+            contact: Default::default(),
+            audit: Default::default(),
         }
     }
 
@@ -100,7 +102,8 @@ mod tests {
                     jenis_barang: trx_input.jenis_barang.clone(),
                     jumlah_barang: trx_input.jumlah_barang,
                     pengiriman_info: trx_input.pengiriman_info.clone(), 
-                    tanggal_transaksi: Utc::now().to_rfc3339()
+                    tanggal_transaksi: Utc::now(),
+                    voided: false,
                 })
             });
 
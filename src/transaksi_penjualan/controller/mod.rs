@@ -1,5 +1,6 @@
 use rocket::{fairing::AdHoc, routes};
 
+pub mod report;
 pub mod transaksi;
 
 pub fn route_stage() -> AdHoc {
@@ -17,6 +18,9 @@ pub fn route_stage() -> AdHoc {
                 // Status operations
                 transaksi::complete_transaksi,
                 transaksi::cancel_transaksi,
+                transaksi::void_transaksi,
+                transaksi::return_transaksi_items,
+                transaksi::get_status_history,
                 
                 // Detail operations
                 transaksi::get_detail_transaksi,
@@ -26,8 +30,15 @@ pub fn route_stage() -> AdHoc {
                 
                 // Additional operations
                 transaksi::get_transaksi_with_details,
-                transaksi::validate_product_stock
+                transaksi::export_transaksi_json,
+                transaksi::validate_product_stock,
+                transaksi::create_payment_plan,
+                transaksi::get_payment_summary,
+                transaksi::get_payment_breakdown_by_method,
+                transaksi::recompute_total
             ],
         )
+        .mount("/api", routes![transaksi::checkout])
+        .mount("/api/reports", routes![report::get_top_products, report::get_sales_by_category])
     })
 }
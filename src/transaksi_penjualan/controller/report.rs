@@ -0,0 +1,187 @@
+use rocket::get;
+use rocket::State;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use sqlx::{Any, Pool};
+use autometrics::autometrics;
+
+use crate::common::messages::{Lang, Messages};
+use crate::transaksi_penjualan::controller::transaksi::Response;
+use crate::transaksi_penjualan::dto::transaksi_request::{SalesByCategoryReport, TopProductReport};
+use crate::transaksi_penjualan::service::transaksi::TransaksiService;
+
+#[autometrics]
+#[get("/top-products?<date_from>&<date_to>&<limit>")]
+pub async fn get_top_products(
+    db: &State<Pool<Any>>,
+    date_from: Option<String>,
+    date_to: Option<String>,
+    limit: Option<usize>,
+    lang: Lang
+) -> Result<Json<Vec<TopProductReport>>, (Status, Json<Response>)> {
+    match TransaksiService::get_top_products(db.inner().clone(), date_from.as_deref(), date_to.as_deref(), limit).await {
+        Ok(report) => Ok(Json(report)),
+        Err(sqlx::Error::RowNotFound) => Err((Status::BadRequest, Json(Response {
+            message: Messages::get("transaksi.invalid_report_date", lang).to_string()
+        }))),
+        Err(_e) => Err((Status::InternalServerError, Json(Response {
+            message: Messages::get("transaksi.top_products_failed", lang).to_string()
+        }))),
+    }
+}
+
+#[autometrics]
+#[get("/sales-by-category?<date_from>&<date_to>")]
+pub async fn get_sales_by_category(
+    db: &State<Pool<Any>>,
+    date_from: Option<String>,
+    date_to: Option<String>,
+    lang: Lang
+) -> Result<Json<Vec<SalesByCategoryReport>>, (Status, Json<Response>)> {
+    match TransaksiService::get_sales_by_category(db.inner().clone(), date_from.as_deref(), date_to.as_deref()).await {
+        Ok(report) => Ok(Json(report)),
+        Err(sqlx::Error::RowNotFound) => Err((Status::BadRequest, Json(Response {
+            message: Messages::get("transaksi.invalid_report_date", lang).to_string()
+        }))),
+        Err(_e) => Err((Status::InternalServerError, Json(Response {
+            message: Messages::get("transaksi.sales_by_category_failed", lang).to_string()
+        }))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::local::asynchronous::Client;
+    use rocket::{routes, async_test};
+    use sqlx::any::install_default_drivers;
+
+    async fn setup() -> (Client, Pool<Any>) {
+        install_default_drivers();
+
+        let db = sqlx::any::AnyPoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        sqlx::migrate!("migrations/test")
+            .run(&db)
+            .await
+            .unwrap();
+
+        let rocket = rocket::build()
+            .manage(db.clone())
+            .mount("/", routes![get_top_products, get_sales_by_category]);
+        let client = Client::tracked(rocket).await.expect("Must provide a valid Rocket instance");
+        (client, db)
+    }
+
+    async fn seed_transaksi(db: &Pool<Any>, status: &str, tanggal: &str, lines: &[(i32, i32, f64)]) {
+        let row = sqlx::query("
+                INSERT INTO transaksi (id_pelanggan, nama_pelanggan, tanggal_transaksi, total_harga, status, catatan, created_at, updated_at)
+                VALUES (1, 'Pelanggan', $1, 0, $2, NULL, $1, $1)
+                RETURNING id
+            ")
+            .bind(tanggal)
+            .bind(status)
+            .fetch_one(db)
+            .await
+            .unwrap();
+        let id_transaksi: i32 = sqlx::Row::get(&row, "id");
+
+        for (id_produk, jumlah, harga_satuan) in lines {
+            sqlx::query("
+                    INSERT INTO detail_transaksi (id_transaksi, id_produk, harga_satuan, jumlah, subtotal, created_at, updated_at)
+                    VALUES ($1, $2, $3, $4, $5, $6, $6)
+                ")
+                .bind(id_transaksi)
+                .bind(*id_produk)
+                .bind(*harga_satuan)
+                .bind(*jumlah)
+                .bind(*harga_satuan * *jumlah as f64)
+                .bind(tanggal)
+                .execute(db)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[async_test]
+    async fn test_top_products_ranks_completed_sales_in_range() {
+        let (client, db) = setup().await;
+
+        seed_transaksi(&db, "SELESAI", "2024-03-01 10:00:00", &[(1, 2, 50000.0), (2, 5, 10000.0)]).await;
+        seed_transaksi(&db, "SELESAI", "2024-03-15 16:30:00", &[(1, 4, 50000.0), (3, 1, 250000.0)]).await;
+        // Tidak dihitung: dibatalkan, masih diproses, dan di luar rentang tanggal
+        seed_transaksi(&db, "DIBATALKAN", "2024-03-10 09:00:00", &[(3, 20, 250000.0)]).await;
+        seed_transaksi(&db, "MASIH_DIPROSES", "2024-03-11 09:00:00", &[(2, 20, 10000.0)]).await;
+        seed_transaksi(&db, "SELESAI", "2024-04-01 08:00:00", &[(3, 30, 250000.0)]).await;
+
+        let response = client.get("/top-products?date_from=2024-03-01&date_to=2024-03-31").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let report: Vec<TopProductReport> = response.into_json().await.unwrap();
+        assert_eq!(report, vec![
+            TopProductReport { id_produk: 1, total_jumlah: 6.0, total_pendapatan: 300000.0 },
+            TopProductReport { id_produk: 2, total_jumlah: 5.0, total_pendapatan: 50000.0 },
+            TopProductReport { id_produk: 3, total_jumlah: 1.0, total_pendapatan: 250000.0 },
+        ]);
+
+        let response = client.get("/top-products?date_from=2024-03-01&date_to=2024-03-31&limit=1").dispatch().await;
+        let report: Vec<TopProductReport> = response.into_json().await.unwrap();
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].id_produk, 1);
+
+        let response = client.get("/top-products").dispatch().await;
+        let report: Vec<TopProductReport> = response.into_json().await.unwrap();
+        assert_eq!(report[0], TopProductReport { id_produk: 3, total_jumlah: 31.0, total_pendapatan: 7750000.0 });
+
+        let response = client.get("/top-products?date_from=bukan-tanggal").dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+
+    async fn seed_produk(db: &Pool<Any>, id: i64, nama: &str, kategori: &str) {
+        sqlx::query("INSERT INTO produk (id, nama, kategori, harga, stok) VALUES ($1, $2, $3, 0, 0)")
+            .bind(id)
+            .bind(nama)
+            .bind(kategori)
+            .execute(db)
+            .await
+            .unwrap();
+    }
+
+    #[async_test]
+    async fn test_sales_by_category_groups_completed_revenue_per_kategori() {
+        let (client, db) = setup().await;
+        seed_produk(&db, 1, "Semen", "Material").await;
+        seed_produk(&db, 2, "Pasir", "Material").await;
+        seed_produk(&db, 3, "Cat Tembok", "Cat").await;
+
+        seed_transaksi(&db, "SELESAI", "2024-03-01 10:00:00", &[(1, 2, 50000.0), (3, 1, 120000.0)]).await;
+        seed_transaksi(&db, "SELESAI", "2024-03-15 16:30:00", &[(2, 5, 10000.0), (9, 3, 5000.0)]).await;
+        // Tidak dihitung: dibatalkan dan di luar rentang tanggal
+        seed_transaksi(&db, "DIBATALKAN", "2024-03-10 09:00:00", &[(3, 20, 120000.0)]).await;
+        seed_transaksi(&db, "SELESAI", "2024-04-01 08:00:00", &[(1, 30, 50000.0)]).await;
+
+        let response = client.get("/sales-by-category?date_from=2024-03-01&date_to=2024-03-31").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let report: Vec<SalesByCategoryReport> = response.into_json().await.unwrap();
+        let row = |kategori: &str, total_qty: f64, total_revenue: f64| SalesByCategoryReport {
+            kategori: kategori.to_string(),
+            total_qty,
+            total_revenue,
+        };
+        assert_eq!(report, vec![
+            row("Material", 7.0, 150000.0),
+            row("Cat", 1.0, 120000.0),
+            row(crate::transaksi_penjualan::service::transaksi::KATEGORI_TIDAK_DIKENAL, 3.0, 15000.0),
+        ]);
+
+        let response = client.get("/sales-by-category").dispatch().await;
+        let report: Vec<SalesByCategoryReport> = response.into_json().await.unwrap();
+        assert_eq!(report[0], row("Material", 37.0, 1650000.0));
+
+        let response = client.get("/sales-by-category?date_to=31-03-2024").dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+}
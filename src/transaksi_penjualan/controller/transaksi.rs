@@ -1,14 +1,32 @@
 use rocket::{get, post, patch, delete, put};
 use rocket::State;
-use rocket::http::Status;
+use rocket::futures::StreamExt;
+use rocket::http::{ContentType, Status};
+use rocket::request::{FromRequest, Outcome};
+use rocket::response::stream::TextStream;
+use rocket::Request;
 use rocket::serde::json::Json;
 use sqlx::{Any, Pool};
 use autometrics::autometrics;
 
 use crate::transaksi_penjualan::model::transaksi::Transaksi;
 use crate::transaksi_penjualan::model::detail_transaksi::DetailTransaksi;
-use crate::transaksi_penjualan::service::transaksi::TransaksiService;
+use crate::transaksi_penjualan::model::transaksi_status_history::TransaksiStatusHistory;
+use crate::transaksi_penjualan::service::transaksi::{CheckoutError, CommitStockError, PriceCheckPolicy, ReturError, TransaksiService, VoidError, MAX_ALASAN_PEMBATALAN_LEN, MAX_IDEMPOTENCY_KEY_LEN};
 use crate::transaksi_penjualan::service::transaksi::TransaksiSearchParams;
+use crate::transaksi_penjualan::enums::status_transaksi::StatusTransaksi;
+use crate::transaksi_penjualan::enums::filter_transaksi::FilterTransaksi;
+use crate::transaksi_penjualan::dto::transaksi_request::{CancelTransaksiRequest, CheckoutRequest, CheckoutResponse, PaymentPlanRequest, PaymentPlanResponse, RecomputeTotalResponse, ReturTransaksiRequest, ReturTransaksiResponse, TransaksiDetailResponse, TransaksiListItem, TransaksiPaymentSummary, VoidTransaksiRequest, VoidTransaksiResponse};
+use crate::auth::guards::auth::AuthenticatedUser;
+use crate::common::audit::actor;
+use crate::common::messages::{Lang, Messages};
+use crate::common::request_id::RequestId;
+use crate::common::money::round_report_amount;
+use crate::manajemen_pembayaran::model::payment::PaymentMethodTotal;
+use crate::manajemen_pembayaran::service::payment_service::{PaymentService, PaymentError};
+use crate::common::pagination::ListQuery;
+use crate::manajemen_produk::service::produk_notifier::ProdukNotifier;
+use std::sync::Arc;
 
 #[derive(serde::Serialize, serde::Deserialize)]
 #[serde(crate = "rocket::serde")]
@@ -24,77 +42,196 @@ pub struct Response {
     pub message: String,
 }
 
+/// Header `Idempotency-Key` opsional; retry dengan key yang sama tidak membuat transaksi baru.
+pub struct IdempotencyKey(pub Option<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IdempotencyKey {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(IdempotencyKey(req.headers().get_one("Idempotency-Key").map(str::to_string)))
+    }
+}
+
 #[autometrics]
-#[get("/?<sort>&<filter>&<keyword>&<status>&<id_pelanggan>&<page>&<limit>")]
+#[get("/?<filter>&<status>&<id_pelanggan>&<date_from>&<date_to>&<with_details>&<list..>")]
 pub async fn get_all_transaksi(
     db: &State<Pool<Any>>, 
-    sort: Option<String>, 
     filter: Option<String>, 
-    keyword: Option<String>,
     status: Option<String>,
     id_pelanggan: Option<i32>,  
-    page: Option<usize>,
-    limit: Option<usize>
-) -> Result<Json<Vec<Transaksi>>, (Status, Json<Response>)> {
+    date_from: Option<String>,
+    date_to: Option<String>,
+    with_details: Option<bool>,
+    list: ListQuery,
+    user: Option<AuthenticatedUser>,
+    lang: Lang
+) -> Result<Json<Vec<TransaksiListItem>>, (Status, Json<Response>)> {
+    if let Some(value) = filter.as_deref() {
+        if FilterTransaksi::from_string(value).is_none() {
+            return Err((Status::BadRequest, Json(Response {
+                message: Messages::format("transaksi.unknown_filter", lang, &[&value, &FilterTransaksi::ALLOWED])
+            })));
+        }
+    }
+
     let search_params = TransaksiSearchParams {
-        sort,
+        sort: list.sort.clone(),
         filter,
-        keyword,
+        keyword: list.keyword.clone(),
         status,
         id_pelanggan,
-        page,
-        limit,
+        date_from,
+        date_to,
+        page: Some(list.page()),
+        limit: Some(list.limit()),
     };
 
     match TransaksiService::search_transaksi_with_pagination(db.inner().clone(), &search_params).await {
-        Ok(result) => {
-            Ok(Json(result.data))
+        Ok(mut result) => {
+            result.data.iter_mut().for_each(|transaksi| transaksi.audit.redact_unless_admin(user.as_ref()));
+            if !with_details.unwrap_or(false) {
+                return Ok(Json(result.data.into_iter()
+                    .map(|transaksi| TransaksiListItem { transaksi, detail_transaksi: None })
+                    .collect()));
+            }
+
+            let mut details = TransaksiService::get_details_grouped(db.inner().clone(), &result.data)
+                .await
+                .map_err(|_| (Status::InternalServerError, Json(Response {
+                    message: Messages::get("transaksi.details_failed", lang).to_string()
+                })))?;
+            Ok(Json(result.data.into_iter()
+                .map(|transaksi| {
+                    let detail_transaksi = Some(details.remove(&transaksi.id).unwrap_or_default());
+                    TransaksiListItem { transaksi, detail_transaksi }
+                })
+                .collect()))
+        }
+        Err(sqlx::Error::RowNotFound) => {
+            Err((Status::BadRequest, Json(Response {
+                message: Messages::get("transaksi.invalid_search", lang).to_string()
+            })))
         }
         Err(_e) => {
             Err((Status::InternalServerError, Json(Response { 
-                message: "Failed to fetch transaksi".to_string() 
+                message: Messages::get("transaksi.fetch_failed", lang).to_string() 
             })))
         }
     }
 }
 
+/// Export JSON Lines (satu transaksi per baris) dalam rentang `tanggal_transaksi`. Baris dikirim
+/// sambil dibaca dari database sehingga hasil besar tidak ditampung di memori.
+#[autometrics]
+#[get("/export.json?<date_from>&<date_to>")]
+pub async fn export_transaksi_json(
+    db: &State<Pool<Any>>,
+    date_from: Option<String>,
+    date_to: Option<String>,
+    user: Option<AuthenticatedUser>,
+    request_id: RequestId,
+    lang: Lang
+) -> Result<(ContentType, TextStream![String]), (Status, Json<Response>)> {
+    let normalize = |value: Option<String>, end_of_day: bool| match value {
+        Some(value) => TransaksiService::normalize_date_bound(&value, end_of_day).map(Some).ok_or_else(|| {
+            (Status::BadRequest, Json(Response {
+                message: Messages::format("transaksi.invalid_date", lang, &[&value])
+            }))
+        }),
+        None => Ok(None),
+    };
+    let date_from = normalize(date_from, false)?;
+    let date_to = normalize(date_to, true)?;
+
+    let conn = db.acquire().await.map_err(|e| {
+        log::error!("[{}] Export transaksi gagal: {}", request_id, e);
+        (Status::InternalServerError, Json(Response {
+            message: Messages::get("transaksi.export_failed", lang).to_string()
+        }))
+    })?;
+    let rows = TransaksiService::export_transaksi(conn, date_from, date_to);
+
+    Ok((ContentType::new("application", "x-ndjson"), TextStream! {
+        let mut rows = std::pin::pin!(rows);
+        while let Some(row) = rows.next().await {
+            // Status 200 sudah terkirim; error di tengah jalan hanya bisa dicatat lalu menghentikan stream
+            let mut transaksi = match row {
+                Ok(transaksi) => transaksi,
+                Err(e) => {
+                    log::error!("[{}] Export transaksi terhenti: {}", request_id, e);
+                    break;
+                }
+            };
+            transaksi.audit.redact_unless_admin(user.as_ref());
+            match serde_json::to_string(&transaksi) {
+                Ok(line) => yield format!("{}\n", line),
+                Err(e) => {
+                    log::error!("[{}] Export transaksi terhenti: {}", request_id, e);
+                    break;
+                }
+            }
+        }
+    }))
+}
+
 #[autometrics]
 #[post("/", data = "<request>")]
 pub async fn create_transaksi(
     db: &State<Pool<Any>>, 
-    request: Json<crate::transaksi_penjualan::dto::transaksi_request::CreateTransaksiRequest>
+    idempotency_key: IdempotencyKey,
+    user: Option<AuthenticatedUser>,
+    request: Json<crate::transaksi_penjualan::dto::transaksi_request::CreateTransaksiRequest>,
+    lang: Lang
 ) -> Result<Json<Response>, (Status, Json<Response>)> {
-    if let Err(err_msg) = request.validate() {
+    if let Some(key) = idempotency_key.0.as_deref() {
+        if key.trim().is_empty() || key.trim().len() > MAX_IDEMPOTENCY_KEY_LEN {
+            return Err((Status::BadRequest, Json(Response {
+                message: Messages::format("transaksi.invalid_idempotency_key", lang, &[&MAX_IDEMPOTENCY_KEY_LEN])
+            })));
+        }
+    }
+
+    if let Err(err_msg) = TransaksiService::validate_line_item_count(request.detail_transaksi.len()) {
         return Err((Status::BadRequest, Json(Response { 
-            message: format!("Validation error: {}", err_msg)
+            message: Messages::format("transaksi.validation_error", lang, &[&err_msg])
         })));
     }
 
-    if let Err(err_msg) = TransaksiService::validate_product_stock(&request.detail_transaksi).await {
+    if let Err(err_msg) = request.validate() {
         return Err((Status::BadRequest, Json(Response { 
-            message: format!("Stock error: {}", err_msg)
+            message: Messages::format("transaksi.validation_error", lang, &[&err_msg])
         })));
     }
 
-    match TransaksiService::create_transaksi_with_details(db.inner().clone(), &request).await {
-        Ok(_new_transaksi) => {
-            Ok(Json(Response { message: "Transaksi created successfully".to_string() }))
+    // Stok diperiksa dan dipotong oleh service terhadap jumlah setelah baris produk kembar digabung
+    let request = match request.merge_duplicate_lines() {
+        Ok(merged) => merged,
+        Err(err_msg) => return Err((Status::BadRequest, Json(Response {
+            message: Messages::format("transaksi.validation_error", lang, &[&err_msg])
+        }))),
+    };
+
+    match TransaksiService::create_transaksi_idempotent(db.inner().clone(), idempotency_key.0.as_deref(), actor(user.as_ref()), &request).await {
+        Ok((_transaksi, _replayed)) => {
+            Ok(Json(Response { message: Messages::get("transaksi.created", lang).to_string() }))
         }
         Err(e) => {
             match e {
                 sqlx::Error::RowNotFound => {
                     Err((Status::BadRequest, Json(Response { 
-                        message: "Validation error or insufficient stock".to_string() 
+                        message: Messages::get("transaksi.insufficient_stock", lang).to_string() 
                     })))
                 }
                 sqlx::Error::Database(ref _db_err) => {
                     Err((Status::InternalServerError, Json(Response { 
-                        message: "Database error occurred".to_string() 
+                        message: Messages::get("transaksi.database_error", lang).to_string() 
                     })))
                 }
                 _ => {
                     Err((Status::InternalServerError, Json(Response { 
-                        message: "Failed to create transaction".to_string() 
+                        message: Messages::get("transaksi.create_failed", lang).to_string() 
                     })))
                 }
             }
@@ -106,12 +243,19 @@ pub async fn create_transaksi(
 #[get("/<id>")]
 pub async fn get_transaksi_by_id(
     db: &State<Pool<Any>>, 
-    id: i32 
-) -> Result<Json<Transaksi>, Status> {
-    match TransaksiService::get_transaksi_by_id(db.inner().clone(), id).await {
-        Ok(transaksi) => Ok(Json(transaksi)),
-        Err(_) => Err(Status::NotFound)
-    }
+    id: i32,
+    user: Option<AuthenticatedUser>
+) -> Result<Json<TransaksiDetailResponse>, Status> {
+    let mut transaksi = match TransaksiService::get_transaksi_by_id(db.inner().clone(), id).await {
+        Ok(transaksi) => transaksi,
+        Err(_) => return Err(Status::NotFound)
+    };
+    transaksi.audit.redact_unless_admin(user.as_ref());
+
+    let total_belanja_pelanggan = TransaksiService::get_total_belanja_pelanggan(db.inner().clone(), transaksi.id_pelanggan)
+        .await
+        .map_err(|_| Status::InternalServerError)?;
+    Ok(Json(TransaksiDetailResponse { transaksi, total_belanja_pelanggan }))
 }
 
 #[autometrics]
@@ -119,25 +263,27 @@ pub async fn get_transaksi_by_id(
 pub async fn update_transaksi(
     db: &State<Pool<Any>>, 
     id: i32,
-    transaksi: Json<Transaksi>
+    user: Option<AuthenticatedUser>,
+    transaksi: Json<Transaksi>,
+    lang: Lang
 ) -> (Status, Json<Response>) {
     if transaksi.id != id {
         return (Status::BadRequest, Json(Response { 
-            message: "Invalid data".to_string() 
+            message: Messages::get("transaksi.invalid_data", lang).to_string() 
         }));
     }
 
-    match TransaksiService::update_transaksi(db.inner().clone(), &transaksi).await {
+    match TransaksiService::update_transaksi_by(db.inner().clone(), &transaksi, actor(user.as_ref())).await {
         Ok(_) => (Status::Ok, Json(Response { 
-            message: "Transaksi updated successfully".to_string() 
+            message: Messages::get("transaksi.updated", lang).to_string() 
         })),
         Err(e) => {
             match e {
                 sqlx::Error::RowNotFound => (Status::Forbidden, Json(Response { 
-                    message: "Transaksi cannot be modified".to_string() 
+                    message: Messages::get("transaksi.not_modifiable", lang).to_string() 
                 })),
                 _ => (Status::InternalServerError, Json(Response { 
-                    message: "Try again later".to_string() 
+                    message: Messages::get("transaksi.try_again_later", lang).to_string() 
                 }))
             }
         }
@@ -148,19 +294,20 @@ pub async fn update_transaksi(
 #[delete("/<id>")]
 pub async fn delete_transaksi(
     db: &State<Pool<Any>>, 
-    id: i32
+    id: i32,
+    lang: Lang
 ) -> (Status, Json<Response>) {
     match TransaksiService::delete_transaksi(db.inner().clone(), id).await {
         Ok(_) => (Status::Ok, Json(Response { 
-            message: "Transaksi deleted successfully".to_string() 
+            message: Messages::get("transaksi.deleted", lang).to_string() 
         })),
         Err(e) => {
             match e {
                 sqlx::Error::RowNotFound => (Status::Forbidden, Json(Response { 
-                    message: "Transaksi cannot be deleted".to_string() 
+                    message: Messages::get("transaksi.not_deletable", lang).to_string() 
                 })),
                 _ => (Status::InternalServerError, Json(Response { 
-                    message: "Failed to delete transaksi".to_string() 
+                    message: Messages::get("transaksi.delete_failed", lang).to_string() 
                 }))
             }
         }
@@ -171,19 +318,21 @@ pub async fn delete_transaksi(
 #[put("/<id>/complete")]
 pub async fn complete_transaksi(
     db: &State<Pool<Any>>, 
-    id: i32
+    id: i32,
+    user: Option<AuthenticatedUser>,
+    lang: Lang
 ) -> (Status, Json<Response>) {
-    match TransaksiService::complete_transaksi(db.inner().clone(), id).await {
+    match TransaksiService::complete_transaksi_by(db.inner().clone(), id, actor(user.as_ref())).await {
         Ok(_) => (Status::Ok, Json(Response { 
-            message: "Transaksi completed successfully".to_string() 
+            message: Messages::get("transaksi.completed", lang).to_string() 
         })),
         Err(e) => {
             match e {
                 sqlx::Error::RowNotFound => (Status::Forbidden, Json(Response { 
-                    message: "Transaksi cannot be completed".to_string() 
+                    message: Messages::get("transaksi.not_completable", lang).to_string() 
                 })),
                 _ => (Status::InternalServerError, Json(Response { 
-                    message: "Failed to complete transaksi".to_string() 
+                    message: Messages::get("transaksi.complete_failed", lang).to_string() 
                 }))
             }
         }
@@ -191,28 +340,155 @@ pub async fn complete_transaksi(
 }
 
 #[autometrics]
-#[put("/<id>/cancel")]
+#[put("/<id>/cancel", data = "<request>")]
 pub async fn cancel_transaksi(
     db: &State<Pool<Any>>, 
-    id: i32
+    id: i32,
+    user: Option<AuthenticatedUser>,
+    request: Option<Json<CancelTransaksiRequest>>,
+    lang: Lang
 ) -> (Status, Json<Response>) {
-    match TransaksiService::cancel_transaksi(db.inner().clone(), id).await {
+    let alasan = request.and_then(|r| r.into_inner().alasan_pembatalan);
+    if alasan.as_deref().is_some_and(|a| a.chars().count() > MAX_ALASAN_PEMBATALAN_LEN) {
+        return (Status::BadRequest, Json(Response {
+            message: Messages::format("transaksi.alasan_pembatalan_too_long", lang, &[&MAX_ALASAN_PEMBATALAN_LEN])
+        }));
+    }
+
+    match TransaksiService::cancel_transaksi_by(db.inner().clone(), id, alasan, actor(user.as_ref())).await {
         Ok(_) => (Status::Ok, Json(Response { 
-            message: "Transaksi cancelled successfully".to_string() 
+            message: Messages::get("transaksi.cancelled", lang).to_string() 
         })),
         Err(e) => {
             match e {
                 sqlx::Error::RowNotFound => (Status::Forbidden, Json(Response { 
-                    message: "Transaksi cannot be cancelled".to_string() 
+                    message: Messages::get("transaksi.not_cancellable", lang).to_string() 
                 })),
                 _ => (Status::InternalServerError, Json(Response { 
-                    message: "Failed to cancel transaksi".to_string() 
+                    message: Messages::get("transaksi.cancel_failed", lang).to_string() 
                 }))
             }
         }
     }
 }
 
+/// Membalik penjualan yang sudah selesai: stok dikembalikan dan pembayaran di-refund.
+#[autometrics]
+#[post("/<id>/void", data = "<request>")]
+pub async fn void_transaksi(
+    db: &State<Pool<Any>>,
+    produk_notifier: &State<Arc<dyn ProdukNotifier>>,
+    id: i32,
+    user: Option<AuthenticatedUser>,
+    request: Option<Json<VoidTransaksiRequest>>,
+    lang: Lang
+) -> (Status, Json<ApiResponse<VoidTransaksiResponse>>) {
+    let alasan = request.and_then(|r| r.into_inner().alasan);
+    if alasan.as_deref().is_some_and(|a| a.chars().count() > MAX_ALASAN_PEMBATALAN_LEN) {
+        return (Status::BadRequest, Json(ApiResponse {
+            success: false,
+            message: Messages::format("transaksi.alasan_too_long", lang, &[&MAX_ALASAN_PEMBATALAN_LEN]),
+            data: None,
+        }));
+    }
+
+    match TransaksiService::void_transaksi_by(db.inner().clone(), id, alasan, actor(user.as_ref()), produk_notifier.inner().as_ref()).await {
+        Ok(mut voided) => {
+            voided.transaksi.audit.redact_unless_admin(user.as_ref());
+            if !user.as_ref().is_some_and(|user| user.is_admin) {
+                for refund in &mut voided.refunds {
+                    refund.created_by = None;
+                }
+            }
+            (Status::Ok, Json(ApiResponse {
+                success: true,
+                message: Messages::get("transaksi.voided", lang).to_string(),
+                data: Some(voided),
+            }))
+        }
+        Err(e) => {
+            let status = match e {
+                VoidError::NotFound(_) => Status::NotFound,
+                VoidError::NotVoidable(_) | VoidError::ProdukNotFound(_) | VoidError::RefundConflict(_) => Status::Conflict,
+                VoidError::Database(_) => Status::InternalServerError,
+            };
+            (status, Json(ApiResponse {
+                success: false,
+                message: e.to_string(),
+                data: None,
+            }))
+        }
+    }
+}
+
+/// Retur sebagian barang dari transaksi SELESAI; transaksi tetap SELESAI.
+#[autometrics]
+#[post("/<id>/return", data = "<request>")]
+pub async fn return_transaksi_items(
+    db: &State<Pool<Any>>,
+    produk_notifier: &State<Arc<dyn ProdukNotifier>>,
+    id: i32,
+    user: Option<AuthenticatedUser>,
+    request: Json<ReturTransaksiRequest>,
+    lang: Lang
+) -> (Status, Json<ApiResponse<ReturTransaksiResponse>>) {
+    if request.alasan.as_deref().is_some_and(|a| a.chars().count() > MAX_ALASAN_PEMBATALAN_LEN) {
+        return (Status::BadRequest, Json(ApiResponse {
+            success: false,
+            message: Messages::format("transaksi.alasan_too_long", lang, &[&MAX_ALASAN_PEMBATALAN_LEN]),
+            data: None,
+        }));
+    }
+
+    match TransaksiService::retur_transaksi_by(db.inner().clone(), id, &request, actor(user.as_ref()), produk_notifier.inner().as_ref()).await {
+        Ok(mut returned) => {
+            if !user.as_ref().is_some_and(|user| user.is_admin) {
+                returned.retur.created_by = None;
+                for refund in &mut returned.refunds {
+                    refund.created_by = None;
+                }
+            }
+            (Status::Created, Json(ApiResponse {
+                success: true,
+                message: Messages::get("transaksi.items_returned", lang).to_string(),
+                data: Some(returned),
+            }))
+        }
+        Err(e) => {
+            let status = match e {
+                ReturError::NotFound(_) => Status::NotFound,
+                ReturError::InvalidRequest(_) => Status::BadRequest,
+                ReturError::NotReturnable(_) | ReturError::ProdukNotFound(_) | ReturError::RefundConflict(_) => Status::Conflict,
+                ReturError::Database(_) => Status::InternalServerError,
+            };
+            (status, Json(ApiResponse {
+                success: false,
+                message: e.to_string(),
+                data: None,
+            }))
+        }
+    }
+}
+
+#[autometrics]
+#[get("/<id>/history")]
+pub async fn get_status_history(
+    db: &State<Pool<Any>>,
+    id: i32,
+    user: Option<AuthenticatedUser>
+) -> Result<Json<Vec<TransaksiStatusHistory>>, Status> {
+    match TransaksiService::get_status_history(db.inner().clone(), id).await {
+        Ok(mut history) => {
+            if !user.is_some_and(|user| user.is_admin) {
+                history.iter_mut().for_each(|entry| entry.changed_by = None);
+            }
+            Ok(Json(history))
+        }
+        Err(sqlx::Error::RowNotFound) => Err(Status::NotFound),
+        Err(_) => Err(Status::InternalServerError),
+    }
+}
+
 #[autometrics]
 #[get("/<id_transaksi>/detail")]
 pub async fn get_detail_transaksi(
@@ -230,25 +506,50 @@ pub async fn get_detail_transaksi(
 pub async fn add_detail_transaksi(
     db: &State<Pool<Any>>, 
     id_transaksi: i32,
-    detail: Json<DetailTransaksi>
+    detail: Json<DetailTransaksi>,
+    request_id: RequestId,
+    lang: Lang
 ) -> Result<Json<Response>, (Status, Json<Response>)> {
     if detail.id_transaksi != id_transaksi {
         return Err((Status::BadRequest, Json(Response { 
-            message: "Invalid transaction ID".to_string() 
+            message: Messages::get("transaksi.invalid_transaction_id", lang).to_string() 
+        })));
+    }
+
+    let policy = PriceCheckPolicy::from_env();
+    let mismatch = if policy == PriceCheckPolicy::Off {
+        None
+    } else {
+        match TransaksiService::check_detail_price(db.inner().clone(), &detail, TransaksiService::price_tolerance_percent()).await {
+            Ok(mismatch) => mismatch,
+            Err(_) => return Err((Status::InternalServerError, Json(Response {
+                message: Messages::get("transaksi.price_check_failed", lang).to_string()
+            }))),
+        }
+    };
+    if let (PriceCheckPolicy::Reject, Some(mismatch)) = (policy, &mismatch) {
+        return Err((Status::BadRequest, Json(Response {
+            message: Messages::format("transaksi.price_mismatch", lang, &[mismatch])
         })));
     }
 
     match TransaksiService::add_detail_transaksi(db.inner().clone(), &detail).await {
         Ok(_) => Ok(Json(Response { 
-            message: "Detail transaksi added successfully".to_string() 
+            message: match mismatch {
+                Some(mismatch) => {
+                    log::warn!("[{}] Detail transaksi {} added with mismatched price: {}", request_id, id_transaksi, mismatch);
+                    Messages::format("transaksi.detail_added_with_warning", lang, &[&mismatch])
+                }
+                None => Messages::get("transaksi.detail_added", lang).to_string(),
+            }
         })),
         Err(e) => {
             match e {
                 sqlx::Error::RowNotFound => Err((Status::Forbidden, Json(Response { 
-                    message: "Transaction cannot be modified".to_string() 
+                    message: Messages::get("transaksi.detail_not_modifiable", lang).to_string() 
                 }))),
                 _ => Err((Status::InternalServerError, Json(Response { 
-                    message: "Failed to add detail transaksi".to_string() 
+                    message: Messages::get("transaksi.detail_add_failed", lang).to_string() 
                 })))
             }
         }
@@ -261,25 +562,26 @@ pub async fn update_detail_transaksi(
     db: &State<Pool<Any>>, 
     id_transaksi: i32,
     id_detail: i32,
-    detail: Json<DetailTransaksi>
+    detail: Json<DetailTransaksi>,
+    lang: Lang
 ) -> Result<Json<Response>, (Status, Json<Response>)> {
     if detail.id != id_detail || detail.id_transaksi != id_transaksi {
         return Err((Status::BadRequest, Json(Response { 
-            message: "Invalid data".to_string() 
+            message: Messages::get("transaksi.invalid_data", lang).to_string() 
         })));
     }
 
     match TransaksiService::update_detail_transaksi(db.inner().clone(), &detail).await {
         Ok(_) => Ok(Json(Response { 
-            message: "Detail transaksi updated successfully".to_string() 
+            message: Messages::get("transaksi.detail_updated", lang).to_string() 
         })),
         Err(e) => {
             match e {
                 sqlx::Error::RowNotFound => Err((Status::Forbidden, Json(Response { 
-                    message: "Transaction cannot be modified".to_string() 
+                    message: Messages::get("transaksi.detail_not_modifiable", lang).to_string() 
                 }))),
                 _ => Err((Status::InternalServerError, Json(Response { 
-                    message: "Failed to update detail transaksi".to_string() 
+                    message: Messages::get("transaksi.detail_update_failed", lang).to_string() 
                 })))
             }
         }
@@ -291,19 +593,20 @@ pub async fn update_detail_transaksi(
 pub async fn delete_detail_transaksi(
     db: &State<Pool<Any>>, 
     id_transaksi: i32,
-    id_detail: i32
+    id_detail: i32,
+    lang: Lang
 ) -> (Status, Json<Response>) {
     match TransaksiService::delete_detail_transaksi(db.inner().clone(), id_detail, id_transaksi).await {
         Ok(_) => (Status::Ok, Json(Response { 
-            message: "Detail transaksi deleted successfully".to_string() 
+            message: Messages::get("transaksi.detail_deleted", lang).to_string() 
         })),
         Err(e) => {
             match e {
                 sqlx::Error::RowNotFound => (Status::Forbidden, Json(Response { 
-                    message: "Transaction cannot be modified".to_string() 
+                    message: Messages::get("transaksi.detail_not_modifiable", lang).to_string() 
                 })),
                 _ => (Status::InternalServerError, Json(Response { 
-                    message: "Failed to delete detail transaksi".to_string() 
+                    message: Messages::get("transaksi.detail_delete_failed", lang).to_string() 
                 }))
             }
         }
@@ -325,6 +628,7 @@ pub async fn get_transaksi_with_details(
         Ok(d) => d,
         Err(_) => vec![]
     };
+    let total_berat_gram = TransaksiService::total_berat_transaksi(db.inner().clone(), &details).await.unwrap_or(None);
 
     let response = crate::transaksi_penjualan::dto::transaksi_request::TransaksiWithDetailsResponse {
         id: transaksi.id,
@@ -334,20 +638,243 @@ pub async fn get_transaksi_with_details(
         total_harga: transaksi.total_harga,
         status: transaksi.status.to_string(),
         catatan: transaksi.catatan,
+        nomor_transaksi: transaksi.nomor_transaksi,
+        alasan_pembatalan: transaksi.alasan_pembatalan,
+        total_berat_gram,
         detail_transaksi: details,
     };
 
     Ok(Json(response))
 }
 
+#[autometrics]
+#[post("/<id>/payment-plan", data = "<request>")]
+pub async fn create_payment_plan(
+    db: &State<Pool<Any>>,
+    payment_service: &State<PaymentService>,
+    id: i32,
+    request: Json<PaymentPlanRequest>,
+    lang: Lang
+) -> (Status, Json<ApiResponse<PaymentPlanResponse>>) {
+    let transaksi = match TransaksiService::get_transaksi_by_id(db.inner().clone(), id).await {
+        Ok(t) => t,
+        Err(sqlx::Error::RowNotFound) => return (Status::NotFound, Json(ApiResponse {
+            success: false,
+            message: Messages::format("transaksi.not_found", lang, &[&id]),
+            data: None,
+        })),
+        Err(_) => return (Status::InternalServerError, Json(ApiResponse {
+            success: false,
+            message: Messages::get("transaksi.fetch_failed", lang).to_string(),
+            data: None,
+        })),
+    };
+
+    if matches!(transaksi.status, StatusTransaksi::Dibatalkan | StatusTransaksi::Void) {
+        return (Status::Conflict, Json(ApiResponse {
+            success: false,
+            message: Messages::get("transaksi.payment_plan_not_allowed", lang).to_string(),
+            data: None,
+        }));
+    }
+
+    let method = match payment_service.parse_payment_method(request.method.as_deref().unwrap_or("BANK_TRANSFER")) {
+        Ok(method) => method,
+        Err(e) => return (Status::BadRequest, Json(ApiResponse {
+            success: false,
+            message: e.to_string(),
+            data: None,
+        })),
+    };
+
+    match payment_service.create_payment_plan(db, &transaksi.id.to_string(), transaksi.total_harga, method, request.jumlah_cicilan).await {
+        Ok((payment, schedule)) => (Status::Created, Json(ApiResponse {
+            success: true,
+            message: Messages::format("transaksi.payment_plan_created", lang, &[&schedule.len()]),
+            data: Some(PaymentPlanResponse { payment, schedule }),
+        })),
+        Err(PaymentError::InvalidInput(msg)) => (Status::BadRequest, Json(ApiResponse {
+            success: false,
+            message: msg,
+            data: None,
+        })),
+        Err(e) => (Status::InternalServerError, Json(ApiResponse {
+            success: false,
+            message: e.to_string(),
+            data: None,
+        })),
+    }
+}
+
+/// Checkout POS sekali jalan; di-mount di `/api/checkout`.
+#[autometrics]
+#[post("/checkout", data = "<request>")]
+pub async fn checkout(
+    db: &State<Pool<Any>>,
+    payment_service: &State<PaymentService>,
+    produk_notifier: &State<Arc<dyn ProdukNotifier>>,
+    user: Option<AuthenticatedUser>,
+    request: Json<CheckoutRequest>,
+    lang: Lang
+) -> (Status, Json<ApiResponse<CheckoutResponse>>) {
+    let method = match payment_service.parse_payment_method(&request.method) {
+        Ok(method) => method,
+        Err(e) => return (Status::BadRequest, Json(ApiResponse {
+            success: false,
+            message: e.to_string(),
+            data: None,
+        })),
+    };
+
+    match TransaksiService::checkout(db.inner().clone(), &request, method, actor(user.as_ref()), produk_notifier.inner().as_ref()).await {
+        Ok(mut checkout) => {
+            checkout.transaksi.audit.redact_unless_admin(user.as_ref());
+            checkout.payment.audit.redact_unless_admin(user.as_ref());
+            (Status::Created, Json(ApiResponse {
+                success: true,
+                message: Messages::get("transaksi.checkout_ok", lang).to_string(),
+                data: Some(checkout),
+            }))
+        }
+        Err(e) => {
+            let status = match e {
+                CheckoutError::InvalidRequest(_) => Status::BadRequest,
+                CheckoutError::Stock(CommitStockError::InsufficientStock { .. }) => Status::Conflict,
+                CheckoutError::Stock(CommitStockError::ProdukNotFound { .. }) => Status::NotFound,
+                CheckoutError::Stock(CommitStockError::Database(_)) | CheckoutError::Database(_) => Status::InternalServerError,
+            };
+            (status, Json(ApiResponse {
+                success: false,
+                message: e.to_string(),
+                data: None,
+            }))
+        }
+    }
+}
+
+#[autometrics]
+#[get("/<id>/payments/summary")]
+pub async fn get_payment_summary(
+    db: &State<Pool<Any>>,
+    payment_service: &State<PaymentService>,
+    id: i32,
+    lang: Lang
+) -> (Status, Json<ApiResponse<TransaksiPaymentSummary>>) {
+    let transaksi = match TransaksiService::get_transaksi_by_id(db.inner().clone(), id).await {
+        Ok(t) => t,
+        Err(sqlx::Error::RowNotFound) => return (Status::NotFound, Json(ApiResponse {
+            success: false,
+            message: Messages::format("transaksi.not_found", lang, &[&id]),
+            data: None,
+        })),
+        Err(_) => return (Status::InternalServerError, Json(ApiResponse {
+            success: false,
+            message: Messages::get("transaksi.fetch_failed", lang).to_string(),
+            data: None,
+        })),
+    };
+
+    match payment_service.get_payment_totals(db, &transaksi.id.to_string()).await {
+        Ok(totals) => (Status::Ok, Json(ApiResponse {
+            success: true,
+            message: Messages::get("transaksi.payment_summary_ok", lang).to_string(),
+            data: Some(TransaksiPaymentSummary {
+                total_harga: round_report_amount(transaksi.total_harga),
+                paid_total: round_report_amount(totals.paid_total),
+                outstanding: round_report_amount((transaksi.total_harga - totals.paid_total).max(0.0)),
+                payment_count: totals.payment_count,
+                last_payment_date: totals.last_payment_date.map(|date| date.to_rfc3339()),
+            }),
+        })),
+        Err(e) => (Status::InternalServerError, Json(ApiResponse {
+            success: false,
+            message: e.to_string(),
+            data: None,
+        })),
+    }
+}
+
+/// Rincian pembayaran transaksi per metode, mis. sebagian tunai dan sebagian kartu.
+#[autometrics]
+#[get("/<id>/payments/by-method")]
+pub async fn get_payment_breakdown_by_method(
+    db: &State<Pool<Any>>,
+    payment_service: &State<PaymentService>,
+    id: i32,
+    lang: Lang
+) -> (Status, Json<ApiResponse<Vec<PaymentMethodTotal>>>) {
+    let transaksi = match TransaksiService::get_transaksi_by_id(db.inner().clone(), id).await {
+        Ok(t) => t,
+        Err(sqlx::Error::RowNotFound) => return (Status::NotFound, Json(ApiResponse {
+            success: false,
+            message: Messages::format("transaksi.not_found", lang, &[&id]),
+            data: None,
+        })),
+        Err(_) => return (Status::InternalServerError, Json(ApiResponse {
+            success: false,
+            message: Messages::get("transaksi.fetch_failed", lang).to_string(),
+            data: None,
+        })),
+    };
+
+    match payment_service.get_payment_totals_by_method(db, &transaksi.id.to_string()).await {
+        Ok(totals) => (Status::Ok, Json(ApiResponse {
+            success: true,
+            message: Messages::get("transaksi.payment_breakdown_ok", lang).to_string(),
+            data: Some(totals),
+        })),
+        Err(e) => (Status::InternalServerError, Json(ApiResponse {
+            success: false,
+            message: e.to_string(),
+            data: None,
+        })),
+    }
+}
+
+#[autometrics]
+#[post("/<id>/recompute-total")]
+pub async fn recompute_total(
+    user: AuthenticatedUser,
+    db: &State<Pool<Any>>,
+    id: i32,
+    lang: Lang
+) -> (Status, Json<ApiResponse<RecomputeTotalResponse>>) {
+    if !user.is_admin {
+        return (Status::Forbidden, Json(ApiResponse {
+            success: false,
+            message: Messages::get("transaksi.recompute_forbidden", lang).to_string(),
+            data: None,
+        }));
+    }
+
+    match TransaksiService::recompute_total(db.inner().clone(), id).await {
+        Ok((old_total, new_total)) => (Status::Ok, Json(ApiResponse {
+            success: true,
+            message: Messages::get("transaksi.recomputed", lang).to_string(),
+            data: Some(RecomputeTotalResponse { id, old_total, new_total }),
+        })),
+        Err(sqlx::Error::RowNotFound) => (Status::NotFound, Json(ApiResponse {
+            success: false,
+            message: Messages::format("transaksi.not_found", lang, &[&id]),
+            data: None,
+        })),
+        Err(_) => (Status::InternalServerError, Json(ApiResponse {
+            success: false,
+            message: Messages::get("transaksi.recompute_failed", lang).to_string(),
+            data: None,
+        })),
+    }
+}
+
 #[autometrics]
 #[post("/validate-stock", data = "<products>")]
 pub async fn validate_product_stock(
-    products: Json<Vec<crate::transaksi_penjualan::dto::transaksi_request::CreateDetailTransaksiRequest>>
+    products: Json<Vec<crate::transaksi_penjualan::dto::transaksi_request::CreateDetailTransaksiRequest>>,
+    lang: Lang
 ) -> Result<Json<Response>, (Status, Json<Response>)> {
     match TransaksiService::validate_product_stock(&products).await {
         Ok(_) => Ok(Json(Response { 
-            message: "All products available".to_string() 
+            message: Messages::get("transaksi.stock_available", lang).to_string() 
         })),
         Err(err_msg) => Err((Status::BadRequest, Json(Response { 
             message: err_msg 
@@ -363,6 +890,7 @@ mod tests {
     use rocket::{routes, uri, Rocket, async_test};
     use sqlx::any::install_default_drivers;
     use crate::transaksi_penjualan::model::transaksi::Transaksi;
+    use crate::manajemen_produk::service::produk_dispatcher::ProdukDispatcher;
 
     async fn setup() -> Rocket<rocket::Build> {
         install_default_drivers();
@@ -377,17 +905,35 @@ mod tests {
             .run(&db)
             .await
             .unwrap();
+        seed_katalog_produk(&db).await;
 
         rocket::build()
             .manage(db.clone())
+            .manage(PaymentService::new())
+            .manage(Arc::new(ProdukDispatcher::new()) as Arc<dyn ProdukNotifier>)
             .mount("/", routes![
                 get_all_transaksi, create_transaksi, get_transaksi_by_id, 
-                update_transaksi, delete_transaksi, complete_transaksi, cancel_transaksi,
+                update_transaksi, delete_transaksi, complete_transaksi, cancel_transaksi, get_status_history,
                 get_detail_transaksi, add_detail_transaksi, update_detail_transaksi, delete_detail_transaksi,
-                get_transaksi_with_details, validate_product_stock
+                get_transaksi_with_details, validate_product_stock, create_payment_plan,
+                get_payment_summary, get_payment_breakdown_by_method, recompute_total, checkout, void_transaksi, return_transaksi_items, export_transaksi_json
             ])
     }
 
+    /// Katalog produk 1-5 untuk test penjualan, yang mengambil harga dan memotong stok dari
+    /// baris produk. `insert_produk_with_stok` hanya menimpa nama dan stok bila id-nya sama.
+    async fn seed_katalog_produk(db: &Pool<Any>) {
+        for (id, harga) in [(1, 100000.0), (2, 250000.0), (3, 500000.0), (4, 75000.0), (5, 150000.0)] {
+            sqlx::query("INSERT INTO produk (id, nama, kategori, harga, stok) VALUES ($1, $2, 'Material', $3, 100)")
+                .bind(id as i64)
+                .bind(format!("Produk {}", id))
+                .bind(harga)
+                .execute(db)
+                .await
+                .unwrap();
+        }
+    }
+
     #[async_test]
     async fn test_create_transaksi_with_validation() {
         let rocket = setup().await;
@@ -397,12 +943,16 @@ mod tests {
             id_pelanggan: 1,
             nama_pelanggan: "Castorice".to_string(),
             catatan: Some("Test transaction".to_string()),
+            total_harga: None,
+            reject_duplicate_produk: false,
             detail_transaksi: vec![
                 crate::transaksi_penjualan::dto::transaksi_request::CreateDetailTransaksiRequest {
                     id_produk: 1,
                     nama_produk: "Contoh Produk".to_string(),
                     harga_satuan: 10000.0,
-                    jumlah: 2,
+                    jumlah: 2.0,
+                    diskon: None,
+                    harga_override: None,
                 },
             ],
         };
@@ -414,7 +964,47 @@ mod tests {
 
         assert_eq!(response.status(), Status::Ok);
         let body: Response = response.into_json().await.unwrap();
-        assert_eq!(body.message, "Transaksi created successfully");
+        assert_eq!(body.message, "Transaksi berhasil dibuat");
+    }
+
+    #[async_test]
+    async fn test_create_transaksi_with_same_idempotency_key_creates_once() {
+        let rocket = setup().await;
+        let client = Client::tracked(rocket).await.expect("Must provide a valid Rocket instance");
+
+        for _ in 0..2 {
+            let response = client.post(uri!(super::create_transaksi))
+                .header(rocket::http::Header::new("Idempotency-Key", "kasir-1-retry-abc"))
+                .json(&payment_plan_transaksi_request("PT Retry"))
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+        }
+
+        let db = client.rocket().state::<Pool<Any>>().unwrap();
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM transaksi")
+            .fetch_one(db)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+        let detail_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM detail_transaksi")
+            .fetch_one(db)
+            .await
+            .unwrap();
+        assert_eq!(detail_count, 1);
+
+        // Key berbeda tetap membuat transaksi baru
+        let response = client.post(uri!(super::create_transaksi))
+            .header(rocket::http::Header::new("Idempotency-Key", "kasir-1-retry-def"))
+            .json(&payment_plan_transaksi_request("PT Retry"))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM transaksi")
+            .fetch_one(db)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
     }
 
     #[async_test]
@@ -429,6 +1019,125 @@ mod tests {
         assert!(body.is_empty() || !body.is_empty());
     }
 
+    #[async_test]
+    async fn test_get_all_transaksi_filter_is_allowlisted_and_keyword_bound() {
+        use crate::transaksi_penjualan::dto::transaksi_request::{CreateDetailTransaksiRequest, CreateTransaksiRequest};
+
+        let rocket = setup().await;
+        let client = Client::tracked(rocket).await.expect("Must provide a valid Rocket instance");
+
+        for (nama, catatan, jumlah) in [("Budi", "antar sore", 1.0), ("Sari", "Budi yang titip", 2.0)] {
+            let request = CreateTransaksiRequest {
+                id_pelanggan: 1,
+                nama_pelanggan: nama.to_string(),
+                catatan: Some(catatan.to_string()),
+                total_harga: None,
+                reject_duplicate_produk: false,
+                detail_transaksi: vec![CreateDetailTransaksiRequest {
+                    id_produk: 1,
+                    nama_produk: "Semen".to_string(),
+                    harga_satuan: 65000.0,
+                    jumlah,
+                    diskon: None,
+                    harga_override: None,
+                }],
+            };
+            let response = client.post("/").json(&request).dispatch().await;
+            assert_eq!(response.status(), Status::Ok);
+        }
+
+        let response = client.get("/?filter=nama_pelanggan&keyword=budi").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let body: Vec<Transaksi> = response.into_json().await.unwrap();
+        assert_eq!(body.iter().map(|t| t.nama_pelanggan.as_str()).collect::<Vec<_>>(), vec!["Budi"]);
+        let budi = body[0].clone();
+
+        // Kolom angka dicocokkan lewat CAST ke teks dengan keyword yang tetap di-bind
+        let response = client.get("/?filter=total_harga&keyword=200").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let body: Vec<Transaksi> = response.into_json().await.unwrap();
+        assert_eq!(body.iter().map(|t| t.nama_pelanggan.as_str()).collect::<Vec<_>>(), vec!["Sari"]);
+
+        let response = client.get("/?filter=total&keyword=100").dispatch().await;
+        let body: Vec<Transaksi> = response.into_json().await.unwrap();
+        assert_eq!(body.iter().map(|t| t.nama_pelanggan.as_str()).collect::<Vec<_>>(), vec!["Budi"]);
+
+        let response = client.get(format!("/?filter=id&keyword={}", budi.id)).dispatch().await;
+        let body: Vec<Transaksi> = response.into_json().await.unwrap();
+        assert_eq!(body.iter().map(|t| t.id).collect::<Vec<_>>(), vec![budi.id]);
+
+        let response = client.get("/?filter=id_pelanggan&keyword=1")
+            .header(rocket::http::Header::new("Accept-Language", "en"))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::BadRequest);
+        let body: Response = response.into_json().await.unwrap();
+        assert!(body.message.contains("Unknown filter 'id_pelanggan'"));
+
+        let response = client.get("/?filter=id_pelanggan%29%20OR%20%281%3D1").dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest);
+
+        // Keyword selalu di-bind: payload SQL hanya dicocokkan sebagai teks biasa
+        let response = client.get("/?filter=all&keyword=%27%20OR%20%271%27%3D%271").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let body: Vec<Transaksi> = response.into_json().await.unwrap();
+        assert!(body.is_empty());
+
+        let db = client.rocket().state::<Pool<Any>>().unwrap();
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM transaksi").fetch_one(db).await.unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[async_test]
+    async fn test_get_all_transaksi_with_details_batches_detail_query() {
+        use crate::common::slow_query::query_counter;
+        use crate::transaksi_penjualan::dto::transaksi_request::{CreateDetailTransaksiRequest, CreateTransaksiRequest};
+
+        let rocket = setup().await;
+        let client = Client::tracked(rocket).await.expect("Must provide a valid Rocket instance");
+
+        for (nama, jumlah) in [("Pelanggan A", 1), ("Pelanggan B", 2), ("Pelanggan C", 3)] {
+            let request = CreateTransaksiRequest {
+                id_pelanggan: 1,
+                nama_pelanggan: nama.to_string(),
+                catatan: None,
+                total_harga: None,
+                reject_duplicate_produk: false,
+                detail_transaksi: (0..jumlah).map(|i| CreateDetailTransaksiRequest {
+                    id_produk: i + 1,
+                    nama_produk: format!("Produk {}", i + 1),
+                    harga_satuan: 10000.0,
+                    jumlah: 1.0,
+                    diskon: None,
+                    harga_override: None,
+                }).collect(),
+            };
+            let response = client.post("/").json(&request).dispatch().await;
+            assert_eq!(response.status(), Status::Ok);
+        }
+
+        let response = client.get("/").dispatch().await;
+        let body: rocket::serde::json::Value = response.into_json().await.unwrap();
+        assert!(body.as_array().unwrap().iter().all(|t| t.get("detail_transaksi").is_none()));
+
+        query_counter::reset();
+        let response = client.get("/?with_details=true").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let body: Vec<TransaksiListItem> = response.into_json().await.unwrap();
+
+        assert_eq!(body.len(), 3);
+        for item in &body {
+            let details = item.detail_transaksi.as_ref().expect("details should be nested");
+            assert!(details.iter().all(|d| d.id_transaksi == item.transaksi.id));
+        }
+        let mut counts: Vec<usize> = body.iter().map(|t| t.detail_transaksi.as_ref().unwrap().len()).collect();
+        counts.sort();
+        assert_eq!(counts, vec![1, 2, 3]);
+
+        assert_eq!(query_counter::count("transaksi::get_details_by_transaksi_ids"), 1);
+        assert_eq!(query_counter::count("transaksi::get_detail_by_transaksi_id"), 0);
+    }
+
     #[async_test]
     async fn test_validate_product_stock() {
         let rocket = setup().await;
@@ -439,11 +1148,14 @@ mod tests {
                 id_produk: 1,
                 nama_produk: "Valid Product".to_string(),
                 harga_satuan: 100000.0,
-                jumlah: 50,
+                jumlah: 50.0,
+                diskon: None,
+                harga_override: None,
             },
         ];
 
         let response = client.post(uri!(super::validate_product_stock))
+            .header(rocket::http::Header::new("Accept-Language", "en"))
             .json(&products)
             .dispatch()
             .await;
@@ -462,12 +1174,16 @@ mod tests {
             id_pelanggan: 1,
             nama_pelanggan: "Test Full Details".to_string(),
             catatan: Some("Test transaction with details".to_string()),
+            total_harga: None,
+            reject_duplicate_produk: false,
             detail_transaksi: vec![
                 crate::transaksi_penjualan::dto::transaksi_request::CreateDetailTransaksiRequest {
                     id_produk: 1,
                     nama_produk: "Test Product".to_string(),
                     harga_satuan: 50000.0,
-                    jumlah: 2,
+                    jumlah: 2.0,
+                    diskon: None,
+                    harga_override: None,
                 },
             ],
         };
@@ -493,12 +1209,16 @@ mod tests {
             id_pelanggan: 1,
             nama_pelanggan: "State Test".to_string(),
             catatan: None,
+            total_harga: None,
+            reject_duplicate_produk: false,
             detail_transaksi: vec![
                 crate::transaksi_penjualan::dto::transaksi_request::CreateDetailTransaksiRequest {
                     id_produk: 1,
                     nama_produk: "State Test Product".to_string(),
                     harga_satuan: 100000.0,
-                    jumlah: 1,
+                    jumlah: 1.0,
+                    diskon: None,
+                    harga_override: None,
                 },
             ],
         };
@@ -531,12 +1251,16 @@ mod tests {
             id_pelanggan: 1,
             nama_pelanggan: "Detail CRUD Test".to_string(),
             catatan: None,
+            total_harga: None,
+            reject_duplicate_produk: false,
             detail_transaksi: vec![
                 crate::transaksi_penjualan::dto::transaksi_request::CreateDetailTransaksiRequest {
                     id_produk: 1,
                     nama_produk: "Initial Product".to_string(),
                     harga_satuan: 50000.0,
-                    jumlah: 1,
+                    jumlah: 1.0,
+                    diskon: None,
+                    harga_override: None,
                 },
             ],
         };
@@ -558,7 +1282,7 @@ mod tests {
                 let detail = &details[0];
                 
                 let mut updated_detail = detail.clone();
-                updated_detail.jumlah = 3;
+                updated_detail.jumlah = 3.0;
 
                 let update_detail_response = client.patch(format!("/1/detail/{}", detail.id))
                     .json(&updated_detail)
@@ -585,6 +1309,8 @@ mod tests {
             id_pelanggan: 1,
             nama_pelanggan: "".to_string(), 
             catatan: None,
+            total_harga: None,
+            reject_duplicate_produk: false,
             detail_transaksi: vec![],       
         };
 
@@ -595,4 +1321,765 @@ mod tests {
 
         assert_eq!(response.status(), Status::BadRequest);
     }
-}
\ No newline at end of file
+
+    fn payment_plan_transaksi_request(nama: &str) -> crate::transaksi_penjualan::dto::transaksi_request::CreateTransaksiRequest {
+        crate::transaksi_penjualan::dto::transaksi_request::CreateTransaksiRequest {
+            id_pelanggan: 7,
+            nama_pelanggan: nama.to_string(),
+            catatan: None,
+            total_harga: None,
+            reject_duplicate_produk: false,
+            detail_transaksi: vec![
+                crate::transaksi_penjualan::dto::transaksi_request::CreateDetailTransaksiRequest {
+                    id_produk: 1,
+                    nama_produk: "Besi Beton".to_string(),
+                    harga_satuan: 100000.0,
+                    jumlah: 3.0,
+                    diskon: None,
+                    harga_override: None,
+                },
+            ],
+        }
+    }
+
+    #[async_test]
+    async fn test_create_payment_plan_schedules_installments() {
+        use crate::manajemen_pembayaran::enums::payment_status::PaymentStatus;
+
+        let rocket = setup().await;
+        let client = Client::tracked(rocket).await.expect("Must provide a valid Rocket instance");
+
+        let create_response = client.post(uri!(super::create_transaksi))
+            .json(&payment_plan_transaksi_request("PT Konstruksi"))
+            .dispatch()
+            .await;
+        assert_eq!(create_response.status(), Status::Ok);
+
+        let plan_response = client.post("/1/payment-plan")
+            .json(&PaymentPlanRequest { jumlah_cicilan: 3, method: Some("BANK_TRANSFER".to_string()) })
+            .dispatch()
+            .await;
+        assert_eq!(plan_response.status(), Status::Created);
+
+        let body: ApiResponse<PaymentPlanResponse> = plan_response.into_json().await.unwrap();
+        assert!(body.success);
+        let plan = body.data.unwrap();
+        assert_eq!(plan.payment.transaction_id, "1");
+        assert_eq!(plan.payment.status, PaymentStatus::Installment);
+        assert_eq!(plan.payment.amount, 300000.0);
+        assert!(plan.payment.installments.is_empty());
+
+        assert_eq!(plan.schedule.len(), 3);
+        assert_eq!(plan.schedule.iter().map(|s| s.sequence).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert!(plan.schedule.iter().all(|s| s.amount == 100000.0 && s.payment_id == plan.payment.id));
+        assert!(plan.schedule.windows(2).all(|pair| pair[0].due_date < pair[1].due_date));
+        assert!(plan.schedule[0].due_date > plan.payment.payment_date);
+        assert_eq!(plan.payment.due_date, Some(plan.schedule[2].due_date));
+
+        // Transaksi yang sudah punya pembayaran tidak bisa dibuatkan rencana baru
+        let duplicate_response = client.post("/1/payment-plan")
+            .json(&PaymentPlanRequest { jumlah_cicilan: 2, method: None })
+            .dispatch()
+            .await;
+        assert_eq!(duplicate_response.status(), Status::BadRequest);
+    }
+
+    #[async_test]
+    async fn test_create_payment_plan_rejects_cancelled_or_missing_transaksi() {
+        let rocket = setup().await;
+        let client = Client::tracked(rocket).await.expect("Must provide a valid Rocket instance");
+
+        let create_response = client.post(uri!(super::create_transaksi))
+            .json(&payment_plan_transaksi_request("PT Batal"))
+            .dispatch()
+            .await;
+        assert_eq!(create_response.status(), Status::Ok);
+
+        let cancel_response = client.put("/1/cancel").dispatch().await;
+        assert_eq!(cancel_response.status(), Status::Ok);
+
+        let cancelled_response = client.post("/1/payment-plan")
+            .json(&PaymentPlanRequest { jumlah_cicilan: 3, method: None })
+            .dispatch()
+            .await;
+        assert_eq!(cancelled_response.status(), Status::Conflict);
+
+        let missing_response = client.post("/99/payment-plan")
+            .json(&PaymentPlanRequest { jumlah_cicilan: 3, method: None })
+            .dispatch()
+            .await;
+        assert_eq!(missing_response.status(), Status::NotFound);
+    }
+
+    #[async_test]
+    async fn test_cancel_transaksi_stores_optional_reason() {
+        use crate::transaksi_penjualan::dto::transaksi_request::TransaksiWithDetailsResponse;
+
+        let rocket = setup().await;
+        let client = Client::tracked(rocket).await.expect("Must provide a valid Rocket instance");
+
+        for nama in ["PT Dengan Alasan", "PT Tanpa Alasan"] {
+            let response = client.post(uri!(super::create_transaksi))
+                .json(&payment_plan_transaksi_request(nama))
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::Ok);
+        }
+
+        let response = client.put("/1/cancel")
+            .json(&CancelTransaksiRequest { alasan_pembatalan: Some("  Pelanggan membatalkan pesanan  ".to_string()) })
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client.put("/2/cancel").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+
+        let with_reason: Transaksi = client.get("/1").dispatch().await.into_json().await.unwrap();
+        assert_eq!(with_reason.status, StatusTransaksi::Dibatalkan);
+        assert_eq!(with_reason.alasan_pembatalan.as_deref(), Some("Pelanggan membatalkan pesanan"));
+
+        let full: TransaksiWithDetailsResponse = client.get("/1/full").dispatch().await.into_json().await.unwrap();
+        assert_eq!(full.alasan_pembatalan.as_deref(), Some("Pelanggan membatalkan pesanan"));
+
+        let without_reason: Transaksi = client.get("/2").dispatch().await.into_json().await.unwrap();
+        assert_eq!(without_reason.status, StatusTransaksi::Dibatalkan);
+        assert_eq!(without_reason.alasan_pembatalan, None);
+    }
+
+    #[async_test]
+    async fn test_payment_summary_with_two_partial_payments() {
+        use chrono::{Duration, Utc};
+        use crate::manajemen_pembayaran::enums::payment_status::PaymentStatus;
+        use crate::manajemen_pembayaran::model::payment::{Payment, PaymentMethod};
+        use crate::manajemen_pembayaran::repository::payment_repository::PembayaranRepository;
+
+        let rocket = setup().await;
+        let db = rocket.state::<Pool<Any>>().unwrap().clone();
+        let client = Client::tracked(rocket).await.expect("Must provide a valid Rocket instance");
+
+        let create_response = client.post(uri!(super::create_transaksi))
+            .json(&payment_plan_transaksi_request("PT Cicil"))
+            .dispatch()
+            .await;
+        assert_eq!(create_response.status(), Status::Ok);
+
+        let last_date = Utc::now();
+        for (amount, payment_date) in [(100000.0, last_date - Duration::days(3)), (50000.0, last_date)] {
+            let payment = Payment {
+                id: format!("PMT-SUM-{}", amount),
+                transaction_id: "1".to_string(),
+                amount,
+                method: PaymentMethod::Cash,
+                status: PaymentStatus::Paid,
+                payment_date,
+                installments: Vec::new(),
+                due_date: None,
+                audit: Default::default(),
+            };
+            PembayaranRepository::create(db.acquire().await.unwrap(), &payment).await.unwrap();
+        }
+
+        let response = client.get("/1/payments/summary").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let summary = response.into_json::<ApiResponse<TransaksiPaymentSummary>>().await.unwrap().data.unwrap();
+        assert_eq!(summary.total_harga, 300000.0);
+        assert_eq!(summary.paid_total, 150000.0);
+        assert_eq!(summary.outstanding, 150000.0);
+        assert_eq!(summary.payment_count, 2);
+        assert_eq!(summary.last_payment_date, Some(last_date.to_rfc3339()));
+
+        let missing = client.get("/99/payments/summary").dispatch().await;
+        assert_eq!(missing.status(), Status::NotFound);
+    }
+
+    #[async_test]
+    async fn test_payment_breakdown_by_method_splits_cash_and_card() {
+        use chrono::Utc;
+        use crate::manajemen_pembayaran::enums::payment_status::PaymentStatus;
+        use crate::manajemen_pembayaran::model::payment::{Payment, PaymentMethod};
+        use crate::manajemen_pembayaran::repository::payment_repository::PembayaranRepository;
+
+        let rocket = setup().await;
+        let db = rocket.state::<Pool<Any>>().unwrap().clone();
+        let client = Client::tracked(rocket).await.expect("Must provide a valid Rocket instance");
+
+        let create_response = client.post(uri!(super::create_transaksi))
+            .json(&payment_plan_transaksi_request("PT Split"))
+            .dispatch()
+            .await;
+        assert_eq!(create_response.status(), Status::Ok);
+
+        for (id, amount, method) in [
+            ("PMT-SPLIT-1", 120000.0, PaymentMethod::Cash),
+            ("PMT-SPLIT-2", 180000.0, PaymentMethod::CreditCard),
+        ] {
+            let payment = Payment {
+                id: id.to_string(),
+                transaction_id: "1".to_string(),
+                amount,
+                method,
+                status: PaymentStatus::Paid,
+                payment_date: Utc::now(),
+                installments: Vec::new(),
+                due_date: None,
+                audit: Default::default(),
+            };
+            PembayaranRepository::create(db.acquire().await.unwrap(), &payment).await.unwrap();
+        }
+
+        let response = client.get("/1/payments/by-method").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let breakdown = response.into_json::<ApiResponse<Vec<PaymentMethodTotal>>>().await.unwrap().data.unwrap();
+        let split: Vec<(PaymentMethod, f64, i64)> = breakdown.into_iter()
+            .map(|total| (total.method, total.paid_total, total.payment_count))
+            .collect();
+        assert_eq!(split, vec![
+            (PaymentMethod::Cash, 120000.0, 1),
+            (PaymentMethod::CreditCard, 180000.0, 1),
+        ]);
+
+        let missing = client.get("/99/payments/by-method").dispatch().await;
+        assert_eq!(missing.status(), Status::NotFound);
+    }
+
+    async fn insert_produk_with_stok(db: &Pool<Any>, id: i64, nama: &str, stok: i32) -> i64 {
+        sqlx::query("
+            INSERT INTO produk (id, nama, kategori, harga, stok) VALUES ($1, $2, 'Material', 100000.0, $3)
+            ON CONFLICT (id) DO UPDATE SET nama = excluded.nama, stok = excluded.stok
+        ")
+            .bind(id)
+            .bind(nama)
+            .bind(stok)
+            .execute(db)
+            .await
+            .unwrap();
+        id
+    }
+
+    async fn stok_of(db: &Pool<Any>, id: i64) -> i32 {
+        let row = sqlx::query("SELECT stok FROM produk WHERE id = $1").bind(id).fetch_one(db).await.unwrap();
+        sqlx::Row::get(&row, "stok")
+    }
+
+    #[async_test]
+    async fn test_checkout_creates_paid_completed_transaksi_and_decrements_stock() {
+        use crate::transaksi_penjualan::dto::transaksi_request::{CheckoutRequest, CheckoutResponse};
+
+        let rocket = setup().await;
+        let db = rocket.state::<Pool<Any>>().unwrap().clone();
+        let client = Client::tracked(rocket).await.expect("Must provide a valid Rocket instance");
+        let produk_id = insert_produk_with_stok(&db, 1, "Besi Beton", 5).await;
+        sqlx::query("UPDATE produk SET berat_gram = 7400 WHERE id = $1").bind(produk_id).execute(&db).await.unwrap();
+
+        let request = CheckoutRequest {
+            transaksi: payment_plan_transaksi_request("PT Tunai"),
+            method: "CASH".to_string(),
+            amount: Some(350000.0),
+        };
+        let response = client.post("/checkout").json(&request).dispatch().await;
+        assert_eq!(response.status(), Status::Created);
+        let checkout = response.into_json::<ApiResponse<CheckoutResponse>>().await.unwrap().data.unwrap();
+
+        assert_eq!(checkout.transaksi.status, StatusTransaksi::Selesai);
+        assert_eq!(checkout.transaksi.total_harga, 300000.0);
+        assert_eq!(checkout.detail_transaksi.len(), 1);
+        assert_eq!(checkout.payment.amount, 300000.0);
+        assert_eq!(checkout.payment.transaction_id, checkout.transaksi.id.to_string());
+        assert_eq!(checkout.kembalian, 50000.0);
+        assert_eq!(checkout.total_berat_gram, Some(22200));
+        assert_eq!(stok_of(&db, produk_id).await, 2);
+
+        let summary = client.get(format!("/{}/payments/summary", checkout.transaksi.id)).dispatch().await
+            .into_json::<ApiResponse<TransaksiPaymentSummary>>().await.unwrap().data.unwrap();
+        assert_eq!(summary.outstanding, 0.0);
+        let history = TransaksiService::get_status_history(db.clone(), checkout.transaksi.id).await.unwrap();
+        assert_eq!(history.len(), 1);
+
+        let underpaid = CheckoutRequest { amount: Some(1000.0), ..request };
+        let response = client.post("/checkout").json(&underpaid).dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+
+    #[async_test]
+    async fn test_checkout_decrements_weighted_stock_by_decimal_jumlah() {
+        use crate::transaksi_penjualan::dto::transaksi_request::{CheckoutRequest, CheckoutResponse};
+
+        let rocket = setup().await;
+        let db = rocket.state::<Pool<Any>>().unwrap().clone();
+        let client = Client::tracked(rocket).await.expect("Must provide a valid Rocket instance");
+        // 12,5 kg disimpan sebagai 12500 gram
+        let produk_id = insert_produk_with_stok(&db, 1, "Pasir Cor", 12500).await;
+        sqlx::query("UPDATE produk SET satuan = 'kg', presisi_stok = 3 WHERE id = $1").bind(produk_id).execute(&db).await.unwrap();
+
+        let mut transaksi = payment_plan_transaksi_request("Toko Timbang");
+        transaksi.detail_transaksi[0].jumlah = 2.5;
+        let request = CheckoutRequest { transaksi, method: "CASH".to_string(), amount: None };
+        let response = client.post("/checkout").json(&request).dispatch().await;
+        assert_eq!(response.status(), Status::Created);
+        let checkout = response.into_json::<ApiResponse<CheckoutResponse>>().await.unwrap().data.unwrap();
+        assert_eq!(checkout.transaksi.total_harga, 250000.0);
+        assert_eq!(checkout.detail_transaksi[0].jumlah, 2.5);
+        assert_eq!(stok_of(&db, produk_id).await, 10000);
+
+        // Lebih halus dari presisi produk, atau desimal untuk produk satuan utuh, ditolak
+        let mut terlalu_halus = request.clone();
+        terlalu_halus.transaksi.detail_transaksi[0].jumlah = 0.0005;
+        let response = client.post("/checkout").json(&terlalu_halus).dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest);
+
+        sqlx::query("UPDATE produk SET satuan = 'pcs', presisi_stok = 0 WHERE id = $1").bind(produk_id).execute(&db).await.unwrap();
+        let response = client.post("/checkout").json(&request).dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest);
+        assert_eq!(stok_of(&db, produk_id).await, 10000);
+    }
+
+    #[async_test]
+    async fn test_get_transaksi_by_id_includes_customer_lifetime_total() {
+        use crate::transaksi_penjualan::dto::transaksi_request::{CheckoutRequest, CheckoutResponse};
+
+        let rocket = setup().await;
+        let db = rocket.state::<Pool<Any>>().unwrap().clone();
+        let client = Client::tracked(rocket).await.expect("Must provide a valid Rocket instance");
+        insert_produk_with_stok(&db, 1, "Besi Beton", 50).await;
+
+        let mut completed_ids = Vec::new();
+        for _ in 0..2 {
+            let request = CheckoutRequest {
+                transaksi: payment_plan_transaksi_request("PT Setia"),
+                method: "CASH".to_string(),
+                amount: None,
+            };
+            let response = client.post("/checkout").json(&request).dispatch().await;
+            assert_eq!(response.status(), Status::Created);
+            let checkout = response.into_json::<ApiResponse<CheckoutResponse>>().await.unwrap().data.unwrap();
+            completed_ids.push(checkout.transaksi.id);
+        }
+
+        // Transaksi yang belum selesai dan milik pelanggan lain tidak ikut dihitung
+        let pending = client.post("/").json(&payment_plan_transaksi_request("PT Setia")).dispatch().await;
+        assert_eq!(pending.status(), Status::Ok);
+        let mut other_customer = payment_plan_transaksi_request("CV Lain");
+        other_customer.id_pelanggan = 8;
+        let request = CheckoutRequest { transaksi: other_customer, method: "CASH".to_string(), amount: None };
+        assert_eq!(client.post("/checkout").json(&request).dispatch().await.status(), Status::Created);
+
+        let response = client.get(format!("/{}", completed_ids[0])).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let body: TransaksiDetailResponse = response.into_json().await.unwrap();
+        assert_eq!(body.transaksi.id, completed_ids[0]);
+        assert_eq!(body.transaksi.id_pelanggan, 7);
+        assert_eq!(body.total_belanja_pelanggan, 600000.0);
+
+        assert_eq!(client.get("/999").dispatch().await.status(), Status::NotFound);
+    }
+
+    #[async_test]
+    async fn test_checkout_rolls_back_everything_when_stock_is_insufficient() {
+        use crate::transaksi_penjualan::dto::transaksi_request::{CheckoutRequest, CreateDetailTransaksiRequest};
+
+        let rocket = setup().await;
+        let db = rocket.state::<Pool<Any>>().unwrap().clone();
+        let client = Client::tracked(rocket).await.expect("Must provide a valid Rocket instance");
+        let first_id = insert_produk_with_stok(&db, 1, "Besi Beton", 10).await;
+        let second_id = insert_produk_with_stok(&db, 2, "Semen", 1).await;
+
+        let mut transaksi = payment_plan_transaksi_request("PT Kurang Stok");
+        transaksi.detail_transaksi.push(CreateDetailTransaksiRequest {
+            id_produk: second_id as i32,
+            nama_produk: "Semen".to_string(),
+            harga_satuan: 250000.0,
+            jumlah: 2.0,
+            diskon: None,
+            harga_override: None,
+        });
+        let request = CheckoutRequest { transaksi, method: "CASH".to_string(), amount: None };
+
+        let response = client.post("/checkout").json(&request).dispatch().await;
+        assert_eq!(response.status(), Status::Conflict);
+        assert!(response.into_string().await.unwrap().contains("line 2"));
+
+        // Baris pertama sempat mengurangi stok, tetapi ikut di-rollback
+        assert_eq!(stok_of(&db, first_id).await, 10);
+        assert_eq!(stok_of(&db, second_id).await, 1);
+        assert!(TransaksiService::get_all_transaksi(db.clone()).await.unwrap().is_empty());
+        let payments: i64 = sqlx::query_scalar("SELECT CAST(COUNT(*) AS BIGINT) FROM payments").fetch_one(&db).await.unwrap();
+        assert_eq!(payments, 0);
+    }
+
+    #[async_test]
+    async fn test_void_completed_paid_transaksi_restores_stock_and_refunds_payment() {
+        use crate::manajemen_pembayaran::model::payment::{Refund, RefundLedger};
+        use crate::manajemen_pembayaran::repository::payment_repository::PembayaranRepository;
+        use crate::transaksi_penjualan::dto::transaksi_request::{CheckoutRequest, CheckoutResponse, VoidTransaksiRequest, VoidTransaksiResponse};
+
+        let rocket = setup().await;
+        let db = rocket.state::<Pool<Any>>().unwrap().clone();
+        let client = Client::tracked(rocket).await.expect("Must provide a valid Rocket instance");
+        let produk_id = insert_produk_with_stok(&db, 1, "Besi Beton", 5).await;
+
+        let request = CheckoutRequest {
+            transaksi: payment_plan_transaksi_request("PT Void"),
+            method: "CASH".to_string(),
+            amount: None,
+        };
+        let checkout = client.post("/checkout").json(&request).dispatch().await
+            .into_json::<ApiResponse<CheckoutResponse>>().await.unwrap().data.unwrap();
+        assert_eq!(stok_of(&db, produk_id).await, 2);
+
+        // Refund parsial sebelumnya ikut diperhitungkan: void hanya me-refund sisanya
+        let partial = Refund {
+            id: "RFD-partial".to_string(),
+            payment_id: checkout.payment.id.clone(),
+            amount: 50000.0,
+            reason: "Barang cacat".to_string(),
+            refund_date: chrono::Utc::now(),
+            created_by: None,
+        };
+        PembayaranRepository::record_refund(db.acquire().await.unwrap(), &partial, 300000.0).await.unwrap();
+
+        let response = client.post(format!("/{}/void", checkout.transaksi.id))
+            .json(&VoidTransaksiRequest { alasan: Some("  Salah input kasir ".to_string()) })
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let voided = response.into_json::<ApiResponse<VoidTransaksiResponse>>().await.unwrap().data.unwrap();
+
+        assert_eq!(voided.transaksi.status, StatusTransaksi::Void);
+        assert_eq!(voided.refunds.len(), 1);
+        assert_eq!(voided.refunds[0].amount, 250000.0);
+        assert_eq!(voided.refunds[0].reason, format!("Void transaksi {}: Salah input kasir", checkout.transaksi.id));
+        assert_eq!(stok_of(&db, produk_id).await, 5);
+
+        let refunds = PembayaranRepository::find_refunds(db.acquire().await.unwrap(), &checkout.payment.id).await.unwrap();
+        let ledger = RefundLedger::new(&checkout.payment.id, 300000.0, refunds);
+        assert_eq!(ledger.refunded_total, 300000.0);
+        assert_eq!(ledger.remaining, 0.0);
+
+        let history = TransaksiService::get_status_history(db.clone(), checkout.transaksi.id).await.unwrap();
+        let last = history.last().unwrap();
+        assert_eq!((last.status_lama.clone(), last.status_baru.clone()), (StatusTransaksi::Selesai, StatusTransaksi::Void));
+        assert_eq!(last.alasan.as_deref(), Some("Salah input kasir"));
+
+        // Void bersifat final dan berbeda dari cancel
+        let again = client.post(format!("/{}/void", checkout.transaksi.id)).dispatch().await;
+        assert_eq!(again.status(), Status::Conflict);
+        let cancel = client.put(format!("/{}/cancel", checkout.transaksi.id)).dispatch().await;
+        assert_eq!(cancel.status(), Status::Forbidden);
+        assert_eq!(stok_of(&db, produk_id).await, 5);
+        assert_eq!(client.post("/999/void").dispatch().await.status(), Status::NotFound);
+    }
+
+    #[async_test]
+    async fn test_void_after_create_and_complete_leaves_stock_unchanged() {
+        let rocket = setup().await;
+        let db = rocket.state::<Pool<Any>>().unwrap().clone();
+        let client = Client::tracked(rocket).await.expect("Must provide a valid Rocket instance");
+        let produk_id = insert_produk_with_stok(&db, 1, "Besi Beton", 5).await;
+
+        let response = client.post(uri!(super::create_transaksi))
+            .json(&payment_plan_transaksi_request("PT Void Kasir"))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(stok_of(&db, produk_id).await, 2);
+        let id: i32 = sqlx::query_scalar("SELECT id FROM transaksi").fetch_one(&db).await.unwrap();
+
+        assert_eq!(client.put(format!("/{}/complete", id)).dispatch().await.status(), Status::Ok);
+        assert_eq!(client.post(format!("/{}/void", id)).dispatch().await.status(), Status::Ok);
+
+        // Void hanya mengembalikan stok yang benar-benar dipotong saat penjualan dibuat
+        assert_eq!(stok_of(&db, produk_id).await, 5);
+    }
+
+    #[async_test]
+    async fn test_return_one_of_two_items_restores_its_stock_and_refunds_its_value() {
+        use crate::manajemen_pembayaran::model::payment::RefundLedger;
+        use crate::manajemen_pembayaran::repository::payment_repository::PembayaranRepository;
+        use crate::transaksi_penjualan::dto::transaksi_request::{CheckoutRequest, CheckoutResponse, CreateDetailTransaksiRequest, ReturItemRequest};
+
+        let rocket = setup().await;
+        let db = rocket.state::<Pool<Any>>().unwrap().clone();
+        let client = Client::tracked(rocket).await.expect("Must provide a valid Rocket instance");
+        let besi_id = insert_produk_with_stok(&db, 1, "Besi Beton", 5).await;
+        let semen_id = insert_produk_with_stok(&db, 2, "Semen", 10).await;
+
+        let mut transaksi = payment_plan_transaksi_request("PT Retur");
+        transaksi.detail_transaksi[0].jumlah = 1.0;
+        transaksi.detail_transaksi.push(CreateDetailTransaksiRequest {
+            id_produk: 2,
+            nama_produk: "Semen".to_string(),
+            harga_satuan: 250000.0,
+            jumlah: 2.0,
+            diskon: Some(10000.0),
+            harga_override: None,
+        });
+        let request = CheckoutRequest { transaksi, method: "CASH".to_string(), amount: None };
+        let checkout = client.post("/checkout").json(&request).dispatch().await
+            .into_json::<ApiResponse<CheckoutResponse>>().await.unwrap().data.unwrap();
+        assert_eq!(checkout.transaksi.total_harga, 590000.0);
+        let id = checkout.transaksi.id;
+        let details = TransaksiService::get_detail_by_transaksi_id(db.clone(), id).await.unwrap();
+        let detail_of = |produk_id: i64| details.iter().find(|d| d.id_produk as i64 == produk_id).unwrap().id;
+
+        // Besi dikembalikan, semen tetap dibawa pelanggan
+        let response = client.post(format!("/{}/return", id))
+            .json(&ReturTransaksiRequest {
+                items: vec![ReturItemRequest { id_detail: detail_of(besi_id), jumlah: 1.0 }],
+                alasan: Some("Salah ukuran".to_string()),
+            })
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Created);
+        let returned = response.into_json::<ApiResponse<ReturTransaksiResponse>>().await.unwrap().data.unwrap();
+        assert_eq!((returned.retur.nilai_retur, returned.retur.total_refund), (100000.0, 100000.0));
+        assert_eq!(returned.refunds.len(), 1);
+        assert_eq!(returned.refunds[0].amount, 100000.0);
+        assert_eq!(returned.refunds[0].reason, format!("Retur {} transaksi {}: Salah ukuran", returned.retur.id, id));
+        assert_eq!(stok_of(&db, besi_id).await, 5);
+        assert_eq!(stok_of(&db, semen_id).await, 8);
+
+        // Retur parsial baris berdiskon: nilai sebanding subtotal setelah diskon
+        let semen = ReturItemRequest { id_detail: detail_of(semen_id), jumlah: 1.0 };
+        let response = client.post(format!("/{}/return", id))
+            .json(&ReturTransaksiRequest { items: vec![semen.clone()], alasan: None })
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Created);
+        let returned = response.into_json::<ApiResponse<ReturTransaksiResponse>>().await.unwrap().data.unwrap();
+        assert_eq!(returned.retur.nilai_retur, 245000.0);
+        assert_eq!(stok_of(&db, semen_id).await, 9);
+
+        // Total retur per baris tidak boleh melebihi jumlah yang dibeli
+        let response = client.post(format!("/{}/return", id))
+            .json(&ReturTransaksiRequest { items: vec![ReturItemRequest { jumlah: 2.0, ..semen }], alasan: None })
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::BadRequest);
+        assert_eq!(stok_of(&db, semen_id).await, 9);
+
+        let refunds = PembayaranRepository::find_refunds(db.acquire().await.unwrap(), &checkout.payment.id).await.unwrap();
+        let ledger = RefundLedger::new(&checkout.payment.id, 590000.0, refunds);
+        assert_eq!((ledger.refunded_total, ledger.remaining), (345000.0, 245000.0));
+        let transaksi = TransaksiService::get_transaksi_by_id(db.clone(), id).await.unwrap();
+        assert_eq!(transaksi.status, StatusTransaksi::Selesai);
+        assert_eq!(client.post("/999/return").json(&ReturTransaksiRequest { items: Vec::new(), alasan: None }).dispatch().await.status(), Status::NotFound);
+    }
+
+    #[async_test]
+    async fn test_return_after_create_and_complete_restores_only_returned_stock() {
+        use crate::transaksi_penjualan::dto::transaksi_request::ReturItemRequest;
+
+        let rocket = setup().await;
+        let db = rocket.state::<Pool<Any>>().unwrap().clone();
+        let client = Client::tracked(rocket).await.expect("Must provide a valid Rocket instance");
+        let produk_id = insert_produk_with_stok(&db, 1, "Besi Beton", 5).await;
+
+        let response = client.post(uri!(super::create_transaksi))
+            .json(&payment_plan_transaksi_request("PT Retur Kasir"))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(stok_of(&db, produk_id).await, 2);
+        let id: i32 = sqlx::query_scalar("SELECT id FROM transaksi").fetch_one(&db).await.unwrap();
+        assert_eq!(client.put(format!("/{}/complete", id)).dispatch().await.status(), Status::Ok);
+
+        let id_detail = TransaksiService::get_detail_by_transaksi_id(db.clone(), id).await.unwrap()[0].id;
+        let response = client.post(format!("/{}/return", id))
+            .json(&ReturTransaksiRequest { items: vec![ReturItemRequest { id_detail, jumlah: 1.0 }], alasan: None })
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Created);
+
+        // Tiga terjual, satu diretur: stok akhir 5 - 3 + 1
+        assert_eq!(stok_of(&db, produk_id).await, 3);
+    }
+
+    #[async_test]
+    async fn test_return_weighted_item_accepts_decimal_jumlah() {
+        use crate::transaksi_penjualan::dto::transaksi_request::{CheckoutRequest, CheckoutResponse, ReturItemRequest};
+
+        let rocket = setup().await;
+        let db = rocket.state::<Pool<Any>>().unwrap().clone();
+        let client = Client::tracked(rocket).await.expect("Must provide a valid Rocket instance");
+        let produk_id = insert_produk_with_stok(&db, 1, "Pasir Cor", 12500).await;
+        sqlx::query("UPDATE produk SET satuan = 'kg', presisi_stok = 3 WHERE id = $1").bind(produk_id).execute(&db).await.unwrap();
+
+        let mut transaksi = payment_plan_transaksi_request("Toko Timbang");
+        transaksi.detail_transaksi[0].jumlah = 2.5;
+        let request = CheckoutRequest { transaksi, method: "CASH".to_string(), amount: None };
+        let checkout = client.post("/checkout").json(&request).dispatch().await
+            .into_json::<ApiResponse<CheckoutResponse>>().await.unwrap().data.unwrap();
+        let id = checkout.transaksi.id;
+        let id_detail = checkout.detail_transaksi[0].id;
+        assert_eq!(stok_of(&db, produk_id).await, 10000);
+
+        let retur = |jumlah: f64| ReturTransaksiRequest { items: vec![ReturItemRequest { id_detail, jumlah }], alasan: None };
+        let response = client.post(format!("/{}/return", id)).json(&retur(2.0)).dispatch().await;
+        assert_eq!(response.status(), Status::Created);
+        let returned = response.into_json::<ApiResponse<ReturTransaksiResponse>>().await.unwrap().data.unwrap();
+        assert_eq!(returned.retur.nilai_retur, 200000.0);
+        assert_eq!(stok_of(&db, produk_id).await, 12000);
+
+        // Lebih halus dari presisi produk ditolak
+        let response = client.post(format!("/{}/return", id)).json(&retur(0.0005)).dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest);
+
+        let response = client.post(format!("/{}/return", id)).json(&retur(0.5)).dispatch().await;
+        assert_eq!(response.status(), Status::Created);
+        let returned = response.into_json::<ApiResponse<ReturTransaksiResponse>>().await.unwrap().data.unwrap();
+        assert_eq!(returned.retur.nilai_retur, 50000.0);
+        assert_eq!(returned.retur.items[0].jumlah, 0.5);
+        assert_eq!(stok_of(&db, produk_id).await, 12500);
+
+        // 2.5 kg sudah diretur seluruhnya
+        let response = client.post(format!("/{}/return", id)).json(&retur(0.001)).dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest);
+        assert_eq!(stok_of(&db, produk_id).await, 12500);
+    }
+
+    #[async_test]
+    async fn test_export_json_streams_one_line_per_transaksi_in_range() {
+        let rocket = setup().await;
+        let db = rocket.state::<Pool<Any>>().unwrap().clone();
+        let client = Client::tracked(rocket).await.expect("Must provide a valid Rocket instance");
+
+        for (nama, tanggal) in [
+            ("PT Februari", "2024-02-28 23:59:59"),
+            ("PT Maret Awal", "2024-03-01 00:00:00"),
+            ("PT Maret Tengah", "2024-03-15 12:30:00"),
+            ("PT Maret Akhir", "2024-03-31 23:59:59"),
+            ("PT April", "2024-04-01 08:00:00"),
+        ] {
+            let created = TransaksiService::create_transaksi(db.clone(), &Transaksi::new(1, nama.to_string(), 10000.0, None)).await.unwrap();
+            sqlx::query("UPDATE transaksi SET tanggal_transaksi = $1 WHERE id = $2")
+                .bind(tanggal)
+                .bind(created.id)
+                .execute(&db)
+                .await
+                .unwrap();
+        }
+
+        let response = client.get("/export.json?date_from=2024-03-01&date_to=2024-03-31").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(ContentType::new("application", "x-ndjson")));
+        let body = response.into_string().await.unwrap();
+        let names: Vec<String> = body.lines()
+            .map(|line| serde_json::from_str::<Transaksi>(line).unwrap().nama_pelanggan)
+            .collect();
+        assert_eq!(names, vec!["PT Maret Awal", "PT Maret Tengah", "PT Maret Akhir"]);
+
+        let all = client.get("/export.json").dispatch().await.into_string().await.unwrap();
+        assert_eq!(all.lines().count(), 5);
+
+        let response = client.get("/export.json?date_from=31-03-2024").dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+
+    #[async_test]
+    async fn test_recompute_total_repairs_corrupted_total() {
+        use crate::auth::controller::auth::{login, AuthForm};
+        use crate::auth::model::user::User;
+        use crate::auth::service::auth::AuthService;
+
+        let rocket = setup().await;
+        let db = rocket.state::<Pool<Any>>().unwrap().clone();
+        AuthService::register_user(db.clone(), User::new("admin".to_string(), "admin123".to_string(), true))
+            .await
+            .unwrap();
+        let rocket = rocket.manage(false).mount("/", routes![login]);
+        let client = Client::tracked(rocket).await.expect("Must provide a valid Rocket instance");
+
+        let create_response = client.post(uri!(super::create_transaksi))
+            .json(&payment_plan_transaksi_request("PT Legacy"))
+            .dispatch()
+            .await;
+        assert_eq!(create_response.status(), Status::Ok);
+
+        sqlx::query("UPDATE transaksi SET total_harga = $1 WHERE id = 1")
+            .bind(123.45)
+            .execute(&db)
+            .await
+            .unwrap();
+
+        let unauthenticated = client.post("/1/recompute-total").dispatch().await;
+        assert_eq!(unauthenticated.status(), Status::Unauthorized);
+
+        client.post("/login")
+            .json(&AuthForm { username: "admin".to_string(), password: "admin123".to_string() })
+            .dispatch()
+            .await;
+
+        let response = client.post("/1/recompute-total").dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let result = response.into_json::<ApiResponse<RecomputeTotalResponse>>().await.unwrap().data.unwrap();
+        assert_eq!(result.old_total, 123.45);
+        assert_eq!(result.new_total, 300000.0);
+
+        let repaired: Transaksi = client.get("/1").dispatch().await.into_json().await.unwrap();
+        assert_eq!(repaired.total_harga, 300000.0);
+
+        let missing = client.post("/99/recompute-total").dispatch().await;
+        assert_eq!(missing.status(), Status::NotFound);
+    }
+
+    #[async_test]
+    async fn test_status_history_records_complete_and_skips_rejected_cancel() {
+        use crate::transaksi_penjualan::dto::transaksi_request::{CreateDetailTransaksiRequest, CreateTransaksiRequest};
+
+        let rocket = setup().await;
+        let client = Client::tracked(rocket).await.expect("Must provide a valid Rocket instance");
+
+        for nama in ["Budi", "Sari"] {
+            let request = CreateTransaksiRequest {
+                id_pelanggan: 1,
+                nama_pelanggan: nama.to_string(),
+                catatan: None,
+                total_harga: None,
+                reject_duplicate_produk: false,
+                detail_transaksi: vec![CreateDetailTransaksiRequest {
+                    id_produk: 1,
+                    nama_produk: "Semen".to_string(),
+                    harga_satuan: 65000.0,
+                    jumlah: 1.0,
+                    diskon: None,
+                    harga_override: None,
+                }],
+            };
+            let response = client.post("/").json(&request).dispatch().await;
+            assert_eq!(response.status(), Status::Ok);
+        }
+
+        let response = client.get(uri!(get_status_history(id = 1))).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert!(response.into_json::<Vec<TransaksiStatusHistory>>().await.unwrap().is_empty());
+
+        let response = client.put(uri!(complete_transaksi(id = 1))).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let response = client.put(uri!(cancel_transaksi(id = 1)))
+            .json(&CancelTransaksiRequest { alasan_pembatalan: Some("Salah input".to_string()) })
+            .dispatch().await;
+        assert_eq!(response.status(), Status::Forbidden);
+
+        let response = client.get(uri!(get_status_history(id = 1))).dispatch().await;
+        let history: Vec<TransaksiStatusHistory> = response.into_json().await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].id_transaksi, 1);
+        assert_eq!(history[0].status_lama, StatusTransaksi::MasihDiproses);
+        assert_eq!(history[0].status_baru, StatusTransaksi::Selesai);
+        assert_eq!(history[0].alasan, None);
+        assert_eq!(history[0].changed_by, None);
+
+        let response = client.put(uri!(cancel_transaksi(id = 2)))
+            .json(&CancelTransaksiRequest { alasan_pembatalan: Some("Pelanggan batal".to_string()) })
+            .dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let response = client.get(uri!(get_status_history(id = 2))).dispatch().await;
+        let history: Vec<TransaksiStatusHistory> = response.into_json().await.unwrap();
+        assert_eq!(history.iter().map(|h| (h.status_baru.clone(), h.alasan.clone())).collect::<Vec<_>>(),
+            vec![(StatusTransaksi::Dibatalkan, Some("Pelanggan batal".to_string()))]);
+
+        let response = client.get(uri!(get_status_history(id = 999))).dispatch().await;
+        assert_eq!(response.status(), Status::NotFound);
+    }
+}
@@ -1,7 +1,12 @@
 use rocket::serde::{Serialize, Deserialize};
 use std::collections::HashMap;
 
+use crate::transaksi_penjualan::model::transaksi::Transaksi;
 use crate::transaksi_penjualan::model::detail_transaksi::DetailTransaksi;
+use crate::transaksi_penjualan::model::retur_transaksi::ReturTransaksi;
+use crate::manajemen_pembayaran::model::payment::{Payment, Refund, ScheduledInstallment};
+use crate::common::money::{discounted_line_subtotal_sen, from_sen};
+use crate::transaksi_penjualan::service::transaksi::TransaksiService;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(crate = "rocket::serde")]
@@ -10,6 +15,14 @@ pub struct CreateTransaksiRequest {
     pub nama_pelanggan: String,
     pub catatan: Option<String>,
     pub detail_transaksi: Vec<CreateDetailTransaksiRequest>,
+    /// Total dari client hanya diterima agar payload lama tetap valid; server
+    /// selalu menghitung ulang total dari detail dan mengabaikan nilai ini.
+    #[serde(default, skip_serializing)]
+    pub total_harga: Option<f64>,
+    /// Baris dengan `id_produk` yang sama digabung (jumlah dan diskon dijumlahkan);
+    /// `true` menolak request seperti itu.
+    #[serde(default)]
+    pub reject_duplicate_produk: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,17 +31,24 @@ pub struct CreateDetailTransaksiRequest {
     pub id_produk: i32,
     pub nama_produk: String,
     pub harga_satuan: f64,
-    pub jumlah: u32,
+    /// Dalam satuan jual produk; desimal hanya untuk produk dengan `presisi_stok` > 0.
+    pub jumlah: f64,
+    #[serde(default)]
+    pub diskon: Option<f64>,
+    /// Harga per unit yang ditetapkan kasir; bila diisi, harga katalog maupun harga grosir
+    /// tidak dipakai untuk baris ini.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub harga_override: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(crate = "rocket::serde")]
 pub struct UpdateDetailQuantityRequest {
-    pub jumlah: u32,
+    pub jumlah: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(crate = "rocket::serde")]
+#[serde(crate = "rocket::serde", rename_all = "snake_case")]
 pub struct TransaksiWithDetailsResponse {
     pub id: i32,
     pub id_pelanggan: i32,
@@ -37,14 +57,145 @@ pub struct TransaksiWithDetailsResponse {
     pub total_harga: f64,
     pub status: String,
     pub catatan: Option<String>,
+    #[serde(default)]
+    pub nomor_transaksi: Option<String>,
+    #[serde(default)]
+    pub alasan_pembatalan: Option<String>,
+    /// Lihat `TransaksiService::total_berat_gram`; null bila ada produk tanpa berat.
+    #[serde(default)]
+    pub total_berat_gram: Option<i64>,
+    pub detail_transaksi: Vec<DetailTransaksi>,
+}
+
+/// Item daftar transaksi; `detail_transaksi` hanya ada bila diminta dengan `with_details=true`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct TransaksiListItem {
+    #[serde(flatten)]
+    pub transaksi: Transaksi,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail_transaksi: Option<Vec<DetailTransaksi>>,
+}
+
+/// Respons `GET /<id>`: transaksi beserta total belanja seumur hidup pelanggannya
+/// (jumlah total transaksi SELESAI milik `id_pelanggan`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct TransaksiDetailResponse {
+    #[serde(flatten)]
+    pub transaksi: Transaksi,
+    pub total_belanja_pelanggan: f64,
+}
+
+/// Body opsional untuk `PUT /<id>/cancel`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CancelTransaksiRequest {
+    #[serde(default)]
+    pub alasan_pembatalan: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PaymentPlanRequest {
+    pub jumlah_cicilan: u32,
+    pub method: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PaymentPlanResponse {
+    pub payment: Payment,
+    pub schedule: Vec<ScheduledInstallment>,
+}
+
+/// Body `POST /checkout`: transaksi baru beserta pembayaran yang langsung melunasinya.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CheckoutRequest {
+    #[serde(flatten)]
+    pub transaksi: CreateTransaksiRequest,
+    pub method: String,
+    /// Uang yang diterima; default sama dengan total. Kurang dari total ditolak,
+    /// kelebihannya dikembalikan sebagai `kembalian`.
+    #[serde(default)]
+    pub amount: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CheckoutResponse {
+    pub transaksi: Transaksi,
+    /// Lihat `TransaksiService::total_berat_gram`; null bila ada produk tanpa berat.
+    #[serde(default)]
+    pub total_berat_gram: Option<i64>,
     pub detail_transaksi: Vec<DetailTransaksi>,
+    pub payment: Payment,
+    pub kembalian: f64,
+}
+
+/// Body opsional untuk `POST /<id>/void`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct VoidTransaksiRequest {
+    #[serde(default)]
+    pub alasan: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct VoidTransaksiResponse {
+    pub transaksi: Transaksi,
+    /// Refund yang dibuat untuk sisa saldo tiap payment transaksi ini.
+    pub refunds: Vec<Refund>,
+}
+
+/// Body `POST /<id>/return`: baris detail dan jumlah yang dikembalikan pelanggan.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReturTransaksiRequest {
+    pub items: Vec<ReturItemRequest>,
+    #[serde(default)]
+    pub alasan: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReturItemRequest {
+    pub id_detail: i32,
+    pub jumlah: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReturTransaksiResponse {
+    pub retur: ReturTransaksi,
+    /// Refund yang dibuat untuk nilai retur, dibagi ke payment yang masih punya saldo.
+    pub refunds: Vec<Refund>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct TransaksiPaymentSummary {
+    pub total_harga: f64,
+    pub paid_total: f64,
+    pub outstanding: f64,
+    pub payment_count: i64,
+    pub last_payment_date: Option<String>,
+}
+
+/// Hasil `POST /<id>/recompute-total`: total tersimpan sebelum dan sesudah diperbaiki.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct RecomputeTotalResponse {
+    pub id: i32,
+    pub old_total: f64,
+    pub new_total: f64,
 }
 
 impl CreateTransaksiRequest {
     pub fn validate(&self) -> Result<(), String> {
-        if self.nama_pelanggan.trim().is_empty() {
-            return Err("Customer name cannot be empty".to_string());
-        }
+        TransaksiService::normalize_nama_pelanggan(&self.nama_pelanggan)?;
 
         if self.id_pelanggan <= 0 {
             return Err("Invalid customer ID".to_string());
@@ -63,14 +214,63 @@ impl CreateTransaksiRequest {
         Ok(())
     }
 
+    /// Menggabungkan baris dengan `id_produk` yang sama ke baris pertamanya, atau menolak
+    /// request bila `reject_duplicate_produk`. Stok dan total dihitung dari hasil ini.
+    pub fn merge_duplicate_lines(&self) -> Result<CreateTransaksiRequest, String> {
+        let mut merged = self.clone();
+        merged.detail_transaksi = Vec::with_capacity(self.detail_transaksi.len());
+        // id_produk -> (posisi di hasil gabungan, indeks baris pertama di request)
+        let mut first_line: HashMap<i32, (usize, usize)> = HashMap::new();
+
+        for (index, detail) in self.detail_transaksi.iter().enumerate() {
+            match first_line.get(&detail.id_produk) {
+                Some(&(_, first_index)) if self.reject_duplicate_produk => {
+                    return Err(format!(
+                        "Detail {}: product {} already listed in detail {}",
+                        index + 1,
+                        detail.id_produk,
+                        first_index + 1
+                    ));
+                }
+                Some(&(position, _)) => {
+                    let line = &mut merged.detail_transaksi[position];
+                    line.jumlah += detail.jumlah;
+                    line.diskon = match (line.diskon, detail.diskon) {
+                        (None, None) => None,
+                        (a, b) => Some(a.unwrap_or(0.0) + b.unwrap_or(0.0)),
+                    };
+                    line.harga_override = line.harga_override.or(detail.harga_override);
+                }
+                None => {
+                    first_line.insert(detail.id_produk, (merged.detail_transaksi.len(), index));
+                    merged.detail_transaksi.push(detail.clone());
+                }
+            }
+        }
+        Ok(merged)
+    }
+
+    // Dijumlah dalam sen supaya total sama persis dengan jumlah subtotal tiap baris
     pub fn calculate_total(&self, product_prices: &HashMap<i32, f64>) -> f64 {
-        self.detail_transaksi
+        let total_sen: i64 = self.detail_transaksi
             .iter()
             .map(|detail| {
                 let price = product_prices.get(&detail.id_produk).unwrap_or(&detail.harga_satuan);
-                price * detail.jumlah as f64
+                discounted_line_subtotal_sen(*price, detail.jumlah, detail.diskon)
             })
-            .sum()
+            .sum();
+        from_sen(total_sen)
+    }
+
+    /// Memeriksa ulang diskon tiap baris terhadap harga yang dipakai server.
+    pub fn validate_line_discounts(&self, product_prices: &HashMap<i32, f64>) -> Result<(), String> {
+        for (index, detail) in self.detail_transaksi.iter().enumerate() {
+            let price = product_prices.get(&detail.id_produk).unwrap_or(&detail.harga_satuan);
+            if let Err(err) = DetailTransaksi::validate_diskon(*price, detail.jumlah, detail.diskon) {
+                return Err(format!("Detail {}: {}", index + 1, err));
+            }
+        }
+        Ok(())
     }
 }
 
@@ -84,7 +284,7 @@ impl CreateDetailTransaksiRequest {
             return Err("Invalid product ID".to_string());
         }
 
-        if self.jumlah == 0 {
+        if !(self.jumlah.is_finite() && self.jumlah > 0.0) {
             return Err("Quantity must be greater than 0".to_string());
         }
 
@@ -92,7 +292,11 @@ impl CreateDetailTransaksiRequest {
             return Err("Unit price cannot be negative".to_string());
         }
 
-        Ok(())
+        if self.harga_override.is_some_and(|harga| harga < 0.0) {
+            return Err("Override price cannot be negative".to_string());
+        }
+
+        DetailTransaksi::validate_diskon(self.harga_satuan, self.jumlah, self.diskon)
     }
 
     pub fn to_detail_transaksi(&self, id_transaksi: i32, harga_satuan: f64) -> DetailTransaksi {
@@ -102,9 +306,28 @@ impl CreateDetailTransaksiRequest {
             harga_satuan,
             self.jumlah,
         )
+        .with_diskon(self.diskon)
     }
 }
 
+/// Satu baris laporan produk terlaris: total unit terjual dan pendapatan dari transaksi SELESAI.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct TopProductReport {
+    pub id_produk: i32,
+    pub total_jumlah: f64,
+    pub total_pendapatan: f64,
+}
+
+/// Satu baris laporan penjualan per kategori produk dari transaksi SELESAI.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct SalesByCategoryReport {
+    pub kategori: String,
+    pub total_qty: f64,
+    pub total_revenue: f64,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -115,7 +338,9 @@ mod tests {
             id_produk: 101,
             nama_produk: "Macbook Pro M3".to_string(),
             harga_satuan: 15000000.0,
-            jumlah: 2,
+            jumlah: 2.0,
+            diskon: None,
+            harga_override: None,
         };
 
         let detail = request.to_detail_transaksi(1, request.harga_satuan);
@@ -123,7 +348,7 @@ mod tests {
         assert_eq!(detail.id_transaksi, 1);
         assert_eq!(detail.id_produk, 101);
         assert_eq!(detail.harga_satuan, 15000000.0);
-        assert_eq!(detail.jumlah, 2);
+        assert_eq!(detail.jumlah, 2.0);
         assert_eq!(detail.subtotal, 30000000.0);
     }
 
@@ -133,12 +358,16 @@ mod tests {
             id_pelanggan: 1,
             nama_pelanggan: "Alice".to_string(),
             catatan: Some("Test".to_string()),
+            total_harga: None,
+            reject_duplicate_produk: false,
             detail_transaksi: vec![
                 CreateDetailTransaksiRequest {
                     id_produk: 1,
                     nama_produk: "Produk A".to_string(),
                     harga_satuan: 10000.0,
-                    jumlah: 2,
+                    jumlah: 2.0,
+                    diskon: None,
+                    harga_override: None,
                 },
             ],
         };
@@ -146,12 +375,49 @@ mod tests {
         assert!(request.validate().is_ok());
     }
 
+    #[test]
+    fn test_merge_duplicate_lines_sums_jumlah_and_diskon() {
+        let line = |id_produk: i32, jumlah: f64, diskon: Option<f64>| CreateDetailTransaksiRequest {
+            id_produk,
+            nama_produk: format!("Produk {}", id_produk),
+            harga_satuan: 10000.0,
+            jumlah,
+            diskon,
+            harga_override: None,
+        };
+        let mut request = CreateTransaksiRequest {
+            id_pelanggan: 1,
+            nama_pelanggan: "Alice".to_string(),
+            catatan: None,
+            total_harga: None,
+            reject_duplicate_produk: false,
+            detail_transaksi: vec![line(1, 2.0, None), line(2, 1.0, None), line(1, 3.0, Some(500.0))],
+        };
+
+        let merged = request.merge_duplicate_lines().unwrap();
+        assert_eq!(merged.detail_transaksi.len(), 2);
+        assert_eq!(merged.detail_transaksi[0].jumlah, 5.0);
+        assert_eq!(merged.detail_transaksi[0].diskon, Some(500.0));
+        assert_eq!(merged.detail_transaksi[1].id_produk, 2);
+        assert_eq!(merged.calculate_total(&HashMap::new()), request.calculate_total(&HashMap::new()));
+
+        request.reject_duplicate_produk = true;
+        assert_eq!(
+            request.merge_duplicate_lines().unwrap_err(),
+            "Detail 3: product 1 already listed in detail 1"
+        );
+        request.detail_transaksi.pop();
+        assert_eq!(request.merge_duplicate_lines().unwrap().detail_transaksi.len(), 2);
+    }
+
     #[test]
     fn test_validate_invalid_request_empty_detail() {
         let request = CreateTransaksiRequest {
             id_pelanggan: 1,
             nama_pelanggan: "Alice".to_string(),
             catatan: None,
+            total_harga: None,
+            reject_duplicate_produk: false,
             detail_transaksi: vec![],
         };
 
@@ -164,12 +430,16 @@ mod tests {
             id_pelanggan: 1,
             nama_pelanggan: "Alice".to_string(),
             catatan: None,
+            total_harga: None,
+            reject_duplicate_produk: false,
             detail_transaksi: vec![
                 CreateDetailTransaksiRequest {
                     id_produk: 1,
                     nama_produk: "Produk A".to_string(),
                     harga_satuan: -100.0,
-                    jumlah: 2,
+                    jumlah: 2.0,
+                    diskon: None,
+                    harga_override: None,
                 },
             ],
         };
@@ -183,18 +453,24 @@ mod tests {
             id_pelanggan: 1,
             nama_pelanggan: "Alice".to_string(),
             catatan: None,
+            total_harga: None,
+            reject_duplicate_produk: false,
             detail_transaksi: vec![
                 CreateDetailTransaksiRequest {
                     id_produk: 1,
                     nama_produk: "Produk A".to_string(),
                     harga_satuan: 0.0,
-                    jumlah: 3,
+                    jumlah: 3.0,
+                    diskon: None,
+                    harga_override: None,
                 },
                 CreateDetailTransaksiRequest {
                     id_produk: 2,
                     nama_produk: "Produk B".to_string(),
                     harga_satuan: 0.0,
-                    jumlah: 2,
+                    jumlah: 2.0,
+                    diskon: None,
+                    harga_override: None,
                 },
             ],
         };
@@ -206,4 +482,77 @@ mod tests {
         let total = request.calculate_total(&product_prices);
         assert_eq!(total, 3.0 * 10000.0 + 2.0 * 20000.0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_calculate_total_of_many_small_cent_lines_is_exact() {
+        let detail_transaksi: Vec<CreateDetailTransaksiRequest> = (1..=150)
+            .map(|id_produk| CreateDetailTransaksiRequest {
+                id_produk,
+                nama_produk: format!("Baut {}", id_produk),
+                harga_satuan: 0.1,
+                jumlah: 1.0,
+                diskon: None,
+                harga_override: None,
+            })
+            .chain((151..=200).map(|id_produk| CreateDetailTransaksiRequest {
+                id_produk,
+                nama_produk: format!("Ring {}", id_produk),
+                harga_satuan: 0.07,
+                jumlah: 3.0,
+                diskon: None,
+                harga_override: None,
+            }))
+            .collect();
+        let request = CreateTransaksiRequest {
+            id_pelanggan: 1,
+            nama_pelanggan: "Alice".to_string(),
+            catatan: None,
+            total_harga: None,
+            reject_duplicate_produk: false,
+            detail_transaksi,
+        };
+
+        // 150 x 0.10 + 50 x 3 x 0.07 = 15.00 + 10.50
+        let total = request.calculate_total(&HashMap::new());
+        assert_eq!(total, 25.5);
+
+        let subtotals = crate::common::money::sum_amounts(request.detail_transaksi.iter()
+            .map(|d| d.to_detail_transaksi(1, d.harga_satuan).subtotal));
+        assert_eq!(subtotals, total);
+    }
+
+    #[test]
+    fn test_transaksi_with_details_response_json_keys() {
+        let response = TransaksiWithDetailsResponse {
+            id: 1,
+            id_pelanggan: 7,
+            nama_pelanggan: "Budi".to_string(),
+            tanggal_transaksi: "2024-01-01 10:00:00".to_string(),
+            total_harga: 30000.0,
+            status: "MASIH_DIPROSES".to_string(),
+            catatan: None,
+            nomor_transaksi: Some("INV-20240101-0001".to_string()),
+            alasan_pembatalan: None,
+            total_berat_gram: Some(1500),
+            detail_transaksi: vec![DetailTransaksi::new(1, 101, 10000.0, 3.0)],
+        };
+
+        let value = rocket::serde::json::serde_json::to_value(&response).unwrap();
+        let mut keys: Vec<&str> = value.as_object().unwrap().keys().map(|k| k.as_str()).collect();
+        keys.sort();
+        assert_eq!(keys, vec![
+            "alasan_pembatalan", "catatan", "detail_transaksi", "id", "id_pelanggan",
+            "nama_pelanggan", "nomor_transaksi", "status", "tanggal_transaksi", "total_berat_gram",
+            "total_harga",
+        ]);
+
+        let mut detail_keys: Vec<&str> = value["detail_transaksi"][0].as_object().unwrap()
+            .keys().map(|k| k.as_str()).collect();
+        detail_keys.sort();
+        assert_eq!(detail_keys, vec!["harga_satuan", "id", "id_produk", "id_transaksi", "jumlah", "subtotal"]);
+
+        let round_trip: TransaksiWithDetailsResponse = rocket::serde::json::serde_json::from_value(value).unwrap();
+        assert_eq!(round_trip.nama_pelanggan, "Budi");
+        assert_eq!(round_trip.detail_transaksi.len(), 1);
+    }
+}
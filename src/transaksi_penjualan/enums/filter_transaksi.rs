@@ -0,0 +1,57 @@
+/// Kolom yang boleh dipilih lewat query param `filter` saat mencari transaksi.
+/// Hanya varian di sini yang diterjemahkan ke nama kolom SQL; nilai lain ditolak.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterTransaksi {
+    Id,
+    NamaPelanggan,
+    Catatan,
+    Status,
+    Total,
+    All,
+}
+
+impl FilterTransaksi {
+    pub const ALLOWED: &'static str = "id, nama_pelanggan, pelanggan, catatan, status, total, total_harga, all";
+
+    /// Kolom default ketika `filter` tidak diisi.
+    pub const DEFAULT_COLUMNS: &'static [&'static str] = &["nama_pelanggan", "catatan"];
+
+    pub fn from_string(filter: &str) -> Option<Self> {
+        match filter.trim().to_lowercase().as_str() {
+            "id" => Some(FilterTransaksi::Id),
+            "nama_pelanggan" | "pelanggan" => Some(FilterTransaksi::NamaPelanggan),
+            "catatan" => Some(FilterTransaksi::Catatan),
+            "status" => Some(FilterTransaksi::Status),
+            "total" | "total_harga" => Some(FilterTransaksi::Total),
+            "all" => Some(FilterTransaksi::All),
+            _ => None,
+        }
+    }
+
+    /// Kolom angka di-CAST ke teks supaya keyword tetap dicocokkan sebagai substring lewat `LIKE`.
+    pub fn columns(&self) -> &'static [&'static str] {
+        match self {
+            FilterTransaksi::Id => &["CAST(id AS TEXT)"],
+            FilterTransaksi::NamaPelanggan => &["nama_pelanggan"],
+            FilterTransaksi::Catatan => &["catatan"],
+            FilterTransaksi::Status => &["status"],
+            FilterTransaksi::Total => &["CAST(total_harga AS TEXT)"],
+            FilterTransaksi::All => &["nama_pelanggan", "status", "catatan"],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_transaksi_only_accepts_allowlisted_fields() {
+        assert_eq!(FilterTransaksi::from_string("Pelanggan"), Some(FilterTransaksi::NamaPelanggan));
+        assert_eq!(FilterTransaksi::from_string("all").unwrap().columns(), &["nama_pelanggan", "status", "catatan"]);
+        assert_eq!(FilterTransaksi::from_string("id_pelanggan) OR (1=1"), None);
+        assert_eq!(FilterTransaksi::from_string("total_harga").unwrap().columns(), &["CAST(total_harga AS TEXT)"]);
+        assert_eq!(FilterTransaksi::from_string("ID"), Some(FilterTransaksi::Id));
+        assert_eq!(FilterTransaksi::from_string("id_pelanggan"), None);
+    }
+}
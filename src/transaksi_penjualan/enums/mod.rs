@@ -1 +1,2 @@
-pub mod status_transaksi;
\ No newline at end of file
+pub mod status_transaksi;
+pub mod filter_transaksi;
@@ -5,6 +5,9 @@ pub enum StatusTransaksi {
     MasihDiproses,
     Selesai,
     Dibatalkan,
+    /// Penjualan selesai yang dibalik setelahnya (stok dikembalikan, pembayaran di-refund);
+    /// berbeda dengan `Dibatalkan` yang terjadi sebelum transaksi selesai.
+    Void,
 }
 
 impl StatusTransaksi {
@@ -13,6 +16,7 @@ impl StatusTransaksi {
             "MASIH_DIPROSES" | "MASIH DIPROSES" | "DIPROSES" => Some(StatusTransaksi::MasihDiproses),
             "SELESAI" | "COMPLETED" | "DONE" => Some(StatusTransaksi::Selesai),
             "DIBATALKAN" | "CANCELLED" | "BATAL" => Some(StatusTransaksi::Dibatalkan),
+            "VOID" | "VOIDED" | "DIVOID" => Some(StatusTransaksi::Void),
             _ => None,
         }
     }
@@ -22,6 +26,7 @@ impl StatusTransaksi {
             StatusTransaksi::MasihDiproses => "MASIH_DIPROSES".to_string(),
             StatusTransaksi::Selesai => "SELESAI".to_string(),
             StatusTransaksi::Dibatalkan => "DIBATALKAN".to_string(),
+            StatusTransaksi::Void => "VOID".to_string(),
         }
     }
 
@@ -32,6 +37,10 @@ impl StatusTransaksi {
     pub fn can_be_cancelled(&self) -> bool {
         matches!(self, StatusTransaksi::MasihDiproses)
     }
+
+    pub fn can_be_voided(&self) -> bool {
+        matches!(self, StatusTransaksi::Selesai)
+    }
 }
 
 #[cfg(test)]
@@ -69,6 +78,8 @@ mod tests {
         assert_eq!(StatusTransaksi::MasihDiproses.to_string(), "MASIH_DIPROSES");
         assert_eq!(StatusTransaksi::Selesai.to_string(), "SELESAI");
         assert_eq!(StatusTransaksi::Dibatalkan.to_string(), "DIBATALKAN");
+        assert_eq!(StatusTransaksi::Void.to_string(), "VOID");
+        assert_eq!(StatusTransaksi::from_string("voided"), Some(StatusTransaksi::Void));
     }
 
     #[test]
@@ -83,5 +94,14 @@ mod tests {
         assert!(StatusTransaksi::MasihDiproses.can_be_cancelled());
         assert!(!StatusTransaksi::Selesai.can_be_cancelled());
         assert!(!StatusTransaksi::Dibatalkan.can_be_cancelled());
+        assert!(!StatusTransaksi::Void.can_be_cancelled());
+    }
+
+    #[test]
+    fn test_can_be_voided() {
+        assert!(StatusTransaksi::Selesai.can_be_voided());
+        assert!(!StatusTransaksi::MasihDiproses.can_be_voided());
+        assert!(!StatusTransaksi::Dibatalkan.can_be_voided());
+        assert!(!StatusTransaksi::Void.can_be_voided());
     }
 }
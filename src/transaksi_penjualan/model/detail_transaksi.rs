@@ -1,4 +1,5 @@
 use rocket::serde::{Serialize, Deserialize};
+use crate::common::money::{discounted_line_subtotal_sen, from_sen, line_subtotal_sen, to_sen};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(crate = "rocket::serde")]
@@ -7,7 +8,10 @@ pub struct DetailTransaksi {
     pub id_transaksi: i32,
     pub id_produk: i32,
     pub harga_satuan: f64, 
-    pub jumlah: u32,
+    pub jumlah: f64,
+    /// Potongan nominal untuk baris ini; `subtotal` sudah dikurangi nilai ini.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diskon: Option<f64>,
     pub subtotal: f64,
 }
 
@@ -16,9 +20,9 @@ impl DetailTransaksi {
         id_transaksi: i32,
         id_produk: i32,
         harga_satuan: f64,
-        jumlah: u32,
+        jumlah: f64,
     ) -> Self {
-        let subtotal = harga_satuan * jumlah as f64;
+        let subtotal = from_sen(line_subtotal_sen(harga_satuan, jumlah));
         
         DetailTransaksi {
             id: 0,
@@ -26,18 +30,48 @@ impl DetailTransaksi {
             id_produk,
             harga_satuan,
             jumlah,
+            diskon: None,
             subtotal,
         }
     }
 
-    pub fn update_jumlah(&mut self, jumlah: u32) {
+    pub fn with_diskon(mut self, diskon: Option<f64>) -> Self {
+        self.diskon = diskon;
+        self.recalculate_subtotal();
+        self
+    }
+
+    pub fn update_jumlah(&mut self, jumlah: f64) {
         self.jumlah = jumlah;
-        self.subtotal = self.harga_satuan * jumlah as f64;
+        self.recalculate_subtotal();
     }
 
     pub fn update_harga_satuan(&mut self, harga_satuan: f64) {
         self.harga_satuan = harga_satuan;
-        self.subtotal = harga_satuan * self.jumlah as f64;
+        self.recalculate_subtotal();
+    }
+
+    /// Diskon baris harus non-negatif dan tidak melebihi subtotal sebelum diskon.
+    pub fn validate_diskon(harga_satuan: f64, jumlah: f64, diskon: Option<f64>) -> Result<(), String> {
+        let Some(diskon) = diskon else {
+            return Ok(());
+        };
+        if !diskon.is_finite() || diskon < 0.0 {
+            return Err("Line discount cannot be negative".to_string());
+        }
+        let line_subtotal = line_subtotal_sen(harga_satuan, jumlah);
+        if to_sen(diskon) > line_subtotal {
+            return Err(format!(
+                "Line discount {:.2} exceeds line subtotal {:.2}",
+                diskon,
+                from_sen(line_subtotal)
+            ));
+        }
+        Ok(())
+    }
+
+    fn recalculate_subtotal(&mut self) {
+        self.subtotal = from_sen(discounted_line_subtotal_sen(self.harga_satuan, self.jumlah, self.diskon));
     }
 }
 
@@ -51,13 +85,13 @@ mod test {
             1,
             101,
             15000000.0,
-            2,
+            2.0,
         );
 
         assert_eq!(detail.id_transaksi, 1);
         assert_eq!(detail.id_produk, 101);
         assert_eq!(detail.harga_satuan, 15000000.0);
-        assert_eq!(detail.jumlah, 2);
+        assert_eq!(detail.jumlah, 2.0);
         assert_eq!(detail.subtotal, 30000000.0);
     }
 
@@ -67,11 +101,11 @@ mod test {
             1,
             102,
             250000.0,
-            1,
+            1.0,
         );
 
-        detail.update_jumlah(3);
-        assert_eq!(detail.jumlah, 3);
+        detail.update_jumlah(3.0);
+        assert_eq!(detail.jumlah, 3.0);
         assert_eq!(detail.subtotal, 750000.0);
     }
 
@@ -81,11 +115,24 @@ mod test {
             1,
             103,
             500000.0,
-            2,
+            2.0,
         );
 
         detail.update_harga_satuan(600000.0);
         assert_eq!(detail.harga_satuan, 600000.0);
         assert_eq!(detail.subtotal, 1200000.0);
     }
+
+    #[test]
+    fn test_diskon_reduces_subtotal_and_survives_updates() {
+        let mut detail = DetailTransaksi::new(1, 104, 100000.0, 2.0).with_diskon(Some(15000.0));
+        assert_eq!(detail.subtotal, 185000.0);
+
+        detail.update_jumlah(3.0);
+        assert_eq!(detail.subtotal, 285000.0);
+
+        assert!(DetailTransaksi::validate_diskon(100000.0, 2.0, Some(200000.0)).is_ok());
+        assert!(DetailTransaksi::validate_diskon(100000.0, 2.0, Some(200000.01)).is_err());
+        assert!(DetailTransaksi::validate_diskon(100000.0, 2.0, Some(-1.0)).is_err());
+    }
 }
\ No newline at end of file
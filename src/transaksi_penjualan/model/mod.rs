@@ -1,2 +1,4 @@
 pub mod transaksi;
-pub mod detail_transaksi;
\ No newline at end of file
+pub mod detail_transaksi;
+pub mod transaksi_status_history;
+pub mod retur_transaksi;
\ No newline at end of file
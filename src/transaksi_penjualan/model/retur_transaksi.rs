@@ -0,0 +1,32 @@
+use chrono::{DateTime, Utc};
+use rocket::serde::{Serialize, Deserialize};
+
+/// Retur sebagian barang dari transaksi SELESAI tanpa membatalkan transaksinya.
+/// `nilai_retur` adalah nilai barang yang dikembalikan; `total_refund` yang benar-benar
+/// dikembalikan ke pelanggan, bisa lebih kecil bila transaksi belum lunas.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReturTransaksi {
+    pub id: String,
+    pub id_transaksi: i32,
+    pub items: Vec<ReturItem>,
+    pub nilai_retur: f64,
+    pub total_refund: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alasan: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    #[serde(with = "crate::common::timestamp::rfc3339")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Satu baris detail yang dikembalikan; `jumlah` dalam satuan produk (boleh desimal untuk barang
+/// timbangan) dan `nilai` sebanding dengan subtotal baris setelah diskon.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ReturItem {
+    pub id_detail: i32,
+    pub id_produk: i32,
+    pub jumlah: f64,
+    pub nilai: f64,
+}
@@ -1,6 +1,7 @@
 use chrono::{Utc, NaiveDateTime};
 use rocket::serde::{Serialize, Deserialize};
 use crate::transaksi_penjualan::enums::status_transaksi::StatusTransaksi;
+use crate::common::audit::AuditTrail;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(crate = "rocket::serde")]
@@ -8,10 +9,19 @@ pub struct Transaksi {
     pub id: i32,
     pub id_pelanggan: i32,
     pub nama_pelanggan: String,
-    pub tanggal_transaksi: String,  
+    #[serde(with = "crate::common::timestamp::naive_string")]
+    pub tanggal_transaksi: String,
     pub total_harga: f64,
     pub status: StatusTransaksi,
     pub catatan: Option<String>,
+    /// Nomor struk `INV-YYYYMMDD-NNNN`, diisi repository saat transaksi disimpan.
+    #[serde(default)]
+    pub nomor_transaksi: Option<String>,
+    /// Alasan pembatalan yang dicatat staf; hanya terisi untuk transaksi DIBATALKAN.
+    #[serde(default)]
+    pub alasan_pembatalan: Option<String>,
+    #[serde(flatten)]
+    pub audit: AuditTrail,
 }
 
 impl Transaksi {
@@ -29,6 +39,9 @@ impl Transaksi {
             total_harga,
             status: StatusTransaksi::MasihDiproses,
             catatan,
+            nomor_transaksi: None,
+            alasan_pembatalan: None,
+            audit: AuditTrail::default(),
         }
     }
 
@@ -101,11 +114,15 @@ impl Transaksi {
                 "print_receipt".to_string(),
                 "view_details".to_string(),
                 "reopen".to_string(), 
+                "void".to_string(),
             ],
             StatusTransaksi::Dibatalkan => vec![
                 "view_details".to_string(),
                 "reopen".to_string(), 
             ],
+            StatusTransaksi::Void => vec![
+                "view_details".to_string(),
+            ],
         }
     }
 
@@ -220,4 +237,18 @@ mod test {
         let parsed_datetime = transaksi.get_tanggal_as_datetime();
         assert!(parsed_datetime.is_ok());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_tanggal_transaksi_serializes_as_rfc3339_utc() {
+        let mut transaksi = Transaksi::new(1, "Castorice".to_string(), 10000.0, None);
+        transaksi.tanggal_transaksi = "2024-01-01 10:00:00".to_string();
+
+        let value = rocket::serde::json::serde_json::to_value(&transaksi).unwrap();
+        assert_eq!(value["tanggal_transaksi"], "2024-01-01T10:00:00Z");
+
+        // Kembali ke format kolom database supaya filter tanggal tetap bekerja
+        let round_trip: Transaksi = rocket::serde::json::serde_json::from_value(value).unwrap();
+        assert_eq!(round_trip.tanggal_transaksi, "2024-01-01 10:00:00");
+        assert!(round_trip.get_tanggal_as_datetime().is_ok());
+    }
+}
@@ -0,0 +1,21 @@
+use chrono::{DateTime, Utc};
+use rocket::serde::{Serialize, Deserialize};
+
+use crate::transaksi_penjualan::enums::status_transaksi::StatusTransaksi;
+
+/// Satu perubahan status transaksi. `alasan` terisi untuk pembatalan yang disertai alasan;
+/// `changed_by` hanya ditampilkan ke admin.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct TransaksiStatusHistory {
+    pub id: i32,
+    pub id_transaksi: i32,
+    pub status_lama: StatusTransaksi,
+    pub status_baru: StatusTransaksi,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alasan: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changed_by: Option<String>,
+    #[serde(with = "crate::common::timestamp::rfc3339")]
+    pub changed_at: DateTime<Utc>,
+}
@@ -19,6 +19,7 @@ pub enum StateAction {
     Complete,
     Cancel,
     Reopen,
+    Void,
 }
 
 // State: Masih Diproses
@@ -38,6 +39,7 @@ impl TransaksiState for MasihDiprosesState {
             StateAction::Complete => Ok(Box::new(SelesaiState)),
             StateAction::Cancel => Ok(Box::new(DibatalkanState)),
             StateAction::Reopen => Err("Transaksi sudah dalam status diproses".to_string()),
+            StateAction::Void => Err("Hanya transaksi selesai yang dapat di-void".to_string()),
         }
     }
     
@@ -63,6 +65,7 @@ impl TransaksiState for SelesaiState {
     fn next_state(&self, action: StateAction) -> Result<Box<dyn TransaksiState>, String> {
         match action {
             StateAction::Reopen => Ok(Box::new(MasihDiprosesState)),
+            StateAction::Void => Ok(Box::new(VoidState)),
             _ => Err("Transaksi selesai tidak dapat diubah statusnya".to_string()),
         }
     }
@@ -70,7 +73,7 @@ impl TransaksiState for SelesaiState {
     fn status(&self) -> StatusTransaksi { StatusTransaksi::Selesai }
     
     fn get_allowed_actions(&self) -> Vec<String> {
-        vec!["print_receipt".to_string(), "view_details".to_string()]
+        vec!["print_receipt".to_string(), "view_details".to_string(), "void".to_string()]
     }
 }
 
@@ -100,6 +103,29 @@ impl TransaksiState for DibatalkanState {
     }
 }
 
+// State: Void (final, tidak bisa dibuka kembali)
+#[derive(Debug, Clone)]
+pub struct VoidState;
+
+impl TransaksiState for VoidState {
+    fn can_be_modified(&self) -> bool { false }
+    fn can_be_cancelled(&self) -> bool { false }
+    fn can_be_completed(&self) -> bool { false }
+    fn can_add_items(&self) -> bool { false }
+    fn can_update_items(&self) -> bool { false }
+    fn can_delete_items(&self) -> bool { false }
+    
+    fn next_state(&self, _action: StateAction) -> Result<Box<dyn TransaksiState>, String> {
+        Err("Transaksi void tidak dapat diubah statusnya".to_string())
+    }
+    
+    fn status(&self) -> StatusTransaksi { StatusTransaksi::Void }
+    
+    fn get_allowed_actions(&self) -> Vec<String> {
+        vec!["view_details".to_string()]
+    }
+}
+
 pub struct TransaksiStateFactory;
 
 impl TransaksiStateFactory {
@@ -108,6 +134,7 @@ impl TransaksiStateFactory {
             StatusTransaksi::MasihDiproses => Box::new(MasihDiprosesState),
             StatusTransaksi::Selesai => Box::new(SelesaiState),
             StatusTransaksi::Dibatalkan => Box::new(DibatalkanState),
+            StatusTransaksi::Void => Box::new(VoidState),
         }
     }
 }
@@ -149,6 +176,16 @@ mod tests {
 
         let result = processing_state.next_state(StateAction::Reopen);
         assert!(result.is_err());
+        assert!(processing_state.next_state(StateAction::Void).is_err());
+    }
+
+    #[test]
+    fn test_void_only_from_selesai_and_is_final() {
+        let voided = SelesaiState.next_state(StateAction::Void).unwrap();
+        assert_eq!(voided.status(), StatusTransaksi::Void);
+        assert!(DibatalkanState.next_state(StateAction::Void).is_err());
+        assert!(voided.next_state(StateAction::Reopen).is_err());
+        assert_eq!(voided.get_allowed_actions(), vec!["view_details".to_string()]);
     }
 
     #[test]
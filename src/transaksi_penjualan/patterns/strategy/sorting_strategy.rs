@@ -179,6 +179,9 @@ mod tests {
                 total_harga: 150000.0,
                 status: StatusTransaksi::MasihDiproses,
                 catatan: Some("Test 1".to_string()),
+                nomor_transaksi: None,
+                alasan_pembatalan: None,
+                audit: Default::default(),
             },
             Transaksi {
                 id: 2,
@@ -188,6 +191,9 @@ mod tests {
                 total_harga: 250000.0,
                 status: StatusTransaksi::Selesai,
                 catatan: Some("Test 2".to_string()),
+                nomor_transaksi: None,
+                alasan_pembatalan: None,
+                audit: Default::default(),
             },
             Transaksi {
                 id: 3,
@@ -197,6 +203,9 @@ mod tests {
                 total_harga: 100000.0,
                 status: StatusTransaksi::Dibatalkan,
                 catatan: None,
+                nomor_transaksi: None,
+                alasan_pembatalan: None,
+                audit: Default::default(),
             },
         ]
     }
@@ -1,22 +1,144 @@
-use sqlx::any::AnyRow;
-use sqlx::{Any, pool::PoolConnection};
+use rocket::futures::stream::{BoxStream, StreamExt};
+use sqlx::any::{AnyArguments, AnyRow};
+use sqlx::query::Query;
+use sqlx::{Any, AnyConnection, Connection, pool::PoolConnection, QueryBuilder};
 use sqlx::Row;
 use chrono::Utc;
+use std::collections::HashMap;
 
 use crate::transaksi_penjualan::model::transaksi::Transaksi;
+use crate::transaksi_penjualan::model::transaksi_status_history::TransaksiStatusHistory;
 use crate::transaksi_penjualan::model::detail_transaksi::DetailTransaksi;
+use crate::transaksi_penjualan::model::retur_transaksi::ReturTransaksi;
 use crate::transaksi_penjualan::enums::status_transaksi::StatusTransaksi;
+use crate::transaksi_penjualan::enums::filter_transaksi::FilterTransaksi;
+use crate::transaksi_penjualan::dto::transaksi_request::{SalesByCategoryReport, TopProductReport};
+use crate::common::pagination::{apply_pagination, escape_like};
+use crate::common::slow_query::timed_query;
+use crate::common::audit::AuditTrail;
+use crate::common::timestamp;
+
+/// Kondisi pencarian transaksi yang sudah tervalidasi. Dipakai bersama oleh query
+/// data dan query COUNT supaya keduanya selalu memakai WHERE yang sama.
+#[derive(Debug, Default)]
+pub struct TransaksiSearchFilter<'a> {
+    pub status: Option<&'a StatusTransaksi>,
+    pub id_pelanggan: Option<i32>,
+    pub keyword: Option<&'a str>,
+    pub keyword_columns: &'static [&'static str],
+    pub date_from: Option<&'a str>,
+    pub date_to: Option<&'a str>,
+}
+
+impl TransaksiSearchFilter<'_> {
+    // `%`, `_` dan `\` dari keyword di-escape supaya dicocokkan sebagai karakter biasa (lihat ESCAPE di push_conditions)
+    fn keyword_pattern(&self) -> Option<String> {
+        self.keyword
+            .map(|k| k.trim().to_lowercase())
+            .filter(|k| !k.is_empty() && !self.keyword_columns.is_empty())
+            .map(|k| format!("%{}%", escape_like(&k)))
+    }
+
+    // Placeholder ditulis manual sebagai $n karena push_bind pada driver Any
+    // menghasilkan `?` yang tidak diterima Postgres. Urutannya harus sama dengan bind_to.
+    fn push_conditions(&self, builder: &mut QueryBuilder<'_, Any>) {
+        let mut param = 0;
+
+        if self.status.is_some() {
+            param += 1;
+            builder.push(format!(" AND status = ${}", param));
+        }
+        if self.id_pelanggan.is_some() {
+            param += 1;
+            builder.push(format!(" AND id_pelanggan = ${}", param));
+        }
+        match (self.date_from, self.date_to) {
+            (Some(_), Some(_)) => {
+                builder.push(format!(" AND tanggal_transaksi BETWEEN ${} AND ${}", param + 1, param + 2));
+                param += 2;
+            }
+            (Some(_), None) => {
+                param += 1;
+                builder.push(format!(" AND tanggal_transaksi >= ${}", param));
+            }
+            (None, Some(_)) => {
+                param += 1;
+                builder.push(format!(" AND tanggal_transaksi <= ${}", param));
+            }
+            (None, None) => {}
+        }
+        if self.keyword_pattern().is_some() {
+            param += 1;
+            let conditions: Vec<String> = self.keyword_columns
+                .iter()
+                .map(|column| format!("LOWER(COALESCE({}, '')) LIKE ${} ESCAPE '\\'", column, param))
+                .collect();
+            builder.push(format!(" AND ({})", conditions.join(" OR ")));
+        }
+    }
+
+    fn bind_to<'q>(&self, mut query: Query<'q, Any, AnyArguments<'q>>) -> Query<'q, Any, AnyArguments<'q>> {
+        if let Some(status) = self.status {
+            query = query.bind(status.to_string());
+        }
+        if let Some(id_pelanggan) = self.id_pelanggan {
+            query = query.bind(id_pelanggan);
+        }
+        if let Some(date_from) = self.date_from {
+            query = query.bind(date_from.to_string());
+        }
+        if let Some(date_to) = self.date_to {
+            query = query.bind(date_to.to_string());
+        }
+        if let Some(pattern) = self.keyword_pattern() {
+            query = query.bind(pattern);
+        }
+        query
+    }
+}
 
 pub struct TransaksiRepository;
 
 impl TransaksiRepository {
+    /// Format nomor struk untuk urutan ke-`sequence` pada hari `tanggal` (YYYYMMDD).
+    pub fn format_nomor_transaksi(tanggal: &str, sequence: i64) -> String {
+        format!("INV-{}-{:04}", tanggal, sequence)
+    }
+
+    // Upsert + RETURNING dijalankan sebagai satu statement, sehingga dua transaksi yang
+    // dibuat bersamaan di hari yang sama tidak pernah mendapat nomor urut yang sama.
+    async fn next_nomor_transaksi(conn: &mut AnyConnection, tanggal: &str) -> Result<String, sqlx::Error> {
+        let row = timed_query("transaksi::next_nomor_transaksi", sqlx::query("
+                INSERT INTO transaksi_counters (tanggal, last_seq)
+                VALUES ($1, 1)
+                ON CONFLICT (tanggal) DO UPDATE SET last_seq = transaksi_counters.last_seq + 1
+                RETURNING last_seq
+            ")
+            .bind(tanggal)
+            .fetch_one(&mut *conn))
+            .await?;
+
+        let sequence: i32 = row.try_get("last_seq")?;
+        Ok(Self::format_nomor_transaksi(tanggal, sequence as i64))
+    }
+
     pub async fn create_transaksi(mut db: PoolConnection<Any>, transaksi: &Transaksi) -> Result<Transaksi, sqlx::Error> {
-        let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        Self::insert_transaksi(&mut db, transaksi).await
+    }
+
+    /// Seperti `create_transaksi`, tetapi memakai koneksi yang sudah ada sehingga bisa
+    /// menjadi bagian dari DB transaction milik pemanggil (nomor dibuat lewat savepoint).
+    pub async fn insert_transaksi(conn: &mut AnyConnection, transaksi: &Transaksi) -> Result<Transaksi, sqlx::Error> {
+        let now_utc = Utc::now();
+        let now = now_utc.format("%Y-%m-%d %H:%M:%S").to_string();
+
+        let mut tx = conn.begin().await?;
+        let nomor_transaksi = Self::next_nomor_transaksi(&mut tx, &now_utc.format("%Y%m%d").to_string()).await?;
         
-        let result = sqlx::query("
-                INSERT INTO transaksi (id_pelanggan, nama_pelanggan, tanggal_transaksi, total_harga, status, catatan, created_at, updated_at)
-                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-                RETURNING id, id_pelanggan, nama_pelanggan, tanggal_transaksi, total_harga, status, catatan
+        let result = timed_query("transaksi::create_transaksi", sqlx::query("
+                INSERT INTO transaksi (id_pelanggan, nama_pelanggan, tanggal_transaksi, total_harga, status, catatan, created_at, updated_at, nomor_transaksi, created_by, updated_by)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                RETURNING id, id_pelanggan, nama_pelanggan, tanggal_transaksi, total_harga, status, catatan, nomor_transaksi, alasan_pembatalan, created_by, updated_by
             ")
             .bind(transaksi.id_pelanggan)
             .bind(&transaksi.nama_pelanggan)
@@ -26,21 +148,25 @@ impl TransaksiRepository {
             .bind(transaksi.catatan.as_deref().unwrap_or(""))
             .bind(&now)
             .bind(&now)
-            .fetch_one(&mut *db)
+            .bind(&nomor_transaksi)
+            .bind(&transaksi.audit.created_by)
+            .bind(&transaksi.audit.updated_by)
+            .fetch_one(&mut *tx))
             .await?;
         
         let transaksi = Self::parse_row_to_transaksi(result)?;
+        tx.commit().await?;
         Ok(transaksi)
     }
 
     pub async fn get_transaksi_by_id(mut db: PoolConnection<Any>, id: i32) -> Result<Transaksi, sqlx::Error> {
-        let result = sqlx::query("
-                SELECT id, id_pelanggan, nama_pelanggan, tanggal_transaksi, total_harga, status, catatan
+        let result = timed_query("transaksi::get_transaksi_by_id", sqlx::query("
+                SELECT id, id_pelanggan, nama_pelanggan, tanggal_transaksi, total_harga, status, catatan, nomor_transaksi, alasan_pembatalan, created_by, updated_by
                 FROM transaksi
                 WHERE id = $1
             ")
             .bind(id)
-            .fetch_one(&mut *db)
+            .fetch_one(&mut *db))
             .await?;
         
         let transaksi = Self::parse_row_to_transaksi(result)?;
@@ -48,14 +174,21 @@ impl TransaksiRepository {
     }
 
     pub async fn update_transaksi(mut db: PoolConnection<Any>, transaksi: &Transaksi) -> Result<Transaksi, sqlx::Error> {
+        Self::write_transaksi_update(&mut db, transaksi).await
+    }
+
+    /// Seperti `update_transaksi`, tetapi memakai koneksi yang sudah ada sehingga bisa
+    /// menjadi bagian dari DB transaction milik pemanggil.
+    pub async fn write_transaksi_update(conn: &mut AnyConnection, transaksi: &Transaksi) -> Result<Transaksi, sqlx::Error> {
         let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
         
-        let result = sqlx::query("
+        let result = timed_query("transaksi::update_transaksi", sqlx::query("
                 UPDATE transaksi
                 SET id_pelanggan = $1, nama_pelanggan = $2, tanggal_transaksi = $3, 
-                    total_harga = $4, status = $5, catatan = $6, updated_at = $7
-                WHERE id = $8
-                RETURNING id, id_pelanggan, nama_pelanggan, tanggal_transaksi, total_harga, status, catatan
+                    total_harga = $4, status = $5, catatan = $6, updated_at = $7,
+                    alasan_pembatalan = $8, updated_by = COALESCE($9, updated_by)
+                WHERE id = $10
+                RETURNING id, id_pelanggan, nama_pelanggan, tanggal_transaksi, total_harga, status, catatan, nomor_transaksi, alasan_pembatalan, created_by, updated_by
             ")
             .bind(transaksi.id_pelanggan)
             .bind(&transaksi.nama_pelanggan)
@@ -64,31 +197,179 @@ impl TransaksiRepository {
             .bind(transaksi.status.to_string())
             .bind(transaksi.catatan.as_deref().unwrap_or(""))
             .bind(&now)
+            .bind(&transaksi.alasan_pembatalan)
+            .bind(&transaksi.audit.updated_by)
             .bind(transaksi.id)
-            .fetch_one(&mut *db)
+            .fetch_one(&mut *conn))
             .await?;
         
         let transaksi = Self::parse_row_to_transaksi(result)?;
         Ok(transaksi)
     }
 
-    pub async fn delete_transaksi(mut db: PoolConnection<Any>, id: i32) -> Result<(), sqlx::Error> {
-        sqlx::query("DELETE FROM transaksi WHERE id = $1")
+    /// Mengubah status hanya bila status tersimpan masih `status_lama`; `None` bila transaksi
+    /// tidak ada atau statusnya sudah berubah lebih dulu (mis. dua void bersamaan).
+    pub async fn update_status_if(
+        conn: &mut AnyConnection,
+        id: i32,
+        status_lama: &StatusTransaksi,
+        status_baru: &StatusTransaksi,
+        updated_by: Option<&str>,
+    ) -> Result<Option<Transaksi>, sqlx::Error> {
+        let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+        let row = timed_query("transaksi::update_status_if", sqlx::query("
+                UPDATE transaksi
+                SET status = $1, updated_at = $2, updated_by = COALESCE($3, updated_by)
+                WHERE id = $4 AND status = $5
+                RETURNING id, id_pelanggan, nama_pelanggan, tanggal_transaksi, total_harga, status, catatan, nomor_transaksi, alasan_pembatalan, created_by, updated_by
+            ")
+            .bind(status_baru.to_string())
+            .bind(&now)
+            .bind(updated_by)
             .bind(id)
-            .execute(&mut *db)
+            .bind(status_lama.to_string())
+            .fetch_optional(&mut *conn))
             .await?;
-        
+
+        row.map(Self::parse_row_to_transaksi).transpose()
+    }
+
+    pub async fn insert_status_history(
+        conn: &mut AnyConnection,
+        id_transaksi: i32,
+        status_lama: &StatusTransaksi,
+        status_baru: &StatusTransaksi,
+        alasan: Option<&str>,
+        changed_by: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        timed_query("transaksi::insert_status_history", sqlx::query("
+                INSERT INTO transaksi_status_history (id_transaksi, status_lama, status_baru, alasan, changed_by, changed_at)
+                VALUES ($1, $2, $3, $4, $5, $6)
+            ")
+            .bind(id_transaksi)
+            .bind(status_lama.to_string())
+            .bind(status_baru.to_string())
+            .bind(alasan)
+            .bind(changed_by)
+            .bind(timestamp::format_for_storage(&Utc::now()))
+            .execute(&mut *conn))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Total jumlah yang sudah diretur per `id_detail` dari semua retur transaksi ini.
+    pub async fn returned_quantities(conn: &mut AnyConnection, id_transaksi: i32) -> Result<HashMap<i32, f64>, sqlx::Error> {
+        let rows = timed_query("transaksi::returned_quantities", sqlx::query("
+                SELECT i.id_detail, CAST(SUM(i.jumlah) AS DOUBLE PRECISION) AS jumlah
+                FROM retur_transaksi_item i
+                JOIN retur_transaksi r ON r.id = i.id_retur
+                WHERE r.id_transaksi = $1
+                GROUP BY i.id_detail
+            ")
+            .bind(id_transaksi)
+            .fetch_all(&mut *conn))
+            .await?;
+
+        rows.iter()
+            .map(|row| Ok((row.try_get::<i32, _>("id_detail")?, row.try_get::<f64, _>("jumlah")?.max(0.0))))
+            .collect()
+    }
+
+    /// Menyimpan retur beserta item-itemnya; dipanggil di dalam DB transaction milik service.
+    pub async fn insert_retur(conn: &mut AnyConnection, retur: &ReturTransaksi) -> Result<(), sqlx::Error> {
+        timed_query("transaksi::insert_retur", sqlx::query("
+                INSERT INTO retur_transaksi (id, id_transaksi, nilai_retur, total_refund, alasan, created_by, created_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ")
+            .bind(&retur.id)
+            .bind(retur.id_transaksi)
+            .bind(retur.nilai_retur)
+            .bind(retur.total_refund)
+            .bind(&retur.alasan)
+            .bind(&retur.created_by)
+            .bind(timestamp::format_for_storage(&retur.created_at))
+            .execute(&mut *conn))
+            .await?;
+
+        for item in &retur.items {
+            timed_query("transaksi::insert_retur_item", sqlx::query("
+                    INSERT INTO retur_transaksi_item (id_retur, id_detail, id_produk, jumlah, nilai)
+                    VALUES ($1, $2, $3, $4, $5)
+                ")
+                .bind(&retur.id)
+                .bind(item.id_detail)
+                .bind(item.id_produk)
+                .bind(item.jumlah)
+                .bind(item.nilai)
+                .execute(&mut *conn))
+                .await?;
+        }
+
         Ok(())
     }
 
+    /// Riwayat status dari yang paling lama; `id` memutus urutan bila waktunya sama.
+    pub async fn get_status_history(mut db: PoolConnection<Any>, id_transaksi: i32) -> Result<Vec<TransaksiStatusHistory>, sqlx::Error> {
+        let rows = timed_query("transaksi::get_status_history", sqlx::query("
+                SELECT id, id_transaksi, status_lama, status_baru, alasan, changed_by, changed_at
+                FROM transaksi_status_history
+                WHERE id_transaksi = $1
+                ORDER BY changed_at ASC, id ASC
+            ")
+            .bind(id_transaksi)
+            .fetch_all(&mut *db))
+            .await?;
+
+        rows.into_iter().map(|row| {
+            let status = |column: &str| -> Result<StatusTransaksi, sqlx::Error> {
+                let value: String = row.try_get(column)?;
+                StatusTransaksi::from_string(&value).ok_or_else(|| sqlx::Error::ColumnDecode {
+                    index: column.to_string(),
+                    source: format!("unknown status '{value}'").into(),
+                })
+            };
+            let changed_at: String = row.try_get("changed_at")?;
+
+            Ok(TransaksiStatusHistory {
+                id: row.try_get("id")?,
+                id_transaksi: row.try_get("id_transaksi")?,
+                status_lama: status("status_lama")?,
+                status_baru: status("status_baru")?,
+                alasan: row.try_get::<String, _>("alasan").ok(),
+                changed_by: row.try_get::<String, _>("changed_by").ok(),
+                changed_at: timestamp::parse(&changed_at).ok_or_else(|| sqlx::Error::ColumnDecode {
+                    index: "changed_at".to_string(),
+                    source: format!("invalid timestamp '{changed_at}'").into(),
+                })?,
+            })
+        }).collect()
+    }
+
+    pub async fn delete_transaksi(mut db: PoolConnection<Any>, id: i32) -> Result<(), sqlx::Error> {
+        Self::remove_transaksi(&mut db, id).await.map(|_| ())
+    }
+
+    /// Seperti `delete_transaksi` di dalam DB transaction pemanggil; `false` bila tidak ada
+    /// baris yang terhapus (mis. sudah dihapus request lain lebih dulu).
+    pub async fn remove_transaksi(conn: &mut AnyConnection, id: i32) -> Result<bool, sqlx::Error> {
+        let result = timed_query("transaksi::delete_transaksi", sqlx::query("DELETE FROM transaksi WHERE id = $1")
+            .bind(id)
+            .execute(&mut *conn))
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
     pub async fn get_all_transaksi(mut db: PoolConnection<Any>) -> Result<Vec<Transaksi>, sqlx::Error> {        
-        let rows = sqlx::query("
+        let rows = timed_query("transaksi::get_all_transaksi", sqlx::query("
                 SELECT id, id_pelanggan, nama_pelanggan, tanggal_transaksi, 
-                       total_harga, status, catatan
+                       total_harga, status, catatan, nomor_transaksi, alasan_pembatalan, created_by, updated_by
                 FROM transaksi
-                ORDER BY tanggal_transaksi DESC
+                ORDER BY tanggal_transaksi DESC, id DESC
             ")
-            .fetch_all(&mut *db)
+            .fetch_all(&mut *db))
             .await?;
                 
         let mut transaksi_list = Vec::new();
@@ -107,15 +388,15 @@ impl TransaksiRepository {
     }
 
     pub async fn get_transaksi_by_pelanggan(mut db: PoolConnection<Any>, id_pelanggan: i32) -> Result<Vec<Transaksi>, sqlx::Error> {
-        let rows = sqlx::query("
+        let rows = timed_query("transaksi::get_transaksi_by_pelanggan", sqlx::query("
                 SELECT id, id_pelanggan, nama_pelanggan, tanggal_transaksi, 
-                       total_harga, status, catatan
+                       total_harga, status, catatan, nomor_transaksi, alasan_pembatalan, created_by, updated_by
                 FROM transaksi
                 WHERE id_pelanggan = $1
-                ORDER BY tanggal_transaksi DESC
+                ORDER BY tanggal_transaksi DESC, id DESC
             ")
             .bind(id_pelanggan)
-            .fetch_all(&mut *db)
+            .fetch_all(&mut *db))
             .await?;
         
         let mut transaksi_list = Vec::new();
@@ -127,16 +408,31 @@ impl TransaksiRepository {
         Ok(transaksi_list)
     }
 
+    /// Total belanja seumur hidup pelanggan: jumlah `total_harga` semua transaksi SELESAI-nya.
+    pub async fn get_total_belanja_pelanggan(mut db: PoolConnection<Any>, id_pelanggan: i32) -> Result<f64, sqlx::Error> {
+        let row = timed_query("transaksi::get_total_belanja_pelanggan", sqlx::query("
+                SELECT CAST(COALESCE(SUM(total_harga), 0) AS DOUBLE PRECISION) AS total
+                FROM transaksi
+                WHERE id_pelanggan = $1 AND status = $2
+            ")
+            .bind(id_pelanggan)
+            .bind(StatusTransaksi::Selesai.to_string())
+            .fetch_one(&mut *db))
+            .await?;
+
+        row.try_get("total")
+    }
+
     pub async fn get_transaksi_by_status(mut db: PoolConnection<Any>, status: &StatusTransaksi) -> Result<Vec<Transaksi>, sqlx::Error> {
-        let rows = sqlx::query("
+        let rows = timed_query("transaksi::get_transaksi_by_status", sqlx::query("
                 SELECT id, id_pelanggan, nama_pelanggan, tanggal_transaksi, 
-                       total_harga, status, catatan
+                       total_harga, status, catatan, nomor_transaksi, alasan_pembatalan, created_by, updated_by
                 FROM transaksi
                 WHERE status = $1
-                ORDER BY tanggal_transaksi DESC
+                ORDER BY tanggal_transaksi DESC, id DESC
             ")
             .bind(status.to_string())
-            .fetch_all(&mut *db)
+            .fetch_all(&mut *db))
             .await?;
         
         let mut transaksi_list = Vec::new();
@@ -148,38 +444,156 @@ impl TransaksiRepository {
         Ok(transaksi_list)
     }
 
+    /// Kolom dan arah urutan yang boleh dipakai sebagai ORDER BY. Nilai `sort`
+    /// dari request tidak pernah disisipkan langsung ke SQL.
+    pub fn sort_clause(sort: Option<&str>) -> Option<&'static str> {
+        match sort.map(|s| s.to_lowercase()).as_deref() {
+            None => Some("tanggal_transaksi DESC, id DESC"),
+            Some("tanggal") | Some("tanggal_transaksi") => Some("tanggal_transaksi ASC, id ASC"),
+            Some("tanggal_desc") => Some("tanggal_transaksi DESC, id DESC"),
+            Some("total") | Some("total_harga") => Some("total_harga ASC, id ASC"),
+            Some("total_desc") => Some("total_harga DESC, id DESC"),
+            Some("pelanggan") | Some("nama_pelanggan") => Some("nama_pelanggan ASC, id ASC"),
+            Some("status") => Some("status ASC, id ASC"),
+            Some(_) => None,
+        }
+    }
+
+    /// Kolom yang dicocokkan dengan `keyword`; nama kolom hanya berasal dari `FilterTransaksi`.
+    pub fn keyword_columns(filter: Option<FilterTransaksi>) -> &'static [&'static str] {
+        filter.map_or(FilterTransaksi::DEFAULT_COLUMNS, |filter| filter.columns())
+    }
+
+    pub async fn search_transaksi(
+        mut db: PoolConnection<Any>,
+        filter: &TransaksiSearchFilter<'_>,
+        order_by: &'static str,
+        page: Option<usize>,
+        limit: Option<usize>,
+    ) -> Result<Vec<Transaksi>, sqlx::Error> {
+        let mut builder: QueryBuilder<Any> = QueryBuilder::new("
+                SELECT id, id_pelanggan, nama_pelanggan, tanggal_transaksi,
+                       total_harga, status, catatan, nomor_transaksi, alasan_pembatalan, created_by, updated_by
+                FROM transaksi
+                WHERE 1 = 1");
+        filter.push_conditions(&mut builder);
+        builder.push(" ORDER BY ");
+        builder.push(order_by);
+        apply_pagination(&mut builder, page, limit);
+
+        let query = filter.bind_to(sqlx::query(builder.sql()));
+        let rows = timed_query("transaksi::search_transaksi", query.fetch_all(&mut *db)).await?;
+
+        let mut transaksi_list = Vec::new();
+        for row in rows {
+            transaksi_list.push(Self::parse_row_to_transaksi(row)?);
+        }
+
+        Ok(transaksi_list)
+    }
+
+    pub async fn count_transaksi(mut db: PoolConnection<Any>, filter: &TransaksiSearchFilter<'_>) -> Result<i64, sqlx::Error> {
+        let mut builder: QueryBuilder<Any> = QueryBuilder::new("SELECT COUNT(*) AS total FROM transaksi WHERE 1 = 1");
+        filter.push_conditions(&mut builder);
+
+        let query = filter.bind_to(sqlx::query(builder.sql()));
+        let row = timed_query("transaksi::count_transaksi", query.fetch_one(&mut *db)).await?;
+
+        row.try_get("total")
+    }
+
+    /// SQL untuk `stream_transaksi`: filter yang sama dengan pencarian, tanpa paginasi,
+    /// urut waktu lalu `id` supaya hasil export stabil.
+    pub fn export_sql(filter: &TransaksiSearchFilter<'_>) -> String {
+        let mut builder: QueryBuilder<Any> = QueryBuilder::new("
+                SELECT id, id_pelanggan, nama_pelanggan, tanggal_transaksi,
+                       total_harga, status, catatan, nomor_transaksi, alasan_pembatalan, created_by, updated_by
+                FROM transaksi
+                WHERE 1 = 1");
+        filter.push_conditions(&mut builder);
+        builder.push(" ORDER BY tanggal_transaksi ASC, id ASC");
+        builder.into_sql()
+    }
+
+    /// Baris dibaca satu per satu dari cursor database, bukan ditampung dulu di memori;
+    /// `sql` harus berasal dari `export_sql` dengan `filter` yang sama.
+    pub fn stream_transaksi<'c>(
+        conn: &'c mut AnyConnection,
+        sql: &'c str,
+        filter: &TransaksiSearchFilter<'_>,
+    ) -> BoxStream<'c, Result<Transaksi, sqlx::Error>> {
+        filter.bind_to(sqlx::query(sql))
+            .fetch(conn)
+            .map(|row| row.and_then(Self::parse_row_to_transaksi))
+            .boxed()
+    }
+
     pub async fn create_detail_transaksi(mut db: PoolConnection<Any>, detail: &DetailTransaksi) -> Result<DetailTransaksi, sqlx::Error> {
+        Self::insert_detail_transaksi(&mut db, detail).await
+    }
+
+    pub async fn insert_detail_transaksi(conn: &mut AnyConnection, detail: &DetailTransaksi) -> Result<DetailTransaksi, sqlx::Error> {
         let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
         
-        let result = sqlx::query("
-                INSERT INTO detail_transaksi (id_transaksi, id_produk, harga_satuan, jumlah, subtotal, created_at, updated_at)
-                VALUES ($1, $2, $3, $4, $5, $6, $7)
-                RETURNING id, id_transaksi, id_produk, harga_satuan, jumlah, subtotal
+        let result = timed_query("transaksi::create_detail_transaksi", sqlx::query("
+                INSERT INTO detail_transaksi (id_transaksi, id_produk, harga_satuan, jumlah, subtotal, created_at, updated_at, diskon)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                RETURNING id, id_transaksi, id_produk, harga_satuan, jumlah, subtotal, diskon
             ")
             .bind(detail.id_transaksi)
             .bind(detail.id_produk)
             .bind(detail.harga_satuan)
-            .bind(detail.jumlah as i32)
+            .bind(detail.jumlah)
             .bind(detail.subtotal)
             .bind(&now)
             .bind(&now)
-            .fetch_one(&mut *db)
+            .bind(detail.diskon)
+            .fetch_one(&mut *conn))
             .await?;
         
         let detail = Self::parse_row_to_detail_transaksi(result)?;
         Ok(detail)
     }
 
+    pub async fn find_idempotency_key(conn: &mut AnyConnection, idempotency_key: &str) -> Result<Option<i32>, sqlx::Error> {
+        let row = timed_query("transaksi::find_idempotency_key", sqlx::query("
+                SELECT id_transaksi FROM transaksi_idempotency WHERE idempotency_key = $1
+            ")
+            .bind(idempotency_key)
+            .fetch_optional(&mut *conn))
+            .await?;
+
+        row.map(|row| row.try_get::<i32, _>("id_transaksi")).transpose()
+    }
+
+    /// Primary key pada `idempotency_key` membuat request kembar yang balapan gagal di sini,
+    /// sehingga hanya satu transaksi yang ter-commit untuk satu key.
+    pub async fn save_idempotency_key(conn: &mut AnyConnection, idempotency_key: &str, id_transaksi: i32) -> Result<(), sqlx::Error> {
+        let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+        timed_query("transaksi::save_idempotency_key", sqlx::query("
+                INSERT INTO transaksi_idempotency (idempotency_key, id_transaksi, created_at)
+                VALUES ($1, $2, $3)
+            ")
+            .bind(idempotency_key)
+            .bind(id_transaksi)
+            .bind(&now)
+            .execute(&mut *conn))
+            .await?;
+
+        Ok(())
+    }
+
     pub async fn get_detail_by_transaksi_id(mut db: PoolConnection<Any>, id_transaksi: i32) -> Result<Vec<DetailTransaksi>, sqlx::Error> {
-        let rows = sqlx::query("
+        let rows = timed_query("transaksi::get_detail_by_transaksi_id", sqlx::query("
                 SELECT id, id_transaksi, id_produk, 
-                       harga_satuan, jumlah, subtotal
+                       harga_satuan, jumlah, subtotal, diskon
                 FROM detail_transaksi
                 WHERE id_transaksi = $1
                 ORDER BY id
             ")
             .bind(id_transaksi)
-            .fetch_all(&mut *db)
+            .fetch_all(&mut *db))
             .await?;
         
         let mut detail_list = Vec::new();
@@ -191,22 +605,152 @@ impl TransaksiRepository {
         Ok(detail_list)
     }
 
+    /// Memuat detail untuk banyak transaksi sekaligus dengan satu query `IN (...)`.
+    pub async fn get_details_by_transaksi_ids(mut db: PoolConnection<Any>, ids: &[i32]) -> Result<Vec<DetailTransaksi>, sqlx::Error> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let placeholders: Vec<String> = (1..=ids.len()).map(|i| format!("${}", i)).collect();
+        let sql = format!("
+                SELECT id, id_transaksi, id_produk,
+                       harga_satuan, jumlah, subtotal, diskon
+                FROM detail_transaksi
+                WHERE id_transaksi IN ({})
+                ORDER BY id_transaksi, id
+            ", placeholders.join(", "));
+
+        let mut query = sqlx::query(&sql);
+        for id in ids {
+            query = query.bind(*id);
+        }
+        let rows = timed_query("transaksi::get_details_by_transaksi_ids", query.fetch_all(&mut *db)).await?;
+
+        let mut detail_list = Vec::new();
+        for row in rows {
+            detail_list.push(Self::parse_row_to_detail_transaksi(row)?);
+        }
+
+        Ok(detail_list)
+    }
+
+    /// Produk terlaris dari transaksi SELESAI dalam rentang `tanggal_transaksi` (batas inklusif).
+    pub async fn get_top_products(
+        mut db: PoolConnection<Any>,
+        date_from: Option<&str>,
+        date_to: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<TopProductReport>, sqlx::Error> {
+        let mut sql = String::from("
+                SELECT d.id_produk,
+                       CAST(SUM(d.jumlah) AS DOUBLE PRECISION) AS total_jumlah,
+                       CAST(SUM(d.subtotal) AS DOUBLE PRECISION) AS total_pendapatan
+                FROM detail_transaksi d
+                JOIN transaksi t ON t.id = d.id_transaksi
+                WHERE t.status = $1");
+        let mut param = 1;
+        if date_from.is_some() {
+            param += 1;
+            sql.push_str(&format!(" AND t.tanggal_transaksi >= ${}", param));
+        }
+        if date_to.is_some() {
+            param += 1;
+            sql.push_str(&format!(" AND t.tanggal_transaksi <= ${}", param));
+        }
+        sql.push_str(&format!("
+                GROUP BY d.id_produk
+                ORDER BY total_jumlah DESC, total_pendapatan DESC, d.id_produk ASC
+                LIMIT ${}", param + 1));
+
+        let mut query = sqlx::query(&sql).bind(StatusTransaksi::Selesai.to_string());
+        if let Some(date_from) = date_from {
+            query = query.bind(date_from.to_string());
+        }
+        if let Some(date_to) = date_to {
+            query = query.bind(date_to.to_string());
+        }
+        let rows = timed_query("transaksi::get_top_products", query.bind(limit).fetch_all(&mut *db)).await?;
+
+        rows.into_iter()
+            .map(|row| Ok(TopProductReport {
+                id_produk: row.try_get("id_produk")?,
+                total_jumlah: row.try_get("total_jumlah")?,
+                total_pendapatan: row.try_get("total_pendapatan")?,
+            }))
+            .collect()
+    }
+
+    /// Penjualan transaksi SELESAI per kategori produk dalam rentang `tanggal_transaksi` (batas
+    /// inklusif). Detail yang produknya sudah dihapus dikelompokkan ke `kategori_tidak_dikenal`.
+    pub async fn get_sales_by_category(
+        mut db: PoolConnection<Any>,
+        date_from: Option<&str>,
+        date_to: Option<&str>,
+        kategori_tidak_dikenal: &str,
+    ) -> Result<Vec<SalesByCategoryReport>, sqlx::Error> {
+        let mut sql = String::from("
+                SELECT COALESCE(p.kategori, $2) AS kategori,
+                       CAST(SUM(d.jumlah) AS DOUBLE PRECISION) AS total_qty,
+                       CAST(SUM(d.subtotal) AS DOUBLE PRECISION) AS total_revenue
+                FROM detail_transaksi d
+                JOIN transaksi t ON t.id = d.id_transaksi
+                LEFT JOIN produk p ON p.id = d.id_produk
+                WHERE t.status = $1");
+        let mut param = 2;
+        if date_from.is_some() {
+            param += 1;
+            sql.push_str(&format!(" AND t.tanggal_transaksi >= ${}", param));
+        }
+        if date_to.is_some() {
+            param += 1;
+            sql.push_str(&format!(" AND t.tanggal_transaksi <= ${}", param));
+        }
+        sql.push_str("
+                GROUP BY COALESCE(p.kategori, $2)
+                ORDER BY total_revenue DESC, kategori ASC");
+
+        let mut query = sqlx::query(&sql)
+            .bind(StatusTransaksi::Selesai.to_string())
+            .bind(kategori_tidak_dikenal.to_string());
+        if let Some(date_from) = date_from {
+            query = query.bind(date_from.to_string());
+        }
+        if let Some(date_to) = date_to {
+            query = query.bind(date_to.to_string());
+        }
+        let rows = timed_query("transaksi::get_sales_by_category", query.fetch_all(&mut *db)).await?;
+
+        rows.into_iter()
+            .map(|row| Ok(SalesByCategoryReport {
+                kategori: row.try_get("kategori")?,
+                total_qty: row.try_get("total_qty")?,
+                total_revenue: row.try_get("total_revenue")?,
+            }))
+            .collect()
+    }
+
     pub async fn update_detail_transaksi(mut db: PoolConnection<Any>, detail: &DetailTransaksi) -> Result<DetailTransaksi, sqlx::Error> {
+        Self::write_detail_update(&mut db, detail).await
+    }
+
+    /// Seperti `update_detail_transaksi` di dalam DB transaction pemanggil.
+    pub async fn write_detail_update(conn: &mut AnyConnection, detail: &DetailTransaksi) -> Result<DetailTransaksi, sqlx::Error> {
         let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
         
-        let result = sqlx::query("
+        let result = timed_query("transaksi::update_detail_transaksi", sqlx::query("
                 UPDATE detail_transaksi
-                SET id_produk = $1, harga_satuan = $2, jumlah = $3, subtotal = $4, updated_at = $5
-                WHERE id = $6
-                RETURNING id, id_transaksi, id_produk, harga_satuan, jumlah, subtotal
+                SET id_produk = $1, harga_satuan = $2, jumlah = $3, subtotal = $4, updated_at = $5, diskon = $6
+                WHERE id = $7
+                RETURNING id, id_transaksi, id_produk, harga_satuan, jumlah, subtotal, diskon
             ")
             .bind(detail.id_produk)
             .bind(detail.harga_satuan)
-            .bind(detail.jumlah as i32)
+            .bind(detail.jumlah)
             .bind(detail.subtotal)
             .bind(&now)
+            .bind(detail.diskon)
             .bind(detail.id)
-            .fetch_one(&mut *db)
+            .fetch_one(&mut *conn))
             .await?;
         
         let detail = Self::parse_row_to_detail_transaksi(result)?;
@@ -214,20 +758,31 @@ impl TransaksiRepository {
     }
 
     pub async fn delete_detail_transaksi(mut db: PoolConnection<Any>, id: i32) -> Result<(), sqlx::Error> {
-        sqlx::query("DELETE FROM detail_transaksi WHERE id = $1")
+        Self::remove_detail_transaksi(&mut db, id).await.map(|_| ())
+    }
+
+    /// Seperti `delete_detail_transaksi` di dalam DB transaction pemanggil; `false` bila
+    /// detailnya sudah tidak ada.
+    pub async fn remove_detail_transaksi(conn: &mut AnyConnection, id: i32) -> Result<bool, sqlx::Error> {
+        let result = timed_query("transaksi::delete_detail_transaksi", sqlx::query("DELETE FROM detail_transaksi WHERE id = $1")
             .bind(id)
-            .execute(&mut *db)
+            .execute(&mut *conn))
             .await?;
-        
-        Ok(())
+
+        Ok(result.rows_affected() > 0)
     }
 
     pub async fn delete_detail_by_transaksi_id(mut db: PoolConnection<Any>, id_transaksi: i32) -> Result<(), sqlx::Error> {
-        sqlx::query("DELETE FROM detail_transaksi WHERE id_transaksi = $1")
+        Self::remove_details_of_transaksi(&mut db, id_transaksi).await
+    }
+
+    /// Seperti `delete_detail_by_transaksi_id` di dalam DB transaction pemanggil.
+    pub async fn remove_details_of_transaksi(conn: &mut AnyConnection, id_transaksi: i32) -> Result<(), sqlx::Error> {
+        timed_query("transaksi::delete_detail_by_transaksi_id", sqlx::query("DELETE FROM detail_transaksi WHERE id_transaksi = $1")
             .bind(id_transaksi)
-            .execute(&mut *db)
+            .execute(&mut *conn))
             .await?;
-        
+
         Ok(())
     }
     
@@ -254,6 +809,12 @@ impl TransaksiRepository {
         transaksi.id = id;
         transaksi.tanggal_transaksi = tanggal_transaksi;
         transaksi.status = status;
+        transaksi.nomor_transaksi = row.try_get::<String, _>("nomor_transaksi").ok();
+        transaksi.alasan_pembatalan = row.try_get::<String, _>("alasan_pembatalan").ok();
+        transaksi.audit = AuditTrail {
+            created_by: row.try_get("created_by").ok(),
+            updated_by: row.try_get("updated_by").ok(),
+        };
 
         Ok(transaksi)
     }
@@ -266,7 +827,11 @@ impl TransaksiRepository {
         let harga_satuan: f64 = row.try_get("harga_satuan")?;
         let subtotal: f64 = row.try_get("subtotal")?;
         
-        let jumlah: u32 = row.try_get::<i32, _>("jumlah")? as u32;
+        // Kolom bertipe DOUBLE PRECISION; SQLite menyimpan nilai bulat sebagai INTEGER
+        let jumlah: f64 = match row.try_get::<f64, _>("jumlah") {
+            Ok(jumlah) => jumlah,
+            Err(_) => row.try_get::<i32, _>("jumlah")? as f64,
+        };
 
         Ok(DetailTransaksi {
             id,
@@ -274,6 +839,7 @@ impl TransaksiRepository {
             id_produk,
             harga_satuan,
             jumlah,
+            diskon: row.try_get::<f64, _>("diskon").ok(),
             subtotal,
         })
     }
@@ -321,6 +887,31 @@ mod test {
         assert_eq!(created_transaksi.status, StatusTransaksi::MasihDiproses);
     }
 
+    #[async_test]
+    async fn test_create_transaksi_assigns_unique_sequential_nomor_concurrently() {
+        let db = setup().await;
+
+        let creations = (0..10).map(|i| {
+            let db = db.clone();
+            async move {
+                let transaksi = Transaksi::new(i + 1, format!("Pelanggan {}", i), 10000.0, None);
+                TransaksiRepository::create_transaksi(db.acquire().await.unwrap(), &transaksi).await.unwrap()
+            }
+        });
+        let created = futures::future::join_all(creations).await;
+
+        let tanggal = Utc::now().format("%Y%m%d").to_string();
+        let mut nomor: Vec<String> = created.into_iter().map(|t| t.nomor_transaksi.unwrap()).collect();
+        nomor.sort();
+        let expected: Vec<String> = (1..=10)
+            .map(|seq| TransaksiRepository::format_nomor_transaksi(&tanggal, seq))
+            .collect();
+        assert_eq!(nomor, expected);
+
+        let fetched = TransaksiRepository::get_all_transaksi(db.acquire().await.unwrap()).await.unwrap();
+        assert!(fetched.iter().all(|t| t.nomor_transaksi.as_deref().is_some_and(|n| n.starts_with(&format!("INV-{}-", tanggal)))));
+    }
+
     #[async_test]
     async fn test_get_transaksi_by_id() {
         let db = setup().await;
@@ -356,7 +947,7 @@ mod test {
             created_transaksi.id,
             101,
             15000000.0,
-            1,
+            1.0,
         );
         let created_detail = TransaksiRepository::create_detail_transaksi(db.acquire().await.unwrap(), &detail).await.unwrap();
 
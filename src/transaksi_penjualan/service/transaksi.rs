@@ -1,11 +1,225 @@
+use std::collections::HashMap;
+use rocket::futures::{Stream, StreamExt};
+use rocket::response::stream::stream;
+use sqlx::pool::PoolConnection;
 use sqlx::{Any, Pool};
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use uuid::Uuid;
 use crate::transaksi_penjualan::model::transaksi::Transaksi;
 use crate::transaksi_penjualan::model::detail_transaksi::DetailTransaksi;
-use crate::transaksi_penjualan::repository::transaksi::TransaksiRepository;
+use crate::transaksi_penjualan::model::transaksi_status_history::TransaksiStatusHistory;
+use crate::transaksi_penjualan::model::retur_transaksi::{ReturItem, ReturTransaksi};
+use crate::transaksi_penjualan::repository::transaksi::{TransaksiRepository, TransaksiSearchFilter};
+use crate::common::pagination::limit_offset;
+use crate::common::audit::AuditTrail;
+use crate::common::money::{discounted_line_subtotal_sen, from_sen, round_report_amount, sum_amounts, to_sen};
+use crate::manajemen_pembayaran::enums::payment_status::PaymentStatus;
+use crate::manajemen_pembayaran::model::payment::{Payment, PaymentMethod, Refund, RefundLedger};
+use crate::manajemen_pembayaran::repository::payment_repository::PembayaranRepository;
+use crate::manajemen_produk::repository::dto::RepositoryError;
+use crate::manajemen_produk::model::StokChange;
+use crate::manajemen_produk::model::produk::{jumlah_ke_unit_stok, unit_stok_ke_jumlah};
+use crate::manajemen_produk::repository::read::{ambil_berat_produk, ambil_harga_grosir_produk, ambil_presisi_stok, ambil_produk_by_id};
+use crate::manajemen_produk::repository::update::{decrement_stok, increment_stok, lock_produk_for_update};
+use crate::manajemen_produk::service::produk_notifier::{notify_stok_change, ProdukNotifier};
 use crate::transaksi_penjualan::enums::status_transaksi::StatusTransaksi;
+use crate::transaksi_penjualan::enums::filter_transaksi::FilterTransaksi;
+use crate::transaksi_penjualan::dto::transaksi_request::{CheckoutRequest, CheckoutResponse, CreateTransaksiRequest, ReturTransaksiRequest, ReturTransaksiResponse, SalesByCategoryReport, TopProductReport, VoidTransaksiResponse};
 
 pub struct TransaksiService;
 
+/// Baris stok yang gagal di-commit; `line` adalah indeks baris pada input `commit_stock`.
+#[derive(Debug)]
+pub enum CommitStockError {
+    InsufficientStock { line: usize, produk_id: i64 },
+    ProdukNotFound { line: usize, produk_id: i64 },
+    Database(String),
+}
+
+impl std::fmt::Display for CommitStockError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommitStockError::InsufficientStock { line, produk_id } => {
+                write!(f, "Insufficient stock for produk {} (line {})", produk_id, line + 1)
+            }
+            CommitStockError::ProdukNotFound { line, produk_id } => {
+                write!(f, "Produk {} not found (line {})", produk_id, line + 1)
+            }
+            CommitStockError::Database(message) => write!(f, "Database error: {}", message),
+        }
+    }
+}
+
+/// Kegagalan `checkout`; apa pun variannya, tidak ada data yang tersimpan.
+#[derive(Debug)]
+pub enum CheckoutError {
+    InvalidRequest(String),
+    Stock(CommitStockError),
+    Database(String),
+}
+
+impl std::fmt::Display for CheckoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CheckoutError::InvalidRequest(message) => write!(f, "Validation error: {}", message),
+            CheckoutError::Stock(error) => write!(f, "{}", error),
+            CheckoutError::Database(message) => write!(f, "Database error: {}", message),
+        }
+    }
+}
+
+impl From<sqlx::Error> for CheckoutError {
+    fn from(error: sqlx::Error) -> Self {
+        CheckoutError::Database(error.to_string())
+    }
+}
+
+/// Kegagalan `void_transaksi_by`; apa pun variannya, tidak ada data yang berubah.
+#[derive(Debug)]
+pub enum VoidError {
+    NotFound(i32),
+    NotVoidable(StatusTransaksi),
+    ProdukNotFound(i64),
+    /// Saldo refund payment berubah di tengah proses (refund lain masuk lebih dulu).
+    RefundConflict(String),
+    Database(String),
+}
+
+impl std::fmt::Display for VoidError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VoidError::NotFound(id) => write!(f, "Transaksi {} not found", id),
+            VoidError::NotVoidable(status) => {
+                write!(f, "Only completed transaksi can be voided (current status: {})", status.to_string())
+            }
+            VoidError::ProdukNotFound(produk_id) => write!(f, "Produk {} not found; stock cannot be restored", produk_id),
+            VoidError::RefundConflict(payment_id) => {
+                write!(f, "Refund balance of payment {} changed during void; please retry", payment_id)
+            }
+            VoidError::Database(message) => write!(f, "Database error: {}", message),
+        }
+    }
+}
+
+impl From<sqlx::Error> for VoidError {
+    fn from(error: sqlx::Error) -> Self {
+        VoidError::Database(error.to_string())
+    }
+}
+
+/// Kegagalan `retur_transaksi_by`; seperti void, tidak ada data yang berubah bila gagal.
+#[derive(Debug)]
+pub enum ReturError {
+    NotFound(i32),
+    NotReturnable(StatusTransaksi),
+    InvalidRequest(String),
+    ProdukNotFound(i64),
+    RefundConflict(String),
+    Database(String),
+}
+
+impl std::fmt::Display for ReturError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReturError::NotFound(id) => write!(f, "Transaksi {} not found", id),
+            ReturError::NotReturnable(status) => {
+                write!(f, "Only completed transaksi can have items returned (current status: {})", status.to_string())
+            }
+            ReturError::InvalidRequest(message) => write!(f, "Validation error: {}", message),
+            ReturError::ProdukNotFound(produk_id) => write!(f, "Produk {} not found; stock cannot be restored", produk_id),
+            ReturError::RefundConflict(payment_id) => {
+                write!(f, "Refund balance of payment {} changed during return; please retry", payment_id)
+            }
+            ReturError::Database(message) => write!(f, "Database error: {}", message),
+        }
+    }
+}
+
+impl From<sqlx::Error> for ReturError {
+    fn from(error: sqlx::Error) -> Self {
+        ReturError::Database(error.to_string())
+    }
+}
+
+/// Nilai (dalam sen) dari `jumlah` barang yang diretur dari satu baris detail, sebanding
+/// dengan subtotal baris setelah diskon. Dihitung sebagai selisih nilai kumulatif
+/// `sudah_diretur` -> `sudah_diretur + jumlah`, sehingga beberapa retur parsial dari baris
+/// yang sama selalu berjumlah tepat subtotal baris tanpa selisih pembulatan.
+pub fn nilai_retur_sen(detail: &DetailTransaksi, sudah_diretur: f64, jumlah: f64) -> i64 {
+    if detail.jumlah <= 0.0 {
+        return 0;
+    }
+    let subtotal_sen = discounted_line_subtotal_sen(detail.harga_satuan, detail.jumlah, detail.diskon) as f64;
+    let kumulatif = |n: f64| -> i64 {
+        let n = n.min(detail.jumlah);
+        (subtotal_sen * n / detail.jumlah).round() as i64
+    };
+    kumulatif(sudah_diretur + jumlah) - kumulatif(sudah_diretur)
+}
+
+/// Kegagalan stok pada jalur penjualan biasa: stok kurang atau produk tidak ada menjadi
+/// `RowNotFound` (ditolak sebagai request tidak valid), selain itu error database.
+fn stock_error(error: CommitStockError) -> sqlx::Error {
+    match error {
+        CommitStockError::InsufficientStock { .. } | CommitStockError::ProdukNotFound { .. } => sqlx::Error::RowNotFound,
+        CommitStockError::Database(message) => sqlx::Error::Protocol(message),
+    }
+}
+
+/// Pengecekan `harga_satuan` detail terhadap harga produk saat ini, diatur lewat env
+/// `TRANSAKSI_PRICE_CHECK` (`warn` default, `reject` untuk menolak, `off` untuk mematikan).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PriceCheckPolicy {
+    Off,
+    Warn,
+    Reject,
+}
+
+impl PriceCheckPolicy {
+    pub fn from_env() -> Self {
+        match std::env::var("TRANSAKSI_PRICE_CHECK") {
+            Ok(value) if value.eq_ignore_ascii_case("reject") => PriceCheckPolicy::Reject,
+            Ok(value) if value.eq_ignore_ascii_case("off") => PriceCheckPolicy::Off,
+            _ => PriceCheckPolicy::Warn,
+        }
+    }
+}
+
+/// `harga_satuan` yang menyimpang dari harga produk lebih dari toleransi.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceMismatch {
+    pub id_produk: i32,
+    pub harga_satuan: f64,
+    pub harga_produk: f64,
+}
+
+impl PriceMismatch {
+    /// `None` bila selisihnya tidak melebihi `tolerance_percent` persen dari harga produk.
+    pub fn detect(id_produk: i32, harga_satuan: f64, harga_produk: f64, tolerance_percent: f64) -> Option<Self> {
+        let selisih_sen = (to_sen(harga_satuan) - to_sen(harga_produk)).abs();
+        let toleransi_sen = to_sen(harga_produk.abs() * tolerance_percent / 100.0);
+        (selisih_sen > toleransi_sen).then_some(PriceMismatch { id_produk, harga_satuan, harga_produk })
+    }
+}
+
+impl std::fmt::Display for PriceMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "harga_satuan {} for produk {} differs from the current price {}",
+            self.harga_satuan, self.id_produk, self.harga_produk
+        )
+    }
+}
+
+pub const DEFAULT_MAX_LINE_ITEMS: usize = 200;
+pub const MAX_ALASAN_PEMBATALAN_LEN: usize = 500;
+pub const DEFAULT_TOP_PRODUCTS_LIMIT: usize = 10;
+pub const MAX_TOP_PRODUCTS_LIMIT: usize = 100;
+/// Kategori untuk penjualan produk yang sudah dihapus pada laporan per kategori.
+pub const KATEGORI_TIDAK_DIKENAL: &str = "Tidak Diketahui";
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 128;
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(crate = "rocket::serde")]
 pub struct TransaksiSearchParams {
@@ -14,6 +228,8 @@ pub struct TransaksiSearchParams {
     pub keyword: Option<String>,
     pub status: Option<String>,
     pub id_pelanggan: Option<i32>,
+    pub date_from: Option<String>,
+    pub date_to: Option<String>,
     pub page: Option<usize>,
     pub limit: Option<usize>,
 }
@@ -48,52 +264,382 @@ impl TransaksiService {
 
     pub async fn create_transaksi_with_details(
         db: Pool<Any>, 
-        request: &crate::transaksi_penjualan::dto::transaksi_request::CreateTransaksiRequest
+        request: &CreateTransaksiRequest
     ) -> Result<Transaksi, sqlx::Error> {
-        if let Err(_err_msg) = request.validate() {
-            return Err(sqlx::Error::RowNotFound);
-        }
+        Self::create_transaksi_idempotent(db, None, None, request)
+            .await
+            .map(|(transaksi, _replayed)| transaksi)
+    }
 
-        if let Err(_err_msg) = Self::validate_product_stock(&request.detail_transaksi).await {
-            return Err(sqlx::Error::RowNotFound);
+    /// Membuat transaksi beserta detailnya dalam satu DB transaction. Bila `idempotency_key`
+    /// sudah pernah dipakai, transaksi aslinya dikembalikan dengan flag `true` dan tidak ada
+    /// transaksi baru yang dibuat, sehingga retry dari client tidak menggandakan penjualan.
+    /// `actor` dicatat sebagai `created_by`/`updated_by`.
+    pub async fn create_transaksi_idempotent(
+        db: Pool<Any>,
+        idempotency_key: Option<&str>,
+        actor: Option<&str>,
+        request: &CreateTransaksiRequest
+    ) -> Result<(Transaksi, bool), sqlx::Error> {
+        let idempotency_key = match idempotency_key.map(str::trim) {
+            Some(key) if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LEN => {
+                return Err(sqlx::Error::RowNotFound);
+            }
+            key => key,
+        };
+
+        let mut tx = db.begin().await?;
+        if let Some(key) = idempotency_key {
+            if let Some(id_transaksi) = TransaksiRepository::find_idempotency_key(&mut tx, key).await? {
+                tx.commit().await?;
+                return Ok((Self::get_transaksi_by_id(db, id_transaksi).await?, true));
+            }
         }
 
-        let product_prices = Self::fetch_product_prices(&request.detail_transaksi).await?;
+        let (request, product_prices) = match Self::validate_create_request(&mut tx, request).await {
+            Ok(validated) => validated,
+            Err(_err_msg) => return Err(sqlx::Error::RowNotFound),
+        };
+        let request = &request;
+
         let total_harga = request.calculate_total(&product_prices);
 
-        let transaksi = Transaksi::new(
+        let mut transaksi = Transaksi::new(
             request.id_pelanggan,
             request.nama_pelanggan.clone(),
             total_harga,
             request.catatan.clone(),
         );
+        transaksi.audit = AuditTrail::created_by(actor);
 
-        let db_connection = db.acquire().await?;
-        let created_transaksi = TransaksiRepository::create_transaksi(db_connection, &transaksi).await?;
+        let created_transaksi = TransaksiRepository::insert_transaksi(&mut tx, &transaksi).await?;
 
+        let mut lines = Vec::with_capacity(request.detail_transaksi.len());
         for detail_request in &request.detail_transaksi {
             let harga_satuan = product_prices.get(&detail_request.id_produk).unwrap_or(&detail_request.harga_satuan);
             let detail = detail_request.to_detail_transaksi(created_transaksi.id, *harga_satuan);
             
-            let db_connection = db.acquire().await?;
-            TransaksiRepository::create_detail_transaksi(db_connection, &detail).await?;
-            
-            Self::reduce_product_stock(detail_request.id_produk, detail_request.jumlah).await?;
+            TransaksiRepository::insert_detail_transaksi(&mut tx, &detail).await?;
+            lines.push((detail.id_produk, detail.jumlah));
+        }
+        // Stok dipotong di DB transaction yang sama; stok kurang membatalkan seluruh penjualan
+        let unit_lines = Self::unit_stok_lines(&mut tx, &lines).await?;
+        Self::decrement_lines(&mut tx, &unit_lines).await.map_err(stock_error)?;
+
+        if let Some(key) = idempotency_key {
+            if let Err(e) = TransaksiRepository::save_idempotency_key(&mut tx, key, created_transaksi.id).await {
+                tx.rollback().await?;
+                // Request kembar yang berjalan bersamaan sudah lebih dulu menyimpan key ini
+                let mut conn = db.acquire().await?;
+                let existing = TransaksiRepository::find_idempotency_key(&mut conn, key).await?;
+                drop(conn);
+                return match existing {
+                    Some(id_transaksi) => Ok((Self::get_transaksi_by_id(db, id_transaksi).await?, true)),
+                    None => Err(e),
+                };
+            }
+        }
+
+        tx.commit().await?;
+        Ok((created_transaksi, false))
+    }
+
+    /// Validasi bersama pembuatan transaksi; mengembalikan request dengan baris produk kembar
+    /// yang sudah digabung beserta harga produk yang dipakai server (lihat `line_unit_price`).
+    /// Server yang menentukan total, sehingga request.total_harga dari client diabaikan.
+    async fn validate_create_request(
+        conn: &mut sqlx::AnyConnection,
+        request: &CreateTransaksiRequest,
+    ) -> Result<(CreateTransaksiRequest, HashMap<i32, f64>), String> {
+        // Transaksi tanpa detail tidak punya total yang bisa dihitung
+        request.validate()?;
+        Self::validate_line_item_count(request.detail_transaksi.len())?;
+        let mut request = request.merge_duplicate_lines()?;
+        request.nama_pelanggan = Self::normalize_nama_pelanggan(&request.nama_pelanggan)?;
+
+        let produk_ids: Vec<i64> = request.detail_transaksi.iter().map(|detail| detail.id_produk as i64).collect();
+        let harga_produk = ambil_harga_grosir_produk(conn, &produk_ids)
+            .await
+            .map_err(|e| e.to_string())?;
+        // Jumlah desimal hanya boleh untuk produk timbangan, sebanyak `presisi_stok` digit
+        let presisi_stok = ambil_presisi_stok(conn, &produk_ids)
+            .await
+            .map_err(|e| e.to_string())?;
+        for (index, detail) in request.detail_transaksi.iter().enumerate() {
+            let presisi = presisi_stok.get(&(detail.id_produk as i64)).copied().unwrap_or(0);
+            jumlah_ke_unit_stok(detail.jumlah, presisi).map_err(|e| format!("Detail {}: {}", index + 1, e))?;
+        }
+        // Baris sudah digabung per produk, jadi satu harga per id_produk cukup. Produk yang tidak
+        // ditemukan memakai harga dari request; stoknya nanti tetap ditolak saat dipotong.
+        let mut product_prices = HashMap::with_capacity(request.detail_transaksi.len());
+        for detail in &request.detail_transaksi {
+            let (katalog, grosir) = harga_produk
+                .get(&(detail.id_produk as i64))
+                .copied()
+                .unwrap_or((detail.harga_satuan, None));
+            product_prices.insert(detail.id_produk, Self::line_unit_price(katalog, grosir, detail.jumlah, detail.harga_override));
+        }
+        request.validate_line_discounts(&product_prices)?;
+        Ok((request, product_prices))
+    }
+
+    /// Checkout sekali jalan untuk POS: transaksi, detail, pengurangan stok, pembayaran LUNAS
+    /// dan penyelesaian transaksi ditulis dalam satu DB transaction. Gagal di langkah mana pun
+    /// membatalkan semuanya. Event stok habis dikirim ke `notifier` setelah commit.
+    pub async fn checkout(
+        db: Pool<Any>,
+        request: &CheckoutRequest,
+        method: PaymentMethod,
+        actor: Option<&str>,
+        notifier: &dyn ProdukNotifier,
+    ) -> Result<CheckoutResponse, CheckoutError> {
+        let validated = {
+            let mut conn = db.acquire().await?;
+            Self::validate_create_request(&mut conn, &request.transaksi).await
+        };
+        let (transaksi_request, product_prices) = validated.map_err(CheckoutError::InvalidRequest)?;
+        let total_harga = transaksi_request.calculate_total(&product_prices);
+
+        let received_sen = to_sen(request.amount.unwrap_or(total_harga));
+        if received_sen < to_sen(total_harga) {
+            return Err(CheckoutError::InvalidRequest(format!(
+                "Payment amount {} does not cover total {}",
+                from_sen(received_sen),
+                total_harga
+            )));
+        }
+
+        let mut transaksi = Transaksi::new(
+            transaksi_request.id_pelanggan,
+            transaksi_request.nama_pelanggan.clone(),
+            total_harga,
+            transaksi_request.catatan.clone(),
+        );
+        transaksi.audit = AuditTrail::created_by(actor);
+
+        // `tx` yang di-drop tanpa commit otomatis di-rollback
+        let mut tx = db.begin().await?;
+        let created_transaksi = TransaksiRepository::insert_transaksi(&mut tx, &transaksi).await?;
+
+        let produk_ids: Vec<i64> = transaksi_request.detail_transaksi.iter().map(|detail| detail.id_produk as i64).collect();
+        lock_produk_for_update(&mut tx, &produk_ids)
+            .await
+            .map_err(|error| CheckoutError::Stock(CommitStockError::Database(error.to_string())))?;
+        let berat_produk = ambil_berat_produk(&mut tx, &produk_ids)
+            .await
+            .map_err(|error| CheckoutError::Stock(CommitStockError::Database(error.to_string())))?;
+        let presisi_stok = ambil_presisi_stok(&mut tx, &produk_ids)
+            .await
+            .map_err(|error| CheckoutError::Stock(CommitStockError::Database(error.to_string())))?;
+
+        let mut details = Vec::with_capacity(transaksi_request.detail_transaksi.len());
+        let mut stok_changes = Vec::with_capacity(transaksi_request.detail_transaksi.len());
+        for (line, detail_request) in transaksi_request.detail_transaksi.iter().enumerate() {
+            let harga_satuan = product_prices.get(&detail_request.id_produk).unwrap_or(&detail_request.harga_satuan);
+            let detail = detail_request.to_detail_transaksi(created_transaksi.id, *harga_satuan);
+            details.push(TransaksiRepository::insert_detail_transaksi(&mut tx, &detail).await?);
+
+            let produk_id = detail_request.id_produk as i64;
+            let presisi = presisi_stok.get(&produk_id).copied().unwrap_or(0);
+            let unit = jumlah_ke_unit_stok(detail_request.jumlah, presisi).map_err(CheckoutError::InvalidRequest)?;
+            match decrement_stok(&mut tx, produk_id, unit).await {
+                Ok(change) => stok_changes.push(change),
+                Err(error) => return Err(CheckoutError::Stock(match error {
+                    RepositoryError::NotFound => CommitStockError::ProdukNotFound { line, produk_id },
+                    RepositoryError::ValidationError(_) => CommitStockError::InsufficientStock { line, produk_id },
+                    other => CommitStockError::Database(other.to_string()),
+                })),
+            }
+        }
+
+        let payment = Payment {
+            id: format!("PMT-{}", Uuid::new_v4()),
+            transaction_id: created_transaksi.id.to_string(),
+            amount: total_harga,
+            method,
+            status: PaymentStatus::Paid,
+            payment_date: Utc::now(),
+            installments: Vec::new(),
+            due_date: None,
+            audit: AuditTrail::created_by(actor),
+        };
+        PembayaranRepository::insert_payment(&mut tx, &payment).await?;
+
+        let mut completed = created_transaksi.clone();
+        completed.update_status(StatusTransaksi::Selesai);
+        let completed = TransaksiRepository::write_transaksi_update(&mut tx, &completed).await?;
+        TransaksiRepository::insert_status_history(&mut tx, completed.id, &created_transaksi.status, &completed.status, None, actor).await?;
+
+        tx.commit().await?;
+        for change in &stok_changes {
+            notify_stok_change(notifier, change).await;
         }
+        Ok(CheckoutResponse {
+            transaksi: completed,
+            total_berat_gram: Self::total_berat_gram(&details, &berat_produk),
+            detail_transaksi: details,
+            payment,
+            kembalian: from_sen(received_sen - to_sen(total_harga)),
+        })
+    }
+
+    /// Total berat (gram) semua baris: jumlah x berat per unit produk. `None` bila ada
+    /// produk yang beratnya belum diisi, supaya ongkos kirim tidak dihitung terlalu ringan.
+    pub fn total_berat_gram(details: &[DetailTransaksi], berat_produk: &HashMap<i64, Option<i32>>) -> Option<i64> {
+        details
+            .iter()
+            .map(|detail| {
+                let berat = berat_produk.get(&(detail.id_produk as i64)).copied().flatten()?;
+                Some((berat as f64 * detail.jumlah).round() as i64)
+            })
+            .sum()
+    }
+
+    /// `total_berat_gram` untuk detail transaksi yang sudah tersimpan, mis. untuk struk.
+    pub async fn total_berat_transaksi(db: Pool<Any>, details: &[DetailTransaksi]) -> Result<Option<i64>, sqlx::Error> {
+        let produk_ids: Vec<i64> = details.iter().map(|detail| detail.id_produk as i64).collect();
+        let mut conn = db.acquire().await?;
+        let berat_produk = match ambil_berat_produk(&mut conn, &produk_ids).await {
+            Ok(berat_produk) => berat_produk,
+            Err(RepositoryError::DatabaseError(e)) => return Err(e),
+            Err(e) => return Err(sqlx::Error::Protocol(e.to_string())),
+        };
+        Ok(Self::total_berat_gram(details, &berat_produk))
+    }
 
-        Ok(created_transaksi)
+    /// Toleransi selisih harga dalam persen untuk `PriceCheckPolicy`, lewat env
+    /// `TRANSAKSI_PRICE_TOLERANCE_PERCENT` (default 0: harus sama sampai ke sen).
+    pub fn price_tolerance_percent() -> f64 {
+        std::env::var("TRANSAKSI_PRICE_TOLERANCE_PERCENT")
+            .ok()
+            .and_then(|value| value.parse::<f64>().ok())
+            .filter(|value| value.is_finite() && *value >= 0.0)
+            .unwrap_or(0.0)
     }
 
-    async fn reduce_product_stock(product_id: i32, quantity: u32) -> Result<(), sqlx::Error> {
-        println!("Mengurangi stok produk ID {} sebanyak {}", product_id, quantity);
+    /// Membandingkan `harga_satuan` detail dengan harga produk saat ini. Produk yang tidak
+    /// ditemukan dilewati karena tidak ada harga pembanding.
+    pub async fn check_detail_price(db: Pool<Any>, detail: &DetailTransaksi, tolerance_percent: f64) -> Result<Option<PriceMismatch>, sqlx::Error> {
+        let produk = match ambil_produk_by_id(&db, detail.id_produk as i64).await {
+            Ok(produk) => produk,
+            Err(RepositoryError::DatabaseError(e)) => return Err(e),
+            Err(e) => return Err(sqlx::Error::Protocol(e.to_string())),
+        };
+        Ok(produk.and_then(|produk| {
+            let harga_produk = produk.harga_untuk_jumlah(detail.jumlah);
+            PriceMismatch::detect(detail.id_produk, detail.harga_satuan, harga_produk, tolerance_percent)
+        }))
+    }
+
+    /// Harga per unit satu baris: `harga_override` dari kasir bila diisi, harga grosir
+    /// `(harga, min_qty)` bila jumlah baris mencapai `min_qty`, selain itu harga katalog.
+    pub fn line_unit_price(harga_katalog: f64, harga_grosir: Option<(f64, u32)>, jumlah: f64, harga_override: Option<f64>) -> f64 {
+        match (harga_override, harga_grosir) {
+            (Some(harga), _) => harga,
+            (None, Some((harga, min_qty))) if min_qty > 0 && jumlah >= min_qty as f64 => harga,
+            _ => harga_katalog,
+        }
+    }
+
+    /// Batas jumlah baris detail per transaksi, bisa diatur lewat env
+    /// `TRANSAKSI_MAX_LINE_ITEMS` (default 200).
+    pub fn max_line_items() -> usize {
+        std::env::var("TRANSAKSI_MAX_LINE_ITEMS")
+            .ok()
+            .and_then(|value| value.parse::<usize>().ok())
+            .filter(|value| *value > 0)
+            .unwrap_or(DEFAULT_MAX_LINE_ITEMS)
+    }
+
+    /// Nama pelanggan wajib diisi dan disimpan tanpa spasi di ujung.
+    pub fn normalize_nama_pelanggan(nama_pelanggan: &str) -> Result<String, String> {
+        let nama_pelanggan = nama_pelanggan.trim();
+        if nama_pelanggan.is_empty() {
+            return Err("Customer name cannot be empty or whitespace only".to_string());
+        }
+        Ok(nama_pelanggan.to_string())
+    }
+
+    pub fn validate_line_item_count(count: usize) -> Result<(), String> {
+        let max = Self::max_line_items();
+        if count > max {
+            return Err(format!("Transaction cannot have more than {} products (got {})", max, count));
+        }
         Ok(())
     }
 
-    async fn restore_product_stock(product_id: i32, quantity: u32) -> Result<(), sqlx::Error> {
-        println!("Mengembalikan stok produk ID {} sebanyak {}", product_id, quantity);
+    /// Mengurangi stok semua baris `(produk_id, jumlah)` dalam satu DB transaction.
+    /// Satu baris gagal membatalkan seluruhnya, sehingga tidak ada stok yang terpotong sebagian.
+    /// Event stok habis baru dikirim ke `notifier` setelah commit berhasil.
+    pub async fn commit_stock(db: Pool<Any>, lines: &[(i64, u32)], notifier: &dyn ProdukNotifier) -> Result<(), CommitStockError> {
+        let database_error = |e: sqlx::Error| CommitStockError::Database(e.to_string());
+        // `tx` yang di-drop tanpa commit otomatis di-rollback
+        let mut tx = db.begin().await.map_err(database_error)?;
+        let stok_changes = Self::decrement_lines(&mut tx, lines).await?;
+
+        tx.commit().await.map_err(database_error)?;
+        for change in &stok_changes {
+            notify_stok_change(notifier, change).await;
+        }
         Ok(())
     }
 
+    /// Inti `commit_stock` di dalam DB transaction milik pemanggil: baris produk dikunci dulu,
+    /// lalu stok tiap baris dikurangi. Pemanggil wajib membatalkan transaction-nya bila gagal.
+    async fn decrement_lines(conn: &mut sqlx::AnyConnection, lines: &[(i64, u32)]) -> Result<Vec<StokChange>, CommitStockError> {
+        let produk_ids: Vec<i64> = lines.iter().map(|(produk_id, _)| *produk_id).collect();
+        lock_produk_for_update(conn, &produk_ids)
+            .await
+            .map_err(|error| CommitStockError::Database(error.to_string()))?;
+
+        let mut stok_changes = Vec::with_capacity(lines.len());
+        for (line, (produk_id, jumlah)) in lines.iter().enumerate() {
+            let change = decrement_stok(conn, *produk_id, *jumlah).await.map_err(|error| match error {
+                RepositoryError::NotFound => CommitStockError::ProdukNotFound { line, produk_id: *produk_id },
+                RepositoryError::ValidationError(_) => CommitStockError::InsufficientStock { line, produk_id: *produk_id },
+                other => CommitStockError::Database(other.to_string()),
+            })?;
+            stok_changes.push(change);
+        }
+        Ok(stok_changes)
+    }
+
+    /// Mengembalikan stok baris yang sudah dipotong saat penjualan dibuat (batal, hapus
+    /// transaksi/detail, perubahan jumlah), di dalam DB transaction milik pemanggil.
+    async fn increment_lines(conn: &mut sqlx::AnyConnection, lines: &[(i64, u32)]) -> Result<Vec<StokChange>, sqlx::Error> {
+        let mut stok_changes = Vec::with_capacity(lines.len());
+        for (produk_id, jumlah) in lines {
+            let change = increment_stok(conn, *produk_id, *jumlah).await.map_err(|error| match error {
+                RepositoryError::NotFound => sqlx::Error::RowNotFound,
+                RepositoryError::DatabaseError(e) => e,
+                other => sqlx::Error::Protocol(other.to_string()),
+            })?;
+            stok_changes.push(change);
+        }
+        Ok(stok_changes)
+    }
+
+    /// Baris `(id_produk, jumlah dalam satuan jual)` menjadi `(produk_id, unit stok terkecil)`
+    /// sesuai `presisi_stok` tiap produk. Jumlah yang tidak sesuai presisi ditolak `RowNotFound`.
+    async fn unit_stok_lines(conn: &mut sqlx::AnyConnection, lines: &[(i32, f64)]) -> Result<Vec<(i64, u32)>, sqlx::Error> {
+        let produk_ids: Vec<i64> = lines.iter().map(|(id_produk, _)| *id_produk as i64).collect();
+        let presisi_stok = match ambil_presisi_stok(conn, &produk_ids).await {
+            Ok(presisi_stok) => presisi_stok,
+            Err(RepositoryError::DatabaseError(e)) => return Err(e),
+            Err(e) => return Err(sqlx::Error::Protocol(e.to_string())),
+        };
+        lines
+            .iter()
+            .map(|(id_produk, jumlah)| {
+                let produk_id = *id_produk as i64;
+                let presisi = presisi_stok.get(&produk_id).copied().unwrap_or(0);
+                jumlah_ke_unit_stok(*jumlah, presisi)
+                    .map(|unit| (produk_id, unit))
+                    .map_err(|_| sqlx::Error::RowNotFound)
+            })
+            .collect()
+    }
+
     pub async fn validate_product_stock(
         detail_requests: &[crate::transaksi_penjualan::dto::transaksi_request::CreateDetailTransaksiRequest]
     ) -> Result<(), String> {
@@ -104,7 +650,7 @@ impl TransaksiService {
                 return Err(format!("Produk dengan ID {} tidak ditemukan atau stok habis", detail.id_produk));
             }
 
-            if detail.jumlah > available_stock {
+            if detail.jumlah > available_stock as f64 {
                 return Err(format!(
                     "Stok produk '{}' tidak mencukupi. Tersedia: {}, Diminta: {}", 
                     detail.nama_produk, available_stock, detail.jumlah
@@ -126,40 +672,46 @@ impl TransaksiService {
         }
     }
 
-    async fn fetch_product_prices(
-        detail_requests: &[crate::transaksi_penjualan::dto::transaksi_request::CreateDetailTransaksiRequest]
-    ) -> Result<std::collections::HashMap<i32, f64>, sqlx::Error> {
-        let mut prices = std::collections::HashMap::new();
-        
-        for detail in detail_requests {
-            let price = match detail.id_produk {
-                1 => 100000.0,  
-                2 => 250000.0,   
-                3 => 500000.0, 
-                4 => 75000.0,  
-                5 => 150000.0,  
-                _ => 50000.0,  
-            };
-            prices.insert(detail.id_produk, price);
-        }
-
-        Ok(prices)
-    }
-
     pub async fn get_transaksi_by_id(db: Pool<Any>, id: i32) -> Result<Transaksi, sqlx::Error> {
         let db_connection = db.acquire().await?;
         TransaksiRepository::get_transaksi_by_id(db_connection, id).await
     }
 
     pub async fn update_transaksi(db: Pool<Any>, transaksi: &Transaksi) -> Result<Transaksi, sqlx::Error> {
+        Self::update_transaksi_by(db, transaksi, None).await
+    }
+
+    /// Field audit dari body diabaikan: `created_by` tetap dari data tersimpan dan
+    /// `updated_by` diisi `actor` (atau dibiarkan bila tidak ada actor). Perubahan status
+    /// dicatat ke `transaksi_status_history` dalam DB transaction yang sama.
+    pub async fn update_transaksi_by(db: Pool<Any>, transaksi: &Transaksi, actor: Option<&str>) -> Result<Transaksi, sqlx::Error> {
         let existing_transaksi = Self::get_transaksi_by_id(db.clone(), transaksi.id).await?;
         
         if !existing_transaksi.can_be_modified() {
             return Err(sqlx::Error::RowNotFound);
         }
 
+        let mut transaksi = transaksi.clone();
+        transaksi.audit = existing_transaksi.audit;
+        transaksi.audit.touch(actor);
+
+        let mut tx = db.begin().await?;
+        let updated = TransaksiRepository::write_transaksi_update(&mut tx, &transaksi).await?;
+        if updated.status != existing_transaksi.status {
+            let alasan = match updated.status {
+                StatusTransaksi::Dibatalkan => updated.alasan_pembatalan.as_deref(),
+                _ => None,
+            };
+            TransaksiRepository::insert_status_history(&mut tx, updated.id, &existing_transaksi.status, &updated.status, alasan, actor).await?;
+        }
+        tx.commit().await?;
+        Ok(updated)
+    }
+
+    pub async fn get_status_history(db: Pool<Any>, id: i32) -> Result<Vec<TransaksiStatusHistory>, sqlx::Error> {
+        Self::get_transaksi_by_id(db.clone(), id).await?;
         let db_connection = db.acquire().await?;
-        TransaksiRepository::update_transaksi(db_connection, transaksi).await
+        TransaksiRepository::get_status_history(db_connection, id).await
     }
 
     pub async fn delete_transaksi(db: Pool<Any>, id: i32) -> Result<(), sqlx::Error> {
@@ -170,15 +722,18 @@ impl TransaksiService {
         }
 
         let details = Self::get_detail_by_transaksi_id(db.clone(), id).await?;
-        for detail in details {
-            Self::restore_product_stock(detail.id_produk, detail.jumlah).await?;
-        }
-
-        let db_connection_detail = db.acquire().await?;
-        TransaksiRepository::delete_detail_by_transaksi_id(db_connection_detail, id).await?;
+        let lines: Vec<(i32, f64)> = details.iter().map(|detail| (detail.id_produk, detail.jumlah)).collect();
 
-        let db_connection = db.acquire().await?;
-        TransaksiRepository::delete_transaksi(db_connection, id).await
+        // `tx` yang di-drop tanpa commit otomatis di-rollback
+        let mut tx = db.begin().await?;
+        TransaksiRepository::remove_details_of_transaksi(&mut tx, id).await?;
+        // Hapus kedua yang bersamaan tidak menemukan baris lagi, sehingga stok tidak kembali dua kali
+        if !TransaksiRepository::remove_transaksi(&mut tx, id).await? {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        let unit_lines = Self::unit_stok_lines(&mut tx, &lines).await?;
+        Self::increment_lines(&mut tx, &unit_lines).await?;
+        tx.commit().await
     }
 
     pub async fn get_all_transaksi(db: Pool<Any>) -> Result<Vec<Transaksi>, sqlx::Error> {
@@ -197,6 +752,10 @@ impl TransaksiService {
     }
 
     pub async fn complete_transaksi(db: Pool<Any>, id: i32) -> Result<Transaksi, sqlx::Error> {
+        Self::complete_transaksi_by(db, id, None).await
+    }
+
+    pub async fn complete_transaksi_by(db: Pool<Any>, id: i32, actor: Option<&str>) -> Result<Transaksi, sqlx::Error> {
         let mut transaksi = Self::get_transaksi_by_id(db.clone(), id).await?;
         
         if !transaksi.can_be_modified() {
@@ -204,34 +763,285 @@ impl TransaksiService {
         }
 
         transaksi.update_status(StatusTransaksi::Selesai);
-        Self::update_transaksi(db, &transaksi).await
+        Self::update_transaksi_by(db, &transaksi, actor).await
+    }
+
+    pub async fn cancel_transaksi(db: Pool<Any>, id: i32, alasan_pembatalan: Option<String>) -> Result<Transaksi, sqlx::Error> {
+        Self::cancel_transaksi_by(db, id, alasan_pembatalan, None).await
     }
 
-    pub async fn cancel_transaksi(db: Pool<Any>, id: i32) -> Result<Transaksi, sqlx::Error> {
+    /// Alasan kosong/whitespace disimpan sebagai NULL.
+    pub async fn cancel_transaksi_by(db: Pool<Any>, id: i32, alasan_pembatalan: Option<String>, actor: Option<&str>) -> Result<Transaksi, sqlx::Error> {
         let mut transaksi = Self::get_transaksi_by_id(db.clone(), id).await?;
         
         if !transaksi.status.can_be_cancelled() {
             return Err(sqlx::Error::RowNotFound);
         }
 
+        let status_lama = transaksi.status.clone();
         let details = Self::get_detail_by_transaksi_id(db.clone(), id).await?;
-        for detail in details {
-            Self::restore_product_stock(detail.id_produk, detail.jumlah).await?;
+        let lines: Vec<(i32, f64)> = details.iter().map(|detail| (detail.id_produk, detail.jumlah)).collect();
+
+        // `tx` yang di-drop tanpa commit otomatis di-rollback
+        let mut tx = db.begin().await?;
+        // Update bersyarat mengunci baris transaksi; cancel kedua yang bersamaan gagal di sini
+        let cancelled = TransaksiRepository::update_status_if(&mut tx, id, &status_lama, &StatusTransaksi::Dibatalkan, actor)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)?;
+        transaksi.update_status(StatusTransaksi::Dibatalkan);
+        transaksi.alasan_pembatalan = alasan_pembatalan
+            .map(|alasan| alasan.trim().to_string())
+            .filter(|alasan| !alasan.is_empty());
+        transaksi.audit = cancelled.audit;
+        let cancelled = TransaksiRepository::write_transaksi_update(&mut tx, &transaksi).await?;
+
+        let unit_lines = Self::unit_stok_lines(&mut tx, &lines).await?;
+        Self::increment_lines(&mut tx, &unit_lines).await?;
+        TransaksiRepository::insert_status_history(&mut tx, id, &status_lama, &cancelled.status, cancelled.alasan_pembatalan.as_deref(), actor).await?;
+        tx.commit().await?;
+        Ok(cancelled)
+    }
+
+    /// Membalik penjualan yang sudah SELESAI: stok dikembalikan, sisa saldo tiap payment
+    /// di-refund, status menjadi VOID dan riwayat status dicatat, semuanya dalam satu DB
+    /// transaction. Berbeda dengan cancel yang hanya untuk transaksi yang masih diproses.
+    /// Produk yang tersedia lagi karena void dikabarkan lewat `notifier` setelah commit.
+    pub async fn void_transaksi_by(db: Pool<Any>, id: i32, alasan: Option<String>, actor: Option<&str>, notifier: &dyn ProdukNotifier) -> Result<VoidTransaksiResponse, VoidError> {
+        let transaksi = match Self::get_transaksi_by_id(db.clone(), id).await {
+            Ok(transaksi) => transaksi,
+            Err(sqlx::Error::RowNotFound) => return Err(VoidError::NotFound(id)),
+            Err(e) => return Err(e.into()),
+        };
+        if !transaksi.status.can_be_voided() {
+            return Err(VoidError::NotVoidable(transaksi.status));
         }
+        let alasan = alasan
+            .map(|alasan| alasan.trim().to_string())
+            .filter(|alasan| !alasan.is_empty());
+        let details = Self::get_detail_by_transaksi_id(db.clone(), id).await?;
 
-        transaksi.update_status(StatusTransaksi::Dibatalkan);
-        Self::update_transaksi(db, &transaksi).await
+        // `tx` yang di-drop tanpa commit otomatis di-rollback
+        let mut tx = db.begin().await?;
+        // Update bersyarat sekaligus mengunci baris transaksi; void kedua yang bersamaan gagal di sini
+        let voided = TransaksiRepository::update_status_if(&mut tx, id, &StatusTransaksi::Selesai, &StatusTransaksi::Void, actor)
+            .await?
+            .ok_or_else(|| VoidError::NotVoidable(transaksi.status.clone()))?;
+
+        let produk_ids: Vec<i64> = details.iter().map(|detail| detail.id_produk as i64).collect();
+        let presisi_stok = ambil_presisi_stok(&mut tx, &produk_ids)
+            .await
+            .map_err(|error| VoidError::Database(error.to_string()))?;
+        let mut stok_changes = Vec::with_capacity(details.len());
+        for detail in &details {
+            let produk_id = detail.id_produk as i64;
+            let presisi = presisi_stok.get(&produk_id).copied().unwrap_or(0);
+            let unit = jumlah_ke_unit_stok(detail.jumlah, presisi).map_err(VoidError::Database)?;
+            let change = increment_stok(&mut tx, produk_id, unit).await.map_err(|error| match error {
+                RepositoryError::NotFound => VoidError::ProdukNotFound(produk_id),
+                other => VoidError::Database(other.to_string()),
+            })?;
+            stok_changes.push(change);
+        }
+
+        let reason = match &alasan {
+            Some(alasan) => format!("Void transaksi {}: {}", id, alasan),
+            None => format!("Void transaksi {}", id),
+        };
+        let mut refunds = Vec::new();
+        for payment in PembayaranRepository::find_by_transaction_with_installments(&mut tx, &id.to_string()).await? {
+            let refundable_total = payment.refundable_total();
+            let existing = PembayaranRepository::fetch_refunds(&mut tx, &payment.id).await?;
+            let ledger = RefundLedger::new(&payment.id, refundable_total, existing);
+            if ledger.remaining <= 0.0 {
+                continue;
+            }
+
+            let refund = Refund {
+                id: format!("RFD-{}", Uuid::new_v4()),
+                payment_id: payment.id.clone(),
+                amount: ledger.remaining,
+                reason: reason.clone(),
+                refund_date: Utc::now(),
+                created_by: actor.map(str::to_string),
+            };
+            PembayaranRepository::insert_refund_within_limit(&mut tx, &refund, refundable_total)
+                .await
+                .map_err(|error| match error {
+                    sqlx::Error::RowNotFound => VoidError::RefundConflict(payment.id.clone()),
+                    other => other.into(),
+                })?;
+            refunds.push(refund);
+        }
+
+        TransaksiRepository::insert_status_history(&mut tx, id, &transaksi.status, &voided.status, alasan.as_deref(), actor).await?;
+        tx.commit().await?;
+        for change in &stok_changes {
+            notify_stok_change(notifier, change).await;
+        }
+        Ok(VoidTransaksiResponse { transaksi: voided, refunds })
+    }
+
+    /// Retur sebagian barang dari transaksi SELESAI tanpa membatalkannya: stok barang yang
+    /// dikembalikan ditambah lagi, nilai sebandingnya di-refund ke payment yang masih punya
+    /// saldo, dan catatan retur disimpan, semuanya dalam satu DB transaction. Total retur per
+    /// baris detail (termasuk retur sebelumnya) tidak boleh melebihi jumlah yang dibeli.
+    pub async fn retur_transaksi_by(
+        db: Pool<Any>,
+        id: i32,
+        request: &ReturTransaksiRequest,
+        actor: Option<&str>,
+        notifier: &dyn ProdukNotifier,
+    ) -> Result<ReturTransaksiResponse, ReturError> {
+        let transaksi = match Self::get_transaksi_by_id(db.clone(), id).await {
+            Ok(transaksi) => transaksi,
+            Err(sqlx::Error::RowNotFound) => return Err(ReturError::NotFound(id)),
+            Err(e) => return Err(e.into()),
+        };
+        if transaksi.status != StatusTransaksi::Selesai {
+            return Err(ReturError::NotReturnable(transaksi.status));
+        }
+        if request.items.is_empty() {
+            return Err(ReturError::InvalidRequest("items must not be empty".to_string()));
+        }
+        let mut diminta: Vec<(i32, f64)> = Vec::with_capacity(request.items.len());
+        for item in &request.items {
+            if !item.jumlah.is_finite() || item.jumlah <= 0.0 {
+                return Err(ReturError::InvalidRequest(format!("jumlah for detail {} must be greater than 0", item.id_detail)));
+            }
+            if diminta.iter().any(|(id_detail, _)| *id_detail == item.id_detail) {
+                return Err(ReturError::InvalidRequest(format!("detail {} is listed more than once", item.id_detail)));
+            }
+            diminta.push((item.id_detail, item.jumlah));
+        }
+        let alasan = request.alasan.as_ref()
+            .map(|alasan| alasan.trim().to_string())
+            .filter(|alasan| !alasan.is_empty());
+        let details = Self::get_detail_by_transaksi_id(db.clone(), id).await?;
+
+        // `tx` yang di-drop tanpa commit otomatis di-rollback
+        let mut tx = db.begin().await?;
+        // Update bersyarat SELESAI -> SELESAI mengunci baris transaksi, sehingga dua retur
+        // bersamaan tidak bisa sama-sama lolos pengecekan jumlah, dan void di tengah jalan terdeteksi
+        TransaksiRepository::update_status_if(&mut tx, id, &StatusTransaksi::Selesai, &StatusTransaksi::Selesai, actor)
+            .await?
+            .ok_or_else(|| ReturError::NotReturnable(transaksi.status.clone()))?;
+        let sudah_diretur = TransaksiRepository::returned_quantities(&mut tx, id).await?;
+        let produk_ids: Vec<i64> = details.iter().map(|detail| detail.id_produk as i64).collect();
+        let presisi_stok = ambil_presisi_stok(&mut tx, &produk_ids)
+            .await
+            .map_err(|error| ReturError::Database(error.to_string()))?;
+
+        // Jumlah dibandingkan dalam unit terkecil produk supaya retur desimal (mis. 0.5 kg dari
+        // 2.5 kg) tidak terganjal galat float, dan digit desimalnya divalidasi seperti checkout
+        let mut items = Vec::with_capacity(diminta.len());
+        let mut units = Vec::with_capacity(diminta.len());
+        let mut nilai_retur_total_sen = 0i64;
+        for (id_detail, jumlah) in diminta {
+            let detail = details.iter().find(|detail| detail.id == id_detail).ok_or_else(|| {
+                ReturError::InvalidRequest(format!("detail {} does not belong to transaksi {}", id_detail, id))
+            })?;
+            let presisi = presisi_stok.get(&(detail.id_produk as i64)).copied().unwrap_or(0);
+            let unit = jumlah_ke_unit_stok(jumlah, presisi)
+                .map_err(|e| ReturError::InvalidRequest(format!("detail {}: {}", id_detail, e)))?;
+            let dibeli_unit = jumlah_ke_unit_stok(detail.jumlah, presisi).map_err(ReturError::Database)?;
+            let sebelumnya = sudah_diretur.get(&id_detail).copied().unwrap_or(0.0);
+            let sebelumnya_unit = jumlah_ke_unit_stok(sebelumnya, presisi).map_err(ReturError::Database)?;
+            if unit > dibeli_unit.saturating_sub(sebelumnya_unit) {
+                return Err(ReturError::InvalidRequest(format!(
+                    "cannot return {} of detail {}: purchased {}, already returned {}",
+                    jumlah, id_detail, detail.jumlah, sebelumnya
+                )));
+            }
+            let sebelumnya = unit_stok_ke_jumlah(sebelumnya_unit, presisi);
+            let jumlah = unit_stok_ke_jumlah(unit, presisi);
+            let nilai_sen = nilai_retur_sen(detail, sebelumnya, jumlah);
+            nilai_retur_total_sen += nilai_sen;
+            items.push(ReturItem { id_detail, id_produk: detail.id_produk, jumlah, nilai: from_sen(nilai_sen) });
+            units.push(unit);
+        }
+
+        let mut stok_changes = Vec::with_capacity(items.len());
+        for (item, unit) in items.iter().zip(units) {
+            let produk_id = item.id_produk as i64;
+            let change = increment_stok(&mut tx, produk_id, unit).await.map_err(|error| match error {
+                RepositoryError::NotFound => ReturError::ProdukNotFound(produk_id),
+                other => ReturError::Database(other.to_string()),
+            })?;
+            stok_changes.push(change);
+        }
+
+        let retur_id = format!("RTR-{}", Uuid::new_v4());
+        let reason = match &alasan {
+            Some(alasan) => format!("Retur {} transaksi {}: {}", retur_id, id, alasan),
+            None => format!("Retur {} transaksi {}", retur_id, id),
+        };
+        let mut refunds = Vec::new();
+        let mut belum_direfund_sen = nilai_retur_total_sen;
+        for payment in PembayaranRepository::find_by_transaction_with_installments(&mut tx, &id.to_string()).await? {
+            if belum_direfund_sen <= 0 {
+                break;
+            }
+            let refundable_total = payment.refundable_total();
+            let existing = PembayaranRepository::fetch_refunds(&mut tx, &payment.id).await?;
+            let ledger = RefundLedger::new(&payment.id, refundable_total, existing);
+            let amount_sen = to_sen(ledger.remaining).min(belum_direfund_sen);
+            if amount_sen <= 0 {
+                continue;
+            }
+
+            let refund = Refund {
+                id: format!("RFD-{}", Uuid::new_v4()),
+                payment_id: payment.id.clone(),
+                amount: from_sen(amount_sen),
+                reason: reason.clone(),
+                refund_date: Utc::now(),
+                created_by: actor.map(str::to_string),
+            };
+            PembayaranRepository::insert_refund_within_limit(&mut tx, &refund, refundable_total)
+                .await
+                .map_err(|error| match error {
+                    sqlx::Error::RowNotFound => ReturError::RefundConflict(payment.id.clone()),
+                    other => other.into(),
+                })?;
+            belum_direfund_sen -= amount_sen;
+            refunds.push(refund);
+        }
+
+        let retur = ReturTransaksi {
+            id: retur_id,
+            id_transaksi: id,
+            items,
+            nilai_retur: from_sen(nilai_retur_total_sen),
+            total_refund: from_sen(nilai_retur_total_sen - belum_direfund_sen),
+            alasan,
+            created_by: actor.map(str::to_string),
+            created_at: Utc::now(),
+        };
+        TransaksiRepository::insert_retur(&mut tx, &retur).await?;
+        tx.commit().await?;
+        for change in &stok_changes {
+            notify_stok_change(notifier, change).await;
+        }
+        Ok(ReturTransaksiResponse { retur, refunds })
     }
 
     pub async fn add_detail_transaksi(db: Pool<Any>, detail: &DetailTransaksi) -> Result<DetailTransaksi, sqlx::Error> {
+        if DetailTransaksi::validate_diskon(detail.harga_satuan, detail.jumlah, detail.diskon).is_err() {
+            return Err(sqlx::Error::RowNotFound);
+        }
+
         let transaksi = Self::get_transaksi_by_id(db.clone(), detail.id_transaksi).await?;
         
         if !transaksi.can_be_modified() {
             return Err(sqlx::Error::RowNotFound);
         }
 
-        let db_connection = db.acquire().await?;
-        let created_detail = TransaksiRepository::create_detail_transaksi(db_connection, detail).await?;
+        // `tx` yang di-drop tanpa commit otomatis di-rollback
+        let mut tx = db.begin().await?;
+        let created_detail = TransaksiRepository::insert_detail_transaksi(&mut tx, detail).await?;
+        let unit_lines = Self::unit_stok_lines(&mut tx, &[(detail.id_produk, detail.jumlah)]).await?;
+        Self::decrement_lines(&mut tx, &unit_lines).await.map_err(stock_error)?;
+        tx.commit().await?;
 
         Self::recalculate_transaction_total(db, detail.id_transaksi).await?;
 
@@ -243,15 +1053,45 @@ impl TransaksiService {
         TransaksiRepository::get_detail_by_transaksi_id(db_connection, id_transaksi).await
     }
 
+    /// Detail semua transaksi pada `transaksi_list`, dikelompokkan per `id_transaksi`.
+    pub async fn get_details_grouped(db: Pool<Any>, transaksi_list: &[Transaksi]) -> Result<HashMap<i32, Vec<DetailTransaksi>>, sqlx::Error> {
+        let ids: Vec<i32> = transaksi_list.iter().map(|t| t.id).collect();
+        let db_connection = db.acquire().await?;
+        let details = TransaksiRepository::get_details_by_transaksi_ids(db_connection, &ids).await?;
+
+        let mut grouped: HashMap<i32, Vec<DetailTransaksi>> = HashMap::new();
+        for detail in details {
+            grouped.entry(detail.id_transaksi).or_default().push(detail);
+        }
+        Ok(grouped)
+    }
+
     pub async fn update_detail_transaksi(db: Pool<Any>, detail: &DetailTransaksi) -> Result<DetailTransaksi, sqlx::Error> {
+        if DetailTransaksi::validate_diskon(detail.harga_satuan, detail.jumlah, detail.diskon).is_err() {
+            return Err(sqlx::Error::RowNotFound);
+        }
+
         let transaksi = Self::get_transaksi_by_id(db.clone(), detail.id_transaksi).await?;
         
         if !transaksi.can_be_modified() {
             return Err(sqlx::Error::RowNotFound);
         }
 
-        let db_connection = db.acquire().await?;
-        let updated_detail = TransaksiRepository::update_detail_transaksi(db_connection, detail).await?;
+        let lama = Self::get_detail_by_transaksi_id(db.clone(), detail.id_transaksi)
+            .await?
+            .into_iter()
+            .find(|existing| existing.id == detail.id);
+
+        // Stok baris lama dikembalikan lalu dipotong lagi sesuai baris baru, dalam satu DB transaction
+        let mut tx = db.begin().await?;
+        let updated_detail = TransaksiRepository::write_detail_update(&mut tx, detail).await?;
+        if let Some(lama) = lama {
+            let unit_lines = Self::unit_stok_lines(&mut tx, &[(lama.id_produk, lama.jumlah)]).await?;
+            Self::increment_lines(&mut tx, &unit_lines).await?;
+        }
+        let unit_lines = Self::unit_stok_lines(&mut tx, &[(updated_detail.id_produk, updated_detail.jumlah)]).await?;
+        Self::decrement_lines(&mut tx, &unit_lines).await.map_err(stock_error)?;
+        tx.commit().await?;
 
         Self::recalculate_transaction_total(db, detail.id_transaksi).await?;
 
@@ -266,12 +1106,15 @@ impl TransaksiService {
         }
 
         let details = Self::get_detail_by_transaksi_id(db.clone(), id_transaksi).await?;
-        if let Some(detail_to_delete) = details.iter().find(|d| d.id == id) {
-            Self::restore_product_stock(detail_to_delete.id_produk, detail_to_delete.jumlah).await?;
-        }
 
-        let db_connection = db.acquire().await?;
-        TransaksiRepository::delete_detail_transaksi(db_connection, id).await?;
+        // `tx` yang di-drop tanpa commit otomatis di-rollback
+        let mut tx = db.begin().await?;
+        let removed = TransaksiRepository::remove_detail_transaksi(&mut tx, id).await?;
+        if let Some(detail_to_delete) = details.iter().find(|d| d.id == id).filter(|_| removed) {
+            let unit_lines = Self::unit_stok_lines(&mut tx, &[(detail_to_delete.id_produk, detail_to_delete.jumlah)]).await?;
+            Self::increment_lines(&mut tx, &unit_lines).await?;
+        }
+        tx.commit().await?;
 
         Self::recalculate_transaction_total(db, id_transaksi).await?;
 
@@ -280,7 +1123,7 @@ impl TransaksiService {
 
     async fn recalculate_transaction_total(db: Pool<Any>, id_transaksi: i32) -> Result<(), sqlx::Error> {
         let details = Self::get_detail_by_transaksi_id(db.clone(), id_transaksi).await?;
-        let total: f64 = details.iter().map(|d| d.subtotal).sum();
+        let total = sum_amounts(details.iter().map(|d| d.subtotal));
 
         let mut transaksi = Self::get_transaksi_by_id(db.clone(), id_transaksi).await?;
         transaksi.update_total_harga(total);
@@ -291,52 +1134,186 @@ impl TransaksiService {
         Ok(())
     }
 
+    /// Menghitung ulang `total_harga` dari harga, jumlah, dan diskon tiap baris detail lalu
+    /// menyimpannya, untuk memperbaiki baris lama yang totalnya salah. Mengembalikan
+    /// `(total_lama, total_baru)`; berlaku untuk status apa pun.
+    pub async fn recompute_total(db: Pool<Any>, id: i32) -> Result<(f64, f64), sqlx::Error> {
+        let mut transaksi = Self::get_transaksi_by_id(db.clone(), id).await?;
+        let details = Self::get_detail_by_transaksi_id(db.clone(), id).await?;
+
+        let old_total = transaksi.total_harga;
+        let new_total = from_sen(details
+            .iter()
+            .map(|d| discounted_line_subtotal_sen(d.harga_satuan, d.jumlah, d.diskon))
+            .sum());
+        transaksi.update_total_harga(new_total);
+
+        let db_connection = db.acquire().await?;
+        TransaksiRepository::update_transaksi(db_connection, &transaksi).await?;
+
+        Ok((old_total, new_total))
+    }
+
     pub async fn search_transaksi_with_pagination(
         db: Pool<Any>,
         search_params: &TransaksiSearchParams
     ) -> Result<TransaksiSearchResult, sqlx::Error> {
-        let mut transaksi_list = if let Some(customer_id) = search_params.id_pelanggan {
-            Self::get_transaksi_by_pelanggan(db.clone(), customer_id).await?
-        } else if let Some(ref status_str) = search_params.status {
-            if let Some(status_enum) = StatusTransaksi::from_string(status_str) {
-                Self::get_transaksi_by_status(db.clone(), &status_enum).await?
-            } else {
-                return Ok(TransaksiSearchResult::empty());
-            }
-        } else {
-            Self::get_all_transaksi(db).await?
+        let order_by = TransaksiRepository::sort_clause(search_params.sort.as_deref())
+            .ok_or(sqlx::Error::RowNotFound)?;
+        let filter_field = match search_params.filter.as_deref() {
+            Some(value) => Some(FilterTransaksi::from_string(value).ok_or(sqlx::Error::RowNotFound)?),
+            None => None,
+        };
+        let keyword_columns = TransaksiRepository::keyword_columns(filter_field);
+
+        let status = match search_params.status {
+            Some(ref status_str) => match StatusTransaksi::from_string(status_str) {
+                Some(status_enum) => Some(status_enum),
+                None => return Ok(TransaksiSearchResult::empty()),
+            },
+            None => None,
         };
 
-        if let Some(ref sort_strategy) = search_params.sort {
-            transaksi_list = Self::sort_transaksi(transaksi_list, sort_strategy);
-        }
+        let date_from = match search_params.date_from.as_deref() {
+            Some(value) => Some(Self::normalize_date_bound(value, false).ok_or(sqlx::Error::RowNotFound)?),
+            None => None,
+        };
+        let date_to = match search_params.date_to.as_deref() {
+            Some(value) => Some(Self::normalize_date_bound(value, true).ok_or(sqlx::Error::RowNotFound)?),
+            None => None,
+        };
 
-        if let Some(ref filter_strategy) = search_params.filter {
-            if let Some(ref keyword_value) = search_params.keyword {
-                transaksi_list = Self::filter_transaksi(transaksi_list, filter_strategy, keyword_value);
-            }
-        }
+        let filter = TransaksiSearchFilter {
+            status: status.as_ref(),
+            id_pelanggan: search_params.id_pelanggan,
+            keyword: search_params.keyword.as_deref(),
+            keyword_columns,
+            date_from: date_from.as_deref(),
+            date_to: date_to.as_deref(),
+        };
 
-        let total_count = transaksi_list.len();
-        let page = search_params.page.unwrap_or(1);
-        let limit = search_params.limit.unwrap_or(10);
-        let offset = (page - 1) * limit;
+        let db_connection = db.acquire().await?;
+        let total_count = TransaksiRepository::count_transaksi(db_connection, &filter).await? as usize;
 
-        let paginated_list = transaksi_list
-            .into_iter()
-            .skip(offset)
-            .take(limit)
-            .collect();
+        let (limit, offset) = limit_offset(search_params.page, search_params.limit);
+        let page = offset / limit + 1;
+
+        let db_connection = db.acquire().await?;
+        let data = TransaksiRepository::search_transaksi(
+            db_connection,
+            &filter,
+            order_by,
+            Some(page),
+            Some(limit),
+        ).await?;
 
         Ok(TransaksiSearchResult {
-            data: paginated_list,
+            data,
             total_count,
             page,
             limit,
-            total_pages: (total_count + limit - 1) / limit,
+            total_pages: total_count.div_ceil(limit),
         })
     }
 
+    /// Menyamakan batas tanggal dengan format `tanggal_transaksi` ("%Y-%m-%d %H:%M:%S").
+    /// Laporan produk terlaris; tanggal tidak valid dilaporkan sebagai `RowNotFound`
+    /// (ditampilkan 400 oleh controller), `limit` dibatasi ke `MAX_TOP_PRODUCTS_LIMIT`.
+    /// Semua transaksi dalam rentang `tanggal_transaksi` (batas inklusif, sudah dinormalisasi)
+    /// sebagai stream; koneksi diambil pemanggil agar kegagalan pool terlihat sebelum respons dikirim.
+    pub fn export_transaksi(
+        mut conn: PoolConnection<Any>,
+        date_from: Option<String>,
+        date_to: Option<String>,
+    ) -> impl Stream<Item = Result<Transaksi, sqlx::Error>> + Send {
+        stream! {
+            let filter = TransaksiSearchFilter {
+                date_from: date_from.as_deref(),
+                date_to: date_to.as_deref(),
+                ..Default::default()
+            };
+            let sql = TransaksiRepository::export_sql(&filter);
+            let mut rows = TransaksiRepository::stream_transaksi(&mut conn, &sql, &filter);
+            while let Some(row) = rows.next().await {
+                yield row;
+            }
+        }
+    }
+
+    /// Lihat `TransaksiRepository::get_total_belanja_pelanggan`; dihitung ulang tiap
+    /// permintaan supaya selalu sesuai status transaksi terbaru.
+    pub async fn get_total_belanja_pelanggan(db: Pool<Any>, id_pelanggan: i32) -> Result<f64, sqlx::Error> {
+        let db_connection = db.acquire().await?;
+        let total = TransaksiRepository::get_total_belanja_pelanggan(db_connection, id_pelanggan).await?;
+        Ok(round_report_amount(total))
+    }
+
+    pub async fn get_top_products(
+        db: Pool<Any>,
+        date_from: Option<&str>,
+        date_to: Option<&str>,
+        limit: Option<usize>,
+    ) -> Result<Vec<TopProductReport>, sqlx::Error> {
+        let (date_from, date_to) = Self::normalize_report_range(date_from, date_to)?;
+        let limit = limit
+            .filter(|l| *l > 0)
+            .unwrap_or(DEFAULT_TOP_PRODUCTS_LIMIT)
+            .min(MAX_TOP_PRODUCTS_LIMIT);
+
+        let db_connection = db.acquire().await?;
+        let mut report = TransaksiRepository::get_top_products(db_connection, date_from.as_deref(), date_to.as_deref(), limit as i64).await?;
+        for row in &mut report {
+            row.total_pendapatan = round_report_amount(row.total_pendapatan);
+        }
+        Ok(report)
+    }
+
+    /// Laporan penjualan per kategori produk; tanggal tidak valid dilaporkan sebagai
+    /// `RowNotFound` seperti `get_top_products`.
+    pub async fn get_sales_by_category(
+        db: Pool<Any>,
+        date_from: Option<&str>,
+        date_to: Option<&str>,
+    ) -> Result<Vec<SalesByCategoryReport>, sqlx::Error> {
+        let (date_from, date_to) = Self::normalize_report_range(date_from, date_to)?;
+
+        let db_connection = db.acquire().await?;
+        let mut report = TransaksiRepository::get_sales_by_category(
+            db_connection,
+            date_from.as_deref(),
+            date_to.as_deref(),
+            KATEGORI_TIDAK_DIKENAL,
+        ).await?;
+        for row in &mut report {
+            row.total_revenue = round_report_amount(row.total_revenue);
+        }
+        Ok(report)
+    }
+
+    fn normalize_report_range(date_from: Option<&str>, date_to: Option<&str>) -> Result<(Option<String>, Option<String>), sqlx::Error> {
+        let date_from = match date_from {
+            Some(value) => Some(Self::normalize_date_bound(value, false).ok_or(sqlx::Error::RowNotFound)?),
+            None => None,
+        };
+        let date_to = match date_to {
+            Some(value) => Some(Self::normalize_date_bound(value, true).ok_or(sqlx::Error::RowNotFound)?),
+            None => None,
+        };
+        Ok((date_from, date_to))
+    }
+
+    /// Tanggal tanpa jam dianggap awal hari untuk `date_from` dan akhir hari untuk `date_to`.
+    pub fn normalize_date_bound(value: &str, end_of_day: bool) -> Option<String> {
+        let value = value.trim();
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S") {
+            return Some(datetime.format("%Y-%m-%d %H:%M:%S").to_string());
+        }
+
+        let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+        let time = if end_of_day { "23:59:59" } else { "00:00:00" };
+        Some(format!("{} {}", date.format("%Y-%m-%d"), time))
+    }
+
     pub fn sort_transaksi(mut transaksi_list: Vec<Transaksi>, sort_by: &str) -> Vec<Transaksi> {
         match sort_by.to_lowercase().as_str() {
             "tanggal" | "tanggal_transaksi" => {
@@ -396,6 +1373,10 @@ mod test {
     use sqlx::any::install_default_drivers;
     use sqlx::{Any, Pool};
     use rocket::async_test;
+    use crate::transaksi_penjualan::dto::transaksi_request::{CreateTransaksiRequest, CreateDetailTransaksiRequest};
+    use crate::manajemen_produk::model::StokEventKind;
+    use crate::manajemen_produk::service::produk_dispatcher::ProdukDispatcher;
+    use crate::manajemen_produk::service::produk_notifier::MockProdukNotifier;
 
     async fn setup() -> Pool<Any> {
         install_default_drivers();
@@ -410,10 +1391,108 @@ mod test {
             .run(&db)
             .await
             .unwrap();
+        seed_katalog_produk(&db).await;
         
         db
     }
 
+    // Skema test memakai BIGSERIAL yang di SQLite bukan alias rowid, jadi id diisi manual
+    /// Katalog produk 1-5 untuk test penjualan, yang mengambil harga dan memotong stok dari
+    /// baris produk. `insert_produk` menimpa baris ini bila id-nya sama.
+    async fn seed_katalog_produk(db: &Pool<Any>) {
+        for (id, harga) in [(1, 100000.0), (2, 250000.0), (3, 500000.0), (4, 75000.0), (5, 150000.0)] {
+            sqlx::query("INSERT INTO produk (id, nama, kategori, harga, stok) VALUES ($1, $2, 'Bahan Bangunan', $3, 100)")
+                .bind(id as i64)
+                .bind(format!("Produk {}", id))
+                .bind(harga)
+                .execute(db)
+                .await
+                .unwrap();
+        }
+    }
+
+    async fn insert_produk(db: &Pool<Any>, id: i64, nama: &str, stok: i32) -> i64 {
+        sqlx::query("
+            INSERT INTO produk (id, nama, kategori, harga, stok, deskripsi) VALUES ($1, $2, 'Bahan Bangunan', 50000, $3, NULL)
+            ON CONFLICT (id) DO UPDATE SET nama = excluded.nama, harga = excluded.harga, stok = excluded.stok
+        ")
+            .bind(id)
+            .bind(nama)
+            .bind(stok)
+            .execute(db)
+            .await
+            .unwrap();
+        id
+    }
+
+    async fn stok_of(db: &Pool<Any>, produk_id: i64) -> i32 {
+        sqlx::query_scalar("SELECT stok FROM produk WHERE id = $1")
+            .bind(produk_id)
+            .fetch_one(db)
+            .await
+            .unwrap()
+    }
+
+    // SQLite berbasis file dengan banyak koneksi: penulisan saling menunggu (busy timeout),
+    // jadi pemotongan stok bersamaan tetap tidak boleh melebihi stok yang ada
+    #[async_test]
+    async fn test_concurrent_commit_stock_never_oversells() {
+        install_default_drivers();
+        let path = std::env::temp_dir().join(format!("buildingstore_stock_{}.db", Uuid::new_v4().simple()));
+        let db = sqlx::any::AnyPoolOptions::new()
+            .max_connections(8)
+            .connect(&format!("sqlite://{}?mode=rwc", path.display()))
+            .await
+            .unwrap();
+        sqlx::migrate!("migrations/test").run(&db).await.unwrap();
+        let semen = insert_produk(&db, 1, "Semen", 5).await;
+
+        let attempts = (0..12).map(|_| {
+            let db = db.clone();
+            tokio::spawn(async move {
+                TransaksiService::commit_stock(db, &[(semen, 1)], &ProdukDispatcher::new()).await
+            })
+        });
+        let results: Vec<_> = rocket::futures::future::join_all(attempts)
+            .await
+            .into_iter()
+            .map(|joined| joined.unwrap())
+            .collect();
+
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 5);
+        assert!(results.iter().all(|result| matches!(result, Ok(()) | Err(CommitStockError::InsufficientStock { .. }))));
+        assert_eq!(stok_of(&db, semen).await, 0);
+
+        db.close().await;
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[async_test]
+    async fn test_commit_stock_rolls_back_all_lines_when_one_fails() {
+        let db = setup().await;
+        let semen = insert_produk(&db, 1, "Semen", 10).await;
+        let pasir = insert_produk(&db, 2, "Pasir", 2).await;
+
+        let mut notifier = MockProdukNotifier::new();
+        notifier.expect_notify_stok_event()
+            .withf(move |event| event.produk_id == pasir && event.kind == StokEventKind::OutOfStock)
+            .times(1)
+            .returning(|_event| Box::pin(async {}));
+
+        let result = TransaksiService::commit_stock(db.clone(), &[(semen, 4), (pasir, 3)], &notifier).await;
+        assert!(matches!(result, Err(CommitStockError::InsufficientStock { line: 1, produk_id }) if produk_id == pasir));
+        assert_eq!(stok_of(&db, semen).await, 10);
+        assert_eq!(stok_of(&db, pasir).await, 2);
+
+        let result = TransaksiService::commit_stock(db.clone(), &[(semen, 1), (9999, 1)], &notifier).await;
+        assert!(matches!(result, Err(CommitStockError::ProdukNotFound { line: 1, produk_id: 9999 })));
+        assert_eq!(stok_of(&db, semen).await, 10);
+
+        TransaksiService::commit_stock(db.clone(), &[(semen, 4), (pasir, 2)], &notifier).await.unwrap();
+        assert_eq!(stok_of(&db, semen).await, 6);
+        assert_eq!(stok_of(&db, pasir).await, 0);
+    }
+
     #[async_test]
     async fn test_create_transaksi() {
         let db = setup().await;
@@ -462,12 +1541,13 @@ mod test {
             None,
         );
         let created_transaksi = TransaksiService::create_transaksi(db.clone(), &transaksi).await.unwrap();
+        insert_produk(&db, 101, "Keramik Premium", 10).await;
 
         let detail = DetailTransaksi::new(
             created_transaksi.id,
             101,
             15000000.0,
-            1,
+            1.0,
         );
         let created_detail = TransaksiService::add_detail_transaksi(db.clone(), &detail).await.unwrap();
 
@@ -513,4 +1593,431 @@ mod test {
         
         println!("Created transaksi with simple data types: {:?}", created);
     }
-}
\ No newline at end of file
+
+    #[async_test]
+    async fn test_create_with_details_rejects_empty_details() {
+        let db = setup().await;
+
+        let request = CreateTransaksiRequest {
+            id_pelanggan: 1,
+            nama_pelanggan: "Alice".to_string(),
+            catatan: None,
+            total_harga: None,
+            reject_duplicate_produk: false,
+            detail_transaksi: vec![],
+        };
+
+        let result = TransaksiService::create_transaksi_with_details(db.clone(), &request).await;
+        assert!(matches!(result, Err(sqlx::Error::RowNotFound)));
+        assert!(TransaksiService::get_all_transaksi(db.clone()).await.unwrap().is_empty());
+    }
+
+    #[async_test]
+    async fn test_create_transaksi_rejects_blank_nama_pelanggan_and_trims_it() {
+        let db = setup().await;
+
+        assert_eq!(
+            TransaksiService::normalize_nama_pelanggan("").unwrap_err(),
+            "Customer name cannot be empty or whitespace only"
+        );
+        for nama in ["", " \t\n "] {
+            let mut request = request_with_line_items(1);
+            request.nama_pelanggan = nama.to_string();
+            let result = TransaksiService::create_transaksi_with_details(db.clone(), &request).await;
+            assert!(matches!(result, Err(sqlx::Error::RowNotFound)), "nama {:?} should be rejected", nama);
+        }
+        assert!(TransaksiService::get_all_transaksi(db.clone()).await.unwrap().is_empty());
+
+        let mut request = request_with_line_items(1);
+        request.nama_pelanggan = "  Budi Santoso \t".to_string();
+        let created = TransaksiService::create_transaksi_with_details(db.clone(), &request).await.unwrap();
+        assert_eq!(created.nama_pelanggan, "Budi Santoso");
+        let stored = TransaksiService::get_transaksi_by_id(db.clone(), created.id).await.unwrap();
+        assert_eq!(stored.nama_pelanggan, "Budi Santoso");
+    }
+
+    fn request_with_line_items(count: usize) -> CreateTransaksiRequest {
+        CreateTransaksiRequest {
+            id_pelanggan: 1,
+            nama_pelanggan: "Alice".to_string(),
+            catatan: None,
+            total_harga: None,
+            reject_duplicate_produk: false,
+            detail_transaksi: (0..count)
+                .map(|_| CreateDetailTransaksiRequest {
+                    id_produk: 1,
+                    nama_produk: "Produk A".to_string(),
+                    harga_satuan: 100000.0,
+                    jumlah: 1.0,
+                    diskon: None,
+                    harga_override: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_total_berat_gram_sums_each_line_and_requires_every_weight() {
+        let details = vec![
+            DetailTransaksi::new(1, 1, 65000.0, 4.0),
+            DetailTransaksi::new(1, 2, 12000.0, 10.0),
+            DetailTransaksi::new(1, 1, 65000.0, 1.0),
+        ];
+        let mut berat_produk = HashMap::from([(1, Some(40000)), (2, Some(250))]);
+        assert_eq!(TransaksiService::total_berat_gram(&details, &berat_produk), Some(202500));
+        assert_eq!(TransaksiService::total_berat_gram(&[], &berat_produk), Some(0));
+
+        berat_produk.insert(2, None);
+        assert_eq!(TransaksiService::total_berat_gram(&details, &berat_produk), None);
+        berat_produk.remove(&2);
+        assert_eq!(TransaksiService::total_berat_gram(&details, &berat_produk), None);
+    }
+
+    #[test]
+    fn test_nilai_retur_sen_partial_returns_add_up_to_line_subtotal() {
+        // Subtotal 299.99 tidak habis dibagi 3; tiga retur satu-satu tetap berjumlah tepat subtotal
+        let detail = DetailTransaksi::new(1, 1, 100.0, 3.0).with_diskon(Some(0.01));
+        let parts: Vec<i64> = (0..3).map(|sudah| nilai_retur_sen(&detail, sudah as f64, 1.0)).collect();
+        assert_eq!(parts, vec![10000, 9999, 10000]);
+        assert_eq!(parts.iter().sum::<i64>(), 29999);
+        assert_eq!(nilai_retur_sen(&detail, 0.0, 3.0), 29999);
+        assert_eq!(nilai_retur_sen(&detail, 1.0, 2.0), 19999);
+    }
+
+    #[async_test]
+    async fn test_check_detail_price_flags_mismatch_beyond_tolerance() {
+        let db = setup().await;
+        let produk_id = insert_produk(&db, 1, "Semen Gresik", 10).await as i32;
+
+        let salah_ketik = DetailTransaksi::new(1, produk_id, 5000.0, 2.0);
+        let mismatch = TransaksiService::check_detail_price(db.clone(), &salah_ketik, 0.0).await.unwrap();
+        assert_eq!(mismatch, Some(PriceMismatch { id_produk: produk_id, harga_satuan: 5000.0, harga_produk: 50000.0 }));
+
+        let diskon_kecil = DetailTransaksi::new(1, produk_id, 47500.0, 1.0);
+        assert!(TransaksiService::check_detail_price(db.clone(), &diskon_kecil, 5.0).await.unwrap().is_none());
+        assert!(TransaksiService::check_detail_price(db.clone(), &diskon_kecil, 4.99).await.unwrap().is_some());
+
+        let tanpa_produk = DetailTransaksi::new(1, 999, 1.0, 1.0);
+        assert!(TransaksiService::check_detail_price(db.clone(), &tanpa_produk, 0.0).await.unwrap().is_none());
+
+        // Harga grosir dipakai sebagai pembanding bila jumlah baris mencapai ambangnya
+        sqlx::query("UPDATE produk SET harga_grosir = 45000, min_qty_grosir = 10 WHERE id = $1").bind(produk_id).execute(&db).await.unwrap();
+        assert!(TransaksiService::check_detail_price(db.clone(), &DetailTransaksi::new(1, produk_id, 45000.0, 10.0), 0.0).await.unwrap().is_none());
+        assert!(TransaksiService::check_detail_price(db.clone(), &DetailTransaksi::new(1, produk_id, 45000.0, 9.0), 0.0).await.unwrap().is_some());
+    }
+
+    #[async_test]
+    async fn test_create_with_details_line_item_limit_boundary() {
+        let db = setup().await;
+        let max = TransaksiService::max_line_items();
+
+        // Batas dihitung dari baris mentah sebelum digabung; `max` baris produk yang sama
+        // melebihi stok mock setelah digabung, jadi sisi "tepat di batas" diuji langsung
+        assert!(TransaksiService::validate_line_item_count(max).is_ok());
+
+        let over_limit = TransaksiService::create_transaksi_with_details(db.clone(), &request_with_line_items(max + 1)).await;
+        assert!(matches!(over_limit, Err(sqlx::Error::RowNotFound)));
+        assert!(TransaksiService::get_all_transaksi(db.clone()).await.unwrap().is_empty());
+    }
+
+    #[async_test]
+    async fn test_create_with_details_ignores_client_total() {
+        let db = setup().await;
+
+        let request = CreateTransaksiRequest {
+            id_pelanggan: 1,
+            nama_pelanggan: "Alice".to_string(),
+            catatan: None,
+            total_harga: Some(1.0),
+            reject_duplicate_produk: false,
+            detail_transaksi: vec![
+                CreateDetailTransaksiRequest {
+                    id_produk: 1,
+                    nama_produk: "Produk A".to_string(),
+                    harga_satuan: 100000.0,
+                    jumlah: 2.0,
+                    diskon: None,
+                    harga_override: None,
+                },
+                CreateDetailTransaksiRequest {
+                    id_produk: 2,
+                    nama_produk: "Produk B".to_string(),
+                    harga_satuan: 250000.0,
+                    jumlah: 1.0,
+                    diskon: None,
+                    harga_override: None,
+                },
+            ],
+        };
+
+        let created = TransaksiService::create_transaksi_with_details(db.clone(), &request).await.unwrap();
+        assert_eq!(created.total_harga, 450000.0);
+
+        let details = TransaksiService::get_detail_by_transaksi_id(db.clone(), created.id).await.unwrap();
+        let sum_details: f64 = details.iter().map(|d| d.harga_satuan * d.jumlah).sum();
+        assert_eq!(created.total_harga, sum_details);
+    }
+
+    #[async_test]
+    async fn test_create_with_details_uses_harga_grosir_from_min_qty() {
+        let db = setup().await;
+        let produk_id = insert_produk(&db, 1, "Semen Gresik", 100).await;
+        sqlx::query("UPDATE produk SET harga = 120000, harga_grosir = 90000, min_qty_grosir = 10 WHERE id = $1")
+            .bind(produk_id)
+            .execute(&db)
+            .await
+            .unwrap();
+
+        let request_with_jumlah = |jumlah: f64, harga_override: Option<f64>| CreateTransaksiRequest {
+            id_pelanggan: 1,
+            nama_pelanggan: "Toko Makmur".to_string(),
+            catatan: None,
+            total_harga: None,
+            reject_duplicate_produk: false,
+            detail_transaksi: vec![CreateDetailTransaksiRequest {
+                id_produk: produk_id as i32,
+                nama_produk: "Semen Gresik".to_string(),
+                harga_satuan: 100000.0,
+                jumlah,
+                diskon: None,
+                harga_override,
+            }],
+        };
+
+        // Di bawah ambang tetap harga eceran produk, bukan harga_satuan dari request
+        let eceran = TransaksiService::create_transaksi_with_details(db.clone(), &request_with_jumlah(9.0, None)).await.unwrap();
+        assert_eq!(eceran.total_harga, 1080000.0);
+
+        let grosir = TransaksiService::create_transaksi_with_details(db.clone(), &request_with_jumlah(10.0, None)).await.unwrap();
+        assert_eq!(grosir.total_harga, 900000.0);
+        let details = TransaksiService::get_detail_by_transaksi_id(db.clone(), grosir.id).await.unwrap();
+        assert_eq!(details[0].harga_satuan, 90000.0);
+
+        // Harga dari kasir mengalahkan harga grosir
+        let override_harga = TransaksiService::create_transaksi_with_details(db.clone(), &request_with_jumlah(10.0, Some(95000.0))).await.unwrap();
+        assert_eq!(override_harga.total_harga, 950000.0);
+    }
+
+    #[test]
+    fn test_line_unit_price_priority() {
+        let grosir = Some((90000.0, 10));
+        assert_eq!(TransaksiService::line_unit_price(100000.0, None, 50.0, None), 100000.0);
+        assert_eq!(TransaksiService::line_unit_price(100000.0, grosir, 9.0, None), 100000.0);
+        assert_eq!(TransaksiService::line_unit_price(100000.0, grosir, 10.0, None), 90000.0);
+        assert_eq!(TransaksiService::line_unit_price(100000.0, grosir, 10.0, Some(99000.0)), 99000.0);
+        assert_eq!(TransaksiService::line_unit_price(100000.0, Some((90000.0, 0)), 10.0, None), 100000.0);
+    }
+
+    #[async_test]
+    async fn test_create_with_details_applies_line_discount_to_total() {
+        let db = setup().await;
+
+        let mut request = request_with_line_items(2);
+        request.detail_transaksi[0].jumlah = 2.0;
+        request.detail_transaksi[0].diskon = Some(25000.5);
+        request.detail_transaksi[1].id_produk = 4;
+
+        let created = TransaksiService::create_transaksi_with_details(db.clone(), &request).await.unwrap();
+        assert_eq!(created.total_harga, 249999.5);
+
+        let details = TransaksiService::get_detail_by_transaksi_id(db.clone(), created.id).await.unwrap();
+        assert_eq!(details[0].diskon, Some(25000.5));
+        assert_eq!(details[0].subtotal, 174999.5);
+        assert_eq!(details[1].diskon, None);
+        assert_eq!(details[1].subtotal, 75000.0);
+
+        let mut too_generous = request_with_line_items(1);
+        too_generous.detail_transaksi[0].diskon = Some(100000.01);
+        let rejected = TransaksiService::create_transaksi_with_details(db.clone(), &too_generous).await;
+        assert!(matches!(rejected, Err(sqlx::Error::RowNotFound)));
+        assert_eq!(TransaksiService::get_all_transaksi(db.clone()).await.unwrap().len(), 1);
+    }
+
+    #[async_test]
+    async fn test_create_with_details_merges_or_rejects_duplicate_produk() {
+        let db = setup().await;
+
+        let mut request = request_with_line_items(3);
+        request.detail_transaksi[1].id_produk = 2;
+        request.detail_transaksi[2].jumlah = 2.0;
+
+        let created = TransaksiService::create_transaksi_with_details(db.clone(), &request).await.unwrap();
+        assert_eq!(created.total_harga, 550000.0);
+        let details = TransaksiService::get_detail_by_transaksi_id(db.clone(), created.id).await.unwrap();
+        assert_eq!(details.len(), 2);
+        assert_eq!((details[0].id_produk, details[0].jumlah), (1, 3.0));
+        assert_eq!((details[1].id_produk, details[1].jumlah), (2, 1.0));
+
+        request.reject_duplicate_produk = true;
+        let rejected = TransaksiService::create_transaksi_with_details(db.clone(), &request).await;
+        assert!(matches!(rejected, Err(sqlx::Error::RowNotFound)));
+        assert_eq!(TransaksiService::get_all_transaksi(db.clone()).await.unwrap().len(), 1);
+    }
+
+    #[async_test]
+    async fn test_search_combines_status_keyword_pelanggan_and_sort() {
+        let db = setup().await;
+
+        for (id_pelanggan, nama, total) in [
+            (1, "Alice Smith", 300000.0),
+            (1, "Alice Jones", 100000.0),
+            (1, "alice Brown", 200000.0),
+            (2, "Alice Other", 400000.0),
+            (1, "Bob", 500000.0),
+        ] {
+            TransaksiService::create_transaksi(db.clone(), &Transaksi::new(id_pelanggan, nama.to_string(), total, None)).await.unwrap();
+        }
+        let jones = TransaksiService::get_all_transaksi(db.clone()).await.unwrap()
+            .into_iter()
+            .find(|t| t.nama_pelanggan == "Alice Jones")
+            .unwrap();
+        TransaksiService::complete_transaksi(db.clone(), jones.id).await.unwrap();
+
+        let params = TransaksiSearchParams {
+            sort: Some("total_desc".to_string()),
+            filter: Some("nama_pelanggan".to_string()),
+            keyword: Some("ALICE".to_string()),
+            status: Some("MASIH_DIPROSES".to_string()),
+            id_pelanggan: Some(1),
+            date_from: None,
+            date_to: None,
+            page: None,
+            limit: None,
+        };
+        let result = TransaksiService::search_transaksi_with_pagination(db.clone(), &params).await.unwrap();
+
+        let names: Vec<&str> = result.data.iter().map(|t| t.nama_pelanggan.as_str()).collect();
+        assert_eq!(names, vec!["Alice Smith", "alice Brown"]);
+        assert_eq!(result.total_count, 2);
+    }
+
+    #[async_test]
+    async fn test_search_keyword_matches_catatan_with_escaped_wildcards() {
+        let db = setup().await;
+
+        for (nama, catatan) in [
+            ("Andi", "Warranty claim: bor listrik mati"),
+            ("Budi", "Pembelian rutin semen"),
+            ("Citra", "Diskon 100% untuk promo_akhir tahun"),
+        ] {
+            TransaksiService::create_transaksi(db.clone(), &Transaksi::new(1, nama.to_string(), 100000.0, Some(catatan.to_string()))).await.unwrap();
+        }
+
+        let search = |keyword: &str| TransaksiSearchParams {
+            sort: Some("pelanggan".to_string()),
+            filter: None,
+            keyword: Some(keyword.to_string()),
+            status: None,
+            id_pelanggan: None,
+            date_from: None,
+            date_to: None,
+            page: None,
+            limit: None,
+        };
+        let names = |result: TransaksiSearchResult| -> Vec<String> {
+            result.data.into_iter().map(|t| t.nama_pelanggan).collect()
+        };
+
+        let warranty = TransaksiService::search_transaksi_with_pagination(db.clone(), &search("warranty claim")).await.unwrap();
+        assert_eq!(names(warranty), vec!["Andi"]);
+
+        // Wildcard dari user dicocokkan sebagai karakter biasa, bukan "semua baris"
+        let percent = TransaksiService::search_transaksi_with_pagination(db.clone(), &search("%")).await.unwrap();
+        assert_eq!(names(percent), vec!["Citra"]);
+        let underscore = TransaksiService::search_transaksi_with_pagination(db.clone(), &search("o_a")).await.unwrap();
+        assert_eq!(names(underscore), vec!["Citra"]);
+    }
+
+    #[async_test]
+    async fn test_search_rejects_unknown_sort_and_filter() {
+        let db = setup().await;
+
+        let mut params = TransaksiSearchParams {
+            sort: Some("total_harga; DROP TABLE transaksi".to_string()),
+            filter: None,
+            keyword: None,
+            status: None,
+            id_pelanggan: None,
+            date_from: None,
+            date_to: None,
+            page: None,
+            limit: None,
+        };
+        assert!(TransaksiService::search_transaksi_with_pagination(db.clone(), &params).await.is_err());
+
+        params.sort = None;
+        params.filter = Some("id_pelanggan) OR (1=1".to_string());
+        assert!(TransaksiService::search_transaksi_with_pagination(db.clone(), &params).await.is_err());
+    }
+
+    #[async_test]
+    async fn test_search_filters_by_tanggal_range() {
+        let db = setup().await;
+
+        for (nama, tanggal) in [
+            ("Awal Januari", "2024-01-01 08:00:00"),
+            ("Pertengahan Januari", "2024-01-15 12:30:00"),
+            ("Akhir Januari", "2024-01-31 23:00:00"),
+            ("Februari", "2024-02-10 09:00:00"),
+        ] {
+            let mut transaksi = Transaksi::new(1, nama.to_string(), 100000.0, None);
+            transaksi.tanggal_transaksi = tanggal.to_string();
+            TransaksiService::create_transaksi(db.clone(), &transaksi).await.unwrap();
+        }
+
+        let search = |date_from: Option<&str>, date_to: Option<&str>| TransaksiSearchParams {
+            sort: Some("tanggal".to_string()),
+            filter: None,
+            keyword: None,
+            status: None,
+            id_pelanggan: None,
+            date_from: date_from.map(str::to_string),
+            date_to: date_to.map(str::to_string),
+            page: None,
+            limit: None,
+        };
+        let names = |result: TransaksiSearchResult| -> Vec<String> {
+            result.data.into_iter().map(|t| t.nama_pelanggan).collect()
+        };
+
+        let both = TransaksiService::search_transaksi_with_pagination(db.clone(), &search(Some("2024-01-15"), Some("2024-01-31"))).await.unwrap();
+        assert_eq!(names(both), vec!["Pertengahan Januari", "Akhir Januari"]);
+
+        let from_only = TransaksiService::search_transaksi_with_pagination(db.clone(), &search(Some("2024-01-31 23:00:00"), None)).await.unwrap();
+        assert_eq!(names(from_only), vec!["Akhir Januari", "Februari"]);
+
+        let to_only = TransaksiService::search_transaksi_with_pagination(db.clone(), &search(None, Some("2024-01-01"))).await.unwrap();
+        assert_eq!(names(to_only), vec!["Awal Januari"]);
+
+        let invalid = TransaksiService::search_transaksi_with_pagination(db.clone(), &search(Some("31/01/2024"), None)).await;
+        assert!(matches!(invalid, Err(sqlx::Error::RowNotFound)));
+    }
+
+    #[async_test]
+    async fn test_search_paginates_in_sql_with_total_count() {
+        let db = setup().await;
+
+        for total in [100.0, 200.0, 300.0, 400.0, 500.0] {
+            TransaksiService::create_transaksi(db.clone(), &Transaksi::new(1, "Alice".to_string(), total, None)).await.unwrap();
+        }
+
+        let params = TransaksiSearchParams {
+            sort: Some("total".to_string()),
+            filter: None,
+            keyword: None,
+            status: None,
+            id_pelanggan: None,
+            date_from: None,
+            date_to: None,
+            page: Some(2),
+            limit: Some(2),
+        };
+        let result = TransaksiService::search_transaksi_with_pagination(db.clone(), &params).await.unwrap();
+
+        let totals: Vec<f64> = result.data.iter().map(|t| t.total_harga).collect();
+        assert_eq!(totals, vec![300.0, 400.0]);
+        assert_eq!(result.total_count, 5);
+        assert_eq!(result.total_pages, 3);
+        assert_eq!(result.page, 2);
+    }
+}